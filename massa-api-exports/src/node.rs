@@ -1,12 +1,13 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use massa_models::node::NodeId;
-use massa_models::stats::{ConsensusStats, ExecutionStats, NetworkStats};
+use massa_models::stats::{ConsensusStats, ExecutionStats, NetworkStats, ProtocolStats};
+use massa_models::{block_id::BlockId, operation::OperationId};
 use massa_models::{config::CompactConfig, slot::Slot, version::Version};
 use massa_time::MassaTime;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 
 /// node status
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -27,6 +28,9 @@ pub struct NodeStatus {
     pub next_cycle_time: MassaTime,
     /// connected nodes (node id, ip address, true if the connection is outgoing, false if incoming)
     pub connected_nodes: BTreeMap<NodeId, (IpAddr, bool)>,
+    /// details of the live connection to each connected node
+    #[serde(default)]
+    pub connections: BTreeMap<NodeId, NodeConnection>,
     /// latest slot, none if now is before genesis timestamp
     pub last_slot: Option<Slot>,
     /// next slot
@@ -84,8 +88,347 @@ impl std::fmt::Display for NodeStatus {
                 node_id,
                 ip_addr,
                 if *is_outgoing { "Out" } else { "In" }
-            )?
+            )?;
+            if let Some(connection) = self.connections.get(node_id) {
+                writeln!(f, "\t{}", connection.describe(self.current_time))?;
+            }
         }
         Ok(())
     }
 }
+
+/// live connection to a node
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NodeConnection {
+    /// address of the node on this connection
+    pub address: SocketAddr,
+    /// true if the connection is outgoing, false if incoming
+    pub is_outgoing: bool,
+    /// transport of the connection, none if unknown
+    pub transport: Option<String>,
+    /// time at which the connection was established, none if unknown
+    pub connected_since: Option<MassaTime>,
+    /// time of the last answer to a keep-alive ping, none if the peer never answered
+    pub last_pong: Option<MassaTime>,
+    /// traffic of the node since it connected
+    pub traffic: PeerTrafficInfo,
+}
+
+impl NodeConnection {
+    /// one line summary of the connection, with its age at `now`
+    pub fn describe(&self, now: MassaTime) -> String {
+        format!(
+            "Address: {} / Transport: {} / Connected for: {} / Last pong: {} / {}",
+            self.address,
+            self.transport.as_deref().unwrap_or("unknown"),
+            self.connected_since
+                .map(|since| format!("{}s", now.saturating_sub(since).to_duration().as_secs()))
+                .unwrap_or_else(|| "unknown".to_string()),
+            self.last_pong
+                .map(|pong| format!("{}s ago", now.saturating_sub(pong).to_duration().as_secs()))
+                .unwrap_or_else(|| "never".to_string()),
+            self.traffic
+        )
+    }
+}
+
+/// traffic of a connected node, to tell the nodes that only download or that flood us
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PeerTrafficInfo {
+    /// block messages received
+    pub block_messages: u64,
+    /// endorsement messages received
+    pub endorsement_messages: u64,
+    /// operation messages received
+    pub operation_messages: u64,
+    /// peer management messages received
+    pub peer_management_messages: u64,
+    /// bytes received, without the ids of the messages
+    pub bytes_received: u64,
+    /// bytes sent, without the ids of the messages
+    pub bytes_sent: u64,
+    /// oversized, malformed or badly signed messages received
+    pub invalid_messages: u64,
+    /// last new block header relayed by the node, with its slot
+    pub last_block: Option<(BlockId, Slot)>,
+}
+
+impl std::fmt::Display for PeerTrafficInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Received: {} blocks, {} endorsements, {} operations, {} peer messages ({} bytes) / \
+            Sent: {} bytes / Invalid: {} / Last block: ",
+            self.block_messages,
+            self.endorsement_messages,
+            self.operation_messages,
+            self.peer_management_messages,
+            self.bytes_received,
+            self.bytes_sent,
+            self.invalid_messages
+        )?;
+        match &self.last_block {
+            Some((block_id, slot)) => write!(f, "{} at {}", block_id, slot),
+            None => write!(f, "none"),
+        }
+    }
+}
+
+/// snapshot of the state of the protocol, for the monitoring dashboards
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProtocolStatsInfo {
+    /// message rates, queues, tester backlog and bans
+    pub stats: ProtocolStats,
+    /// connected nodes
+    pub connections: BTreeMap<NodeId, NodeConnection>,
+}
+
+impl std::fmt::Display for ProtocolStatsInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.stats)?;
+        writeln!(f, "Connected nodes:")?;
+        for (node_id, connection) in &self.connections {
+            writeln!(
+                f,
+                "\tNode's ID: {} / {} connection / {}",
+                node_id,
+                if connection.is_outgoing { "Out" } else { "In" },
+                connection.describe(self.stats.timestamp)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// result of the check of a bootstrap server
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BootstrapServerCheck {
+    /// address of the server
+    pub address: SocketAddr,
+    /// node id the server was authenticated with
+    pub node_id: NodeId,
+    /// version advertised by the server
+    pub version: Version,
+    /// handshake round-trip time
+    pub ping: MassaTime,
+    /// difference between the server clock and ours
+    pub clock_delta: MassaTime,
+    /// last final slot of the server
+    pub last_final_slot: Slot,
+    /// size in bytes of the first final state part, a lower bound of the state size
+    pub first_part_size: u64,
+}
+
+impl std::fmt::Display for BootstrapServerCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Bootstrap server: {}", self.address)?;
+        writeln!(f, "Node's ID: {} (identity verified)", self.node_id)?;
+        writeln!(f, "Version: {}", self.version)?;
+        writeln!(f, "Ping: {} ms", self.ping.to_millis())?;
+        writeln!(f, "Clock delta: {} ms", self.clock_delta.to_millis())?;
+        writeln!(f, "Last final slot: {}", self.last_final_slot)?;
+        writeln!(
+            f,
+            "Estimated state size: at least {} bytes",
+            self.first_part_size
+        )?;
+        Ok(())
+    }
+}
+
+/// event of the connection audit log
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConnectionEventInfo {
+    /// time of the event
+    pub timestamp: MassaTime,
+    /// kind of event (handshake, disconnection, ban...)
+    pub kind: String,
+    /// node concerned, none if unknown (handshake not finished yet)
+    pub node_id: Option<NodeId>,
+    /// address of the connection, none if unknown
+    pub address: Option<SocketAddr>,
+    /// reason of the event, if any
+    pub reason: Option<String>,
+}
+
+impl std::fmt::Display for ConnectionEventInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.timestamp, self.kind)?;
+        if let Some(node_id) = &self.node_id {
+            write!(f, " / Node: {}", node_id)?;
+        }
+        if let Some(address) = &self.address {
+            write!(f, " / Address: {}", address)?;
+        }
+        if let Some(reason) = &self.reason {
+            write!(f, " / Reason: {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// connection to a peer, as listed by the admin service
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConnectedPeerInfo {
+    /// node id of the peer
+    pub node_id: NodeId,
+    /// address of the peer on this connection
+    pub address: SocketAddr,
+    /// true if we dialed the peer
+    pub is_outgoing: bool,
+    /// transport of the connection
+    pub transport: Option<String>,
+    /// time at which the handshake succeeded
+    pub connected_since: Option<MassaTime>,
+    /// traffic of the peer since it connected
+    pub traffic: PeerTrafficInfo,
+    /// tags attached to the peer by the operator
+    pub tags: Vec<String>,
+    /// note attached to the peer by the operator
+    pub note: Option<String>,
+}
+
+/// outcome of the test of a peer address
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PeerTestInfo {
+    /// tested address
+    pub address: SocketAddr,
+    /// true if a connection could be opened to the address
+    pub reachable: bool,
+    /// node id of the peer if the handshake succeeded
+    pub node_id: Option<NodeId>,
+    /// reason of the failure of the handshake
+    pub error: Option<String>,
+    /// time taken to connect and run the handshake
+    pub latency: Option<MassaTime>,
+}
+
+impl std::fmt::Display for PeerTestInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Address: {}", self.address)?;
+        writeln!(f, "Reachable: {}", self.reachable)?;
+        if let Some(node_id) = &self.node_id {
+            writeln!(f, "Node's ID: {} (handshake succeeded)", node_id)?;
+        }
+        if let Some(error) = &self.error {
+            writeln!(f, "Handshake failed: {}", error)?;
+        }
+        if let Some(latency) = &self.latency {
+            writeln!(f, "Latency: {} ms", latency.to_millis())?;
+        }
+        Ok(())
+    }
+}
+
+/// known peer with its listeners, as exported by a node and imported by another one
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExportedPeerInfo {
+    /// node id of the peer
+    pub node_id: NodeId,
+    /// listeners of the peer and their transport
+    pub listeners: BTreeMap<SocketAddr, String>,
+    /// category of the peer
+    pub category: Option<String>,
+    /// last time the node heard of the peer
+    pub last_seen: MassaTime,
+}
+
+/// signed report of a ban, as exported by a node and imported by another one
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExportedBanReportInfo {
+    /// node id of the banned peer
+    pub node_id: NodeId,
+    /// kind of offense
+    pub kind: String,
+    /// time of the ban
+    pub timestamp: MassaTime,
+    /// node that banned the peer and signed the report
+    pub reporter: NodeId,
+    /// signed report, with the message that proves the offense
+    pub report: Vec<u8>,
+}
+
+/// outcome of an imported ban report
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ImportedBanReportInfo {
+    /// banned peer, if the report was accepted
+    pub node_id: Option<NodeId>,
+    /// reason of the refusal of the report
+    pub error: Option<String>,
+}
+
+impl std::fmt::Display for ImportedBanReportInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.node_id, &self.error) {
+            (Some(node_id), _) => write!(f, "Banned {}", node_id),
+            (None, Some(error)) => write!(f, "Refused: {}", error),
+            (None, None) => write!(f, "Refused"),
+        }
+    }
+}
+
+/// reachability of a listener of the node, as seen by the peers asked to dial it back
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ListenerReachabilityInfo {
+    /// listener as announced to the peers
+    pub address: SocketAddr,
+    /// peers that completed a handshake with the listener
+    pub reachable_from: Vec<NodeId>,
+    /// peers that could not connect to the listener
+    pub unreachable_from: Vec<NodeId>,
+    /// peers that didn't answer in time
+    pub unanswered: Vec<NodeId>,
+}
+
+impl std::fmt::Display for ListenerReachabilityInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reachability = if !self.reachable_from.is_empty() {
+            "reachable"
+        } else if !self.unreachable_from.is_empty() {
+            "unreachable"
+        } else {
+            "unknown"
+        };
+        writeln!(f, "Listener {}: {}", self.address, reachability)?;
+        for (label, node_ids) in [
+            ("Reached from", &self.reachable_from),
+            ("Not reached from", &self.unreachable_from),
+            ("No answer from", &self.unanswered),
+        ] {
+            if !node_ids.is_empty() {
+                let node_ids: Vec<String> = node_ids.iter().map(|id| id.to_string()).collect();
+                writeln!(f, "    {}: {}", label, node_ids.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// event published by the protocol, streamed to the WebSocket subscribers
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProtocolEventInfo {
+    /// a connection changed: handshake, disconnection, ban...
+    Connection(ConnectionEventInfo),
+    /// a node sent us the header of a block we didn't know
+    BlockHeaderReceived {
+        /// id of the block
+        block_id: BlockId,
+        /// slot of the block
+        slot: Slot,
+        /// node that sent the header
+        node_id: NodeId,
+    },
+    /// a node sent us operations we didn't know
+    OperationsReceived {
+        /// ids of the new operations
+        operation_ids: Vec<OperationId>,
+        /// node that sent the operations
+        node_id: NodeId,
+    },
+    /// the subscriber didn't keep up: the oldest events were dropped
+    Lagged {
+        /// number of events missed
+        missed: u64,
+    },
+}
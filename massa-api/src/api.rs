@@ -3,7 +3,7 @@
 use std::net::SocketAddr;
 
 use crate::api_trait::MassaApiServer;
-use crate::{ApiServer, ApiV2, StopHandle, API};
+use crate::{connection_event_info, node_id_of, ApiServer, ApiV2, StopHandle, API};
 use async_trait::async_trait;
 use futures::future::{self, Either};
 use futures::StreamExt;
@@ -11,6 +11,7 @@ use jsonrpsee::core::{Error as JsonRpseeError, RpcResult, SubscriptionResult};
 use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage};
 use massa_api_exports::config::APIConfig;
 use massa_api_exports::error::ApiError;
+use massa_api_exports::node::ProtocolEventInfo;
 use massa_api_exports::page::{PageRequest, PagedVec, PagedVecV2};
 use massa_api_exports::ApiRequest;
 use massa_consensus_exports::{ConsensusChannels, ConsensusController};
@@ -21,9 +22,10 @@ use massa_models::slot::Slot;
 use massa_models::timeslots::get_latest_block_slot_at_timestamp;
 use massa_models::version::Version;
 use massa_pool_exports::PoolChannels;
+use massa_protocol_exports::{ProtocolEvent, ProtocolEventBus};
 use massa_time::MassaTime;
 use serde::Serialize;
-use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
 
 impl API<ApiV2> {
     /// generate a new massa API
@@ -32,6 +34,7 @@ impl API<ApiV2> {
         consensus_channels: ConsensusChannels,
         execution_controller: Box<dyn ExecutionController>,
         pool_channels: PoolChannels,
+        protocol_event_bus: ProtocolEventBus,
         api_settings: APIConfig,
         version: Version,
     ) -> Self {
@@ -40,6 +43,7 @@ impl API<ApiV2> {
             consensus_channels,
             execution_controller,
             pool_channels,
+            protocol_event_bus,
             api_settings,
             version,
         })
@@ -149,6 +153,69 @@ impl MassaApiServer for API<ApiV2> {
     ) -> SubscriptionResult {
         broadcast_via_ws(self.0.pool_channels.operation_sender.clone(), pending).await
     }
+
+    async fn subscribe_protocol_events(
+        &self,
+        pending: PendingSubscriptionSink,
+    ) -> SubscriptionResult {
+        let sink = pending.accept().await?;
+        let closed = sink.closed();
+        // a slow subscriber skips the oldest events kept by the bus and is told how many it
+        // missed, the protocol never waits for it
+        let stream =
+            BroadcastStream::new(self.0.protocol_event_bus.subscribe()).map(|item| match item {
+                Ok(event) => protocol_event_info(event),
+                Err(BroadcastStreamRecvError::Lagged(missed)) => {
+                    ProtocolEventInfo::Lagged { missed }
+                }
+            });
+        futures::pin_mut!(closed, stream);
+
+        loop {
+            match future::select(closed, stream.next()).await {
+                // subscription closed.
+                Either::Left((_, _)) => break Ok(()),
+
+                // received new event from the bus.
+                Either::Right((Some(event), c)) => {
+                    let notif = SubscriptionMessage::from_json(&event)?;
+
+                    if sink.send(notif).await.is_err() {
+                        break Ok(());
+                    }
+
+                    closed = c;
+                }
+
+                // Bus is closed.
+                Either::Right((None, _)) => break Ok(()),
+            }
+        }
+    }
+}
+
+fn protocol_event_info(event: ProtocolEvent) -> ProtocolEventInfo {
+    match event {
+        ProtocolEvent::Connection(event) => {
+            ProtocolEventInfo::Connection(connection_event_info(event))
+        }
+        ProtocolEvent::BlockHeaderReceived {
+            block_id,
+            slot,
+            peer_id,
+        } => ProtocolEventInfo::BlockHeaderReceived {
+            block_id,
+            slot,
+            node_id: node_id_of(&peer_id),
+        },
+        ProtocolEvent::OperationsReceived {
+            operation_ids,
+            peer_id,
+        } => ProtocolEventInfo::OperationsReceived {
+            operation_ids,
+            node_id: node_id_of(&peer_id),
+        },
+    }
 }
 
 // Brodcast the stream(sender) content via a WebSocket
@@ -57,4 +57,12 @@ pub trait MassaApi {
 		item = Operation
 	)]
     async fn subscribe_new_operations(&self) -> SubscriptionResult;
+
+    /// Protocol events: connections, bans, new blocks headers and operations received.
+    #[subscription(
+        name = "subscribe_protocol_events" => "protocol_events",
+        unsubscribe = "unsubscribe_protocol_events",
+        item = ProtocolEventInfo
+    )]
+    async fn subscribe_protocol_events(&self) -> SubscriptionResult;
 }
@@ -17,11 +17,15 @@ use massa_api_exports::{
     endorsement::EndorsementInfo,
     error::ApiError::WrongAPI,
     execution::{ExecuteReadOnlyResponse, ReadOnlyBytecodeExecution, ReadOnlyCall},
-    node::NodeStatus,
+    node::{
+        BootstrapServerCheck, ConnectionEventInfo, NodeConnection, NodeStatus, PeerTrafficInfo,
+        ProtocolStatsInfo,
+    },
     operation::{OperationInfo, OperationInput},
     page::{PageRequest, PagedVec},
     TimeInterval,
 };
+use massa_bootstrap::BootstrapConfig;
 use massa_consensus_exports::{ConsensusChannels, ConsensusController};
 use massa_execution_exports::ExecutionController;
 use massa_models::clique::Clique;
@@ -31,16 +35,27 @@ use massa_models::operation::OperationId;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::PreHashSet;
 use massa_models::{
-    address::Address, block::Block, block_id::BlockId, endorsement::EndorsementId,
-    execution::EventFilter, slot::Slot, version::Version,
+    address::Address,
+    block::Block,
+    block_id::BlockId,
+    endorsement::EndorsementId,
+    execution::{EventFilter, ExecutedOpsDump},
+    slot::Slot,
+    version::Version,
 };
 use massa_pool_exports::{PoolChannels, PoolController};
 use massa_pos_exports::SelectorController;
-use massa_protocol_exports::{ProtocolConfig, ProtocolController};
+use massa_protocol_exports::{
+    ConnectionEvent, PeerConnectionInfo, PeerConnectionType, PeerId, PeerTrafficStats,
+    ProtocolConfig, ProtocolController, ProtocolEventBus,
+};
+use massa_signature::{PublicKey, PUBLIC_KEY_SIZE_BYTES};
 use massa_storage::Storage;
+use massa_time::MassaTime;
 use massa_wallet::Wallet;
 use parking_lot::RwLock;
 use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use tokio::sync::mpsc;
@@ -88,6 +103,10 @@ pub struct Private {
     pub stop_node_channel: mpsc::Sender<()>,
     /// User wallet
     pub node_wallet: Arc<RwLock<Wallet>>,
+    /// bootstrap settings, used to check bootstrap servers
+    pub bootstrap_config: BootstrapConfig,
+    /// node version
+    pub version: Version,
 }
 
 /// API v2 content
@@ -100,6 +119,8 @@ pub struct ApiV2 {
     pub execution_controller: Box<dyn ExecutionController>,
     /// link(channels) to the pool component
     pub pool_channels: PoolChannels,
+    /// events published by the protocol component
+    pub protocol_event_bus: ProtocolEventBus,
     /// API settings
     pub api_settings: APIConfig,
     /// node version
@@ -302,6 +323,32 @@ pub trait MassaRpc {
     #[method(name = "node_remove_from_bootstrap_blacklist")]
     async fn node_remove_from_bootstrap_blacklist(&self, arg: Vec<IpAddr>) -> RpcResult<()>;
 
+    /// Connect to a bootstrap server and check its identity, version and final slot
+    /// without bootstrapping from it.
+    /// If no node id is given, the one associated to the address in the bootstrap list is used.
+    #[method(name = "node_check_bootstrap")]
+    async fn node_check_bootstrap(
+        &self,
+        addr: SocketAddr,
+        node_id: Option<NodeId>,
+    ) -> RpcResult<BootstrapServerCheck>;
+
+    /// Dump the final executed operations by slot, with their ids, paginated over the slots.
+    /// Meant to be diffed against the dump of another node.
+    #[method(name = "dump_executed_ops")]
+    async fn dump_executed_ops(
+        &self,
+        page_request: Option<PageRequest>,
+    ) -> RpcResult<ExecutedOpsDump>;
+
+    /// Last connection events (handshakes, disconnections, bans, fallbacks) kept by the node,
+    /// oldest first. If a time is given, only the events since then are returned.
+    #[method(name = "node_get_connection_events")]
+    async fn node_get_connection_events(
+        &self,
+        since: Option<MassaTime>,
+    ) -> RpcResult<Vec<ConnectionEventInfo>>;
+
     /// Unban given IP address(es).
     /// No confirmation to expect.
     #[method(name = "node_unban_by_ip")]
@@ -316,6 +363,11 @@ pub trait MassaRpc {
     #[method(name = "get_status")]
     async fn get_status(&self) -> RpcResult<NodeStatus>;
 
+    /// Snapshot of the state of the protocol for the monitoring dashboards: connected nodes,
+    /// message rates and queues of the handlers, peers waiting to be tested and bans.
+    #[method(name = "get_protocol_stats")]
+    async fn get_protocol_stats(&self) -> RpcResult<ProtocolStatsInfo>;
+
     /// Get cliques.
     #[method(name = "get_cliques")]
     async fn get_cliques(&self) -> RpcResult<Vec<Clique>>;
@@ -382,3 +434,59 @@ pub trait MassaRpc {
 fn wrong_api<T>() -> RpcResult<T> {
     Err((WrongAPI).into())
 }
+
+//TODO: Change when unify node id and peer id
+fn node_id_of(peer_id: &PeerId) -> NodeId {
+    NodeId::new(
+        PublicKey::from_bytes(
+            peer_id.to_bytes()[..PUBLIC_KEY_SIZE_BYTES]
+                .try_into()
+                .unwrap(),
+        )
+        .unwrap(),
+    )
+}
+
+fn connection_event_info(event: ConnectionEvent) -> ConnectionEventInfo {
+    ConnectionEventInfo {
+        timestamp: event.timestamp,
+        kind: event.kind.to_string(),
+        node_id: event.peer_id.as_ref().map(node_id_of),
+        address: event.address,
+        reason: event.reason,
+    }
+}
+
+fn node_connections(
+    peers: &HashMap<PeerId, PeerConnectionInfo>,
+) -> BTreeMap<NodeId, NodeConnection> {
+    peers
+        .iter()
+        .map(|(peer_id, peer)| {
+            (
+                node_id_of(peer_id),
+                NodeConnection {
+                    address: peer.address,
+                    is_outgoing: peer.connection_type == PeerConnectionType::OUT,
+                    transport: peer.transport.map(|transport| format!("{:?}", transport)),
+                    connected_since: peer.connected_since,
+                    last_pong: peer.last_pong,
+                    traffic: peer_traffic_info(&peer.traffic),
+                },
+            )
+        })
+        .collect()
+}
+
+fn peer_traffic_info(traffic: &PeerTrafficStats) -> PeerTrafficInfo {
+    PeerTrafficInfo {
+        block_messages: traffic.block_messages,
+        endorsement_messages: traffic.endorsement_messages,
+        operation_messages: traffic.operation_messages,
+        peer_management_messages: traffic.peer_management_messages,
+        bytes_received: traffic.bytes_received,
+        bytes_sent: traffic.bytes_sent,
+        invalid_messages: traffic.invalid_messages,
+        last_block: traffic.last_block,
+    }
+}
@@ -1,6 +1,6 @@
 //! Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-use crate::{MassaRpcServer, Private, RpcServer, StopHandle, Value, API};
+use crate::{connection_event_info, MassaRpcServer, Private, RpcServer, StopHandle, Value, API};
 
 use async_trait::async_trait;
 use jsonrpsee::core::{Error as JsonRpseeError, RpcResult};
@@ -12,11 +12,12 @@ use massa_api_exports::{
     endorsement::EndorsementInfo,
     error::ApiError,
     execution::{ExecuteReadOnlyResponse, ReadOnlyBytecodeExecution, ReadOnlyCall},
-    node::NodeStatus,
+    node::{BootstrapServerCheck, ConnectionEventInfo, NodeStatus, ProtocolStatsInfo},
     operation::{OperationInfo, OperationInput},
     page::{PageRequest, PagedVec},
     ListType, ScrudOperation, TimeInterval,
 };
+use massa_bootstrap::{check_bootstrap_server, BootstrapConfig, DefaultConnector};
 use massa_execution_exports::ExecutionController;
 use massa_hash::Hash;
 use massa_models::clique::Clique;
@@ -25,11 +26,18 @@ use massa_models::node::NodeId;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::PreHashSet;
 use massa_models::{
-    address::Address, block::Block, block_id::BlockId, endorsement::EndorsementId,
-    execution::EventFilter, operation::OperationId, slot::Slot,
+    address::Address,
+    block::Block,
+    block_id::BlockId,
+    endorsement::EndorsementId,
+    execution::{EventFilter, ExecutedOpsDump},
+    operation::OperationId,
+    slot::Slot,
+    version::Version,
 };
 use massa_protocol_exports::{PeerId, ProtocolController};
 use massa_signature::{KeyPair, PUBLIC_KEY_SIZE_BYTES};
+use massa_time::MassaTime;
 use massa_wallet::Wallet;
 
 use parking_lot::RwLock;
@@ -48,6 +56,8 @@ impl API<Private> {
         execution_controller: Box<dyn ExecutionController>,
         api_settings: APIConfig,
         node_wallet: Arc<RwLock<Wallet>>,
+        bootstrap_config: BootstrapConfig,
+        version: Version,
     ) -> (Self, mpsc::Receiver<()>) {
         let (stop_node_channel, rx) = mpsc::channel(1);
         (
@@ -57,6 +67,8 @@ impl API<Private> {
                 api_settings,
                 stop_node_channel,
                 node_wallet,
+                bootstrap_config,
+                version,
             }),
             rx,
         )
@@ -197,6 +209,33 @@ impl MassaRpcServer for API<Private> {
             .map_err(|e| ApiError::ProtocolError(e).into())
     }
 
+    async fn dump_executed_ops(
+        &self,
+        page_request: Option<PageRequest>,
+    ) -> RpcResult<ExecutedOpsDump> {
+        // the page offset is a page index, as in `PagedVec`
+        let (offset, limit) = match page_request {
+            Some(PageRequest { limit, offset }) => (offset.saturating_mul(limit), limit),
+            None => (0, usize::MAX),
+        };
+        Ok(self
+            .0
+            .execution_controller
+            .get_executed_ops_dump(offset, limit))
+    }
+
+    async fn node_get_connection_events(
+        &self,
+        since: Option<MassaTime>,
+    ) -> RpcResult<Vec<ConnectionEventInfo>> {
+        let events = self
+            .0
+            .protocol_controller
+            .get_connection_events(since, None)
+            .map_err(ApiError::ProtocolError)?;
+        Ok(events.into_iter().map(connection_event_info).collect())
+    }
+
     async fn node_unban_by_ip(&self, _ips: Vec<IpAddr>) -> RpcResult<()> {
         //TODO: Reinvoke
         // let network_command_sender = self.0.network_command_sender.clone();
@@ -213,6 +252,10 @@ impl MassaRpcServer for API<Private> {
         crate::wrong_api::<NodeStatus>()
     }
 
+    async fn get_protocol_stats(&self) -> RpcResult<ProtocolStatsInfo> {
+        crate::wrong_api::<ProtocolStatsInfo>()
+    }
+
     async fn get_cliques(&self) -> RpcResult<Vec<Clique>> {
         crate::wrong_api::<Vec<Clique>>()
     }
@@ -357,6 +400,54 @@ impl MassaRpcServer for API<Private> {
         )
     }
 
+    async fn node_check_bootstrap(
+        &self,
+        addr: SocketAddr,
+        node_id: Option<NodeId>,
+    ) -> RpcResult<BootstrapServerCheck> {
+        let node_id = match node_id {
+            Some(node_id) => node_id,
+            None => self
+                .0
+                .bootstrap_config
+                .bootstrap_list
+                .iter()
+                .find(|(bootstrap_addr, _)| *bootstrap_addr == addr)
+                .map(|(_, node_id)| *node_id)
+                .ok_or_else(|| {
+                    ApiError::BadRequest(format!(
+                        "{} is not in the bootstrap list, please provide its node id",
+                        addr
+                    ))
+                })?,
+        };
+        let bootstrap_config = self.0.bootstrap_config.clone();
+        let version = self.0.version;
+        let info = tokio::task::spawn_blocking(move || {
+            check_bootstrap_server(
+                &bootstrap_config,
+                DefaultConnector,
+                &addr,
+                &node_id,
+                version,
+            )
+        })
+        .await
+        .map_err(|e| ApiError::InternalServerError(e.to_string()))?
+        .map_err(|e| {
+            ApiError::InternalServerError(format!("bootstrap server check failed: {}", e))
+        })?;
+        Ok(BootstrapServerCheck {
+            address: addr,
+            node_id,
+            version: info.version,
+            ping: info.ping,
+            clock_delta: info.clock_delta,
+            last_final_slot: info.last_final_slot,
+            first_part_size: info.first_part_size,
+        })
+    }
+
     async fn get_openrpc_spec(&self) -> RpcResult<Value> {
         crate::wrong_api::<Value>()
     }
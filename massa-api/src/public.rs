@@ -12,7 +12,7 @@ use massa_api_exports::{
     endorsement::EndorsementInfo,
     error::ApiError,
     execution::{ExecuteReadOnlyResponse, ReadOnlyBytecodeExecution, ReadOnlyCall, ReadOnlyResult},
-    node::NodeStatus,
+    node::{BootstrapServerCheck, ConnectionEventInfo, NodeStatus, ProtocolStatsInfo},
     operation::{OperationInfo, OperationInput},
     page::{PageRequest, PagedVec},
     slot::SlotAmount,
@@ -33,7 +33,7 @@ use massa_models::{
     timeslots,
 };
 use massa_pos_exports::SelectorController;
-use massa_protocol_exports::{PeerConnectionType, ProtocolConfig, ProtocolController};
+use massa_protocol_exports::{ProtocolConfig, ProtocolController};
 use massa_serialization::{DeserializeError, Deserializer};
 
 use itertools::{izip, Itertools};
@@ -45,7 +45,7 @@ use massa_models::{
     composite::PubkeySig,
     config::CompactConfig,
     endorsement::EndorsementId,
-    execution::EventFilter,
+    execution::{EventFilter, ExecutedOpsDump},
     node::NodeId,
     operation::OperationId,
     output_event::SCOutputEvent,
@@ -55,7 +55,7 @@ use massa_models::{
     version::Version,
 };
 use massa_pool_exports::PoolController;
-use massa_signature::{KeyPair, PublicKey, PUBLIC_KEY_SIZE_BYTES};
+use massa_signature::KeyPair;
 use massa_storage::Storage;
 use massa_time::MassaTime;
 use std::collections::BTreeMap;
@@ -358,23 +358,11 @@ impl MassaRpcServer for API<Public> {
             Err(e) => return Err(ApiError::ModelsError(e).into()),
         };
 
-        let connected_nodes = peers
+        let connections = crate::node_connections(&peers);
+        let connected_nodes = connections
             .iter()
-            .map(|(id, peer)| {
-                let is_outgoing = match peer.1 {
-                    PeerConnectionType::IN => false,
-                    PeerConnectionType::OUT => true,
-                };
-                //TODO: Use the peerid correctly
-                (
-                    NodeId::new(
-                        PublicKey::from_bytes(
-                            id.to_bytes()[..PUBLIC_KEY_SIZE_BYTES].try_into().unwrap(),
-                        )
-                        .unwrap(),
-                    ),
-                    (peer.0.ip(), is_outgoing),
-                )
+            .map(|(node_id, connection)| {
+                (*node_id, (connection.address.ip(), connection.is_outgoing))
             })
             .collect::<BTreeMap<_, _>>();
 
@@ -417,6 +405,7 @@ impl MassaRpcServer for API<Public> {
             current_cycle_time,
             next_cycle_time,
             connected_nodes,
+            connections,
             last_slot,
             next_slot,
             execution_stats,
@@ -428,6 +417,17 @@ impl MassaRpcServer for API<Public> {
         })
     }
 
+    async fn get_protocol_stats(&self) -> RpcResult<ProtocolStatsInfo> {
+        let (stats, peers) = match self.0.protocol_controller.get_protocol_stats() {
+            Ok((stats, peers)) => (stats, peers),
+            Err(e) => return Err(ApiError::ProtocolError(e).into()),
+        };
+        Ok(ProtocolStatsInfo {
+            stats,
+            connections: crate::node_connections(&peers),
+        })
+    }
+
     async fn get_cliques(&self) -> RpcResult<Vec<Clique>> {
         let consensus_controller = self.0.consensus_controller.clone();
         Ok(consensus_controller.get_cliques())
@@ -1030,6 +1030,25 @@ impl MassaRpcServer for API<Public> {
         crate::wrong_api::<()>()
     }
 
+    async fn node_check_bootstrap(
+        &self,
+        _: SocketAddr,
+        _: Option<NodeId>,
+    ) -> RpcResult<BootstrapServerCheck> {
+        crate::wrong_api::<BootstrapServerCheck>()
+    }
+
+    async fn dump_executed_ops(&self, _: Option<PageRequest>) -> RpcResult<ExecutedOpsDump> {
+        crate::wrong_api::<ExecutedOpsDump>()
+    }
+
+    async fn node_get_connection_events(
+        &self,
+        _: Option<MassaTime>,
+    ) -> RpcResult<Vec<ConnectionEventInfo>> {
+        crate::wrong_api::<Vec<ConnectionEventInfo>>()
+    }
+
     async fn get_openrpc_spec(&self) -> RpcResult<Value> {
         let openrpc_spec_path = self.0.api_settings.openrpc_spec_path.clone();
         let openrpc: RpcResult<Value> = std::fs::read_to_string(openrpc_spec_path)
@@ -11,7 +11,10 @@ use crate::{
 };
 use massa_hash::{Hash, HASH_SIZE_BYTES};
 use massa_ledger_exports::LedgerChanges;
-use massa_models::{slot::Slot, streaming_step::StreamingStep};
+use massa_models::{
+    slot::Slot,
+    streaming_step::{get_map_streaming_chunk, StreamingBudget, StreamingCost, StreamingStep},
+};
 use massa_serialization::{
     Deserializer, SerializeError, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
 };
@@ -22,7 +25,7 @@ use nom::{
     IResult, Parser,
 };
 use std::collections::BTreeMap;
-use std::ops::Bound::{Excluded, Included, Unbounded};
+use std::ops::Bound::Included;
 
 const ASYNC_POOL_HASH_INITIAL_BYTES: &[u8; 32] = &[0; HASH_SIZE_BYTES];
 
@@ -214,26 +217,19 @@ impl AsyncPool {
         BTreeMap<AsyncMessageId, AsyncMessage>,
         StreamingStep<AsyncMessageId>,
     ) {
-        let mut pool_part = BTreeMap::new();
-        let left_bound = match cursor {
-            StreamingStep::Started => Unbounded,
-            StreamingStep::Ongoing(last_id) => Excluded(last_id),
-            StreamingStep::Finished(_) => return (pool_part, cursor),
-        };
-        let mut pool_part_last_id: Option<AsyncMessageId> = None;
-        for (id, message) in self.messages.range((left_bound, Unbounded)) {
-            if pool_part.len() < self.config.bootstrap_part_size as usize {
-                pool_part.insert(*id, message.clone());
-                pool_part_last_id = Some(*id);
-            } else {
-                break;
-            }
-        }
-        if let Some(last_id) = pool_part_last_id {
-            (pool_part, StreamingStep::Ongoing(last_id))
-        } else {
-            (pool_part, StreamingStep::Finished(None))
-        }
+        let (pool_part, next_step) = get_map_streaming_chunk(
+            &self.messages,
+            cursor,
+            StreamingBudget::entries(self.config.bootstrap_part_size),
+            |_, _| StreamingCost::entry(0),
+        );
+        (
+            pool_part
+                .into_iter()
+                .map(|(id, message)| (*id, message.clone()))
+                .collect(),
+            next_step,
+        )
     }
 
     /// Set a part of the async pool.
@@ -50,6 +50,11 @@ impl BootstrapClientBinder {
         }
     }
 
+    /// Public key of the bootstrap server
+    pub fn remote_pubkey(&self) -> &PublicKey {
+        &self.remote_pubkey
+    }
+
     /// Performs a handshake. Should be called after connection
     /// NOT cancel-safe
     pub fn handshake(&mut self, version: Version) -> Result<(), BootstrapError> {
@@ -79,6 +79,12 @@ impl BootstrapServerBinder {
             write_error_timeout,
         }
     }
+
+    /// Keypair the server messages are signed with
+    pub fn local_keypair(&self) -> &KeyPair {
+        &self.local_keypair
+    }
+
     /// Performs a handshake. Should be called after connection
     /// MUST always be followed by a send of the `BootstrapMessage::BootstrapTime`
     pub fn handshake_timeout(
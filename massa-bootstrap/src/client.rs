@@ -3,13 +3,19 @@ use std::{
     collections::HashSet,
     io,
     net::{SocketAddr, TcpStream},
-    sync::{Arc, Condvar, Mutex},
+    sync::{mpsc, Arc, Condvar, Mutex},
     time::Duration,
 };
 
+use massa_executed_ops::ExecutedOpsPart;
 use massa_final_state::FinalState;
+use massa_ledger_exports::Key as LedgerKey;
 use massa_logging::massa_trace;
-use massa_models::{node::NodeId, streaming_step::StreamingStep, version::Version};
+use massa_models::{
+    block_header::SecuredHeader, node::NodeId, slot::Slot, streaming_step::StreamingStep,
+    version::Version,
+};
+use massa_serialization::Serializer;
 use massa_signature::PublicKey;
 use massa_time::MassaTime;
 use massa_versioning_worker::versioning::{MipStore, MipStoreRaw};
@@ -23,7 +29,11 @@ use tracing::{debug, info, warn};
 use crate::{
     bindings::BootstrapClientBinder,
     error::BootstrapError,
-    messages::{BootstrapClientMessage, BootstrapServerMessage},
+    manifest::{BootstrapManifest, StateComponent},
+    messages::{
+        BootstrapClientMessage, BootstrapServerMessage, BootstrapServerMessageSerializer,
+        MAX_LIGHT_BOOTSTRAP_KEYS,
+    },
     settings::IpType,
     BootstrapConfig, GlobalBootstrapState,
 };
@@ -60,163 +70,269 @@ impl BSConnector for DefaultConnector {
         TcpStream::connect_timeout(&addr, duration.to_duration())
     }
 }
+/// Number of bootstrap parts that can be received ahead of the one being applied
+const RECEIVED_PARTS_BUFFER_SIZE: usize = 2;
+
+/// Message asking for the first part of the final state and consensus
+fn initial_bootstrap_part_message() -> BootstrapClientMessage {
+    BootstrapClientMessage::AskBootstrapPart {
+        last_slot: None,
+        last_ledger_step: StreamingStep::Started,
+        last_pool_step: StreamingStep::Started,
+        last_cycle_step: StreamingStep::Started,
+        last_credits_step: StreamingStep::Started,
+        last_ops_step: StreamingStep::Started,
+        last_ops_hash: ExecutedOpsPart::initial_cumulative_hash(),
+        last_de_step: StreamingStep::Started,
+        last_consensus_step: StreamingStep::Started,
+        send_last_start_period: true,
+    }
+}
+
 /// This function will send the starting point to receive a stream of the ledger and will receive and process each part until receive a `BootstrapServerMessage::FinalStateFinished` message from the server.
 /// `next_bootstrap_message` passed as parameter must be `BootstrapClientMessage::AskFinalStatePart` enum variant.
 /// `next_bootstrap_message` will be updated after receiving each part so that in case of connection lost we can restart from the last message we processed.
+///
+/// The next parts are received on a dedicated thread while the current one is applied,
+/// and the independent streams of a part are applied concurrently.
+/// `verified_parts` counts the parts verified and applied, so that the caller can tell
+/// whether the server stalled mid-stream.
+///
+/// The stream ends with a manifest of the server state, signed by the server,
+/// which the bootstrapped state has to match.
 fn stream_final_state_and_consensus(
     cfg: &BootstrapConfig,
     client: &mut BootstrapClientBinder,
     next_bootstrap_message: &mut BootstrapClientMessage,
     global_bootstrap_state: &mut GlobalBootstrapState,
+    verified_parts: &mut u64,
 ) -> Result<(), BootstrapError> {
-    if let BootstrapClientMessage::AskBootstrapPart { .. } = &next_bootstrap_message {
+    if let BootstrapClientMessage::AskBootstrapPart { last_ops_hash, .. } = &next_bootstrap_message
+    {
+        let mut last_ops_hash = *last_ops_hash;
         client.send_timeout(
             next_bootstrap_message,
             Some(cfg.write_timeout.to_duration()),
         )?;
 
-        loop {
-            match client.next_timeout(Some(cfg.read_timeout.to_duration()))? {
-                BootstrapServerMessage::BootstrapPart {
-                    slot,
-                    ledger_part,
-                    async_pool_part,
-                    pos_cycle_part,
-                    pos_credits_part,
-                    exec_ops_part,
-                    exec_de_part,
-                    final_state_changes,
-                    consensus_part,
-                    consensus_outdated_ids,
-                    last_start_period,
-                } => {
-                    // Set final state
-                    let mut write_final_state = global_bootstrap_state.final_state.write();
-
-                    // We only need to receive the initial_state once
-                    if let Some(last_start_period) = last_start_period {
-                        write_final_state.last_start_period = last_start_period;
-                    }
+        let server_pubkey = *client.remote_pubkey();
+        let mut state_checked = false;
+        // a server taking longer than this to send a part is considered stalled
+        let part_timeout = cfg.stall_timeout.to_duration();
+        std::thread::scope(|scope| {
+            let (message_tx, message_rx) = mpsc::sync_channel(RECEIVED_PARTS_BUFFER_SIZE);
+            scope.spawn(move || loop {
+                let message = client.next_timeout(Some(part_timeout));
+                let in_stream = matches!(
+                    message,
+                    Ok(BootstrapServerMessage::BootstrapPart { .. })
+                        | Ok(BootstrapServerMessage::BootstrapManifest { .. })
+                );
+                // stop at the end of the stream, or when the parts are not applied anymore
+                if message_tx.send(message).is_err() || !in_stream {
+                    break;
+                }
+            });
+
+            for message in message_rx {
+                match message? {
+                    BootstrapServerMessage::BootstrapPart {
+                        slot,
+                        ledger_part,
+                        async_pool_part,
+                        pos_cycle_part,
+                        pos_credits_part,
+                        exec_ops_part,
+                        exec_de_part,
+                        final_state_changes,
+                        consensus_part,
+                        consensus_outdated_ids,
+                        last_start_period,
+                    } => {
+                        // Verify the executed operations part before applying anything, so that
+                        // the stream resumes from the last verified part on the next attempt
+                        if let Err(err) = exec_ops_part.verify(&last_ops_hash) {
+                            return Err(BootstrapError::GeneralError(format!(
+                                "Invalid executed operations part: {}",
+                                err
+                            )));
+                        }
+                        last_ops_hash = exec_ops_part.cumulative_hash;
 
-                    let last_ledger_step = write_final_state.ledger.set_ledger_part(ledger_part)?;
-                    let last_pool_step =
-                        write_final_state.async_pool.set_pool_part(async_pool_part);
-                    let last_cycle_step = write_final_state
-                        .pos_state
-                        .set_cycle_history_part(pos_cycle_part);
-                    let last_credits_step = write_final_state
-                        .pos_state
-                        .set_deferred_credits_part(pos_credits_part);
-                    let last_ops_step = write_final_state
-                        .executed_ops
-                        .set_executed_ops_part(exec_ops_part);
-                    let last_de_step = write_final_state
-                        .executed_denunciations
-                        .set_executed_de_part(exec_de_part);
-                    for (changes_slot, changes) in final_state_changes.iter() {
-                        write_final_state.ledger.apply_changes(
-                            changes.ledger_changes.clone(),
-                            *changes_slot,
-                            None,
-                        );
-                        write_final_state
-                            .async_pool
-                            .apply_changes_unchecked(&changes.async_pool_changes);
-                        if !changes.pos_changes.is_empty() {
-                            write_final_state.pos_state.apply_changes(
-                                changes.pos_changes.clone(),
+                        // Set final state
+                        let mut write_final_state = global_bootstrap_state.final_state.write();
+                        let final_state = &mut *write_final_state;
+
+                        // We only need to receive the initial_state once
+                        if let Some(last_start_period) = last_start_period {
+                            final_state.last_start_period = last_start_period;
+                        }
+
+                        // The ledger part is written to disk on its own thread
+                        // while the parts of the in-memory components are applied
+                        let ledger = &final_state.ledger;
+                        let (
+                            ledger_result,
+                            last_pool_step,
+                            last_cycle_step,
+                            last_credits_step,
+                            last_ops_step,
+                            last_de_step,
+                        ) = std::thread::scope(|part_scope| {
+                            let ledger_handle =
+                                part_scope.spawn(move || ledger.set_ledger_part(ledger_part));
+                            let last_pool_step =
+                                final_state.async_pool.set_pool_part(async_pool_part);
+                            let last_cycle_step =
+                                final_state.pos_state.set_cycle_history_part(pos_cycle_part);
+                            let last_credits_step = final_state
+                                .pos_state
+                                .set_deferred_credits_part(pos_credits_part);
+                            let last_ops_step = final_state
+                                .executed_ops
+                                .set_executed_ops_part(exec_ops_part);
+                            let last_de_step = final_state
+                                .executed_denunciations
+                                .set_executed_de_part(exec_de_part);
+                            let ledger_result = ledger_handle
+                                .join()
+                                .expect("bootstrap ledger part thread panicked");
+                            (
+                                ledger_result,
+                                last_pool_step,
+                                last_cycle_step,
+                                last_credits_step,
+                                last_ops_step,
+                                last_de_step,
+                            )
+                        });
+                        let last_ledger_step = ledger_result?;
+
+                        for (changes_slot, changes) in final_state_changes.iter() {
+                            final_state.ledger.apply_changes(
+                                changes.ledger_changes.clone(),
+                                *changes_slot,
+                                None,
+                            );
+                            final_state
+                                .async_pool
+                                .apply_changes_unchecked(&changes.async_pool_changes);
+                            if !changes.pos_changes.is_empty() {
+                                final_state.pos_state.apply_changes(
+                                    changes.pos_changes.clone(),
+                                    *changes_slot,
+                                    false,
+                                )?;
+                            }
+                            // applied at every slot, even without changes, to prune the executed
+                            // operations at the same cadence as the server
+                            final_state.executed_ops.apply_changes(
+                                changes.executed_ops_changes.clone(),
                                 *changes_slot,
-                                false,
                             )?;
+                            final_state.executed_ops.prune_pending();
+                            final_state.executed_denunciations.apply_changes(
+                                changes.executed_denunciations_changes.clone(),
+                                *changes_slot,
+                            );
                         }
-                        if !changes.executed_ops_changes.is_empty() {
-                            write_final_state
-                                .executed_ops
-                                .apply_changes(changes.executed_ops_changes.clone(), *changes_slot);
+                        final_state.slot = slot;
+                        drop(write_final_state);
+
+                        // Set consensus blocks
+                        if let Some(graph) = global_bootstrap_state.graph.as_mut() {
+                            // Extend the final blocks with the received part
+                            graph.final_blocks.extend(consensus_part.final_blocks);
+                            // Remove every outdated block
+                            graph.final_blocks.retain(|block_export| {
+                                !consensus_outdated_ids.contains(&block_export.block.id)
+                            });
+                        } else {
+                            global_bootstrap_state.graph = Some(consensus_part);
                         }
+                        let last_consensus_step = StreamingStep::Ongoing(
+                            // Note that this unwrap call is safe because of the above conditional statement
+                            global_bootstrap_state
+                                .graph
+                                .as_ref()
+                                .unwrap()
+                                .final_blocks
+                                .iter()
+                                .map(|b_export| b_export.block.id)
+                                .collect(),
+                        );
+
+                        // Set new message in case of disconnection
+                        *next_bootstrap_message = BootstrapClientMessage::AskBootstrapPart {
+                            last_slot: Some(slot),
+                            last_ledger_step,
+                            last_pool_step,
+                            last_cycle_step,
+                            last_credits_step,
+                            last_ops_step,
+                            last_ops_hash,
+                            last_de_step,
+                            last_consensus_step,
+                            send_last_start_period: false,
+                        };
+                        *verified_parts += 1;
+
+                        // Logs for an easier diagnostic if needed
+                        debug!(
+                            "client final state bootstrap cursors: {:?}",
+                            next_bootstrap_message
+                        );
+                        debug!(
+                            "client final state slot changes length: {}",
+                            final_state_changes.len()
+                        );
                     }
-                    write_final_state.slot = slot;
-
-                    // Set consensus blocks
-                    if let Some(graph) = global_bootstrap_state.graph.as_mut() {
-                        // Extend the final blocks with the received part
-                        graph.final_blocks.extend(consensus_part.final_blocks);
-                        // Remove every outdated block
-                        graph.final_blocks.retain(|block_export| {
-                            !consensus_outdated_ids.contains(&block_export.block.id)
-                        });
-                    } else {
-                        global_bootstrap_state.graph = Some(consensus_part);
+                    BootstrapServerMessage::BootstrapManifest { manifest } => {
+                        manifest.verify_signature(&server_pubkey)?;
+                        if let Err(err) =
+                            manifest.check_final_state(&global_bootstrap_state.final_state.read())
+                        {
+                            // the received state cannot be trusted, start over from scratch
+                            *next_bootstrap_message = initial_bootstrap_part_message();
+                            global_bootstrap_state.final_state.write().reset();
+                            return Err(err);
+                        }
+                        state_checked = true;
+                    }
+                    BootstrapServerMessage::BootstrapFinished => {
+                        if !state_checked {
+                            return Err(BootstrapError::ManifestError(
+                                "state stream finished without a manifest".to_string(),
+                            ));
+                        }
+                        info!("State bootstrap complete");
+                        // Set next bootstrap message
+                        *next_bootstrap_message = BootstrapClientMessage::AskBootstrapPeers;
+                        return Ok(());
+                    }
+                    BootstrapServerMessage::SlotTooOld => {
+                        info!("Slot is too old retry bootstrap from scratch");
+                        *next_bootstrap_message = initial_bootstrap_part_message();
+                        let mut write_final_state = global_bootstrap_state.final_state.write();
+                        write_final_state.reset();
+                        return Err(BootstrapError::GeneralError(String::from("Slot too old")));
+                    }
+                    // At this point, we have succesfully received the next message from the server, and it's an error-message String
+                    BootstrapServerMessage::BootstrapError { error } => {
+                        return Err(BootstrapError::GeneralError(error))
+                    }
+                    _ => {
+                        return Err(BootstrapError::GeneralError(
+                            "unexpected message".to_string(),
+                        ))
                     }
-                    let last_consensus_step = StreamingStep::Ongoing(
-                        // Note that this unwrap call is safe because of the above conditional statement
-                        global_bootstrap_state
-                            .graph
-                            .as_ref()
-                            .unwrap()
-                            .final_blocks
-                            .iter()
-                            .map(|b_export| b_export.block.id)
-                            .collect(),
-                    );
-
-                    // Set new message in case of disconnection
-                    *next_bootstrap_message = BootstrapClientMessage::AskBootstrapPart {
-                        last_slot: Some(slot),
-                        last_ledger_step,
-                        last_pool_step,
-                        last_cycle_step,
-                        last_credits_step,
-                        last_ops_step,
-                        last_de_step,
-                        last_consensus_step,
-                        send_last_start_period: false,
-                    };
-
-                    // Logs for an easier diagnostic if needed
-                    debug!(
-                        "client final state bootstrap cursors: {:?}",
-                        next_bootstrap_message
-                    );
-                    debug!(
-                        "client final state slot changes length: {}",
-                        final_state_changes.len()
-                    );
-                }
-                BootstrapServerMessage::BootstrapFinished => {
-                    info!("State bootstrap complete");
-                    // Set next bootstrap message
-                    *next_bootstrap_message = BootstrapClientMessage::AskBootstrapPeers;
-                    return Ok(());
-                }
-                BootstrapServerMessage::SlotTooOld => {
-                    info!("Slot is too old retry bootstrap from scratch");
-                    *next_bootstrap_message = BootstrapClientMessage::AskBootstrapPart {
-                        last_slot: None,
-                        last_ledger_step: StreamingStep::Started,
-                        last_pool_step: StreamingStep::Started,
-                        last_cycle_step: StreamingStep::Started,
-                        last_credits_step: StreamingStep::Started,
-                        last_ops_step: StreamingStep::Started,
-                        last_de_step: StreamingStep::Started,
-                        last_consensus_step: StreamingStep::Started,
-                        send_last_start_period: true,
-                    };
-                    let mut write_final_state = global_bootstrap_state.final_state.write();
-                    write_final_state.reset();
-                    return Err(BootstrapError::GeneralError(String::from("Slot too old")));
-                }
-                // At this point, we have succesfully received the next message from the server, and it's an error-message String
-                BootstrapServerMessage::BootstrapError { error } => {
-                    return Err(BootstrapError::GeneralError(error))
-                }
-                _ => {
-                    return Err(BootstrapError::GeneralError(
-                        "unexpected message".to_string(),
-                    ))
                 }
             }
-        }
+            // the receiving thread only stops after forwarding a message that ends the stream
+            Err(BootstrapError::GeneralError(
+                "bootstrap part reception stopped unexpectedly".to_string(),
+            ))
+        })
     } else {
         Err(BootstrapError::GeneralError(format!(
             "Try to stream the final state but the message to send to the server was {:#?}",
@@ -233,6 +349,7 @@ fn bootstrap_from_server(
     next_bootstrap_message: &mut BootstrapClientMessage,
     global_bootstrap_state: &mut GlobalBootstrapState,
     our_version: Version,
+    verified_parts: &mut u64,
 ) -> Result<(), BootstrapError> {
     massa_trace!("bootstrap.lib.bootstrap_from_server", {});
 
@@ -319,11 +436,32 @@ fn bootstrap_from_server(
     loop {
         match next_bootstrap_message {
             BootstrapClientMessage::AskBootstrapPart { .. } => {
+                // check that the state of the server can be bootstrapped before streaming it
+                let manifest = match send_client_message(
+                    &BootstrapClientMessage::AskBootstrapManifest,
+                    client,
+                    write_timeout,
+                    cfg.read_timeout.into(),
+                    "ask bootstrap manifest timed out",
+                )? {
+                    BootstrapServerMessage::BootstrapManifest { manifest } => manifest,
+                    BootstrapServerMessage::BootstrapError { error } => {
+                        return Err(BootstrapError::ReceivedError(error))
+                    }
+                    other => return Err(BootstrapError::UnexpectedServerMessage(other)),
+                };
+                manifest.verify_signature(client.remote_pubkey())?;
+                manifest.check_compatibility()?;
+                debug!(
+                    "bootstrap server state at slot {}: {:?}",
+                    manifest.slot, manifest.components
+                );
                 stream_final_state_and_consensus(
                     cfg,
                     client,
                     next_bootstrap_message,
                     global_bootstrap_state,
+                    verified_parts,
                 )?;
             }
             BootstrapClientMessage::AskBootstrapPeers => {
@@ -369,6 +507,12 @@ fn bootstrap_from_server(
             BootstrapClientMessage::BootstrapError { error: _ } => {
                 panic!("The next message to send shouldn't be BootstrapError");
             }
+            BootstrapClientMessage::AskBootstrapManifest => {
+                panic!("The manifest is asked for before streaming the state, it shouldn't be the next message to send");
+            }
+            BootstrapClientMessage::AskLightBootstrap { .. } => {
+                panic!("The light bootstrap is not part of the full bootstrap, it shouldn't be the next message to send");
+            }
         };
     }
     info!("Successful bootstrap");
@@ -487,18 +631,7 @@ pub fn get_state(
     // we filter the bootstrap list to keep only the ip addresses we are compatible with
     let filtered_bootstrap_list = get_bootstrap_list_iter(bootstrap_config)?;
 
-    let mut next_bootstrap_message: BootstrapClientMessage =
-        BootstrapClientMessage::AskBootstrapPart {
-            last_slot: None,
-            last_ledger_step: StreamingStep::Started,
-            last_pool_step: StreamingStep::Started,
-            last_cycle_step: StreamingStep::Started,
-            last_credits_step: StreamingStep::Started,
-            last_ops_step: StreamingStep::Started,
-            last_de_step: StreamingStep::Started,
-            last_consensus_step: StreamingStep::Started,
-            send_last_start_period: true,
-        };
+    let mut next_bootstrap_message: BootstrapClientMessage = initial_bootstrap_part_message();
     let mut global_bootstrap_state = GlobalBootstrapState::new(final_state);
 
     loop {
@@ -515,6 +648,7 @@ pub fn get_state(
                 }
             }
             info!("Start bootstrapping from {}", addr);
+            let mut verified_parts = 0;
             match connect_to_server(
                 &mut connector,
                 bootstrap_config,
@@ -522,7 +656,7 @@ pub fn get_state(
                 &node_id.get_public_key(),
             ) {
                 Ok(mut client) => {
-                    match bootstrap_from_server(bootstrap_config, &mut client, &mut next_bootstrap_message, &mut global_bootstrap_state,version)
+                    match bootstrap_from_server(bootstrap_config, &mut client, &mut next_bootstrap_message, &mut global_bootstrap_state, version, &mut verified_parts)
                       // cancellable
                     {
                         Err(BootstrapError::ReceivedError(error)) => warn!("Error received from bootstrap server: {}", error),
@@ -541,6 +675,18 @@ pub fn get_state(
                 }
             };
 
+            // The server failed or stalled mid-stream: resume right away from another server,
+            // starting from the last verified part
+            if verified_parts > 0
+                && matches!(
+                    next_bootstrap_message,
+                    BootstrapClientMessage::AskBootstrapPart { .. }
+                )
+            {
+                info!("Bootstrap from server {} stopped after {} verified parts. Your node will resume from another server.", addr, verified_parts);
+                continue;
+            }
+
             info!("Bootstrap from server {} failed. Your node will try to bootstrap from another server in {}.", addr, format_duration(bootstrap_config.retry_delay.to_duration()).to_string());
 
             // Before, we would use a simple sleep(...), and that was fine
@@ -577,6 +723,224 @@ pub fn get_state(
     }
 }
 
+/// Information reported by a bootstrap server, see `check_bootstrap_server`
+#[derive(Debug, Clone)]
+pub struct BootstrapServerInfo {
+    /// version advertised by the server
+    pub version: Version,
+    /// round-trip time of the handshake
+    pub ping: MassaTime,
+    /// difference between the server clock and ours
+    pub clock_delta: MassaTime,
+    /// slot of the final state of the server
+    pub last_final_slot: Slot,
+    /// size in bytes of the first final state part sent by the server.
+    /// The full state is at least this size, and the size of parts is capped by
+    /// `max_bootstrap_final_state_parts_size`.
+    pub first_part_size: u64,
+}
+
+/// Connects to a bootstrap server without bootstrapping from it to check that it is usable.
+///
+/// Every message of the server is signed, so receiving a valid answer proves that the server
+/// owns the key of `node_id`. The server is then asked for the first part of its final state
+/// to learn its last final slot, and the connection is closed.
+pub fn check_bootstrap_server(
+    bootstrap_config: &BootstrapConfig,
+    mut connector: impl BSConnector,
+    addr: &SocketAddr,
+    node_id: &NodeId,
+    our_version: Version,
+) -> Result<BootstrapServerInfo, BootstrapError> {
+    massa_trace!("bootstrap.lib.check_bootstrap_server", {});
+    let mut client = connect_to_server(
+        &mut connector,
+        bootstrap_config,
+        addr,
+        &node_id.get_public_key(),
+    )?;
+
+    // read error (if sent by the server)
+    match client.next_timeout(Some(bootstrap_config.read_error_timeout.to_duration())) {
+        Err(BootstrapError::TimedOut(_)) => {}
+        Err(e) => return Err(e),
+        Ok(BootstrapServerMessage::BootstrapError { error }) => {
+            return Err(BootstrapError::ReceivedError(error))
+        }
+        Ok(msg) => return Err(BootstrapError::UnexpectedServerMessage(msg)),
+    };
+
+    let send_time = MassaTime::now()?;
+    client.handshake(our_version)?;
+    let (server_time, version) =
+        match client.next_timeout(Some(bootstrap_config.read_timeout.into()))? {
+            BootstrapServerMessage::BootstrapTime {
+                server_time,
+                version,
+            } => (server_time, version),
+            BootstrapServerMessage::BootstrapError { error } => {
+                return Err(BootstrapError::ReceivedError(error))
+            }
+            msg => return Err(BootstrapError::UnexpectedServerMessage(msg)),
+        };
+    let recv_time = MassaTime::now()?;
+    let ping = recv_time.saturating_sub(send_time);
+    let adjusted_server_time = server_time.checked_add(ping.checked_div_u64(2)?)?;
+    let clock_delta = adjusted_server_time.abs_diff(recv_time);
+
+    if !our_version.is_compatible(&version) {
+        return Err(BootstrapError::IncompatibleVersionError(format!(
+            "remote is running incompatible version: {} (local node version: {})",
+            version, our_version
+        )));
+    }
+
+    let part = send_client_message(
+        &initial_bootstrap_part_message(),
+        &mut client,
+        bootstrap_config.write_timeout.into(),
+        bootstrap_config.read_timeout.into(),
+        "ask bootstrap part timed out",
+    )?;
+    let mut buffer = Vec::new();
+    BootstrapServerMessageSerializer::new().serialize(&part, &mut buffer)?;
+    let last_final_slot = match part {
+        BootstrapServerMessage::BootstrapPart { slot, .. } => slot,
+        BootstrapServerMessage::BootstrapError { error } => {
+            return Err(BootstrapError::ReceivedError(error))
+        }
+        other => return Err(BootstrapError::UnexpectedServerMessage(other)),
+    };
+
+    // We don't care if the server doesn't receive this message, the socket is closed anyway.
+    let _ = client.send_timeout(
+        &BootstrapClientMessage::BootstrapError {
+            error: "bootstrap server check done".to_string(),
+        },
+        Some(bootstrap_config.write_error_timeout.into()),
+    );
+
+    Ok(BootstrapServerInfo {
+        version,
+        ping,
+        clock_delta,
+        last_final_slot,
+        first_part_size: buffer.len() as u64,
+    })
+}
+
+/// State of a bootstrap server obtained by a light client, see `get_light_state`
+#[derive(Debug, Clone)]
+pub struct LightBootstrapState {
+    /// signed manifest of the final state of the server
+    pub manifest: BootstrapManifest,
+    /// headers of the final blocks of the server, with valid signatures
+    pub headers: Vec<SecuredHeader>,
+    /// asked ledger keys with their value, `None` if the key is not in the ledger
+    pub ledger_entries: Vec<(LedgerKey, Option<Vec<u8>>)>,
+}
+
+/// Connects to a bootstrap server to get its state without downloading it, for light clients.
+///
+/// The server sends the signed manifest of its final state, the headers of its final blocks
+/// and the values of the asked ledger `keys` with their proof against the ledger hash of the
+/// manifest. The manifest signature, the proof and the header signatures are checked before
+/// returning.
+pub fn get_light_state(
+    bootstrap_config: &BootstrapConfig,
+    mut connector: impl BSConnector,
+    addr: &SocketAddr,
+    node_id: &NodeId,
+    our_version: Version,
+    keys: Vec<LedgerKey>,
+) -> Result<LightBootstrapState, BootstrapError> {
+    massa_trace!("bootstrap.lib.get_light_state", {});
+    if keys.len() as u64 > MAX_LIGHT_BOOTSTRAP_KEYS {
+        return Err(BootstrapError::GeneralError(format!(
+            "cannot ask proofs for more than {} ledger keys",
+            MAX_LIGHT_BOOTSTRAP_KEYS
+        )));
+    }
+    let mut client = connect_to_server(
+        &mut connector,
+        bootstrap_config,
+        addr,
+        &node_id.get_public_key(),
+    )?;
+
+    // read error (if sent by the server)
+    match client.next_timeout(Some(bootstrap_config.read_error_timeout.to_duration())) {
+        Err(BootstrapError::TimedOut(_)) => {}
+        Err(e) => return Err(e),
+        Ok(BootstrapServerMessage::BootstrapError { error }) => {
+            return Err(BootstrapError::ReceivedError(error))
+        }
+        Ok(msg) => return Err(BootstrapError::UnexpectedServerMessage(msg)),
+    };
+
+    client.handshake(our_version)?;
+    let version = match client.next_timeout(Some(bootstrap_config.read_timeout.into()))? {
+        BootstrapServerMessage::BootstrapTime { version, .. } => version,
+        BootstrapServerMessage::BootstrapError { error } => {
+            return Err(BootstrapError::ReceivedError(error))
+        }
+        msg => return Err(BootstrapError::UnexpectedServerMessage(msg)),
+    };
+    if !our_version.is_compatible(&version) {
+        return Err(BootstrapError::IncompatibleVersionError(format!(
+            "remote is running incompatible version: {} (local node version: {})",
+            version, our_version
+        )));
+    }
+
+    let (manifest, headers, ledger_proof) = match send_client_message(
+        &BootstrapClientMessage::AskLightBootstrap { keys },
+        &mut client,
+        bootstrap_config.write_timeout.into(),
+        bootstrap_config.read_timeout.into(),
+        "ask light bootstrap timed out",
+    )? {
+        BootstrapServerMessage::LightBootstrapState {
+            manifest,
+            headers,
+            ledger_proof,
+        } => (manifest, headers, ledger_proof),
+        BootstrapServerMessage::BootstrapError { error } => {
+            return Err(BootstrapError::ReceivedError(error))
+        }
+        other => return Err(BootstrapError::UnexpectedServerMessage(other)),
+    };
+
+    // We don't care if the server doesn't receive this message, the socket is closed anyway.
+    let _ = client.send_timeout(
+        &BootstrapClientMessage::BootstrapSuccess,
+        Some(bootstrap_config.write_error_timeout.into()),
+    );
+
+    manifest.verify_signature(client.remote_pubkey())?;
+    manifest.check_compatibility()?;
+    let Some(ledger_manifest) = manifest
+        .components
+        .iter()
+        .find(|c| c.component == StateComponent::Ledger) else {
+        return Err(BootstrapError::ManifestError(
+            "ledger is missing from the manifest".to_string(),
+        ));
+    };
+    ledger_proof
+        .verify(&ledger_manifest.hash)
+        .map_err(|err| BootstrapError::ManifestError(err.to_string()))?;
+    for header in &headers {
+        header.verify_signature()?;
+    }
+
+    Ok(LightBootstrapState {
+        manifest,
+        headers,
+        ledger_entries: ledger_proof.entries,
+    })
+}
+
 fn get_bootstrap_list_iter(
     bootstrap_config: &BootstrapConfig,
 ) -> Result<Vec<(SocketAddr, NodeId)>, BootstrapError> {
@@ -5,6 +5,7 @@ use std::io::ErrorKind;
 use crate::messages::{BootstrapClientMessage, BootstrapServerMessage};
 use displaydoc::Display;
 use massa_consensus_exports::error::ConsensusError;
+use massa_executed_ops::ExecutedOpsError;
 use massa_final_state::FinalStateError;
 use massa_hash::MassaHashError;
 use massa_pos_exports::PosError;
@@ -47,12 +48,16 @@ pub enum BootstrapError {
     ProtocolError(#[from] ProtocolError),
     /// final state error: {0}
     FinalStateError(#[from] FinalStateError),
+    /// executed operations error: {0}
+    ExecutedOpsError(#[from] ExecutedOpsError),
     /// Proof-of-Stake error: {0}
     PoSError(#[from] PosError),
     /// missing keypair file
     MissingKeyError,
     /// incompatible version: {0}
     IncompatibleVersionError(String),
+    /// bootstrap manifest error: {0}
+    ManifestError(String),
     /// Received error: {0}
     ReceivedError(String),
     /// clock error: {0}
@@ -27,15 +27,24 @@ mod error;
 pub use error::BootstrapError;
 mod listener;
 pub use listener::BootstrapTcpListener;
+mod manifest;
+pub use manifest::{
+    BootstrapManifest, BootstrapManifestDeserializer, BootstrapManifestSerializer,
+    ComponentManifest, StateComponent,
+};
 mod messages;
 mod server;
 mod settings;
 mod tools;
-pub use client::{get_state, DefaultConnector};
+pub use client::{
+    check_bootstrap_server, get_light_state, get_state, BootstrapServerInfo, DefaultConnector,
+    LightBootstrapState,
+};
 use massa_versioning_worker::versioning::MipStore;
 pub use messages::{
     BootstrapClientMessage, BootstrapClientMessageDeserializer, BootstrapClientMessageSerializer,
     BootstrapServerMessage, BootstrapServerMessageDeserializer, BootstrapServerMessageSerializer,
+    MAX_LIGHT_BOOTSTRAP_KEYS,
 };
 pub use server::{start_bootstrap_server, BootstrapManager};
 pub use settings::IpType;
@@ -0,0 +1,457 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Signed manifest of the final state served by a bootstrap server.
+//!
+//! The manifest lists each state component with the format version of its bootstrap parts,
+//! an estimate of its size, its hash and the version of the scheme of its hash.
+//! Clients check that they support every format and hash scheme
+//! before streaming the state, and check that the bootstrapped state matches the manifest
+//! once the stream is over. Since the manifest is signed by the bootstrap node, it can also
+//! be used to check a state obtained from a third-party host.
+
+use crate::error::BootstrapError;
+use massa_executed_ops::{ExecutedOpsHashScheme, EXECUTED_OPS_PART_VERSION};
+use massa_final_state::{FinalState, FinalStateHashes};
+use massa_hash::{Hash, HashDeserializer, HashSerializer};
+use massa_models::slot::{Slot, SlotDeserializer, SlotSerializer};
+use massa_serialization::{
+    Deserializer, OptionDeserializer, OptionSerializer, SerializeError, Serializer,
+    U32VarIntDeserializer, U32VarIntSerializer, U64VarIntDeserializer, U64VarIntSerializer,
+};
+use massa_signature::{KeyPair, PublicKey, Signature, SignatureDeserializer};
+use nom::error::{context, ContextError, ParseError};
+use nom::multi::length_count;
+use nom::sequence::tuple;
+use nom::{IResult, Parser};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use std::ops::Bound::{Excluded, Included};
+
+/// Components of the final state listed in a bootstrap manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[repr(u32)]
+pub enum StateComponent {
+    /// execution ledger
+    Ledger = 0u32,
+    /// asynchronous message pool
+    AsyncPool = 1u32,
+    /// Proof of Stake cycle history
+    CycleHistory = 2u32,
+    /// Proof of Stake deferred credits
+    DeferredCredits = 3u32,
+    /// executed operations
+    ExecutedOps = 4u32,
+    /// executed denunciations
+    ExecutedDenunciations = 5u32,
+}
+
+impl StateComponent {
+    /// Every component of the final state
+    pub const ALL: [StateComponent; 6] = [
+        StateComponent::Ledger,
+        StateComponent::AsyncPool,
+        StateComponent::CycleHistory,
+        StateComponent::DeferredCredits,
+        StateComponent::ExecutedOps,
+        StateComponent::ExecutedDenunciations,
+    ];
+
+    /// Format version of the bootstrap parts of the component supported by this node
+    pub fn supported_version(&self) -> u64 {
+        match self {
+            StateComponent::ExecutedOps => EXECUTED_OPS_PART_VERSION,
+            _ => 0,
+        }
+    }
+
+    /// Whether this node can compute the hash of the component with the scheme of `version`
+    pub fn supports_hash_scheme(&self, version: u64) -> bool {
+        match self {
+            StateComponent::ExecutedOps => ExecutedOpsHashScheme::from_version(version).is_some(),
+            _ => version == 0,
+        }
+    }
+}
+
+/// Description of a component of the final state in a bootstrap manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentManifest {
+    /// the described component
+    pub component: StateComponent,
+    /// format version of the bootstrap parts of the component
+    pub version: u64,
+    /// number of items of the component, if it is known without going through the whole component
+    pub item_count: Option<u64>,
+    /// hash of the component
+    pub hash: Hash,
+    /// version of the scheme of the hash of the component
+    pub hash_scheme: u64,
+}
+
+/// Signed description of the final state of a bootstrap server at a given slot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootstrapManifest {
+    /// slot at the output of which the described state is attached
+    pub slot: Slot,
+    /// final state hash
+    pub final_state_hash: Hash,
+    /// description of each component of the state
+    pub components: Vec<ComponentManifest>,
+    /// signature of the manifest by the bootstrap node
+    pub signature: Signature,
+}
+
+/// Hash of the global cycle hashes taken into account in the final state hash
+fn cycle_history_hash(hashes: &FinalStateHashes) -> Hash {
+    let mut hash_concat: Vec<u8> = Vec::with_capacity(hashes.cycle_history.len() * 32);
+    for cycle_hash in &hashes.cycle_history {
+        hash_concat.extend(cycle_hash.to_bytes());
+    }
+    Hash::compute_from(&hash_concat)
+}
+
+/// Hash of each component of the state, in the order of `StateComponent::ALL`
+fn component_hashes(hashes: &FinalStateHashes) -> [Hash; 6] {
+    [
+        hashes.ledger,
+        hashes.async_pool,
+        cycle_history_hash(hashes),
+        hashes.deferred_credits,
+        hashes.executed_ops,
+        hashes.executed_denunciations,
+    ]
+}
+
+/// Hash of the content of a manifest, its signature excluded
+fn compute_content_hash(
+    slot: &Slot,
+    final_state_hash: &Hash,
+    components: &[ComponentManifest],
+) -> Result<Hash, BootstrapError> {
+    let mut buffer = Vec::new();
+    BootstrapManifestSerializer::new().serialize_content(
+        slot,
+        final_state_hash,
+        components,
+        &mut buffer,
+    )?;
+    Ok(Hash::compute_from(&buffer))
+}
+
+impl BootstrapManifest {
+    /// Describes the given final state and signs the description with `keypair`
+    pub fn new(final_state: &FinalState, keypair: &KeyPair) -> Result<Self, BootstrapError> {
+        let hashes = final_state.get_component_hashes();
+        let item_counts = [
+            // the size of the ledger is unknown without going through the whole disk ledger
+            None,
+            Some(final_state.async_pool.messages.len() as u64),
+            Some(final_state.pos_state.cycle_history.len() as u64),
+            Some(
+                final_state
+                    .pos_state
+                    .deferred_credits
+                    .credits
+                    .values()
+                    .map(|credits| credits.len() as u64)
+                    .sum(),
+            ),
+            Some(final_state.executed_ops.len() as u64),
+            Some(final_state.executed_denunciations.len() as u64),
+        ];
+        let components: Vec<ComponentManifest> = StateComponent::ALL
+            .iter()
+            .zip(component_hashes(&hashes))
+            .zip(item_counts)
+            .map(|((component, hash), item_count)| ComponentManifest {
+                component: *component,
+                version: component.supported_version(),
+                item_count,
+                hash,
+                hash_scheme: match component {
+                    StateComponent::ExecutedOps => final_state.executed_ops.hash_scheme().version(),
+                    _ => 0,
+                },
+            })
+            .collect();
+        let final_state_hash = hashes.final_state_hash();
+        let signature = keypair.sign(&compute_content_hash(
+            &final_state.slot,
+            &final_state_hash,
+            &components,
+        )?)?;
+        Ok(BootstrapManifest {
+            slot: final_state.slot,
+            final_state_hash,
+            components,
+            signature,
+        })
+    }
+
+    /// Hash of the manifest content, which is what the bootstrap node signs
+    pub fn compute_hash(&self) -> Result<Hash, BootstrapError> {
+        compute_content_hash(&self.slot, &self.final_state_hash, &self.components)
+    }
+
+    /// Checks that the manifest was signed by the owner of `public_key`
+    pub fn verify_signature(&self, public_key: &PublicKey) -> Result<(), BootstrapError> {
+        public_key.verify_signature(&self.compute_hash()?, &self.signature)?;
+        Ok(())
+    }
+
+    /// Checks that every component of the state is listed with a format supported by this node
+    pub fn check_compatibility(&self) -> Result<(), BootstrapError> {
+        for component in StateComponent::ALL {
+            let Some(component_manifest) = self.components.iter().find(|c| c.component == component) else {
+                return Err(BootstrapError::ManifestError(format!(
+                    "component {:?} is missing from the manifest",
+                    component
+                )));
+            };
+            if component_manifest.version != component.supported_version() {
+                return Err(BootstrapError::IncompatibleVersionError(format!(
+                    "component {:?} is served with format version {} (supported version: {})",
+                    component,
+                    component_manifest.version,
+                    component.supported_version()
+                )));
+            }
+            if !component.supports_hash_scheme(component_manifest.hash_scheme) {
+                return Err(BootstrapError::IncompatibleVersionError(format!(
+                    "component {:?} is hashed with the unknown scheme version {}",
+                    component, component_manifest.hash_scheme
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the given final state is the complete state described by the manifest
+    pub fn check_final_state(&self, final_state: &FinalState) -> Result<(), BootstrapError> {
+        if final_state.slot != self.slot {
+            return Err(BootstrapError::ManifestError(format!(
+                "state is attached to slot {} but the manifest describes slot {}",
+                final_state.slot, self.slot
+            )));
+        }
+        let mut hashes = final_state.get_component_hashes();
+        // the server may hash the executed operations with another scheme than this node
+        let executed_ops_scheme = self
+            .components
+            .iter()
+            .find(|c| c.component == StateComponent::ExecutedOps)
+            .and_then(|c| ExecutedOpsHashScheme::from_version(c.hash_scheme));
+        if let Some(scheme) = executed_ops_scheme {
+            if scheme != final_state.executed_ops.hash_scheme() {
+                hashes.executed_ops = final_state.executed_ops.compute_hash(scheme);
+            }
+        }
+        for (component, hash) in StateComponent::ALL.iter().zip(component_hashes(&hashes)) {
+            match self.components.iter().find(|c| c.component == *component) {
+                Some(component_manifest) if component_manifest.hash == hash => {}
+                Some(_) => {
+                    return Err(BootstrapError::ManifestError(format!(
+                        "component {:?} does not match the manifest hash",
+                        component
+                    )))
+                }
+                None => {
+                    return Err(BootstrapError::ManifestError(format!(
+                        "component {:?} is missing from the manifest",
+                        component
+                    )))
+                }
+            }
+        }
+        if hashes.final_state_hash() != self.final_state_hash {
+            return Err(BootstrapError::ManifestError(
+                "final state hash does not match the manifest".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Serializer for `BootstrapManifest`
+pub struct BootstrapManifestSerializer {
+    slot_serializer: SlotSerializer,
+    hash_serializer: HashSerializer,
+    u32_serializer: U32VarIntSerializer,
+    u64_serializer: U64VarIntSerializer,
+    opt_u64_serializer: OptionSerializer<u64, U64VarIntSerializer>,
+}
+
+impl Default for BootstrapManifestSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BootstrapManifestSerializer {
+    /// Creates a new `BootstrapManifestSerializer`
+    pub fn new() -> Self {
+        Self {
+            slot_serializer: SlotSerializer::new(),
+            hash_serializer: HashSerializer::new(),
+            u32_serializer: U32VarIntSerializer::new(),
+            u64_serializer: U64VarIntSerializer::new(),
+            opt_u64_serializer: OptionSerializer::new(U64VarIntSerializer::new()),
+        }
+    }
+
+    /// Serializes everything but the signature of the manifest
+    fn serialize_content(
+        &self,
+        slot: &Slot,
+        final_state_hash: &Hash,
+        components: &[ComponentManifest],
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.slot_serializer.serialize(slot, buffer)?;
+        self.hash_serializer.serialize(final_state_hash, buffer)?;
+        self.u32_serializer.serialize(
+            &components.len().try_into().map_err(|_| {
+                SerializeError::GeneralError("Fail to convert usize to u32".to_string())
+            })?,
+            buffer,
+        )?;
+        for component_manifest in components {
+            self.u32_serializer
+                .serialize(&u32::from(component_manifest.component), buffer)?;
+            self.u64_serializer
+                .serialize(&component_manifest.version, buffer)?;
+            self.opt_u64_serializer
+                .serialize(&component_manifest.item_count, buffer)?;
+            self.hash_serializer
+                .serialize(&component_manifest.hash, buffer)?;
+            self.u64_serializer
+                .serialize(&component_manifest.hash_scheme, buffer)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serializer<BootstrapManifest> for BootstrapManifestSerializer {
+    fn serialize(
+        &self,
+        value: &BootstrapManifest,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.serialize_content(
+            &value.slot,
+            &value.final_state_hash,
+            &value.components,
+            buffer,
+        )?;
+        buffer.extend(value.signature.to_bytes());
+        Ok(())
+    }
+}
+
+/// Deserializer for `BootstrapManifest`
+pub struct BootstrapManifestDeserializer {
+    slot_deserializer: SlotDeserializer,
+    hash_deserializer: HashDeserializer,
+    length_deserializer: U32VarIntDeserializer,
+    component_deserializer: U32VarIntDeserializer,
+    u64_deserializer: U64VarIntDeserializer,
+    opt_u64_deserializer: OptionDeserializer<u64, U64VarIntDeserializer>,
+    signature_deserializer: SignatureDeserializer,
+}
+
+impl BootstrapManifestDeserializer {
+    /// Creates a new `BootstrapManifestDeserializer`
+    pub fn new(thread_count: u8) -> Self {
+        Self {
+            slot_deserializer: SlotDeserializer::new(
+                (Included(0), Included(u64::MAX)),
+                (Included(0), Excluded(thread_count)),
+            ),
+            hash_deserializer: HashDeserializer::new(),
+            length_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(StateComponent::ALL.len() as u32),
+            ),
+            component_deserializer: U32VarIntDeserializer::new(Included(0), Included(u32::MAX)),
+            u64_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+            opt_u64_deserializer: OptionDeserializer::new(U64VarIntDeserializer::new(
+                Included(0),
+                Included(u64::MAX),
+            )),
+            signature_deserializer: SignatureDeserializer::new(),
+        }
+    }
+}
+
+impl Deserializer<BootstrapManifest> for BootstrapManifestDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], BootstrapManifest, E> {
+        context(
+            "Failed BootstrapManifest deserialization",
+            tuple((
+                context("Failed slot deserialization", |input| {
+                    self.slot_deserializer.deserialize(input)
+                }),
+                context("Failed final_state_hash deserialization", |input| {
+                    self.hash_deserializer.deserialize(input)
+                }),
+                context(
+                    "Failed components deserialization",
+                    length_count(
+                        context("Failed length deserialization", |input| {
+                            self.length_deserializer.deserialize(input)
+                        }),
+                        tuple((
+                            context("Failed component deserialization", |input: &'a [u8]| {
+                                let (rest, id) = self.component_deserializer.deserialize(input)?;
+                                let component = StateComponent::try_from(id).map_err(|_| {
+                                    nom::Err::Error(ParseError::from_error_kind(
+                                        input,
+                                        nom::error::ErrorKind::Fail,
+                                    ))
+                                })?;
+                                Ok((rest, component))
+                            }),
+                            context("Failed version deserialization", |input| {
+                                self.u64_deserializer.deserialize(input)
+                            }),
+                            context("Failed item_count deserialization", |input| {
+                                self.opt_u64_deserializer.deserialize(input)
+                            }),
+                            context("Failed hash deserialization", |input| {
+                                self.hash_deserializer.deserialize(input)
+                            }),
+                            context("Failed hash_scheme deserialization", |input| {
+                                self.u64_deserializer.deserialize(input)
+                            }),
+                        ))
+                        .map(
+                            |(component, version, item_count, hash, hash_scheme)| {
+                                ComponentManifest {
+                                    component,
+                                    version,
+                                    item_count,
+                                    hash,
+                                    hash_scheme,
+                                }
+                            },
+                        ),
+                    ),
+                ),
+                context("Failed signature deserialization", |input| {
+                    self.signature_deserializer.deserialize(input)
+                }),
+            )),
+        )
+        .map(
+            |(slot, final_state_hash, components, signature)| BootstrapManifest {
+                slot,
+                final_state_hash,
+                components,
+                signature,
+            },
+        )
+        .parse(buffer)
+    }
+}
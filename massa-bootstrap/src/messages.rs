@@ -1,5 +1,8 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use crate::manifest::{
+    BootstrapManifest, BootstrapManifestDeserializer, BootstrapManifestSerializer,
+};
 use crate::settings::BootstrapServerMessageDeserializerArgs;
 use massa_async_pool::{
     AsyncMessage, AsyncMessageId, AsyncMessageIdDeserializer, AsyncMessageIdSerializer,
@@ -9,15 +12,22 @@ use massa_consensus_exports::bootstrapable_graph::{
     BootstrapableGraph, BootstrapableGraphDeserializer, BootstrapableGraphSerializer,
 };
 use massa_executed_ops::{
-    ExecutedDenunciationsDeserializer, ExecutedDenunciationsSerializer, ExecutedOpsDeserializer,
-    ExecutedOpsSerializer,
+    ExecutedDenunciationsDeserializer, ExecutedDenunciationsSerializer,
+    ExecutedOpsCursorDeserializer, ExecutedOpsCursorSerializer, ExecutedOpsPart,
+    ExecutedOpsPartDeserializer, ExecutedOpsPartSerializer, ExecutedOpsStreamingStep,
 };
 use massa_final_state::{StateChanges, StateChangesDeserializer, StateChangesSerializer};
-use massa_ledger_exports::{Key as LedgerKey, KeyDeserializer, KeySerializer};
+use massa_hash::{Hash, HashDeserializer, HashSerializer};
+use massa_ledger_exports::{
+    Key as LedgerKey, KeyDeserializer, KeySerializer, LedgerProof, LedgerProofDeserializer,
+    LedgerProofSerializer,
+};
+use massa_models::block_header::{BlockHeader, BlockHeaderDeserializer, SecuredHeader};
 use massa_models::block_id::{BlockId, BlockIdDeserializer, BlockIdSerializer};
 use massa_models::denunciation::DenunciationIndex;
 use massa_models::operation::OperationId;
 use massa_models::prehash::PreHashSet;
+use massa_models::secure_share::{SecureShareDeserializer, SecureShareSerializer};
 use massa_models::serialization::{
     PreHashSetDeserializer, PreHashSetSerializer, VecU8Deserializer, VecU8Serializer,
 };
@@ -54,6 +64,9 @@ use std::collections::{BTreeMap, HashSet};
 use std::convert::TryInto;
 use std::ops::Bound::{Excluded, Included};
 
+/// Maximum number of ledger keys a light client can ask proofs for in one request
+pub const MAX_LIGHT_BOOTSTRAP_KEYS: u64 = 1000;
+
 /// Messages used during bootstrap by server
 #[derive(Debug, Clone)]
 #[allow(clippy::large_enum_variant)]
@@ -83,7 +96,7 @@ pub enum BootstrapServerMessage {
         /// Part of the Proof of Stake `deferred_credits`
         pos_credits_part: DeferredCredits,
         /// Part of the executed operations
-        exec_ops_part: BTreeMap<Slot, PreHashSet<OperationId>>,
+        exec_ops_part: ExecutedOpsPart,
         /// Part of the executed operations
         exec_de_part: BTreeMap<Slot, HashSet<DenunciationIndex>>,
         /// Ledger change for addresses inferior to `address` of the client message until the actual slot.
@@ -100,6 +113,20 @@ pub enum BootstrapServerMessage {
         /// Server mip store
         store: MipStoreRaw,
     },
+    /// Signed manifest of the final state of the server
+    BootstrapManifest {
+        /// Server final state manifest
+        manifest: BootstrapManifest,
+    },
+    /// State served to light clients, which don't download the full state
+    LightBootstrapState {
+        /// Server final state manifest
+        manifest: BootstrapManifest,
+        /// Headers of the final blocks of the server
+        headers: Vec<SecuredHeader>,
+        /// Values of the asked ledger keys, proved against the ledger hash of the manifest
+        ledger_proof: LedgerProof,
+    },
     /// Message sent when the final state and consensus bootstrap are finished
     BootstrapFinished,
     /// Slot sent to get state changes is too old
@@ -117,6 +144,20 @@ impl ToString for BootstrapServerMessage {
             BootstrapServerMessage::BootstrapTime { .. } => "BootstrapTime".to_string(),
             BootstrapServerMessage::BootstrapPeers { .. } => "BootstrapPeers".to_string(),
             BootstrapServerMessage::BootstrapPart { .. } => "BootstrapPart".to_string(),
+            BootstrapServerMessage::BootstrapManifest { manifest } => format!(
+                "BootstrapManifest {{ slot: {}, final_state_hash: {} }}",
+                manifest.slot, manifest.final_state_hash
+            ),
+            BootstrapServerMessage::LightBootstrapState {
+                manifest,
+                headers,
+                ledger_proof,
+            } => format!(
+                "LightBootstrapState {{ slot: {}, headers: {}, ledger_entries: {} }}",
+                manifest.slot,
+                headers.len(),
+                ledger_proof.entries.len()
+            ),
             BootstrapServerMessage::BootstrapFinished => "BootstrapFinished".to_string(),
             BootstrapServerMessage::SlotTooOld => "SlotTooOld".to_string(),
             BootstrapServerMessage::BootstrapError { error } => {
@@ -139,6 +180,8 @@ enum MessageServerTypeId {
     SlotTooOld = 4u32,
     BootstrapError = 5u32,
     MipStore = 6u32,
+    Manifest = 7u32,
+    LightState = 8u32,
 }
 
 /// Serializer for `BootstrapServerMessage`
@@ -156,10 +199,13 @@ pub struct BootstrapServerMessageSerializer {
     async_pool_serializer: AsyncPoolSerializer,
     opt_pos_cycle_serializer: OptionSerializer<CycleInfo, CycleInfoSerializer>,
     pos_credits_serializer: DeferredCreditsSerializer,
-    exec_ops_serializer: ExecutedOpsSerializer,
+    exec_ops_serializer: ExecutedOpsPartSerializer,
     exec_de_serializer: ExecutedDenunciationsSerializer,
     opt_last_start_period_serializer: OptionSerializer<u64, U64VarIntSerializer>,
     store_serializer: MipStoreRawSerializer,
+    manifest_serializer: BootstrapManifestSerializer,
+    header_serializer: SecureShareSerializer,
+    ledger_proof_serializer: LedgerProofSerializer,
 }
 
 impl Default for BootstrapServerMessageSerializer {
@@ -185,10 +231,13 @@ impl BootstrapServerMessageSerializer {
             async_pool_serializer: AsyncPoolSerializer::new(),
             opt_pos_cycle_serializer: OptionSerializer::new(CycleInfoSerializer::new()),
             pos_credits_serializer: DeferredCreditsSerializer::new(),
-            exec_ops_serializer: ExecutedOpsSerializer::new(),
+            exec_ops_serializer: ExecutedOpsPartSerializer::new(),
             exec_de_serializer: ExecutedDenunciationsSerializer::new(),
             opt_last_start_period_serializer: OptionSerializer::new(U64VarIntSerializer::new()),
             store_serializer: MipStoreRawSerializer::new(),
+            manifest_serializer: BootstrapManifestSerializer::new(),
+            header_serializer: SecureShareSerializer::new(),
+            ledger_proof_serializer: LedgerProofSerializer::new(),
         }
     }
 }
@@ -287,6 +336,31 @@ impl Serializer<BootstrapServerMessage> for BootstrapServerMessageSerializer {
                     .serialize(&u32::from(MessageServerTypeId::MipStore), buffer)?;
                 self.store_serializer.serialize(store_raw, buffer)?;
             }
+            BootstrapServerMessage::BootstrapManifest { manifest } => {
+                self.u32_serializer
+                    .serialize(&u32::from(MessageServerTypeId::Manifest), buffer)?;
+                self.manifest_serializer.serialize(manifest, buffer)?;
+            }
+            BootstrapServerMessage::LightBootstrapState {
+                manifest,
+                headers,
+                ledger_proof,
+            } => {
+                self.u32_serializer
+                    .serialize(&u32::from(MessageServerTypeId::LightState), buffer)?;
+                self.manifest_serializer.serialize(manifest, buffer)?;
+                self.u32_serializer.serialize(
+                    &headers.len().try_into().map_err(|_| {
+                        SerializeError::GeneralError("Fail to convert usize to u32".to_string())
+                    })?,
+                    buffer,
+                )?;
+                for header in headers {
+                    self.header_serializer.serialize(header, buffer)?;
+                }
+                self.ledger_proof_serializer
+                    .serialize(ledger_proof, buffer)?;
+            }
             BootstrapServerMessage::BootstrapFinished => {
                 self.u32_serializer
                     .serialize(&u32::from(MessageServerTypeId::FinalStateFinished), buffer)?;
@@ -327,10 +401,14 @@ pub struct BootstrapServerMessageDeserializer {
     async_pool_deserializer: AsyncPoolDeserializer,
     opt_pos_cycle_deserializer: OptionDeserializer<CycleInfo, CycleInfoDeserializer>,
     pos_credits_deserializer: DeferredCreditsDeserializer,
-    exec_ops_deserializer: ExecutedOpsDeserializer,
+    exec_ops_deserializer: ExecutedOpsPartDeserializer,
     executed_de_deserializer: ExecutedDenunciationsDeserializer,
     opt_last_start_period_deserializer: OptionDeserializer<u64, U64VarIntDeserializer>,
     store_deserializer: MipStoreRawDeserializer,
+    manifest_deserializer: BootstrapManifestDeserializer,
+    length_headers_deserializer: U32VarIntDeserializer,
+    header_deserializer: SecureShareDeserializer<BlockHeader, BlockHeaderDeserializer>,
+    ledger_proof_deserializer: LedgerProofDeserializer,
 }
 
 impl BootstrapServerMessageDeserializer {
@@ -403,10 +481,11 @@ impl BootstrapServerMessageDeserializer {
                 args.max_credits_length,
                 false,
             ),
-            exec_ops_deserializer: ExecutedOpsDeserializer::new(
+            exec_ops_deserializer: ExecutedOpsPartDeserializer::new(
                 args.thread_count,
                 args.max_executed_ops_length,
                 args.max_operations_per_block as u64,
+                args.max_executed_ops_part_length,
             ),
             executed_de_deserializer: ExecutedDenunciationsDeserializer::new(
                 args.thread_count,
@@ -421,6 +500,22 @@ impl BootstrapServerMessageDeserializer {
                 args.mip_store_stats_block_considered,
                 args.mip_store_stats_counters_max,
             ),
+            manifest_deserializer: BootstrapManifestDeserializer::new(args.thread_count),
+            length_headers_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(args.max_bootstrap_blocks_length),
+            ),
+            header_deserializer: SecureShareDeserializer::new(BlockHeaderDeserializer::new(
+                args.thread_count,
+                args.endorsement_count,
+                args.max_denunciations_per_block_header,
+                None,
+            )),
+            ledger_proof_deserializer: LedgerProofDeserializer::new(
+                MAX_LIGHT_BOOTSTRAP_KEYS,
+                args.max_datastore_key_length,
+                args.max_bootstrap_final_state_parts_size,
+            ),
         }
     }
 }
@@ -446,7 +541,8 @@ impl Deserializer<BootstrapServerMessage> for BootstrapServerMessageDeserializer
     ///     max_datastore_value_length: 1000,
     ///     max_datastore_entry_count: 1000, max_bootstrap_error_length: 1000, max_changes_slot_count: 1000,
     ///     max_rolls_length: 1000, max_production_stats_length: 1000, max_credits_length: 1000,
-    ///     max_executed_ops_length: 1000, max_ops_changes_length: 1000,
+    ///     max_executed_ops_length: 1000, max_executed_ops_part_length: 1000,
+    ///     max_ops_changes_length: 1000,
     ///     mip_store_stats_block_considered: 100, mip_store_stats_counters_max: 10,
     ///     max_denunciations_per_block_header: 128, max_denunciation_changes_length: 1000,};
     /// let message_deserializer = BootstrapServerMessageDeserializer::new(args);
@@ -588,6 +684,40 @@ impl Deserializer<BootstrapServerMessage> for BootstrapServerMessageDeserializer
                     },
                 )
                 .parse(input),
+                MessageServerTypeId::Manifest => {
+                    context("Failed manifest deserialization", |input| {
+                        self.manifest_deserializer.deserialize(input)
+                    })
+                    .map(|manifest| BootstrapServerMessage::BootstrapManifest { manifest })
+                    .parse(input)
+                }
+                MessageServerTypeId::LightState => tuple((
+                    context("Failed manifest deserialization", |input| {
+                        self.manifest_deserializer.deserialize(input)
+                    }),
+                    context(
+                        "Failed headers deserialization",
+                        length_count(
+                            context("Failed length deserialization", |input| {
+                                self.length_headers_deserializer.deserialize(input)
+                            }),
+                            context("Failed header deserialization", |input| {
+                                self.header_deserializer.deserialize(input)
+                            }),
+                        ),
+                    ),
+                    context("Failed ledger_proof deserialization", |input| {
+                        self.ledger_proof_deserializer.deserialize(input)
+                    }),
+                ))
+                .map(|(manifest, headers, ledger_proof)| {
+                    BootstrapServerMessage::LightBootstrapState {
+                        manifest,
+                        headers,
+                        ledger_proof,
+                    }
+                })
+                .parse(input),
                 MessageServerTypeId::FinalStateFinished => {
                     Ok((input, BootstrapServerMessage::BootstrapFinished))
                 }
@@ -626,8 +756,10 @@ pub enum BootstrapClientMessage {
         last_cycle_step: StreamingStep<u64>,
         /// Last received Proof of Stake credits slot
         last_credits_step: StreamingStep<Slot>,
-        /// Last received executed operation associated slot
-        last_ops_step: StreamingStep<Slot>,
+        /// Last received executed operation and its associated slot
+        last_ops_step: ExecutedOpsStreamingStep,
+        /// Cumulative hash of the last verified executed operations part
+        last_ops_hash: Hash,
         /// Last received executed denunciations associated slot
         last_de_step: StreamingStep<Slot>,
         /// Last received consensus block slot
@@ -637,6 +769,13 @@ pub enum BootstrapClientMessage {
     },
     /// Ask for mip store
     AskBootstrapMipStore,
+    /// Ask for the manifest of the server final state
+    AskBootstrapManifest,
+    /// Ask for the state served to light clients, with proofs of the given ledger keys
+    AskLightBootstrap {
+        /// Ledger keys to prove
+        keys: Vec<LedgerKey>,
+    },
     /// Bootstrap error
     BootstrapError {
         /// Error message
@@ -654,6 +793,8 @@ enum MessageClientTypeId {
     BootstrapError = 2u32,
     BootstrapSuccess = 3u32,
     AskBootstrapMipStore = 4u32,
+    AskBootstrapManifest = 5u32,
+    AskLightBootstrap = 6u32,
 }
 
 /// Serializer for `BootstrapClientMessage`
@@ -664,11 +805,15 @@ pub struct BootstrapClientMessageSerializer {
     pool_step_serializer: StreamingStepSerializer<AsyncMessageId, AsyncMessageIdSerializer>,
     cycle_step_serializer: StreamingStepSerializer<u64, U64VarIntSerializer>,
     slot_step_serializer: StreamingStepSerializer<Slot, SlotSerializer>,
+    ops_step_serializer: StreamingStepSerializer<(Slot, OperationId), ExecutedOpsCursorSerializer>,
+    hash_serializer: HashSerializer,
     block_ids_step_serializer: StreamingStepSerializer<
         PreHashSet<BlockId>,
         PreHashSetSerializer<BlockId, BlockIdSerializer>,
     >,
     bool_serializer: BoolSerializer,
+    u64_serializer: U64VarIntSerializer,
+    key_serializer: KeySerializer,
 }
 
 impl BootstrapClientMessageSerializer {
@@ -681,10 +826,14 @@ impl BootstrapClientMessageSerializer {
             pool_step_serializer: StreamingStepSerializer::new(AsyncMessageIdSerializer::new()),
             cycle_step_serializer: StreamingStepSerializer::new(U64VarIntSerializer::new()),
             slot_step_serializer: StreamingStepSerializer::new(SlotSerializer::new()),
+            ops_step_serializer: StreamingStepSerializer::new(ExecutedOpsCursorSerializer::new()),
+            hash_serializer: HashSerializer::new(),
             block_ids_step_serializer: StreamingStepSerializer::new(PreHashSetSerializer::new(
                 BlockIdSerializer::new(),
             )),
             bool_serializer: BoolSerializer::new(),
+            u64_serializer: U64VarIntSerializer::new(),
+            key_serializer: KeySerializer::new(true),
         }
     }
 }
@@ -726,6 +875,7 @@ impl Serializer<BootstrapClientMessage> for BootstrapClientMessageSerializer {
                 last_cycle_step,
                 last_credits_step,
                 last_ops_step,
+                last_ops_hash,
                 last_de_step,
                 last_consensus_step,
                 send_last_start_period,
@@ -742,7 +892,8 @@ impl Serializer<BootstrapClientMessage> for BootstrapClientMessageSerializer {
                         .serialize(last_cycle_step, buffer)?;
                     self.slot_step_serializer
                         .serialize(last_credits_step, buffer)?;
-                    self.slot_step_serializer.serialize(last_ops_step, buffer)?;
+                    self.ops_step_serializer.serialize(last_ops_step, buffer)?;
+                    self.hash_serializer.serialize(last_ops_hash, buffer)?;
                     self.slot_step_serializer.serialize(last_de_step, buffer)?;
                     self.block_ids_step_serializer
                         .serialize(last_consensus_step, buffer)?;
@@ -771,6 +922,21 @@ impl Serializer<BootstrapClientMessage> for BootstrapClientMessageSerializer {
                     buffer,
                 )?;
             }
+            BootstrapClientMessage::AskBootstrapManifest => {
+                self.u32_serializer.serialize(
+                    &u32::from(MessageClientTypeId::AskBootstrapManifest),
+                    buffer,
+                )?;
+            }
+            BootstrapClientMessage::AskLightBootstrap { keys } => {
+                self.u32_serializer
+                    .serialize(&u32::from(MessageClientTypeId::AskLightBootstrap), buffer)?;
+                self.u64_serializer
+                    .serialize(&(keys.len() as u64), buffer)?;
+                for key in keys {
+                    self.key_serializer.serialize(key, buffer)?;
+                }
+            }
         }
         Ok(())
     }
@@ -785,11 +951,16 @@ pub struct BootstrapClientMessageDeserializer {
     pool_step_deserializer: StreamingStepDeserializer<AsyncMessageId, AsyncMessageIdDeserializer>,
     cycle_step_deserializer: StreamingStepDeserializer<u64, U64VarIntDeserializer>,
     slot_step_deserializer: StreamingStepDeserializer<Slot, SlotDeserializer>,
+    ops_step_deserializer:
+        StreamingStepDeserializer<(Slot, OperationId), ExecutedOpsCursorDeserializer>,
+    hash_deserializer: HashDeserializer,
     block_ids_step_deserializer: StreamingStepDeserializer<
         PreHashSet<BlockId>,
         PreHashSetDeserializer<BlockId, BlockIdDeserializer>,
     >,
     bool_deserializer: BoolDeserializer,
+    length_keys_deserializer: U64VarIntDeserializer,
+    key_deserializer: KeyDeserializer,
 }
 
 impl BootstrapClientMessageDeserializer {
@@ -821,6 +992,10 @@ impl BootstrapClientMessageDeserializer {
                 (Included(0), Included(u64::MAX)),
                 (Included(0), Excluded(thread_count)),
             )),
+            ops_step_deserializer: StreamingStepDeserializer::new(
+                ExecutedOpsCursorDeserializer::new(thread_count),
+            ),
+            hash_deserializer: HashDeserializer::new(),
             block_ids_step_deserializer: StreamingStepDeserializer::new(
                 PreHashSetDeserializer::new(
                     BlockIdDeserializer::new(),
@@ -829,6 +1004,11 @@ impl BootstrapClientMessageDeserializer {
                 ),
             ),
             bool_deserializer: BoolDeserializer::new(),
+            length_keys_deserializer: U64VarIntDeserializer::new(
+                Included(0),
+                Included(MAX_LIGHT_BOOTSTRAP_KEYS),
+            ),
+            key_deserializer: KeyDeserializer::new(max_datastore_key_length, true),
         }
     }
 }
@@ -878,6 +1058,22 @@ impl Deserializer<BootstrapClientMessage> for BootstrapClientMessageDeserializer
                 MessageClientTypeId::AskBootstrapMipStore => {
                     Ok((input, BootstrapClientMessage::AskBootstrapMipStore))
                 }
+                MessageClientTypeId::AskBootstrapManifest => {
+                    Ok((input, BootstrapClientMessage::AskBootstrapManifest))
+                }
+                MessageClientTypeId::AskLightBootstrap => context(
+                    "Failed keys deserialization",
+                    length_count(
+                        context("Failed length deserialization", |input| {
+                            self.length_keys_deserializer.deserialize(input)
+                        }),
+                        context("Failed key deserialization", |input| {
+                            self.key_deserializer.deserialize(input)
+                        }),
+                    ),
+                )
+                .map(|keys| BootstrapClientMessage::AskLightBootstrap { keys })
+                .parse(input),
                 MessageClientTypeId::AskFinalStatePart => {
                     if input.is_empty() {
                         Ok((
@@ -889,6 +1085,7 @@ impl Deserializer<BootstrapClientMessage> for BootstrapClientMessageDeserializer
                                 last_cycle_step: StreamingStep::Started,
                                 last_credits_step: StreamingStep::Started,
                                 last_ops_step: StreamingStep::Started,
+                                last_ops_hash: ExecutedOpsPart::initial_cumulative_hash(),
                                 last_de_step: StreamingStep::Started,
                                 last_consensus_step: StreamingStep::Started,
                                 send_last_start_period: true,
@@ -912,7 +1109,10 @@ impl Deserializer<BootstrapClientMessage> for BootstrapClientMessageDeserializer
                                 self.slot_step_deserializer.deserialize(input)
                             }),
                             context("Failed last_ops_step deserialization", |input| {
-                                self.slot_step_deserializer.deserialize(input)
+                                self.ops_step_deserializer.deserialize(input)
+                            }),
+                            context("Failed last_ops_hash deserialization", |input| {
+                                self.hash_deserializer.deserialize(input)
                             }),
                             context("Failed last_de_step deserialization", |input| {
                                 self.slot_step_deserializer.deserialize(input)
@@ -932,6 +1132,7 @@ impl Deserializer<BootstrapClientMessage> for BootstrapClientMessageDeserializer
                                 last_cycle_step,
                                 last_credits_step,
                                 last_ops_step,
+                                last_ops_hash,
                                 last_de_step,
                                 last_consensus_step,
                                 send_last_start_period,
@@ -943,6 +1144,7 @@ impl Deserializer<BootstrapClientMessage> for BootstrapClientMessageDeserializer
                                     last_cycle_step,
                                     last_credits_step,
                                     last_ops_step,
+                                    last_ops_hash,
                                     last_de_step,
                                     last_consensus_step,
                                     send_last_start_period,
@@ -30,7 +30,9 @@ use crossbeam::channel::tick;
 use humantime::format_duration;
 use massa_async_pool::AsyncMessageId;
 use massa_consensus_exports::{bootstrapable_graph::BootstrapableGraph, ConsensusController};
+use massa_executed_ops::ExecutedOpsStreamingStep;
 use massa_final_state::{FinalState, FinalStateError};
+use massa_hash::Hash;
 use massa_ledger_exports::Key as LedgerKey;
 use massa_logging::massa_trace;
 use massa_models::{
@@ -38,7 +40,7 @@ use massa_models::{
     version::Version,
 };
 
-use massa_protocol_exports::ProtocolController;
+use massa_protocol_exports::{BootstrapClientReport, ProtocolController};
 use massa_signature::KeyPair;
 use massa_time::MassaTime;
 use massa_versioning_worker::versioning::MipStore;
@@ -58,6 +60,7 @@ use crate::{
     bindings::BootstrapServerBinder,
     error::BootstrapError,
     listener::{BootstrapListenerStopHandle, PollEvent},
+    manifest::BootstrapManifest,
     messages::{BootstrapClientMessage, BootstrapServerMessage},
     BootstrapConfig,
 };
@@ -257,9 +260,15 @@ impl<L: BSEventPoller> BootstrapServer<'_, L> {
 
             // check whether incoming peer IP is allowed.
             if let Err(error_msg) = self.white_black_list.is_ip_allowed(&remote_addr) {
+                if matches!(error_msg, BootstrapError::BlackListed(_)) {
+                    self.report_client(&remote_addr, BootstrapClientReport::Blacklisted);
+                }
                 server_binding.close_and_send_error(error_msg.to_string(), remote_addr, move || {});
                 continue;
             };
+            if self.white_black_list.is_ip_whitelisted(remote_addr.ip()) {
+                self.report_client(&remote_addr, BootstrapClientReport::Whitelisted);
+            }
 
             // the `- 1` is to account for the top-level Arc that is created at the top
             // of this method. subsequent counts correspond to each `clone` that is passed
@@ -292,6 +301,7 @@ impl<L: BSEventPoller> BootstrapServer<'_, L> {
                     per_ip_min_interval,
                 ) {
                     // Client has been too greedy: send out the bad-news :(
+                    self.report_client(&remote_addr, BootstrapClientReport::RateLimited);
                     let msg = format!(
                         "Your last bootstrap on this server was {} ago and you have to wait {} before retrying.",
                         format_duration(msg),
@@ -348,6 +358,20 @@ impl<L: BSEventPoller> BootstrapServer<'_, L> {
         }
     }
 
+    /// Feed our decision about a client to the peer database of the protocol,
+    /// so that repeated offenses escalate to a ban of its IP.
+    fn report_client(&self, remote_addr: &SocketAddr, report: BootstrapClientReport) {
+        if let Err(err) = self
+            .protocol_controller
+            .report_bootstrap_client(remote_addr.ip(), report)
+        {
+            warn!(
+                "could not report bootstrap client {} to protocol: {}",
+                remote_addr, err
+            );
+        }
+    }
+
     /// Checks latest attempt. If too recent, provides the bad news (as an error).
     /// Updates the latest attempt to "now" if it's all good.
     ///
@@ -452,13 +476,17 @@ pub fn stream_bootstrap_information(
     mut last_pool_step: StreamingStep<AsyncMessageId>,
     mut last_cycle_step: StreamingStep<u64>,
     mut last_credits_step: StreamingStep<Slot>,
-    mut last_ops_step: StreamingStep<Slot>,
+    mut last_ops_step: ExecutedOpsStreamingStep,
+    mut last_ops_hash: Hash,
     mut last_de_step: StreamingStep<Slot>,
     mut last_consensus_step: StreamingStep<PreHashSet<BlockId>>,
     mut send_last_start_period: bool,
     bs_deadline: &Instant,
     write_timeout: Duration,
 ) -> Result<(), BootstrapError> {
+    // The executed operations parts of the session are served from one consistent state,
+    // the operations executed meanwhile reach the client through the streamed changes
+    let executed_ops = final_state.read().executed_ops.freeze();
     loop {
         #[cfg(test)]
         {
@@ -475,6 +503,7 @@ pub fn stream_bootstrap_information(
         let exec_de_part;
         let final_state_changes;
         let last_start_period;
+        let mut manifest = None;
 
         let mut slot_too_old = false;
 
@@ -488,33 +517,57 @@ pub fn stream_bootstrap_information(
                 None
             };
 
-            let (data, new_ledger_step) = final_state_read
-                .ledger
-                .get_ledger_part(last_ledger_step.clone())?;
+            // The streams are independent: the ledger part is read from disk on its own thread
+            // while the parts of the in-memory components are gathered
+            let (ledger_result, pool_part, cycle_result, credits_part, ops_part, de_part) =
+                std::thread::scope(|scope| {
+                    let ledger_handle = scope.spawn(|| {
+                        final_state_read
+                            .ledger
+                            .get_ledger_part(last_ledger_step.clone())
+                    });
+                    let pool_part = final_state_read.async_pool.get_pool_part(last_pool_step);
+                    let cycle_result = final_state_read
+                        .pos_state
+                        .get_cycle_history_part(last_cycle_step);
+                    let credits_part = final_state_read
+                        .pos_state
+                        .get_deferred_credits_part(last_credits_step);
+                    let ops_part =
+                        executed_ops.get_executed_ops_part(last_ops_step, &last_ops_hash);
+                    let de_part = final_state_read
+                        .executed_denunciations
+                        .get_executed_de_part(last_de_step);
+                    let ledger_result = ledger_handle
+                        .join()
+                        .expect("bootstrap ledger part thread panicked");
+                    (
+                        ledger_result,
+                        pool_part,
+                        cycle_result,
+                        credits_part,
+                        ops_part,
+                        de_part,
+                    )
+                });
+
+            let (data, new_ledger_step) = ledger_result?;
             ledger_part = data;
 
-            let (pool_data, new_pool_step) =
-                final_state_read.async_pool.get_pool_part(last_pool_step);
+            let (pool_data, new_pool_step) = pool_part;
             async_pool_part = pool_data;
 
-            let (cycle_data, new_cycle_step) = final_state_read
-                .pos_state
-                .get_cycle_history_part(last_cycle_step)?;
+            let (cycle_data, new_cycle_step) = cycle_result?;
             pos_cycle_part = cycle_data;
 
-            let (credits_data, new_credits_step) = final_state_read
-                .pos_state
-                .get_deferred_credits_part(last_credits_step);
+            let (credits_data, new_credits_step) = credits_part;
             pos_credits_part = credits_data;
 
-            let (ops_data, new_ops_step) = final_state_read
-                .executed_ops
-                .get_executed_ops_part(last_ops_step);
+            let (ops_data, new_ops_step) = ops_part;
+            last_ops_hash = ops_data.cumulative_hash;
             exec_ops_part = ops_data;
 
-            let (de_data, new_de_step) = final_state_read
-                .executed_denunciations
-                .get_executed_de_part(last_de_step);
+            let (de_data, new_de_step) = de_part;
             exec_de_part = de_data;
 
             if let Some(slot) = last_slot && slot != final_state_read.slot {
@@ -529,7 +582,6 @@ pub fn stream_bootstrap_information(
                     new_pool_step,
                     new_cycle_step,
                     new_credits_step,
-                    new_ops_step,
                     new_de_step,
                 ) {
                     Ok(data) => data,
@@ -553,6 +605,21 @@ pub fn stream_bootstrap_information(
             last_slot = Some(final_state_read.slot);
             current_slot = final_state_read.slot;
             send_last_start_period = false;
+
+            // Once the client caught up with the state, describe it so that the client can check its completeness
+            if final_state_changes.is_empty()
+                && last_ledger_step.finished()
+                && last_pool_step.finished()
+                && last_cycle_step.finished()
+                && last_credits_step.finished()
+                && last_ops_step.finished()
+                && last_de_step.finished()
+            {
+                manifest = Some(BootstrapManifest::new(
+                    &final_state_read,
+                    server.local_keypair(),
+                )?);
+            }
         }
 
         if slot_too_old {
@@ -611,6 +678,12 @@ pub fn stream_bootstrap_information(
             && final_state_changes_step.finished()
             && last_consensus_step.finished()
         {
+            if let Some(manifest) = manifest {
+                server.send_msg(
+                    write_timeout,
+                    BootstrapServerMessage::BootstrapManifest { manifest },
+                )?;
+            }
             server.send_msg(write_timeout, BootstrapServerMessage::BootstrapFinished)?;
             break;
         }
@@ -726,6 +799,7 @@ fn manage_bootstrap(
                     last_cycle_step,
                     last_credits_step,
                     last_ops_step,
+                    last_ops_hash,
                     last_de_step,
                     last_consensus_step,
                     send_last_start_period,
@@ -740,6 +814,7 @@ fn manage_bootstrap(
                         last_cycle_step,
                         last_credits_step,
                         last_ops_step,
+                        last_ops_hash,
                         last_de_step,
                         last_consensus_step,
                         send_last_start_period,
@@ -747,6 +822,50 @@ fn manage_bootstrap(
                         bootstrap_config.write_timeout.to_duration(),
                     )?;
                 }
+                BootstrapClientMessage::AskBootstrapManifest => {
+                    let manifest =
+                        BootstrapManifest::new(&final_state.read(), server.local_keypair())?;
+                    let Some(write_timeout) = step_timeout_duration(&deadline, &bootstrap_config.write_timeout.to_duration()) else {
+                        return Err(BootstrapError::Interupted("insufficient time left to respond te request for manifest".to_string()));
+                    };
+
+                    server.send_msg(
+                        write_timeout,
+                        BootstrapServerMessage::BootstrapManifest { manifest },
+                    )?;
+                }
+                BootstrapClientMessage::AskLightBootstrap { keys } => {
+                    // the manifest and the proof are built from the same state
+                    let (manifest, ledger_proof) = {
+                        let final_state_read = final_state.read();
+                        (
+                            BootstrapManifest::new(&final_state_read, server.local_keypair())?,
+                            final_state_read.get_ledger_proof(&keys),
+                        )
+                    };
+                    // headers of the final blocks required by the state at the manifest slot
+                    let (graph, _, _) = consensus_controller.get_bootstrap_part(
+                        StreamingStep::Started,
+                        StreamingStep::Finished(Some(manifest.slot)),
+                    )?;
+                    let headers = graph
+                        .final_blocks
+                        .into_iter()
+                        .map(|block_export| block_export.block.content.header)
+                        .collect();
+                    let Some(write_timeout) = step_timeout_duration(&deadline, &bootstrap_config.write_timeout.to_duration()) else {
+                        return Err(BootstrapError::Interupted("insufficient time left to respond te request for light bootstrap".to_string()));
+                    };
+
+                    server.send_msg(
+                        write_timeout,
+                        BootstrapServerMessage::LightBootstrapState {
+                            manifest,
+                            headers,
+                            ledger_proof,
+                        },
+                    )?;
+                }
                 BootstrapClientMessage::AskBootstrapMipStore => {
                     let vs = mip_store.0.read().to_owned();
                     let Some(write_timeout) = step_timeout_duration(&deadline, &bootstrap_config.write_timeout.to_duration()) else {
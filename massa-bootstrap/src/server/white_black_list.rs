@@ -82,6 +82,16 @@ impl SharedWhiteBlackList<'_> {
             Ok(())
         }
     }
+
+    /// Whether the IP is in the whitelist
+    pub(crate) fn is_ip_whitelisted(&self, ip: IpAddr) -> bool {
+        let ip = normalize_ip(ip);
+        self.inner
+            .read()
+            .white_list
+            .as_ref()
+            .map_or(false, |ip_list| ip_list.contains(&ip))
+    }
 }
 
 impl WhiteBlackListInner {
@@ -39,6 +39,9 @@ pub struct BootstrapConfig {
     pub bootstrap_timeout: MassaTime,
     /// readout timeout
     pub read_timeout: MassaTime,
+    /// timeout for receiving each final state part, after which the client
+    /// fails over to another bootstrap server and resumes from its last verified part
+    pub stall_timeout: MassaTime,
     /// write timeout
     pub write_timeout: MassaTime,
     /// readout error timeout
@@ -117,6 +120,8 @@ pub struct BootstrapConfig {
     pub max_credits_length: u64,
     /// max executed ops
     pub max_executed_ops_length: u64,
+    /// max executed ops in a bootstrap part, over all its slots
+    pub max_executed_ops_part_length: u64,
     /// max executed ops changes
     pub max_ops_changes_length: u64,
     /// consensus bootstrap part size
@@ -173,6 +178,7 @@ pub struct BootstrapClientConfig {
     pub max_production_stats_length: u64,
     pub max_credits_length: u64,
     pub max_executed_ops_length: u64,
+    pub max_executed_ops_part_length: u64,
     pub max_ops_changes_length: u64,
     pub mip_store_stats_block_considered: usize,
     pub mip_store_stats_counters_max: usize,
@@ -205,6 +211,7 @@ pub struct BootstrapServerMessageDeserializerArgs {
     pub max_production_stats_length: u64,
     pub max_credits_length: u64,
     pub max_executed_ops_length: u64,
+    pub max_executed_ops_part_length: u64,
     pub max_ops_changes_length: u64,
     pub mip_store_stats_block_considered: usize,
     pub mip_store_stats_counters_max: usize,
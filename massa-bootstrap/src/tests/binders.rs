@@ -13,17 +13,17 @@ use massa_models::config::{
     MAX_BOOTSTRAP_FINAL_STATE_PARTS_SIZE, MAX_DATASTORE_ENTRY_COUNT, MAX_DATASTORE_KEY_LENGTH,
     MAX_DATASTORE_VALUE_LENGTH, MAX_DEFERRED_CREDITS_LENGTH, MAX_DENUNCIATIONS_PER_BLOCK_HEADER,
     MAX_DENUNCIATION_CHANGES_LENGTH, MAX_EXECUTED_OPS_CHANGES_LENGTH, MAX_EXECUTED_OPS_LENGTH,
-    MAX_LEDGER_CHANGES_COUNT, MAX_LISTENERS_PER_PEER, MAX_OPERATIONS_PER_BLOCK,
-    MAX_PRODUCTION_STATS_LENGTH, MAX_ROLLS_COUNT_LENGTH, MIP_STORE_STATS_BLOCK_CONSIDERED,
-    MIP_STORE_STATS_COUNTERS_MAX, THREAD_COUNT,
+    MAX_EXECUTED_OPS_PART_LENGTH, MAX_LEDGER_CHANGES_COUNT, MAX_LISTENERS_PER_PEER,
+    MAX_OPERATIONS_PER_BLOCK, MAX_PRODUCTION_STATS_LENGTH, MAX_ROLLS_COUNT_LENGTH,
+    MIP_STORE_STATS_BLOCK_CONSIDERED, MIP_STORE_STATS_COUNTERS_MAX, THREAD_COUNT,
 };
 use massa_models::node::NodeId;
 use massa_models::version::Version;
-use massa_protocol_exports::{PeerId, TransportType};
+use massa_protocol_exports::{BootstrapPeerInfo, PeerId, TransportType};
 use massa_signature::{KeyPair, PublicKey};
 use massa_time::MassaTime;
 use std::collections::HashMap;
-use std::net::TcpStream;
+use std::net::{SocketAddr, TcpStream};
 use std::str::FromStr;
 
 lazy_static::lazy_static! {
@@ -33,6 +33,14 @@ lazy_static::lazy_static! {
     };
 }
 
+fn peer_info(listeners: HashMap<SocketAddr, TransportType>) -> BootstrapPeerInfo {
+    BootstrapPeerInfo {
+        listeners,
+        category: None,
+        last_seen: MassaTime::from_millis(1000),
+    }
+}
+
 impl BootstrapClientBinder {
     pub fn test_default(client_duplex: TcpStream, remote_pubkey: PublicKey) -> Self {
         let cfg = BootstrapClientConfig {
@@ -58,6 +66,7 @@ impl BootstrapClientBinder {
             max_production_stats_length: MAX_PRODUCTION_STATS_LENGTH,
             max_credits_length: MAX_DEFERRED_CREDITS_LENGTH,
             max_executed_ops_length: MAX_EXECUTED_OPS_LENGTH,
+            max_executed_ops_part_length: MAX_EXECUTED_OPS_PART_LENGTH,
             max_ops_changes_length: MAX_EXECUTED_OPS_CHANGES_LENGTH,
             mip_store_stats_block_considered: MIP_STORE_STATS_BLOCK_CONSIDERED,
             mip_store_stats_counters_max: MIP_STORE_STATS_COUNTERS_MAX,
@@ -113,7 +122,7 @@ fn test_binders() {
                     bootstrap_config.bootstrap_list[0].0.clone(),
                     TransportType::Tcp,
                 );
-                let vector_peers = vec![(peer_id1, listeners)];
+                let vector_peers = vec![(peer_id1, peer_info(listeners))];
                 let test_peers_message = BootstrapServerMessage::BootstrapPeers {
                     peers: BootstrapPeers(vector_peers.clone()),
                 };
@@ -141,9 +150,9 @@ fn test_binders() {
                     TransportType::Tcp,
                 );
                 let vector_peers = vec![
-                    (peer_id2, listeners.clone()),
-                    (peer_id3, listeners.clone()),
-                    (peer_id4, listeners.clone()),
+                    (peer_id2, peer_info(listeners.clone())),
+                    (peer_id3, peer_info(listeners.clone())),
+                    (peer_id4, peer_info(listeners.clone())),
                 ];
                 let test_peers_message = BootstrapServerMessage::BootstrapPeers {
                     peers: BootstrapPeers(vector_peers.clone()),
@@ -170,7 +179,7 @@ fn test_binders() {
                     bootstrap_config.bootstrap_list[0].0.clone(),
                     TransportType::Tcp,
                 );
-                let vector_peers = vec![(peer_id1, listeners)];
+                let vector_peers = vec![(peer_id1, peer_info(listeners))];
 
                 let version: Version = Version::from_str("TEST.1.10").unwrap();
 
@@ -199,9 +208,9 @@ fn test_binders() {
                     TransportType::Tcp,
                 );
                 let vector_peers = vec![
-                    (peer_id2, listeners.clone()),
-                    (peer_id3, listeners.clone()),
-                    (peer_id4, listeners.clone()),
+                    (peer_id2, peer_info(listeners.clone())),
+                    (peer_id3, peer_info(listeners.clone())),
+                    (peer_id4, peer_info(listeners.clone())),
                 ];
                 let message = client.next_timeout(None).unwrap();
                 match message {
@@ -263,7 +272,7 @@ fn test_binders_double_send_server_works() {
                     bootstrap_config.bootstrap_list[0].0.clone(),
                     TransportType::Tcp,
                 );
-                let vector_peers = vec![(peer_id1, listeners.clone())];
+                let vector_peers = vec![(peer_id1, peer_info(listeners.clone()))];
                 let test_peers_message = BootstrapServerMessage::BootstrapPeers {
                     peers: BootstrapPeers(vector_peers.clone()),
                 };
@@ -282,9 +291,9 @@ fn test_binders_double_send_server_works() {
                     TransportType::Tcp,
                 );
                 let vector_peers = vec![
-                    (peer_id2, listeners.clone()),
-                    (peer_id3, listeners.clone()),
-                    (peer_id4, listeners.clone()),
+                    (peer_id2, peer_info(listeners.clone())),
+                    (peer_id3, peer_info(listeners.clone())),
+                    (peer_id4, peer_info(listeners.clone())),
                 ];
                 let test_peers_message = BootstrapServerMessage::BootstrapPeers {
                     peers: BootstrapPeers(vector_peers.clone()),
@@ -311,7 +320,7 @@ fn test_binders_double_send_server_works() {
                     bootstrap_config.bootstrap_list[0].0.clone(),
                     TransportType::Tcp,
                 );
-                let vector_peers = vec![(peer_id1, listeners.clone())];
+                let vector_peers = vec![(peer_id1, peer_info(listeners.clone()))];
                 let version: Version = Version::from_str("TEST.1.10").unwrap();
 
                 client.handshake(version).unwrap();
@@ -330,9 +339,9 @@ fn test_binders_double_send_server_works() {
                     TransportType::Tcp,
                 );
                 let vector_peers = vec![
-                    (peer_id2, listeners.clone()),
-                    (peer_id3, listeners.clone()),
-                    (peer_id4, listeners.clone()),
+                    (peer_id2, peer_info(listeners.clone())),
+                    (peer_id3, peer_info(listeners.clone())),
+                    (peer_id4, peer_info(listeners.clone())),
                 ];
                 let message = client.next_timeout(None).unwrap();
                 match message {
@@ -388,7 +397,7 @@ fn test_binders_try_double_send_client_works() {
                     bootstrap_config.bootstrap_list[0].0.clone(),
                     TransportType::Tcp,
                 );
-                let vector_peers = vec![(peer_id1, listeners.clone())];
+                let vector_peers = vec![(peer_id1, peer_info(listeners.clone()))];
                 let test_peers_message = BootstrapServerMessage::BootstrapPeers {
                     peers: BootstrapPeers(vector_peers.clone()),
                 };
@@ -433,7 +442,7 @@ fn test_binders_try_double_send_client_works() {
                     bootstrap_config.bootstrap_list[0].0.clone(),
                     TransportType::Tcp,
                 );
-                let vector_peers = vec![(peer_id1.clone(), listeners.clone())];
+                let vector_peers = vec![(peer_id1.clone(), peer_info(listeners.clone()))];
                 let version: Version = Version::from_str("TEST.1.10").unwrap();
 
                 client.handshake(version).unwrap();
@@ -469,7 +478,7 @@ fn test_binders_try_double_send_client_works() {
                     bootstrap_config.bootstrap_list[0].0.clone(),
                     TransportType::Tcp,
                 );
-                let vector_peers = vec![(peer_id1, listeners.clone())];
+                let vector_peers = vec![(peer_id1, peer_info(listeners.clone()))];
                 let message = client.next_timeout(None).unwrap();
                 match message {
                     BootstrapServerMessage::BootstrapPeers { peers } => {
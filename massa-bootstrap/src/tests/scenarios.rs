@@ -20,7 +20,9 @@ use massa_async_pool::AsyncPoolConfig;
 use massa_consensus_exports::{
     bootstrapable_graph::BootstrapableGraph, test_exports::MockConsensusControllerImpl,
 };
-use massa_executed_ops::{ExecutedDenunciationsConfig, ExecutedOpsConfig};
+use massa_executed_ops::{
+    ExecutedDenunciationsConfig, ExecutedOpsConfig, ExecutedOpsHashScheme, ExecutedOpsPruningMode,
+};
 use massa_final_state::{
     test_exports::{assert_eq_final_state, assert_eq_final_state_hash},
     FinalState, FinalStateConfig, StateChanges,
@@ -29,8 +31,8 @@ use massa_hash::{Hash, HASH_SIZE_BYTES};
 use massa_ledger_exports::LedgerConfig;
 use massa_models::config::{
     DENUNCIATION_EXPIRE_PERIODS, ENDORSEMENT_COUNT, EXECUTED_OPS_BOOTSTRAP_PART_SIZE,
-    MAX_DENUNCIATIONS_PER_BLOCK_HEADER, MIP_STORE_STATS_BLOCK_CONSIDERED,
-    MIP_STORE_STATS_COUNTERS_MAX,
+    EXECUTED_OPS_BOOTSTRAP_PART_SIZE_BYTES, MAX_DENUNCIATIONS_PER_BLOCK_HEADER,
+    MIP_STORE_STATS_BLOCK_CONSIDERED, MIP_STORE_STATS_COUNTERS_MAX,
 };
 use massa_models::{
     address::Address, config::MAX_DATASTORE_VALUE_LENGTH, node::NodeId, slot::Slot,
@@ -135,6 +137,11 @@ fn mock_bootstrap_manager(addr: SocketAddr, bootstrap_config: BootstrapConfig) -
         executed_ops_config: ExecutedOpsConfig {
             thread_count,
             bootstrap_part_size: 10,
+            bootstrap_part_size_bytes: EXECUTED_OPS_BOOTSTRAP_PART_SIZE_BYTES,
+            retention_periods: 0,
+            operation_validity_periods: 100,
+            pruning_mode: ExecutedOpsPruningMode::Immediate,
+            hash_scheme: ExecutedOpsHashScheme::Xor,
         },
         final_history_length: 100,
         initial_seed_string: "".into(),
@@ -248,6 +255,11 @@ fn test_bootstrap_server() {
         executed_ops_config: ExecutedOpsConfig {
             thread_count,
             bootstrap_part_size: 10,
+            bootstrap_part_size_bytes: EXECUTED_OPS_BOOTSTRAP_PART_SIZE_BYTES,
+            retention_periods: 0,
+            operation_validity_periods: 100,
+            pruning_mode: ExecutedOpsPruningMode::Immediate,
+            hash_scheme: ExecutedOpsHashScheme::Xor,
         },
         executed_denunciations_config: ExecutedDenunciationsConfig {
             denunciation_expire_periods: DENUNCIATION_EXPIRE_PERIODS,
@@ -375,9 +387,7 @@ fn test_bootstrap_server() {
         })
         .unwrap();
 
-    // launch the modifier thread
-    let list_changes: Arc<RwLock<Vec<(Slot, StateChanges)>>> = Arc::new(RwLock::new(Vec::new()));
-    let list_changes_clone = list_changes.clone();
+    // launch the modifier thread, settling slots on the server as the finalization does
     let mod_thread = std::thread::Builder::new()
         .name("modifier thread".to_string())
         .spawn(move || {
@@ -394,10 +404,24 @@ fn test_bootstrap_server() {
                     executed_denunciations_changes: get_random_executed_de_changes(10),
                 };
                 final_write
-                    .changes_history
-                    .push_back((next, changes.clone()));
-                let mut list_changes_write = list_changes_clone.write();
-                list_changes_write.push((next, changes));
+                    .pos_state
+                    .apply_changes(changes.pos_changes.clone(), next, false)
+                    .unwrap();
+                final_write
+                    .ledger
+                    .apply_changes(changes.ledger_changes.clone(), next, None);
+                final_write
+                    .async_pool
+                    .apply_changes_unchecked(&changes.async_pool_changes);
+                final_write
+                    .executed_ops
+                    .apply_changes(changes.executed_ops_changes.clone(), next)
+                    .unwrap();
+                final_write.executed_ops.prune_pending();
+                final_write
+                    .executed_denunciations
+                    .apply_changes(changes.executed_denunciations_changes.clone(), next);
+                final_write.changes_history.push_back((next, changes));
             }
         })
         .unwrap();
@@ -414,29 +438,6 @@ fn test_bootstrap_server() {
         Arc::new((Mutex::new(false), Condvar::new())),
     )
     .unwrap();
-    // apply the changes to the server state before matching with the client
-    {
-        let mut final_state_server_write = final_state_server.write();
-        let list_changes_read = list_changes.read().clone();
-        // note: skip the first change to match the update loop behaviour
-        for (slot, change) in list_changes_read.iter().skip(1) {
-            final_state_server_write
-                .pos_state
-                .apply_changes(change.pos_changes.clone(), *slot, false)
-                .unwrap();
-            final_state_server_write.ledger.apply_changes(
-                change.ledger_changes.clone(),
-                *slot,
-                None,
-            );
-            final_state_server_write
-                .async_pool
-                .apply_changes_unchecked(&change.async_pool_changes);
-            final_state_server_write
-                .executed_ops
-                .apply_changes(change.executed_ops_changes.clone(), *slot);
-        }
-    }
     // Make sure the modifier thread has done its job
     mod_thread.join().unwrap();
 
@@ -28,11 +28,12 @@ use massa_models::config::{
     MAX_BOOTSTRAP_FINAL_STATE_PARTS_SIZE, MAX_CONSENSUS_BLOCKS_IDS, MAX_DATASTORE_ENTRY_COUNT,
     MAX_DATASTORE_KEY_LENGTH, MAX_DATASTORE_VALUE_LENGTH, MAX_DEFERRED_CREDITS_LENGTH,
     MAX_DENUNCIATIONS_PER_BLOCK_HEADER, MAX_DENUNCIATION_CHANGES_LENGTH,
-    MAX_EXECUTED_OPS_CHANGES_LENGTH, MAX_EXECUTED_OPS_LENGTH, MAX_FUNCTION_NAME_LENGTH,
-    MAX_LEDGER_CHANGES_COUNT, MAX_OPERATIONS_PER_BLOCK, MAX_OPERATION_DATASTORE_ENTRY_COUNT,
-    MAX_OPERATION_DATASTORE_KEY_LENGTH, MAX_OPERATION_DATASTORE_VALUE_LENGTH, MAX_PARAMETERS_SIZE,
-    MAX_PRODUCTION_STATS_LENGTH, MAX_ROLLS_COUNT_LENGTH, MIP_STORE_STATS_BLOCK_CONSIDERED,
-    MIP_STORE_STATS_COUNTERS_MAX, PERIODS_PER_CYCLE, THREAD_COUNT,
+    MAX_EXECUTED_OPS_CHANGES_LENGTH, MAX_EXECUTED_OPS_LENGTH, MAX_EXECUTED_OPS_PART_LENGTH,
+    MAX_FUNCTION_NAME_LENGTH, MAX_LEDGER_CHANGES_COUNT, MAX_OPERATIONS_PER_BLOCK,
+    MAX_OPERATION_DATASTORE_ENTRY_COUNT, MAX_OPERATION_DATASTORE_KEY_LENGTH,
+    MAX_OPERATION_DATASTORE_VALUE_LENGTH, MAX_PARAMETERS_SIZE, MAX_PRODUCTION_STATS_LENGTH,
+    MAX_ROLLS_COUNT_LENGTH, MIP_STORE_STATS_BLOCK_CONSIDERED, MIP_STORE_STATS_COUNTERS_MAX,
+    PERIODS_PER_CYCLE, THREAD_COUNT,
 };
 use massa_models::denunciation::DenunciationIndex;
 use massa_models::node::NodeId;
@@ -52,7 +53,7 @@ use massa_models::{
     slot::Slot,
 };
 use massa_pos_exports::{CycleInfo, DeferredCredits, PoSChanges, PoSFinalState, ProductionStats};
-use massa_protocol_exports::{BootstrapPeers, PeerId, TransportType};
+use massa_protocol_exports::{BootstrapPeerInfo, BootstrapPeers, PeerId, TransportType};
 use massa_serialization::{DeserializeError, Deserializer, Serializer};
 use massa_signature::KeyPair;
 use massa_time::MassaTime;
@@ -209,7 +210,9 @@ pub fn get_random_executed_ops(
     config: ExecutedOpsConfig,
 ) -> ExecutedOps {
     let mut executed_ops = ExecutedOps::new(config.clone());
-    executed_ops.apply_changes(get_random_executed_ops_changes(10), slot);
+    executed_ops
+        .apply_changes(get_random_executed_ops_changes(10), slot)
+        .unwrap();
     executed_ops
 }
 
@@ -320,6 +323,7 @@ pub fn get_bootstrap_config(bootstrap_public_key: NodeId) -> BootstrapConfig {
         retry_delay: 200.into(),
         max_ping: MassaTime::from_millis(500),
         read_timeout: 1000.into(),
+        stall_timeout: 1000.into(),
         write_timeout: 1000.into(),
         read_error_timeout: 200.into(),
         write_error_timeout: 200.into(),
@@ -367,6 +371,7 @@ pub fn get_bootstrap_config(bootstrap_public_key: NodeId) -> BootstrapConfig {
         max_production_stats_length: MAX_PRODUCTION_STATS_LENGTH,
         max_credits_length: MAX_DEFERRED_CREDITS_LENGTH,
         max_executed_ops_length: MAX_EXECUTED_OPS_LENGTH,
+        max_executed_ops_part_length: MAX_EXECUTED_OPS_PART_LENGTH,
         max_ops_changes_length: MAX_EXECUTED_OPS_CHANGES_LENGTH,
         consensus_bootstrap_part_size: CONSENSUS_BOOTSTRAP_PART_SIZE,
         max_consensus_block_ids: MAX_CONSENSUS_BLOCKS_IDS,
@@ -485,11 +490,19 @@ pub fn get_peers(keypair: &KeyPair) -> BootstrapPeers {
     BootstrapPeers(vec![
         (
             PeerId::from_bytes(keypair.get_public_key().to_bytes()).unwrap(),
-            listeners1,
+            BootstrapPeerInfo {
+                listeners: listeners1,
+                category: Some("Bootstrap".to_string()),
+                last_seen: MassaTime::from_millis(1000),
+            },
         ),
         (
             PeerId::from_bytes(keypair.get_public_key().to_bytes()).unwrap(),
-            listeners2,
+            BootstrapPeerInfo {
+                listeners: listeners2,
+                category: None,
+                last_seen: MassaTime::from_millis(2000),
+            },
         ),
     ])
 }
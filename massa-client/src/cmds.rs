@@ -8,7 +8,9 @@ use massa_api_exports::{
     address::{AddressInfo, CompactAddressInfo},
     datastore::DatastoreEntryInput,
     execution::{ReadOnlyBytecodeExecution, ReadOnlyCall},
+    node::{ExportedBanReportInfo, ExportedPeerInfo},
     operation::OperationInput,
+    page::PageRequest,
 };
 use massa_models::node::NodeId;
 use massa_models::prehash::PreHashMap;
@@ -30,7 +32,7 @@ use serde::Serialize;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Write as _;
 use std::fmt::{Debug, Display};
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
 use strum::{EnumMessage, EnumProperty, IntoEnumIterator};
 use strum_macros::{Display, EnumIter, EnumString};
@@ -134,6 +136,20 @@ pub enum Command {
     )]
     node_bootstrap_blacklist,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "SocketAddr [NodeId]", pwd_not_needed = "true"),
+        message = "connect to a bootstrap server to check its identity, version and last final slot without bootstrapping from it. NodeId defaults to the one of the bootstrap list"
+    )]
+    node_check_bootstrap,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "[PageSize PageIndex]", pwd_not_needed = "true"),
+        message = "dump the final executed operations by slot, to be diffed against the dump of another node. No args dumps all of them"
+    )]
+    dump_executed_ops,
+
     #[strum(
         ascii_case_insensitive,
         props(args = "(add or remove) [IpAddr]", pwd_not_needed = "true"),
@@ -141,6 +157,16 @@ pub enum Command {
     )]
     node_peers_whitelist,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(
+            args = "(list, ban, unban, test, export, import, export_bans, import_bans, reachability or annotate) [NodeId1 NodeId2 ... | SocketAddr | File | NodeId Tag1,Tag2 Note]",
+            pwd_not_needed = "true"
+        ),
+        message = "Manage the peers through the gRPC admin service: list the connected ones, ban or unban node ids, test an address, export the known peers to a file or import them from one, export the reports of the bans to a file or import them from one, check that the listeners of the node are reachable from the outside, attach tags and a note to a peer (\"protected\" peers are never evicted, the connections of \"logged\" ones are always logged)"
+    )]
+    node_peers,
+
     #[strum(
         ascii_case_insensitive,
         props(pwd_not_needed = "true"),
@@ -148,6 +174,13 @@ pub enum Command {
     )]
     get_status,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(pwd_not_needed = "true"),
+        message = "show a snapshot of the protocol state (message rates, queues, peers waiting to be tested, bans, connections)"
+    )]
+    get_protocol_stats,
+
     #[strum(
         ascii_case_insensitive,
         props(args = "Address1 Address2 ...", pwd_not_needed = "true"),
@@ -311,6 +344,50 @@ pub enum Command {
     when_moon,
 }
 
+/// Operations of the `node_peers` command, one per command of the admin service
+#[derive(Debug, Display, EnumString, EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum PeerOperation {
+    #[strum(ascii_case_insensitive, message = "list the connected peers")]
+    List,
+    #[strum(ascii_case_insensitive, message = "ban the given node id(s)")]
+    Ban,
+    #[strum(ascii_case_insensitive, message = "unban the given node id(s)")]
+    Unban,
+    #[strum(ascii_case_insensitive, message = "test the given address right away")]
+    Test,
+    #[strum(
+        ascii_case_insensitive,
+        message = "export the known peers, to the given file if any"
+    )]
+    Export,
+    #[strum(
+        ascii_case_insensitive,
+        message = "import the peers exported in the given file"
+    )]
+    Import,
+    #[strum(
+        ascii_case_insensitive,
+        message = "export the signed reports of the bans, to the given file if any"
+    )]
+    ExportBans,
+    #[strum(
+        ascii_case_insensitive,
+        message = "ban the peers of the reports exported in the given file, once checked by the node"
+    )]
+    ImportBans,
+    #[strum(
+        ascii_case_insensitive,
+        message = "ask a few connected peers to dial back the listeners of the node"
+    )]
+    Reachability,
+    #[strum(
+        ascii_case_insensitive,
+        message = "replace the comma separated tags and the note of the given node id, none to remove them"
+    )]
+    Annotate,
+}
+
 #[derive(Debug, Display, EnumString, EnumIter)]
 #[strum(serialize_all = "snake_case")]
 pub enum ListOperation {
@@ -572,6 +649,11 @@ impl Command {
                 Err(e) => rpc_error!(e),
             },
 
+            Command::get_protocol_stats => match client.public.get_protocol_stats().await {
+                Ok(protocol_stats) => Ok(Box::new(protocol_stats)),
+                Err(e) => rpc_error!(e),
+            },
+
             Command::get_addresses => {
                 let addresses = parse_vec::<Address>(parameters)?;
                 match client.public.get_addresses(addresses).await {
@@ -1217,6 +1299,34 @@ impl Command {
                     res
                 }
             }
+            Command::node_check_bootstrap => {
+                if parameters.is_empty() || parameters.len() > 2 {
+                    bail!("wrong number of parameters");
+                }
+                let addr = parameters[0].parse::<SocketAddr>()?;
+                let node_id = match parameters.get(1) {
+                    Some(node_id) => Some(node_id.parse::<NodeId>()?),
+                    None => None,
+                };
+                match client.private.node_check_bootstrap(addr, node_id).await {
+                    Ok(check) => Ok(Box::new(check)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+            Command::dump_executed_ops => {
+                let page_request = match parameters.len() {
+                    0 => None,
+                    2 => Some(PageRequest {
+                        limit: parameters[0].parse::<usize>()?,
+                        offset: parameters[1].parse::<usize>()?,
+                    }),
+                    _ => bail!("wrong number of parameters"),
+                };
+                match client.private.dump_executed_ops(page_request).await {
+                    Ok(dump) => Ok(Box::new(dump)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
             Command::node_bootstrap_whitelist => {
                 if parameters.is_empty() {
                     match client.private.node_bootstrap_whitelist().await {
@@ -1338,6 +1448,156 @@ impl Command {
                     res
                 }
             }
+            Command::node_peers => {
+                if parameters.is_empty() {
+                    bail!("missing operation, supported operations are: [list, ban, unban, test, export, import, export_bans, import_bans, reachability, annotate]");
+                }
+                let peer_op = match parameters[0].parse::<PeerOperation>() {
+                    Ok(op) => op,
+                    Err(_) => bail!(
+                        "failed to parse operation, supported operations are: [list, ban, unban, test, export, import, export_bans, import_bans, reachability, annotate]"
+                    ),
+                };
+                let args = &parameters[1..];
+                match peer_op {
+                    PeerOperation::List => match client.admin.list_peers().await {
+                        Ok(peers) => Ok(Box::new(peers)),
+                        Err(e) => rpc_error!(e),
+                    },
+                    PeerOperation::Ban | PeerOperation::Unban => {
+                        if args.is_empty() {
+                            bail!("[NodeId] parameter shouldn't be empty");
+                        }
+                        let ids = parse_vec::<NodeId>(args)?;
+                        let res = match peer_op {
+                            PeerOperation::Ban => client.admin.ban_peers(ids).await,
+                            _ => client.admin.unban_peers(ids).await,
+                        };
+                        match res {
+                            Ok(()) => {
+                                if !json {
+                                    println!("Request of {} successfully sent!", peer_op)
+                                }
+                                Ok(Box::new(()))
+                            }
+                            Err(e) => rpc_error!(e),
+                        }
+                    }
+                    PeerOperation::Test => {
+                        if args.len() != 1 {
+                            bail!("wrong number of parameters");
+                        }
+                        let address = args[0].parse::<SocketAddr>()?;
+                        match client.admin.test_peer(address).await {
+                            Ok(result) => Ok(Box::new(result)),
+                            Err(e) => rpc_error!(e),
+                        }
+                    }
+                    PeerOperation::Export => {
+                        if args.len() > 1 {
+                            bail!("wrong number of parameters");
+                        }
+                        let peers = match client.admin.export_peers().await {
+                            Ok(peers) => peers,
+                            Err(e) => rpc_error!(e),
+                        };
+                        match args.first() {
+                            Some(path) => {
+                                std::fs::write(path, serde_json::to_string_pretty(&peers)?)?;
+                                if !json {
+                                    println!("{} peers exported to {}", peers.len(), path);
+                                }
+                                Ok(Box::new(()))
+                            }
+                            None => Ok(Box::new(peers)),
+                        }
+                    }
+                    PeerOperation::Import => {
+                        if args.len() != 1 {
+                            bail!("wrong number of parameters");
+                        }
+                        let peers: Vec<ExportedPeerInfo> =
+                            serde_json::from_str(&std::fs::read_to_string(&args[0])?)?;
+                        let count = peers.len();
+                        match client.admin.import_peers(peers).await {
+                            Ok(()) => {
+                                if !json {
+                                    println!("{} peers sent to the node, they will be tested before being added", count);
+                                }
+                                Ok(Box::new(()))
+                            }
+                            Err(e) => rpc_error!(e),
+                        }
+                    }
+                    PeerOperation::ExportBans => {
+                        if args.len() > 1 {
+                            bail!("wrong number of parameters");
+                        }
+                        let reports = match client.admin.export_ban_reports().await {
+                            Ok(reports) => reports,
+                            Err(e) => rpc_error!(e),
+                        };
+                        match args.first() {
+                            Some(path) => {
+                                std::fs::write(path, serde_json::to_string_pretty(&reports)?)?;
+                                if !json {
+                                    println!("{} ban reports exported to {}", reports.len(), path);
+                                }
+                                Ok(Box::new(()))
+                            }
+                            None => Ok(Box::new(reports)),
+                        }
+                    }
+                    PeerOperation::ImportBans => {
+                        if args.len() != 1 {
+                            bail!("wrong number of parameters");
+                        }
+                        let reports: Vec<ExportedBanReportInfo> =
+                            serde_json::from_str(&std::fs::read_to_string(&args[0])?)?;
+                        match client.admin.import_ban_reports(reports).await {
+                            Ok(results) => Ok(Box::new(results)),
+                            Err(e) => rpc_error!(e),
+                        }
+                    }
+                    PeerOperation::Reachability => {
+                        if !args.is_empty() {
+                            bail!("wrong number of parameters");
+                        }
+                        if !json {
+                            println!("Waiting for the peers to dial back the listeners...");
+                        }
+                        match client.admin.test_reachability().await {
+                            Ok(listeners) => Ok(Box::new(listeners)),
+                            Err(e) => rpc_error!(e),
+                        }
+                    }
+                    PeerOperation::Annotate => {
+                        if args.is_empty() {
+                            bail!("[NodeId] parameter shouldn't be empty");
+                        }
+                        let node_id = args[0].parse::<NodeId>()?;
+                        let tags = args
+                            .get(1)
+                            .map(|tags| {
+                                tags.split(',')
+                                    .filter(|tag| !tag.is_empty())
+                                    .map(str::to_string)
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+                        let note = (args.len() > 2).then(|| args[2..].join(" "));
+                        match client.admin.annotate_peer(node_id, tags, note).await {
+                            Ok(()) => {
+                                if !json {
+                                    println!("Annotation of {} successfully sent!", node_id)
+                                }
+                                Ok(Box::new(()))
+                            }
+                            Err(e) => rpc_error!(e),
+                        }
+                    }
+                }
+            }
             Command::exit => {
                 std::process::exit(0);
             }
@@ -4,11 +4,20 @@ use crate::cmds::ExtendedWallet;
 use console::style;
 use erased_serde::{Serialize, Serializer};
 use massa_api_exports::{
-    address::AddressInfo, block::BlockInfo, datastore::DatastoreEntryOutput,
-    endorsement::EndorsementInfo, execution::ExecuteReadOnlyResponse, node::NodeStatus,
+    address::AddressInfo,
+    block::BlockInfo,
+    datastore::DatastoreEntryOutput,
+    endorsement::EndorsementInfo,
+    execution::ExecuteReadOnlyResponse,
+    node::{
+        BootstrapServerCheck, ConnectedPeerInfo, ExportedBanReportInfo, ExportedPeerInfo,
+        ImportedBanReportInfo, ListenerReachabilityInfo, NodeStatus, PeerTestInfo,
+        ProtocolStatsInfo,
+    },
     operation::OperationInfo,
 };
 use massa_models::composite::PubkeySig;
+use massa_models::execution::ExecutedOpsDump;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::PreHashSet;
 use massa_models::stats::{ConsensusStats, ExecutionStats, NetworkStats};
@@ -261,7 +270,10 @@ impl Output for NodeStatus {
                     Style::Id.style(node_id),
                     Style::Protocol.style(ip_addr),
                     if *is_outgoing { "Out" } else { "In" }
-                )
+                );
+                if let Some(connection) = self.connections.get(node_id) {
+                    println!("\t{}", connection.describe(self.current_time));
+                }
             }
         }
     }
@@ -290,6 +302,16 @@ impl Output for ExecutionStats {
             "\tActive cursor: {}",
             Style::Protocol.style(self.active_cursor)
         );
+        let executed_ops_stats = &self.final_executed_ops_stats;
+        println!(
+            "\tTracked executed operations: {}",
+            Style::Protocol.style(executed_ops_stats.tracked_ops_count)
+        );
+        println!(
+            "\tPruned executed operations: {} ({} at the last slot)",
+            Style::Protocol.style(executed_ops_stats.pruned_ops_count),
+            Style::Protocol.style(executed_ops_stats.last_pruned_ops_count)
+        );
     }
 }
 
@@ -316,6 +338,70 @@ impl Output for NetworkStats {
             "\tActive nodes: {}",
             Style::Good.style(self.active_node_count)
         );
+        let gossip_stats = &self.operation_gossip_stats;
+        println!(
+            "\tAnnounced operation ids sent: {} ({} skipped as known by the peer)",
+            Style::Protocol.style(gossip_stats.announced_ids_sent),
+            Style::Protocol.style(gossip_stats.announced_ids_skipped)
+        );
+        println!(
+            "\tAnnounced operation ids received: {} ({} already known)",
+            Style::Protocol.style(gossip_stats.announced_ids_received),
+            Style::Protocol.style(gossip_stats.announced_ids_known)
+        );
+        println!(
+            "\tOperations sent / received: {} / {}",
+            Style::Protocol.style(gossip_stats.ops_sent),
+            Style::Protocol.style(gossip_stats.ops_received)
+        );
+        println!(
+            "\tOperations pushed without being asked: {}",
+            Style::Protocol.style(gossip_stats.ops_pushed)
+        );
+        for (name, known_stats) in [
+            ("blocks", &self.known_blocks_stats),
+            ("operations", &self.known_operations_stats),
+            ("endorsements", &self.known_endorsements_stats),
+        ] {
+            println!(
+                "\tKnown {} cache: {} items over {} peers, {} hits / {} misses, {} evictions",
+                name,
+                Style::Protocol.style(known_stats.entry_count),
+                Style::Protocol.style(known_stats.peer_count),
+                Style::Good.style(known_stats.hits),
+                Style::Protocol.style(known_stats.misses),
+                Style::Protocol.style(known_stats.evictions)
+            );
+        }
+        for channel_stats in &self.channel_stats {
+            println!(
+                "\t{} channel: {}/{} messages, {} dropped",
+                channel_stats.name,
+                Style::Protocol.style(channel_stats.len),
+                Style::Protocol.style(channel_stats.capacity),
+                Style::Bad.style(channel_stats.dropped)
+            );
+        }
+        println!(
+            "\tEndorsement deduplication: {} tracked, {} duplicates dropped",
+            Style::Protocol.style(self.endorsement_dedup_stats.tracked_count),
+            Style::Protocol.style(self.endorsement_dedup_stats.duplicates_dropped)
+        );
+        println!(
+            "\tHandshake failures: {} failures, {} IP bans, {} IPs banned",
+            Style::Protocol.style(self.handshake_failure_stats.failures),
+            Style::Bad.style(self.handshake_failure_stats.ip_bans),
+            Style::Bad.style(self.handshake_failure_stats.banned_ip_count)
+        );
+        println!(
+            "\tEarly headers: {}/{} buffered, {} released, {} expired, {} evicted ({:.1}% hit rate)",
+            Style::Protocol.style(self.early_header_stats.buffered_count),
+            Style::Protocol.style(self.early_header_stats.capacity),
+            Style::Good.style(self.early_header_stats.released),
+            Style::Protocol.style(self.early_header_stats.expired),
+            Style::Bad.style(self.early_header_stats.evicted),
+            self.early_header_stats.hit_rate()
+        );
     }
 }
 
@@ -587,3 +673,116 @@ impl Output for ExecuteReadOnlyResponse {
         println!("{}", self);
     }
 }
+
+impl Output for BootstrapServerCheck {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
+impl Output for ProtocolStatsInfo {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
+impl Output for ExecutedOpsDump {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
+impl Output for Vec<ConnectedPeerInfo> {
+    fn pretty_print(&self) {
+        println!(
+            "{:<55} {:<47} {:<4} {:<9} Connected since",
+            "Node's ID", "Address", "Dir", "Transport"
+        );
+        for peer in self {
+            println!(
+                "{:<55} {:<47} {:<4} {:<9} {}",
+                Style::Id.style(peer.node_id),
+                Style::Protocol.style(peer.address),
+                if peer.is_outgoing { "Out" } else { "In" },
+                peer.transport.as_deref().unwrap_or("-"),
+                peer.connected_since
+                    .map(|time| time.to_utc_string())
+                    .unwrap_or_else(|| "-".to_string())
+            );
+            println!("\t{}", peer.traffic);
+            if !peer.tags.is_empty() || peer.note.is_some() {
+                println!(
+                    "\tTags: {} / Note: {}",
+                    peer.tags.join(", "),
+                    peer.note.as_deref().unwrap_or("-")
+                );
+            }
+        }
+    }
+}
+
+impl Output for PeerTestInfo {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
+impl Output for Vec<ExportedPeerInfo> {
+    fn pretty_print(&self) {
+        println!(
+            "{:<55} {:<10} {:<24} Listeners",
+            "Node's ID", "Category", "Last seen"
+        );
+        for peer in self {
+            let listeners: Vec<String> = peer
+                .listeners
+                .iter()
+                .map(|(address, transport)| format!("{} ({})", address, transport))
+                .collect();
+            println!(
+                "{:<55} {:<10} {:<24} {}",
+                Style::Id.style(peer.node_id),
+                peer.category.as_deref().unwrap_or("-"),
+                peer.last_seen.to_utc_string(),
+                listeners.join(", ")
+            );
+        }
+    }
+}
+
+impl Output for Vec<ExportedBanReportInfo> {
+    fn pretty_print(&self) {
+        println!(
+            "{:<55} {:<20} {:<24} Reporter",
+            "Node's ID", "Offense", "Banned at"
+        );
+        for report in self {
+            println!(
+                "{:<55} {:<20} {:<24} {}",
+                Style::Id.style(report.node_id),
+                report.kind,
+                report.timestamp.to_utc_string(),
+                Style::Id.style(report.reporter)
+            );
+        }
+    }
+}
+
+impl Output for Vec<ImportedBanReportInfo> {
+    fn pretty_print(&self) {
+        for result in self {
+            println!("{}", result);
+        }
+    }
+}
+
+impl Output for Vec<ListenerReachabilityInfo> {
+    fn pretty_print(&self) {
+        if self.is_empty() {
+            println!("No listener to test: the node has no routable IP or no TCP listener");
+        }
+        for listener in self {
+            print!("{}", listener);
+        }
+    }
+}
@@ -3,7 +3,7 @@
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
 use crate::settings::SETTINGS;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use atty::Stream;
 use cmds::Command;
 use console::style;
@@ -33,9 +33,15 @@ struct Args {
     /// Port to listen on (Massa private API).
     #[structopt(long)]
     private_port: Option<u16>,
+    /// Port to listen on (Massa gRPC API, for the admin commands).
+    #[structopt(long)]
+    grpc_port: Option<u16>,
     /// Address to listen on
     #[structopt(long)]
     ip: Option<IpAddr>,
+    /// Name of the node profile to connect to, as defined in the `profiles` section of the config
+    #[structopt(long)]
+    profile: Option<String>,
     /// Command that client would execute (non-interactive mode)
     #[structopt(name = "COMMAND", default_value = "help")]
     command: Command,
@@ -96,35 +102,77 @@ fn main(args: Args) -> anyhow::Result<()> {
 }
 
 async fn run(args: Args) -> Result<()> {
+    // TODO: move settings loading in another crate ... see #1277
+    let settings = SETTINGS.clone();
+
+    let profile = match &args.profile {
+        Some(name) => match settings.profiles.get(name) {
+            Some(profile) => Some(profile.clone()),
+            None => bail!("unknown profile: {}", name),
+        },
+        None => None,
+    };
+
+    let mut headers = settings.client.headers.clone();
+    if let Some(token) = profile
+        .as_ref()
+        .and_then(|profile| profile.auth_token.as_ref())
+    {
+        headers.push(("Authorization".to_string(), format!("Bearer {}", token)));
+    }
+
     let client_config = ClientConfig {
-        max_request_body_size: SETTINGS.client.max_request_body_size,
-        request_timeout: SETTINGS.client.request_timeout,
-        max_concurrent_requests: SETTINGS.client.max_concurrent_requests,
-        certificate_store: SETTINGS.client.certificate_store.clone(),
-        id_kind: SETTINGS.client.id_kind.clone(),
-        max_log_length: SETTINGS.client.max_log_length,
-        headers: SETTINGS.client.headers.clone(),
+        max_request_body_size: settings.client.max_request_body_size,
+        request_timeout: settings.client.request_timeout,
+        max_concurrent_requests: settings.client.max_concurrent_requests,
+        certificate_store: profile
+            .as_ref()
+            .and_then(|profile| profile.certificate_store.clone())
+            .unwrap_or_else(|| settings.client.certificate_store.clone()),
+        id_kind: settings.client.id_kind.clone(),
+        max_log_length: settings.client.max_log_length,
+        headers,
     };
 
     let http_config = HttpConfig {
         client_config,
-        enabled: SETTINGS.client.http.enabled,
+        enabled: settings.client.http.enabled,
+        tls: profile.as_ref().map_or(false, |profile| profile.tls),
     };
 
-    // TODO: move settings loading in another crate ... see #1277
-    let settings = SETTINGS.clone();
+    // command line values take precedence over the profile, which takes precedence over the default node
+    let address = match (args.ip, &profile) {
+        (Some(ip), _) => ip,
+        (None, Some(profile)) => profile.ip,
+        (None, None) => settings.default_node.ip,
+    };
+    let public_port = match (args.public_port, &profile) {
+        (Some(public_port), _) => public_port,
+        (None, Some(profile)) => profile.public_port,
+        (None, None) => settings.default_node.public_port,
+    };
+    let private_port = match (args.private_port, &profile) {
+        (Some(private_port), _) => private_port,
+        (None, Some(profile)) => profile.private_port,
+        (None, None) => settings.default_node.private_port,
+    };
 
-    let address = match args.ip {
-        Some(ip) => ip,
-        None => settings.default_node.ip,
+    let grpc_port = match (args.grpc_port, &profile) {
+        (Some(grpc_port), _) => grpc_port,
+        (None, Some(profile)) => profile.grpc_port.unwrap_or(settings.default_node.grpc_port),
+        (None, None) => settings.default_node.grpc_port,
     };
-    let public_port = match args.public_port {
-        Some(public_port) => public_port,
-        None => settings.default_node.public_port,
+    // the admin token of a local node is read from its config folder
+    let admin_token = match &profile {
+        Some(profile) => profile.admin_token.clone(),
+        None => std::fs::read_to_string(&settings.default_node.admin_token_path)
+            .ok()
+            .map(|token| token.trim().to_string()),
     };
-    let private_port = match args.private_port {
-        Some(private_port) => private_port,
-        None => settings.default_node.private_port,
+
+    let history_file_path = match &args.profile {
+        Some(name) => settings.profile_history_file_path(name),
+        None => settings.history_file_path.clone(),
     };
 
     // Setup panic handlers,
@@ -137,10 +185,25 @@ async fn run(args: Args) -> Result<()> {
         std::process::exit(1);
     }));
 
-    let client = Client::new(address, public_port, private_port, &http_config).await;
+    let client = Client::new(
+        address,
+        public_port,
+        private_port,
+        grpc_port,
+        admin_token.as_deref(),
+        &http_config,
+    )
+    .await;
     if atty::is(Stream::Stdout) && args.command == Command::help && !args.json {
         // Interactive mode
-        repl::run(&client, &args.wallet, args.password).await?;
+        repl::run(
+            &client,
+            &args.wallet,
+            args.password,
+            &history_file_path,
+            args.profile.as_deref(),
+        )
+        .await?;
     } else {
         // Non-Interactive mode
 
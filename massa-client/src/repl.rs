@@ -3,7 +3,6 @@
 use crate::ask_password;
 use crate::cmds::Command;
 use crate::massa_fancy_ascii_art_logo;
-use crate::settings::SETTINGS;
 use anyhow::Result;
 use console::style;
 use massa_sdk::Client;
@@ -80,6 +79,8 @@ pub(crate) async fn run(
     client: &Client,
     wallet_path: &Path,
     args_password: Option<String>,
+    history_file_path: &Path,
+    profile: Option<&str>,
 ) -> Result<()> {
     massa_fancy_ascii_art_logo!();
     println!("Use 'exit' or 'CTRL+D or CTRL+C' to quit the prompt");
@@ -99,14 +100,22 @@ pub(crate) async fn run(
         .build();
     let mut rl: Editor<MyHelper> = Editor::with_config(config)?;
     rl.set_helper(Some(h));
-    if rl.load_history(&SETTINGS.history_file_path).is_err() {
+    if rl.load_history(history_file_path).is_err() {
         println!("No previous history.");
     }
+    // show the profile in the prompt so that commands are not sent to the wrong node
+    let prompt = match profile {
+        Some(profile) => {
+            println!("Using profile {}", style(profile).green());
+            format!("command [{}] > ", profile)
+        }
+        None => "command > ".to_string(),
+    };
 
     let mut wallet_opt = None;
 
     loop {
-        let readline = rl.readline("command > ");
+        let readline = rl.readline(&prompt);
         match readline {
             Ok(line) => {
                 if line.is_empty() {
@@ -158,7 +167,7 @@ pub(crate) async fn run(
             }
         }
     }
-    rl.append_history(&SETTINGS.history_file_path).unwrap();
+    rl.append_history(history_file_path).unwrap();
     Ok(())
 }
 
@@ -4,7 +4,7 @@
 use massa_models::config::build_massa_settings;
 use massa_time::MassaTime;
 use serde::Deserialize;
-use std::{net::IpAddr, path::PathBuf};
+use std::{collections::HashMap, net::IpAddr, path::PathBuf};
 
 lazy_static::lazy_static! {
     pub static ref SETTINGS: Settings = build_massa_settings("massa-client", "MASSA_CLIENT");
@@ -17,6 +17,9 @@ pub struct Settings {
     pub history_file_path: PathBuf,
     pub timeout: MassaTime,
     pub client: ClientSettings,
+    /// named nodes that can be selected with `--profile`
+    #[serde(default)]
+    pub profiles: HashMap<String, NodeProfile>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -24,6 +27,41 @@ pub struct DefaultNode {
     pub ip: IpAddr,
     pub private_port: u16,
     pub public_port: u16,
+    pub grpc_port: u16,
+    /// token of the gRPC admin service, written by the node in its config folder
+    pub admin_token_path: PathBuf,
+}
+
+/// Connection profile of a node
+#[derive(Debug, Deserialize, Clone)]
+pub struct NodeProfile {
+    pub ip: IpAddr,
+    pub private_port: u16,
+    pub public_port: u16,
+    /// port of the gRPC API, `default_node.grpc_port` if not set
+    pub grpc_port: Option<u16>,
+    /// token of the gRPC admin service of the node, used by the `node_peers` commands
+    pub admin_token: Option<String>,
+    /// token sent in the `Authorization` header of every request
+    pub auth_token: Option<String>,
+    /// connect with HTTPS instead of HTTP
+    #[serde(default)]
+    pub tls: bool,
+    /// certificate store used for TLS, `client.certificate_store` if not set
+    pub certificate_store: Option<String>,
+}
+
+impl Settings {
+    /// History file of a profile, next to the default one and suffixed with the profile name
+    pub fn profile_history_file_path(&self, profile: &str) -> PathBuf {
+        let mut file_name = self
+            .history_file_path
+            .file_name()
+            .unwrap_or_default()
+            .to_os_string();
+        file_name.push(format!(".{}", profile));
+        self.history_file_path.with_file_name(file_name)
+    }
 }
 
 /// Client settings
@@ -53,3 +91,14 @@ pub struct HttpSettings {
 fn test_load_client_config() {
     let _ = *SETTINGS;
 }
+
+#[cfg(test)]
+#[test]
+fn test_profile_history_file_path() {
+    let mut settings = SETTINGS.clone();
+    settings.history_file_path = PathBuf::from("config/.massa_history");
+    assert_eq!(
+        settings.profile_history_file_path("mainnet"),
+        PathBuf::from("config/.massa_history.mainnet")
+    );
+}
@@ -76,6 +76,12 @@ pub trait ConsensusController: Send + Sync {
     /// The id of best parents for the next block to be produced along with their period
     fn get_best_parents(&self) -> Vec<(BlockId, u64)>;
 
+    /// Get the latest final blocks
+    ///
+    /// # Returns
+    /// The id of the latest final block of each thread along with its period
+    fn get_latest_final_blocks_periods(&self) -> Vec<(BlockId, u64)>;
+
     /// Get the block id of the block at a specific slot in the blockclique
     ///
     /// # Arguments
@@ -59,6 +59,9 @@ pub enum MockConsensusControllerMessage {
     GetBestParents {
         response_tx: mpsc::Sender<Vec<(BlockId, u64)>>,
     },
+    GetLatestFinalBlocksPeriods {
+        response_tx: mpsc::Sender<Vec<(BlockId, u64)>>,
+    },
     GetBlockcliqueBlockAtSlot {
         slot: Slot,
         response_tx: mpsc::Sender<Option<BlockId>>,
@@ -124,6 +127,8 @@ mockall::mock! {
 
         fn get_best_parents(&self) -> Vec<(BlockId, u64)>;
 
+        fn get_latest_final_blocks_periods(&self) -> Vec<(BlockId, u64)>;
+
         fn get_blockclique_block_at_slot(&self, slot: Slot) -> Option<BlockId>;
 
         fn get_latest_blockclique_block_at_slot(&self, slot: Slot) -> BlockId;
@@ -255,6 +260,16 @@ impl ConsensusController for ConsensusControllerImpl {
         response_rx.recv().unwrap()
     }
 
+    fn get_latest_final_blocks_periods(&self) -> Vec<(BlockId, u64)> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockConsensusControllerMessage::GetLatestFinalBlocksPeriods { response_tx })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
     fn get_blockclique_block_at_slot(&self, slot: Slot) -> Option<BlockId> {
         let (response_tx, response_rx) = mpsc::channel();
         self.0
@@ -201,6 +201,14 @@ impl ConsensusController for ConsensusControllerImpl {
         self.shared_state.read().best_parents.clone()
     }
 
+    /// Get the latest final blocks
+    ///
+    /// # Returns:
+    /// A block id and a period for each thread of the graph
+    fn get_latest_final_blocks_periods(&self) -> Vec<(BlockId, u64)> {
+        self.shared_state.read().latest_final_blocks_periods.clone()
+    }
+
     /// Get the block, that is in the blockclique, at a given slot.
     ///
     /// # Arguments:
@@ -0,0 +1,78 @@
+//! Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Compares the ingestion of a large executed operations bootstrap part,
+//! inserting the operations one by one versus in a batch whose hashes are XORed in parallel.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use massa_executed_ops::{
+    ExecutedOps, ExecutedOpsConfig, ExecutedOpsHashScheme, ExecutedOpsPart, ExecutedOpsPartOps,
+    ExecutedOpsPruningMode,
+};
+use massa_hash::Hash;
+use massa_models::{operation::OperationId, secure_share::Id, slot::Slot};
+
+/// Number of operations of the bootstrap part
+const PART_OPS_COUNT: u32 = 100_000;
+
+fn executed_ops() -> ExecutedOps {
+    ExecutedOps::new(ExecutedOpsConfig {
+        thread_count: 32,
+        bootstrap_part_size: 10,
+        bootstrap_part_size_bytes: 1_000_000,
+        retention_periods: 0,
+        operation_validity_periods: 100,
+        pruning_mode: ExecutedOpsPruningMode::Immediate,
+        hash_scheme: ExecutedOpsHashScheme::Xor,
+    })
+}
+
+fn part_ops() -> ExecutedOpsPartOps {
+    let mut ops = ExecutedOpsPartOps::new();
+    for i in 0..PART_OPS_COUNT {
+        ops.entry(Slot::new(i as u64 / 1_000 + 1, (i % 32) as u8))
+            .or_default()
+            .insert(
+                OperationId::new(Hash::compute_from(&i.to_be_bytes())),
+                Some(true),
+            );
+    }
+    ops
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let ops = part_ops();
+
+    c.bench_function("executed ops part ingestion (sequential)", |b| {
+        b.iter_batched(
+            executed_ops,
+            |mut executed_ops| {
+                for (slot, ids) in ops.iter() {
+                    for op_id in ids.keys() {
+                        executed_ops.ops.insert(*slot, *op_id);
+                    }
+                }
+                black_box(executed_ops)
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    c.bench_function("executed ops part ingestion (batched)", |b| {
+        b.iter_batched(
+            || {
+                (
+                    executed_ops(),
+                    ExecutedOpsPart::new(ops.clone(), &ExecutedOpsPart::initial_cumulative_hash()),
+                )
+            },
+            |(mut executed_ops, part)| {
+                executed_ops.set_executed_ops_part(part);
+                black_box(executed_ops)
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
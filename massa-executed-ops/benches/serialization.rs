@@ -0,0 +1,89 @@
+//! Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Compares the serialization of executed operations bootstrap parts of realistic sizes,
+//! into a buffer allocated once to the exact serialized size versus a buffer grown as it is
+//! written, as the serializer used to do.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use massa_executed_ops::{
+    ExecutedOpsPart, ExecutedOpsPartOps, ExecutedOpsPartSerializer, EXECUTED_OPS_PART_VERSION,
+};
+use massa_hash::{Hash, HashSerializer};
+use massa_models::{
+    operation::{OperationId, OperationIdSerializer},
+    secure_share::Id,
+    slot::{Slot, SlotSerializer},
+};
+use massa_serialization::{
+    BoolSerializer, OptionSerializer, SerializeError, Serializer, U64VarIntSerializer,
+};
+
+/// Part of `ops_count` operations spread over the slots of 32 threads
+fn part(ops_count: u32) -> ExecutedOpsPart {
+    let mut ops = ExecutedOpsPartOps::new();
+    for i in 0..ops_count {
+        ops.entry(Slot::new(i as u64 / 1_000 + 1, (i % 32) as u8))
+            .or_default()
+            .insert(
+                OperationId::new(Hash::compute_from(&i.to_be_bytes())),
+                Some(i % 3 != 0),
+            );
+    }
+    ExecutedOpsPart::new(ops, &ExecutedOpsPart::initial_cumulative_hash())
+}
+
+/// Serialization of a part into a growing buffer, sorting each slot in a new vector
+fn serialize_growing(part: &ExecutedOpsPart) -> Result<Vec<u8>, SerializeError> {
+    let u64_serializer = U64VarIntSerializer::new();
+    let slot_serializer = SlotSerializer::new();
+    let operation_id_serializer = OperationIdSerializer::new();
+    let status_serializer = OptionSerializer::new(BoolSerializer::new());
+    let hash_serializer = HashSerializer::new();
+    let mut buffer = Vec::new();
+    u64_serializer.serialize(&EXECUTED_OPS_PART_VERSION, &mut buffer)?;
+    u64_serializer.serialize(&(part.ops.len() as u64), &mut buffer)?;
+    for (slot, ids) in &part.ops {
+        slot_serializer.serialize(slot, &mut buffer)?;
+        u64_serializer.serialize(&(ids.len() as u64), &mut buffer)?;
+        let mut ids: Vec<(&OperationId, &Option<bool>)> = ids.iter().collect();
+        ids.sort_unstable_by_key(|(op_id, _)| *op_id);
+        for (op_id, status) in ids {
+            operation_id_serializer.serialize(op_id, &mut buffer)?;
+            status_serializer.serialize(status, &mut buffer)?;
+        }
+    }
+    hash_serializer.serialize(&part.part_hash, &mut buffer)?;
+    hash_serializer.serialize(&part.cumulative_hash, &mut buffer)?;
+    Ok(buffer)
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let serializer = ExecutedOpsPartSerializer::new();
+    let mut group = c.benchmark_group("executed ops part serialization");
+    // from a default part to the multi-megabyte parts of a large bootstrap
+    for ops_count in [3_000u32, 30_000, 100_000] {
+        let part = part(ops_count);
+        let mut expected = Vec::new();
+        serializer.serialize(&part, &mut expected).unwrap();
+        assert_eq!(serialize_growing(&part).unwrap(), expected);
+
+        group.bench_with_input(BenchmarkId::new("growing", ops_count), &part, |b, part| {
+            b.iter(|| black_box(serialize_growing(part).unwrap()))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("pre-sized", ops_count),
+            &part,
+            |b, part| {
+                b.iter(|| {
+                    let mut buffer = Vec::new();
+                    serializer.serialize(part, &mut buffer).unwrap();
+                    black_box(buffer)
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -4,8 +4,86 @@
 pub struct ExecutedOpsConfig {
     /// Number of threads
     pub thread_count: u8,
-    /// Maximum size of a bootstrap part
+    /// Maximum number of slots in a bootstrap part
     pub bootstrap_part_size: u64,
+    /// Approximate maximum serialized size of a bootstrap part, in bytes
+    pub bootstrap_part_size_bytes: u64,
+    /// Number of periods an executed operation is kept after its expiry slot
+    pub retention_periods: u64,
+    /// Number of periods before its expiry period during which an operation can be executed.
+    /// Changes with an operation expiring outside of this window are rejected.
+    pub operation_validity_periods: u64,
+    /// How the operations that left the retention window are pruned
+    pub pruning_mode: ExecutedOpsPruningMode,
+    /// Scheme of the accumulated hash of the executed operations
+    pub hash_scheme: ExecutedOpsHashScheme,
+}
+
+/// Pruning mode of the executed operations.
+/// It changes the operations kept at a given slot, and therefore the executed operations hash:
+/// it must be the same on all the nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutedOpsPruningMode {
+    /// Prune all the operations that left the retention window when changes are applied
+    Immediate,
+    /// Only record the pruning horizon when changes are applied,
+    /// and prune the operations that left the retention window in batches
+    /// with `ExecutedOps::prune_pending`
+    Deferred {
+        /// Maximum number of operations pruned by a batch
+        max_ops_per_batch: u64,
+    },
+}
+
+impl ExecutedOpsPruningMode {
+    /// Version of the mode
+    pub fn version(&self) -> u64 {
+        match self {
+            ExecutedOpsPruningMode::Immediate => 0,
+            ExecutedOpsPruningMode::Deferred { .. } => 1,
+        }
+    }
+
+    /// Mode of a version, if known by this node, pruning at most `max_ops_per_batch`
+    /// operations per batch in deferred mode
+    pub fn from_version(version: u64, max_ops_per_batch: u64) -> Option<Self> {
+        match version {
+            0 => Some(ExecutedOpsPruningMode::Immediate),
+            1 => Some(ExecutedOpsPruningMode::Deferred { max_ops_per_batch }),
+            _ => None,
+        }
+    }
+}
+
+/// Scheme of the accumulated hash of the executed operations.
+/// It changes the executed operations hash, and therefore the final state hash:
+/// it must be the same on all the nodes. Each scheme has a version, recorded in the snapshots
+/// and the bootstrap manifests so that a state hashed with another scheme can still be checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutedOpsHashScheme {
+    /// XOR of the hashes of the operation ids (version 0)
+    Xor,
+    /// LtHash of the hashes of the operation ids (version 1)
+    LtHash,
+}
+
+impl ExecutedOpsHashScheme {
+    /// Version of the scheme
+    pub fn version(&self) -> u64 {
+        match self {
+            ExecutedOpsHashScheme::Xor => 0,
+            ExecutedOpsHashScheme::LtHash => 1,
+        }
+    }
+
+    /// Scheme of a version, if known by this node
+    pub fn from_version(version: u64) -> Option<Self> {
+        match version {
+            0 => Some(ExecutedOpsHashScheme::Xor),
+            1 => Some(ExecutedOpsHashScheme::LtHash),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
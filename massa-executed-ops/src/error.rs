@@ -0,0 +1,38 @@
+//! Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! This file defines all error types for executed operations management
+
+use displaydoc::Display;
+use massa_models::{operation::OperationId, slot::Slot};
+use thiserror::Error;
+
+/// Executed operations error
+#[non_exhaustive]
+#[derive(Display, Error, Debug, Clone, PartialEq, Eq)]
+pub enum ExecutedOpsError {
+    /// changes applied at slot {slot} while changes were already applied at slot {last_applied_slot}
+    OutOfOrderSlot {
+        /// slot of the rejected changes
+        slot: Slot,
+        /// slot of the last applied changes
+        last_applied_slot: Slot,
+    },
+    /// operation {0} was already executed with another slot or execution status
+    ConflictingOperation(OperationId),
+    /// operation {op_id} expiring at slot {expiry_slot} cannot be executed at slot {slot}
+    ExpirySlotOutOfWindow {
+        /// the rejected operation
+        op_id: OperationId,
+        /// recorded expiry slot of the operation
+        expiry_slot: Slot,
+        /// slot of the rejected changes
+        slot: Slot,
+    },
+    /// operation {op_id} expires at slot {expiry_slot}, in a thread that does not exist
+    InvalidExpiryThread {
+        /// the rejected operation
+        op_id: OperationId,
+        /// recorded expiry slot of the operation
+        expiry_slot: Slot,
+    },
+}
@@ -4,41 +4,31 @@
 //! Used to detect denunciation reuse.
 
 use std::collections::{BTreeMap, HashSet};
-use std::ops::Bound::{Excluded, Included, Unbounded};
 
 use nom::{
-    error::{context, ContextError, ParseError},
-    multi::length_count,
-    sequence::tuple,
-    IResult, Parser,
+    error::{ContextError, ParseError},
+    IResult,
 };
 
-use crate::{ExecutedDenunciationsChanges, ExecutedDenunciationsConfig};
+use crate::{
+    ExecutedDenunciationsChanges, ExecutedDenunciationsConfig, SlotIndexedSet,
+    SlotIndexedSetDeserializer, SlotIndexedSetSerializer,
+};
 
-use massa_hash::{Hash, HASH_SIZE_BYTES};
-use massa_models::denunciation::Denunciation;
 use massa_models::streaming_step::StreamingStep;
 use massa_models::{
     denunciation::{DenunciationIndex, DenunciationIndexDeserializer, DenunciationIndexSerializer},
-    slot::{Slot, SlotDeserializer, SlotSerializer},
-};
-use massa_serialization::{
-    Deserializer, SerializeError, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
+    slot::Slot,
 };
-
-const EXECUTED_DENUNCIATIONS_HASH_INITIAL_BYTES: &[u8; 32] = &[0; HASH_SIZE_BYTES];
+use massa_serialization::{Deserializer, SerializeError, Serializer};
 
 /// A structure to list and prune previously executed denunciations
 #[derive(Debug, Clone)]
 pub struct ExecutedDenunciations {
     /// Executed denunciations configuration
     config: ExecutedDenunciationsConfig,
-    /// for better pruning complexity
-    pub sorted_denunciations: BTreeMap<Slot, HashSet<DenunciationIndex>>,
-    /// for better insertion complexity
-    pub denunciations: HashSet<DenunciationIndex>,
-    /// Accumulated hash of the executed denunciations
-    pub hash: Hash,
+    /// Executed denunciations indexed by slot, with their accumulated hash
+    pub denunciations: SlotIndexedSet<DenunciationIndex>,
 }
 
 impl ExecutedDenunciations {
@@ -46,27 +36,23 @@ impl ExecutedDenunciations {
     pub fn new(config: ExecutedDenunciationsConfig) -> Self {
         Self {
             config,
-            sorted_denunciations: Default::default(),
-            denunciations: Default::default(),
-            hash: Hash::from_bytes(EXECUTED_DENUNCIATIONS_HASH_INITIAL_BYTES),
+            denunciations: SlotIndexedSet::new(),
         }
     }
 
-    /// Reset the executed operations
+    /// Reset the executed denunciations
     ///
     /// USED FOR BOOTSTRAP ONLY
     pub fn reset(&mut self) {
-        self.sorted_denunciations.clear();
-        self.denunciations.clear();
-        self.hash = Hash::from_bytes(EXECUTED_DENUNCIATIONS_HASH_INITIAL_BYTES);
+        self.denunciations.reset();
     }
 
-    /// Returns the number of executed operations
+    /// Returns the number of executed denunciations
     pub fn len(&self) -> usize {
         self.denunciations.len()
     }
 
-    /// Check executed ops emptiness
+    /// Check executed denunciations emptiness
     pub fn is_empty(&self) -> bool {
         self.denunciations.is_empty()
     }
@@ -76,56 +62,20 @@ impl ExecutedDenunciations {
         self.denunciations.contains(de_idx)
     }
 
-    /// Internal function used to insert the values of an operation id iter and update the object hash
-    fn extend_and_compute_hash<'a, I>(&mut self, values: I)
-    where
-        I: Iterator<Item = &'a DenunciationIndex>,
-    {
-        for de_idx in values {
-            if self.denunciations.insert((*de_idx).clone()) {
-                self.hash ^= de_idx.get_hash();
-            }
-        }
-    }
-
-    /// Apply speculative operations changes to the final executed denunciations state
+    /// Apply speculative denunciations changes to the final executed denunciations state,
+    /// then prune the denunciations that have expired, assuming the given slot is final
     pub fn apply_changes(&mut self, changes: ExecutedDenunciationsChanges, slot: Slot) {
-        self.extend_and_compute_hash(changes.iter());
-        for de_idx in changes {
-            self.sorted_denunciations
-                .entry(*de_idx.get_slot())
-                .and_modify(|ids| {
-                    ids.insert(de_idx.clone());
-                })
-                .or_insert_with(|| {
-                    let mut new = HashSet::default();
-                    new.insert(de_idx.clone());
-                    new
-                });
-        }
-
-        self.prune(slot);
-    }
-
-    /// Prune all denunciations that have expired, assuming the given slot is final
-    fn prune(&mut self, slot: Slot) {
-        let drained: Vec<(Slot, HashSet<DenunciationIndex>)> = self
-            .sorted_denunciations
-            .drain_filter(|de_idx_slot, _| {
-                Denunciation::is_expired(
-                    &de_idx_slot.period,
-                    &slot.period,
-                    &self.config.denunciation_expire_periods,
-                )
-            })
-            .collect();
-
-        for (_slot, de_indexes) in drained {
-            for de_idx in de_indexes {
-                self.denunciations.remove(&de_idx);
-                self.hash ^= de_idx.get_hash();
-            }
-        }
+        self.denunciations.extend(
+            changes
+                .into_iter()
+                .map(|de_idx| (*de_idx.get_slot(), de_idx)),
+        );
+        // same bound as `Denunciation::is_expired`
+        self.denunciations.prune(Slot::new(
+            slot.period
+                .saturating_sub(self.config.denunciation_expire_periods),
+            0,
+        ));
     }
 
     /// Get a part of the executed denunciations.
@@ -140,26 +90,31 @@ impl ExecutedDenunciations {
         BTreeMap<Slot, HashSet<DenunciationIndex>>,
         StreamingStep<Slot>,
     ) {
-        let mut de_part = BTreeMap::new();
-        let left_bound = match cursor {
-            StreamingStep::Started => Unbounded,
-            StreamingStep::Ongoing(slot) => Excluded(slot),
-            StreamingStep::Finished(_) => return (de_part, cursor),
+        // parts hold whole slots: resume after the greatest index a slot can hold
+        let after_slot = |slot: Slot| {
+            (
+                slot,
+                DenunciationIndex::Endorsement {
+                    slot,
+                    index: u32::MAX,
+                },
+            )
         };
-        let mut de_part_last_slot: Option<Slot> = None;
-        for (slot, ids) in self.sorted_denunciations.range((left_bound, Unbounded)) {
-            if de_part.len() < self.config.bootstrap_part_size as usize {
-                de_part.insert(*slot, ids.clone());
-                de_part_last_slot = Some(*slot);
-            } else {
-                break;
-            }
-        }
-        if let Some(last_slot) = de_part_last_slot {
-            (de_part, StreamingStep::Ongoing(last_slot))
-        } else {
-            (de_part, StreamingStep::Finished(None))
-        }
+        let cursor = match cursor {
+            StreamingStep::Started => StreamingStep::Started,
+            StreamingStep::Ongoing(slot) => StreamingStep::Ongoing(after_slot(slot)),
+            StreamingStep::Finished(slot) => StreamingStep::Finished(slot.map(after_slot)),
+        };
+        let (de_part, next_step) =
+            self.denunciations
+                .get_part(cursor, self.config.bootstrap_part_size, u64::MAX, 0);
+        (
+            de_part
+                .into_iter()
+                .map(|(slot, ids)| (slot, ids.into_iter().collect()))
+                .collect(),
+            slot_step(next_step),
+        )
     }
 
     /// Set a part of the executed denunciations.
@@ -167,30 +122,72 @@ impl ExecutedDenunciations {
     /// Takes the data returned from `get_executed_de_part` as input.
     ///
     /// # Returns
-    /// The next executed de streaming step
+    /// The next executed de streaming step: the last slot of the part, as the known slots
+    /// can go beyond it because of the streamed changes
     pub fn set_executed_de_part(
         &mut self,
         part: BTreeMap<Slot, HashSet<DenunciationIndex>>,
     ) -> StreamingStep<Slot> {
-        self.sorted_denunciations.extend(part.clone());
-        self.extend_and_compute_hash(part.iter().flat_map(|(_, ids)| ids));
-        if let Some(slot) = self
-            .sorted_denunciations
-            .last_key_value()
-            .map(|(slot, _)| slot)
-        {
-            StreamingStep::Ongoing(*slot)
-        } else {
-            StreamingStep::Finished(None)
+        slot_step(self.denunciations.set_part(part))
+    }
+}
+
+/// Keep the slot of the cursor of a streaming step, parts holding whole slots
+fn slot_step(step: StreamingStep<(Slot, DenunciationIndex)>) -> StreamingStep<Slot> {
+    match step {
+        StreamingStep::Started => StreamingStep::Started,
+        StreamingStep::Ongoing((slot, _)) => StreamingStep::Ongoing(slot),
+        StreamingStep::Finished(last) => StreamingStep::Finished(last.map(|(slot, _)| slot)),
+    }
+}
+
+#[test]
+fn test_executed_de_streaming_with_changes() {
+    let config = ExecutedDenunciationsConfig {
+        denunciation_expire_periods: 100,
+        bootstrap_part_size: 2,
+    };
+    let mut server = ExecutedDenunciations::new(config.clone());
+    let mut changes = HashSet::new();
+    for period in 1..=6 {
+        for index in 0..3 {
+            changes.insert(DenunciationIndex::Endorsement {
+                slot: Slot::new(period, 0),
+                index,
+            });
+        }
+    }
+    server.apply_changes(changes, Slot::new(1, 0));
+
+    let mut client = ExecutedDenunciations::new(config);
+    let mut cursor = StreamingStep::Started;
+    let mut step = 0;
+    loop {
+        let (part, server_step) = server.get_executed_de_part(cursor);
+        if let StreamingStep::Finished(_) = server_step {
+            break;
         }
+        cursor = client.set_executed_de_part(part);
+        if step == 0 {
+            // a denunciation of a slot not streamed yet is executed and sent as a change
+            let mut changes = HashSet::new();
+            changes.insert(DenunciationIndex::Endorsement {
+                slot: Slot::new(5, 0),
+                index: 3,
+            });
+            server.apply_changes(changes.clone(), Slot::new(1, 0));
+            client.apply_changes(changes, Slot::new(1, 0));
+        }
+        step += 1;
     }
+    assert_eq!(client.denunciations.sorted, server.denunciations.sorted);
+    assert_eq!(client.denunciations.ids, server.denunciations.ids);
+    assert_eq!(client.denunciations.hash, server.denunciations.hash);
 }
 
 /// `ExecutedDenunciations` Serializer
 pub struct ExecutedDenunciationsSerializer {
-    slot_serializer: SlotSerializer,
-    de_idx_serializer: DenunciationIndexSerializer,
-    u64_serializer: U64VarIntSerializer,
+    set_serializer: SlotIndexedSetSerializer<DenunciationIndex, DenunciationIndexSerializer>,
 }
 
 impl Default for ExecutedDenunciationsSerializer {
@@ -203,9 +200,7 @@ impl ExecutedDenunciationsSerializer {
     /// Create a new `ExecutedDenunciations` Serializer
     pub fn new() -> Self {
         Self {
-            slot_serializer: SlotSerializer::new(),
-            de_idx_serializer: DenunciationIndexSerializer::new(),
-            u64_serializer: U64VarIntSerializer::new(),
+            set_serializer: SlotIndexedSetSerializer::new(DenunciationIndexSerializer::new()),
         }
     }
 }
@@ -216,30 +211,13 @@ impl Serializer<BTreeMap<Slot, HashSet<DenunciationIndex>>> for ExecutedDenuncia
         value: &BTreeMap<Slot, HashSet<DenunciationIndex>>,
         buffer: &mut Vec<u8>,
     ) -> Result<(), SerializeError> {
-        // exec denunciations length
-        self.u64_serializer
-            .serialize(&(value.len() as u64), buffer)?;
-        // executed ops
-        for (slot, ids) in value {
-            // slot
-            self.slot_serializer.serialize(slot, buffer)?;
-            // slot ids length
-            self.u64_serializer.serialize(&(ids.len() as u64), buffer)?;
-            // slots ids
-            for de_idx in ids {
-                self.de_idx_serializer.serialize(de_idx, buffer)?;
-            }
-        }
-        Ok(())
+        self.set_serializer.serialize(value, buffer)
     }
 }
 
 /// Deserializer for `ExecutedDenunciations`
 pub struct ExecutedDenunciationsDeserializer {
-    de_idx_deserializer: DenunciationIndexDeserializer,
-    slot_deserializer: SlotDeserializer,
-    ops_length_deserializer: U64VarIntDeserializer,
-    slot_ops_length_deserializer: U64VarIntDeserializer,
+    set_deserializer: SlotIndexedSetDeserializer<DenunciationIndex, DenunciationIndexDeserializer>,
 }
 
 impl ExecutedDenunciationsDeserializer {
@@ -251,21 +229,11 @@ impl ExecutedDenunciationsDeserializer {
         max_denunciations_per_block_header: u64,
     ) -> Self {
         Self {
-            de_idx_deserializer: DenunciationIndexDeserializer::new(
+            set_deserializer: SlotIndexedSetDeserializer::new(
+                DenunciationIndexDeserializer::new(thread_count, endorsement_count),
                 thread_count,
-                endorsement_count,
-            ),
-            slot_deserializer: SlotDeserializer::new(
-                (Included(u64::MIN), Included(u64::MAX)),
-                (Included(0), Excluded(thread_count)),
-            ),
-            ops_length_deserializer: U64VarIntDeserializer::new(
-                Included(u64::MIN),
-                Included(max_executed_de_length),
-            ),
-            slot_ops_length_deserializer: U64VarIntDeserializer::new(
-                Included(u64::MIN),
-                Included(max_denunciations_per_block_header),
+                max_executed_de_length,
+                max_denunciations_per_block_header,
             ),
         }
     }
@@ -278,34 +246,6 @@ impl Deserializer<BTreeMap<Slot, HashSet<DenunciationIndex>>>
         &self,
         buffer: &'a [u8],
     ) -> IResult<&'a [u8], BTreeMap<Slot, HashSet<DenunciationIndex>>, E> {
-        context(
-            "ExecutedDenunciations",
-            length_count(
-                context("length", |input| {
-                    self.ops_length_deserializer.deserialize(input)
-                }),
-                context(
-                    "slot de_idx",
-                    tuple((
-                        context("slot", |input| self.slot_deserializer.deserialize(input)),
-                        length_count(
-                            context("slot denunciations length", |input| {
-                                self.slot_ops_length_deserializer.deserialize(input)
-                            }),
-                            context("denunciation index", |input| {
-                                self.de_idx_deserializer.deserialize(input)
-                            }),
-                        ),
-                    )),
-                ),
-            ),
-        )
-        .map(|operations| {
-            operations
-                .into_iter()
-                .map(|(slot, ids)| (slot, ids.into_iter().collect()))
-                .collect()
-        })
-        .parse(buffer)
+        self.set_deserializer.deserialize(buffer)
     }
 }
@@ -3,55 +3,191 @@
 //! This file defines a structure to list and prune previously executed operations.
 //! Used to detect operation reuse.
 
-use crate::{ops_changes::ExecutedOpsChanges, ExecutedOpsConfig};
-use massa_hash::{Hash, HASH_SIZE_BYTES};
+use crate::{
+    error::ExecutedOpsError,
+    frozen_executed_ops::FrozenExecutedOps,
+    ops_changes::ExecutedOpsChanges,
+    slot_indexed_set::{
+        is_canonical, slot_size, varint_size, SlotIndexedSet, SlotIndexedSetDeserializer,
+        SlotIndexedSetSerializer,
+    },
+    ExecutedOpsConfig, ExecutedOpsHashScheme, ExecutedOpsPruningMode,
+};
+use bitvec::vec::BitVec;
+use massa_hash::{Hash, HashDeserializer, HashSerializer, HASH_SIZE_BYTES};
 use massa_models::{
-    operation::{OperationId, OperationIdDeserializer},
-    prehash::PreHashSet,
-    secure_share::Id,
+    execution::{ExecutedOpsDump, ExecutedOpsSlotDump},
+    operation::{
+        OperationId, OperationIdDeserializer, OperationIdSerializer, OPERATION_ID_SIZE_BYTES,
+    },
+    prehash::{PreHashMap, PreHashSet},
     slot::{Slot, SlotDeserializer, SlotSerializer},
+    stats::ExecutedOpsStats,
     streaming_step::StreamingStep,
 };
 use massa_serialization::{
-    Deserializer, SerializeError, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
+    BoolDeserializer, BoolSerializer, DeserializeError, Deserializer, OptionDeserializer,
+    OptionSerializer, SerializeError, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
 };
 use nom::{
+    combinator::verify,
     error::{context, ContextError, ParseError},
     multi::length_count,
     sequence::tuple,
     IResult, Parser,
 };
 use std::{
+    cell::Cell,
     collections::{BTreeMap, HashMap},
-    ops::Bound::{Excluded, Included, Unbounded},
+    io::{Error as IoError, ErrorKind, Read, Write},
+    ops::Bound::{Excluded, Included},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Instant,
 };
 
-const EXECUTED_OPS_HASH_INITIAL_BYTES: &[u8; 32] = &[0; HASH_SIZE_BYTES];
+#[cfg(test)]
+use massa_models::secure_share::Id;
+
+/// Upper bound of the serialized size of an operation of a bootstrap part and of its status
+pub(crate) const PART_OP_ENTRY_MAX_BYTES: u64 = OPERATION_ID_SIZE_BYTES as u64 + 2;
+
+/// Version of the executed ops bootstrap part format
+pub const EXECUTED_OPS_PART_VERSION: u64 = 2;
+
+/// Operations of an executed ops bootstrap part by slot, with their execution status,
+/// if known (true: success, false: fail)
+pub type ExecutedOpsPartOps = BTreeMap<Slot, PreHashMap<OperationId, Option<bool>>>;
+
+/// Executed operations bootstrap part.
+///
+/// Each part carries the hash of its operations and a cumulative hash chaining it to the parts
+/// streamed before it, so the client can verify every part as it arrives and resume the stream
+/// from the last verified one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutedOpsPart {
+    /// operations of the part
+    pub ops: ExecutedOpsPartOps,
+    /// hash of the canonical serialization of `ops`
+    pub part_hash: Hash,
+    /// hash of the cumulative hash of the previous part followed by `part_hash`
+    pub cumulative_hash: Hash,
+}
+
+impl ExecutedOpsPart {
+    /// Cumulative hash preceding the first part of a stream
+    pub fn initial_cumulative_hash() -> Hash {
+        Hash::from_bytes(&[0; HASH_SIZE_BYTES])
+    }
+
+    /// Create a part, chaining its cumulative hash to the one of the previous part
+    pub fn new(ops: ExecutedOpsPartOps, previous_cumulative_hash: &Hash) -> Self {
+        let (part_hash, cumulative_hash) = Self::compute_hashes(&ops, previous_cumulative_hash);
+        ExecutedOpsPart {
+            ops,
+            part_hash,
+            cumulative_hash,
+        }
+    }
+
+    /// Check the hashes of a received part against its operations
+    /// and the cumulative hash of the previous part
+    pub fn verify(&self, previous_cumulative_hash: &Hash) -> Result<(), String> {
+        let (part_hash, cumulative_hash) =
+            Self::compute_hashes(&self.ops, previous_cumulative_hash);
+        if part_hash != self.part_hash {
+            return Err(format!(
+                "executed ops part hash mismatch: expected {}, got {}",
+                part_hash, self.part_hash
+            ));
+        }
+        if cumulative_hash != self.cumulative_hash {
+            return Err(format!(
+                "executed ops cumulative hash mismatch: expected {}, got {}",
+                cumulative_hash, self.cumulative_hash
+            ));
+        }
+        Ok(())
+    }
+
+    fn compute_hashes(ops: &ExecutedOpsPartOps, previous_cumulative_hash: &Hash) -> (Hash, Hash) {
+        let serializer = ExecutedOpsPartSerializer::new();
+        let mut buffer = Vec::with_capacity(serializer.ops_serialized_size(ops));
+        serializer
+            .serialize_ops(ops, &mut buffer)
+            .expect("critical: executed ops part serialization failed");
+        let part_hash = Hash::compute_from(&buffer);
+        let mut chained = previous_cumulative_hash.to_bytes().to_vec();
+        chained.extend(part_hash.to_bytes());
+        (part_hash, Hash::compute_from(&chained))
+    }
+}
+
+/// Magic bytes at the start of an executed ops snapshot file
+const EXECUTED_OPS_SNAPSHOT_MAGIC: &[u8; 4] = b"MXOS";
+
+/// Version of the executed ops snapshot format.
+/// Version 0 snapshots hold no hash scheme, their hash is the XOR of the operation ids.
+const EXECUTED_OPS_SNAPSHOT_VERSION: u64 = 1;
+
+/// Cursor of the executed ops bootstrap streaming: the last streamed operation and its slot.
+/// Operations of a slot are streamed in ascending order so a slot can be split across parts.
+pub type ExecutedOpsStreamingStep = StreamingStep<(Slot, OperationId)>;
+
+/// Counters of the activity of `ExecutedOps`, exposed through `ExecutedOpsStats`.
+/// Atomic so that the bootstrap server can count the parts it reads through a shared reference.
+#[derive(Debug, Default)]
+pub(crate) struct ExecutedOpsMetrics {
+    inserted_ops_count: AtomicU64,
+    pruned_ops_count: AtomicU64,
+    last_pruned_ops_count: AtomicU64,
+    prune_time_micros: AtomicU64,
+    bootstrap_parts_sent: AtomicU64,
+    bootstrap_ops_sent: AtomicU64,
+    bootstrap_parts_received: AtomicU64,
+    bootstrap_ops_received: AtomicU64,
+}
+
+impl ExecutedOpsMetrics {
+    /// Count a bootstrap part sent with `ops_count` operations
+    pub(crate) fn record_part_sent(&self, ops_count: u64) {
+        self.bootstrap_parts_sent.fetch_add(1, Ordering::Relaxed);
+        self.bootstrap_ops_sent
+            .fetch_add(ops_count, Ordering::Relaxed);
+    }
+}
 
 /// A structure to list and prune previously executed operations
 #[derive(Debug, Clone)]
 pub struct ExecutedOps {
     /// Executed operations configuration
     config: ExecutedOpsConfig,
-    /// Executed operations btreemap with slot as index for better pruning complexity
-    pub sorted_ops: BTreeMap<Slot, PreHashSet<OperationId>>,
-    /// Executed operations only for better insertion complexity
-    pub ops: PreHashSet<OperationId>,
-    /// Accumulated hash of the executed operations
-    pub hash: Hash,
-    /// execution status of operations (true: success, false: fail)
-    pub op_exec_status: HashMap<OperationId, bool>,
+    /// Executed operations indexed by slot, with their accumulated hash
+    pub ops: SlotIndexedSet<OperationId>,
+    /// execution status of operations (true: success, false: fail).
+    /// Shared with the frozen views, and copied on write while one is alive.
+    pub op_exec_status: Arc<HashMap<OperationId, bool>>,
+    /// Slot of the last applied changes, used to reject out-of-order applications
+    last_applied_slot: Option<Slot>,
+    /// Operations expiring strictly before this slot left the retention window
+    /// and are pruned, at once or in batches depending on the pruning mode
+    prune_horizon: Option<Slot>,
+    /// Activity counters, shared with the clones and the frozen views
+    metrics: Arc<ExecutedOpsMetrics>,
 }
 
 impl ExecutedOps {
     /// Creates a new `ExecutedOps`
     pub fn new(config: ExecutedOpsConfig) -> Self {
         Self {
+            ops: SlotIndexedSet::with_hash_scheme(config.hash_scheme),
             config,
-            sorted_ops: BTreeMap::new(),
-            ops: PreHashSet::default(),
-            hash: Hash::from_bytes(EXECUTED_OPS_HASH_INITIAL_BYTES),
-            op_exec_status: HashMap::new(),
+            op_exec_status: Default::default(),
+            last_applied_slot: None,
+            prune_horizon: None,
+            metrics: Default::default(),
         }
     }
 
@@ -61,23 +197,13 @@ impl ExecutedOps {
         config: ExecutedOpsConfig,
         sorted_ops: BTreeMap<Slot, PreHashSet<OperationId>>,
     ) -> Self {
-        let mut hash = Hash::from_bytes(EXECUTED_OPS_HASH_INITIAL_BYTES);
-        let mut ops = PreHashSet::default();
-        for (_, op_ids) in sorted_ops.clone() {
-            for op_id in op_ids {
-                if ops.insert(op_id) {
-                    // This let's us compute the accumulated hash of all op_ids in the struct.
-                    // We XOR the hash to allow reversibility if we remove an op_id.
-                    hash ^= *op_id.get_hash();
-                }
-            }
-        }
         Self {
+            ops: SlotIndexedSet::from_sorted(sorted_ops, config.hash_scheme),
             config,
-            sorted_ops,
-            ops,
-            hash,
-            op_exec_status: HashMap::new(),
+            op_exec_status: Default::default(),
+            last_applied_slot: None,
+            prune_horizon: None,
+            metrics: Default::default(),
         }
     }
 
@@ -85,9 +211,10 @@ impl ExecutedOps {
     ///
     /// USED FOR BOOTSTRAP ONLY
     pub fn reset(&mut self) {
-        self.sorted_ops.clear();
-        self.ops.clear();
-        self.hash = Hash::from_bytes(EXECUTED_OPS_HASH_INITIAL_BYTES);
+        self.ops.reset();
+        self.op_exec_status = Default::default();
+        self.last_applied_slot = None;
+        self.prune_horizon = None;
     }
 
     /// Returns the number of executed operations
@@ -100,37 +227,246 @@ impl ExecutedOps {
         self.ops.is_empty()
     }
 
-    /// Internal function used to insert the values of an operation id iter and update the object hash
-    fn extend_and_compute_hash<'a, I>(&mut self, values: I)
-    where
-        I: Iterator<Item = &'a OperationId>,
-    {
-        for op_id in values {
-            if self.ops.insert(*op_id) {
-                self.hash ^= *op_id.get_hash();
+    /// Get the statistics of the executed operations
+    pub fn get_stats(&self) -> ExecutedOpsStats {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        ExecutedOpsStats {
+            tracked_ops_count: self.ops.len(),
+            inserted_ops_count: load(&self.metrics.inserted_ops_count),
+            pruned_ops_count: load(&self.metrics.pruned_ops_count),
+            last_pruned_ops_count: load(&self.metrics.last_pruned_ops_count),
+            prune_time_micros: load(&self.metrics.prune_time_micros),
+            bootstrap_parts_sent: load(&self.metrics.bootstrap_parts_sent),
+            bootstrap_ops_sent: load(&self.metrics.bootstrap_ops_sent),
+            bootstrap_parts_received: load(&self.metrics.bootstrap_parts_received),
+            bootstrap_ops_received: load(&self.metrics.bootstrap_ops_received),
+        }
+    }
+
+    /// Dump up to `limit` slots of the executed operations, starting from the slot at index
+    /// `offset`, with their operations in ascending order.
+    /// Meant to diagnose operation reuse disagreements by diffing the dumps of two nodes.
+    pub fn get_dump(&self, offset: usize, limit: usize) -> ExecutedOpsDump {
+        let slots = self
+            .ops
+            .sorted
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .map(|(slot, ids)| {
+                let mut op_ids: Vec<OperationId> = ids.iter().copied().collect();
+                op_ids.sort_unstable();
+                ExecutedOpsSlotDump {
+                    slot: *slot,
+                    op_count: op_ids.len(),
+                    op_ids,
+                }
+            })
+            .collect();
+        ExecutedOpsDump {
+            last_applied_slot: self.last_applied_slot,
+            hash: self.ops.hash,
+            total_slot_count: self.ops.sorted.len(),
+            total_op_count: self.ops.len(),
+            offset,
+            slots,
+        }
+    }
+
+    /// Iterate over the operations executed in the slots between `start_slot` and `end_slot`,
+    /// both included, ordered by slot
+    pub fn get_ops_in_range(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+    ) -> impl Iterator<Item = (&Slot, &OperationId)> {
+        self.ops.iter_range(start_slot, end_slot)
+    }
+
+    /// Count the operations executed in the slots between `start_slot` and `end_slot`,
+    /// both included
+    pub fn count_ops_in_range(&self, start_slot: Slot, end_slot: Slot) -> usize {
+        self.ops.count_range(start_slot, end_slot)
+    }
+
+    /// Recompute the accumulated hash from scratch out of the executed operations
+    pub fn recompute_hash(&self) -> Hash {
+        self.ops.recompute_hash()
+    }
+
+    /// Scheme of the accumulated hash of the executed operations
+    pub fn hash_scheme(&self) -> ExecutedOpsHashScheme {
+        self.ops.hash_scheme()
+    }
+
+    /// Compute the accumulated hash of the executed operations with `scheme`.
+    /// Used to check the state against the hash of a node using another scheme.
+    pub fn compute_hash(&self, scheme: ExecutedOpsHashScheme) -> Hash {
+        self.ops.compute_hash(scheme)
+    }
+
+    /// Migrate the accumulated hash of the executed operations to `scheme`,
+    /// recomputing it from the operations.
+    /// Must be done by all the nodes at the same slot, as it changes the final state hash.
+    ///
+    /// # Returns
+    /// Whether the scheme changed
+    pub fn set_hash_scheme(&mut self, scheme: ExecutedOpsHashScheme) -> bool {
+        self.config.hash_scheme = scheme;
+        self.ops.set_hash_scheme(scheme)
+    }
+
+    /// Check that the executed operations set and its slot index hold the same operations
+    /// and that the accumulated hash matches them.
+    ///
+    /// # Returns
+    /// A description of the first divergence found, if any
+    pub fn verify_integrity(&self) -> Result<(), String> {
+        self.ops.verify_integrity()
+    }
+
+    /// Apply speculative operations changes to the final executed operations state.
+    /// The operations that left the retention window at `slot` are pruned right away in
+    /// `Immediate` pruning mode, and left to `prune_pending` in `Deferred` pruning mode.
+    ///
+    /// Changes must be applied at non-decreasing slots. Re-applying changes that were already
+    /// applied is a no-op, but an operation that is already known with another slot or execution
+    /// status is rejected, as indexing it twice would corrupt the accumulated hash.
+    /// An operation whose expiry slot could not have been executed at `slot` is rejected too,
+    /// as it would be pruned too early or kept forever.
+    /// Nothing is modified if an error is returned.
+    pub fn apply_changes(
+        &mut self,
+        changes: ExecutedOpsChanges,
+        slot: Slot,
+    ) -> Result<(), ExecutedOpsError> {
+        self.check_changes(&changes, slot)?;
+        self.last_applied_slot = Some(slot);
+
+        Arc::make_mut(&mut self.op_exec_status).extend(
+            changes
+                .iter()
+                .map(|(op_id, (op_exec_success, _))| (*op_id, *op_exec_success)),
+        );
+        let inserted_count = self
+            .ops
+            .extend(changes.into_iter().map(|(op_id, (_, slot))| (slot, op_id)));
+        self.metrics
+            .inserted_ops_count
+            .fetch_add(inserted_count as u64, Ordering::Relaxed);
+
+        let horizon = self.retention_horizon(slot);
+        self.prune_horizon = Some(horizon);
+        if self.config.pruning_mode == ExecutedOpsPruningMode::Immediate {
+            self.prune(horizon, None);
+        }
+        Ok(())
+    }
+
+    /// Prune a batch of the operations that left the retention window, in `Deferred` pruning mode.
+    /// Meant to be called once per applied slot, so that large prunes are spread over slots.
+    ///
+    /// # Returns
+    /// The number of pruned operations
+    pub fn prune_pending(&mut self) -> u64 {
+        match (self.config.pruning_mode, self.prune_horizon) {
+            (ExecutedOpsPruningMode::Deferred { max_ops_per_batch }, Some(horizon)) => {
+                self.prune(horizon, Some(max_ops_per_batch))
             }
+            _ => 0,
         }
     }
 
-    /// Apply speculative operations changes to the final executed operations state
-    pub fn apply_changes(&mut self, changes: ExecutedOpsChanges, slot: Slot) {
-        self.extend_and_compute_hash(changes.keys());
-        for (op_id, (op_exec_success, slot)) in changes {
-            self.sorted_ops
-                .entry(slot)
-                .and_modify(|ids| {
-                    ids.insert(op_id);
-                })
-                .or_insert_with(|| {
-                    let mut new = PreHashSet::default();
-                    new.insert(op_id);
-                    new
+    /// Prune at once all the operations that left the retention window at `slot`,
+    /// whatever the pruning mode
+    ///
+    /// # Returns
+    /// The number of pruned operations
+    pub fn prune_now(&mut self, slot: Slot) -> u64 {
+        let horizon = self.retention_horizon(slot);
+        self.prune(horizon, None)
+    }
+
+    /// Pruning horizon at `slot`: the operations expiring strictly before it left the retention window
+    fn retention_horizon(&self, slot: Slot) -> Slot {
+        Slot::new(
+            slot.period.saturating_sub(self.config.retention_periods),
+            slot.thread,
+        )
+    }
+
+    /// Check that changes can be applied at `slot` without corrupting the executed operations
+    fn check_changes(
+        &self,
+        changes: &ExecutedOpsChanges,
+        slot: Slot,
+    ) -> Result<(), ExecutedOpsError> {
+        if let Some(last_applied_slot) = self.last_applied_slot {
+            if slot < last_applied_slot {
+                return Err(ExecutedOpsError::OutOfOrderSlot {
+                    slot,
+                    last_applied_slot,
                 });
+            }
+        }
+        for (op_id, (op_exec_success, op_slot)) in changes {
+            self.check_expiry_slot(op_id, op_slot, slot)?;
+            if !self.ops.contains(op_id) {
+                continue;
+            }
+            let same_slot = self
+                .ops
+                .sorted
+                .get(op_slot)
+                .map_or(false, |ids| ids.contains(op_id));
+            let same_status = self
+                .op_exec_status
+                .get(op_id)
+                .map_or(true, |status| status == op_exec_success);
+            if !same_slot || !same_status {
+                return Err(ExecutedOpsError::ConflictingOperation(*op_id));
+            }
+        }
+        Ok(())
+    }
 
-            self.op_exec_status.insert(op_id, op_exec_success);
+    /// Check that an operation expiring at `expiry_slot` could be executed at `slot`:
+    /// its expiry period must be within `operation_validity_periods` periods from `slot`
+    fn check_expiry_slot(
+        &self,
+        op_id: &OperationId,
+        expiry_slot: &Slot,
+        slot: Slot,
+    ) -> Result<(), ExecutedOpsError> {
+        if expiry_slot.thread >= self.config.thread_count {
+            return Err(ExecutedOpsError::InvalidExpiryThread {
+                op_id: *op_id,
+                expiry_slot: *expiry_slot,
+            });
         }
+        let last_valid_period = slot
+            .period
+            .saturating_add(self.config.operation_validity_periods);
+        if expiry_slot.period < slot.period || expiry_slot.period > last_valid_period {
+            return Err(ExecutedOpsError::ExpirySlotOutOfWindow {
+                op_id: *op_id,
+                expiry_slot: *expiry_slot,
+                slot,
+            });
+        }
+        Ok(())
+    }
 
-        self.prune(slot);
+    /// Get the execution status of an executed operation, if known
+    /// (true: success, false: fail)
+    pub fn get_op_exec_status(&self, op_id: &OperationId) -> Option<bool> {
+        self.op_exec_status.get(op_id).copied()
+    }
+
+    /// Get the expiry slot of an executed operation, the last slot at which it is valid, if known.
+    /// Its record is pruned once the periods following it leave the retention window.
+    pub fn get_expiry_slot(&self, op_id: &OperationId) -> Option<Slot> {
+        self.ops.get_slot(op_id)
     }
 
     /// Check if an operation was executed
@@ -138,81 +474,230 @@ impl ExecutedOps {
         self.ops.contains(op_id)
     }
 
-    /// Prune all operations that expire strictly before `slot`
-    fn prune(&mut self, slot: Slot) {
-        let kept = self.sorted_ops.split_off(&slot);
-        let removed = std::mem::take(&mut self.sorted_ops);
-        for (_, ids) in removed {
-            for op_id in ids {
-                self.ops.remove(&op_id);
-                self.op_exec_status.remove(&op_id);
-                self.hash ^= *op_id.get_hash();
-            }
+    /// Check which operations of a batch were executed.
+    /// Bit `i` of the result is set if `op_ids[i]` was executed.
+    pub fn contains_multi(&self, op_ids: &[OperationId]) -> BitVec {
+        self.ops.contains_multi(op_ids)
+    }
+
+    /// Prune the operations that expire strictly before `slot`, at most `max_ops` of them if set
+    fn prune(&mut self, slot: Slot, max_ops: Option<u64>) -> u64 {
+        let start = Instant::now();
+        let mut pruned_count: u64 = 0;
+        let pruned = match max_ops {
+            Some(max_ops) => self.ops.prune_batch(slot, max_ops),
+            None => self.ops.prune(slot),
+        };
+        let op_exec_status = Arc::make_mut(&mut self.op_exec_status);
+        for op_id in pruned.values().flatten() {
+            op_exec_status.remove(op_id);
+            pruned_count += 1;
         }
-        self.sorted_ops = kept;
+        let elapsed_micros = start.elapsed().as_micros().try_into().unwrap_or(u64::MAX);
+        self.metrics
+            .pruned_ops_count
+            .fetch_add(pruned_count, Ordering::Relaxed);
+        self.metrics
+            .last_pruned_ops_count
+            .store(pruned_count, Ordering::Relaxed);
+        self.metrics
+            .prune_time_micros
+            .fetch_add(elapsed_micros, Ordering::Relaxed);
+        pruned_count
     }
 
     /// Get a part of the executed operations.
     /// Used exclusively by the bootstrap server.
     ///
+    /// The part starts strictly after the operation of the cursor, so streaming resumes correctly
+    /// even if its slot was pruned in the meantime.
+    /// Operations are added until the approximate serialized size of the part reaches
+    /// `bootstrap_part_size_bytes`, splitting a slot if needed, or until the part holds
+    /// `bootstrap_part_size` slots. A part always holds at least one operation.
+    /// Its cumulative hash is chained to `previous_cumulative_hash`, the one of the previous part.
+    ///
     /// # Returns
     /// A tuple containing the data and the next executed ops streaming step
     pub fn get_executed_ops_part(
         &self,
-        cursor: StreamingStep<Slot>,
-    ) -> (BTreeMap<Slot, PreHashSet<OperationId>>, StreamingStep<Slot>) {
-        let mut ops_part = BTreeMap::new();
-        let left_bound = match cursor {
-            StreamingStep::Started => Unbounded,
-            StreamingStep::Ongoing(slot) => Excluded(slot),
-            StreamingStep::Finished(_) => return (ops_part, cursor),
-        };
-        let mut ops_part_last_slot: Option<Slot> = None;
-        for (slot, ids) in self.sorted_ops.range((left_bound, Unbounded)) {
-            if ops_part.len() < self.config.bootstrap_part_size as usize {
-                ops_part.insert(*slot, ids.clone());
-                ops_part_last_slot = Some(*slot);
-            } else {
-                break;
-            }
-        }
-        if let Some(last_slot) = ops_part_last_slot {
-            (ops_part, StreamingStep::Ongoing(last_slot))
-        } else {
-            (ops_part, StreamingStep::Finished(None))
+        cursor: ExecutedOpsStreamingStep,
+        previous_cumulative_hash: &Hash,
+    ) -> (ExecutedOpsPart, ExecutedOpsStreamingStep) {
+        self.freeze()
+            .get_executed_ops_part(cursor, previous_cumulative_hash)
+    }
+
+    /// Take a frozen view of the executed operations, which keeps serving their current state
+    /// while changes are applied.
+    /// Cheap: the view shares the maps, which are only copied by the next change.
+    pub fn freeze(&self) -> FrozenExecutedOps {
+        FrozenExecutedOps {
+            config: self.config.clone(),
+            sorted: self.ops.sorted.clone(),
+            op_exec_status: self.op_exec_status.clone(),
+            slot: self.last_applied_slot,
+            metrics: self.metrics.clone(),
         }
     }
 
     /// Set a part of the executed operations.
     /// Used exclusively by the bootstrap client.
-    /// Takes the data returned from `get_executed_ops_part` as input.
+    /// Takes the data returned from `get_executed_ops_part` as input,
+    /// which is expected to have been checked with `ExecutedOpsPart::verify` beforehand.
+    ///
+    /// The operations of a slot are merged with the ones already known for it, as the changes
+    /// streamed alongside the parts may have inserted some of them already.
+    /// Known execution statuses are recorded as well.
     ///
     /// # Returns
-    /// The next executed ops streaming step
-    pub fn set_executed_ops_part(
-        &mut self,
-        part: BTreeMap<Slot, PreHashSet<OperationId>>,
-    ) -> StreamingStep<Slot> {
-        self.sorted_ops.extend(part.clone());
-        self.extend_and_compute_hash(part.iter().flat_map(|(_, ids)| ids));
-        if let Some(slot) = self.sorted_ops.last_key_value().map(|(slot, _)| slot) {
-            StreamingStep::Ongoing(*slot)
-        } else {
-            StreamingStep::Finished(None)
+    /// The next executed ops streaming step: the last operation of the part, as the known slots
+    /// can go beyond it because of the streamed changes
+    pub fn set_executed_ops_part(&mut self, part: ExecutedOpsPart) -> ExecutedOpsStreamingStep {
+        let part = part.ops;
+        if !part.is_empty() {
+            self.metrics
+                .bootstrap_parts_received
+                .fetch_add(1, Ordering::Relaxed);
+            self.metrics.bootstrap_ops_received.fetch_add(
+                part.values().map(|ids| ids.len() as u64).sum(),
+                Ordering::Relaxed,
+            );
         }
+        let op_exec_status = Arc::make_mut(&mut self.op_exec_status);
+        for (op_id, status) in part.values().flatten() {
+            if let Some(status) = status {
+                op_exec_status.insert(*op_id, *status);
+            }
+        }
+        self.ops.set_part(
+            part.into_iter()
+                .map(|(slot, ids)| (slot, ids.into_keys()))
+                .collect(),
+        )
+    }
+
+    /// Write a snapshot of the executed operations.
+    ///
+    /// The snapshot contains a format version, the version of the hash scheme, the accumulated
+    /// hash and the executed operations by slot, followed by a checksum of all the previous bytes.
+    pub fn export_snapshot<W: Write>(&self, mut writer: W) -> Result<(), IoError> {
+        let ops_serializer = ExecutedOpsSerializer::new();
+        let mut buffer = Vec::with_capacity(
+            EXECUTED_OPS_SNAPSHOT_MAGIC.len()
+                + varint_size(EXECUTED_OPS_SNAPSHOT_VERSION)
+                + varint_size(self.hash_scheme().version())
+                + 2 * HASH_SIZE_BYTES
+                + ops_serializer.serialized_size(self.ops.sorted.as_ref()),
+        );
+        buffer.extend(EXECUTED_OPS_SNAPSHOT_MAGIC);
+        let serialize_error = |err: SerializeError| IoError::new(ErrorKind::Other, err);
+        U64VarIntSerializer::new()
+            .serialize(&EXECUTED_OPS_SNAPSHOT_VERSION, &mut buffer)
+            .map_err(serialize_error)?;
+        U64VarIntSerializer::new()
+            .serialize(&self.hash_scheme().version(), &mut buffer)
+            .map_err(serialize_error)?;
+        buffer.extend(self.ops.hash.to_bytes());
+        ops_serializer
+            .serialize(self.ops.sorted.as_ref(), &mut buffer)
+            .map_err(serialize_error)?;
+        let checksum = Hash::compute_from(&buffer);
+        buffer.extend(checksum.to_bytes());
+        writer.write_all(&buffer)?;
+        writer.flush()
+    }
+
+    /// Restore the executed operations from a snapshot written by `export_snapshot`.
+    ///
+    /// Fails if the checksum, the format version or the accumulated hash do not match.
+    /// The hash is checked with the scheme of the snapshot, then migrated to the one of `config`.
+    pub fn import_snapshot<R: Read>(
+        config: ExecutedOpsConfig,
+        mut reader: R,
+    ) -> Result<Self, IoError> {
+        let invalid_data = |msg: &str| IoError::new(ErrorKind::InvalidData, msg.to_string());
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer)?;
+        if buffer.len() < EXECUTED_OPS_SNAPSHOT_MAGIC.len() + HASH_SIZE_BYTES
+            || !buffer.starts_with(EXECUTED_OPS_SNAPSHOT_MAGIC)
+        {
+            return Err(invalid_data("not an executed ops snapshot"));
+        }
+        let (content, checksum) = buffer.split_at(buffer.len() - HASH_SIZE_BYTES);
+        if Hash::compute_from(content).to_bytes().as_slice() != checksum {
+            return Err(invalid_data("executed ops snapshot checksum mismatch"));
+        }
+        let content = &content[EXECUTED_OPS_SNAPSHOT_MAGIC.len()..];
+        let version_deserializer =
+            U64VarIntDeserializer::new(Included(u64::MIN), Included(u64::MAX));
+        let (rest, version) = version_deserializer
+            .deserialize::<DeserializeError>(content)
+            .map_err(|_| invalid_data("invalid executed ops snapshot version"))?;
+        let (rest, hash_scheme) = match version {
+            0 => (rest, ExecutedOpsHashScheme::Xor),
+            EXECUTED_OPS_SNAPSHOT_VERSION => {
+                let (rest, scheme_version) = version_deserializer
+                    .deserialize::<DeserializeError>(rest)
+                    .map_err(|_| invalid_data("invalid executed ops snapshot hash scheme"))?;
+                let hash_scheme = ExecutedOpsHashScheme::from_version(scheme_version)
+                    .ok_or_else(|| invalid_data("unsupported executed ops snapshot hash scheme"))?;
+                (rest, hash_scheme)
+            }
+            _ => return Err(invalid_data("unsupported executed ops snapshot version")),
+        };
+        if rest.len() < HASH_SIZE_BYTES {
+            return Err(invalid_data("truncated executed ops snapshot"));
+        }
+        let (hash_bytes, rest) = rest.split_at(HASH_SIZE_BYTES);
+        let expected_hash = Hash::from_bytes(
+            hash_bytes
+                .try_into()
+                .map_err(|_| invalid_data("invalid executed ops snapshot hash"))?,
+        );
+        // the whole set is stored at once so the streaming bounds do not apply
+        let (rest, sorted_ops) =
+            ExecutedOpsDeserializer::new(config.thread_count, u64::MAX, u64::MAX)
+                .deserialize::<DeserializeError>(rest)
+                .map_err(|_| invalid_data("invalid executed ops snapshot content"))?;
+        if !rest.is_empty() {
+            return Err(invalid_data("trailing bytes in executed ops snapshot"));
+        }
+        let target_scheme = config.hash_scheme;
+        let mut executed_ops = ExecutedOps::new_with_hash(
+            ExecutedOpsConfig {
+                hash_scheme,
+                ..config
+            },
+            sorted_ops,
+        );
+        if executed_ops.ops.hash != expected_hash {
+            return Err(invalid_data("executed ops snapshot hash mismatch"));
+        }
+        executed_ops.set_hash_scheme(target_scheme);
+        Ok(executed_ops)
+    }
+}
+
+/// Config of the executed ops used by the tests, overridden field by field where needed
+#[cfg(test)]
+fn test_config() -> ExecutedOpsConfig {
+    ExecutedOpsConfig {
+        thread_count: 2,
+        bootstrap_part_size: 10,
+        bootstrap_part_size_bytes: 1_000,
+        retention_periods: 0,
+        operation_validity_periods: 100,
+        pruning_mode: ExecutedOpsPruningMode::Immediate,
+        hash_scheme: ExecutedOpsHashScheme::Xor,
     }
 }
 
 #[test]
 fn test_executed_ops_xor_computing() {
     use massa_models::prehash::PreHashMap;
-    use massa_models::secure_share::Id;
 
     // initialize the executed ops config
-    let config = ExecutedOpsConfig {
-        thread_count: 2,
-        bootstrap_part_size: 10,
-    };
+    let config = test_config();
 
     // initialize the executed ops and executed ops changes
     let mut a = ExecutedOps::new(config.clone());
@@ -243,38 +728,803 @@ fn test_executed_ops_xor_computing() {
         );
     }
 
-    // apply change_b to a which performs a.hash ^ $(change_b)
+    // apply change_b to a which performs a.ops.hash ^ $(change_b)
     let apply_slot = Slot {
         period: 0,
         thread: 0,
     };
-    a.apply_changes(change_a, apply_slot);
-    a.apply_changes(change_b, apply_slot);
-    c.apply_changes(change_c, apply_slot);
+    a.apply_changes(change_a, apply_slot).unwrap();
+    a.apply_changes(change_b, apply_slot).unwrap();
+    c.apply_changes(change_c, apply_slot).unwrap();
 
-    // check that a.hash ^ $(change_b) = c.hash
-    assert_eq!(a.hash, c.hash, "'a' and 'c' hashes are not equal");
+    // check that a.ops.hash ^ $(change_b) = c.ops.hash
+    assert_eq!(a.ops.hash, c.ops.hash, "'a' and 'c' hashes are not equal");
 
     // prune every element
     let prune_slot = Slot {
         period: 20,
         thread: 0,
     };
-    a.apply_changes(PreHashMap::default(), prune_slot);
-    a.prune(prune_slot);
+    a.apply_changes(PreHashMap::default(), prune_slot).unwrap();
+    a.prune_now(prune_slot);
 
     // at this point the hash should have been XORed with itself
     assert_eq!(
-        a.hash,
-        Hash::from_bytes(EXECUTED_OPS_HASH_INITIAL_BYTES),
+        a.ops.hash,
+        Hash::from_bytes(&[0; HASH_SIZE_BYTES]),
         "'a' was not reset to its initial value"
     );
 }
 
+#[test]
+fn test_executed_ops_stats() {
+    let config = test_config();
+    let mut server = ExecutedOps::new(config.clone());
+    let mut changes = PreHashMap::default();
+    for i in 0u8..10 {
+        changes.insert(
+            OperationId::new(Hash::compute_from(&[i])),
+            (true, Slot::new(i as u64 / 2, 0)),
+        );
+    }
+    server.apply_changes(changes, Slot::new(0, 0)).unwrap();
+    server
+        .apply_changes(PreHashMap::default(), Slot::new(2, 0))
+        .unwrap();
+    let stats = server.get_stats();
+    assert_eq!(stats.inserted_ops_count, 10);
+    assert_eq!(stats.pruned_ops_count, 4);
+    assert_eq!(stats.last_pruned_ops_count, 4);
+    assert_eq!(stats.tracked_ops_count, 6);
+
+    let mut client = ExecutedOps::new(config);
+    stream_executed_ops(&mut server, &mut client, |_, _| {});
+    let server_stats = server.get_stats();
+    let client_stats = client.get_stats();
+    assert_eq!(server_stats.bootstrap_ops_sent, 6);
+    assert_eq!(client_stats.bootstrap_ops_received, 6);
+    assert_eq!(
+        server_stats.bootstrap_parts_sent,
+        client_stats.bootstrap_parts_received
+    );
+    assert_eq!(client_stats.tracked_ops_count, 6);
+}
+
+#[test]
+fn test_executed_ops_contains_multi() {
+    use massa_models::prehash::PreHashMap;
+
+    let mut executed_ops = ExecutedOps::new(test_config());
+    let op_ids: Vec<OperationId> = (0u8..4)
+        .map(|i| OperationId::new(Hash::compute_from(&[i])))
+        .collect();
+    let mut changes = PreHashMap::default();
+    changes.insert(op_ids[1], (true, Slot::new(5, 0)));
+    changes.insert(op_ids[3], (false, Slot::new(5, 1)));
+    executed_ops
+        .apply_changes(changes, Slot::new(0, 0))
+        .unwrap();
+
+    let executed = executed_ops.contains_multi(&op_ids);
+    assert_eq!(executed.len(), op_ids.len());
+    for (op_id, is_executed) in op_ids.iter().zip(executed.iter()) {
+        assert_eq!(*is_executed, executed_ops.contains(op_id));
+    }
+    assert!(executed_ops.contains_multi(&[]).is_empty());
+}
+
+#[cfg(test)]
+fn stream_executed_ops(
+    server: &mut ExecutedOps,
+    client: &mut ExecutedOps,
+    mut between_parts: impl FnMut(&mut ExecutedOps, &mut ExecutedOps),
+) {
+    let mut cursor = StreamingStep::Started;
+    let mut cumulative_hash = ExecutedOpsPart::initial_cumulative_hash();
+    loop {
+        let (part, server_step) = server.get_executed_ops_part(cursor, &cumulative_hash);
+        if let StreamingStep::Finished(_) = server_step {
+            break;
+        }
+        part.verify(&cumulative_hash).unwrap();
+        cumulative_hash = part.cumulative_hash;
+        cursor = client.set_executed_ops_part(part);
+        between_parts(server, client);
+    }
+}
+
+#[test]
+fn test_executed_ops_streaming_round_trip() {
+    use massa_models::prehash::PreHashMap;
+
+    let config = ExecutedOpsConfig {
+        bootstrap_part_size: 3,
+        ..test_config()
+    };
+    let mut server = ExecutedOps::new(config.clone());
+    let mut changes = PreHashMap::default();
+    for i in 0u8..40 {
+        changes.insert(
+            OperationId::new(Hash::compute_from(&[i])),
+            (i % 3 == 0, Slot::new(i as u64 / 4, i % 2)),
+        );
+    }
+    server.apply_changes(changes, Slot::new(0, 0)).unwrap();
+
+    let mut client = ExecutedOps::new(config);
+    stream_executed_ops(&mut server, &mut client, |_, _| {});
+    assert_eq!(client.ops.sorted, server.ops.sorted);
+    assert_eq!(client.ops, server.ops);
+    assert_eq!(client.ops.hash, server.ops.hash);
+    assert_eq!(client.op_exec_status, server.op_exec_status);
+}
+
+#[test]
+fn test_executed_ops_streaming_with_prune_and_changes() {
+    use massa_models::prehash::PreHashMap;
+
+    let config = ExecutedOpsConfig {
+        bootstrap_part_size: 2,
+        ..test_config()
+    };
+    let mut server = ExecutedOps::new(config.clone());
+    let mut changes = PreHashMap::default();
+    for i in 0u8..40 {
+        changes.insert(
+            OperationId::new(Hash::compute_from(&[i])),
+            (true, Slot::new(i as u64 / 2, 0)),
+        );
+    }
+    server.apply_changes(changes, Slot::new(0, 0)).unwrap();
+
+    let mut client = ExecutedOps::new(config);
+    let mut step = 0u8;
+    stream_executed_ops(&mut server, &mut client, |server, client| {
+        // the server finalizes a slot between each part: the part cursor can get pruned,
+        // and the same changes are streamed to the client
+        step += 1;
+        let mut changes = PreHashMap::default();
+        changes.insert(
+            OperationId::new(Hash::compute_from(&[100, step])),
+            (true, Slot::new(30, 0)),
+        );
+        let prune_slot = Slot::new(step as u64 * 2, 0);
+        server.apply_changes(changes.clone(), prune_slot).unwrap();
+        client.apply_changes(changes, prune_slot).unwrap();
+    });
+    assert_eq!(client.ops.sorted, server.ops.sorted);
+    assert_eq!(client.ops, server.ops);
+    assert_eq!(client.ops.hash, server.ops.hash);
+}
+
+#[test]
+fn test_executed_ops_part_byte_budget() {
+    use crate::slot_indexed_set::SLOT_ENTRY_MAX_OVERHEAD_BYTES;
+    use massa_models::prehash::PreHashMap;
+
+    // room for a slot entry and two more operations of the same slot
+    let config = ExecutedOpsConfig {
+        bootstrap_part_size_bytes: SLOT_ENTRY_MAX_OVERHEAD_BYTES + 3 * PART_OP_ENTRY_MAX_BYTES,
+        ..test_config()
+    };
+    let mut server = ExecutedOps::new(config.clone());
+    let mut changes = PreHashMap::default();
+    for i in 0u8..10 {
+        changes.insert(
+            OperationId::new(Hash::compute_from(&[i])),
+            (true, Slot::new(if i < 7 { 1 } else { 2 }, 0)),
+        );
+    }
+    server.apply_changes(changes, Slot::new(0, 0)).unwrap();
+
+    let mut client = ExecutedOps::new(config);
+    let mut cursor = StreamingStep::Started;
+    let initial_hash = ExecutedOpsPart::initial_cumulative_hash();
+    let mut part_count = 0;
+    loop {
+        let (part, server_step) = server.get_executed_ops_part(cursor, &initial_hash);
+        if let StreamingStep::Finished(_) = server_step {
+            break;
+        }
+        // the slot of 7 operations is split, and never more than 3 operations are sent at once
+        assert!(part.ops.values().map(|ids| ids.len()).sum::<usize>() <= 3);
+        cursor = client.set_executed_ops_part(part);
+        part_count += 1;
+    }
+    assert_eq!(part_count, 4);
+    assert_eq!(client.ops.sorted, server.ops.sorted);
+    assert_eq!(client.ops.hash, server.ops.hash);
+}
+
+#[test]
+fn test_executed_ops_snapshot_round_trip() {
+    use massa_models::prehash::PreHashMap;
+
+    let config = test_config();
+    let mut executed_ops = ExecutedOps::new(config.clone());
+    let mut changes = PreHashMap::default();
+    for i in 0u8..20 {
+        changes.insert(
+            OperationId::new(Hash::compute_from(&[i])),
+            (true, Slot::new(i as u64, i % 2)),
+        );
+    }
+    executed_ops
+        .apply_changes(changes, Slot::new(0, 0))
+        .unwrap();
+
+    let mut snapshot = Vec::new();
+    executed_ops.export_snapshot(&mut snapshot).unwrap();
+    let restored = ExecutedOps::import_snapshot(config.clone(), snapshot.as_slice()).unwrap();
+    assert_eq!(restored.ops.hash, executed_ops.ops.hash);
+    assert_eq!(restored.ops.sorted, executed_ops.ops.sorted);
+    assert_eq!(restored.ops, executed_ops.ops);
+
+    // any corruption is detected by the checksum
+    let middle = snapshot.len() / 2;
+    snapshot[middle] ^= 1;
+    let err = ExecutedOps::import_snapshot(config, snapshot.as_slice()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_executed_ops_part_serialization() {
+    use massa_models::prehash::PreHashMap;
+
+    let mut executed_ops = ExecutedOps::new(ExecutedOpsConfig {
+        bootstrap_part_size_bytes: 10_000,
+        ..test_config()
+    });
+    let mut changes = PreHashMap::default();
+    for i in 0u8..10 {
+        changes.insert(
+            OperationId::new(Hash::compute_from(&[i])),
+            (i % 2 == 0, Slot::new(i as u64 / 3, 1)),
+        );
+    }
+    executed_ops
+        .apply_changes(changes, Slot::new(0, 0))
+        .unwrap();
+    // an operation whose status is unknown, as after a snapshot import
+    let unknown_op = OperationId::new(Hash::compute_from(&[10]));
+    Arc::make_mut(&mut executed_ops.op_exec_status).remove(&unknown_op);
+    executed_ops.ops.ids.insert(unknown_op, Slot::new(4, 0));
+    Arc::make_mut(&mut executed_ops.ops.sorted)
+        .entry(Slot::new(4, 0))
+        .or_default()
+        .insert(unknown_op);
+
+    let initial_hash = ExecutedOpsPart::initial_cumulative_hash();
+    let (part, _) = executed_ops.get_executed_ops_part(StreamingStep::Started, &initial_hash);
+    assert_eq!(part.ops.values().map(|ids| ids.len()).sum::<usize>(), 11);
+    assert_eq!(part.ops[&Slot::new(4, 0)][&unknown_op], None);
+    let mut buffer = Vec::new();
+    ExecutedOpsPartSerializer::new()
+        .serialize(&part, &mut buffer)
+        .unwrap();
+    let (rest, deserialized) = ExecutedOpsPartDeserializer::new(2, 10, 10, 100)
+        .deserialize::<DeserializeError>(&buffer)
+        .unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(deserialized, part);
+    assert!(deserialized.verify(&initial_hash).is_ok());
+
+    // a part altered in transit, or chained to another stream, fails verification
+    let mut altered = deserialized.clone();
+    altered
+        .ops
+        .get_mut(&Slot::new(4, 0))
+        .unwrap()
+        .insert(unknown_op, Some(true));
+    assert!(altered.verify(&initial_hash).is_err());
+    assert!(deserialized.verify(&part.cumulative_hash).is_err());
+
+    // a part holding more operations than the budget is rejected, even within the per-slot bounds
+    assert!(ExecutedOpsPartDeserializer::new(2, 10, 10, 11)
+        .deserialize::<DeserializeError>(&buffer)
+        .is_ok());
+    assert!(ExecutedOpsPartDeserializer::new(2, 10, 10, 10)
+        .deserialize::<DeserializeError>(&buffer)
+        .is_err());
+
+    // another part format version is rejected
+    buffer[0] = 0;
+    assert!(ExecutedOpsPartDeserializer::new(2, 10, 10, 100)
+        .deserialize::<DeserializeError>(&buffer)
+        .is_err());
+}
+
+#[test]
+fn test_executed_ops_serialized_size() {
+    use massa_models::prehash::PreHashMap;
+
+    // slot periods and id counts on both sides of the var int length boundaries
+    let mut sorted: BTreeMap<Slot, PreHashSet<OperationId>> = BTreeMap::new();
+    let mut part_ops = ExecutedOpsPartOps::new();
+    for (slot_index, period) in [0u64, 127, 128, 1 << 20, u64::MAX].into_iter().enumerate() {
+        let ids: PreHashMap<OperationId, Option<bool>> = (0u32..130 * slot_index as u32)
+            .map(|i| {
+                let op_id = OperationId::new(Hash::compute_from(
+                    &[&i.to_be_bytes()[..], &period.to_be_bytes()[..]].concat(),
+                ));
+                (op_id, [None, Some(true), Some(false)][i as usize % 3])
+            })
+            .collect();
+        sorted.insert(Slot::new(period, 1), ids.keys().copied().collect());
+        part_ops.insert(Slot::new(period, 1), ids);
+    }
+
+    let serializer = ExecutedOpsSerializer::new();
+    let size = serializer.serialized_size(&sorted);
+    let mut buffer = Vec::new();
+    serializer.serialize(&sorted, &mut buffer).unwrap();
+    assert_eq!(buffer.len(), size);
+    // the buffer is allocated once, to the exact size
+    assert_eq!(buffer.capacity(), size);
+
+    let part = ExecutedOpsPart::new(part_ops, &ExecutedOpsPart::initial_cumulative_hash());
+    let serializer = ExecutedOpsPartSerializer::new();
+    let size = serializer.serialized_size(&part);
+    let mut buffer = Vec::new();
+    serializer.serialize(&part, &mut buffer).unwrap();
+    assert_eq!(buffer.len(), size);
+    assert_eq!(buffer.capacity(), size);
+    assert_eq!(
+        serializer.ops_serialized_size(&part.ops),
+        size - varint_size(EXECUTED_OPS_PART_VERSION) - 2 * HASH_SIZE_BYTES
+    );
+}
+
+#[test]
+fn test_executed_ops_canonical_serialization() {
+    let config = ExecutedOpsConfig {
+        bootstrap_part_size_bytes: 10_000,
+        ..test_config()
+    };
+    let slot = Slot::new(1, 0);
+    let mut op_ids: Vec<OperationId> = (0u8..8)
+        .map(|i| OperationId::new(Hash::compute_from(&[i])))
+        .collect();
+
+    // identical sets built in different orders serialize identically
+    let mut ascending = ExecutedOps::new(config.clone());
+    let mut descending = ExecutedOps::new(config);
+    op_ids.sort_unstable();
+    for op_id in &op_ids {
+        ascending.ops.insert(slot, *op_id);
+    }
+    for op_id in op_ids.iter().rev() {
+        descending.ops.insert(slot, *op_id);
+    }
+    let mut ascending_buffer = Vec::new();
+    let mut descending_buffer = Vec::new();
+    ExecutedOpsSerializer::new()
+        .serialize(ascending.ops.sorted.as_ref(), &mut ascending_buffer)
+        .unwrap();
+    ExecutedOpsSerializer::new()
+        .serialize(descending.ops.sorted.as_ref(), &mut descending_buffer)
+        .unwrap();
+    assert_eq!(ascending_buffer, descending_buffer);
+    let (rest, _) = ExecutedOpsDeserializer::new(2, 10, 10)
+        .require_canonical()
+        .deserialize::<DeserializeError>(&ascending_buffer)
+        .unwrap();
+    assert!(rest.is_empty());
+
+    // unsorted ids are only accepted when the canonical form is not required
+    let mut unsorted_buffer = Vec::new();
+    let u64_serializer = U64VarIntSerializer::new();
+    u64_serializer.serialize(&1, &mut unsorted_buffer).unwrap();
+    SlotSerializer::new()
+        .serialize(&slot, &mut unsorted_buffer)
+        .unwrap();
+    u64_serializer
+        .serialize(&(op_ids.len() as u64), &mut unsorted_buffer)
+        .unwrap();
+    for op_id in op_ids.iter().rev() {
+        OperationIdSerializer::new()
+            .serialize(op_id, &mut unsorted_buffer)
+            .unwrap();
+    }
+    let (_, deserialized) = ExecutedOpsDeserializer::new(2, 10, 10)
+        .deserialize::<DeserializeError>(&unsorted_buffer)
+        .unwrap();
+    assert_eq!(&deserialized, ascending.ops.sorted.as_ref());
+    assert!(ExecutedOpsDeserializer::new(2, 10, 10)
+        .require_canonical()
+        .deserialize::<DeserializeError>(&unsorted_buffer)
+        .is_err());
+}
+
+#[test]
+fn test_executed_ops_in_range() {
+    use massa_models::prehash::PreHashMap;
+
+    let mut executed_ops = ExecutedOps::new(test_config());
+    let mut changes = PreHashMap::default();
+    for i in 0u8..10 {
+        changes.insert(
+            OperationId::new(Hash::compute_from(&[i])),
+            (true, Slot::new(i as u64 / 2, 0)),
+        );
+    }
+    executed_ops
+        .apply_changes(changes, Slot::new(0, 0))
+        .unwrap();
+
+    let in_range: Vec<_> = executed_ops
+        .get_ops_in_range(Slot::new(1, 0), Slot::new(2, 0))
+        .collect();
+    assert_eq!(in_range.len(), 4);
+    assert!(in_range
+        .iter()
+        .all(|(slot, _)| **slot >= Slot::new(1, 0) && **slot <= Slot::new(2, 0)));
+    assert!(in_range.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    assert_eq!(
+        executed_ops.count_ops_in_range(Slot::new(1, 0), Slot::new(2, 0)),
+        4
+    );
+    assert_eq!(
+        executed_ops.count_ops_in_range(Slot::new(0, 0), Slot::new(10, 1)),
+        10
+    );
+
+    // an empty or reversed interval yields nothing
+    assert_eq!(
+        executed_ops
+            .get_ops_in_range(Slot::new(2, 1), Slot::new(2, 1))
+            .count(),
+        0
+    );
+    assert_eq!(
+        executed_ops
+            .get_ops_in_range(Slot::new(3, 0), Slot::new(1, 0))
+            .count(),
+        0
+    );
+    assert_eq!(
+        executed_ops.count_ops_in_range(Slot::new(3, 0), Slot::new(1, 0)),
+        0
+    );
+}
+
+#[test]
+fn test_executed_ops_apply_changes_guards() {
+    use massa_models::prehash::PreHashMap;
+
+    let mut executed_ops = ExecutedOps::new(test_config());
+    let op_ids: Vec<OperationId> = (0u8..3)
+        .map(|i| OperationId::new(Hash::compute_from(&[i])))
+        .collect();
+    let mut changes = PreHashMap::default();
+    changes.insert(op_ids[0], (true, Slot::new(5, 0)));
+    changes.insert(op_ids[1], (false, Slot::new(6, 1)));
+    executed_ops
+        .apply_changes(changes.clone(), Slot::new(2, 0))
+        .unwrap();
+    let hash = executed_ops.ops.hash;
+
+    // replaying the same changes leaves the hash untouched
+    executed_ops
+        .apply_changes(changes.clone(), Slot::new(2, 0))
+        .unwrap();
+    assert_eq!(executed_ops.ops.hash, hash);
+    assert!(executed_ops.verify_integrity().is_ok());
+
+    // an operation replayed with another slot or status is rejected
+    let mut moved = PreHashMap::default();
+    moved.insert(op_ids[0], (true, Slot::new(7, 0)));
+    assert_eq!(
+        executed_ops.apply_changes(moved, Slot::new(2, 0)),
+        Err(ExecutedOpsError::ConflictingOperation(op_ids[0]))
+    );
+    let mut flipped = PreHashMap::default();
+    flipped.insert(op_ids[1], (true, Slot::new(6, 1)));
+    assert_eq!(
+        executed_ops.apply_changes(flipped, Slot::new(3, 0)),
+        Err(ExecutedOpsError::ConflictingOperation(op_ids[1]))
+    );
+
+    // changes at an earlier slot are rejected without modifying anything
+    let mut late = PreHashMap::default();
+    late.insert(op_ids[2], (true, Slot::new(6, 0)));
+    assert_eq!(
+        executed_ops.apply_changes(late, Slot::new(1, 1)),
+        Err(ExecutedOpsError::OutOfOrderSlot {
+            slot: Slot::new(1, 1),
+            last_applied_slot: Slot::new(2, 0),
+        })
+    );
+    assert!(!executed_ops.contains(&op_ids[2]));
+    assert_eq!(executed_ops.ops.hash, hash);
+    assert!(executed_ops.verify_integrity().is_ok());
+
+    // an operation that could not be executed at the slot of the changes is rejected
+    for expiry_slot in [Slot::new(1, 0), Slot::new(104, 0)] {
+        let mut out_of_window = PreHashMap::default();
+        out_of_window.insert(op_ids[2], (true, expiry_slot));
+        assert_eq!(
+            executed_ops.apply_changes(out_of_window, Slot::new(3, 0)),
+            Err(ExecutedOpsError::ExpirySlotOutOfWindow {
+                op_id: op_ids[2],
+                expiry_slot,
+                slot: Slot::new(3, 0),
+            })
+        );
+    }
+    let mut unknown_thread = PreHashMap::default();
+    unknown_thread.insert(op_ids[2], (true, Slot::new(5, 2)));
+    assert_eq!(
+        executed_ops.apply_changes(unknown_thread, Slot::new(3, 0)),
+        Err(ExecutedOpsError::InvalidExpiryThread {
+            op_id: op_ids[2],
+            expiry_slot: Slot::new(5, 2),
+        })
+    );
+    // the last period of the window is accepted
+    let mut last_period = PreHashMap::default();
+    last_period.insert(op_ids[2], (true, Slot::new(103, 1)));
+    executed_ops
+        .apply_changes(last_period, Slot::new(3, 0))
+        .unwrap();
+    assert!(executed_ops.contains(&op_ids[2]));
+}
+
+#[test]
+fn test_executed_ops_deferred_pruning() {
+    use massa_models::prehash::PreHashMap;
+
+    let config = |pruning_mode| ExecutedOpsConfig {
+        retention_periods: 1,
+        pruning_mode,
+        ..test_config()
+    };
+    let mut changes = PreHashMap::default();
+    for i in 0u8..8 {
+        changes.insert(
+            OperationId::new(Hash::compute_from(&[i])),
+            (true, Slot::new(1 + i as u64 / 4, 0)),
+        );
+    }
+    let mut immediate = ExecutedOps::new(config(ExecutedOpsPruningMode::Immediate));
+    let mut deferred = ExecutedOps::new(config(ExecutedOpsPruningMode::Deferred {
+        max_ops_per_batch: 3,
+    }));
+    for executed_ops in [&mut immediate, &mut deferred] {
+        executed_ops
+            .apply_changes(changes.clone(), Slot::new(1, 0))
+            .unwrap();
+        executed_ops
+            .apply_changes(PreHashMap::default(), Slot::new(2, 0))
+            .unwrap();
+        // operations are kept one period after their expiry slot
+        assert_eq!(executed_ops.len(), 8);
+        executed_ops
+            .apply_changes(PreHashMap::default(), Slot::new(4, 0))
+            .unwrap();
+    }
+    assert!(immediate.is_empty());
+    assert_eq!(immediate.prune_pending(), 0);
+
+    // deferred pruning removes the expired operations in batches
+    assert_eq!(deferred.len(), 8);
+    let mut manual = deferred.clone();
+    for (pruned, remaining) in [(3, 5), (3, 2), (2, 0), (0, 0)] {
+        assert_eq!(deferred.prune_pending(), pruned);
+        assert_eq!(deferred.len(), remaining);
+        assert!(deferred.verify_integrity().is_ok());
+    }
+    assert_eq!(deferred.ops.hash, immediate.ops.hash);
+
+    // pending operations can be pruned at once
+    assert_eq!(manual.prune_now(Slot::new(4, 0)), 8);
+    assert_eq!(manual.ops.hash, immediate.ops.hash);
+}
+
+#[test]
+fn test_executed_ops_expiry_slot() {
+    use massa_models::prehash::PreHashMap;
+
+    let mut executed_ops = ExecutedOps::new(ExecutedOpsConfig {
+        retention_periods: 1,
+        ..test_config()
+    });
+    let op_ids: Vec<OperationId> = (0u8..3)
+        .map(|i| OperationId::new(Hash::compute_from(&[i])))
+        .collect();
+    let mut changes = PreHashMap::default();
+    changes.insert(op_ids[0], (true, Slot::new(2, 0)));
+    changes.insert(op_ids[1], (false, Slot::new(5, 1)));
+    executed_ops
+        .apply_changes(changes, Slot::new(1, 0))
+        .unwrap();
+    assert_eq!(
+        executed_ops.get_expiry_slot(&op_ids[0]),
+        Some(Slot::new(2, 0))
+    );
+    assert_eq!(
+        executed_ops.get_expiry_slot(&op_ids[1]),
+        Some(Slot::new(5, 1))
+    );
+    assert_eq!(executed_ops.get_expiry_slot(&op_ids[2]), None);
+
+    // the expiry slot is forgotten along with the pruned operation
+    executed_ops
+        .apply_changes(PreHashMap::default(), Slot::new(4, 0))
+        .unwrap();
+    assert_eq!(executed_ops.get_expiry_slot(&op_ids[0]), None);
+    assert_eq!(
+        executed_ops.get_expiry_slot(&op_ids[1]),
+        Some(Slot::new(5, 1))
+    );
+    assert_eq!(executed_ops.ops.ids.len(), 1);
+    assert!(executed_ops.verify_integrity().is_ok());
+}
+
+#[test]
+fn test_executed_ops_verify_integrity() {
+    use massa_models::prehash::PreHashMap;
+
+    let mut executed_ops = ExecutedOps::new(test_config());
+    assert!(executed_ops.verify_integrity().is_ok());
+    let mut changes = PreHashMap::default();
+    for i in 0u8..10 {
+        changes.insert(
+            OperationId::new(Hash::compute_from(&[i])),
+            (true, Slot::new(i as u64 + 1, 0)),
+        );
+    }
+    executed_ops
+        .apply_changes(changes, Slot::new(1, 0))
+        .unwrap();
+    assert!(executed_ops.verify_integrity().is_ok());
+    assert_eq!(executed_ops.recompute_hash(), executed_ops.ops.hash);
+
+    // an operation indexed by slot but missing from the set
+    let mut diverged = executed_ops.clone();
+    let removed = *diverged.ops.ids.keys().next().unwrap();
+    diverged.ops.ids.remove(&removed);
+    assert!(diverged.verify_integrity().is_err());
+
+    // an operation in the set but not indexed by slot
+    let mut diverged = executed_ops.clone();
+    diverged
+        .ops
+        .ids
+        .insert(OperationId::new(Hash::compute_from(&[42])), Slot::new(1, 0));
+    assert!(diverged.verify_integrity().is_err());
+
+    // an operation set at another slot than the one it is indexed by
+    let mut diverged = executed_ops.clone();
+    let moved = *diverged.ops.ids.keys().next().unwrap();
+    diverged.ops.ids.insert(moved, Slot::new(42, 0));
+    assert!(diverged.verify_integrity().is_err());
+
+    // a stale accumulated hash
+    let mut diverged = executed_ops;
+    diverged.ops.hash = Hash::compute_from(&[42]);
+    assert!(diverged.verify_integrity().is_err());
+}
+
+#[test]
+fn test_executed_ops_dump() {
+    use massa_models::prehash::PreHashMap;
+
+    let mut executed_ops = ExecutedOps::new(test_config());
+    let mut changes = PreHashMap::default();
+    for i in 0u8..9 {
+        changes.insert(
+            OperationId::new(Hash::compute_from(&[i])),
+            (true, Slot::new(i as u64 / 3 + 1, 0)),
+        );
+    }
+    executed_ops
+        .apply_changes(changes, Slot::new(1, 0))
+        .unwrap();
+
+    let dump = executed_ops.get_dump(1, 5);
+    assert_eq!(dump.last_applied_slot, Some(Slot::new(1, 0)));
+    assert_eq!(dump.hash, executed_ops.ops.hash);
+    assert_eq!(dump.total_slot_count, 3);
+    assert_eq!(dump.total_op_count, 9);
+    assert_eq!(dump.offset, 1);
+    let slots: Vec<Slot> = dump.slots.iter().map(|slot_dump| slot_dump.slot).collect();
+    assert_eq!(slots, vec![Slot::new(2, 0), Slot::new(3, 0)]);
+    for slot_dump in &dump.slots {
+        assert_eq!(slot_dump.op_count, 3);
+        assert!(slot_dump.op_ids.windows(2).all(|ids| ids[0] < ids[1]));
+    }
+
+    // a page past the end is empty
+    assert!(executed_ops.get_dump(3, 5).slots.is_empty());
+}
+
+#[test]
+fn test_executed_ops_batch_hash() {
+    // large enough for the hashes to be XORed in parallel
+    let ops: Vec<(Slot, OperationId)> = (0u32..10_000)
+        .map(|i| {
+            (
+                Slot::new(i as u64 % 7 + 1, 0),
+                OperationId::new(Hash::compute_from(&i.to_be_bytes())),
+            )
+        })
+        .collect();
+    let mut sequential = SlotIndexedSet::new();
+    for (slot, op_id) in &ops {
+        sequential.insert(*slot, *op_id);
+    }
+
+    let mut batched = SlotIndexedSet::new();
+    let (first, second) = ops.split_at(6_000);
+    assert_eq!(batched.extend(first.iter().copied()), 6_000);
+    // ids already in the set do not change the hash
+    assert_eq!(batched.extend(ops[..100].iter().copied()), 0);
+    assert_eq!(batched.extend(second.iter().copied()), 4_000);
+    assert_eq!(batched, sequential);
+    assert_eq!(batched.hash, batched.recompute_hash());
+}
+
+#[test]
+fn test_executed_ops_hash_scheme() {
+    use massa_models::prehash::PreHashMap;
+
+    let xor_config = test_config();
+    let lt_hash_config = ExecutedOpsConfig {
+        hash_scheme: ExecutedOpsHashScheme::LtHash,
+        ..xor_config.clone()
+    };
+    let changes: PreHashMap<OperationId, (bool, Slot)> = (0u32..5_000)
+        .map(|i| {
+            (
+                OperationId::new(Hash::compute_from(&i.to_be_bytes())),
+                (true, Slot::new(i as u64 % 10 + 1, (i % 2) as u8)),
+            )
+        })
+        .collect();
+
+    let mut xor = ExecutedOps::new(xor_config.clone());
+    xor.apply_changes(changes.clone(), Slot::new(0, 0)).unwrap();
+    // large enough for the hashes to be accumulated in parallel
+    let mut batched = ExecutedOps::new(lt_hash_config.clone());
+    batched
+        .apply_changes(changes.clone(), Slot::new(0, 0))
+        .unwrap();
+    let mut sequential = SlotIndexedSet::with_hash_scheme(ExecutedOpsHashScheme::LtHash);
+    for (op_id, (_, slot)) in &changes {
+        sequential.insert(*slot, *op_id);
+    }
+    assert_eq!(batched.ops, sequential);
+    assert_ne!(batched.ops.hash, xor.ops.hash);
+    assert_eq!(
+        batched.compute_hash(ExecutedOpsHashScheme::Xor),
+        xor.ops.hash
+    );
+    assert!(batched.verify_integrity().is_ok());
+
+    // the migration recomputes the hash from the operations
+    let mut migrated = xor.clone();
+    assert!(migrated.set_hash_scheme(ExecutedOpsHashScheme::LtHash));
+    assert!(!migrated.set_hash_scheme(ExecutedOpsHashScheme::LtHash));
+    assert_eq!(migrated.ops.hash, batched.ops.hash);
+
+    // a snapshot is checked with its own scheme and migrated to the configured one
+    let mut snapshot = Vec::new();
+    xor.export_snapshot(&mut snapshot).unwrap();
+    let restored =
+        ExecutedOps::import_snapshot(lt_hash_config.clone(), snapshot.as_slice()).unwrap();
+    assert_eq!(restored.hash_scheme(), ExecutedOpsHashScheme::LtHash);
+    assert_eq!(restored.ops.hash, batched.ops.hash);
+
+    // pruning everything brings the hash back to the one of the empty set
+    batched.prune_now(Slot::new(20, 0));
+    assert!(batched.is_empty());
+    assert_eq!(batched.ops.hash, ExecutedOps::new(lt_hash_config).ops.hash);
+}
+
 /// `ExecutedOps` Serializer
 pub struct ExecutedOpsSerializer {
-    slot_serializer: SlotSerializer,
-    u64_serializer: U64VarIntSerializer,
+    inner: SlotIndexedSetSerializer<OperationId, OperationIdSerializer>,
 }
 
 impl Default for ExecutedOpsSerializer {
@@ -287,53 +1537,166 @@ impl ExecutedOpsSerializer {
     /// Create a new `ExecutedOps` Serializer
     pub fn new() -> ExecutedOpsSerializer {
         ExecutedOpsSerializer {
-            slot_serializer: SlotSerializer::new(),
-            u64_serializer: U64VarIntSerializer::new(),
+            inner: SlotIndexedSetSerializer::new(OperationIdSerializer::new()),
         }
     }
+
+    /// Exact serialized size of `value`, to serialize it into a buffer allocated once
+    pub fn serialized_size(&self, value: &BTreeMap<Slot, PreHashSet<OperationId>>) -> usize {
+        self.inner.serialized_size(value, OPERATION_ID_SIZE_BYTES)
+    }
 }
 
 impl Serializer<BTreeMap<Slot, PreHashSet<OperationId>>> for ExecutedOpsSerializer {
+    /// The buffer is grown once to the exact serialized size, if its capacity is too small
     fn serialize(
         &self,
         value: &BTreeMap<Slot, PreHashSet<OperationId>>,
         buffer: &mut Vec<u8>,
     ) -> Result<(), SerializeError> {
-        // executed ops length
+        buffer.reserve_exact(self.serialized_size(value));
+        self.inner.serialize(value, buffer)
+    }
+}
+
+/// Serializer for `ExecutedOpsPart`
+pub struct ExecutedOpsPartSerializer {
+    slot_serializer: SlotSerializer,
+    u64_serializer: U64VarIntSerializer,
+    operation_id_serializer: OperationIdSerializer,
+    status_serializer: OptionSerializer<bool, BoolSerializer>,
+    hash_serializer: HashSerializer,
+}
+
+impl Default for ExecutedOpsPartSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExecutedOpsPartSerializer {
+    /// Create a new `ExecutedOpsPart` Serializer
+    pub fn new() -> Self {
+        Self {
+            slot_serializer: SlotSerializer::new(),
+            u64_serializer: U64VarIntSerializer::new(),
+            operation_id_serializer: OperationIdSerializer::new(),
+            status_serializer: OptionSerializer::new(BoolSerializer::new()),
+            hash_serializer: HashSerializer::new(),
+        }
+    }
+
+    /// Exact serialized size of a part, to serialize it into a buffer allocated once
+    pub fn serialized_size(&self, value: &ExecutedOpsPart) -> usize {
+        varint_size(EXECUTED_OPS_PART_VERSION)
+            + self.ops_serialized_size(&value.ops)
+            + 2 * HASH_SIZE_BYTES
+    }
+
+    /// Exact serialized size of the operations of a part
+    fn ops_serialized_size(&self, value: &ExecutedOpsPartOps) -> usize {
+        varint_size(value.len() as u64)
+            + value
+                .iter()
+                .map(|(slot, ids)| {
+                    slot_size(slot)
+                        + varint_size(ids.len() as u64)
+                        + ids
+                            .values()
+                            .map(|status| OPERATION_ID_SIZE_BYTES + 1 + status.map_or(0, |_| 1))
+                            .sum::<usize>()
+                })
+                .sum::<usize>()
+    }
+
+    /// Serialize the operations of a part, which is also what its part hash is computed from
+    fn serialize_ops(
+        &self,
+        value: &ExecutedOpsPartOps,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        // slots length
         self.u64_serializer
             .serialize(&(value.len() as u64), buffer)?;
-        // executed ops
+        // sorting buffer, reused by all the slots
+        let mut sorted_ids: Vec<(&OperationId, &Option<bool>)> = Vec::with_capacity(
+            value
+                .values()
+                .map(|ids| ids.len())
+                .max()
+                .unwrap_or_default(),
+        );
         for (slot, ids) in value {
             // slot
             self.slot_serializer.serialize(slot, buffer)?;
             // slot ids length
             self.u64_serializer.serialize(&(ids.len() as u64), buffer)?;
-            // slots ids
-            for op_id in ids {
-                buffer.extend(op_id.to_bytes());
+            // slot ids and their execution status, sorted by id for a canonical encoding
+            sorted_ids.clear();
+            sorted_ids.extend(ids.iter());
+            sorted_ids.sort_unstable_by_key(|(op_id, _)| *op_id);
+            for (op_id, status) in sorted_ids.iter() {
+                self.operation_id_serializer.serialize(op_id, buffer)?;
+                self.status_serializer.serialize(status, buffer)?;
             }
         }
         Ok(())
     }
 }
 
-/// Deserializer for `ExecutedOps`
-pub struct ExecutedOpsDeserializer {
+impl Serializer<ExecutedOpsPart> for ExecutedOpsPartSerializer {
+    /// The buffer is grown once to the exact serialized size, if its capacity is too small:
+    /// parts can weigh several megabytes
+    fn serialize(
+        &self,
+        value: &ExecutedOpsPart,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        buffer.reserve_exact(self.serialized_size(value));
+        // part format version
+        self.u64_serializer
+            .serialize(&EXECUTED_OPS_PART_VERSION, buffer)?;
+        // operations
+        self.serialize_ops(&value.ops, buffer)?;
+        // part hashes
+        self.hash_serializer.serialize(&value.part_hash, buffer)?;
+        self.hash_serializer
+            .serialize(&value.cumulative_hash, buffer)?;
+        Ok(())
+    }
+}
+
+/// Deserializer for `ExecutedOpsPart`
+pub struct ExecutedOpsPartDeserializer {
+    version_deserializer: U64VarIntDeserializer,
     operation_id_deserializer: OperationIdDeserializer,
+    status_deserializer: OptionDeserializer<bool, BoolDeserializer>,
     slot_deserializer: SlotDeserializer,
     ops_length_deserializer: U64VarIntDeserializer,
     slot_ops_length_deserializer: U64VarIntDeserializer,
+    hash_deserializer: HashDeserializer,
+    max_part_ops: u64,
+    require_canonical: bool,
 }
 
-impl ExecutedOpsDeserializer {
-    /// Create a new deserializer for `ExecutedOps`
+impl ExecutedOpsPartDeserializer {
+    /// Create a new deserializer for `ExecutedOpsPart`.
+    /// The slots and their operations are bounded by `max_executed_ops_length` and
+    /// `max_operations_per_block`, and the operations of all the slots by `max_part_ops`.
     pub fn new(
         thread_count: u8,
         max_executed_ops_length: u64,
         max_operations_per_block: u64,
-    ) -> ExecutedOpsDeserializer {
-        ExecutedOpsDeserializer {
+        max_part_ops: u64,
+    ) -> Self {
+        Self {
+            // only the current part format version is accepted
+            version_deserializer: U64VarIntDeserializer::new(
+                Included(EXECUTED_OPS_PART_VERSION),
+                Included(EXECUTED_OPS_PART_VERSION),
+            ),
             operation_id_deserializer: OperationIdDeserializer::new(),
+            status_deserializer: OptionDeserializer::new(BoolDeserializer::new()),
             slot_deserializer: SlotDeserializer::new(
                 (Included(u64::MIN), Included(u64::MAX)),
                 (Included(0), Excluded(thread_count)),
@@ -346,43 +1709,206 @@ impl ExecutedOpsDeserializer {
                 Included(u64::MIN),
                 Included(max_operations_per_block),
             ),
+            hash_deserializer: HashDeserializer::new(),
+            max_part_ops,
+            require_canonical: false,
         }
     }
+
+    /// Reject parts that are not in canonical form (sorted operation ids within each slot)
+    pub fn require_canonical(mut self) -> Self {
+        self.require_canonical = true;
+        self
+    }
 }
 
-impl Deserializer<BTreeMap<Slot, PreHashSet<OperationId>>> for ExecutedOpsDeserializer {
+impl Deserializer<ExecutedOpsPart> for ExecutedOpsPartDeserializer {
     fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
         &self,
         buffer: &'a [u8],
-    ) -> IResult<&'a [u8], BTreeMap<Slot, PreHashSet<OperationId>>, E> {
+    ) -> IResult<&'a [u8], ExecutedOpsPart, E> {
+        // operations the part can still hold, taken from as the slot lengths are read so that
+        // a part declaring too many operations is rejected before they are parsed
+        let ops_budget = Cell::new(self.max_part_ops);
         context(
-            "ExecutedOps",
-            length_count(
-                context("ExecutedOps length", |input| {
-                    self.ops_length_deserializer.deserialize(input)
+            "ExecutedOpsPart",
+            tuple((
+                context("ExecutedOpsPart version", |input| {
+                    self.version_deserializer.deserialize(input)
                 }),
-                context(
-                    "slot operations",
-                    tuple((
-                        context("slot", |input| self.slot_deserializer.deserialize(input)),
-                        length_count(
-                            context("slot operations length", |input| {
-                                self.slot_ops_length_deserializer.deserialize(input)
-                            }),
-                            context("operation id", |input| {
-                                self.operation_id_deserializer.deserialize(input)
-                            }),
+                verify(
+                    length_count(
+                        context("ExecutedOpsPart length", |input| {
+                            self.ops_length_deserializer.deserialize(input)
+                        }),
+                        context(
+                            "slot operations",
+                            tuple((
+                                context("slot", |input| self.slot_deserializer.deserialize(input)),
+                                length_count(
+                                    context("slot operations length", |input| {
+                                        let (rest, length) =
+                                            self.slot_ops_length_deserializer.deserialize(input)?;
+                                        match ops_budget.get().checked_sub(length) {
+                                            Some(remaining) => {
+                                                ops_budget.set(remaining);
+                                                Ok((rest, length))
+                                            }
+                                            None => Err(nom::Err::Error(E::add_context(
+                                                input,
+                                                "ExecutedOpsPart operations budget exceeded",
+                                                E::from_error_kind(
+                                                    input,
+                                                    nom::error::ErrorKind::TooLarge,
+                                                ),
+                                            ))),
+                                        }
+                                    }),
+                                    tuple((
+                                        context("operation id", |input| {
+                                            self.operation_id_deserializer.deserialize(input)
+                                        }),
+                                        context("operation execution status", |input| {
+                                            self.status_deserializer.deserialize(input)
+                                        }),
+                                    )),
+                                ),
+                            )),
                         ),
-                    )),
+                    ),
+                    |slots: &Vec<(Slot, Vec<(OperationId, Option<bool>)>)>| {
+                        !self.require_canonical || is_canonical(slots, |(op_id, _)| op_id)
+                    },
                 ),
+                context("part hash", |input| {
+                    self.hash_deserializer.deserialize(input)
+                }),
+                context("cumulative hash", |input| {
+                    self.hash_deserializer.deserialize(input)
+                }),
+            )),
+        )
+        .map(
+            |(_, operations, part_hash, cumulative_hash)| ExecutedOpsPart {
+                ops: operations
+                    .into_iter()
+                    .map(|(slot, ids)| (slot, ids.into_iter().collect()))
+                    .collect(),
+                part_hash,
+                cumulative_hash,
+            },
+        )
+        .parse(buffer)
+    }
+}
+
+/// Serializer for the cursor of the executed ops bootstrap streaming
+#[derive(Clone)]
+pub struct ExecutedOpsCursorSerializer {
+    slot_serializer: SlotSerializer,
+    operation_id_serializer: OperationIdSerializer,
+}
+
+impl Default for ExecutedOpsCursorSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExecutedOpsCursorSerializer {
+    /// Create a new serializer for the executed ops streaming cursor
+    pub fn new() -> Self {
+        Self {
+            slot_serializer: SlotSerializer::new(),
+            operation_id_serializer: OperationIdSerializer::new(),
+        }
+    }
+}
+
+impl Serializer<(Slot, OperationId)> for ExecutedOpsCursorSerializer {
+    fn serialize(
+        &self,
+        (slot, op_id): &(Slot, OperationId),
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.slot_serializer.serialize(slot, buffer)?;
+        self.operation_id_serializer.serialize(op_id, buffer)?;
+        Ok(())
+    }
+}
+
+/// Deserializer for the cursor of the executed ops bootstrap streaming
+#[derive(Clone)]
+pub struct ExecutedOpsCursorDeserializer {
+    slot_deserializer: SlotDeserializer,
+    operation_id_deserializer: OperationIdDeserializer,
+}
+
+impl ExecutedOpsCursorDeserializer {
+    /// Create a new deserializer for the executed ops streaming cursor
+    pub fn new(thread_count: u8) -> Self {
+        Self {
+            slot_deserializer: SlotDeserializer::new(
+                (Included(u64::MIN), Included(u64::MAX)),
+                (Included(0), Excluded(thread_count)),
             ),
+            operation_id_deserializer: OperationIdDeserializer::new(),
+        }
+    }
+}
+
+impl Deserializer<(Slot, OperationId)> for ExecutedOpsCursorDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], (Slot, OperationId), E> {
+        context(
+            "ExecutedOps cursor",
+            tuple((
+                context("slot", |input| self.slot_deserializer.deserialize(input)),
+                context("operation id", |input| {
+                    self.operation_id_deserializer.deserialize(input)
+                }),
+            )),
         )
-        .map(|operations| {
-            operations
-                .into_iter()
-                .map(|(slot, ids)| (slot, ids.into_iter().collect()))
-                .collect()
-        })
         .parse(buffer)
     }
 }
+
+/// Deserializer for `ExecutedOps`
+pub struct ExecutedOpsDeserializer {
+    inner: SlotIndexedSetDeserializer<OperationId, OperationIdDeserializer>,
+}
+
+impl ExecutedOpsDeserializer {
+    /// Create a new deserializer for `ExecutedOps`
+    pub fn new(
+        thread_count: u8,
+        max_executed_ops_length: u64,
+        max_operations_per_block: u64,
+    ) -> ExecutedOpsDeserializer {
+        ExecutedOpsDeserializer {
+            inner: SlotIndexedSetDeserializer::new(
+                OperationIdDeserializer::new(),
+                thread_count,
+                max_executed_ops_length,
+                max_operations_per_block,
+            ),
+        }
+    }
+
+    /// Reject input that is not in canonical form (sorted operation ids within each slot)
+    pub fn require_canonical(mut self) -> Self {
+        self.inner = self.inner.require_canonical();
+        self
+    }
+}
+
+impl Deserializer<BTreeMap<Slot, PreHashSet<OperationId>>> for ExecutedOpsDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], BTreeMap<Slot, PreHashSet<OperationId>>, E> {
+        context("ExecutedOps", |input| self.inner.deserialize(input)).parse(buffer)
+    }
+}
@@ -0,0 +1,138 @@
+//! Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! This file defines a read-only view layering speculative executed operations changes
+//! over the final executed operations, without copying either of them.
+
+use crate::{ExecutedOps, ExecutedOpsChanges};
+use bitvec::vec::BitVec;
+use massa_models::{operation::OperationId, slot::Slot};
+
+/// Read-only view of the executed operations: the final `ExecutedOps` with layers of
+/// uncommitted `ExecutedOpsChanges` on top of it.
+/// Lookups resolve through the layers, from the newest to the oldest, then through the final state.
+pub struct ExecutedOpsView<'a> {
+    /// Final executed operations
+    base: &'a ExecutedOps,
+    /// Uncommitted changes, oldest first
+    layers: Vec<&'a ExecutedOpsChanges>,
+}
+
+impl<'a> ExecutedOpsView<'a> {
+    /// Creates a view of the final executed operations without any change on top of it
+    pub fn new(base: &'a ExecutedOps) -> Self {
+        ExecutedOpsView {
+            base,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Add a layer of changes on top of the view.
+    /// Layers must be pushed from the oldest to the newest.
+    pub fn push_layer(&mut self, changes: &'a ExecutedOpsChanges) {
+        self.layers.push(changes);
+    }
+
+    /// Check if an operation was executed, either in a layer or in the final state
+    pub fn contains(&self, op_id: &OperationId) -> bool {
+        self.layers
+            .iter()
+            .rev()
+            .any(|changes| changes.contains_key(op_id))
+            || self.base.contains(op_id)
+    }
+
+    /// Check which operations of a batch were executed.
+    /// Bit `i` of the result is set if `op_ids[i]` was executed.
+    /// The final state is only queried for the operations absent from all layers.
+    pub fn contains_multi(&self, op_ids: &[OperationId]) -> BitVec {
+        let mut executed: BitVec = op_ids
+            .iter()
+            .map(|op_id| {
+                self.layers
+                    .iter()
+                    .rev()
+                    .any(|changes| changes.contains_key(op_id))
+            })
+            .collect();
+        let remaining: Vec<usize> = executed.iter_zeros().collect();
+        if !remaining.is_empty() {
+            let remaining_ids: Vec<OperationId> =
+                remaining.iter().map(|index| op_ids[*index]).collect();
+            for index in self.base.contains_multi(&remaining_ids).iter_ones() {
+                executed.set(remaining[index], true);
+            }
+        }
+        executed
+    }
+
+    /// Get the expiry slot of an executed operation, if known.
+    /// The slot of the newest layer containing the operation prevails.
+    pub fn get_expiry_slot(&self, op_id: &OperationId) -> Option<Slot> {
+        self.layers
+            .iter()
+            .rev()
+            .find_map(|changes| changes.get(op_id).map(|(_, slot)| *slot))
+            .or_else(|| self.base.get_expiry_slot(op_id))
+    }
+
+    /// Get the execution status of an executed operation, if known
+    /// (true: success, false: fail).
+    /// The status of the newest layer containing the operation prevails.
+    pub fn get_op_exec_status(&self, op_id: &OperationId) -> Option<bool> {
+        self.layers
+            .iter()
+            .rev()
+            .find_map(|changes| changes.get(op_id).map(|(status, _)| *status))
+            .or_else(|| self.base.get_op_exec_status(op_id))
+    }
+}
+
+#[test]
+fn test_executed_ops_view_layers() {
+    use crate::{ExecutedOpsConfig, ExecutedOpsHashScheme, ExecutedOpsPruningMode};
+    use massa_hash::Hash;
+    use massa_models::{prehash::PreHashMap, secure_share::Id};
+
+    let op_ids: Vec<OperationId> = (0u8..4)
+        .map(|i| OperationId::new(Hash::compute_from(&[i])))
+        .collect();
+    let mut executed_ops = ExecutedOps::new(ExecutedOpsConfig {
+        thread_count: 2,
+        bootstrap_part_size: 10,
+        bootstrap_part_size_bytes: 1_000,
+        retention_periods: 0,
+        operation_validity_periods: 100,
+        pruning_mode: ExecutedOpsPruningMode::Immediate,
+        hash_scheme: ExecutedOpsHashScheme::Xor,
+    });
+    let mut final_changes = PreHashMap::default();
+    final_changes.insert(op_ids[0], (true, Slot::new(5, 0)));
+    executed_ops
+        .apply_changes(final_changes, Slot::new(0, 0))
+        .unwrap();
+
+    let mut old_layer: ExecutedOpsChanges = PreHashMap::default();
+    old_layer.insert(op_ids[1], (true, Slot::new(5, 0)));
+    let mut new_layer: ExecutedOpsChanges = PreHashMap::default();
+    new_layer.insert(op_ids[1], (false, Slot::new(5, 0)));
+    new_layer.insert(op_ids[2], (false, Slot::new(5, 1)));
+
+    let mut view = ExecutedOpsView::new(&executed_ops);
+    assert!(view.contains(&op_ids[0]));
+    assert!(!view.contains(&op_ids[1]));
+    view.push_layer(&old_layer);
+    view.push_layer(&new_layer);
+
+    let executed = view.contains_multi(&op_ids);
+    for (op_id, is_executed) in op_ids.iter().zip(executed.iter()) {
+        assert_eq!(*is_executed, view.contains(op_id));
+    }
+    assert_eq!(executed.count_ones(), 3);
+    assert!(!view.contains(&op_ids[3]));
+    assert_eq!(view.get_op_exec_status(&op_ids[0]), Some(true));
+    assert_eq!(view.get_op_exec_status(&op_ids[1]), Some(false));
+    assert_eq!(view.get_op_exec_status(&op_ids[3]), None);
+    assert_eq!(view.get_expiry_slot(&op_ids[0]), Some(Slot::new(5, 0)));
+    assert_eq!(view.get_expiry_slot(&op_ids[2]), Some(Slot::new(5, 1)));
+    assert_eq!(view.get_expiry_slot(&op_ids[3]), None);
+}
@@ -0,0 +1,132 @@
+//! Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! This file defines a frozen view of the executed operations, used to serve all the bootstrap
+//! parts of a session out of one consistent state while new slots are finalized.
+
+use crate::{
+    executed_ops::{ExecutedOpsMetrics, PART_OP_ENTRY_MAX_BYTES},
+    slot_indexed_set::get_sorted_part,
+    ExecutedOpsConfig, ExecutedOpsPart, ExecutedOpsPartOps, ExecutedOpsStreamingStep,
+};
+use massa_hash::Hash;
+use massa_models::{operation::OperationId, prehash::PreHashSet, slot::Slot};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+/// Immutable view of the executed operations as they were when it was taken
+/// with `ExecutedOps::freeze`.
+/// It shares the maps of `ExecutedOps`, which copies them on its first change while the view is alive.
+#[derive(Debug, Clone)]
+pub struct FrozenExecutedOps {
+    /// Executed operations configuration
+    pub(crate) config: ExecutedOpsConfig,
+    /// Executed operations by slot
+    pub(crate) sorted: Arc<BTreeMap<Slot, PreHashSet<OperationId>>>,
+    /// Execution status of operations (true: success, false: fail)
+    pub(crate) op_exec_status: Arc<HashMap<OperationId, bool>>,
+    /// Slot of the last changes applied before the view was taken
+    pub(crate) slot: Option<Slot>,
+    /// Activity counters of the executed operations the view was taken from
+    pub(crate) metrics: Arc<ExecutedOpsMetrics>,
+}
+
+impl FrozenExecutedOps {
+    /// Slot of the last changes applied before the view was taken, if any
+    pub fn slot(&self) -> Option<Slot> {
+        self.slot
+    }
+
+    /// Returns the number of executed operations in the view
+    pub fn len(&self) -> usize {
+        self.sorted.values().map(|ids| ids.len()).sum()
+    }
+
+    /// Check the view emptiness
+    pub fn is_empty(&self) -> bool {
+        self.sorted.values().all(|ids| ids.is_empty())
+    }
+
+    /// Get a part of the executed operations of the view.
+    /// See `ExecutedOps::get_executed_ops_part`.
+    ///
+    /// # Returns
+    /// A tuple containing the data and the next executed ops streaming step
+    pub fn get_executed_ops_part(
+        &self,
+        cursor: ExecutedOpsStreamingStep,
+        previous_cumulative_hash: &Hash,
+    ) -> (ExecutedOpsPart, ExecutedOpsStreamingStep) {
+        let (part, step) = get_sorted_part(
+            &self.sorted,
+            cursor,
+            self.config.bootstrap_part_size,
+            self.config.bootstrap_part_size_bytes,
+            PART_OP_ENTRY_MAX_BYTES,
+        );
+        let ops_part: ExecutedOpsPartOps = part
+            .into_iter()
+            .map(|(slot, ids)| {
+                let ids = ids
+                    .into_iter()
+                    .map(|op_id| (op_id, self.op_exec_status.get(&op_id).copied()))
+                    .collect();
+                (slot, ids)
+            })
+            .collect();
+        if !ops_part.is_empty() {
+            self.metrics
+                .record_part_sent(ops_part.values().map(|ids| ids.len() as u64).sum());
+        }
+        (
+            ExecutedOpsPart::new(ops_part, previous_cumulative_hash),
+            step,
+        )
+    }
+}
+
+#[test]
+fn test_frozen_executed_ops_consistency() {
+    use crate::{ExecutedOps, ExecutedOpsHashScheme, ExecutedOpsPruningMode};
+    use massa_models::{prehash::PreHashMap, secure_share::Id, streaming_step::StreamingStep};
+
+    let mut executed_ops = ExecutedOps::new(ExecutedOpsConfig {
+        thread_count: 2,
+        bootstrap_part_size: 10,
+        bootstrap_part_size_bytes: 10_000,
+        retention_periods: 0,
+        operation_validity_periods: 100,
+        pruning_mode: ExecutedOpsPruningMode::Immediate,
+        hash_scheme: ExecutedOpsHashScheme::Xor,
+    });
+    let mut changes = PreHashMap::default();
+    for i in 0u8..6 {
+        changes.insert(
+            OperationId::new(Hash::compute_from(&[i])),
+            (true, Slot::new(1 + i as u64 / 2, 0)),
+        );
+    }
+    executed_ops
+        .apply_changes(changes, Slot::new(1, 0))
+        .unwrap();
+    let frozen = executed_ops.freeze();
+    let initial_hash = ExecutedOpsPart::initial_cumulative_hash();
+    let (before, _) = frozen.get_executed_ops_part(StreamingStep::Started, &initial_hash);
+
+    // a prune and new operations after the freeze are not seen by the view
+    let mut changes = PreHashMap::default();
+    changes.insert(
+        OperationId::new(Hash::compute_from(&[6])),
+        (false, Slot::new(9, 0)),
+    );
+    executed_ops
+        .apply_changes(changes, Slot::new(3, 0))
+        .unwrap();
+    assert_eq!(executed_ops.len(), 3);
+    assert_eq!(frozen.len(), 6);
+    assert_eq!(frozen.slot(), Some(Slot::new(1, 0)));
+    let (after, _) = frozen.get_executed_ops_part(StreamingStep::Started, &initial_hash);
+    assert_eq!(after, before);
+    assert!(executed_ops.verify_integrity().is_ok());
+}
@@ -0,0 +1,212 @@
+//! Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! This file defines the accumulated hashes of a set of ids.
+//! An accumulated hash does not depend on the order in which the ids were inserted,
+//! and is updated incrementally as ids are inserted and removed.
+
+use crate::ExecutedOpsHashScheme;
+use massa_hash::{Hash, HASH_SIZE_BYTES};
+use rayon::prelude::*;
+
+/// Number of 16-bit lanes of an LtHash state
+const LT_HASH_LANES: usize = 1024;
+
+/// Number of hashes from which they are accumulated in parallel
+const PARALLEL_HASH_MIN_ITEMS: usize = 4096;
+
+/// Number of hashes accumulated sequentially by each parallel task
+const PARALLEL_HASH_CHUNK_SIZE: usize = 1024;
+
+/// Hash of a set of hashes, updated as hashes are inserted and removed
+pub trait HashAccumulator: Clone + PartialEq {
+    /// Add a hash to the set
+    fn insert(&mut self, hash: &Hash);
+    /// Remove a hash that was added to the set
+    fn remove(&mut self, hash: &Hash);
+    /// Add the hashes accumulated by `other` to the set
+    fn merge(&mut self, other: &Self);
+    /// Hash of the set
+    fn digest(&self) -> Hash;
+}
+
+/// XOR of the hashes.
+/// Cheap, but a set with a given hash can be forged by solving a linear system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XorHash(Hash);
+
+impl Default for XorHash {
+    fn default() -> Self {
+        XorHash(Hash::from_bytes(&[0; HASH_SIZE_BYTES]))
+    }
+}
+
+impl HashAccumulator for XorHash {
+    fn insert(&mut self, hash: &Hash) {
+        self.0 ^= *hash;
+    }
+
+    fn remove(&mut self, hash: &Hash) {
+        // XOR is its own inverse
+        self.0 ^= *hash;
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.0 ^= other.0;
+    }
+
+    fn digest(&self) -> Hash {
+        self.0
+    }
+}
+
+/// LtHash of the hashes: each hash is expanded to `LT_HASH_LANES` lanes of 16 bits,
+/// which are summed modulo 2^16.
+/// Forging a set with a given hash reduces to a hard lattice problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LtHash {
+    lanes: Vec<u16>,
+}
+
+impl Default for LtHash {
+    fn default() -> Self {
+        LtHash {
+            lanes: vec![0; LT_HASH_LANES],
+        }
+    }
+}
+
+impl LtHash {
+    /// Lanes of a hash, out of the extendable output of the hash function
+    fn expand(hash: &Hash) -> Vec<u16> {
+        let mut bytes = [0u8; 2 * LT_HASH_LANES];
+        blake3::Hasher::new()
+            .update(hash.to_bytes())
+            .finalize_xof()
+            .fill(&mut bytes);
+        bytes
+            .chunks_exact(2)
+            .map(|lane| u16::from_le_bytes([lane[0], lane[1]]))
+            .collect()
+    }
+}
+
+impl HashAccumulator for LtHash {
+    fn insert(&mut self, hash: &Hash) {
+        for (lane, value) in self.lanes.iter_mut().zip(LtHash::expand(hash)) {
+            *lane = lane.wrapping_add(value);
+        }
+    }
+
+    fn remove(&mut self, hash: &Hash) {
+        for (lane, value) in self.lanes.iter_mut().zip(LtHash::expand(hash)) {
+            *lane = lane.wrapping_sub(value);
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        for (lane, value) in self.lanes.iter_mut().zip(&other.lanes) {
+            *lane = lane.wrapping_add(*value);
+        }
+    }
+
+    fn digest(&self) -> Hash {
+        let bytes: Vec<u8> = self
+            .lanes
+            .iter()
+            .flat_map(|lane| lane.to_le_bytes())
+            .collect();
+        Hash::compute_from(&bytes)
+    }
+}
+
+/// Accumulated hash with the scheme of the configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccumulatedHash {
+    /// see `XorHash`
+    Xor(XorHash),
+    /// see `LtHash`
+    LtHash(LtHash),
+}
+
+impl AccumulatedHash {
+    /// Accumulated hash of an empty set
+    pub fn new(scheme: ExecutedOpsHashScheme) -> Self {
+        match scheme {
+            ExecutedOpsHashScheme::Xor => AccumulatedHash::Xor(XorHash::default()),
+            ExecutedOpsHashScheme::LtHash => AccumulatedHash::LtHash(LtHash::default()),
+        }
+    }
+
+    /// Accumulate the hashes of `items`, as given by `hash`, splitting large batches
+    /// in chunks that are accumulated in parallel.
+    /// The accumulation being commutative and associative, the result does not depend on the split.
+    pub fn from_items<V: Sync>(
+        scheme: ExecutedOpsHashScheme,
+        items: &[V],
+        hash: impl Fn(&V) -> Hash + Sync,
+    ) -> Self {
+        let accumulate_chunk = |chunk: &[V]| {
+            let mut accumulator = AccumulatedHash::new(scheme);
+            for item in chunk {
+                accumulator.insert(&hash(item));
+            }
+            accumulator
+        };
+        if items.len() < PARALLEL_HASH_MIN_ITEMS {
+            return accumulate_chunk(items);
+        }
+        items
+            .par_chunks(PARALLEL_HASH_CHUNK_SIZE)
+            .map(accumulate_chunk)
+            .reduce(
+                || AccumulatedHash::new(scheme),
+                |mut acc, chunk_accumulator| {
+                    acc.merge(&chunk_accumulator);
+                    acc
+                },
+            )
+    }
+
+    /// Scheme of the accumulated hash
+    pub fn scheme(&self) -> ExecutedOpsHashScheme {
+        match self {
+            AccumulatedHash::Xor(_) => ExecutedOpsHashScheme::Xor,
+            AccumulatedHash::LtHash(_) => ExecutedOpsHashScheme::LtHash,
+        }
+    }
+}
+
+impl HashAccumulator for AccumulatedHash {
+    fn insert(&mut self, hash: &Hash) {
+        match self {
+            AccumulatedHash::Xor(accumulator) => accumulator.insert(hash),
+            AccumulatedHash::LtHash(accumulator) => accumulator.insert(hash),
+        }
+    }
+
+    fn remove(&mut self, hash: &Hash) {
+        match self {
+            AccumulatedHash::Xor(accumulator) => accumulator.remove(hash),
+            AccumulatedHash::LtHash(accumulator) => accumulator.remove(hash),
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        match (self, other) {
+            (AccumulatedHash::Xor(accumulator), AccumulatedHash::Xor(other)) => {
+                accumulator.merge(other)
+            }
+            (AccumulatedHash::LtHash(accumulator), AccumulatedHash::LtHash(other)) => {
+                accumulator.merge(other)
+            }
+            _ => panic!("critical: accumulated hashes of different schemes cannot be merged"),
+        }
+    }
+
+    fn digest(&self) -> Hash {
+        match self {
+            AccumulatedHash::Xor(accumulator) => accumulator.digest(),
+            AccumulatedHash::LtHash(accumulator) => accumulator.digest(),
+        }
+    }
+}
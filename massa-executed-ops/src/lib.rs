@@ -3,12 +3,22 @@
 
 mod config;
 mod denunciations_changes;
+mod error;
 mod executed_denunciations;
 mod executed_ops;
+mod executed_ops_view;
+mod frozen_executed_ops;
+mod hash_scheme;
 mod ops_changes;
+mod slot_indexed_set;
 
 pub use config::*;
 pub use denunciations_changes::*;
+pub use error::*;
 pub use executed_denunciations::*;
 pub use executed_ops::*;
+pub use executed_ops_view::*;
+pub use frozen_executed_ops::*;
+pub use hash_scheme::*;
 pub use ops_changes::*;
+pub use slot_indexed_set::*;
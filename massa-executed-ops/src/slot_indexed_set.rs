@@ -0,0 +1,759 @@
+//! Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! This file defines a generic set of ids indexed by the slot at which they can be pruned,
+//! along with an accumulated hash of its content, computed with a configurable scheme.
+//! It holds the logic shared by the indexes of previously executed items.
+
+use crate::{
+    hash_scheme::{AccumulatedHash, HashAccumulator},
+    ExecutedOpsHashScheme,
+};
+use bitvec::vec::BitVec;
+use massa_hash::Hash;
+use massa_models::{
+    denunciation::DenunciationIndex,
+    operation::OperationId,
+    prehash::BuildHashMapper,
+    secure_share::Id,
+    slot::{Slot, SlotDeserializer, SlotSerializer},
+    streaming_step::{get_streaming_chunk, StreamingBudget, StreamingCost, StreamingStep},
+};
+use massa_serialization::{
+    Deserializer, SerializeError, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
+};
+use nom::{
+    combinator::verify,
+    error::{context, ContextError, ParseError},
+    multi::length_count,
+    sequence::tuple,
+    IResult, Parser,
+};
+use std::{
+    collections::{
+        hash_map::{Entry, RandomState},
+        BTreeMap, HashMap, HashSet,
+    },
+    fmt::Display,
+    hash::{BuildHasher, Hash as StdHash},
+    marker::PhantomData,
+    ops::Bound::{Excluded, Included, Unbounded},
+    sync::Arc,
+};
+
+/// Upper bound of the serialized size of a slot and of the length of its id list
+pub(crate) const SLOT_ENTRY_MAX_OVERHEAD_BYTES: u64 = 10 + 1 + 10;
+
+/// Serialized size of `value` as a u64 var int
+pub(crate) fn varint_size(value: u64) -> usize {
+    ((64 - value.leading_zeros()).max(1) as usize + 6) / 7
+}
+
+/// Serialized size of a slot
+pub(crate) fn slot_size(slot: &Slot) -> usize {
+    varint_size(slot.period) + 1
+}
+
+/// Id of the items of a `SlotIndexedSet`
+pub trait SlotIndexedId: Copy + Ord + StdHash + Display {
+    /// Builder of the hashers of the maps and sets of ids
+    type BuildHasher: BuildHasher + Default + Clone;
+
+    /// Hash of the id, accumulated in the hash of the set
+    fn id_hash(&self) -> Hash;
+}
+
+impl SlotIndexedId for OperationId {
+    type BuildHasher = BuildHashMapper<OperationId>;
+
+    fn id_hash(&self) -> Hash {
+        *self.get_hash()
+    }
+}
+
+impl SlotIndexedId for DenunciationIndex {
+    type BuildHasher = RandomState;
+
+    fn id_hash(&self) -> Hash {
+        self.get_hash()
+    }
+}
+
+/// Ids of a slot of a `SlotIndexedSet`
+pub type SlotIds<T> = HashSet<T, <T as SlotIndexedId>::BuildHasher>;
+
+/// A set of ids indexed by slot, with the accumulated hash of their hashes
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlotIndexedSet<T>
+where
+    T: SlotIndexedId,
+{
+    /// Ids by slot for better pruning complexity.
+    /// Shared with the frozen views of the set, and copied on write while one is alive.
+    pub sorted: Arc<BTreeMap<Slot, SlotIds<T>>>,
+    /// Ids with their slot, for better insertion and lookup complexity
+    pub ids: HashMap<T, Slot, T::BuildHasher>,
+    /// Accumulated hash of the ids
+    pub hash: Hash,
+    /// State of the accumulated hash, in the scheme of the set
+    accumulator: AccumulatedHash,
+}
+
+impl<T> Default for SlotIndexedSet<T>
+where
+    T: SlotIndexedId,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SlotIndexedSet<T>
+where
+    T: SlotIndexedId,
+{
+    /// Creates an empty `SlotIndexedSet`, with the XOR of the hashes of its ids
+    pub fn new() -> Self {
+        Self::with_hash_scheme(ExecutedOpsHashScheme::Xor)
+    }
+
+    /// Creates an empty `SlotIndexedSet`, hashing its ids with `scheme`
+    pub fn with_hash_scheme(scheme: ExecutedOpsHashScheme) -> Self {
+        let accumulator = AccumulatedHash::new(scheme);
+        Self {
+            sorted: Default::default(),
+            ids: HashMap::default(),
+            hash: accumulator.digest(),
+            accumulator,
+        }
+    }
+
+    /// Creates a `SlotIndexedSet` from ids indexed by slot and computes its hash with `scheme`
+    pub fn from_sorted(sorted: BTreeMap<Slot, SlotIds<T>>, scheme: ExecutedOpsHashScheme) -> Self
+    where
+        T: Sync,
+    {
+        let mut set = Self::with_hash_scheme(scheme);
+        set.extend(
+            sorted
+                .into_iter()
+                .flat_map(|(slot, ids)| ids.into_iter().map(move |id| (slot, id))),
+        );
+        set
+    }
+
+    /// Clear the set, keeping its hash scheme
+    pub fn reset(&mut self) {
+        self.sorted = Default::default();
+        self.ids.clear();
+        self.accumulator = AccumulatedHash::new(self.hash_scheme());
+        self.hash = self.accumulator.digest();
+    }
+
+    /// Scheme of the accumulated hash of the set
+    pub fn hash_scheme(&self) -> ExecutedOpsHashScheme {
+        self.accumulator.scheme()
+    }
+
+    /// Switch the accumulated hash of the set to `scheme`, recomputing it from the ids
+    ///
+    /// # Returns
+    /// Whether the scheme changed
+    pub fn set_hash_scheme(&mut self, scheme: ExecutedOpsHashScheme) -> bool
+    where
+        T: Sync,
+    {
+        if scheme == self.hash_scheme() {
+            return false;
+        }
+        self.accumulator = self.accumulate(scheme);
+        self.hash = self.accumulator.digest();
+        true
+    }
+
+    /// Returns the number of ids
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Check the set emptiness
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Check if an id is in the set
+    pub fn contains(&self, id: &T) -> bool {
+        self.ids.contains_key(id)
+    }
+
+    /// Get the slot at which an id is indexed, if it is in the set
+    pub fn get_slot(&self, id: &T) -> Option<Slot> {
+        self.ids.get(id).copied()
+    }
+
+    /// Check which ids of a batch are in the set.
+    /// Bit `i` of the result is set if `ids[i]` is in the set.
+    pub fn contains_multi(&self, ids: &[T]) -> BitVec {
+        ids.iter().map(|id| self.ids.contains_key(id)).collect()
+    }
+
+    /// Insert an id at the given slot.
+    /// The hash is only updated if the id was not in the set already.
+    /// An id inserted at several slots is only indexed by the earliest one, and pruned with it.
+    ///
+    /// # Returns
+    /// Whether the id was not in the set already
+    pub fn insert(&mut self, slot: Slot, id: T) -> bool {
+        let inserted = insert_id(&mut self.ids, Arc::make_mut(&mut self.sorted), slot, id);
+        if inserted {
+            // the accumulated hash is reversible, the id can be removed later
+            self.accumulator.insert(&id.id_hash());
+            self.hash = self.accumulator.digest();
+        }
+        inserted
+    }
+
+    /// Insert a batch of ids at their slots.
+    /// The hashes of the ids that were not in the set already are accumulated in parallel
+    /// for large batches, and folded into the hash once.
+    ///
+    /// # Returns
+    /// The number of ids that were not in the set already
+    pub fn extend<I>(&mut self, ids: I) -> usize
+    where
+        I: IntoIterator<Item = (Slot, T)>,
+        T: Sync,
+    {
+        let sorted = Arc::make_mut(&mut self.sorted);
+        let mut inserted = Vec::new();
+        for (slot, id) in ids {
+            if insert_id(&mut self.ids, sorted, slot, id) {
+                inserted.push(id);
+            }
+        }
+        self.accumulator.merge(&AccumulatedHash::from_items(
+            self.accumulator.scheme(),
+            &inserted,
+            |id| id.id_hash(),
+        ));
+        self.hash = self.accumulator.digest();
+        inserted.len()
+    }
+
+    /// Remove all the ids indexed strictly before `slot`
+    ///
+    /// # Returns
+    /// The removed ids by slot
+    pub fn prune(&mut self, slot: Slot) -> BTreeMap<Slot, SlotIds<T>> {
+        let sorted = Arc::make_mut(&mut self.sorted);
+        let kept = sorted.split_off(&slot);
+        let removed = std::mem::replace(sorted, kept);
+        for id in removed.values().flatten() {
+            self.ids.remove(id);
+            self.accumulator.remove(&id.id_hash());
+        }
+        self.hash = self.accumulator.digest();
+        removed
+    }
+
+    /// Remove at most `max_ids` of the ids indexed strictly before `slot`,
+    /// from the earliest slot and, within a slot, from the smallest id
+    ///
+    /// # Returns
+    /// The removed ids by slot
+    pub fn prune_batch(&mut self, slot: Slot, max_ids: u64) -> BTreeMap<Slot, SlotIds<T>> {
+        let mut removed: BTreeMap<Slot, SlotIds<T>> = BTreeMap::new();
+        let sorted = Arc::make_mut(&mut self.sorted);
+        let mut budget = max_ids;
+        while budget > 0 {
+            let Some(mut entry) = sorted.first_entry() else {
+                break;
+            };
+            if *entry.key() >= slot {
+                break;
+            }
+            if entry.get().len() as u64 <= budget {
+                budget -= entry.get().len() as u64;
+                let (entry_slot, ids) = entry.remove_entry();
+                removed.insert(entry_slot, ids);
+            } else {
+                // split the slot, removing its smallest ids so that batches are deterministic
+                let mut ids: Vec<T> = entry.get().iter().copied().collect();
+                ids.sort_unstable();
+                ids.truncate(budget as usize);
+                for id in &ids {
+                    entry.get_mut().remove(id);
+                }
+                removed.insert(*entry.key(), ids.into_iter().collect());
+                budget = 0;
+            }
+        }
+        for id in removed.values().flatten() {
+            self.ids.remove(id);
+            self.accumulator.remove(&id.id_hash());
+        }
+        self.hash = self.accumulator.digest();
+        removed
+    }
+
+    /// Iterate over the ids of the slots between `start_slot` and `end_slot`,
+    /// both included, ordered by slot
+    pub fn iter_range(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+    ) -> impl Iterator<Item = (&Slot, &T)> {
+        (start_slot <= end_slot)
+            .then(|| self.sorted.range(start_slot..=end_slot))
+            .into_iter()
+            .flatten()
+            .flat_map(|(slot, ids)| ids.iter().map(move |id| (slot, id)))
+    }
+
+    /// Count the ids of the slots between `start_slot` and `end_slot`, both included
+    pub fn count_range(&self, start_slot: Slot, end_slot: Slot) -> usize {
+        if start_slot > end_slot {
+            return 0;
+        }
+        self.sorted
+            .range(start_slot..=end_slot)
+            .map(|(_, ids)| ids.len())
+            .sum()
+    }
+
+    /// Recompute the accumulated hash from scratch out of the ids
+    pub fn recompute_hash(&self) -> Hash
+    where
+        T: Sync,
+    {
+        self.compute_hash(self.hash_scheme())
+    }
+
+    /// Compute the accumulated hash of the ids with `scheme`, which may not be the one of the set
+    pub fn compute_hash(&self, scheme: ExecutedOpsHashScheme) -> Hash
+    where
+        T: Sync,
+    {
+        self.accumulate(scheme).digest()
+    }
+
+    fn accumulate(&self, scheme: ExecutedOpsHashScheme) -> AccumulatedHash
+    where
+        T: Sync,
+    {
+        let ids: Vec<T> = self.ids.keys().copied().collect();
+        AccumulatedHash::from_items(scheme, &ids, |id| id.id_hash())
+    }
+
+    /// Check that `ids` and `sorted` hold the same ids and that the accumulated hash
+    /// matches them.
+    ///
+    /// # Returns
+    /// A description of the first divergence found, if any
+    pub fn verify_integrity(&self) -> Result<(), String>
+    where
+        T: Sync,
+    {
+        let mut sorted_count: usize = 0;
+        for (slot, ids) in self.sorted.iter() {
+            for id in ids {
+                match self.ids.get(id) {
+                    None => {
+                        return Err(format!(
+                            "id {} of slot {} is missing from the set",
+                            id, slot
+                        ))
+                    }
+                    Some(id_slot) if id_slot != slot => {
+                        return Err(format!(
+                            "id {} of slot {} is set at slot {}",
+                            id, slot, id_slot
+                        ))
+                    }
+                    Some(_) => {}
+                }
+            }
+            sorted_count += ids.len();
+        }
+        if sorted_count != self.ids.len() {
+            return Err(format!(
+                "{} ids are indexed by slot but the set holds {}",
+                sorted_count,
+                self.ids.len()
+            ));
+        }
+        let recomputed_hash = self.recompute_hash();
+        if recomputed_hash != self.hash {
+            return Err(format!(
+                "accumulated hash {} does not match the recomputed hash {}",
+                self.hash, recomputed_hash
+            ));
+        }
+        Ok(())
+    }
+
+    /// Get a part of the set for bootstrap streaming.
+    ///
+    /// The part starts strictly after the id of the cursor, so streaming resumes correctly
+    /// even if its slot was pruned in the meantime. The ids of a slot are taken in ascending
+    /// order so a slot can be split across parts.
+    /// Ids are added until the approximate serialized size of the part, counting `id_size`
+    /// bytes per id, reaches `max_bytes`, or until the part holds `max_slots` slots.
+    /// A part always holds at least one id.
+    ///
+    /// # Returns
+    /// A tuple containing the ids by slot and the next streaming step
+    pub fn get_part(
+        &self,
+        cursor: StreamingStep<(Slot, T)>,
+        max_slots: u64,
+        max_bytes: u64,
+        id_size: u64,
+    ) -> (BTreeMap<Slot, Vec<T>>, StreamingStep<(Slot, T)>) {
+        get_sorted_part(&self.sorted, cursor, max_slots, max_bytes, id_size)
+    }
+
+    /// Insert a part of the set received through bootstrap streaming.
+    ///
+    /// The ids of a slot are merged with the ones already known for it, as the changes
+    /// streamed alongside the parts may have inserted some of them already.
+    ///
+    /// # Returns
+    /// The next streaming step: the last id of the part, as the known slots
+    /// can go beyond it because of the streamed changes
+    pub fn set_part<I>(&mut self, part: BTreeMap<Slot, I>) -> StreamingStep<(Slot, T)>
+    where
+        I: IntoIterator<Item = T>,
+        T: Sync,
+    {
+        let mut last_id: Option<(Slot, T)> = None;
+        let mut part_ids = Vec::new();
+        for (slot, ids) in part {
+            // ids of a part are not necessarily sorted on the receiving side
+            let mut slot_last_id: Option<T> = None;
+            for id in ids {
+                part_ids.push((slot, id));
+                slot_last_id = slot_last_id.max(Some(id));
+            }
+            if let Some(id) = slot_last_id {
+                last_id = Some((slot, id));
+            }
+        }
+        self.extend(part_ids);
+        if let Some(last_id) = last_id {
+            StreamingStep::Ongoing(last_id)
+        } else {
+            StreamingStep::Finished(None)
+        }
+    }
+}
+
+/// Index `id` at `slot` in `ids` and `sorted`. An id already indexed at a later slot is moved
+/// to `slot`, and one already indexed at an earlier slot is left there, so that each id is
+/// indexed by a single slot and pruned once.
+///
+/// # Returns
+/// Whether the id was not in `ids` already
+fn insert_id<T>(
+    ids: &mut HashMap<T, Slot, T::BuildHasher>,
+    sorted: &mut BTreeMap<Slot, SlotIds<T>>,
+    slot: Slot,
+    id: T,
+) -> bool
+where
+    T: SlotIndexedId,
+{
+    match ids.entry(id) {
+        Entry::Occupied(mut entry) => {
+            let previous_slot = *entry.get();
+            if slot < previous_slot {
+                entry.insert(slot);
+                if let Some(previous_ids) = sorted.get_mut(&previous_slot) {
+                    previous_ids.remove(&id);
+                    if previous_ids.is_empty() {
+                        sorted.remove(&previous_slot);
+                    }
+                }
+                sorted.entry(slot).or_default().insert(id);
+            }
+            false
+        }
+        Entry::Vacant(entry) => {
+            entry.insert(slot);
+            sorted.entry(slot).or_default().insert(id);
+            true
+        }
+    }
+}
+
+/// Serializer for the ids by slot of a `SlotIndexedSet`
+pub struct SlotIndexedSetSerializer<T, ST>
+where
+    ST: Serializer<T>,
+{
+    slot_serializer: SlotSerializer,
+    u64_serializer: U64VarIntSerializer,
+    id_serializer: ST,
+    phantom_t: PhantomData<T>,
+}
+
+impl<T, ST> SlotIndexedSetSerializer<T, ST>
+where
+    ST: Serializer<T>,
+{
+    /// Create a new serializer, serializing ids with `id_serializer`
+    pub fn new(id_serializer: ST) -> Self {
+        Self {
+            slot_serializer: SlotSerializer::new(),
+            u64_serializer: U64VarIntSerializer::new(),
+            id_serializer,
+            phantom_t: PhantomData,
+        }
+    }
+
+    /// Exact serialized size of the ids by slot, ids being serialized in `id_size` bytes
+    pub fn serialized_size(&self, value: &BTreeMap<Slot, SlotIds<T>>, id_size: usize) -> usize {
+        varint_size(value.len() as u64)
+            + value
+                .iter()
+                .map(|(slot, ids)| {
+                    slot_size(slot) + varint_size(ids.len() as u64) + ids.len() * id_size
+                })
+                .sum::<usize>()
+    }
+}
+
+/// Get a bootstrap part out of ids indexed by slot, see `SlotIndexedSet::get_part`
+pub(crate) fn get_sorted_part<T>(
+    sorted: &BTreeMap<Slot, SlotIds<T>>,
+    cursor: StreamingStep<(Slot, T)>,
+    max_slots: u64,
+    max_bytes: u64,
+    id_size: u64,
+) -> (BTreeMap<Slot, Vec<T>>, StreamingStep<(Slot, T)>)
+where
+    T: SlotIndexedId,
+{
+    // the ids of the slots following the cursor, in ascending order
+    let ids_after = |last_streamed: Option<&(Slot, T)>| {
+        let last_streamed = last_streamed.copied();
+        let left_bound = match last_streamed {
+            Some((slot, _)) => Included(slot),
+            None => Unbounded,
+        };
+        sorted
+            .range((left_bound, Unbounded))
+            .flat_map(move |(slot, ids)| {
+                let mut ids: Vec<T> = ids
+                    .iter()
+                    .filter(|id| match &last_streamed {
+                        Some((last_slot, last_id)) if last_slot == slot => *id > last_id,
+                        _ => true,
+                    })
+                    .copied()
+                    .collect();
+                ids.sort_unstable();
+                ids.into_iter().map(move |id| ((*slot, id), ()))
+            })
+    };
+    // the first id of a slot counts the slot against the budget, with its overhead
+    let mut last_slot: Option<Slot> = None;
+    let (ids, next_step) = get_streaming_chunk(
+        cursor,
+        ids_after,
+        StreamingBudget {
+            max_entries: max_slots,
+            max_bytes,
+        },
+        |(slot, _), _| {
+            if last_slot.replace(*slot) == Some(*slot) {
+                StreamingCost {
+                    entries: 0,
+                    bytes: id_size,
+                }
+            } else {
+                StreamingCost::entry(SLOT_ENTRY_MAX_OVERHEAD_BYTES + id_size)
+            }
+        },
+    );
+    let mut part: BTreeMap<Slot, Vec<T>> = BTreeMap::new();
+    for ((slot, id), _) in ids {
+        part.entry(slot).or_default().push(id);
+    }
+    (part, next_step)
+}
+
+/// Check that slots are strictly increasing and that the ids of each slot, as given by `key`,
+/// are strictly increasing too. This is the canonical form produced by the serializers.
+pub(crate) fn is_canonical<V, K: Ord>(slots: &[(Slot, Vec<V>)], key: impl Fn(&V) -> &K) -> bool {
+    slots.windows(2).all(|pair| pair[0].0 < pair[1].0)
+        && slots
+            .iter()
+            .all(|(_, ids)| ids.windows(2).all(|pair| key(&pair[0]) < key(&pair[1])))
+}
+
+impl<T, ST> Serializer<BTreeMap<Slot, SlotIds<T>>> for SlotIndexedSetSerializer<T, ST>
+where
+    T: SlotIndexedId,
+    ST: Serializer<T>,
+{
+    fn serialize(
+        &self,
+        value: &BTreeMap<Slot, SlotIds<T>>,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        // slots length
+        self.u64_serializer
+            .serialize(&(value.len() as u64), buffer)?;
+        // sorting buffer, reused by all the slots
+        let mut sorted_ids: Vec<&T> = Vec::with_capacity(
+            value
+                .values()
+                .map(|ids| ids.len())
+                .max()
+                .unwrap_or_default(),
+        );
+        for (slot, ids) in value {
+            // slot
+            self.slot_serializer.serialize(slot, buffer)?;
+            // slot ids length
+            self.u64_serializer.serialize(&(ids.len() as u64), buffer)?;
+            // slot ids, sorted so that identical sets serialize identically
+            sorted_ids.clear();
+            sorted_ids.extend(ids.iter());
+            sorted_ids.sort_unstable();
+            for id in sorted_ids.iter() {
+                self.id_serializer.serialize(id, buffer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Deserializer for the ids by slot of a `SlotIndexedSet`
+pub struct SlotIndexedSetDeserializer<T, DT>
+where
+    DT: Deserializer<T>,
+{
+    id_deserializer: DT,
+    slot_deserializer: SlotDeserializer,
+    slots_length_deserializer: U64VarIntDeserializer,
+    slot_ids_length_deserializer: U64VarIntDeserializer,
+    require_canonical: bool,
+    phantom_t: PhantomData<T>,
+}
+
+impl<T, DT> SlotIndexedSetDeserializer<T, DT>
+where
+    DT: Deserializer<T>,
+{
+    /// Create a new deserializer, deserializing ids with `id_deserializer`
+    pub fn new(
+        id_deserializer: DT,
+        thread_count: u8,
+        max_slots_length: u64,
+        max_ids_per_slot: u64,
+    ) -> Self {
+        Self {
+            id_deserializer,
+            slot_deserializer: SlotDeserializer::new(
+                (Included(u64::MIN), Included(u64::MAX)),
+                (Included(0), Excluded(thread_count)),
+            ),
+            slots_length_deserializer: U64VarIntDeserializer::new(
+                Included(u64::MIN),
+                Included(max_slots_length),
+            ),
+            slot_ids_length_deserializer: U64VarIntDeserializer::new(
+                Included(u64::MIN),
+                Included(max_ids_per_slot),
+            ),
+            require_canonical: false,
+            phantom_t: PhantomData,
+        }
+    }
+
+    /// Reject input that is not in canonical form (sorted ids within each slot)
+    pub fn require_canonical(mut self) -> Self {
+        self.require_canonical = true;
+        self
+    }
+}
+
+impl<T, DT> Deserializer<BTreeMap<Slot, SlotIds<T>>> for SlotIndexedSetDeserializer<T, DT>
+where
+    T: SlotIndexedId,
+    DT: Deserializer<T>,
+{
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], BTreeMap<Slot, SlotIds<T>>, E> {
+        context(
+            "SlotIndexedSet",
+            verify(
+                length_count(
+                    context("SlotIndexedSet length", |input| {
+                        self.slots_length_deserializer.deserialize(input)
+                    }),
+                    context(
+                        "slot ids",
+                        tuple((
+                            context("slot", |input| self.slot_deserializer.deserialize(input)),
+                            length_count(
+                                context("slot ids length", |input| {
+                                    self.slot_ids_length_deserializer.deserialize(input)
+                                }),
+                                context("id", |input| self.id_deserializer.deserialize(input)),
+                            ),
+                        )),
+                    ),
+                ),
+                |slots: &Vec<(Slot, Vec<T>)>| {
+                    !self.require_canonical || is_canonical(slots, |id| id)
+                },
+            ),
+        )
+        .map(|slots| {
+            slots
+                .into_iter()
+                .map(|(slot, ids)| (slot, ids.into_iter().collect()))
+                .collect()
+        })
+        .parse(buffer)
+    }
+}
+
+#[test]
+fn test_slot_indexed_set_id_at_several_slots() {
+    let id = OperationId::new(Hash::compute_from(&[0]));
+    let other_id = OperationId::new(Hash::compute_from(&[1]));
+    for scheme in [ExecutedOpsHashScheme::Xor, ExecutedOpsHashScheme::LtHash] {
+        let mut expected = SlotIndexedSet::with_hash_scheme(scheme);
+        expected.insert(Slot::new(5, 0), other_id);
+
+        let mut set = SlotIndexedSet::with_hash_scheme(scheme);
+        assert!(set.insert(Slot::new(3, 0), id));
+        assert!(!set.insert(Slot::new(4, 0), id));
+        set.insert(Slot::new(5, 0), other_id);
+        assert_eq!(set.get_slot(&id), Some(Slot::new(3, 0)));
+        assert!(set.verify_integrity().is_ok());
+
+        // the id is pruned once, with both of its slots
+        let removed = set.prune(Slot::new(5, 0));
+        assert_eq!(removed.values().flatten().count(), 1);
+        assert!(!set.contains(&id));
+        assert!(set.verify_integrity().is_ok());
+        assert_eq!(set.hash, expected.hash);
+
+        // an earlier slot moves the id, which a batch prunes once too
+        let mut set = SlotIndexedSet::with_hash_scheme(scheme);
+        set.extend([(Slot::new(4, 0), id), (Slot::new(5, 0), other_id)]);
+        set.extend([(Slot::new(3, 0), id)]);
+        assert_eq!(set.get_slot(&id), Some(Slot::new(3, 0)));
+        assert_eq!(set.count_range(Slot::new(4, 0), Slot::new(4, 1)), 0);
+        assert_eq!(set.prune_batch(Slot::new(5, 0), 10).len(), 1);
+        assert!(!set.contains(&id));
+        assert!(set.verify_integrity().is_ok());
+        assert_eq!(set.hash, expected.hash);
+    }
+}
@@ -58,6 +58,12 @@ pub struct ExecutionConfig {
     pub max_bytecode_size: u64,
     /// Max datastore value size
     pub max_datastore_value_size: u64,
+    /// Max size of the data of an event emitted by a smart contract, longer data is truncated
+    pub max_event_size: usize,
+    /// Gas charged per byte of the data of an event emitted by a smart contract
+    pub event_data_gas_cost_per_byte: u64,
+    /// Version of the VM component from which the event data is limited and charged per byte
+    pub event_data_limit_vm_version: u32,
     /// Storage cost constants
     pub storage_costs_constants: StorageCostsConstants,
     /// Max gas for read only executions
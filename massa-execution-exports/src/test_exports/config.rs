@@ -42,6 +42,9 @@ impl Default for ExecutionConfig {
             max_datastore_key_length: MAX_DATASTORE_KEY_LENGTH,
             max_bytecode_size: MAX_BYTECODE_LENGTH,
             max_datastore_value_size: MAX_DATASTORE_VALUE_LENGTH,
+            max_event_size: MAX_EVENT_DATA_SIZE,
+            event_data_gas_cost_per_byte: EVENT_DATA_GAS_COST_PER_BYTE,
+            event_data_limit_vm_version: EVENT_DATA_LIMIT_VM_VERSION,
             storage_costs_constants,
             max_read_only_gas: 100_000_000,
             gas_costs: GasCosts::new(
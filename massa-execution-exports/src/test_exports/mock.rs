@@ -6,13 +6,14 @@ use crate::{
     ExecutionAddressInfo, ExecutionController, ExecutionError, ReadOnlyExecutionOutput,
     ReadOnlyExecutionRequest,
 };
+use massa_hash::{Hash, HASH_SIZE_BYTES};
 use massa_ledger_exports::LedgerEntry;
 use massa_models::denunciation::DenunciationIndex;
 use massa_models::{
     address::Address,
     amount::Amount,
     block_id::BlockId,
-    execution::EventFilter,
+    execution::{EventFilter, ExecutedOpsDump},
     operation::OperationId,
     output_event::SCOutputEvent,
     prehash::{PreHashMap, PreHashSet},
@@ -129,6 +130,7 @@ impl ExecutionController for MockExecutionController {
             final_block_count: 0,
             final_executed_operations_count: 0,
             active_cursor: Slot::new(0, 0),
+            final_executed_ops_stats: Default::default(),
         }
     }
 
@@ -233,7 +235,7 @@ impl ExecutionController for MockExecutionController {
             self.0
                 .lock()
                 .send(MockExecutionControllerMessage::IsDenunciationExecuted {
-                    de_idx: denunciation_index.clone(),
+                    de_idx: *denunciation_index,
                     response_tx,
                 })
         {
@@ -251,4 +253,15 @@ impl ExecutionController for MockExecutionController {
     fn get_op_exec_status(&self) -> (HashMap<OperationId, bool>, HashMap<OperationId, bool>) {
         (HashMap::new(), HashMap::new())
     }
+
+    fn get_executed_ops_dump(&self, offset: usize, _limit: usize) -> ExecutedOpsDump {
+        ExecutedOpsDump {
+            last_applied_slot: None,
+            hash: Hash::from_bytes(&[0; HASH_SIZE_BYTES]),
+            total_slot_count: 0,
+            total_op_count: 0,
+            offset,
+            slots: Vec::new(),
+        }
+    }
 }
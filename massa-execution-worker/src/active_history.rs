@@ -44,22 +44,6 @@ impl ActiveHistory {
         }
     }
 
-    /// Lazily query (from end to beginning) the active list of executed ops to check if an op was executed.
-    ///
-    /// Returns a `HistorySearchResult`.
-    pub fn fetch_executed_op(&self, op_id: &OperationId) -> HistorySearchResult<()> {
-        for history_element in self.0.iter().rev() {
-            if history_element
-                .state_changes
-                .executed_ops_changes
-                .contains_key(op_id)
-            {
-                return HistorySearchResult::Present(());
-            }
-        }
-        HistorySearchResult::NoInfo
-    }
-
     /// Lazily query (from end to beginning) the active list of executed denunciations.
     ///
     /// Returns a `HistorySearchResult`.
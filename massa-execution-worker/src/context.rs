@@ -13,6 +13,7 @@ use crate::speculative_executed_ops::SpeculativeExecutedOps;
 use crate::speculative_ledger::SpeculativeLedger;
 use crate::vesting_manager::VestingManager;
 use crate::{active_history::ActiveHistory, speculative_roll_state::SpeculativeRollState};
+use bitvec::vec::BitVec;
 use massa_async_pool::{AsyncMessage, AsyncMessageId};
 use massa_executed_ops::{ExecutedDenunciationsChanges, ExecutedOpsChanges};
 use massa_execution_exports::{
@@ -113,6 +114,12 @@ pub struct ExecutionContext {
     /// max gas for this execution
     pub max_gas: u64,
 
+    /// gas charged so far for the data of the events emitted during this execution
+    pub event_data_gas: u64,
+
+    /// version of the VM component active at the execution slot
+    pub vm_version: u32,
+
     /// coin spending allowance for the operation creator
     pub creator_coin_spending_allowance: Option<Amount>,
 
@@ -200,6 +207,8 @@ impl ExecutionContext {
                 active_history,
             ),
             max_gas: Default::default(),
+            event_data_gas: Default::default(),
+            vm_version: Default::default(),
             creator_coin_spending_allowance: Default::default(),
             slot: Slot::new(0, 0),
             created_addr_index: Default::default(),
@@ -957,9 +966,16 @@ impl ExecutionContext {
         self.events.push(event);
     }
 
-    /// Check if an operation was previously executed (to prevent reuse)
-    pub fn is_op_executed(&self, op_id: &OperationId) -> bool {
-        self.speculative_executed_ops.is_op_executed(op_id)
+    /// Get the expiry slot of an operation if it was previously executed (to prevent reuse)
+    pub fn get_executed_op_expiry_slot(&self, op_id: &OperationId) -> Option<Slot> {
+        self.speculative_executed_ops
+            .get_executed_op_expiry_slot(op_id)
+    }
+
+    /// Check which operations of a batch were previously executed (to prevent reuse).
+    /// Bit `i` of the result is set if `op_ids[i]` was executed.
+    pub fn are_ops_executed(&self, op_ids: &[OperationId]) -> BitVec {
+        self.speculative_executed_ops.are_ops_executed(op_ids)
     }
 
     /// Check if a denunciation was previously executed (to prevent reuse)
@@ -969,7 +985,7 @@ impl ExecutionContext {
     }
 
     /// Insert an executed operation.
-    /// Does not check for reuse, please use `is_op_executed` before.
+    /// Does not check for reuse, please use `get_executed_op_expiry_slot` before.
     ///
     /// # Arguments
     /// * `op_id`: operation ID
@@ -989,7 +1005,7 @@ impl ExecutionContext {
     ///
     pub fn insert_executed_denunciation(&mut self, denunciation_idx: &DenunciationIndex) {
         self.speculative_executed_denunciations
-            .insert_executed_denunciation(denunciation_idx.clone());
+            .insert_executed_denunciation(*denunciation_idx);
     }
 
     /// gets the cycle information for an address
@@ -24,7 +24,7 @@ use massa_ledger_exports::{SetOrDelete, SetUpdateOrDelete};
 use massa_models::address::ExecutionAddressCycleInfo;
 use massa_models::bytecode::Bytecode;
 use massa_models::denunciation::{Denunciation, DenunciationIndex};
-use massa_models::execution::EventFilter;
+use massa_models::execution::{EventFilter, ExecutedOpsDump};
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::PreHashSet;
 use massa_models::stats::ExecutionStats;
@@ -40,7 +40,7 @@ use massa_module_cache::controller::ModuleCache;
 use massa_pos_exports::SelectorController;
 use massa_sc_runtime::{Interface, Response, VMError};
 use massa_storage::Storage;
-use massa_versioning_worker::versioning::MipStore;
+use massa_versioning_worker::versioning::{MipComponent, MipStore};
 use parking_lot::{Mutex, RwLock};
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::Arc;
@@ -176,7 +176,15 @@ impl ExecutionState {
 
     /// Get execution statistics
     pub fn get_stats(&self) -> ExecutionStats {
-        self.stats_counter.get_stats(self.active_cursor)
+        self.stats_counter.get_stats(
+            self.active_cursor,
+            self.final_state.read().executed_ops.get_stats(),
+        )
+    }
+
+    /// Dump up to `limit` slots of the final executed operations, starting from the slot at index `offset`
+    pub fn get_executed_ops_dump(&self, offset: usize, limit: usize) -> ExecutedOpsDump {
+        self.final_state.read().executed_ops.get_dump(offset, limit)
     }
 
     /// Applies the output of an execution to the final execution state.
@@ -259,10 +267,11 @@ impl ExecutionState {
         let mut context = context_guard!(self);
 
         // ignore the operation if it was already executed
-        if context.is_op_executed(&operation_id) {
-            return Err(ExecutionError::IncludeOperationError(
-                "operation was executed previously".to_string(),
-            ));
+        if let Some(expiry_slot) = context.get_executed_op_expiry_slot(&operation_id) {
+            return Err(ExecutionError::IncludeOperationError(format!(
+                "operation was executed previously, its record expires at slot {}",
+                expiry_slot
+            )));
         }
 
         // Set the creator coin spending allowance.
@@ -288,6 +297,7 @@ impl ExecutionState {
 
         // set the context max gas to match the one defined in the operation
         context.max_gas = operation.get_gas_usage();
+        context.event_data_gas = 0;
 
         // set the creator address
         context.creator_address = Some(operation.content_creator_address);
@@ -740,7 +750,7 @@ impl ExecutionState {
                 "not enough gas to pay for singlepass compilation".to_string(),
             ))?;
         // run the VM
-        massa_sc_runtime::run_main(
+        let response = massa_sc_runtime::run_main(
             &*self.execution_interface,
             module,
             remaining_gas,
@@ -750,6 +760,7 @@ impl ExecutionState {
             context: "ExecuteSC".to_string(),
             error,
         })?;
+        self.charge_event_data_gas(response.remaining_gas)?;
 
         Ok(())
     }
@@ -848,10 +859,11 @@ impl ExecutionState {
             }
             _ => (),
         }
-        response.map_err(|error| ExecutionError::VMError {
+        let response = response.map_err(|error| ExecutionError::VMError {
             context: "CallSC".to_string(),
             error,
         })?;
+        self.charge_event_data_gas(response.remaining_gas)?;
         Ok(())
     }
 
@@ -872,6 +884,7 @@ impl ExecutionState {
             let mut context = context_guard!(self);
             context_snapshot = context.get_snapshot();
             context.max_gas = message.max_gas;
+            context.event_data_gas = 0;
             context.creator_address = None;
             context.creator_coin_spending_allowance = None;
             context.stack = vec![
@@ -939,10 +952,21 @@ impl ExecutionState {
             self.config.gas_costs.clone(),
         );
         match response {
-            Ok(Response { init_gas_cost, .. }) => {
+            Ok(Response {
+                init_gas_cost,
+                remaining_gas,
+                ..
+            }) => {
                 self.module_cache
                     .write()
                     .set_init_cost(&bytecode, init_gas_cost);
+                if let Err(err) = self.charge_event_data_gas(remaining_gas) {
+                    // not enough gas left for the event data: reset context to snapshot and reimburse sender
+                    let mut context = context_guard!(self);
+                    context.reset_to_snapshot(context_snapshot, err.clone());
+                    context.cancel_async_message(&message);
+                    return Err(err);
+                }
                 Ok(())
             }
             Err(error) => {
@@ -990,6 +1014,7 @@ impl ExecutionState {
             self.module_cache.clone(),
             self.vesting_manager.clone(),
         );
+        execution_context.vm_version = self.vm_version_at(slot);
 
         // Get asynchronous messages to execute
         let messages = execution_context.take_async_batch(self.config.max_async_gas);
@@ -1063,9 +1088,23 @@ impl ExecutionState {
             // Set block credits
             let mut block_credits = self.config.block_reward;
 
+            // Check in one pass which operations were executed before this block.
+            // Reuse within the block is still detected when executing each operation.
+            let previously_executed = {
+                let op_ids: Vec<OperationId> = operations.iter().map(|op| op.id).collect();
+                context_guard!(self).are_ops_executed(&op_ids)
+            };
+
             // Try executing the operations of this block in the order in which they appear in the block.
             // Errors are logged but do not interrupt the execution of the slot.
-            for operation in operations.into_iter() {
+            for (operation, executed) in operations.into_iter().zip(previously_executed) {
+                if executed {
+                    debug!(
+                        "failed executing operation {} in block {}: operation was executed previously",
+                        operation.id, block_id
+                    );
+                    continue;
+                }
                 if let Err(err) = self.execute_operation(
                     &operation,
                     stored_block.content.header.content.slot,
@@ -1363,7 +1402,7 @@ impl ExecutionState {
         };
 
         // create a readonly execution context
-        let execution_context = ExecutionContext::readonly(
+        let mut execution_context = ExecutionContext::readonly(
             self.config.clone(),
             slot,
             req.max_gas,
@@ -1373,6 +1412,7 @@ impl ExecutionState {
             self.module_cache.clone(),
             self.vesting_manager.clone(),
         );
+        execution_context.vm_version = self.vm_version_at(&slot);
 
         // run the interpreter according to the target type
         let exec_response = match req.target {
@@ -1441,15 +1481,36 @@ impl ExecutionState {
             }
         };
 
+        // charge the event data on the gas left by the VM
+        let remaining_gas = self.charge_event_data_gas(exec_response.remaining_gas)?;
+
         // return the execution output
         let execution_output = context_guard!(self).settle_slot();
         Ok(ReadOnlyExecutionOutput {
             out: execution_output,
-            gas_cost: req.max_gas.saturating_sub(exec_response.remaining_gas),
+            gas_cost: req.max_gas.saturating_sub(remaining_gas),
             call_result: exec_response.ret,
         })
     }
 
+    /// Charges the gas of the event data emitted during an execution on the gas left by the VM,
+    /// so that the VM and the event data share the max gas of the execution.
+    ///
+    /// # Arguments
+    /// * `remaining_gas`: gas left by the VM at the end of the execution
+    ///
+    /// # Returns
+    /// The gas left once the event data is paid for
+    fn charge_event_data_gas(&self, remaining_gas: u64) -> Result<u64, ExecutionError> {
+        let event_data_gas = context_guard!(self).event_data_gas;
+        remaining_gas.checked_sub(event_data_gas).ok_or_else(|| {
+            ExecutionError::RuntimeError(format!(
+                "not enough gas to pay for the event data: {} gas left, {} needed",
+                remaining_gas, event_data_gas
+            ))
+        })
+    }
+
     /// Gets a balance both at the latest final and candidate executed slots
     pub fn get_final_and_candidate_balance(
         &self,
@@ -1637,8 +1698,11 @@ impl ExecutionState {
 
         {
             // check final state
-            let final_state = self.final_state.read();
-            ops.retain(|op_id| !final_state.executed_ops.contains(op_id));
+            let op_ids: Vec<OperationId> = ops.iter().copied().collect();
+            let executed = self.final_state.read().executed_ops.contains_multi(&op_ids);
+            for index in executed.iter_ones() {
+                ops.remove(&op_ids[index]);
+            }
         }
 
         ops
@@ -1683,8 +1747,28 @@ impl ExecutionState {
     pub fn get_op_exec_status(&self) -> (HashMap<OperationId, bool>, HashMap<OperationId, bool>) {
         (
             self.active_history.read().get_op_exec_status(),
-            self.final_state.read().executed_ops.op_exec_status.clone(),
+            self.final_state
+                .read()
+                .executed_ops
+                .op_exec_status
+                .as_ref()
+                .clone(),
+        )
+    }
+
+    /// Version of the VM component active at the given slot, 0 if none is
+    fn vm_version_at(&self, slot: &Slot) -> u32 {
+        get_block_slot_timestamp(
+            self.config.thread_count,
+            self.config.t0,
+            self.config.genesis_timestamp,
+            *slot,
         )
+        .map(|slot_ts| {
+            self.mip_store
+                .get_latest_component_version_at(&MipComponent::VM, slot_ts)
+        })
+        .unwrap_or(0)
     }
 
     /// Update MipStore with block header stats
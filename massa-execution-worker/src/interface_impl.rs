@@ -32,6 +32,24 @@ use tracing::debug;
 ))]
 use massa_models::datastore::Datastore;
 
+/// Appended to the data of an event that was truncated
+pub(crate) const EVENT_TRUNCATION_MARKER: &str = "[truncated]";
+
+/// Truncate event data to at most `max_size` bytes (or the marker alone if it doesn't fit), on a char boundary.
+/// Truncated data ends with `EVENT_TRUNCATION_MARKER` so that readers know it is incomplete.
+pub(crate) fn truncate_event_data(mut data: String, max_size: usize) -> String {
+    if data.len() <= max_size {
+        return data;
+    }
+    let mut end = max_size.saturating_sub(EVENT_TRUNCATION_MARKER.len());
+    while !data.is_char_boundary(end) {
+        end -= 1;
+    }
+    data.truncate(end);
+    data.push_str(EVENT_TRUNCATION_MARKER);
+    data
+}
+
 /// helper for locking the context mutex
 macro_rules! context_guard {
     ($self:ident) => {
@@ -655,11 +673,27 @@ impl Interface for InterfaceImpl {
     }
 
     /// Emits an execution event to be stored.
+    /// From the VM version `event_data_limit_vm_version`, data longer than `max_event_size`
+    /// is truncated and ends with `EVENT_TRUNCATION_MARKER`, and each byte of data is charged
+    /// `event_data_gas_cost_per_byte` gas. That gas is taken from the gas left by the VM at the end
+    /// of the execution, which fails if not enough is left.
     ///
     /// # Arguments:
     /// data: the string data that is the payload of the event
     fn generate_event(&self, data: String) -> Result<()> {
         let mut context = context_guard!(self);
+        let data = if context.vm_version >= self.config.event_data_limit_vm_version {
+            let data = truncate_event_data(data, self.config.max_event_size);
+            context.event_data_gas = context.event_data_gas.saturating_add(
+                (data.len() as u64).saturating_mul(self.config.event_data_gas_cost_per_byte),
+            );
+            if context.event_data_gas > context.max_gas {
+                bail!("not enough gas to emit an event of {} bytes", data.len());
+            }
+            data
+        } else {
+            data
+        };
         let event = context.event_create(data, false);
         context.event_emit(event);
         Ok(())
@@ -2,8 +2,9 @@
 
 //! Speculative list of previously executed operations, to prevent reuse.
 
-use crate::active_history::{ActiveHistory, HistorySearchResult};
-use massa_executed_ops::ExecutedOpsChanges;
+use crate::active_history::ActiveHistory;
+use bitvec::vec::BitVec;
+use massa_executed_ops::{ExecutedOps, ExecutedOpsChanges, ExecutedOpsView};
 use massa_final_state::FinalState;
 use massa_models::{operation::OperationId, slot::Slot};
 use parking_lot::RwLock;
@@ -55,30 +56,46 @@ impl SpeculativeExecutedOps {
         self.executed_ops = snapshot;
     }
 
-    /// Checks if an operation was executed previously
-    pub fn is_op_executed(&self, op_id: &OperationId) -> bool {
-        // check in the curent changes
-        if self.executed_ops.contains_key(op_id) {
-            return true;
+    /// Gets the expiry slot of an operation if it was executed previously
+    pub fn get_executed_op_expiry_slot(&self, op_id: &OperationId) -> Option<Slot> {
+        // check in the curent changes first to avoid locking
+        if let Some((_, slot)) = self.executed_ops.get(op_id) {
+            return Some(*slot);
         }
 
-        // check in the active history, backwards
-        match self.active_history.read().fetch_executed_op(op_id) {
-            HistorySearchResult::Present(_) => {
-                return true;
-            }
-            HistorySearchResult::Absent => {
-                return false;
-            }
-            HistorySearchResult::NoInfo => {}
-        }
+        // check in the active history, backwards, then in the final state
+        let history = self.active_history.read();
+        let final_state = self.final_state.read();
+        self.view(&history, &final_state.executed_ops)
+            .get_expiry_slot(op_id)
+    }
 
-        // check in the final state
-        self.final_state.read().executed_ops.contains(op_id)
+    /// Checks which operations of a batch were executed previously.
+    /// Bit `i` of the result is set if `op_ids[i]` was executed.
+    /// Each layer (current changes, active history, final state) is locked and visited once.
+    pub fn are_ops_executed(&self, op_ids: &[OperationId]) -> BitVec {
+        let history = self.active_history.read();
+        let final_state = self.final_state.read();
+        self.view(&history, &final_state.executed_ops)
+            .contains_multi(op_ids)
+    }
+
+    /// Layers the active history and the current changes over the final executed operations
+    fn view<'a>(
+        &'a self,
+        history: &'a ActiveHistory,
+        final_executed_ops: &'a ExecutedOps,
+    ) -> ExecutedOpsView<'a> {
+        let mut view = ExecutedOpsView::new(final_executed_ops);
+        for history_element in history.0.iter() {
+            view.push_layer(&history_element.state_changes.executed_ops_changes);
+        }
+        view.push_layer(&self.executed_ops);
+        view
     }
 
     /// Insert an executed operation.
-    /// Does not check for reuse, please use `SpeculativeExecutedOps::is_op_executed` before.
+    /// Does not check for reuse, please use `SpeculativeExecutedOps::get_executed_op_expiry_slot` before.
     ///
     /// # Arguments
     /// * `op_id`: operation ID
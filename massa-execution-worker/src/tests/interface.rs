@@ -7,7 +7,7 @@ mod tests {
     use massa_sc_runtime::Interface;
     use std::str::FromStr;
 
-    use crate::interface_impl::InterfaceImpl;
+    use crate::interface_impl::{truncate_event_data, InterfaceImpl, EVENT_TRUNCATION_MARKER};
     #[test]
     fn test_hash_sha256() {
         let interface = InterfaceImpl::new_default(
@@ -19,4 +19,21 @@ mod tests {
             &hex!("3fc9b689459d738f8c88a3a48aa9e33542016b7a4052e001aaa536fca74813cb")[..];
         assert_eq!(actual_hash, expected_hash);
     }
+
+    #[test]
+    fn test_truncate_event_data() {
+        assert_eq!(truncate_event_data("short".to_string(), 100), "short");
+        let truncated = truncate_event_data("a".repeat(100), 50);
+        assert_eq!(truncated.len(), 50);
+        assert!(truncated.ends_with(EVENT_TRUNCATION_MARKER));
+        // never cut in the middle of a multi-byte char
+        let truncated = truncate_event_data("é".repeat(100), 50);
+        assert!(truncated.len() <= 50);
+        assert!(truncated.ends_with(EVENT_TRUNCATION_MARKER));
+        // the marker alone doesn't fit
+        assert_eq!(
+            truncate_event_data("a".repeat(100), 5),
+            EVENT_TRUNCATION_MARKER
+        );
+    }
 }
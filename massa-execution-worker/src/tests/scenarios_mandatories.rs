@@ -1,6 +1,7 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 #[cfg(test)]
 mod tests {
+    use crate::interface_impl::EVENT_TRUNCATION_MARKER;
     use crate::start_execution_worker;
     use crate::tests::mock::{
         create_block, get_initials_vesting, get_random_address_full, get_sample_state,
@@ -27,7 +28,10 @@ mod tests {
     use massa_signature::KeyPair;
     use massa_storage::Storage;
     use massa_time::MassaTime;
-    use massa_versioning_worker::versioning::{MipStatsConfig, MipStore};
+    use massa_versioning_worker::{
+        mips::get_mip_list,
+        versioning::{MipState, MipStatsConfig, MipStore},
+    };
     use num::rational::Ratio;
     use serial_test::serial;
     use std::{
@@ -173,6 +177,104 @@ mod tests {
         manager.stop();
     }
 
+    #[test]
+    #[serial]
+    fn event_data_truncated_from_vm_v1_activation() {
+        let vesting = get_initials_vesting(false);
+        // setup the period duration and an event data limit below the data of `event_test.wasm`
+        let exec_cfg = ExecutionConfig {
+            t0: 100.into(),
+            cursor_delay: 0.into(),
+            initial_vesting_path: vesting.path().to_path_buf(),
+            max_event_size: 4,
+            ..ExecutionConfig::default()
+        };
+        // init the MIP store with the VM version 1 MIP, its vote having started before genesis
+        let mip_stats_config = MipStatsConfig {
+            block_count_considered: 10,
+            counters_max: MIP_STORE_STATS_COUNTERS_MAX,
+        };
+        let [(mut mip_vm_v1, _)] = get_mip_list();
+        mip_vm_v1.start = exec_cfg
+            .genesis_timestamp
+            .saturating_sub(mip_vm_v1.activation_delay)
+            .saturating_sub(MassaTime::from_millis(10_000));
+        mip_vm_v1.timeout = exec_cfg
+            .genesis_timestamp
+            .saturating_add(MassaTime::from_millis(3_600_000));
+        let mip_vm_v1_start = mip_vm_v1.start;
+        let activation_delay = mip_vm_v1.activation_delay;
+        // a store per run: the worker updates the stats of the store it is given
+        let new_mip_store = || {
+            MipStore::try_from((
+                [(mip_vm_v1.clone(), MipState::new(mip_vm_v1_start))],
+                mip_stats_config.clone(),
+            ))
+            .unwrap()
+        };
+
+        let run_event_test = |mip_store: MipStore| {
+            let (sample_state, _keep_file, _keep_dir) = get_sample_state(0).unwrap();
+            let storage = Storage::create_root();
+            let (mut manager, controller) = start_execution_worker(
+                exec_cfg.clone(),
+                sample_state.clone(),
+                sample_state.read().pos_state.selector.clone(),
+                mip_store,
+                ExecutionChannels {
+                    slot_execution_output_sender: broadcast::channel(5000).0,
+                },
+            );
+            init_execution_worker(&exec_cfg, &storage, controller.clone());
+            std::thread::sleep(Duration::from_millis(1000));
+            let mut res = controller
+                .execute_readonly_request(ReadOnlyExecutionRequest {
+                    max_gas: 1_000_000,
+                    call_stack: vec![],
+                    target: ReadOnlyExecutionTarget::BytecodeExecution(
+                        include_bytes!("./wasm/event_test.wasm").to_vec(),
+                    ),
+                    is_final: true,
+                })
+                .expect("readonly execution failed");
+            manager.stop();
+            res.out
+                .events
+                .take()
+                .pop_back()
+                .expect("no event emitted")
+                .data
+        };
+
+        // the vote hasn't locked in the VM version 1: the event data is kept whole
+        let data = run_event_test(new_mip_store());
+        assert!(data.len() > exec_cfg.max_event_size);
+        assert!(!data.ends_with(EVENT_TRUNCATION_MARKER));
+
+        // every considered block announces the version 1: the MIP starts, locks in,
+        // and is active once the activation delay elapsed, before genesis
+        let mut mip_store = new_mip_store();
+        for _ in 0..mip_stats_config.block_count_considered {
+            mip_store.update_network_version_stats(
+                mip_vm_v1_start.saturating_add(MassaTime::from_millis(1_000)),
+                Some((0, 1)),
+            );
+        }
+        mip_store.update_network_version_stats(
+            mip_vm_v1_start.saturating_add(MassaTime::from_millis(2_000)),
+            Some((0, 1)),
+        );
+        mip_store.update_network_version_stats(
+            mip_vm_v1_start
+                .saturating_add(activation_delay)
+                .saturating_add(MassaTime::from_millis(3_000)),
+            Some((0, 1)),
+        );
+        let data = run_event_test(mip_store);
+        assert!(data.ends_with(EVENT_TRUNCATION_MARKER));
+        assert!(data.len() <= EVENT_TRUNCATION_MARKER.len().max(exec_cfg.max_event_size));
+    }
+
     /// Feeds the execution worker with genesis blocks to start it
     fn init_execution_worker(
         config: &ExecutionConfig,
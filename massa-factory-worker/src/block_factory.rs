@@ -251,6 +251,30 @@ impl BlockFactoryWorker {
             .register_block(block_id, slot, block_storage, true);
     }
 
+    /// Tell protocol ahead of time that one of the managed keys produces the block of that slot,
+    /// so that it holds the low-priority gossip around the production.
+    fn announce_production(&self, slot: Slot) {
+        let block_producer_addr = match self.channels.selector.get_producer(slot) {
+            Ok(addr) => addr,
+            Err(_) => return,
+        };
+        if self
+            .wallet
+            .read()
+            .find_associated_keypair(&block_producer_addr)
+            .is_none()
+        {
+            return;
+        }
+        if let Err(err) = self
+            .channels
+            .protocol
+            .set_production_slots(vec![slot], Vec::new())
+        {
+            warn!("could not announce block production to protocol: {}", err);
+        }
+    }
+
     /// main run loop of the block creator thread
     fn run(&mut self) {
         let mut prev_slot = None;
@@ -258,6 +282,9 @@ impl BlockFactoryWorker {
             // get next slot
             let (slot, block_instant) = self.get_next_slot(prev_slot);
 
+            // announce our production
+            self.announce_production(slot);
+
             // wait until slot
             if !self.interruptible_wait_until(block_instant) {
                 break;
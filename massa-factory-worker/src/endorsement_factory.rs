@@ -202,6 +202,32 @@ impl EndorsementFactoryWorker {
         }
     }
 
+    /// Tell protocol ahead of time that one of the managed keys endorses at that slot, so that
+    /// it holds the low-priority gossip around the production.
+    fn announce_production(&self, slot: Slot) {
+        let producer_addrs = match self.channels.selector.get_selection(slot) {
+            Ok(sel) => sel.endorsements,
+            Err(_) => return,
+        };
+        let wallet = self.wallet.read();
+        if !producer_addrs
+            .iter()
+            .any(|addr| wallet.find_associated_keypair(addr).is_some())
+        {
+            return;
+        }
+        if let Err(err) = self
+            .channels
+            .protocol
+            .set_production_slots(Vec::new(), vec![slot])
+        {
+            warn!(
+                "could not announce endorsement production to protocol: {}",
+                err
+            );
+        }
+    }
+
     /// main run loop of the endorsement creator thread
     fn run(&mut self) {
         let mut prev_slot = None;
@@ -209,6 +235,9 @@ impl EndorsementFactoryWorker {
             // get next slot
             let (slot, endorsement_instant) = self.get_next_slot(prev_slot);
 
+            // announce our production
+            self.announce_production(slot);
+
             // wait until slot
             if !self.interruptible_wait_until(endorsement_instant) {
                 break;
@@ -58,7 +58,10 @@ impl TestFactory {
         let (pool_controller, pool_receiver) = MockPoolController::new_with_receiver();
         let mut storage = Storage::create_root();
         let mut factory_config = FactoryConfig::default();
-        let protocol_controller = MockProtocolController::new();
+        let mut protocol_controller = MockProtocolController::new();
+        protocol_controller
+            .expect_set_production_slots()
+            .returning(|_, _| Ok(()));
         let producer_keypair = default_keypair;
         let producer_address = Address::from_public_key(&producer_keypair.get_public_key());
         let mut accounts = PreHashMap::default();
@@ -15,7 +15,7 @@ use massa_executed_ops::{
     ExecutedOps, ExecutedOpsDeserializer, ExecutedOpsSerializer,
 };
 use massa_hash::{Hash, HashDeserializer, HASH_SIZE_BYTES};
-use massa_ledger_exports::{Key as LedgerKey, LedgerChanges, LedgerController};
+use massa_ledger_exports::{Key as LedgerKey, LedgerChanges, LedgerController, LedgerProof};
 use massa_models::denunciation::DenunciationIndex;
 use massa_models::{
     // TODO: uncomment when deserializing the final state from ledger
@@ -37,7 +37,7 @@ use massa_serialization::{Deserializer, SerializeError, Serializer};
 use nom::{error::context, sequence::tuple, IResult, Parser};
 use std::collections::{BTreeMap, HashSet, VecDeque};
 use std::ops::Bound::{Excluded, Included};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 /// Represents a final state `(ledger, async pool, executed_ops, executed_de and the state of the PoS)`
 pub struct FinalState {
@@ -69,6 +69,45 @@ pub struct FinalState {
 
 const FINAL_STATE_HASH_INITIAL_BYTES: &[u8; 32] = &[0; HASH_SIZE_BYTES];
 
+/// Hashes of the components of a final state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinalStateHashes {
+    /// ledger hash
+    pub ledger: Hash,
+    /// async pool hash
+    pub async_pool: Hash,
+    /// PoS deferred credits hash
+    pub deferred_credits: Hash,
+    /// global hashes of the hashed PoS cycles, the bootstrap safety cycle excluded
+    pub cycle_history: Vec<Hash>,
+    /// executed operations hash
+    pub executed_ops: Hash,
+    /// executed denunciations hash
+    pub executed_denunciations: Hash,
+}
+
+impl FinalStateHashes {
+    /// Compute the final state hash from the hashes of its components
+    pub fn final_state_hash(&self) -> Hash {
+        // 1. init hash concatenation with the ledger hash
+        let mut hash_concat: Vec<u8> = self.ledger.to_bytes().to_vec();
+        // 2. async_pool hash
+        hash_concat.extend(self.async_pool.to_bytes());
+        // 3. pos deferred_credit hash
+        hash_concat.extend(self.deferred_credits.to_bytes());
+        // 4. pos cycle history hashes
+        for cycle_hash in &self.cycle_history {
+            hash_concat.extend(cycle_hash.to_bytes());
+        }
+        // 5. executed operations hash
+        hash_concat.extend(self.executed_ops.to_bytes());
+        // 6. executed denunciations hash
+        hash_concat.extend(self.executed_denunciations.to_bytes());
+        // 7. compute the final state hash
+        Hash::compute_from(&hash_concat)
+    }
+}
+
 impl FinalState {
     /// Initializes a new `FinalState`
     ///
@@ -395,37 +434,55 @@ impl FinalState {
     /// Used when finalizing a slot.
     /// Slot information is only used for logging.
     pub fn compute_state_hash_at_slot(&mut self, slot: Slot) {
-        // 1. init hash concatenation with the ledger hash
-        let ledger_hash = self.ledger.get_ledger_hash();
-        let mut hash_concat: Vec<u8> = ledger_hash.to_bytes().to_vec();
-        // 2. async_pool hash
-        hash_concat.extend(self.async_pool.hash.to_bytes());
-        // 3. pos deferred_credit hash
-        let deferred_credit_hash = match self.pos_state.deferred_credits.get_hash() {
-            Some(hash) => hash,
-            None => self
+        // enable the deferred credits hash tracker so that the hash is maintained from now on
+        self.pos_state
+            .deferred_credits
+            .enable_hash_tracker_and_compute_hash();
+        self.final_state_hash = self.get_component_hashes().final_state_hash();
+
+        info!(
+            "final_state hash at slot {}: {}",
+            slot, self.final_state_hash
+        );
+    }
+
+    /// Get the hashes of the components of the current state.
+    ///
+    /// If the deferred credits hash tracker is not enabled, the deferred credits hash is computed on a copy.
+    pub fn get_component_hashes(&self) -> FinalStateHashes {
+        let deferred_credits = match self.pos_state.deferred_credits.get_hash() {
+            Some(hash) => *hash,
+            None => *self
                 .pos_state
                 .deferred_credits
+                .clone()
                 .enable_hash_tracker_and_compute_hash(),
         };
-        hash_concat.extend(deferred_credit_hash.to_bytes());
-        // 4. pos cycle history hashes, skip the bootstrap safety cycle if there is one
+        // skip the bootstrap safety cycle if there is one
         let n = (self.pos_state.cycle_history.len() == self.config.pos_config.cycle_history_length)
             as usize;
-        for cycle_info in self.pos_state.cycle_history.iter().skip(n) {
-            hash_concat.extend(cycle_info.cycle_global_hash.to_bytes());
+        FinalStateHashes {
+            ledger: self.ledger.get_ledger_hash(),
+            async_pool: self.async_pool.hash,
+            deferred_credits,
+            cycle_history: self
+                .pos_state
+                .cycle_history
+                .iter()
+                .skip(n)
+                .map(|cycle_info| cycle_info.cycle_global_hash)
+                .collect(),
+            executed_ops: self.executed_ops.ops.hash,
+            executed_denunciations: self.executed_denunciations.denunciations.hash,
         }
-        // 5. executed operations hash
-        hash_concat.extend(self.executed_ops.hash.to_bytes());
-        // 6. executed denunciations hash
-        hash_concat.extend(self.executed_denunciations.hash.to_bytes());
-        // 7. compute and save final state hash
-        self.final_state_hash = Hash::compute_from(&hash_concat);
+    }
 
-        info!(
-            "final_state hash at slot {}: {}",
-            slot, self.final_state_hash
-        );
+    /// Get the values of the given ledger keys with the proof that they match
+    /// the ledger hash of `get_component_hashes`.
+    ///
+    /// Used by the light bootstrap, which serves the proof along with the signed state manifest.
+    pub fn get_ledger_proof(&self, keys: &[LedgerKey]) -> LedgerProof {
+        self.ledger.get_ledger_proof(keys)
     }
 
     /// Performs the initial draws.
@@ -439,6 +496,7 @@ impl FinalState {
     /// Once this is called, the state is attached at the output of the provided slot.
     ///
     /// Panics if the new slot is not the one coming just after the current one.
+    /// Panics if the executed operations changes conflict with the recorded ones.
     pub fn finalize(&mut self, slot: Slot, changes: StateChanges) {
         // check slot consistency
         let next_slot = self
@@ -461,8 +519,12 @@ impl FinalState {
         // TODO:
         // do not panic above, it might just mean that the lookback cycle is not available
         // bootstrap again instead
+        // changes that conflict with the recorded executed operations mean this node diverged
+        // from the network: settling the slot without them would fork the final state
         self.executed_ops
-            .apply_changes(changes.executed_ops_changes.clone(), self.slot);
+            .apply_changes(changes.executed_ops_changes.clone(), self.slot)
+            .expect("critical: could not apply executed operations changes in final state");
+        self.executed_ops.prune_pending();
         self.executed_denunciations
             .apply_changes(changes.executed_denunciations_changes.clone(), self.slot);
 
@@ -477,7 +539,7 @@ impl FinalState {
                 async_pool_messages: self.async_pool.messages.clone(),
                 cycle_history: self.pos_state.cycle_history.clone(),
                 deferred_credits: self.pos_state.deferred_credits.clone(),
-                sorted_ops: self.executed_ops.sorted_ops.clone(),
+                sorted_ops: self.executed_ops.ops.sorted.as_ref().clone(),
                 latest_consistent_slot: self.slot,
                 final_state_hash_from_snapshot: self.final_state_hash,
             };
@@ -538,7 +600,8 @@ impl FinalState {
     /// * proof-of-stake deferred credits change if main bootstrap process is finished
     /// * proof-of-stake deferred credits change that is after `slot` and before or equal to `credits_step` slot
     /// * proof-of-stake cycle history change if main bootstrap process is finished
-    /// * executed ops change if main bootstrap process is finished
+    /// * executed ops changes, as the executed ops parts of a bootstrap session are served
+    ///   from a view frozen when it started
     ///
     /// Produces an error when the `slot` is too old for `self.changes_history`
     #[allow(clippy::too_many_arguments)]
@@ -549,7 +612,6 @@ impl FinalState {
         pool_step: StreamingStep<AsyncMessageId>,
         cycle_step: StreamingStep<u64>,
         credits_step: StreamingStep<Slot>,
-        ops_step: StreamingStep<Slot>,
         de_step: StreamingStep<Slot>,
     ) -> Result<Vec<(Slot, StateChanges)>, FinalStateError> {
         let position_slot = if let Some((first_slot, _)) = self.changes_history.front() {
@@ -663,10 +725,8 @@ impl FinalState {
                     changes.pos_changes.production_stats.clone();
             }
 
-            // Get executed operations changes if executed ops main bootstrap finished
-            if ops_step.finished() {
-                slot_changes.executed_ops_changes = changes.executed_ops_changes.clone();
-            }
+            // Get executed operations changes, which are not in the frozen executed ops parts
+            slot_changes.executed_ops_changes = changes.executed_ops_changes.clone();
             if de_step.finished() {
                 slot_changes.executed_denunciations_changes =
                     changes.executed_denunciations_changes.clone();
@@ -701,8 +761,13 @@ impl From<FinalState> for FinalStateRaw {
             async_pool_messages: value.async_pool.messages,
             cycle_history: value.pos_state.cycle_history,
             deferred_credits: value.pos_state.deferred_credits,
-            sorted_ops: value.executed_ops.sorted_ops,
-            sorted_denunciations: value.executed_denunciations.sorted_denunciations,
+            sorted_ops: value.executed_ops.ops.sorted.as_ref().clone(),
+            sorted_denunciations: value
+                .executed_denunciations
+                .denunciations
+                .sorted
+                .as_ref()
+                .clone(),
             latest_consistent_slot: value.slot,
             final_state_hash_from_snapshot: value.final_state_hash,
         }
@@ -47,7 +47,7 @@ mod state_changes;
 
 pub use config::FinalStateConfig;
 pub use error::FinalStateError;
-pub use final_state::FinalState;
+pub use final_state::{FinalState, FinalStateHashes};
 pub use state_changes::{StateChanges, StateChangesDeserializer, StateChangesSerializer};
 
 #[cfg(test)]
@@ -60,8 +60,8 @@ pub fn assert_eq_final_state(v1: &FinalState, v2: &FinalState) {
         "executed_ops.ops mismatch"
     );
     assert_eq!(
-        v1.executed_ops.sorted_ops, v2.executed_ops.sorted_ops,
-        "executed_ops.sorted_ops mismatch"
+        v1.executed_ops.ops.sorted, v2.executed_ops.ops.sorted,
+        "executed_ops.ops.sorted mismatch"
     );
 }
 
@@ -104,7 +104,7 @@ pub fn assert_eq_final_state_hash(v1: &FinalState, v2: &FinalState) {
         );
     }
     assert_eq!(
-        v1.executed_ops.hash, v2.executed_ops.hash,
+        v1.executed_ops.ops.hash, v2.executed_ops.ops.hash,
         "executed ops hash mismatch"
     );
 }
@@ -8,6 +8,7 @@ use crate::{FinalState, FinalStateConfig};
 use massa_async_pool::{AsyncPool, AsyncPoolConfig};
 use massa_executed_ops::{
     ExecutedDenunciations, ExecutedDenunciationsConfig, ExecutedOps, ExecutedOpsConfig,
+    ExecutedOpsHashScheme, ExecutedOpsPruningMode,
 };
 use massa_hash::{Hash, HASH_SIZE_BYTES};
 use massa_ledger_exports::LedgerConfig;
@@ -18,7 +19,8 @@ use massa_models::config::{
 };
 use massa_models::{
     config::{
-        DEFERRED_CREDITS_BOOTSTRAP_PART_SIZE, EXECUTED_OPS_BOOTSTRAP_PART_SIZE, PERIODS_PER_CYCLE,
+        DEFERRED_CREDITS_BOOTSTRAP_PART_SIZE, EXECUTED_OPS_BOOTSTRAP_PART_SIZE,
+        EXECUTED_OPS_BOOTSTRAP_PART_SIZE_BYTES, OPERATION_VALIDITY_PERIODS, PERIODS_PER_CYCLE,
         POS_SAVED_CYCLES, THREAD_COUNT,
     },
     slot::Slot,
@@ -54,6 +56,11 @@ impl Default for FinalStateConfig {
             executed_ops_config: ExecutedOpsConfig {
                 thread_count: THREAD_COUNT,
                 bootstrap_part_size: EXECUTED_OPS_BOOTSTRAP_PART_SIZE,
+                bootstrap_part_size_bytes: EXECUTED_OPS_BOOTSTRAP_PART_SIZE_BYTES,
+                retention_periods: 0,
+                operation_validity_periods: OPERATION_VALIDITY_PERIODS,
+                pruning_mode: ExecutedOpsPruningMode::Immediate,
+                hash_scheme: ExecutedOpsHashScheme::Xor,
             },
             executed_denunciations_config: ExecutedDenunciationsConfig {
                 denunciation_expire_periods: DENUNCIATION_EXPIRE_PERIODS,
@@ -0,0 +1,254 @@
+//! Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Deterministic ordering audit.
+//!
+//! Every consensus-relevant structure of the final state is fed with fixed fixtures,
+//! applied in several orders, and the produced hashes (or selection orders) are compared
+//! against golden values committed below. A mismatch means that an iteration order or a
+//! serialization changed with the platform or a dependency update, which would fork the network.
+//!
+//! Golden values must only be updated along with an intentional, network-wide format change.
+
+use massa_async_pool::{AsyncMessage, AsyncPool, AsyncPoolChanges, AsyncPoolConfig, Change};
+use massa_executed_ops::{
+    ExecutedOps, ExecutedOpsChanges, ExecutedOpsConfig, ExecutedOpsHashScheme,
+    ExecutedOpsPruningMode,
+};
+use massa_hash::Hash;
+use massa_ledger_exports::{
+    LedgerChanges, LedgerConfig, LedgerController, LedgerEntry, LedgerEntryUpdate, SetOrDelete,
+    SetOrKeep, SetUpdateOrDelete,
+};
+use massa_ledger_worker::FinalLedger;
+use massa_models::{
+    address::{Address, UserAddress},
+    amount::Amount,
+    bytecode::Bytecode,
+    operation::OperationId,
+    secure_share::Id,
+    slot::Slot,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+/// Ledger hash after the creation of the fixture entries
+const GOLDEN_LEDGER_HASH_STEP_1: &str = "r5SQbuDEfbYq5r9YG7ZRofWrmG6kwz9A82PHp9KaLc1WNqaTi";
+/// Ledger hash after the updates and deletions of the fixture entries
+const GOLDEN_LEDGER_HASH_STEP_2: &str = "j2b6Z6HKT4TnrSBnWPcoG4QrkZVJibieMVkKbBsJ5JPyEr2Pu";
+/// Executed ops hash after the insertion of all the fixture operations
+const GOLDEN_EXECUTED_OPS_HASH: &str = "WcmM7sw8oRynPsPjXHD4A9XDAP2VK9aqc2rfVquib3AcHTDFU";
+/// Executed ops hash after the pruning of the operations expiring at period 1
+const GOLDEN_EXECUTED_OPS_PRUNED_HASH: &str = "t8dsLfrMWx1p7avWnaPu9bEWNEYcXLDSS6QcuNcbJCCU7HF68";
+/// Emission indexes of the fixture async messages, in execution order
+const GOLDEN_ASYNC_SELECTION: [u64; 5] = [6, 4, 0, 2, 1];
+
+/// Orders in which the fixture items are applied
+fn permutations(len: usize) -> Vec<Vec<usize>> {
+    let forward: Vec<usize> = (0..len).collect();
+    let backward: Vec<usize> = (0..len).rev().collect();
+    let interleaved: Vec<usize> = (0..len)
+        .step_by(2)
+        .chain((1..len).step_by(2).rev())
+        .collect();
+    vec![forward, backward, interleaved]
+}
+
+fn assert_golden(name: &str, hash: Hash, golden: &str) {
+    assert_eq!(
+        hash,
+        Hash::from_str(golden).unwrap(),
+        "ordering audit: {} hash differs from its golden value",
+        name
+    );
+}
+
+fn fixture_address(index: u8) -> Address {
+    Address::User(UserAddress(Hash::compute_from(
+        format!("determinism-address-{}", index).as_bytes(),
+    )))
+}
+
+fn fixture_ledger_entry(index: u8) -> LedgerEntry {
+    LedgerEntry {
+        balance: Amount::from_raw(1000 * (index as u64 + 1)),
+        bytecode: Bytecode(vec![index; 3]),
+        datastore: (0..3)
+            .map(|key| {
+                (
+                    format!("key-{}", key).into_bytes(),
+                    format!("value-{}-{}", index, key).into_bytes(),
+                )
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn test_ledger_changes_ordering() {
+    for order in permutations(4) {
+        let (config, _initial_ledger, _disk_ledger) = LedgerConfig::sample(&HashMap::new());
+        let mut ledger = FinalLedger::new(config, false);
+
+        let mut changes = LedgerChanges::default();
+        for index in order.iter() {
+            let index = *index as u8;
+            changes.0.insert(
+                fixture_address(index),
+                SetUpdateOrDelete::Set(fixture_ledger_entry(index)),
+            );
+        }
+        ledger.apply_changes(changes, Slot::new(1, 0), None);
+        assert_golden(
+            "ledger (creation)",
+            ledger.get_ledger_hash(),
+            GOLDEN_LEDGER_HASH_STEP_1,
+        );
+
+        let mut step_changes = vec![
+            (
+                fixture_address(0),
+                SetUpdateOrDelete::Update(LedgerEntryUpdate {
+                    balance: SetOrKeep::Set(Amount::from_raw(7)),
+                    bytecode: SetOrKeep::Keep,
+                    datastore: BTreeMap::from([
+                        (b"key-0".to_vec(), SetOrDelete::Set(b"updated".to_vec())),
+                        (b"key-1".to_vec(), SetOrDelete::Delete),
+                        (b"key-9".to_vec(), SetOrDelete::Set(b"new".to_vec())),
+                    ]),
+                }),
+            ),
+            (fixture_address(1), SetUpdateOrDelete::Delete),
+            (
+                fixture_address(4),
+                SetUpdateOrDelete::Update(LedgerEntryUpdate {
+                    balance: SetOrKeep::Set(Amount::from_raw(5)),
+                    ..Default::default()
+                }),
+            ),
+        ];
+        if order[0] != 0 {
+            step_changes.reverse();
+        }
+        let mut changes = LedgerChanges::default();
+        for (address, change) in step_changes {
+            changes.0.insert(address, change);
+        }
+        ledger.apply_changes(changes, Slot::new(2, 0), None);
+        assert_golden(
+            "ledger (update)",
+            ledger.get_ledger_hash(),
+            GOLDEN_LEDGER_HASH_STEP_2,
+        );
+    }
+}
+
+#[test]
+fn test_executed_ops_ordering() {
+    let ops: Vec<(OperationId, Slot)> = (0..12u64)
+        .map(|index| {
+            (
+                OperationId::new(Hash::compute_from(
+                    format!("determinism-op-{}", index).as_bytes(),
+                )),
+                Slot::new(1 + index / 4, (index % 4) as u8),
+            )
+        })
+        .collect();
+    for (batch_size, order) in [1, 3, 12].into_iter().zip(permutations(ops.len())) {
+        let mut executed_ops = ExecutedOps::new(ExecutedOpsConfig {
+            thread_count: 32,
+            bootstrap_part_size: 10,
+            bootstrap_part_size_bytes: 100_000,
+            retention_periods: 0,
+            operation_validity_periods: 100,
+            pruning_mode: ExecutedOpsPruningMode::Immediate,
+            hash_scheme: ExecutedOpsHashScheme::Xor,
+        });
+        for batch in order.chunks(batch_size) {
+            let changes: ExecutedOpsChanges = batch
+                .iter()
+                .map(|index| (ops[*index].0, (true, ops[*index].1)))
+                .collect();
+            executed_ops
+                .apply_changes(changes, Slot::new(1, 0))
+                .unwrap();
+        }
+        assert_golden(
+            "executed ops (insertion)",
+            executed_ops.ops.hash,
+            GOLDEN_EXECUTED_OPS_HASH,
+        );
+
+        executed_ops
+            .apply_changes(ExecutedOpsChanges::default(), Slot::new(2, 0))
+            .unwrap();
+        assert_golden(
+            "executed ops (pruning)",
+            executed_ops.ops.hash,
+            GOLDEN_EXECUTED_OPS_PRUNED_HASH,
+        );
+    }
+}
+
+#[test]
+fn test_async_message_selection_ordering() {
+    // (emission slot, emission index, fee, max gas, validity end)
+    let fixtures = [
+        (Slot::new(1, 0), 0, 100, 10, Slot::new(10, 0)),
+        (Slot::new(1, 0), 1, 50, 10, Slot::new(10, 0)),
+        (Slot::new(1, 0), 2, 20, 2, Slot::new(10, 0)),
+        (Slot::new(1, 0), 3, 300, 100, Slot::new(10, 0)),
+        (Slot::new(0, 1), 4, 10, 1, Slot::new(10, 0)),
+        (Slot::new(1, 0), 5, 1000, 50, Slot::new(1, 0)),
+        (Slot::new(1, 0), 6, 90, 3, Slot::new(10, 0)),
+    ];
+    let messages: Vec<AsyncMessage> = fixtures
+        .iter()
+        .map(|(emission_slot, index, fee, max_gas, validity_end)| {
+            AsyncMessage::new_with_hash(
+                *emission_slot,
+                *index,
+                fixture_address(0),
+                fixture_address(1),
+                "handler".to_string(),
+                *max_gas,
+                Amount::from_raw(*fee),
+                Amount::from_raw(0),
+                Slot::new(0, 0),
+                *validity_end,
+                vec![*index as u8],
+                None,
+            )
+        })
+        .collect();
+
+    let mut pool_hash = None;
+    for order in permutations(messages.len()) {
+        let mut pool = AsyncPool::new(AsyncPoolConfig::default());
+        let changes = AsyncPoolChanges(
+            order
+                .iter()
+                .map(|index| {
+                    let message = messages[*index].clone();
+                    Change::Add(message.compute_id(), message)
+                })
+                .collect(),
+        );
+        pool.apply_changes_unchecked(&changes);
+        assert_eq!(
+            *pool_hash.get_or_insert(pool.hash),
+            pool.hash,
+            "ordering audit: async pool hash depends on the insertion order"
+        );
+
+        let selected: Vec<u64> = pool
+            .take_batch_to_execute(Slot::new(2, 0), 30)
+            .into_iter()
+            .map(|(_, message)| message.emission_index)
+            .collect();
+        assert_eq!(
+            selected, GOLDEN_ASYNC_SELECTION,
+            "ordering audit: async message selection differs from its golden value"
+        );
+    }
+}
@@ -0,0 +1,441 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+use crate::error::GrpcError;
+use crate::server::MassaGrpc;
+use massa_models::node::NodeId;
+use massa_proto::massa::api::v1 as grpc;
+use massa_protocol_exports::{
+    BootstrapPeerInfo, BootstrapPeers, OutConnectionTargets, PeerAnnotation, PeerConnectionType,
+    PeerId, PeerTrafficStats, TransportType,
+};
+use massa_signature::{PublicKey, PUBLIC_KEY_SIZE_BYTES};
+use massa_time::MassaTime;
+use rand::RngCore;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::str::FromStr;
+use tonic::metadata::MetadataValue;
+use tracing::log::info;
+
+/// Number of random bytes of a generated admin token
+const ADMIN_TOKEN_SIZE_BYTES: usize = 32;
+
+/// Read the admin token from `path`, or generate one and write it there, readable by the
+/// node operator only
+pub fn load_or_create_admin_token(path: &Path) -> Result<String, GrpcError> {
+    if path.is_file() {
+        let token = std::fs::read_to_string(path).map_err(|e| {
+            GrpcError::InternalServerError(format!("could not read the admin token: {}", e))
+        })?;
+        return Ok(token.trim().to_string());
+    }
+    let mut bytes = [0u8; ADMIN_TOKEN_SIZE_BYTES];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let token: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    write_private_file(path, &token).map_err(|e| {
+        GrpcError::InternalServerError(format!("could not write the admin token: {}", e))
+    })?;
+    info!("gRPC admin token written to {}", path.display());
+    Ok(token)
+}
+
+#[cfg(unix)]
+fn write_private_file(path: &Path, content: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(content.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn write_private_file(path: &Path, content: &str) -> std::io::Result<()> {
+    std::fs::write(path, content)
+}
+
+/// Rejects the admin requests that don't carry the admin token
+#[derive(Clone)]
+pub struct AdminAuthInterceptor {
+    expected: MetadataValue<tonic::metadata::Ascii>,
+}
+
+impl AdminAuthInterceptor {
+    /// Creates an interceptor accepting the requests authenticated with `token`
+    pub fn new(token: &str) -> Result<Self, GrpcError> {
+        let expected = format!("Bearer {}", token).parse().map_err(|_| {
+            GrpcError::InternalServerError("the admin token is not valid ASCII".to_string())
+        })?;
+        Ok(Self { expected })
+    }
+}
+
+impl tonic::service::Interceptor for AdminAuthInterceptor {
+    fn call(&mut self, request: tonic::Request<()>) -> Result<tonic::Request<()>, tonic::Status> {
+        match request.metadata().get("authorization") {
+            Some(value) if constant_time_eq(value.as_bytes(), self.expected.as_bytes()) => {
+                Ok(request)
+            }
+            _ => Err(tonic::Status::unauthenticated(
+                "invalid or missing admin token",
+            )),
+        }
+    }
+}
+
+/// Compares two byte strings in a time that doesn't depend on where they differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+//TODO: Change when unify node id and peer id
+fn peer_id_from_node_id(node_id: &str) -> Result<PeerId, GrpcError> {
+    let node_id = NodeId::from_str(node_id)
+        .map_err(|_| GrpcError::InvalidArgument(format!("invalid node id: {}", node_id)))?;
+    PeerId::from_bytes(
+        node_id.get_public_key().to_bytes()[..PUBLIC_KEY_SIZE_BYTES]
+            .try_into()
+            .unwrap(),
+    )
+    .map_err(|e| GrpcError::InvalidArgument(format!("invalid node id: {}", e)))
+}
+
+fn node_id_from_peer_id(peer_id: &PeerId) -> String {
+    NodeId::new(
+        PublicKey::from_bytes(
+            peer_id.to_bytes()[..PUBLIC_KEY_SIZE_BYTES]
+                .try_into()
+                .unwrap(),
+        )
+        .unwrap(),
+    )
+    .to_string()
+}
+
+fn transport_from_str(transport: &str) -> Result<TransportType, GrpcError> {
+    match transport {
+        "Tcp" => Ok(TransportType::Tcp),
+        "Quic" => Ok(TransportType::Quic),
+        _ => Err(GrpcError::InvalidArgument(format!(
+            "invalid transport: {}",
+            transport
+        ))),
+    }
+}
+
+/// Ban peers
+pub(crate) fn ban_peer(
+    grpc: &MassaGrpc,
+    request: tonic::Request<grpc::BanPeerRequest>,
+) -> Result<grpc::BanPeerResponse, GrpcError> {
+    let inner_req = request.into_inner();
+    let peer_ids = inner_req
+        .node_ids
+        .iter()
+        .map(|node_id| peer_id_from_node_id(node_id))
+        .collect::<Result<_, _>>()?;
+    grpc.protocol_command_sender.ban_peers(peer_ids)?;
+    Ok(grpc::BanPeerResponse { id: inner_req.id })
+}
+
+/// Unban peers
+pub(crate) fn unban_peer(
+    grpc: &MassaGrpc,
+    request: tonic::Request<grpc::UnbanPeerRequest>,
+) -> Result<grpc::UnbanPeerResponse, GrpcError> {
+    let inner_req = request.into_inner();
+    let peer_ids = inner_req
+        .node_ids
+        .iter()
+        .map(|node_id| peer_id_from_node_id(node_id))
+        .collect::<Result<_, _>>()?;
+    grpc.protocol_command_sender.unban_peers(peer_ids)?;
+    Ok(grpc::UnbanPeerResponse { id: inner_req.id })
+}
+
+/// List the connected peers
+pub(crate) fn list_peers(
+    grpc: &MassaGrpc,
+    request: tonic::Request<grpc::ListPeersRequest>,
+) -> Result<grpc::ListPeersResponse, GrpcError> {
+    let (_, peers) = grpc.protocol_command_sender.get_stats()?;
+    let peers = peers
+        .iter()
+        .map(|(peer_id, connection)| grpc::PeerConnection {
+            node_id: node_id_from_peer_id(peer_id),
+            address: connection.address.to_string(),
+            is_outgoing: connection.connection_type == PeerConnectionType::OUT,
+            transport: connection
+                .transport
+                .map(|transport| format!("{:?}", transport)),
+            connected_since: connection.connected_since.map(|time| time.to_millis()),
+            traffic: Some(peer_traffic(&connection.traffic)),
+            tags: connection
+                .annotation
+                .as_ref()
+                .map(|annotation| annotation.tags.iter().cloned().collect())
+                .unwrap_or_default(),
+            note: connection
+                .annotation
+                .as_ref()
+                .and_then(|annotation| annotation.note.clone()),
+        })
+        .collect();
+    Ok(grpc::ListPeersResponse {
+        id: request.into_inner().id,
+        peers,
+    })
+}
+
+fn peer_traffic(traffic: &PeerTrafficStats) -> grpc::PeerTraffic {
+    grpc::PeerTraffic {
+        block_messages: traffic.block_messages,
+        endorsement_messages: traffic.endorsement_messages,
+        operation_messages: traffic.operation_messages,
+        peer_management_messages: traffic.peer_management_messages,
+        bytes_received: traffic.bytes_received,
+        bytes_sent: traffic.bytes_sent,
+        invalid_messages: traffic.invalid_messages,
+        last_block_id: traffic.last_block.map(|(block_id, _)| block_id.to_string()),
+        last_block_period: traffic.last_block.map(|(_, slot)| slot.period),
+        last_block_thread: traffic.last_block.map(|(_, slot)| u32::from(slot.thread)),
+    }
+}
+
+/// Test a peer address right away
+pub(crate) fn test_peer(
+    grpc: &MassaGrpc,
+    request: tonic::Request<grpc::TestPeerRequest>,
+) -> Result<grpc::TestPeerResponse, GrpcError> {
+    let inner_req = request.into_inner();
+    let address = SocketAddr::from_str(&inner_req.address).map_err(|_| {
+        GrpcError::InvalidArgument(format!("invalid address: {}", inner_req.address))
+    })?;
+    let result = grpc.protocol_command_sender.test_peer(address)?;
+    let (node_id, error) = match &result.handshake {
+        Ok(peer_id) => (Some(node_id_from_peer_id(peer_id)), None),
+        Err(err) => (None, Some(err.clone())),
+    };
+    Ok(grpc::TestPeerResponse {
+        id: inner_req.id,
+        address: result.address.to_string(),
+        reachable: result.reachable,
+        node_id,
+        error,
+        latency: result.latency.map(|latency| latency.to_millis()),
+    })
+}
+
+/// Set the number of out connections to maintain per category
+pub(crate) fn set_targets(
+    grpc: &MassaGrpc,
+    request: tonic::Request<grpc::SetTargetsRequest>,
+) -> Result<grpc::SetTargetsResponse, GrpcError> {
+    let inner_req = request.into_inner();
+    let targets = OutConnectionTargets {
+        categories: inner_req
+            .categories
+            .into_iter()
+            .map(|category| (category.category, category.target as usize))
+            .collect(),
+        default: inner_req.default_target as usize,
+    };
+    grpc.protocol_command_sender
+        .set_out_connection_targets(targets)?;
+    Ok(grpc::SetTargetsResponse { id: inner_req.id })
+}
+
+/// Export the known peers with their listeners
+pub(crate) fn export_peers(
+    grpc: &MassaGrpc,
+    request: tonic::Request<grpc::ExportPeersRequest>,
+) -> Result<grpc::ExportPeersResponse, GrpcError> {
+    let peers = grpc.protocol_command_sender.get_bootstrap_peers()?;
+    let peers = peers
+        .0
+        .into_iter()
+        .map(|(peer_id, info)| grpc::ExportedPeer {
+            node_id: node_id_from_peer_id(&peer_id),
+            listeners: info
+                .listeners
+                .into_iter()
+                .map(|(address, transport)| grpc::PeerListener {
+                    address: address.to_string(),
+                    transport: format!("{:?}", transport),
+                })
+                .collect(),
+            category: info.category,
+            last_seen: info.last_seen.to_millis(),
+        })
+        .collect();
+    Ok(grpc::ExportPeersResponse {
+        id: request.into_inner().id,
+        peers,
+    })
+}
+
+/// Import peers to test, as exported by another node
+pub(crate) fn import_peers(
+    grpc: &MassaGrpc,
+    request: tonic::Request<grpc::ImportPeersRequest>,
+) -> Result<grpc::ImportPeersResponse, GrpcError> {
+    let inner_req = request.into_inner();
+    let peers = inner_req
+        .peers
+        .into_iter()
+        .map(|peer| {
+            let listeners = peer
+                .listeners
+                .iter()
+                .map(|listener| {
+                    let address = SocketAddr::from_str(&listener.address).map_err(|_| {
+                        GrpcError::InvalidArgument(format!("invalid address: {}", listener.address))
+                    })?;
+                    Ok((address, transport_from_str(&listener.transport)?))
+                })
+                .collect::<Result<HashMap<_, _>, GrpcError>>()?;
+            Ok((
+                peer_id_from_node_id(&peer.node_id)?,
+                BootstrapPeerInfo {
+                    listeners,
+                    category: peer.category,
+                    last_seen: MassaTime::from_millis(peer.last_seen),
+                },
+            ))
+        })
+        .collect::<Result<_, GrpcError>>()?;
+    grpc.protocol_command_sender
+        .import_peers(BootstrapPeers(peers))?;
+    Ok(grpc::ImportPeersResponse { id: inner_req.id })
+}
+
+/// Export the signed reports of the bans proven by a message of the banned peer
+pub(crate) fn export_ban_reports(
+    grpc: &MassaGrpc,
+    request: tonic::Request<grpc::ExportBanReportsRequest>,
+) -> Result<grpc::ExportBanReportsResponse, GrpcError> {
+    let reports = grpc
+        .protocol_command_sender
+        .export_ban_reports()?
+        .into_iter()
+        .map(|report| grpc::ExportedBanReport {
+            node_id: node_id_from_peer_id(&report.peer_id),
+            kind: report.kind.to_string(),
+            timestamp: report.timestamp.to_millis(),
+            reporter: node_id_from_peer_id(&report.reporter),
+            report: report.signed,
+        })
+        .collect();
+    Ok(grpc::ExportBanReportsResponse {
+        id: request.into_inner().id,
+        reports,
+    })
+}
+
+/// Ban the peers of reports exported by another node, once their offense is checked again
+pub(crate) fn import_ban_reports(
+    grpc: &MassaGrpc,
+    request: tonic::Request<grpc::ImportBanReportsRequest>,
+) -> Result<grpc::ImportBanReportsResponse, GrpcError> {
+    let inner_req = request.into_inner();
+    let reports = inner_req
+        .reports
+        .into_iter()
+        .map(|report| report.report)
+        .collect();
+    let results = grpc
+        .protocol_command_sender
+        .import_ban_reports(reports)?
+        .into_iter()
+        .map(|result| match result {
+            Ok(peer_id) => grpc::ImportedBanReport {
+                node_id: Some(node_id_from_peer_id(&peer_id)),
+                error: None,
+            },
+            Err(error) => grpc::ImportedBanReport {
+                node_id: None,
+                error: Some(error),
+            },
+        })
+        .collect();
+    Ok(grpc::ImportBanReportsResponse {
+        id: inner_req.id,
+        results,
+    })
+}
+
+/// Ask a few connected peers to dial back the listeners of the node
+pub(crate) fn test_reachability(
+    grpc: &MassaGrpc,
+    request: tonic::Request<grpc::TestReachabilityRequest>,
+) -> Result<grpc::TestReachabilityResponse, GrpcError> {
+    let node_ids = |peer_ids: Vec<PeerId>| peer_ids.iter().map(node_id_from_peer_id).collect();
+    let listeners = grpc
+        .protocol_command_sender
+        .test_reachability()?
+        .into_iter()
+        .map(|listener| grpc::ListenerReachability {
+            address: listener.address.to_string(),
+            reachable_from: node_ids(listener.reachable_from),
+            unreachable_from: node_ids(listener.unreachable_from),
+            unanswered: node_ids(listener.unanswered),
+        })
+        .collect();
+    Ok(grpc::TestReachabilityResponse {
+        id: request.into_inner().id,
+        listeners,
+    })
+}
+
+/// Replace the tags and note attached to a peer
+pub(crate) fn annotate_peer(
+    grpc: &MassaGrpc,
+    request: tonic::Request<grpc::AnnotatePeerRequest>,
+) -> Result<grpc::AnnotatePeerResponse, GrpcError> {
+    let inner_req = request.into_inner();
+    let peer_id = peer_id_from_node_id(&inner_req.node_id)?;
+    let annotation = PeerAnnotation {
+        tags: inner_req.tags.into_iter().collect(),
+        note: inner_req.note.filter(|note| !note.is_empty()),
+    };
+    grpc.protocol_command_sender
+        .annotate_peer(peer_id, annotation)?;
+    Ok(grpc::AnnotatePeerResponse { id: inner_req.id })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_or_create_admin_token, AdminAuthInterceptor};
+    use tonic::service::Interceptor;
+
+    #[test]
+    fn test_admin_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("admin_token");
+        let token = load_or_create_admin_token(&path).unwrap();
+        assert_eq!(token.len(), 64);
+        // the token is kept across restarts
+        assert_eq!(load_or_create_admin_token(&path).unwrap(), token);
+
+        let mut interceptor = AdminAuthInterceptor::new(&token).unwrap();
+        let mut request = tonic::Request::new(());
+        request.metadata_mut().insert(
+            "authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        assert!(interceptor.call(request).is_ok());
+
+        let mut request = tonic::Request::new(());
+        request
+            .metadata_mut()
+            .insert("authorization", "Bearer wrong".parse().unwrap());
+        assert_eq!(
+            interceptor.call(request).unwrap_err().code(),
+            tonic::Code::Unauthenticated
+        );
+        assert!(interceptor.call(tonic::Request::new(())).is_err());
+    }
+}
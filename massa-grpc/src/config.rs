@@ -98,4 +98,8 @@ pub struct GrpcConfig {
     pub server_private_key_path: PathBuf,
     /// client certificate authority root path
     pub client_certificate_authority_root_path: PathBuf,
+    /// whether to enable the admin service, to manage the peers
+    pub enable_admin: bool,
+    /// path of the token authenticating the admin requests, generated if missing
+    pub admin_token_path: PathBuf,
 }
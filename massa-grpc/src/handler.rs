@@ -2,6 +2,10 @@
 
 use massa_proto::massa::api::v1 as grpc;
 
+use crate::admin::{
+    annotate_peer, ban_peer, export_ban_reports, export_peers, import_ban_reports, import_peers,
+    list_peers, set_targets, test_peer, test_reachability, unban_peer,
+};
 use crate::api::{
     get_blocks, get_blocks_by_slots, get_datastore_entries, get_largest_stakers,
     get_next_block_best_parents, get_operations, get_sc_execution_events, get_selector_draws,
@@ -225,3 +229,97 @@ impl grpc::massa_service_server::MassaService for MassaGrpc {
         ))
     }
 }
+
+#[tonic::async_trait]
+impl grpc::massa_admin_service_server::MassaAdminService for MassaGrpc {
+    /// handler for ban peer
+    async fn ban_peer(
+        &self,
+        request: tonic::Request<grpc::BanPeerRequest>,
+    ) -> Result<tonic::Response<grpc::BanPeerResponse>, tonic::Status> {
+        Ok(tonic::Response::new(ban_peer(self, request)?))
+    }
+
+    /// handler for unban peer
+    async fn unban_peer(
+        &self,
+        request: tonic::Request<grpc::UnbanPeerRequest>,
+    ) -> Result<tonic::Response<grpc::UnbanPeerResponse>, tonic::Status> {
+        Ok(tonic::Response::new(unban_peer(self, request)?))
+    }
+
+    /// handler for list peers
+    async fn list_peers(
+        &self,
+        request: tonic::Request<grpc::ListPeersRequest>,
+    ) -> Result<tonic::Response<grpc::ListPeersResponse>, tonic::Status> {
+        Ok(tonic::Response::new(list_peers(self, request)?))
+    }
+
+    /// handler for test peer
+    async fn test_peer(
+        &self,
+        request: tonic::Request<grpc::TestPeerRequest>,
+    ) -> Result<tonic::Response<grpc::TestPeerResponse>, tonic::Status> {
+        // the test dials the address and waits for the handshake
+        Ok(tonic::Response::new(tokio::task::block_in_place(|| {
+            test_peer(self, request)
+        })?))
+    }
+
+    /// handler for set targets
+    async fn set_targets(
+        &self,
+        request: tonic::Request<grpc::SetTargetsRequest>,
+    ) -> Result<tonic::Response<grpc::SetTargetsResponse>, tonic::Status> {
+        Ok(tonic::Response::new(set_targets(self, request)?))
+    }
+
+    /// handler for export peers
+    async fn export_peers(
+        &self,
+        request: tonic::Request<grpc::ExportPeersRequest>,
+    ) -> Result<tonic::Response<grpc::ExportPeersResponse>, tonic::Status> {
+        Ok(tonic::Response::new(export_peers(self, request)?))
+    }
+
+    /// handler for import peers
+    async fn import_peers(
+        &self,
+        request: tonic::Request<grpc::ImportPeersRequest>,
+    ) -> Result<tonic::Response<grpc::ImportPeersResponse>, tonic::Status> {
+        Ok(tonic::Response::new(import_peers(self, request)?))
+    }
+
+    /// handler for export ban reports
+    async fn export_ban_reports(
+        &self,
+        request: tonic::Request<grpc::ExportBanReportsRequest>,
+    ) -> Result<tonic::Response<grpc::ExportBanReportsResponse>, tonic::Status> {
+        Ok(tonic::Response::new(export_ban_reports(self, request)?))
+    }
+
+    /// handler for import ban reports
+    async fn import_ban_reports(
+        &self,
+        request: tonic::Request<grpc::ImportBanReportsRequest>,
+    ) -> Result<tonic::Response<grpc::ImportBanReportsResponse>, tonic::Status> {
+        Ok(tonic::Response::new(import_ban_reports(self, request)?))
+    }
+
+    /// handler for test reachability
+    async fn test_reachability(
+        &self,
+        request: tonic::Request<grpc::TestReachabilityRequest>,
+    ) -> Result<tonic::Response<grpc::TestReachabilityResponse>, tonic::Status> {
+        Ok(tonic::Response::new(test_reachability(self, request)?))
+    }
+
+    /// handler for annotate peer
+    async fn annotate_peer(
+        &self,
+        request: tonic::Request<grpc::AnnotatePeerRequest>,
+    ) -> Result<tonic::Response<grpc::AnnotatePeerResponse>, tonic::Status> {
+        Ok(tonic::Response::new(annotate_peer(self, request)?))
+    }
+}
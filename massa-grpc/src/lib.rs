@@ -7,6 +7,7 @@
 //!
 //! ## **Structure**
 //!
+//! * `admin.rs`: implements the admin service methods, reserved to the node operator.
 //! * `api.rs`: implements gRPC service methods without streams.
 //! * `handler.rs`: defines the logic for handling incoming gRPC requests.
 //! * `server`: initializes the gRPC service and serve It.
@@ -20,6 +21,8 @@ use tonic_health as _;
 use tonic_reflection as _;
 use tonic_web as _;
 
+/// business code and authentication of the admin service
+pub mod admin;
 /// business code for non stream methods
 pub mod api;
 /// gRPC configuration
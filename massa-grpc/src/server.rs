@@ -1,5 +1,6 @@
 // Copyright (c) 2023 MASSA LABS <info@massa.net>
 
+use crate::admin::{load_or_create_admin_token, AdminAuthInterceptor};
 use crate::config::GrpcConfig;
 use crate::error::GrpcError;
 use futures_util::FutureExt;
@@ -8,13 +9,16 @@ use massa_consensus_exports::{ConsensusChannels, ConsensusController};
 use massa_execution_exports::{ExecutionChannels, ExecutionController};
 use massa_pool_exports::{PoolChannels, PoolController};
 use massa_pos_exports::SelectorController;
+use massa_proto::massa::api::v1::massa_admin_service_server::MassaAdminServiceServer;
 use massa_proto::massa::api::v1::massa_service_server::MassaServiceServer;
 use massa_proto::massa::api::v1::FILE_DESCRIPTOR_SET;
 use massa_protocol_exports::ProtocolController;
 use massa_storage::Storage;
+use std::sync::Arc;
 use tokio::sync::oneshot;
 use tonic::{
     codec::CompressionEncoding,
+    service::interceptor::InterceptedService,
     transport::{Certificate, Identity, ServerTlsConfig},
 };
 use tonic_health::server::HealthReporter;
@@ -51,7 +55,8 @@ pub struct MassaGrpc {
 impl MassaGrpc {
     /// Start the gRPC API
     pub async fn serve(self, config: &GrpcConfig) -> Result<StopHandle, GrpcError> {
-        let mut svc = MassaServiceServer::new(self)
+        let grpc = Arc::new(self);
+        let mut svc = MassaServiceServer::from_arc(grpc.clone())
             .max_decoding_message_size(config.max_decoding_message_size)
             .max_encoding_message_size(config.max_encoding_message_size);
 
@@ -115,6 +120,20 @@ impl MassaGrpc {
             None
         };
 
+        let admin_service_opt = if config.enable_admin {
+            let token = load_or_create_admin_token(&config.admin_token_path)?;
+            let admin_service = MassaAdminServiceServer::from_arc(grpc)
+                .max_decoding_message_size(config.max_decoding_message_size)
+                .max_encoding_message_size(config.max_encoding_message_size);
+            info!("gRPC admin service enabled");
+            Some(InterceptedService::new(
+                admin_service,
+                AdminAuthInterceptor::new(&token)?,
+            ))
+        } else {
+            None
+        };
+
         let health_service_opt = if config.enable_health {
             let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
             health_reporter
@@ -142,6 +161,7 @@ impl MassaGrpc {
                     .layer(GrpcWebLayer::new())
                     .add_optional_service(reflection_service_opt)
                     .add_optional_service(health_service_opt)
+                    .add_optional_service(admin_service_opt)
                     .add_service(svc);
 
                 tokio::spawn(
@@ -153,6 +173,7 @@ impl MassaGrpc {
                     .layer(GrpcWebLayer::new())
                     .add_optional_service(reflection_service_opt)
                     .add_optional_service(health_service_opt)
+                    .add_optional_service(admin_service_opt)
                     .add_service(svc);
 
                 tokio::spawn(
@@ -163,6 +184,7 @@ impl MassaGrpc {
             let router = server_builder
                 .add_optional_service(reflection_service_opt)
                 .add_optional_service(health_service_opt)
+                .add_optional_service(admin_service_opt)
                 .add_service(svc);
 
             tokio::spawn(router.serve_with_shutdown(config.bind, shutdown_recv.map(drop)));
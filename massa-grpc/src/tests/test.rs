@@ -92,6 +92,8 @@ async fn test_start_grpc_server() {
         server_certificate_path: PathBuf::default(),
         server_private_key_path: PathBuf::default(),
         client_certificate_authority_root_path: PathBuf::default(),
+        enable_admin: false,
+        admin_token_path: PathBuf::default(),
     };
 
     let service = MassaGrpc {
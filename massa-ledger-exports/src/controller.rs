@@ -6,7 +6,7 @@ use massa_models::{
 use std::collections::BTreeSet;
 use std::fmt::Debug;
 
-use crate::{Key, LedgerChanges, LedgerError};
+use crate::{Key, LedgerChanges, LedgerError, LedgerProof};
 
 pub trait LedgerController: Send + Sync + Debug {
     /// Allows applying `LedgerChanges` to the final ledger
@@ -58,6 +58,10 @@ pub trait LedgerController: Send + Sync + Debug {
     /// Get the current disk ledger hash
     fn get_ledger_hash(&self) -> Hash;
 
+    /// Get the values of the given keys with the proof that they match the current ledger hash
+    /// Used for light bootstrap
+    fn get_ledger_proof(&self, keys: &[Key]) -> LedgerProof;
+
     /// Get a part of the ledger
     /// Used for bootstrap
     /// Return: Tuple with data and last key
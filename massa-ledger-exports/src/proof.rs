@@ -0,0 +1,184 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Proof of the values of selected ledger keys against the ledger hash.
+//!
+//! The ledger hash is the XOR of the hashes of every key/value pair of the ledger and of the
+//! ledger slot. A proof gives the values of the selected keys and the XOR of the hashes of
+//! everything else, so that the ledger hash can be recomputed from the selected values.
+//!
+//! Since anyone can pick the XOR of the other hashes, a proof only shows that the values are
+//! consistent with the ledger hash claimed by its issuer: it must come with a signature of that
+//! hash by a trusted node, like the bootstrap manifest. Keys absent from the ledger contribute
+//! nothing to the hash, so their absence is only vouched for by that signature.
+
+use crate::{Key, KeyDeserializer, KeySerializer, LedgerError};
+use massa_hash::{Hash, HashDeserializer, HashSerializer};
+use massa_models::serialization::{VecU8Deserializer, VecU8Serializer};
+use massa_serialization::{
+    Deserializer, OptionDeserializer, OptionSerializer, SerializeError, Serializer,
+    U64VarIntDeserializer, U64VarIntSerializer,
+};
+use nom::error::{context, ContextError, ParseError};
+use nom::multi::length_count;
+use nom::sequence::tuple;
+use nom::{IResult, Parser};
+use std::collections::HashSet;
+use std::ops::Bound::Included;
+
+/// Hash of a key/value pair of the disk ledger, as accumulated in the ledger hash.
+///
+/// `serialized_key` is the key serialized in the disk format, without the datastore key length.
+pub fn ledger_entry_hash(serialized_key: &[u8], value: &[u8]) -> Hash {
+    let mut len_bytes = Vec::new();
+    // varint serialization never fails
+    U64VarIntSerializer::new()
+        .serialize(&(serialized_key.len() as u64), &mut len_bytes)
+        .expect("critical: key length serialization failed");
+    Hash::compute_from(&[&len_bytes, serialized_key, value].concat())
+}
+
+/// Values of selected ledger keys, with the XOR of the hashes of the rest of the ledger
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedgerProof {
+    /// selected keys with their value, `None` if the key is not in the ledger
+    pub entries: Vec<(Key, Option<Vec<u8>>)>,
+    /// XOR of the hashes of everything in the ledger hash but the selected entries
+    pub complement_hash: Hash,
+}
+
+impl LedgerProof {
+    /// Checks that the ledger hash recomputed from the proof is `ledger_hash`
+    pub fn verify(&self, ledger_hash: &Hash) -> Result<(), LedgerError> {
+        let key_serializer = KeySerializer::new(false);
+        let mut seen_keys = HashSet::new();
+        let mut hash = self.complement_hash;
+        for (key, value) in &self.entries {
+            let mut serialized_key = Vec::new();
+            key_serializer
+                .serialize(key, &mut serialized_key)
+                .map_err(|err| LedgerError::ProofError(err.to_string()))?;
+            // a key listed twice would cancel out of the XOR
+            if !seen_keys.insert(serialized_key.clone()) {
+                return Err(LedgerError::ProofError(format!(
+                    "key {:?} is listed twice",
+                    key
+                )));
+            }
+            if let Some(value) = value {
+                hash ^= ledger_entry_hash(&serialized_key, value);
+            }
+        }
+        if hash != *ledger_hash {
+            return Err(LedgerError::ProofError(format!(
+                "recomputed ledger hash {} does not match {}",
+                hash, ledger_hash
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Serializer for `LedgerProof`
+pub struct LedgerProofSerializer {
+    u64_serializer: U64VarIntSerializer,
+    key_serializer: KeySerializer,
+    value_serializer: OptionSerializer<Vec<u8>, VecU8Serializer>,
+    hash_serializer: HashSerializer,
+}
+
+impl Default for LedgerProofSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LedgerProofSerializer {
+    /// Creates a new `LedgerProofSerializer`
+    pub fn new() -> Self {
+        Self {
+            u64_serializer: U64VarIntSerializer::new(),
+            key_serializer: KeySerializer::new(true),
+            value_serializer: OptionSerializer::new(VecU8Serializer::new()),
+            hash_serializer: HashSerializer::new(),
+        }
+    }
+}
+
+impl Serializer<LedgerProof> for LedgerProofSerializer {
+    fn serialize(&self, value: &LedgerProof, buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        self.u64_serializer
+            .serialize(&(value.entries.len() as u64), buffer)?;
+        for (key, entry_value) in &value.entries {
+            self.key_serializer.serialize(key, buffer)?;
+            self.value_serializer.serialize(entry_value, buffer)?;
+        }
+        self.hash_serializer
+            .serialize(&value.complement_hash, buffer)?;
+        Ok(())
+    }
+}
+
+/// Deserializer for `LedgerProof`
+pub struct LedgerProofDeserializer {
+    length_deserializer: U64VarIntDeserializer,
+    key_deserializer: KeyDeserializer,
+    value_deserializer: OptionDeserializer<Vec<u8>, VecU8Deserializer>,
+    hash_deserializer: HashDeserializer,
+}
+
+impl LedgerProofDeserializer {
+    /// Creates a new `LedgerProofDeserializer`
+    ///
+    /// # Arguments
+    /// * `max_entries`: maximum number of keys in a proof
+    /// * `max_datastore_key_length`: maximum length of a datastore key
+    /// * `max_value_length`: maximum length of a value
+    pub fn new(max_entries: u64, max_datastore_key_length: u8, max_value_length: u64) -> Self {
+        Self {
+            length_deserializer: U64VarIntDeserializer::new(Included(0), Included(max_entries)),
+            key_deserializer: KeyDeserializer::new(max_datastore_key_length, true),
+            value_deserializer: OptionDeserializer::new(VecU8Deserializer::new(
+                Included(0),
+                Included(max_value_length),
+            )),
+            hash_deserializer: HashDeserializer::new(),
+        }
+    }
+}
+
+impl Deserializer<LedgerProof> for LedgerProofDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], LedgerProof, E> {
+        context(
+            "Failed LedgerProof deserialization",
+            tuple((
+                context(
+                    "Failed entries deserialization",
+                    length_count(
+                        context("Failed length deserialization", |input| {
+                            self.length_deserializer.deserialize(input)
+                        }),
+                        tuple((
+                            context("Failed key deserialization", |input| {
+                                self.key_deserializer.deserialize(input)
+                            }),
+                            context("Failed value deserialization", |input| {
+                                self.value_deserializer.deserialize(input)
+                            }),
+                        )),
+                    ),
+                ),
+                context("Failed complement_hash deserialization", |input| {
+                    self.hash_deserializer.deserialize(input)
+                }),
+            )),
+        )
+        .map(|(entries, complement_hash)| LedgerProof {
+            entries,
+            complement_hash,
+        })
+        .parse(buffer)
+    }
+}
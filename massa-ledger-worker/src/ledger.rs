@@ -5,7 +5,7 @@
 use crate::ledger_db::{LedgerDB, LedgerSubEntry};
 use massa_hash::Hash;
 use massa_ledger_exports::{
-    Key, LedgerChanges, LedgerConfig, LedgerController, LedgerEntry, LedgerError,
+    Key, LedgerChanges, LedgerConfig, LedgerController, LedgerEntry, LedgerError, LedgerProof,
 };
 use massa_models::{
     address::Address,
@@ -163,6 +163,13 @@ impl LedgerController for FinalLedger {
         self.sorted_ledger.get_ledger_hash()
     }
 
+    /// Get the values of the given keys with the proof that they match the current ledger hash.
+    ///
+    /// Solely used by the light bootstrap.
+    fn get_ledger_proof(&self, keys: &[Key]) -> LedgerProof {
+        self.sorted_ledger.get_ledger_proof(keys)
+    }
+
     /// Get a part of the disk ledger.
     ///
     /// Solely used by the bootstrap.
@@ -13,7 +13,7 @@ use massa_models::{
     slot::{Slot, SlotDeserializer, SlotSerializer},
     streaming_step::StreamingStep,
 };
-use massa_serialization::{DeserializeError, Deserializer, Serializer, U64VarIntSerializer};
+use massa_serialization::{DeserializeError, Deserializer, Serializer};
 use nom::multi::many0;
 use nom::sequence::tuple;
 use rocksdb::{
@@ -42,7 +42,6 @@ const CF_ERROR: &str = "critical: rocksdb column family operation failed";
 const LEDGER_HASH_ERROR: &str = "critical: saved ledger hash is corrupted";
 const KEY_DESER_ERROR: &str = "critical: key deserialization failed";
 const KEY_SER_ERROR: &str = "critical: key serialization failed";
-const KEY_LEN_SER_ERROR: &str = "critical: key length serialization failed";
 const SLOT_KEY: &[u8; 1] = b"s";
 const LEDGER_HASH_KEY: &[u8; 1] = b"h";
 const LEDGER_FINAL_STATE_KEY: &[u8; 2] = b"fs";
@@ -83,7 +82,6 @@ pub(crate) struct LedgerDB {
     bytecode_serializer: BytecodeSerializer,
     slot_serializer: SlotSerializer,
     slot_deserializer: SlotDeserializer,
-    len_serializer: U64VarIntSerializer,
     ledger_part_size_message_bytes: u64,
     #[cfg(feature = "testing")]
     amount_deserializer: AmountDeserializer,
@@ -171,7 +169,6 @@ impl LedgerDB {
                 (Bound::Included(u64::MIN), Bound::Included(u64::MAX)),
                 (Bound::Included(0_u8), Bound::Excluded(thread_count)),
             ),
-            len_serializer: U64VarIntSerializer::new(),
             ledger_part_size_message_bytes,
             #[cfg(feature = "testing")]
             amount_deserializer: AmountDeserializer::new(
@@ -328,6 +325,37 @@ impl LedgerDB {
         Some(iter.collect())
     }
 
+    /// Get the values of the given keys with the XOR of the hashes of the rest of the ledger,
+    /// so that the ledger hash can be recomputed from the values.
+    /// Mainly used in the light bootstrap process.
+    ///
+    /// # Arguments
+    /// * `keys`: keys to prove, duplicates are only proved once
+    pub fn get_ledger_proof(&self, keys: &[Key]) -> LedgerProof {
+        let handle = self.db.cf_handle(LEDGER_CF).expect(CF_ERROR);
+        let mut complement_hash = self.get_ledger_hash();
+        let mut proved_keys = BTreeSet::new();
+        let mut entries = Vec::with_capacity(keys.len());
+        for key in keys {
+            let mut serialized_key = Vec::new();
+            self.key_serializer_db
+                .serialize(key, &mut serialized_key)
+                .expect(KEY_SER_ERROR);
+            if !proved_keys.insert(serialized_key.clone()) {
+                continue;
+            }
+            let value = self.db.get_cf(handle, &serialized_key).expect(CRUD_ERROR);
+            if let Some(value) = &value {
+                complement_hash ^= ledger_entry_hash(&serialized_key, value);
+            }
+            entries.push((key.clone(), value));
+        }
+        LedgerProof {
+            entries,
+            complement_hash,
+        }
+    }
+
     /// Get a part of the disk Ledger.
     /// Mainly used in the bootstrap process.
     ///
@@ -529,11 +557,7 @@ impl LedgerDB {
         self.key_serializer_db
             .serialize(key, &mut serialized_key)
             .expect(KEY_SER_ERROR);
-        let mut len_bytes = Vec::new();
-        self.len_serializer
-            .serialize(&(serialized_key.len() as u64), &mut len_bytes)
-            .expect(KEY_LEN_SER_ERROR);
-        let hash = Hash::compute_from(&[&len_bytes, &serialized_key, value].concat());
+        let hash = ledger_entry_hash(&serialized_key, value);
         batch.ledger_hash ^= hash;
         batch.aeh_list.insert(serialized_key.clone(), hash);
         batch.write_batch.put_cf(handle, serialized_key, value);
@@ -598,10 +622,6 @@ impl LedgerDB {
             .serialize(key, &mut serialized_key)
             .expect(KEY_SER_ERROR);
 
-        let mut len_bytes = Vec::new();
-        self.len_serializer
-            .serialize(&(serialized_key.len() as u64), &mut len_bytes)
-            .expect(KEY_LEN_SER_ERROR);
         if let Some(added_hash) = batch.aeh_list.get(&serialized_key) {
             batch.ledger_hash ^= *added_hash;
         } else if let Some(prev_bytes) = self
@@ -609,10 +629,9 @@ impl LedgerDB {
             .get_pinned_cf(handle, &serialized_key)
             .expect(CRUD_ERROR)
         {
-            batch.ledger_hash ^=
-                Hash::compute_from(&[&len_bytes, &serialized_key, &prev_bytes[..]].concat());
+            batch.ledger_hash ^= ledger_entry_hash(&serialized_key, &prev_bytes);
         }
-        let hash = Hash::compute_from(&[&len_bytes, &serialized_key, value].concat());
+        let hash = ledger_entry_hash(&serialized_key, value);
         batch.ledger_hash ^= hash;
         batch.aeh_list.insert(serialized_key.clone(), hash);
         batch.write_batch.put_cf(handle, serialized_key, value);
@@ -679,12 +698,7 @@ impl LedgerDB {
             .get_pinned_cf(handle, &serialized_key)
             .expect(CRUD_ERROR)
         {
-            let mut len_bytes = Vec::new();
-            self.len_serializer
-                .serialize(&(serialized_key.len() as u64), &mut len_bytes)
-                .expect(KEY_LEN_SER_ERROR);
-            batch.ledger_hash ^=
-                Hash::compute_from(&[&len_bytes, &serialized_key, &prev_bytes[..]].concat());
+            batch.ledger_hash ^= ledger_entry_hash(&serialized_key, &prev_bytes);
         }
         batch.write_batch.delete_cf(handle, serialized_key);
     }
@@ -914,6 +928,29 @@ mod tests {
         db.set_ledger_part(&res.0[..]).unwrap();
     }
 
+    #[test]
+    fn test_ledger_proof() {
+        let addr = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let (db, data) = init_test_ledger(addr);
+        let ledger_hash = db.get_ledger_hash();
+
+        let datastore_key = Key::new(&addr, KeyType::DATASTORE(b"2".to_vec()));
+        let mut proof = db.get_ledger_proof(&[
+            Key::new(&addr, KeyType::BALANCE),
+            datastore_key.clone(),
+            datastore_key,
+            Key::new(&addr, KeyType::DATASTORE(b"missing".to_vec())),
+        ]);
+        assert_eq!(proof.entries.len(), 3);
+        assert_eq!(proof.entries[1].1.as_ref(), data.get(&b"2".to_vec()));
+        assert!(proof.entries[2].1.is_none());
+        proof.verify(&ledger_hash).unwrap();
+
+        // a tampered value does not match the ledger hash anymore
+        proof.entries[1].1 = Some(b"z".to_vec());
+        assert!(proof.verify(&ledger_hash).is_err());
+    }
+
     #[test]
     fn test_end_prefix() {
         assert_eq!(end_prefix(&[5, 6, 7]), Some(vec![5, 6, 8]));
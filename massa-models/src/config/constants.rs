@@ -78,6 +78,14 @@ lazy_static::lazy_static! {
         .parse()
         .unwrap()
     };
+    /// identifier of the network: nodes of different networks don't stay connected to each other
+    pub static ref CHAINID: u64 = if cfg!(feature = "sandbox") {
+        77658366
+    } else {
+        77658377
+    };
+    /// Time from which the nodes vote for the VM version 1, which limits the data of the events and charges gas for it
+    pub static ref VM_V1_MIP_START: MassaTime = *GENESIS_TIMESTAMP;
 
 }
 
@@ -146,6 +154,22 @@ pub const ASYNC_POOL_BOOTSTRAP_PART_SIZE: u64 = 100;
 pub const DEFERRED_CREDITS_BOOTSTRAP_PART_SIZE: u64 = 100;
 /// Maximum executed ops per slot in a bootstrap batch
 pub const EXECUTED_OPS_BOOTSTRAP_PART_SIZE: u64 = 10;
+/// Approximate maximum size of the executed ops of a bootstrap batch, in bytes.
+/// It must allow less operations than `MAX_OPERATIONS_PER_BLOCK`, the bound of the operations
+/// of a slot in a batch.
+pub const EXECUTED_OPS_BOOTSTRAP_PART_SIZE_BYTES: u64 = 100_000;
+/// Number of periods an executed operation is kept after its expiry slot
+pub const EXECUTED_OPS_RETENTION_PERIODS: u64 = 0;
+/// Maximum number of executed operations pruned per slot, in deferred pruning mode.
+/// Twice the operations a block can hold, so that pruning keeps up with execution.
+pub const EXECUTED_OPS_PRUNING_BATCH_SIZE: u64 = 2 * MAX_OPERATIONS_PER_BLOCK as u64;
+/// Version of the pruning mode of the executed operations (0: immediate, 1: deferred).
+/// It changes the final state hash: all the nodes of a network must use the same version,
+/// and it can only change with a new network version.
+pub const EXECUTED_OPS_PRUNING_MODE_VERSION: u64 = 0;
+/// Version of the scheme of the executed operations hash (0: XOR, 1: LtHash).
+/// It changes the final state hash: all the nodes of a network must use the same version.
+pub const EXECUTED_OPS_HASH_SCHEME_VERSION: u64 = 0;
 /// Maximum number of consensus blocks in a bootstrap batch
 pub const CONSENSUS_BOOTSTRAP_PART_SIZE: u64 = 50;
 /// Maximum number of consensus block ids when sending a bootstrap cursor from the client
@@ -158,6 +182,10 @@ pub const MAX_PRODUCTION_STATS_LENGTH: u64 = 10_000;
 pub const MAX_DEFERRED_CREDITS_LENGTH: u64 = 10_000;
 /// Maximum size of executed ops
 pub const MAX_EXECUTED_OPS_LENGTH: u64 = 1_000;
+/// Maximum number of executed ops in a bootstrap part, over all its slots: the operations of
+/// `EXECUTED_OPS_BOOTSTRAP_PART_SIZE_BYTES` (at least 33 bytes each), with the margin of a slot.
+pub const MAX_EXECUTED_OPS_PART_LENGTH: u64 =
+    EXECUTED_OPS_BOOTSTRAP_PART_SIZE_BYTES / 33 + MAX_OPERATIONS_PER_BLOCK as u64;
 /// Maximum size of executed ops changes
 pub const MAX_EXECUTED_OPS_CHANGES_LENGTH: u64 = 20_000;
 /// Maximum length of a datastore key
@@ -168,6 +196,12 @@ pub const MAX_OPERATION_DATASTORE_KEY_LENGTH: u8 = MAX_DATASTORE_KEY_LENGTH;
 pub const MAX_DATASTORE_VALUE_LENGTH: u64 = 10_000_000;
 /// Maximum length of a datastore value
 pub const MAX_BYTECODE_LENGTH: u64 = 10_000_000;
+/// Maximum size in bytes of the data of an event emitted by a smart contract
+pub const MAX_EVENT_DATA_SIZE: usize = 50_000;
+/// Gas charged per byte of the data of an event emitted by a smart contract
+pub const EVENT_DATA_GAS_COST_PER_BYTE: u64 = 10;
+/// Version of the VM component from which the data of the events is limited and charged per byte
+pub const EVENT_DATA_LIMIT_VM_VERSION: u32 = 1;
 /// Maximum length of an operation datastore value
 pub const MAX_OPERATION_DATASTORE_VALUE_LENGTH: u64 = 500_000;
 /// Maximum ledger changes in a block
@@ -298,6 +332,18 @@ pub const VERSIONING_THRESHOLD_TRANSITION_ACCEPTED: Amount = Amount::from_mantis
 pub const MIP_STORE_STATS_BLOCK_CONSIDERED: usize = 1000;
 /// Max number of stats counters
 pub const MIP_STORE_STATS_COUNTERS_MAX: usize = 10;
+/// Duration of the vote for the VM version 1, after which it fails if it didn't lock in
+pub const VM_V1_MIP_VOTE_DURATION: MassaTime = if cfg!(feature = "sandbox") {
+    MassaTime::from_millis(24 * 3600 * 1000)
+} else {
+    MassaTime::from_millis(60 * 24 * 3600 * 1000)
+};
+/// Delay between the lock in of the VM version 1 and its activation
+pub const VM_V1_MIP_ACTIVATION_DELAY: MassaTime = if cfg!(feature = "sandbox") {
+    MassaTime::from_millis(60 * 1000)
+} else {
+    MassaTime::from_millis(7 * 24 * 3600 * 1000)
+};
 
 //
 // Constants for denunciation factory
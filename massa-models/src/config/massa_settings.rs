@@ -25,40 +25,52 @@
 //!
 use directories::ProjectDirs;
 use serde::Deserialize;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-/// Merge the settings
-/// 1. default
-/// 2. in path specified in `MASSA_CONFIG_PATH` environment variable (`base_config/config.toml` by default)
-/// 3. in path specified in `MASSA_CONFIG_OVERRIDE_PATH` environment variable (`config/config.toml` by default)
-#[inline]
-pub fn build_massa_settings<T: Deserialize<'static>>(app_name: &str, env_prefix: &str) -> T {
-    let mut builder = config::Config::builder();
+/// Paths of the configuration files merged in the settings, in merge order
+pub fn massa_settings_paths(app_name: &str) -> Vec<PathBuf> {
     let config_path = std::env::var("MASSA_CONFIG_PATH")
         .unwrap_or_else(|_| "base_config/config.toml".to_string());
-
-    builder = builder.add_source(config::File::with_name(&config_path));
+    let mut paths = vec![PathBuf::from(config_path)];
 
     let config_override_path = std::env::var("MASSA_CONFIG_OVERRIDE_PATH")
         .unwrap_or_else(|_| "config/config.toml".to_string());
 
     if Path::new(&config_override_path).is_file() {
-        builder = builder.add_source(config::File::with_name(&config_override_path));
+        paths.push(PathBuf::from(config_override_path));
     }
 
     if let Some(proj_dirs) = ProjectDirs::from("com", "MassaLabs", app_name) {
         // Portable user config loading
         let user_config_path = proj_dirs.config_dir();
         if user_config_path.exists() {
-            let path_str = user_config_path.to_str().unwrap();
-            builder = builder.add_source(config::File::with_name(path_str));
+            paths.push(user_config_path.to_path_buf());
         }
     }
+    paths
+}
 
-    let s = builder
-        .add_source(config::Environment::with_prefix(env_prefix))
-        .build()
-        .unwrap();
+/// Merge the settings
+/// 1. default
+/// 2. in path specified in `MASSA_CONFIG_PATH` environment variable (`base_config/config.toml` by default)
+/// 3. in path specified in `MASSA_CONFIG_OVERRIDE_PATH` environment variable (`config/config.toml` by default)
+#[inline]
+pub fn build_massa_settings<T: Deserialize<'static>>(app_name: &str, env_prefix: &str) -> T {
+    try_build_massa_settings(app_name, env_prefix).unwrap()
+}
 
-    s.try_deserialize().unwrap()
+/// Same as `build_massa_settings`, returning an error if the settings are invalid instead of panicking
+pub fn try_build_massa_settings<T: Deserialize<'static>>(
+    app_name: &str,
+    env_prefix: &str,
+) -> Result<T, config::ConfigError> {
+    let mut builder = config::Config::builder();
+    for path in massa_settings_paths(app_name) {
+        builder = builder.add_source(config::File::with_name(&path.to_string_lossy()));
+    }
+
+    builder
+        .add_source(config::Environment::with_prefix(env_prefix))
+        .build()?
+        .try_deserialize()
 }
@@ -745,7 +745,7 @@ impl Deserializer<Denunciation> for DenunciationDeserializer {
 
 // Denunciation Index
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
 /// Index for Denunciations in collections (e.g. like a HashMap...)
 pub enum DenunciationIndex {
     /// Variant for Block header denunciation index
@@ -795,6 +795,19 @@ impl DenunciationIndex {
     }
 }
 
+impl std::fmt::Display for DenunciationIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DenunciationIndex::BlockHeader { slot } => {
+                write!(f, "block header denunciation at slot {}", slot)
+            }
+            DenunciationIndex::Endorsement { slot, index } => {
+                write!(f, "endorsement {} denunciation at slot {}", index, slot)
+            }
+        }
+    }
+}
+
 /// Create a `DenunciationIndex` from a `Denunciation`
 impl From<&Denunciation> for DenunciationIndex {
     fn from(value: &Denunciation) -> Self {
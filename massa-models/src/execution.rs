@@ -2,8 +2,10 @@
 
 use crate::amount::Amount;
 use crate::{address::Address, operation::OperationId, slot::Slot};
+use massa_hash::Hash;
 use massa_time::MassaTime;
 use serde::{Deserialize, Serialize};
+use std::fmt::Formatter;
 
 /// filter used when retrieving SC output events
 #[derive(Default, Debug, Deserialize, Clone, Serialize)]
@@ -44,3 +46,65 @@ pub struct TempFileVestingRange {
     /// max rolls
     pub max_rolls: Option<u64>,
 }
+
+/// Operations executed in a slot, as listed in an executed operations dump
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ExecutedOpsSlotDump {
+    /// slot in which the operations expire
+    pub slot: Slot,
+    /// number of operations executed in the slot
+    pub op_count: usize,
+    /// ids of the operations, in ascending order
+    pub op_ids: Vec<OperationId>,
+}
+
+/// Page of the final executed operations, in a stable and human-readable form
+/// so that the dumps of two nodes can be diffed
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ExecutedOpsDump {
+    /// slot of the last changes applied to the executed operations
+    pub last_applied_slot: Option<Slot>,
+    /// accumulated hash of all the executed operations
+    pub hash: Hash,
+    /// total number of slots holding executed operations
+    pub total_slot_count: usize,
+    /// total number of executed operations
+    pub total_op_count: usize,
+    /// index of the first slot of the page
+    pub offset: usize,
+    /// slots of the page, in ascending order
+    pub slots: Vec<ExecutedOpsSlotDump>,
+}
+
+impl std::fmt::Display for ExecutedOpsDump {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Executed operations dump:")?;
+        match self.last_applied_slot {
+            Some(slot) => writeln!(f, "\tLast applied slot: {}", slot)?,
+            None => writeln!(f, "\tLast applied slot: none")?,
+        }
+        writeln!(f, "\tHash: {}", self.hash)?;
+        writeln!(
+            f,
+            "\tOperations: {} in {} slots",
+            self.total_op_count, self.total_slot_count
+        )?;
+        writeln!(
+            f,
+            "\tSlots {} to {}:",
+            self.offset,
+            self.offset + self.slots.len()
+        )?;
+        for slot_dump in &self.slots {
+            writeln!(
+                f,
+                "\t\t{}: {} operations",
+                slot_dump.slot, slot_dump.op_count
+            )?;
+            for op_id in &slot_dump.op_ids {
+                writeln!(f, "\t\t\t{}", op_id)?;
+            }
+        }
+        Ok(())
+    }
+}
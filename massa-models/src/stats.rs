@@ -18,6 +18,9 @@ pub struct ExecutionStats {
     pub final_executed_operations_count: usize,
     /// active execution cursor slot
     pub active_cursor: Slot,
+    /// statistics of the final executed operations
+    #[serde(default)]
+    pub final_executed_ops_stats: ExecutedOpsStats,
 }
 
 impl std::fmt::Display for ExecutionStats {
@@ -44,6 +47,54 @@ impl std::fmt::Display for ExecutionStats {
             self.final_executed_operations_count
         )?;
         writeln!(f, "\tActive cursor: {}", self.active_cursor)?;
+        write!(f, "{}", self.final_executed_ops_stats)?;
+        Ok(())
+    }
+}
+
+/// statistics of the final executed operations, counted since the node started
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutedOpsStats {
+    /// number of executed operations currently tracked
+    pub tracked_ops_count: usize,
+    /// number of executed operations inserted
+    pub inserted_ops_count: u64,
+    /// number of executed operations pruned
+    pub pruned_ops_count: u64,
+    /// number of executed operations pruned at the last final slot
+    pub last_pruned_ops_count: u64,
+    /// total time spent pruning, in microseconds
+    pub prune_time_micros: u64,
+    /// number of bootstrap parts sent
+    pub bootstrap_parts_sent: u64,
+    /// number of executed operations in the bootstrap parts sent
+    pub bootstrap_ops_sent: u64,
+    /// number of bootstrap parts received
+    pub bootstrap_parts_received: u64,
+    /// number of executed operations in the bootstrap parts received
+    pub bootstrap_ops_received: u64,
+}
+
+impl std::fmt::Display for ExecutedOpsStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Executed operations stats:")?;
+        writeln!(f, "\tTracked operations: {}", self.tracked_ops_count)?;
+        writeln!(f, "\tInserted operations: {}", self.inserted_ops_count)?;
+        writeln!(
+            f,
+            "\tPruned operations: {} ({} at the last slot, {} µs spent pruning)",
+            self.pruned_ops_count, self.last_pruned_ops_count, self.prune_time_micros
+        )?;
+        writeln!(
+            f,
+            "\tBootstrap parts sent: {} ({} operations)",
+            self.bootstrap_parts_sent, self.bootstrap_ops_sent
+        )?;
+        writeln!(
+            f,
+            "\tBootstrap parts received: {} ({} operations)",
+            self.bootstrap_parts_received, self.bootstrap_ops_received
+        )?;
         Ok(())
     }
 }
@@ -61,6 +112,30 @@ pub struct NetworkStats {
     pub banned_peer_count: u64,
     /// active node count
     pub active_node_count: u64,
+    /// statistics of the operation gossip
+    #[serde(default)]
+    pub operation_gossip_stats: OperationGossipStats,
+    /// statistics of the cache of the blocks known by the peers
+    #[serde(default)]
+    pub known_blocks_stats: KnownItemsStats,
+    /// statistics of the cache of the operations known by the peers
+    #[serde(default)]
+    pub known_operations_stats: KnownItemsStats,
+    /// statistics of the cache of the endorsements known by the peers
+    #[serde(default)]
+    pub known_endorsements_stats: KnownItemsStats,
+    /// occupancy of the channels from the network to the protocol handlers
+    #[serde(default)]
+    pub channel_stats: Vec<ChannelStats>,
+    /// statistics of the deduplication of the endorsements received
+    #[serde(default)]
+    pub endorsement_dedup_stats: EndorsementDedupStats,
+    /// statistics of the temporary bans of the IPs failing their handshakes
+    #[serde(default)]
+    pub handshake_failure_stats: HandshakeFailureStats,
+    /// statistics of the buffer of the headers with unknown parents or early slots
+    #[serde(default)]
+    pub early_header_stats: EarlyHeaderStats,
 }
 
 impl std::fmt::Display for NetworkStats {
@@ -71,6 +146,280 @@ impl std::fmt::Display for NetworkStats {
         writeln!(f, "\tKnown peers: {}", self.known_peer_count)?;
         writeln!(f, "\tBanned peers: {}", self.banned_peer_count)?;
         writeln!(f, "\tActive nodes: {}", self.active_node_count)?;
+        write!(f, "{}", self.operation_gossip_stats)?;
+        writeln!(f, "\tKnown blocks cache: {}", self.known_blocks_stats)?;
+        writeln!(
+            f,
+            "\tKnown operations cache: {}",
+            self.known_operations_stats
+        )?;
+        writeln!(
+            f,
+            "\tKnown endorsements cache: {}",
+            self.known_endorsements_stats
+        )?;
+        for channel_stats in &self.channel_stats {
+            writeln!(f, "\t{}", channel_stats)?;
+        }
+        writeln!(
+            f,
+            "\tEndorsement deduplication: {}",
+            self.endorsement_dedup_stats
+        )?;
+        writeln!(f, "\tHandshake failures: {}", self.handshake_failure_stats)?;
+        writeln!(f, "\tEarly headers: {}", self.early_header_stats)?;
+        Ok(())
+    }
+}
+
+/// statistics of the operation gossip, counted since the node started
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OperationGossipStats {
+    /// number of operation ids announced to peers
+    pub announced_ids_sent: u64,
+    /// number of operation ids not announced to a peer because it already knew them
+    pub announced_ids_skipped: u64,
+    /// number of operation ids received in announcements
+    pub announced_ids_received: u64,
+    /// number of operation ids received in announcements that we already had
+    pub announced_ids_known: u64,
+    /// number of operation ids asked to peers
+    pub asked_ids_sent: u64,
+    /// number of operation ids asked by peers
+    pub asked_ids_received: u64,
+    /// number of operations sent to peers that asked for them
+    pub ops_sent: u64,
+    /// number of operations received from peers
+    pub ops_received: u64,
+    /// number of operations pushed in full to peers that didn't ask for them
+    #[serde(default)]
+    pub ops_pushed: u64,
+    /// number of peers whose known operations are tracked
+    pub known_ops_peer_count: u64,
+    /// number of operations tracked as known by the peers, all peers included
+    pub known_ops_entry_count: u64,
+}
+
+impl std::fmt::Display for OperationGossipStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Operation gossip stats:")?;
+        writeln!(
+            f,
+            "\tAnnounced ids sent: {} ({} skipped as known by the peer)",
+            self.announced_ids_sent, self.announced_ids_skipped
+        )?;
+        writeln!(
+            f,
+            "\tAnnounced ids received: {} ({} already known)",
+            self.announced_ids_received, self.announced_ids_known
+        )?;
+        writeln!(f, "\tAsked ids sent: {}", self.asked_ids_sent)?;
+        writeln!(f, "\tAsked ids received: {}", self.asked_ids_received)?;
+        writeln!(f, "\tOperations sent: {}", self.ops_sent)?;
+        writeln!(f, "\tOperations pushed: {}", self.ops_pushed)?;
+        writeln!(f, "\tOperations received: {}", self.ops_received)?;
+        writeln!(
+            f,
+            "\tKnown operations tracked: {} over {} peers",
+            self.known_ops_entry_count, self.known_ops_peer_count
+        )?;
+        Ok(())
+    }
+}
+
+/// statistics of a cache of the items known by the peers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KnownItemsStats {
+    /// number of peers whose known items are tracked
+    pub peer_count: u64,
+    /// number of items tracked, all peers included
+    pub entry_count: u64,
+    /// number of lookups of an item known by the peer
+    pub hits: u64,
+    /// number of lookups of an item not known by the peer
+    pub misses: u64,
+    /// number of items evicted to respect the size limits
+    pub evictions: u64,
+}
+
+impl std::fmt::Display for KnownItemsStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} items over {} peers, {} hits, {} misses, {} evictions",
+            self.entry_count, self.peer_count, self.hits, self.misses, self.evictions
+        )
+    }
+}
+
+/// statistics of the deduplication of the endorsements received, by slot, index and producer
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EndorsementDedupStats {
+    /// number of endorsements tracked in the deduplication window
+    pub tracked_count: u64,
+    /// number of endorsements dropped before their signature was verified, since the node started
+    pub duplicates_dropped: u64,
+}
+
+impl std::fmt::Display for EndorsementDedupStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} endorsements tracked, {} duplicates dropped",
+            self.tracked_count, self.duplicates_dropped
+        )
+    }
+}
+
+/// statistics of the failed handshakes and of the temporary bans of the IPs failing them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HandshakeFailureStats {
+    /// number of failed handshakes, since the node started
+    pub failures: u64,
+    /// number of temporary IP bans following repeated failed handshakes, since the node started
+    pub ip_bans: u64,
+    /// number of IPs currently banned
+    pub banned_ip_count: u64,
+}
+
+impl std::fmt::Display for HandshakeFailureStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} failures, {} IP bans, {} IPs banned",
+            self.failures, self.ip_bans, self.banned_ip_count
+        )
+    }
+}
+
+/// statistics of the buffer of the block headers received before their parents or their slot
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EarlyHeaderStats {
+    /// number of headers in the buffer
+    pub buffered_count: u64,
+    /// maximum number of headers in the buffer
+    pub capacity: u64,
+    /// number of headers buffered, since the node started
+    pub inserted: u64,
+    /// number of headers released once their parents were known and their slot started
+    pub released: u64,
+    /// number of headers handed to consensus after waiting too long for their parents
+    pub expired: u64,
+    /// number of headers handed to consensus early because the buffer was full
+    pub evicted: u64,
+}
+
+impl EarlyHeaderStats {
+    /// Percentage of the headers leaving the buffer that were released as expected
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.released + self.expired + self.evicted;
+        if total == 0 {
+            return 0.0;
+        }
+        100.0 * self.released as f64 / total as f64
+    }
+}
+
+impl std::fmt::Display for EarlyHeaderStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{} buffered, {} inserted, {} released, {} expired, {} evicted ({:.1}% hit rate)",
+            self.buffered_count,
+            self.capacity,
+            self.inserted,
+            self.released,
+            self.expired,
+            self.evicted,
+            self.hit_rate()
+        )
+    }
+}
+
+/// occupancy of a bounded channel between the network and a protocol handler
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChannelStats {
+    /// name of the channel
+    pub name: String,
+    /// number of messages waiting in the channel
+    pub len: u64,
+    /// capacity of the channel
+    pub capacity: u64,
+    /// number of messages dropped because the channel was full, since the node started
+    pub dropped: u64,
+    /// number of messages sent in the channel, since the node started
+    #[serde(default)]
+    pub sent: u64,
+}
+
+impl std::fmt::Display for ChannelStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} channel: {}/{} messages, {} sent, {} dropped",
+            self.name, self.len, self.capacity, self.sent, self.dropped
+        )
+    }
+}
+
+/// messages received by a protocol handler
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HandlerStats {
+    /// channel from the network to the handler
+    pub channel: ChannelStats,
+    /// messages sent to the handler per second, since the previous snapshot
+    pub messages_per_second: f64,
+}
+
+impl std::fmt::Display for HandlerStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({:.1} messages/s)",
+            self.channel, self.messages_per_second
+        )
+    }
+}
+
+/// snapshot of the state of the protocol, for the monitoring dashboards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolStats {
+    /// time of the snapshot
+    pub timestamp: MassaTime,
+    /// in connections count
+    pub in_connection_count: u64,
+    /// out connections count
+    pub out_connection_count: u64,
+    /// total known peers count
+    pub known_peer_count: u64,
+    /// messages received by each handler and occupancy of their channels
+    pub handler_stats: Vec<HandlerStats>,
+    /// announced peers waiting to be tested
+    pub tester_backlog: ChannelStats,
+    /// banned node count
+    pub banned_peer_count: u64,
+    /// number of IPs currently banned for failing their handshakes
+    pub banned_ip_count: u64,
+    /// number of temporary bans of IPs failing their handshakes, since the node started
+    pub ip_bans: u64,
+}
+
+impl std::fmt::Display for ProtocolStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Protocol stats at {}:", self.timestamp.to_utc_string())?;
+        writeln!(f, "\tIn connections: {}", self.in_connection_count)?;
+        writeln!(f, "\tOut connections: {}", self.out_connection_count)?;
+        writeln!(f, "\tKnown peers: {}", self.known_peer_count)?;
+        for handler_stats in &self.handler_stats {
+            writeln!(f, "\t{}", handler_stats)?;
+        }
+        writeln!(f, "\tTester backlog: {}", self.tester_backlog)?;
+        writeln!(f, "\tBanned peers: {}", self.banned_peer_count)?;
+        writeln!(
+            f,
+            "\tBanned IPs: {} ({} bans since start)",
+            self.banned_ip_count, self.ip_bans
+        )?;
         Ok(())
     }
 }
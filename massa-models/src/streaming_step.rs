@@ -6,7 +6,11 @@ use nom::{
     error::{context, ContextError, ParseError},
     IResult, Parser,
 };
-use std::{marker::PhantomData, ops::Bound::Included};
+use std::{
+    collections::BTreeMap,
+    marker::PhantomData,
+    ops::Bound::{Excluded, Included, Unbounded},
+};
 
 /// Streaming step cursor
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
@@ -28,6 +32,120 @@ impl<T> StreamingStep<T> {
     }
 }
 
+/// Limits of a chunk of streamed entries
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct StreamingBudget {
+    /// Maximum number of entries, as counted by their `StreamingCost`
+    pub max_entries: u64,
+    /// Approximate maximum size of the entries in bytes, as counted by their `StreamingCost`
+    pub max_bytes: u64,
+}
+
+impl StreamingBudget {
+    /// Budget of `max_entries` entries, whatever their size
+    pub fn entries(max_entries: u64) -> Self {
+        StreamingBudget {
+            max_entries,
+            max_bytes: u64::MAX,
+        }
+    }
+}
+
+/// Share of the `StreamingBudget` of a chunk taken by an entry
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct StreamingCost {
+    /// Entries counted against `max_entries`
+    pub entries: u64,
+    /// Bytes counted against `max_bytes`
+    pub bytes: u64,
+}
+
+impl StreamingCost {
+    /// Cost of a single entry of `bytes` bytes
+    pub fn entry(bytes: u64) -> Self {
+        StreamingCost { entries: 1, bytes }
+    }
+}
+
+/// Get the chunk of entries following `cursor`.
+///
+/// `entries_after` gives the entries strictly after a key, or all the entries if there is no
+/// key, in ascending key order. Entries are taken until the next one would exceed `budget`,
+/// their share of the budget being given by `cost`. A chunk always holds at least one entry
+/// when there are entries left, so that streaming goes on whatever the budget.
+///
+/// # Returns
+/// The entries of the chunk and the next streaming step: the key of the last entry of the
+/// chunk, or `Finished(None)` once no entry follows the cursor. A finished cursor is returned
+/// as is with an empty chunk.
+pub fn get_streaming_chunk<K, E, I>(
+    cursor: StreamingStep<K>,
+    entries_after: impl FnOnce(Option<&K>) -> I,
+    budget: StreamingBudget,
+    mut cost: impl FnMut(&K, &E) -> StreamingCost,
+) -> (Vec<(K, E)>, StreamingStep<K>)
+where
+    K: Clone,
+    I: IntoIterator<Item = (K, E)>,
+{
+    let entries = match &cursor {
+        StreamingStep::Started => entries_after(None),
+        StreamingStep::Ongoing(last_key) => entries_after(Some(last_key)),
+        StreamingStep::Finished(_) => return (Vec::new(), cursor),
+    };
+    let mut chunk = Vec::new();
+    let mut chunk_entries: u64 = 0;
+    let mut chunk_bytes: u64 = 0;
+    for (key, entry) in entries {
+        let entry_cost = cost(&key, &entry);
+        let next_entries = chunk_entries.saturating_add(entry_cost.entries);
+        let next_bytes = chunk_bytes.saturating_add(entry_cost.bytes);
+        if !chunk.is_empty() && (next_entries > budget.max_entries || next_bytes > budget.max_bytes)
+        {
+            break;
+        }
+        chunk_entries = next_entries;
+        chunk_bytes = next_bytes;
+        chunk.push((key, entry));
+    }
+    match chunk.last() {
+        Some((last_key, _)) => {
+            let next_step = StreamingStep::Ongoing(last_key.clone());
+            (chunk, next_step)
+        }
+        None => (chunk, StreamingStep::Finished(None)),
+    }
+}
+
+/// Get the chunk of the entries of `map` following `cursor`, see `get_streaming_chunk`
+pub fn get_map_streaming_chunk<'a, K, V>(
+    map: &'a BTreeMap<K, V>,
+    cursor: StreamingStep<K>,
+    budget: StreamingBudget,
+    mut cost: impl FnMut(&K, &V) -> StreamingCost,
+) -> (Vec<(&'a K, &'a V)>, StreamingStep<K>)
+where
+    K: Ord + Clone,
+{
+    let (chunk, next_step) = get_streaming_chunk(
+        cursor,
+        |last_key| {
+            let left_bound = match last_key {
+                Some(last_key) => Excluded(last_key.clone()),
+                None => Unbounded,
+            };
+            map.range((left_bound, Unbounded))
+                .map(|(key, value)| (key.clone(), (key, value)))
+        },
+        budget,
+        |_, (key, value)| cost(key, value),
+    );
+    (
+        chunk.into_iter().map(|(_, entry)| entry).collect(),
+        next_step,
+    )
+}
+
 /// `StreamingStep` serializer
 pub struct StreamingStepSerializer<T, ST>
 where
@@ -135,3 +253,67 @@ where
         .parse(buffer)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::{get_map_streaming_chunk, StreamingBudget, StreamingCost, StreamingStep};
+
+    /// Streams random maps chunk by chunk, the entries weighing their value in bytes
+    #[test]
+    fn test_map_streaming_chunks() {
+        for seed in 0..256 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let map: BTreeMap<u16, u64> = (0..rng.gen_range(0..64))
+                .map(|_| (rng.gen(), rng.gen_range(0..100)))
+                .collect();
+            let max_entries = rng.gen_range(0..8);
+            let max_bytes = rng.gen_range(0..300);
+            let budget = StreamingBudget {
+                max_entries,
+                max_bytes,
+            };
+            let mut streamed = BTreeMap::new();
+            let mut cursor = StreamingStep::Started;
+            while !cursor.finished() {
+                let (chunk, next_cursor) =
+                    get_map_streaming_chunk(&map, cursor, budget, |_, bytes| {
+                        StreamingCost::entry(*bytes)
+                    });
+                if chunk.len() > 1 {
+                    assert!(chunk.len() as u64 <= max_entries, "seed {}", seed);
+                    assert!(
+                        chunk.iter().map(|(_, bytes)| **bytes).sum::<u64>() <= max_bytes,
+                        "seed {}",
+                        seed
+                    );
+                }
+                match next_cursor {
+                    // chunks follow each other without overlap
+                    StreamingStep::Ongoing(last_key) => {
+                        assert!(!chunk.is_empty(), "seed {}", seed);
+                        assert_eq!(*chunk.last().unwrap().0, last_key, "seed {}", seed);
+                        if let StreamingStep::Ongoing(previous_key) = cursor {
+                            assert!(*chunk[0].0 > previous_key, "seed {}", seed);
+                        }
+                    }
+                    StreamingStep::Finished(None) => assert!(chunk.is_empty(), "seed {}", seed),
+                    _ => panic!("seed {}: unexpected cursor {:?}", seed, next_cursor),
+                }
+                streamed.extend(chunk.into_iter().map(|(key, value)| (*key, *value)));
+                cursor = next_cursor;
+            }
+            assert_eq!(streamed, map, "seed {}", seed);
+
+            // a finished stream stays finished
+            let (chunk, cursor) = get_map_streaming_chunk(&map, cursor, budget, |_, bytes| {
+                StreamingCost::entry(*bytes)
+            });
+            assert!(chunk.is_empty(), "seed {}", seed);
+            assert_eq!(cursor, StreamingStep::Finished(None), "seed {}", seed);
+        }
+    }
+}
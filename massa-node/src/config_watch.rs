@@ -0,0 +1,118 @@
+//! Watches the config files and reloads the protocol settings that can change without a restart
+
+use crate::settings::Settings;
+use crossbeam_channel::{bounded, Sender};
+use massa_models::config::{massa_settings_paths, try_build_massa_settings};
+use massa_protocol_exports::{ProtocolConfig, ProtocolController};
+use massa_time::MassaTime;
+use peernet::transports::TransportType;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::thread::JoinHandle;
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+pub struct ConfigWatcher {
+    stop_tx: Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl ConfigWatcher {
+    /// Checks the config files every `interval` and pushes the new protocol settings to protocol when they changed
+    pub fn start(
+        protocol_controller: Box<dyn ProtocolController>,
+        mut protocol_config: ProtocolConfig,
+        interval: MassaTime,
+    ) -> ConfigWatcher {
+        let (stop_tx, stop_rx) = bounded(1);
+        let handle = std::thread::Builder::new()
+            .name("config-watch".to_string())
+            .spawn(move || {
+                let mut modified = files_modified();
+                while stop_rx.recv_timeout(interval.to_duration()).is_err() {
+                    let new_modified = files_modified();
+                    if new_modified == modified {
+                        continue;
+                    }
+                    modified = new_modified;
+                    let settings =
+                        match try_build_massa_settings::<Settings>("massa-node", "MASSA_NODE") {
+                            Ok(settings) => settings,
+                            Err(err) => {
+                                warn!("Config files changed but can't be read: {}", err);
+                                continue;
+                            }
+                        };
+                    let new_config = updated_protocol_config(&protocol_config, &settings);
+                    match protocol_controller.reload_config(new_config.clone()) {
+                        Ok(()) => {
+                            info!("Protocol settings reloaded from the config files");
+                            protocol_config = new_config;
+                        }
+                        Err(err) => warn!("Protocol settings not reloaded: {}", err),
+                    }
+                }
+            })
+            .expect("failed to spawn thread : config-watch");
+        ConfigWatcher { stop_tx, handle }
+    }
+
+    pub fn stop(self) {
+        let _ = self.stop_tx.send(());
+        self.handle
+            .join()
+            .expect("config watcher thread panicked on try to join");
+    }
+}
+
+/// Last modification times of the config files
+fn files_modified() -> Vec<(PathBuf, Option<SystemTime>)> {
+    massa_settings_paths("massa-node")
+        .into_iter()
+        .map(|path| {
+            let modified = std::fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+/// Protocol config built from the current one with the values of the settings.
+/// Protocol rejects the changes of the values that need a restart.
+fn updated_protocol_config(current: &ProtocolConfig, settings: &Settings) -> ProtocolConfig {
+    let protocol = &settings.protocol;
+    let mut listeners = HashMap::default();
+    listeners.insert(protocol.bind, TransportType::Tcp);
+    let mut config = current.clone();
+    config.keypair_file = protocol.keypair_file.clone();
+    config.listeners = listeners;
+    config.routable_ip = protocol.routable_ip.or(settings.network.routable_ip);
+    config.operation_announcement_interval = protocol.operation_announcement_interval;
+    config.operation_announcement_buffer_capacity = protocol.operation_announcement_buffer_capacity;
+    config.operation_quarantine_min_samples = protocol.operation_quarantine_min_samples;
+    config.operation_quarantine_min_useful_percent =
+        protocol.operation_quarantine_min_useful_percent;
+    config.operation_eager_fanout = protocol.operation_eager_fanout;
+    config.operation_batch_proc_period = protocol.operation_batch_proc_period;
+    config.operation_batch_buffer_capacity = protocol.operation_batch_buffer_capacity;
+    config.asked_operations_pruning_period = protocol.asked_operations_pruning_period;
+    config.operation_reconciliation_interval = protocol.operation_reconciliation_interval;
+    config.ask_block_timeout = protocol.ask_block_timeout;
+    config.early_header_max_advance = protocol.early_header_max_advance;
+    config.early_header_expiry = protocol.early_header_expiry;
+    config.send_queues_flush_interval = protocol.send_queues_flush_interval;
+    config.try_connection_timer = protocol.try_connection_timer;
+    config.peers_categories = protocol.peers_categories.clone();
+    config.default_category_info = protocol.default_category_info;
+    config.max_operations_per_message = protocol.max_operations_per_message;
+    config.max_simultaneous_ask_blocks_per_node = protocol.max_simultaneous_ask_blocks_per_node;
+    config.max_block_body_retrieval_peers = protocol.max_block_body_retrieval_peers;
+    config.max_message_size_violations = protocol.max_message_size_violations;
+    config.transports = protocol.transports;
+    config.max_size_block_message = protocol.max_size_block_message;
+    config.max_size_endorsement_message = protocol.max_size_endorsement_message;
+    config.max_size_operation_message = protocol.max_size_operation_message;
+    config.max_size_peer_management_message = protocol.max_size_peer_management_message;
+    config
+}
@@ -6,7 +6,8 @@
 #![feature(ip)]
 extern crate massa_logging;
 
-use crate::settings::SETTINGS;
+use crate::config_watch::ConfigWatcher;
+use crate::settings::{KeypairProtectionSetting, SETTINGS};
 
 use crossbeam_channel::{Receiver, TryRecvError};
 use dialoguer::Password;
@@ -21,7 +22,9 @@ use massa_bootstrap::{
 use massa_consensus_exports::events::ConsensusEvent;
 use massa_consensus_exports::{ConsensusChannels, ConsensusConfig, ConsensusManager};
 use massa_consensus_worker::start_consensus_worker;
-use massa_executed_ops::{ExecutedDenunciationsConfig, ExecutedOpsConfig};
+use massa_executed_ops::{
+    ExecutedDenunciationsConfig, ExecutedOpsConfig, ExecutedOpsHashScheme, ExecutedOpsPruningMode,
+};
 use massa_execution_exports::{
     ExecutionChannels, ExecutionConfig, ExecutionManager, GasCosts, StorageCostsConstants,
 };
@@ -36,10 +39,13 @@ use massa_ledger_worker::FinalLedger;
 use massa_logging::massa_trace;
 use massa_models::address::Address;
 use massa_models::config::constants::{
-    ASYNC_POOL_BOOTSTRAP_PART_SIZE, BLOCK_REWARD, BOOTSTRAP_RANDOMNESS_SIZE_BYTES, CHANNEL_SIZE,
-    CONSENSUS_BOOTSTRAP_PART_SIZE, DEFERRED_CREDITS_BOOTSTRAP_PART_SIZE, DELTA_F0,
-    DENUNCIATION_EXPIRE_PERIODS, ENDORSEMENT_COUNT, END_TIMESTAMP,
-    EXECUTED_OPS_BOOTSTRAP_PART_SIZE, GENESIS_KEY, GENESIS_TIMESTAMP, INITIAL_DRAW_SEED,
+    ASYNC_POOL_BOOTSTRAP_PART_SIZE, BLOCK_REWARD, BOOTSTRAP_RANDOMNESS_SIZE_BYTES, CHAINID,
+    CHANNEL_SIZE, CONSENSUS_BOOTSTRAP_PART_SIZE, DEFERRED_CREDITS_BOOTSTRAP_PART_SIZE, DELTA_F0,
+    DENUNCIATION_EXPIRE_PERIODS, ENDORSEMENT_COUNT, END_TIMESTAMP, EVENT_DATA_GAS_COST_PER_BYTE,
+    EVENT_DATA_LIMIT_VM_VERSION, EXECUTED_OPS_BOOTSTRAP_PART_SIZE,
+    EXECUTED_OPS_BOOTSTRAP_PART_SIZE_BYTES, EXECUTED_OPS_HASH_SCHEME_VERSION,
+    EXECUTED_OPS_PRUNING_BATCH_SIZE, EXECUTED_OPS_PRUNING_MODE_VERSION,
+    EXECUTED_OPS_RETENTION_PERIODS, GENESIS_KEY, GENESIS_TIMESTAMP, INITIAL_DRAW_SEED,
     LEDGER_COST_PER_BYTE, LEDGER_ENTRY_BASE_SIZE, LEDGER_ENTRY_DATASTORE_BASE_SIZE,
     LEDGER_PART_SIZE_MESSAGE_BYTES, MAX_ADVERTISE_LENGTH, MAX_ASK_BLOCKS_PER_MESSAGE,
     MAX_ASYNC_GAS, MAX_ASYNC_MESSAGE_DATA, MAX_ASYNC_POOL_LENGTH, MAX_BLOCK_SIZE,
@@ -47,14 +53,15 @@ use massa_models::config::constants::{
     MAX_BOOTSTRAP_FINAL_STATE_PARTS_SIZE, MAX_BYTECODE_LENGTH, MAX_CONSENSUS_BLOCKS_IDS,
     MAX_DATASTORE_ENTRY_COUNT, MAX_DATASTORE_KEY_LENGTH, MAX_DATASTORE_VALUE_LENGTH,
     MAX_DEFERRED_CREDITS_LENGTH, MAX_DENUNCIATIONS_PER_BLOCK_HEADER,
-    MAX_DENUNCIATION_CHANGES_LENGTH, MAX_ENDORSEMENTS_PER_MESSAGE, MAX_EXECUTED_OPS_CHANGES_LENGTH,
-    MAX_EXECUTED_OPS_LENGTH, MAX_FUNCTION_NAME_LENGTH, MAX_GAS_PER_BLOCK, MAX_LEDGER_CHANGES_COUNT,
-    MAX_LISTENERS_PER_PEER, MAX_OPERATIONS_PER_BLOCK, MAX_OPERATIONS_PER_MESSAGE,
-    MAX_OPERATION_DATASTORE_ENTRY_COUNT, MAX_OPERATION_DATASTORE_KEY_LENGTH,
-    MAX_OPERATION_DATASTORE_VALUE_LENGTH, MAX_OPERATION_STORAGE_TIME, MAX_PARAMETERS_SIZE,
-    MAX_PEERS_IN_ANNOUNCEMENT_LIST, MAX_PRODUCTION_STATS_LENGTH, MAX_ROLLS_COUNT_LENGTH,
-    MAX_SIZE_CHANNEL_COMMANDS_CONNECTIVITY, MAX_SIZE_CHANNEL_COMMANDS_PEERS,
-    MAX_SIZE_CHANNEL_COMMANDS_PEER_TESTERS, MAX_SIZE_CHANNEL_COMMANDS_PROPAGATION_BLOCKS,
+    MAX_DENUNCIATION_CHANGES_LENGTH, MAX_ENDORSEMENTS_PER_MESSAGE, MAX_EVENT_DATA_SIZE,
+    MAX_EXECUTED_OPS_CHANGES_LENGTH, MAX_EXECUTED_OPS_LENGTH, MAX_EXECUTED_OPS_PART_LENGTH,
+    MAX_FUNCTION_NAME_LENGTH, MAX_GAS_PER_BLOCK, MAX_LEDGER_CHANGES_COUNT, MAX_LISTENERS_PER_PEER,
+    MAX_OPERATIONS_PER_BLOCK, MAX_OPERATIONS_PER_MESSAGE, MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+    MAX_OPERATION_DATASTORE_KEY_LENGTH, MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+    MAX_OPERATION_STORAGE_TIME, MAX_PARAMETERS_SIZE, MAX_PEERS_IN_ANNOUNCEMENT_LIST,
+    MAX_PRODUCTION_STATS_LENGTH, MAX_ROLLS_COUNT_LENGTH, MAX_SIZE_CHANNEL_COMMANDS_CONNECTIVITY,
+    MAX_SIZE_CHANNEL_COMMANDS_PEERS, MAX_SIZE_CHANNEL_COMMANDS_PEER_TESTERS,
+    MAX_SIZE_CHANNEL_COMMANDS_PROPAGATION_BLOCKS,
     MAX_SIZE_CHANNEL_COMMANDS_PROPAGATION_ENDORSEMENTS,
     MAX_SIZE_CHANNEL_COMMANDS_PROPAGATION_OPERATIONS, MAX_SIZE_CHANNEL_COMMANDS_RETRIEVAL_BLOCKS,
     MAX_SIZE_CHANNEL_COMMANDS_RETRIEVAL_ENDORSEMENTS,
@@ -67,15 +74,23 @@ use massa_models::config::constants::{
     ROLL_COUNT_TO_SLASH_ON_DENUNCIATION, ROLL_PRICE, SELECTOR_DRAW_CACHE_SIZE, T0, THREAD_COUNT,
     VERSION,
 };
+use massa_models::node::NodeId;
 use massa_pool_exports::{PoolChannels, PoolConfig, PoolManager};
 use massa_pool_worker::start_pool_controller;
 use massa_pos_exports::{PoSConfig, SelectorConfig, SelectorManager};
 use massa_pos_worker::start_selector_worker;
-use massa_protocol_exports::{ProtocolConfig, ProtocolManager};
+use massa_protocol_exports::{
+    IdentityConfig, KeypairProtection, PeerId, ProtocolConfig, ProtocolManager,
+};
+use massa_protocol_worker::keystore::NodeKeystore;
 use massa_protocol_worker::{create_protocol_controller, start_protocol_controller};
+use massa_signature::{KeyPair, PUBLIC_KEY_SIZE_BYTES};
 use massa_storage::Storage;
 use massa_time::MassaTime;
-use massa_versioning_worker::versioning::{MipStatsConfig, MipStore};
+use massa_versioning_worker::{
+    mips::get_mip_list,
+    versioning::{MipStatsConfig, MipStore},
+};
 use massa_wallet::Wallet;
 use parking_lot::RwLock;
 use peernet::transports::TransportType;
@@ -92,11 +107,13 @@ use tokio::sync::{broadcast, mpsc};
 use tracing::{error, info, warn};
 use tracing_subscriber::filter::{filter_fn, LevelFilter};
 
+mod config_watch;
 mod settings;
 
 async fn launch(
     args: &Args,
     node_wallet: Arc<RwLock<Wallet>>,
+    keypair_protection: KeypairProtection,
 ) -> (
     Receiver<ConsensusEvent>,
     Option<BootstrapManager>,
@@ -106,6 +123,7 @@ async fn launch(
     Box<dyn PoolManager>,
     Box<dyn ProtocolManager>,
     Box<dyn FactoryManager>,
+    Option<ConfigWatcher>,
     mpsc::Receiver<()>,
     StopHandle,
     StopHandle,
@@ -163,6 +181,16 @@ async fn launch(
     let executed_ops_config = ExecutedOpsConfig {
         thread_count: THREAD_COUNT,
         bootstrap_part_size: EXECUTED_OPS_BOOTSTRAP_PART_SIZE,
+        bootstrap_part_size_bytes: EXECUTED_OPS_BOOTSTRAP_PART_SIZE_BYTES,
+        retention_periods: EXECUTED_OPS_RETENTION_PERIODS,
+        operation_validity_periods: OPERATION_VALIDITY_PERIODS,
+        pruning_mode: ExecutedOpsPruningMode::from_version(
+            EXECUTED_OPS_PRUNING_MODE_VERSION,
+            EXECUTED_OPS_PRUNING_BATCH_SIZE,
+        )
+        .expect("unknown executed ops pruning mode version"),
+        hash_scheme: ExecutedOpsHashScheme::from_version(EXECUTED_OPS_HASH_SCHEME_VERSION)
+            .expect("unknown executed ops hash scheme version"),
     };
     let executed_denunciations_config = ExecutedDenunciationsConfig {
         denunciation_expire_periods: DENUNCIATION_EXPIRE_PERIODS,
@@ -257,6 +285,7 @@ async fn launch(
         connect_timeout: SETTINGS.bootstrap.connect_timeout,
         bootstrap_timeout: SETTINGS.bootstrap.bootstrap_timeout,
         read_timeout: SETTINGS.bootstrap.read_timeout,
+        stall_timeout: SETTINGS.bootstrap.stall_timeout,
         write_timeout: SETTINGS.bootstrap.write_timeout,
         read_error_timeout: SETTINGS.bootstrap.read_error_timeout,
         write_error_timeout: SETTINGS.bootstrap.write_error_timeout,
@@ -296,6 +325,7 @@ async fn launch(
         max_production_stats_length: MAX_PRODUCTION_STATS_LENGTH,
         max_credits_length: MAX_DEFERRED_CREDITS_LENGTH,
         max_executed_ops_length: MAX_EXECUTED_OPS_LENGTH,
+        max_executed_ops_part_length: MAX_EXECUTED_OPS_PART_LENGTH,
         max_ops_changes_length: MAX_EXECUTED_OPS_CHANGES_LENGTH,
         consensus_bootstrap_part_size: CONSENSUS_BOOTSTRAP_PART_SIZE,
         max_consensus_block_ids: MAX_CONSENSUS_BLOCKS_IDS,
@@ -345,13 +375,13 @@ async fn launch(
             .expect("Overflow when creating constant ledger_entry_datastore_base_size"),
     };
 
-    // Creates an empty default store
+    // Creates a store with the MIPs known by this node version
     let mip_stats_config = MipStatsConfig {
         block_count_considered: MIP_STORE_STATS_BLOCK_CONSIDERED,
         counters_max: MIP_STORE_STATS_COUNTERS_MAX,
     };
-    let mut mip_store =
-        MipStore::try_from(([], mip_stats_config)).expect("Cannot create an empty MIP store");
+    let mut mip_store = MipStore::try_from((get_mip_list(), mip_stats_config))
+        .expect("Cannot create the MIP store");
     if let Some(bootstrap_mip_store) = bootstrap_state.mip_store {
         mip_store
             .update_with(&bootstrap_mip_store)
@@ -378,6 +408,9 @@ async fn launch(
         max_datastore_key_length: MAX_DATASTORE_KEY_LENGTH,
         max_bytecode_size: MAX_BYTECODE_LENGTH,
         max_datastore_value_size: MAX_DATASTORE_VALUE_LENGTH,
+        max_event_size: MAX_EVENT_DATA_SIZE,
+        event_data_gas_cost_per_byte: EVENT_DATA_GAS_COST_PER_BYTE,
+        event_data_limit_vm_version: EVENT_DATA_LIMIT_VM_VERSION,
         storage_costs_constants,
         max_read_only_gas: SETTINGS.execution.max_read_only_gas,
         initial_vesting_path: SETTINGS.execution.initial_vesting_path.clone(),
@@ -461,21 +494,74 @@ async fn launch(
         ask_block_timeout: SETTINGS.protocol.ask_block_timeout,
         max_known_blocks_size: SETTINGS.protocol.max_known_blocks_size,
         max_node_known_blocks_size: SETTINGS.protocol.max_node_known_blocks_size,
+        max_total_node_known_blocks_size: SETTINGS.protocol.max_total_node_known_blocks_size,
+        max_early_headers: SETTINGS.protocol.max_early_headers,
+        early_header_max_advance: SETTINGS.protocol.early_header_max_advance,
+        early_header_expiry: SETTINGS.protocol.early_header_expiry,
         max_node_wanted_blocks_size: SETTINGS.protocol.max_node_wanted_blocks_size,
         max_known_ops_size: SETTINGS.protocol.max_known_ops_size,
         max_node_known_ops_size: SETTINGS.protocol.max_node_known_ops_size,
+        max_total_node_known_ops_size: SETTINGS.protocol.max_total_node_known_ops_size,
         max_known_endorsements_size: SETTINGS.protocol.max_known_endorsements_size,
         max_node_known_endorsements_size: SETTINGS.protocol.max_node_known_endorsements_size,
+        max_total_node_known_endorsements_size: SETTINGS
+            .protocol
+            .max_total_node_known_endorsements_size,
         max_simultaneous_ask_blocks_per_node: SETTINGS
             .protocol
             .max_simultaneous_ask_blocks_per_node,
+        max_block_body_retrieval_peers: SETTINGS.protocol.max_block_body_retrieval_peers,
+        relay_headers_before_integration: SETTINGS.protocol.relay_headers_before_integration,
+        max_blocks_slot_range: SETTINGS.protocol.max_blocks_slot_range,
+        blocks_slot_range_min_interval: SETTINGS.protocol.blocks_slot_range_min_interval,
         max_send_wait: SETTINGS.protocol.max_send_wait,
         operation_batch_buffer_capacity: SETTINGS.protocol.operation_batch_buffer_capacity,
         operation_announcement_buffer_capacity: SETTINGS
             .protocol
             .operation_announcement_buffer_capacity,
+        operation_quarantine_min_samples: SETTINGS.protocol.operation_quarantine_min_samples,
+        operation_quarantine_min_useful_percent: SETTINGS
+            .protocol
+            .operation_quarantine_min_useful_percent,
+        operation_eager_fanout: SETTINGS.protocol.operation_eager_fanout,
+        endorsement_eager_fanout: SETTINGS.protocol.endorsement_eager_fanout,
         operation_batch_proc_period: SETTINGS.protocol.operation_batch_proc_period,
         asked_operations_pruning_period: SETTINGS.protocol.asked_operations_pruning_period,
+        max_operation_ask_attempts: SETTINGS.protocol.max_operation_ask_attempts,
+        max_unanswered_requests: SETTINGS.protocol.max_unanswered_requests,
+        max_queued_messages_per_peer: SETTINGS.protocol.max_queued_messages_per_peer,
+        send_queues_flush_interval: SETTINGS.protocol.send_queues_flush_interval,
+        max_size_block_message: SETTINGS.protocol.max_size_block_message,
+        max_size_endorsement_message: SETTINGS.protocol.max_size_endorsement_message,
+        max_size_operation_message: SETTINGS.protocol.max_size_operation_message,
+        max_size_peer_management_message: SETTINGS.protocol.max_size_peer_management_message,
+        max_message_size_violations: SETTINGS.protocol.max_message_size_violations,
+        sig_verification_thread_count: SETTINGS.protocol.sig_verification_thread_count,
+        sig_verification_max_pending_per_peer: SETTINGS
+            .protocol
+            .sig_verification_max_pending_per_peer,
+        operation_reconciliation_interval: SETTINGS.protocol.operation_reconciliation_interval,
+        operation_reconciliation_buckets: SETTINGS.protocol.operation_reconciliation_buckets,
+        operation_reconciliation_max_ids: SETTINGS.protocol.operation_reconciliation_max_ids,
+        misbehavior_max_peers: SETTINGS.protocol.misbehavior_max_peers,
+        misbehavior_max_records_per_peer: SETTINGS.protocol.misbehavior_max_records_per_peer,
+        max_misbehaviors_before_ban: SETTINGS.protocol.max_misbehaviors_before_ban,
+        misbehavior_ban_window: SETTINGS.protocol.misbehavior_ban_window,
+        keep_alive_interval: SETTINGS.protocol.keep_alive_interval,
+        keep_alive_max_missed: SETTINGS.protocol.keep_alive_max_missed,
+        peer_status_interval: SETTINGS.protocol.peer_status_interval,
+        peer_status_max_lag: SETTINGS.protocol.peer_status_max_lag,
+        peer_gossip_interval: SETTINGS.protocol.peer_gossip_interval,
+        peer_gossip_stable_interval: SETTINGS.protocol.peer_gossip_stable_interval,
+        peer_gossip_min_known_peers: SETTINGS.protocol.peer_gossip_min_known_peers,
+        connection_audit_log_size: SETTINGS.protocol.connection_audit_log_size,
+        relayed_ids_cache_size: SETTINGS.protocol.relayed_ids_cache_size,
+        relayed_ids_retention: SETTINGS.protocol.relayed_ids_retention,
+        relayed_ids_save_interval: SETTINGS.protocol.relayed_ids_save_interval,
+        broadcast_enabled: SETTINGS.api.enable_broadcast,
+        broadcast_protocol_events_channel_capacity: SETTINGS
+            .protocol
+            .broadcast_protocol_events_channel_capacity,
         operation_announcement_interval: SETTINGS.protocol.operation_announcement_interval,
         max_operations_per_message: SETTINGS.protocol.max_operations_per_message,
         max_serialized_operations_size_per_block: MAX_BLOCK_SIZE as usize,
@@ -491,8 +577,43 @@ async fn launch(
         max_endorsements_per_message: MAX_ENDORSEMENTS_PER_MESSAGE as u64,
         max_denunciations_in_block_header: MAX_DENUNCIATIONS_PER_BLOCK_HEADER,
         initial_peers: SETTINGS.protocol.initial_peers_file.clone(),
+        out_connection_targets_file: SETTINGS.protocol.out_connection_targets_file.clone(),
+        peer_annotations_file: SETTINGS.protocol.peer_annotations_file.clone(),
+        capture_file: SETTINGS.protocol.capture_file.clone(),
+        relayed_ids_file: SETTINGS.protocol.relayed_ids_file.clone(),
+        peer_db_journal_file: SETTINGS.protocol.peer_db_journal_file.clone(),
+        max_peers_per_address_bucket: SETTINGS.protocol.max_peers_per_address_bucket,
+        bootstrap_offense_ban_threshold: SETTINGS.protocol.bootstrap_offense_ban_threshold,
+        bootstrap_offense_window: SETTINGS.protocol.bootstrap_offense_window,
+        bootstrap_ip_ban_duration: SETTINGS.protocol.bootstrap_ip_ban_duration,
+        handshake_failure_ban_threshold: SETTINGS.protocol.handshake_failure_ban_threshold,
+        handshake_failure_window: SETTINGS.protocol.handshake_failure_window,
+        handshake_failure_ban_duration: SETTINGS.protocol.handshake_failure_ban_duration,
+        handshake_failure_max_ban_duration: SETTINGS.protocol.handshake_failure_max_ban_duration,
+        half_open_connection_deadline: SETTINGS.protocol.half_open_connection_deadline,
+        max_half_open_connections_per_ip: SETTINGS.protocol.max_half_open_connections_per_ip,
+        dial_back_peers: SETTINGS.protocol.dial_back_peers,
+        dial_back_timeout: SETTINGS.protocol.dial_back_timeout,
+        traffic_padding_bucket_size: SETTINGS.protocol.traffic_padding_bucket_size,
+        max_inbound_gossip_percent: SETTINGS.protocol.max_inbound_gossip_percent,
+        max_new_addresses: SETTINGS.protocol.max_new_addresses,
+        max_tried_addresses: SETTINGS.protocol.max_tried_addresses,
+        max_address_failures: SETTINGS.protocol.max_address_failures,
+        gossip_quiet_margin: SETTINGS.protocol.gossip_quiet_margin,
         listeners,
         keypair_file: SETTINGS.protocol.keypair_file.clone(),
+        keypair_protection,
+        identities: SETTINGS
+            .protocol
+            .identities
+            .iter()
+            .map(|identity| IdentityConfig {
+                keypair_file: identity.keypair_file.clone(),
+                listeners: HashMap::from([(identity.bind, TransportType::Tcp)]),
+                peers_categories: identity.peers_categories.clone(),
+                default_category_info: identity.default_category_info,
+            })
+            .collect(),
         max_known_blocks_saved_size: SETTINGS.protocol.max_known_blocks_size,
         asked_operations_buffer_capacity: SETTINGS.protocol.max_known_ops_size,
         thread_tester_count: SETTINGS.protocol.thread_tester_count,
@@ -525,11 +646,26 @@ async fn launch(
         max_size_block_infos: MAX_ASK_BLOCKS_PER_MESSAGE as u64,
         max_size_listeners_per_peer: MAX_LISTENERS_PER_PEER,
         max_size_peers_announcement: MAX_PEERS_IN_ANNOUNCEMENT_LIST,
-        read_write_limit_bytes_per_second: SETTINGS.protocol.read_write_limit_bytes_per_second
-            as u128,
         try_connection_timer: SETTINGS.protocol.try_connection_timer,
         max_in_connections: SETTINGS.protocol.max_in_connections,
-        timeout_connection: SETTINGS.protocol.timeout_connection,
+        reserved_in_slots: SETTINGS.protocol.reserved_in_slots,
+        reserved_peers: SETTINGS
+            .protocol
+            .reserved_nodes
+            .iter()
+            .map(|node_id| {
+                PeerId::from_bytes(
+                    node_id.get_public_key().to_bytes()[..PUBLIC_KEY_SIZE_BYTES]
+                        .try_into()
+                        .unwrap(),
+                )
+                .expect("invalid reserved node id")
+            })
+            .collect(),
+        transports: SETTINGS.protocol.transports,
+        max_concurrent_dials: SETTINGS.protocol.max_concurrent_dials,
+        dial_cooldown: SETTINGS.protocol.dial_cooldown,
+        dial_jitter: SETTINGS.protocol.dial_jitter,
         routable_ip: SETTINGS
             .protocol
             .routable_ip
@@ -538,6 +674,7 @@ async fn launch(
         peers_categories: SETTINGS.protocol.peers_categories.clone(),
         default_category_info: SETTINGS.protocol.default_category_info,
         version: *VERSION,
+        chain_id: *CHAINID,
     };
 
     let (protocol_controller, protocol_channels) =
@@ -602,6 +739,7 @@ async fn launch(
         shared_storage.clone(),
     );
 
+    let protocol_event_bus = protocol_channels.event_bus.clone();
     let (protocol_manager, keypair, node_id) = start_protocol_controller(
         protocol_config.clone(),
         consensus_controller.clone(),
@@ -612,6 +750,16 @@ async fn launch(
     )
     .expect("could not start protocol controller");
 
+    // watch the config files to reload the protocol settings
+    let config_watcher = (SETTINGS.protocol.config_watch_interval > MassaTime::from_millis(0))
+        .then(|| {
+            ConfigWatcher::start(
+                protocol_controller.clone(),
+                protocol_config.clone(),
+                SETTINGS.protocol.config_watch_interval,
+            )
+        });
+
     // launch factory
     let factory_config = FactoryConfig {
         thread_count: THREAD_COUNT,
@@ -646,7 +794,7 @@ async fn launch(
             consensus_controller.clone(),
             protocol_controller.clone(),
             final_state.clone(),
-            bootstrap_config,
+            bootstrap_config.clone(),
             keypair.clone(),
             *VERSION,
             mip_store.clone(),
@@ -694,6 +842,7 @@ async fn launch(
         consensus_channels.clone(),
         execution_controller.clone(),
         pool_channels.clone(),
+        protocol_event_bus,
         api_config.clone(),
         *VERSION,
     );
@@ -768,6 +917,8 @@ async fn launch(
                 .grpc
                 .client_certificate_authority_root_path
                 .clone(),
+            enable_admin: SETTINGS.grpc.enable_admin,
+            admin_token_path: SETTINGS.grpc.admin_token_path.clone(),
         };
 
         let grpc_api = MassaGrpc {
@@ -812,6 +963,8 @@ async fn launch(
         execution_controller.clone(),
         api_config.clone(),
         node_wallet,
+        bootstrap_config.clone(),
+        *VERSION,
     );
     let api_private_handle = api_private
         .serve(&SETTINGS.api.bind_private, &api_config)
@@ -882,6 +1035,7 @@ async fn launch(
         pool_manager,
         protocol_manager,
         factory_manager,
+        config_watcher,
         api_private_stop_rx,
         api_private_handle,
         api_public_handle,
@@ -898,6 +1052,7 @@ struct Managers {
     pool_manager: Box<dyn PoolManager>,
     protocol_manager: Box<dyn ProtocolManager>,
     factory_manager: Box<dyn FactoryManager>,
+    config_watcher: Option<ConfigWatcher>,
 }
 
 async fn stop(
@@ -910,6 +1065,7 @@ async fn stop(
         mut pool_manager,
         mut protocol_manager,
         mut factory_manager,
+        config_watcher,
     }: Managers,
     api_private_handle: StopHandle,
     api_public_handle: StopHandle,
@@ -945,6 +1101,11 @@ async fn stop(
     // stop factory
     factory_manager.stop();
 
+    // stop config watcher
+    if let Some(config_watcher) = config_watcher {
+        config_watcher.stop();
+    }
+
     // stop protocol controller
     protocol_manager.stop();
 
@@ -974,6 +1135,18 @@ struct Args {
     /// Wallet password
     #[structopt(short = "p", long = "pwd")]
     password: Option<String>,
+    /// Node key file password, when the file is protected by a passphrase
+    #[structopt(long = "node-key-pwd")]
+    node_key_password: Option<String>,
+    /// Write the node keypair in clear to the given file and exit
+    #[structopt(long = "export-node-key")]
+    export_node_key: Option<PathBuf>,
+    /// Replace the node keypair by the one in the given file and exit
+    #[structopt(long = "import-node-key")]
+    import_node_key: Option<PathBuf>,
+    /// Replace the node keypair by a new one and exit. This changes the node id.
+    #[structopt(long = "rotate-node-key")]
+    rotate_node_key: bool,
 
     /// restart_from_snapshot_at_period
     #[structopt(long = "restart-from-snapshot-at-period")]
@@ -1015,6 +1188,65 @@ fn load_wallet(password: Option<String>, path: &Path) -> anyhow::Result<Arc<RwLo
     )?)))
 }
 
+/// Protection of the node key file, asking for its password if necessary
+fn load_keypair_protection(password: Option<String>, path: &Path) -> KeypairProtection {
+    match SETTINGS.protocol.keypair_protection {
+        KeypairProtectionSetting::None => KeypairProtection::None,
+        KeypairProtectionSetting::Passphrase => {
+            KeypairProtection::Passphrase(password.unwrap_or_else(|| {
+                if path.is_file() {
+                    Password::new()
+                        .with_prompt("Enter node key file password")
+                        .interact()
+                        .expect(
+                            "IO error: Password reading failed, node key file couldn't be unlocked",
+                        )
+                } else {
+                    Password::new()
+                        .with_prompt("Enter new password for node key file")
+                        .with_confirmation("Confirm password", "Passwords mismatching")
+                        .interact()
+                        .expect(
+                            "IO error: Password reading failed, node key file couldn't be created",
+                        )
+                }
+            }))
+        }
+    }
+}
+
+/// Run the node key operations asked on the command line.
+/// Returns true if one was run, in which case the node doesn't start.
+fn manage_node_key(args: &Args, keystore: &NodeKeystore) -> anyhow::Result<bool> {
+    if let Some(path) = &args.export_node_key {
+        let keypair = keystore.export()?;
+        std::fs::write(path, serde_json::to_string(&keypair)?)?;
+        println!(
+            "Node key of {} exported to {}",
+            NodeId::new(keypair.get_public_key()),
+            path.display()
+        );
+    } else if let Some(path) = &args.import_node_key {
+        let keypair = serde_json::from_str::<KeyPair>(&std::fs::read_to_string(path)?)?;
+        keystore.import(&keypair)?;
+        println!(
+            "Node key of {} imported, the previous one is kept in {}",
+            NodeId::new(keypair.get_public_key()),
+            keystore.backup_path().display()
+        );
+    } else if args.rotate_node_key {
+        let keypair = keystore.rotate()?;
+        println!(
+            "New node id: {}, the previous key is kept in {}",
+            NodeId::new(keypair.get_public_key()),
+            keystore.backup_path().display()
+        );
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
+}
+
 #[paw::main]
 fn main(args: Args) -> anyhow::Result<()> {
     let tokio_rt = tokio::runtime::Builder::new_multi_thread()
@@ -1061,6 +1293,16 @@ async fn run(args: Args) -> anyhow::Result<()> {
         std::process::exit(1);
     }));
 
+    // resolve the protection of the node key file, asking for its password if necessary
+    let keypair_protection = load_keypair_protection(
+        cur_args.node_key_password.clone(),
+        &SETTINGS.protocol.keypair_file,
+    );
+    let keystore = NodeKeystore::new(&SETTINGS.protocol.keypair_file, keypair_protection.clone());
+    if manage_node_key(&cur_args, &keystore)? {
+        return Ok(());
+    }
+
     // load or create wallet, asking for password if necessary
     let node_wallet = load_wallet(
         cur_args.password.clone(),
@@ -1077,12 +1319,13 @@ async fn run(args: Args) -> anyhow::Result<()> {
             pool_manager,
             protocol_manager,
             factory_manager,
+            config_watcher,
             mut api_private_stop_rx,
             api_private_handle,
             api_public_handle,
             api_handle,
             grpc_handle,
-        ) = launch(&cur_args, node_wallet.clone()).await;
+        ) = launch(&cur_args, node_wallet.clone(), keypair_protection.clone()).await;
 
         // interrupt signal listener
         let (tx, rx) = crossbeam_channel::bounded(1);
@@ -1145,6 +1388,7 @@ async fn run(args: Args) -> anyhow::Result<()> {
                 pool_manager,
                 protocol_manager,
                 factory_manager,
+                config_watcher,
             },
             api_private_handle,
             api_public_handle,
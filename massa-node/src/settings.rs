@@ -5,7 +5,7 @@ use std::{collections::HashMap, path::PathBuf};
 
 use massa_bootstrap::IpType;
 use massa_models::{config::build_massa_settings, node::NodeId};
-use massa_protocol_exports::PeerCategoryInfo;
+use massa_protocol_exports::{PeerCategoryInfo, TransportsConfig};
 use massa_time::MassaTime;
 use serde::Deserialize;
 use std::net::{IpAddr, SocketAddr};
@@ -14,6 +14,16 @@ lazy_static::lazy_static! {
     pub static ref SETTINGS: Settings = build_massa_settings("massa-node", "MASSA_NODE");
 }
 
+/// Protection of the node keypair file at rest
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeypairProtectionSetting {
+    /// stored in clear
+    None,
+    /// encrypted with a password asked at startup
+    Passphrase,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct LoggingSettings {
     pub level: usize,
@@ -59,6 +69,7 @@ pub struct BootstrapSettings {
     pub bind: Option<SocketAddr>,
     pub connect_timeout: MassaTime,
     pub read_timeout: MassaTime,
+    pub stall_timeout: MassaTime,
     pub write_timeout: MassaTime,
     pub read_error_timeout: MassaTime,
     pub write_error_timeout: MassaTime,
@@ -181,18 +192,38 @@ pub struct ProtocolSettings {
     pub max_known_blocks_size: usize,
     /// max known blocks of foreign nodes we keep in memory (by node)
     pub max_node_known_blocks_size: usize,
+    /// max known blocks of foreign nodes we keep in memory (all nodes included)
+    pub max_total_node_known_blocks_size: usize,
+    /// max headers with unknown parents or early slots kept until they can be processed (0 to disable)
+    pub max_early_headers: usize,
+    /// max time between now and the slot of a header kept until its slot
+    pub early_header_max_advance: MassaTime,
+    /// max time a header waits for its parents before it is processed anyway
+    pub early_header_expiry: MassaTime,
     /// max wanted blocks per node kept in memory
     pub max_node_wanted_blocks_size: usize,
     /// max known operations current node kept in memory
     pub max_known_ops_size: usize,
     /// max known operations of foreign nodes we keep in memory (by node)
     pub max_node_known_ops_size: usize,
+    /// max known operations of foreign nodes we keep in memory (all nodes included)
+    pub max_total_node_known_ops_size: usize,
     /// max known endorsements by our node that we kept in memory
     pub max_known_endorsements_size: usize,
     /// max known endorsements of foreign nodes we keep in memory (by node)
     pub max_node_known_endorsements_size: usize,
+    /// max known endorsements of foreign nodes we keep in memory (all nodes included)
+    pub max_total_node_known_endorsements_size: usize,
     /// we ask for the same block `max_simultaneous_ask_blocks_per_node` times at the same time
     pub max_simultaneous_ask_blocks_per_node: usize,
+    /// max number of peers the missing operations of a block are asked to in parallel
+    pub max_block_body_retrieval_peers: usize,
+    /// relay the headers of new blocks as soon as they are checked, before their integration
+    pub relay_headers_before_integration: bool,
+    /// max number of slots of a range of blocks asked by a peer
+    pub max_blocks_slot_range: u64,
+    /// min time between two ranges of blocks served to the same peer
+    pub blocks_slot_range_min_interval: MassaTime,
     /// Max wait time for sending a Network or Node event.
     pub max_send_wait: MassaTime,
     /// Maximum number of batches in the memory buffer.
@@ -201,10 +232,80 @@ pub struct ProtocolSettings {
     /// Maximum number of operations in the announcement buffer.
     /// Immediately announce if overflow.
     pub operation_announcement_buffer_capacity: usize,
+    /// Number of operations received from a peer before it can be quarantined (0 to disable)
+    pub operation_quarantine_min_samples: u64,
+    /// Minimum percentage of useful operations of a peer, below it the peer is quarantined
+    pub operation_quarantine_min_useful_percent: u64,
+    /// Number of random peers to which the new operations are sent in full, the others only get their ids
+    pub operation_eager_fanout: usize,
+    /// Number of random peers to which the new endorsements are sent in full, the others only get their ids
+    pub endorsement_eager_fanout: usize,
     /// Start processing batches in the buffer each `operation_batch_proc_period` in millisecond
     pub operation_batch_proc_period: MassaTime,
     /// All operations asked are prune each `operation_asked_pruning_period` millisecond
     pub asked_operations_pruning_period: MassaTime,
+    /// Maximum number of peers an operation is asked to before we stop re-asking it when asks time out
+    pub max_operation_ask_attempts: usize,
+    /// Number of requests in a row a peer can leave unanswered before being banned (0 for no limit)
+    pub max_unanswered_requests: u64,
+    /// Maximum number of messages queued for a peer whose connection is congested
+    pub max_queued_messages_per_peer: usize,
+    /// Interval at which the messages queued for congested peers are sent
+    pub send_queues_flush_interval: MassaTime,
+    /// Maximum size in bytes of a block message received from a peer
+    pub max_size_block_message: u64,
+    /// Maximum size in bytes of an endorsement message received from a peer
+    pub max_size_endorsement_message: u64,
+    /// Maximum size in bytes of an operation message received from a peer
+    pub max_size_operation_message: u64,
+    /// Maximum size in bytes of a peer management message received from a peer
+    pub max_size_peer_management_message: u64,
+    /// Number of oversized messages after which a peer is banned (0 for no ban)
+    pub max_message_size_violations: u64,
+    /// Number of threads verifying the signatures of the operations and endorsements received (0 to verify them in the handler threads)
+    pub sig_verification_thread_count: usize,
+    /// Maximum number of batches of signatures of a peer waiting to be verified
+    pub sig_verification_max_pending_per_peer: usize,
+    /// Interval at which the digest of our operations is sent to a random peer to recover the operations missed by gossip (0 to disable)
+    pub operation_reconciliation_interval: MassaTime,
+    /// Number of buckets of the operations digests
+    pub operation_reconciliation_buckets: u32,
+    /// Maximum number of operations summarized in a digest, the most recent first
+    pub operation_reconciliation_max_ids: usize,
+    /// Maximum number of peers whose offenses are kept in the misbehavior ledger
+    pub misbehavior_max_peers: usize,
+    /// Maximum number of offenses kept for each peer in the misbehavior ledger
+    pub misbehavior_max_records_per_peer: usize,
+    /// Number of offenses within `misbehavior_ban_window` after which a peer is banned (0 for no ban)
+    pub max_misbehaviors_before_ban: usize,
+    /// Period over which the offenses of a peer are counted
+    pub misbehavior_ban_window: MassaTime,
+    /// Interval at which the config files are checked for changes to reload in protocol (0 to disable)
+    pub config_watch_interval: MassaTime,
+    /// Interval between two keep-alive pings sent to a connected peer
+    pub keep_alive_interval: MassaTime,
+    /// Number of unanswered keep-alive pings after which a connection is closed (0 to disable)
+    pub keep_alive_max_missed: u64,
+    /// Interval between two statuses (final block and head of the blockclique) sent to the connected peers (0 to disable)
+    pub peer_status_interval: MassaTime,
+    /// Number of periods behind our head after which a peer is considered stuck
+    pub peer_status_max_lag: u64,
+    /// Interval between two lists of peers sent while we miss out connections or know few peers
+    pub peer_gossip_interval: MassaTime,
+    /// Interval between two lists of peers sent once the connections are stable
+    pub peer_gossip_stable_interval: MassaTime,
+    /// Number of known peers below which the lists of peers are sent at the short interval
+    pub peer_gossip_min_known_peers: usize,
+    /// Number of connection events kept in the audit log (0 to disable it)
+    pub connection_audit_log_size: usize,
+    /// Maximum number of recently relayed ids kept for each kind of item
+    pub relayed_ids_cache_size: usize,
+    /// Time during which a relayed item isn't relayed again (0 to disable)
+    pub relayed_ids_retention: MassaTime,
+    /// Interval between two saves of the relayed ids
+    pub relayed_ids_save_interval: MassaTime,
+    /// Number of protocol events kept for the slowest API subscriber
+    pub broadcast_protocol_events_channel_capacity: usize,
     /// Interval at which operations are announced in batches.
     pub operation_announcement_interval: MassaTime,
     /// Maximum of operations sent in one message.
@@ -215,8 +316,56 @@ pub struct ProtocolSettings {
     pub max_endorsements_propagation_time: MassaTime,
     /// Path for initial peers
     pub initial_peers_file: PathBuf,
+    /// Path where the out connection targets set at runtime are saved
+    pub out_connection_targets_file: PathBuf,
+    /// Path where the tags and notes attached to the peers by the operator are saved
+    pub peer_annotations_file: PathBuf,
+    /// Path where the received messages are captured, to reproduce an issue
+    pub capture_file: Option<PathBuf>,
+    /// Path where the recently relayed ids are saved, to not relay them again after a restart
+    pub relayed_ids_file: Option<PathBuf>,
+    /// Path where the bans and the trusted peers are journaled, to keep them after an unclean shutdown
+    pub peer_db_journal_file: Option<PathBuf>,
+    /// Max number of known peers kept in the same address bucket
+    pub max_peers_per_address_bucket: usize,
+    /// Number of bootstrap offenses of an IP within the window after which it is banned (0 to disable)
+    pub bootstrap_offense_ban_threshold: u64,
+    /// Window in which the bootstrap offenses of an IP are counted
+    pub bootstrap_offense_window: MassaTime,
+    /// Duration of the ban of an IP after too many bootstrap offenses
+    pub bootstrap_ip_ban_duration: MassaTime,
+    /// Number of failed handshakes of an IP within the window after which it is temporarily banned (0 to disable)
+    pub handshake_failure_ban_threshold: u64,
+    /// Sliding window in which the failed handshakes of an IP are counted
+    pub handshake_failure_window: MassaTime,
+    /// Duration of the first ban of an IP after too many failed handshakes
+    pub handshake_failure_ban_duration: MassaTime,
+    /// Maximum duration of a ban after too many failed handshakes
+    pub handshake_failure_max_ban_duration: MassaTime,
+    /// Time after which a connection whose handshake isn't over is closed (0 to disable)
+    pub half_open_connection_deadline: MassaTime,
+    /// Maximum number of connections of an IP whose handshake is in progress (0 for no limit)
+    pub max_half_open_connections_per_ip: usize,
+    /// Number of connected peers asked to dial back each listener in a reachability self-test
+    pub dial_back_peers: usize,
+    /// Time after which the peers that didn't answer a reachability self-test are reported as such
+    pub dial_back_timeout: MassaTime,
+    /// Size of the buckets to which the hellos and the peer management messages are padded (0 to disable)
+    pub traffic_padding_bucket_size: u64,
+    /// Maximum percentage of the peers tested and gossiped that were only learned through inbound connections
+    pub max_inbound_gossip_percent: u64,
+    /// Maximum number of tested addresses that never passed a handshake
+    pub max_new_addresses: usize,
+    /// Maximum number of tested addresses that passed a handshake
+    pub max_tried_addresses: usize,
+    /// Failed tests after which an address is dropped, or demoted if it passed a handshake before
+    pub max_address_failures: u32,
+    /// Time around our block and endorsement productions during which the low-priority gossip is held
+    pub gossip_quiet_margin: MassaTime,
     /// Keypair
     pub keypair_file: PathBuf,
+    /// Protection of the keypair file at rest
+    pub keypair_protection: KeypairProtectionSetting,
     /// Ip we are bind to listen to
     pub bind: SocketAddr,
     /// Ip seen by others. If none the bind ip is used
@@ -225,18 +374,43 @@ pub struct ProtocolSettings {
     pub connect_timeout: MassaTime,
     /// Number of tester threads
     pub thread_tester_count: u8,
-    /// Number of bytes we can read/write by seconds in a connection (must be a 10 multiple)
-    pub read_write_limit_bytes_per_second: u64,
     /// try connection timer
     pub try_connection_timer: MassaTime,
-    /// Timeout connection
-    pub timeout_connection: MassaTime,
-    /// Nb in connections
+    /// Maximum number of outbound dials in progress, for all the origins
+    pub max_concurrent_dials: usize,
+    /// Minimum time between two dials of the same IP
+    pub dial_cooldown: MassaTime,
+    /// Maximum random delay added before a dial to spread the bursts
+    pub dial_jitter: MassaTime,
+    /// Nb in connections, for all the transports
     pub max_in_connections: usize,
+    /// Nb in connections kept for the reserved nodes
+    pub reserved_in_slots: usize,
+    /// Nodes allowed to use the reserved in connections
+    pub reserved_nodes: Vec<NodeId>,
+    /// Timeouts, limits and rate limits of each transport
+    pub transports: TransportsConfig,
     /// Peers limits per category
     pub peers_categories: HashMap<String, PeerCategoryInfo>,
     /// Limits for default category
     pub default_category_info: PeerCategoryInfo,
+    /// Additional identities, each listening with its own keypair
+    #[serde(default)]
+    pub identities: Vec<IdentitySettings>,
+}
+
+/// Additional network identity of the node
+#[derive(Debug, Deserialize, Clone)]
+pub struct IdentitySettings {
+    /// Keypair of the identity, protected like the node one
+    pub keypair_file: PathBuf,
+    /// Ip the identity listens to
+    pub bind: SocketAddr,
+    /// Peers limits per category on the listener of the identity
+    #[serde(default)]
+    pub peers_categories: HashMap<String, PeerCategoryInfo>,
+    /// Limits for default category on the listener of the identity
+    pub default_category_info: PeerCategoryInfo,
 }
 
 /// gRPC settings
@@ -301,6 +475,10 @@ pub struct GrpcSettings {
     pub server_private_key_path: PathBuf,
     /// client certificate authority root path
     pub client_certificate_authority_root_path: PathBuf,
+    /// whether to enable the admin service, to manage the peers
+    pub enable_admin: bool,
+    /// path of the token authenticating the admin requests, generated if missing
+    pub admin_token_path: PathBuf,
 }
 
 #[cfg(test)]
@@ -3,16 +3,14 @@ use crate::{DeferredCredits, PoSConfig};
 use bitvec::vec::BitVec;
 use massa_hash::Hash;
 use massa_models::error::ModelsError;
-use massa_models::streaming_step::StreamingStep;
+use massa_models::streaming_step::{
+    get_map_streaming_chunk, StreamingBudget, StreamingCost, StreamingStep,
+};
 use massa_models::{address::Address, prehash::PreHashMap, slot::Slot};
 use massa_serialization::{Serializer, U64VarIntSerializer};
 use std::collections::VecDeque;
 use std::ops::RangeBounds;
-use std::{
-    collections::BTreeMap,
-    ops::Bound::{Excluded, Unbounded},
-    path::PathBuf,
-};
+use std::{collections::BTreeMap, path::PathBuf};
 use tracing::debug;
 
 #[derive(Clone)]
@@ -496,25 +494,16 @@ impl PoSFinalState {
         cursor: StreamingStep<Slot>,
     ) -> (DeferredCredits, StreamingStep<Slot>) {
         let mut credits_part = DeferredCredits::new_with_hash();
-        let left_bound = match cursor {
-            StreamingStep::Started => Unbounded,
-            StreamingStep::Ongoing(last_slot) => Excluded(last_slot),
-            StreamingStep::Finished(_) => return (credits_part, cursor),
-        };
-        let mut credit_part_last_slot: Option<Slot> = None;
-        for (slot, credits) in self.deferred_credits.credits.range((left_bound, Unbounded)) {
-            if credits_part.credits.len() < self.config.credits_bootstrap_part_size as usize {
-                credits_part.credits.insert(*slot, credits.clone());
-                credit_part_last_slot = Some(*slot);
-            } else {
-                break;
-            }
-        }
-        if let Some(last_slot) = credit_part_last_slot {
-            (credits_part, StreamingStep::Ongoing(last_slot))
-        } else {
-            (credits_part, StreamingStep::Finished(None))
+        let (credits, next_step) = get_map_streaming_chunk(
+            &self.deferred_credits.credits,
+            cursor,
+            StreamingBudget::entries(self.config.credits_bootstrap_part_size),
+            |_, _| StreamingCost::entry(0),
+        );
+        for (slot, credits) in credits {
+            credits_part.credits.insert(*slot, credits.clone());
         }
+        (credits_part, next_step)
     }
 
     /// Sets a part of the Proof of Stake `cycle_history`. Used only in the bootstrap process.
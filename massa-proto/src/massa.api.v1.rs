@@ -1,3 +1,390 @@
+/// BanPeerRequest holds request for BanPeer
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BanPeerRequest {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Node ids of the peers to ban
+    #[prost(string, repeated, tag = "2")]
+    pub node_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// BanPeerResponse holds response from BanPeer
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BanPeerResponse {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+/// UnbanPeerRequest holds request for UnbanPeer
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UnbanPeerRequest {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Node ids of the peers to unban
+    #[prost(string, repeated, tag = "2")]
+    pub node_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// UnbanPeerResponse holds response from UnbanPeer
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UnbanPeerResponse {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+/// ListPeersRequest holds request for ListPeers
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListPeersRequest {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+/// ListPeersResponse holds response from ListPeers
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListPeersResponse {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Connected peers
+    #[prost(message, repeated, tag = "2")]
+    pub peers: ::prost::alloc::vec::Vec<PeerConnection>,
+}
+/// Connection to a peer
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PeerConnection {
+    /// Node id of the peer
+    #[prost(string, tag = "1")]
+    pub node_id: ::prost::alloc::string::String,
+    /// Address of the peer on this connection
+    #[prost(string, tag = "2")]
+    pub address: ::prost::alloc::string::String,
+    /// Whether we dialed the peer
+    #[prost(bool, tag = "3")]
+    pub is_outgoing: bool,
+    /// Transport of the connection (Optional)
+    #[prost(string, optional, tag = "4")]
+    pub transport: ::core::option::Option<::prost::alloc::string::String>,
+    /// Time at which the handshake succeeded, in milliseconds since 1970-01-01 (Optional)
+    #[prost(fixed64, optional, tag = "5")]
+    pub connected_since: ::core::option::Option<u64>,
+    /// Traffic of the peer since it connected
+    #[prost(message, optional, tag = "6")]
+    pub traffic: ::core::option::Option<PeerTraffic>,
+    /// Tags attached to the peer by the operator
+    #[prost(string, repeated, tag = "7")]
+    pub tags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Note attached to the peer by the operator (Optional)
+    #[prost(string, optional, tag = "8")]
+    pub note: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// Traffic of a connected peer
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PeerTraffic {
+    /// Block messages received from the peer
+    #[prost(fixed64, tag = "1")]
+    pub block_messages: u64,
+    /// Endorsement messages received from the peer
+    #[prost(fixed64, tag = "2")]
+    pub endorsement_messages: u64,
+    /// Operation messages received from the peer
+    #[prost(fixed64, tag = "3")]
+    pub operation_messages: u64,
+    /// Peer management messages received from the peer
+    #[prost(fixed64, tag = "4")]
+    pub peer_management_messages: u64,
+    /// Bytes received from the peer, without the ids of the messages
+    #[prost(fixed64, tag = "5")]
+    pub bytes_received: u64,
+    /// Bytes sent to the peer, without the ids of the messages
+    #[prost(fixed64, tag = "6")]
+    pub bytes_sent: u64,
+    /// Oversized, malformed or badly signed messages received from the peer
+    #[prost(fixed64, tag = "7")]
+    pub invalid_messages: u64,
+    /// Id of the last new block header relayed by the peer (Optional)
+    #[prost(string, optional, tag = "8")]
+    pub last_block_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Period of the slot of the last block relayed by the peer (Optional)
+    #[prost(fixed64, optional, tag = "9")]
+    pub last_block_period: ::core::option::Option<u64>,
+    /// Thread of the slot of the last block relayed by the peer (Optional)
+    #[prost(fixed32, optional, tag = "10")]
+    pub last_block_thread: ::core::option::Option<u32>,
+}
+/// TestPeerRequest holds request for TestPeer
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TestPeerRequest {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Address to test
+    #[prost(string, tag = "2")]
+    pub address: ::prost::alloc::string::String,
+}
+/// TestPeerResponse holds response from TestPeer
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TestPeerResponse {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Address that was tested
+    #[prost(string, tag = "2")]
+    pub address: ::prost::alloc::string::String,
+    /// Whether a connection could be opened to the address
+    #[prost(bool, tag = "3")]
+    pub reachable: bool,
+    /// Node id of the peer if the handshake succeeded (Optional)
+    #[prost(string, optional, tag = "4")]
+    pub node_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Reason of the failure of the handshake (Optional)
+    #[prost(string, optional, tag = "5")]
+    pub error: ::core::option::Option<::prost::alloc::string::String>,
+    /// Time taken to connect and run the handshake in milliseconds (Optional)
+    #[prost(fixed64, optional, tag = "6")]
+    pub latency: ::core::option::Option<u64>,
+}
+/// SetTargetsRequest holds request for SetTargets
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetTargetsRequest {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Target of each category
+    #[prost(message, repeated, tag = "2")]
+    pub categories: ::prost::alloc::vec::Vec<CategoryTarget>,
+    /// Target for the peers that don't belong to any category
+    #[prost(fixed64, tag = "3")]
+    pub default_target: u64,
+}
+/// Number of out connections to maintain for a category
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CategoryTarget {
+    /// Name of the category
+    #[prost(string, tag = "1")]
+    pub category: ::prost::alloc::string::String,
+    /// Number of out connections
+    #[prost(fixed64, tag = "2")]
+    pub target: u64,
+}
+/// SetTargetsResponse holds response from SetTargets
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetTargetsResponse {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+/// ExportPeersRequest holds request for ExportPeers
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportPeersRequest {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+/// ExportPeersResponse holds response from ExportPeers
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportPeersResponse {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Known peers
+    #[prost(message, repeated, tag = "2")]
+    pub peers: ::prost::alloc::vec::Vec<ExportedPeer>,
+}
+/// A known peer with its listeners
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportedPeer {
+    /// Node id of the peer
+    #[prost(string, tag = "1")]
+    pub node_id: ::prost::alloc::string::String,
+    /// Listeners of the peer
+    #[prost(message, repeated, tag = "2")]
+    pub listeners: ::prost::alloc::vec::Vec<PeerListener>,
+    /// Category of the peer (Optional)
+    #[prost(string, optional, tag = "3")]
+    pub category: ::core::option::Option<::prost::alloc::string::String>,
+    /// Last time we heard of the peer, in milliseconds since 1970-01-01
+    #[prost(fixed64, tag = "4")]
+    pub last_seen: u64,
+}
+/// Listener of a peer
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PeerListener {
+    /// Address of the listener
+    #[prost(string, tag = "1")]
+    pub address: ::prost::alloc::string::String,
+    /// Transport of the listener
+    #[prost(string, tag = "2")]
+    pub transport: ::prost::alloc::string::String,
+}
+/// ImportPeersRequest holds request for ImportPeers
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportPeersRequest {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Peers to test
+    #[prost(message, repeated, tag = "2")]
+    pub peers: ::prost::alloc::vec::Vec<ExportedPeer>,
+}
+/// ImportPeersResponse holds response from ImportPeers
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportPeersResponse {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+/// ExportBanReportsRequest holds request for ExportBanReports
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportBanReportsRequest {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+/// ExportBanReportsResponse holds response from ExportBanReports
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportBanReportsResponse {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Reports of the bans
+    #[prost(message, repeated, tag = "2")]
+    pub reports: ::prost::alloc::vec::Vec<ExportedBanReport>,
+}
+/// Signed report of a ban with the message that proves the offense
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportedBanReport {
+    /// Node id of the banned peer
+    #[prost(string, tag = "1")]
+    pub node_id: ::prost::alloc::string::String,
+    /// Kind of offense
+    #[prost(string, tag = "2")]
+    pub kind: ::prost::alloc::string::String,
+    /// Time of the ban, in milliseconds since 1970-01-01
+    #[prost(fixed64, tag = "3")]
+    pub timestamp: u64,
+    /// Node id of the node that banned the peer and signed the report
+    #[prost(string, tag = "4")]
+    pub reporter: ::prost::alloc::string::String,
+    /// Signed report, checked again by the importing node
+    #[prost(bytes = "vec", tag = "5")]
+    pub report: ::prost::alloc::vec::Vec<u8>,
+}
+/// ImportBanReportsRequest holds request for ImportBanReports
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportBanReportsRequest {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Reports to check, only their signed content is used
+    #[prost(message, repeated, tag = "2")]
+    pub reports: ::prost::alloc::vec::Vec<ExportedBanReport>,
+}
+/// ImportBanReportsResponse holds response from ImportBanReports
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportBanReportsResponse {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Outcome of each report, in the order of the request
+    #[prost(message, repeated, tag = "2")]
+    pub results: ::prost::alloc::vec::Vec<ImportedBanReport>,
+}
+/// Outcome of an imported ban report
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportedBanReport {
+    /// Node id of the banned peer, if the report was accepted
+    #[prost(string, optional, tag = "1")]
+    pub node_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Reason of the refusal of the report (Optional)
+    #[prost(string, optional, tag = "2")]
+    pub error: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// TestReachabilityRequest holds request for TestReachability
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TestReachabilityRequest {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+/// TestReachabilityResponse holds response from TestReachability
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TestReachabilityResponse {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Reachability of each announced TCP listener, empty without a routable IP
+    #[prost(message, repeated, tag = "2")]
+    pub listeners: ::prost::alloc::vec::Vec<ListenerReachability>,
+}
+/// Reachability of a listener, as seen by the peers asked to dial it back
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListenerReachability {
+    /// Listener as announced to the peers
+    #[prost(string, tag = "1")]
+    pub address: ::prost::alloc::string::String,
+    /// Node ids of the peers that completed a handshake with the listener
+    #[prost(string, repeated, tag = "2")]
+    pub reachable_from: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Node ids of the peers that could not connect to the listener
+    #[prost(string, repeated, tag = "3")]
+    pub unreachable_from: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Node ids of the peers that didn't answer in time
+    #[prost(string, repeated, tag = "4")]
+    pub unanswered: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// AnnotatePeerRequest holds request for AnnotatePeer
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AnnotatePeerRequest {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Node id of the peer
+    #[prost(string, tag = "2")]
+    pub node_id: ::prost::alloc::string::String,
+    /// Tags of the peer, "protected" peers are never evicted and "logged" ones always logged
+    #[prost(string, repeated, tag = "3")]
+    pub tags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Note about the peer (Optional)
+    #[prost(string, optional, tag = "4")]
+    pub note: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// AnnotatePeerResponse holds response from AnnotatePeer
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AnnotatePeerResponse {
+    /// Request id
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
 /// When an address is drawn to create an endorsement it is selected for a specific index
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -1772,16 +2159,17 @@ impl OpType {
     }
 }
 /// Generated client implementations.
-pub mod massa_service_client {
+pub mod massa_admin_service_client {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
     use tonic::codegen::*;
     use tonic::codegen::http::Uri;
-    /// Massa gRPC service
+    /// Massa gRPC admin service, reserved to the node operator.
+    /// The requests must carry the local admin token in an `authorization: Bearer <token>` metadata.
     #[derive(Debug, Clone)]
-    pub struct MassaServiceClient<T> {
+    pub struct MassaAdminServiceClient<T> {
         inner: tonic::client::Grpc<T>,
     }
-    impl MassaServiceClient<tonic::transport::Channel> {
+    impl MassaAdminServiceClient<tonic::transport::Channel> {
         /// Attempt to create a new client by connecting to a given endpoint.
         pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
         where
@@ -1792,7 +2180,7 @@ pub mod massa_service_client {
             Ok(Self::new(conn))
         }
     }
-    impl<T> MassaServiceClient<T>
+    impl<T> MassaAdminServiceClient<T>
     where
         T: tonic::client::GrpcService<tonic::body::BoxBody>,
         T::Error: Into<StdError>,
@@ -1810,7 +2198,7 @@ pub mod massa_service_client {
         pub fn with_interceptor<F>(
             inner: T,
             interceptor: F,
-        ) -> MassaServiceClient<InterceptedService<T, F>>
+        ) -> MassaAdminServiceClient<InterceptedService<T, F>>
         where
             F: tonic::service::Interceptor,
             T::ResponseBody: Default,
@@ -1824,7 +2212,7 @@ pub mod massa_service_client {
                 http::Request<tonic::body::BoxBody>,
             >>::Error: Into<StdError> + Send + Sync,
         {
-            MassaServiceClient::new(InterceptedService::new(inner, interceptor))
+            MassaAdminServiceClient::new(InterceptedService::new(inner, interceptor))
         }
         /// Compress requests with the given encoding.
         ///
@@ -1857,14 +2245,11 @@ pub mod massa_service_client {
             self.inner = self.inner.max_encoding_message_size(limit);
             self
         }
-        /// Get blocks by ids
-        pub async fn get_blocks(
+        /// Ban peers
+        pub async fn ban_peer(
             &mut self,
-            request: impl tonic::IntoRequest<super::GetBlocksRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::GetBlocksResponse>,
-            tonic::Status,
-        > {
+            request: impl tonic::IntoRequest<super::BanPeerRequest>,
+        ) -> std::result::Result<tonic::Response<super::BanPeerResponse>, tonic::Status> {
             self.inner
                 .ready()
                 .await
@@ -1876,21 +2261,18 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/GetBlocks",
+                "/massa.api.v1.MassaAdminService/BanPeer",
             );
             let mut req = request.into_request();
             req.extensions_mut()
-                .insert(GrpcMethod::new("massa.api.v1.MassaService", "GetBlocks"));
+                .insert(GrpcMethod::new("massa.api.v1.MassaAdminService", "BanPeer"));
             self.inner.unary(req, path, codec).await
         }
-        /// Get blocks by slots
-        pub async fn get_blocks_by_slots(
+        /// Unban peers
+        pub async fn unban_peer(
             &mut self,
-            request: impl tonic::IntoRequest<super::GetBlocksBySlotsRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::GetBlocksBySlotsResponse>,
-            tonic::Status,
-        > {
+            request: impl tonic::IntoRequest<super::UnbanPeerRequest>,
+        ) -> std::result::Result<tonic::Response<super::UnbanPeerResponse>, tonic::Status> {
             self.inner
                 .ready()
                 .await
@@ -1902,23 +2284,18 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/GetBlocksBySlots",
+                "/massa.api.v1.MassaAdminService/UnbanPeer",
             );
             let mut req = request.into_request();
             req.extensions_mut()
-                .insert(
-                    GrpcMethod::new("massa.api.v1.MassaService", "GetBlocksBySlots"),
-                );
+                .insert(GrpcMethod::new("massa.api.v1.MassaAdminService", "UnbanPeer"));
             self.inner.unary(req, path, codec).await
         }
-        /// Get datastore entries
-        pub async fn get_datastore_entries(
+        /// List the connected peers
+        pub async fn list_peers(
             &mut self,
-            request: impl tonic::IntoRequest<super::GetDatastoreEntriesRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::GetDatastoreEntriesResponse>,
-            tonic::Status,
-        > {
+            request: impl tonic::IntoRequest<super::ListPeersRequest>,
+        ) -> std::result::Result<tonic::Response<super::ListPeersResponse>, tonic::Status> {
             self.inner
                 .ready()
                 .await
@@ -1930,23 +2307,18 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/GetDatastoreEntries",
+                "/massa.api.v1.MassaAdminService/ListPeers",
             );
             let mut req = request.into_request();
             req.extensions_mut()
-                .insert(
-                    GrpcMethod::new("massa.api.v1.MassaService", "GetDatastoreEntries"),
-                );
+                .insert(GrpcMethod::new("massa.api.v1.MassaAdminService", "ListPeers"));
             self.inner.unary(req, path, codec).await
         }
-        /// Get largest stakers
-        pub async fn get_largest_stakers(
+        /// Test a peer address right away
+        pub async fn test_peer(
             &mut self,
-            request: impl tonic::IntoRequest<super::GetLargestStakersRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::GetLargestStakersResponse>,
-            tonic::Status,
-        > {
+            request: impl tonic::IntoRequest<super::TestPeerRequest>,
+        ) -> std::result::Result<tonic::Response<super::TestPeerResponse>, tonic::Status> {
             self.inner
                 .ready()
                 .await
@@ -1958,23 +2330,18 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/GetLargestStakers",
+                "/massa.api.v1.MassaAdminService/TestPeer",
             );
             let mut req = request.into_request();
             req.extensions_mut()
-                .insert(
-                    GrpcMethod::new("massa.api.v1.MassaService", "GetLargestStakers"),
-                );
+                .insert(GrpcMethod::new("massa.api.v1.MassaAdminService", "TestPeer"));
             self.inner.unary(req, path, codec).await
         }
-        /// Get next block best parents
-        pub async fn get_next_block_best_parents(
+        /// Set the number of out connections to maintain per category
+        pub async fn set_targets(
             &mut self,
-            request: impl tonic::IntoRequest<super::GetNextBlockBestParentsRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::GetNextBlockBestParentsResponse>,
-            tonic::Status,
-        > {
+            request: impl tonic::IntoRequest<super::SetTargetsRequest>,
+        ) -> std::result::Result<tonic::Response<super::SetTargetsResponse>, tonic::Status> {
             self.inner
                 .ready()
                 .await
@@ -1986,26 +2353,18 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/GetNextBlockBestParents",
+                "/massa.api.v1.MassaAdminService/SetTargets",
             );
             let mut req = request.into_request();
             req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "massa.api.v1.MassaService",
-                        "GetNextBlockBestParents",
-                    ),
-                );
+                .insert(GrpcMethod::new("massa.api.v1.MassaAdminService", "SetTargets"));
             self.inner.unary(req, path, codec).await
         }
-        /// Get operations
-        pub async fn get_operations(
+        /// Export the known peers with their listeners
+        pub async fn export_peers(
             &mut self,
-            request: impl tonic::IntoRequest<super::GetOperationsRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::GetOperationsResponse>,
-            tonic::Status,
-        > {
+            request: impl tonic::IntoRequest<super::ExportPeersRequest>,
+        ) -> std::result::Result<tonic::Response<super::ExportPeersResponse>, tonic::Status> {
             self.inner
                 .ready()
                 .await
@@ -2017,21 +2376,18 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/GetOperations",
+                "/massa.api.v1.MassaAdminService/ExportPeers",
             );
             let mut req = request.into_request();
             req.extensions_mut()
-                .insert(GrpcMethod::new("massa.api.v1.MassaService", "GetOperations"));
+                .insert(GrpcMethod::new("massa.api.v1.MassaAdminService", "ExportPeers"));
             self.inner.unary(req, path, codec).await
         }
-        /// Get smart contracts execution events
-        pub async fn get_sc_execution_events(
+        /// Import peers to test, as exported by another node
+        pub async fn import_peers(
             &mut self,
-            request: impl tonic::IntoRequest<super::GetScExecutionEventsRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::GetScExecutionEventsResponse>,
-            tonic::Status,
-        > {
+            request: impl tonic::IntoRequest<super::ImportPeersRequest>,
+        ) -> std::result::Result<tonic::Response<super::ImportPeersResponse>, tonic::Status> {
             self.inner
                 .ready()
                 .await
@@ -2043,23 +2399,18 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/GetScExecutionEvents",
+                "/massa.api.v1.MassaAdminService/ImportPeers",
             );
             let mut req = request.into_request();
             req.extensions_mut()
-                .insert(
-                    GrpcMethod::new("massa.api.v1.MassaService", "GetScExecutionEvents"),
-                );
+                .insert(GrpcMethod::new("massa.api.v1.MassaAdminService", "ImportPeers"));
             self.inner.unary(req, path, codec).await
         }
-        /// Get selector draws
-        pub async fn get_selector_draws(
+        /// Export the signed reports of the bans proven by a message of the banned peer
+        pub async fn export_ban_reports(
             &mut self,
-            request: impl tonic::IntoRequest<super::GetSelectorDrawsRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::GetSelectorDrawsResponse>,
-            tonic::Status,
-        > {
+            request: impl tonic::IntoRequest<super::ExportBanReportsRequest>,
+        ) -> std::result::Result<tonic::Response<super::ExportBanReportsResponse>, tonic::Status> {
             self.inner
                 .ready()
                 .await
@@ -2071,23 +2422,18 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/GetSelectorDraws",
+                "/massa.api.v1.MassaAdminService/ExportBanReports",
             );
             let mut req = request.into_request();
             req.extensions_mut()
-                .insert(
-                    GrpcMethod::new("massa.api.v1.MassaService", "GetSelectorDraws"),
-                );
+                .insert(GrpcMethod::new("massa.api.v1.MassaAdminService", "ExportBanReports"));
             self.inner.unary(req, path, codec).await
         }
-        /// Get transactions throughput
-        pub async fn get_transactions_throughput(
+        /// Ban the peers of reports exported by another node, once their offense is checked again
+        pub async fn import_ban_reports(
             &mut self,
-            request: impl tonic::IntoRequest<super::GetTransactionsThroughputRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::GetTransactionsThroughputResponse>,
-            tonic::Status,
-        > {
+            request: impl tonic::IntoRequest<super::ImportBanReportsRequest>,
+        ) -> std::result::Result<tonic::Response<super::ImportBanReportsResponse>, tonic::Status> {
             self.inner
                 .ready()
                 .await
@@ -2099,26 +2445,18 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/GetTransactionsThroughput",
+                "/massa.api.v1.MassaAdminService/ImportBanReports",
             );
             let mut req = request.into_request();
             req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "massa.api.v1.MassaService",
-                        "GetTransactionsThroughput",
-                    ),
-                );
+                .insert(GrpcMethod::new("massa.api.v1.MassaAdminService", "ImportBanReports"));
             self.inner.unary(req, path, codec).await
         }
-        /// Get node version
-        pub async fn get_version(
+        /// Ask a few connected peers to dial back the listeners of the node
+        pub async fn test_reachability(
             &mut self,
-            request: impl tonic::IntoRequest<super::GetVersionRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::GetVersionResponse>,
-            tonic::Status,
-        > {
+            request: impl tonic::IntoRequest<super::TestReachabilityRequest>,
+        ) -> std::result::Result<tonic::Response<super::TestReachabilityResponse>, tonic::Status> {
             self.inner
                 .ready()
                 .await
@@ -2130,19 +2468,130 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/GetVersion",
+                "/massa.api.v1.MassaAdminService/TestReachability",
             );
             let mut req = request.into_request();
             req.extensions_mut()
-                .insert(GrpcMethod::new("massa.api.v1.MassaService", "GetVersion"));
+                .insert(GrpcMethod::new("massa.api.v1.MassaAdminService", "TestReachability"));
             self.inner.unary(req, path, codec).await
         }
-        /// New received and produced blocks
-        pub async fn new_blocks(
+        /// Replace the tags and note attached to a peer
+        pub async fn annotate_peer(
             &mut self,
-            request: impl tonic::IntoStreamingRequest<Message = super::NewBlocksRequest>,
+            request: impl tonic::IntoRequest<super::AnnotatePeerRequest>,
+        ) -> std::result::Result<tonic::Response<super::AnnotatePeerResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/massa.api.v1.MassaAdminService/AnnotatePeer",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("massa.api.v1.MassaAdminService", "AnnotatePeer"));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated client implementations.
+pub mod massa_service_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    /// Massa gRPC service
+    #[derive(Debug, Clone)]
+    pub struct MassaServiceClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl MassaServiceClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> MassaServiceClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> MassaServiceClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + Send + Sync,
+        {
+            MassaServiceClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        /// Get blocks by ids
+        pub async fn get_blocks(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetBlocksRequest>,
         ) -> std::result::Result<
-            tonic::Response<tonic::codec::Streaming<super::NewBlocksResponse>>,
+            tonic::Response<super::GetBlocksResponse>,
             tonic::Status,
         > {
             self.inner
@@ -2156,21 +2605,19 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/NewBlocks",
+                "/massa.api.v1.MassaService/GetBlocks",
             );
-            let mut req = request.into_streaming_request();
+            let mut req = request.into_request();
             req.extensions_mut()
-                .insert(GrpcMethod::new("massa.api.v1.MassaService", "NewBlocks"));
-            self.inner.streaming(req, path, codec).await
+                .insert(GrpcMethod::new("massa.api.v1.MassaService", "GetBlocks"));
+            self.inner.unary(req, path, codec).await
         }
-        /// New received and produced blocks headers
-        pub async fn new_blocks_headers(
+        /// Get blocks by slots
+        pub async fn get_blocks_by_slots(
             &mut self,
-            request: impl tonic::IntoStreamingRequest<
-                Message = super::NewBlocksHeadersRequest,
-            >,
+            request: impl tonic::IntoRequest<super::GetBlocksBySlotsRequest>,
         ) -> std::result::Result<
-            tonic::Response<tonic::codec::Streaming<super::NewBlocksHeadersResponse>>,
+            tonic::Response<super::GetBlocksBySlotsResponse>,
             tonic::Status,
         > {
             self.inner
@@ -2184,23 +2631,21 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/NewBlocksHeaders",
+                "/massa.api.v1.MassaService/GetBlocksBySlots",
             );
-            let mut req = request.into_streaming_request();
+            let mut req = request.into_request();
             req.extensions_mut()
                 .insert(
-                    GrpcMethod::new("massa.api.v1.MassaService", "NewBlocksHeaders"),
+                    GrpcMethod::new("massa.api.v1.MassaService", "GetBlocksBySlots"),
                 );
-            self.inner.streaming(req, path, codec).await
+            self.inner.unary(req, path, codec).await
         }
-        /// New received and produced endorsements
-        pub async fn new_endorsements(
+        /// Get datastore entries
+        pub async fn get_datastore_entries(
             &mut self,
-            request: impl tonic::IntoStreamingRequest<
-                Message = super::NewEndorsementsRequest,
-            >,
+            request: impl tonic::IntoRequest<super::GetDatastoreEntriesRequest>,
         ) -> std::result::Result<
-            tonic::Response<tonic::codec::Streaming<super::NewEndorsementsResponse>>,
+            tonic::Response<super::GetDatastoreEntriesResponse>,
             tonic::Status,
         > {
             self.inner
@@ -2214,21 +2659,21 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/NewEndorsements",
+                "/massa.api.v1.MassaService/GetDatastoreEntries",
             );
-            let mut req = request.into_streaming_request();
+            let mut req = request.into_request();
             req.extensions_mut()
-                .insert(GrpcMethod::new("massa.api.v1.MassaService", "NewEndorsements"));
-            self.inner.streaming(req, path, codec).await
+                .insert(
+                    GrpcMethod::new("massa.api.v1.MassaService", "GetDatastoreEntries"),
+                );
+            self.inner.unary(req, path, codec).await
         }
-        /// New received and produced blocks with operations
-        pub async fn new_filled_blocks(
+        /// Get largest stakers
+        pub async fn get_largest_stakers(
             &mut self,
-            request: impl tonic::IntoStreamingRequest<
-                Message = super::NewFilledBlocksRequest,
-            >,
+            request: impl tonic::IntoRequest<super::GetLargestStakersRequest>,
         ) -> std::result::Result<
-            tonic::Response<tonic::codec::Streaming<super::NewFilledBlocksResponse>>,
+            tonic::Response<super::GetLargestStakersResponse>,
             tonic::Status,
         > {
             self.inner
@@ -2242,21 +2687,21 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/NewFilledBlocks",
+                "/massa.api.v1.MassaService/GetLargestStakers",
             );
-            let mut req = request.into_streaming_request();
+            let mut req = request.into_request();
             req.extensions_mut()
-                .insert(GrpcMethod::new("massa.api.v1.MassaService", "NewFilledBlocks"));
-            self.inner.streaming(req, path, codec).await
+                .insert(
+                    GrpcMethod::new("massa.api.v1.MassaService", "GetLargestStakers"),
+                );
+            self.inner.unary(req, path, codec).await
         }
-        /// New received and produced operations
-        pub async fn new_operations(
+        /// Get next block best parents
+        pub async fn get_next_block_best_parents(
             &mut self,
-            request: impl tonic::IntoStreamingRequest<
-                Message = super::NewOperationsRequest,
-            >,
+            request: impl tonic::IntoRequest<super::GetNextBlockBestParentsRequest>,
         ) -> std::result::Result<
-            tonic::Response<tonic::codec::Streaming<super::NewOperationsResponse>>,
+            tonic::Response<super::GetNextBlockBestParentsResponse>,
             tonic::Status,
         > {
             self.inner
@@ -2270,23 +2715,191 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/NewOperations",
+                "/massa.api.v1.MassaService/GetNextBlockBestParents",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "massa.api.v1.MassaService",
+                        "GetNextBlockBestParents",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Get operations
+        pub async fn get_operations(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetOperationsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetOperationsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/massa.api.v1.MassaService/GetOperations",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("massa.api.v1.MassaService", "GetOperations"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Get smart contracts execution events
+        pub async fn get_sc_execution_events(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetScExecutionEventsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetScExecutionEventsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/massa.api.v1.MassaService/GetScExecutionEvents",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("massa.api.v1.MassaService", "GetScExecutionEvents"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Get selector draws
+        pub async fn get_selector_draws(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetSelectorDrawsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetSelectorDrawsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/massa.api.v1.MassaService/GetSelectorDraws",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("massa.api.v1.MassaService", "GetSelectorDraws"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Get transactions throughput
+        pub async fn get_transactions_throughput(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetTransactionsThroughputRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetTransactionsThroughputResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/massa.api.v1.MassaService/GetTransactionsThroughput",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "massa.api.v1.MassaService",
+                        "GetTransactionsThroughput",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Get node version
+        pub async fn get_version(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetVersionRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetVersionResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/massa.api.v1.MassaService/GetVersion",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("massa.api.v1.MassaService", "GetVersion"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// New received and produced blocks
+        pub async fn new_blocks(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::NewBlocksRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::NewBlocksResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/massa.api.v1.MassaService/NewBlocks",
             );
             let mut req = request.into_streaming_request();
             req.extensions_mut()
-                .insert(GrpcMethod::new("massa.api.v1.MassaService", "NewOperations"));
+                .insert(GrpcMethod::new("massa.api.v1.MassaService", "NewBlocks"));
             self.inner.streaming(req, path, codec).await
         }
-        /// New received and slot execution events
-        pub async fn new_slot_execution_outputs(
+        /// New received and produced blocks headers
+        pub async fn new_blocks_headers(
             &mut self,
             request: impl tonic::IntoStreamingRequest<
-                Message = super::NewSlotExecutionOutputsRequest,
+                Message = super::NewBlocksHeadersRequest,
             >,
         ) -> std::result::Result<
-            tonic::Response<
-                tonic::codec::Streaming<super::NewSlotExecutionOutputsResponse>,
-            >,
+            tonic::Response<tonic::codec::Streaming<super::NewBlocksHeadersResponse>>,
             tonic::Status,
         > {
             self.inner
@@ -2300,24 +2913,23 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/NewSlotExecutionOutputs",
+                "/massa.api.v1.MassaService/NewBlocksHeaders",
             );
             let mut req = request.into_streaming_request();
             req.extensions_mut()
                 .insert(
-                    GrpcMethod::new(
-                        "massa.api.v1.MassaService",
-                        "NewSlotExecutionOutputs",
-                    ),
+                    GrpcMethod::new("massa.api.v1.MassaService", "NewBlocksHeaders"),
                 );
             self.inner.streaming(req, path, codec).await
         }
-        /// Send blocks
-        pub async fn send_blocks(
+        /// New received and produced endorsements
+        pub async fn new_endorsements(
             &mut self,
-            request: impl tonic::IntoStreamingRequest<Message = super::SendBlocksRequest>,
+            request: impl tonic::IntoStreamingRequest<
+                Message = super::NewEndorsementsRequest,
+            >,
         ) -> std::result::Result<
-            tonic::Response<tonic::codec::Streaming<super::SendBlocksResponse>>,
+            tonic::Response<tonic::codec::Streaming<super::NewEndorsementsResponse>>,
             tonic::Status,
         > {
             self.inner
@@ -2331,21 +2943,21 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/SendBlocks",
+                "/massa.api.v1.MassaService/NewEndorsements",
             );
             let mut req = request.into_streaming_request();
             req.extensions_mut()
-                .insert(GrpcMethod::new("massa.api.v1.MassaService", "SendBlocks"));
+                .insert(GrpcMethod::new("massa.api.v1.MassaService", "NewEndorsements"));
             self.inner.streaming(req, path, codec).await
         }
-        /// Send endorsements
-        pub async fn send_endorsements(
+        /// New received and produced blocks with operations
+        pub async fn new_filled_blocks(
             &mut self,
             request: impl tonic::IntoStreamingRequest<
-                Message = super::SendEndorsementsRequest,
+                Message = super::NewFilledBlocksRequest,
             >,
         ) -> std::result::Result<
-            tonic::Response<tonic::codec::Streaming<super::SendEndorsementsResponse>>,
+            tonic::Response<tonic::codec::Streaming<super::NewFilledBlocksResponse>>,
             tonic::Status,
         > {
             self.inner
@@ -2359,23 +2971,21 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/SendEndorsements",
+                "/massa.api.v1.MassaService/NewFilledBlocks",
             );
             let mut req = request.into_streaming_request();
             req.extensions_mut()
-                .insert(
-                    GrpcMethod::new("massa.api.v1.MassaService", "SendEndorsements"),
-                );
+                .insert(GrpcMethod::new("massa.api.v1.MassaService", "NewFilledBlocks"));
             self.inner.streaming(req, path, codec).await
         }
-        /// Send operations
-        pub async fn send_operations(
+        /// New received and produced operations
+        pub async fn new_operations(
             &mut self,
             request: impl tonic::IntoStreamingRequest<
-                Message = super::SendOperationsRequest,
+                Message = super::NewOperationsRequest,
             >,
         ) -> std::result::Result<
-            tonic::Response<tonic::codec::Streaming<super::SendOperationsResponse>>,
+            tonic::Response<tonic::codec::Streaming<super::NewOperationsResponse>>,
             tonic::Status,
         > {
             self.inner
@@ -2389,22 +2999,22 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/SendOperations",
+                "/massa.api.v1.MassaService/NewOperations",
             );
             let mut req = request.into_streaming_request();
             req.extensions_mut()
-                .insert(GrpcMethod::new("massa.api.v1.MassaService", "SendOperations"));
+                .insert(GrpcMethod::new("massa.api.v1.MassaService", "NewOperations"));
             self.inner.streaming(req, path, codec).await
         }
-        /// Transactions throughput
-        pub async fn transactions_throughput(
+        /// New received and slot execution events
+        pub async fn new_slot_execution_outputs(
             &mut self,
             request: impl tonic::IntoStreamingRequest<
-                Message = super::TransactionsThroughputRequest,
+                Message = super::NewSlotExecutionOutputsRequest,
             >,
         ) -> std::result::Result<
             tonic::Response<
-                tonic::codec::Streaming<super::TransactionsThroughputResponse>,
+                tonic::codec::Streaming<super::NewSlotExecutionOutputsResponse>,
             >,
             tonic::Status,
         > {
@@ -2419,18 +3029,806 @@ pub mod massa_service_client {
                 })?;
             let codec = tonic::codec::ProstCodec::default();
             let path = http::uri::PathAndQuery::from_static(
-                "/massa.api.v1.MassaService/TransactionsThroughput",
+                "/massa.api.v1.MassaService/NewSlotExecutionOutputs",
             );
             let mut req = request.into_streaming_request();
             req.extensions_mut()
                 .insert(
                     GrpcMethod::new(
                         "massa.api.v1.MassaService",
-                        "TransactionsThroughput",
+                        "NewSlotExecutionOutputs",
                     ),
                 );
             self.inner.streaming(req, path, codec).await
         }
+        /// Send blocks
+        pub async fn send_blocks(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::SendBlocksRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::SendBlocksResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/massa.api.v1.MassaService/SendBlocks",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("massa.api.v1.MassaService", "SendBlocks"));
+            self.inner.streaming(req, path, codec).await
+        }
+        /// Send endorsements
+        pub async fn send_endorsements(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<
+                Message = super::SendEndorsementsRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::SendEndorsementsResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/massa.api.v1.MassaService/SendEndorsements",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("massa.api.v1.MassaService", "SendEndorsements"),
+                );
+            self.inner.streaming(req, path, codec).await
+        }
+        /// Send operations
+        pub async fn send_operations(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<
+                Message = super::SendOperationsRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::SendOperationsResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/massa.api.v1.MassaService/SendOperations",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("massa.api.v1.MassaService", "SendOperations"));
+            self.inner.streaming(req, path, codec).await
+        }
+        /// Transactions throughput
+        pub async fn transactions_throughput(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<
+                Message = super::TransactionsThroughputRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<
+                tonic::codec::Streaming<super::TransactionsThroughputResponse>,
+            >,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/massa.api.v1.MassaService/TransactionsThroughput",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "massa.api.v1.MassaService",
+                        "TransactionsThroughput",
+                    ),
+                );
+            self.inner.streaming(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod massa_admin_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with MassaAdminServiceServer.
+    #[async_trait]
+    pub trait MassaAdminService: Send + Sync + 'static {
+        /// Ban peers
+        async fn ban_peer(
+            &self,
+            request: tonic::Request<super::BanPeerRequest>,
+        ) -> std::result::Result<tonic::Response<super::BanPeerResponse>, tonic::Status>;
+        /// Unban peers
+        async fn unban_peer(
+            &self,
+            request: tonic::Request<super::UnbanPeerRequest>,
+        ) -> std::result::Result<tonic::Response<super::UnbanPeerResponse>, tonic::Status>;
+        /// List the connected peers
+        async fn list_peers(
+            &self,
+            request: tonic::Request<super::ListPeersRequest>,
+        ) -> std::result::Result<tonic::Response<super::ListPeersResponse>, tonic::Status>;
+        /// Test a peer address right away
+        async fn test_peer(
+            &self,
+            request: tonic::Request<super::TestPeerRequest>,
+        ) -> std::result::Result<tonic::Response<super::TestPeerResponse>, tonic::Status>;
+        /// Set the number of out connections to maintain per category
+        async fn set_targets(
+            &self,
+            request: tonic::Request<super::SetTargetsRequest>,
+        ) -> std::result::Result<tonic::Response<super::SetTargetsResponse>, tonic::Status>;
+        /// Export the known peers with their listeners
+        async fn export_peers(
+            &self,
+            request: tonic::Request<super::ExportPeersRequest>,
+        ) -> std::result::Result<tonic::Response<super::ExportPeersResponse>, tonic::Status>;
+        /// Import peers to test, as exported by another node
+        async fn import_peers(
+            &self,
+            request: tonic::Request<super::ImportPeersRequest>,
+        ) -> std::result::Result<tonic::Response<super::ImportPeersResponse>, tonic::Status>;
+        /// Export the signed reports of the bans proven by a message of the banned peer
+        async fn export_ban_reports(
+            &self,
+            request: tonic::Request<super::ExportBanReportsRequest>,
+        ) -> std::result::Result<tonic::Response<super::ExportBanReportsResponse>, tonic::Status>;
+        /// Ban the peers of reports exported by another node, once their offense is checked again
+        async fn import_ban_reports(
+            &self,
+            request: tonic::Request<super::ImportBanReportsRequest>,
+        ) -> std::result::Result<tonic::Response<super::ImportBanReportsResponse>, tonic::Status>;
+        /// Ask a few connected peers to dial back the listeners of the node
+        async fn test_reachability(
+            &self,
+            request: tonic::Request<super::TestReachabilityRequest>,
+        ) -> std::result::Result<tonic::Response<super::TestReachabilityResponse>, tonic::Status>;
+        /// Replace the tags and note attached to a peer
+        async fn annotate_peer(
+            &self,
+            request: tonic::Request<super::AnnotatePeerRequest>,
+        ) -> std::result::Result<tonic::Response<super::AnnotatePeerResponse>, tonic::Status>;
+    }
+    /// Massa gRPC admin service, reserved to the node operator.
+    /// The requests must carry the local admin token in an `authorization: Bearer <token>` metadata.
+    #[derive(Debug)]
+    pub struct MassaAdminServiceServer<T: MassaAdminService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: MassaAdminService> MassaAdminServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for MassaAdminServiceServer<T>
+    where
+        T: MassaAdminService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/massa.api.v1.MassaAdminService/BanPeer" => {
+                    #[allow(non_camel_case_types)]
+                    struct BanPeerSvc<T: MassaAdminService>(pub Arc<T>);
+                    impl<
+                        T: MassaAdminService,
+                    > tonic::server::UnaryService<super::BanPeerRequest>
+                    for BanPeerSvc<T> {
+                        type Response = super::BanPeerResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::BanPeerRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).ban_peer(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = BanPeerSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/massa.api.v1.MassaAdminService/UnbanPeer" => {
+                    #[allow(non_camel_case_types)]
+                    struct UnbanPeerSvc<T: MassaAdminService>(pub Arc<T>);
+                    impl<
+                        T: MassaAdminService,
+                    > tonic::server::UnaryService<super::UnbanPeerRequest>
+                    for UnbanPeerSvc<T> {
+                        type Response = super::UnbanPeerResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UnbanPeerRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).unban_peer(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UnbanPeerSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/massa.api.v1.MassaAdminService/ListPeers" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListPeersSvc<T: MassaAdminService>(pub Arc<T>);
+                    impl<
+                        T: MassaAdminService,
+                    > tonic::server::UnaryService<super::ListPeersRequest>
+                    for ListPeersSvc<T> {
+                        type Response = super::ListPeersResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListPeersRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).list_peers(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListPeersSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/massa.api.v1.MassaAdminService/TestPeer" => {
+                    #[allow(non_camel_case_types)]
+                    struct TestPeerSvc<T: MassaAdminService>(pub Arc<T>);
+                    impl<
+                        T: MassaAdminService,
+                    > tonic::server::UnaryService<super::TestPeerRequest>
+                    for TestPeerSvc<T> {
+                        type Response = super::TestPeerResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::TestPeerRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).test_peer(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = TestPeerSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/massa.api.v1.MassaAdminService/SetTargets" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetTargetsSvc<T: MassaAdminService>(pub Arc<T>);
+                    impl<
+                        T: MassaAdminService,
+                    > tonic::server::UnaryService<super::SetTargetsRequest>
+                    for SetTargetsSvc<T> {
+                        type Response = super::SetTargetsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SetTargetsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).set_targets(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SetTargetsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/massa.api.v1.MassaAdminService/ExportPeers" => {
+                    #[allow(non_camel_case_types)]
+                    struct ExportPeersSvc<T: MassaAdminService>(pub Arc<T>);
+                    impl<
+                        T: MassaAdminService,
+                    > tonic::server::UnaryService<super::ExportPeersRequest>
+                    for ExportPeersSvc<T> {
+                        type Response = super::ExportPeersResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ExportPeersRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).export_peers(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ExportPeersSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/massa.api.v1.MassaAdminService/ImportPeers" => {
+                    #[allow(non_camel_case_types)]
+                    struct ImportPeersSvc<T: MassaAdminService>(pub Arc<T>);
+                    impl<
+                        T: MassaAdminService,
+                    > tonic::server::UnaryService<super::ImportPeersRequest>
+                    for ImportPeersSvc<T> {
+                        type Response = super::ImportPeersResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ImportPeersRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).import_peers(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ImportPeersSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/massa.api.v1.MassaAdminService/ExportBanReports" => {
+                    #[allow(non_camel_case_types)]
+                    struct ExportBanReportsSvc<T: MassaAdminService>(pub Arc<T>);
+                    impl<
+                        T: MassaAdminService,
+                    > tonic::server::UnaryService<super::ExportBanReportsRequest>
+                    for ExportBanReportsSvc<T> {
+                        type Response = super::ExportBanReportsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ExportBanReportsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).export_ban_reports(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ExportBanReportsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/massa.api.v1.MassaAdminService/ImportBanReports" => {
+                    #[allow(non_camel_case_types)]
+                    struct ImportBanReportsSvc<T: MassaAdminService>(pub Arc<T>);
+                    impl<
+                        T: MassaAdminService,
+                    > tonic::server::UnaryService<super::ImportBanReportsRequest>
+                    for ImportBanReportsSvc<T> {
+                        type Response = super::ImportBanReportsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ImportBanReportsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).import_ban_reports(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ImportBanReportsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/massa.api.v1.MassaAdminService/TestReachability" => {
+                    #[allow(non_camel_case_types)]
+                    struct TestReachabilitySvc<T: MassaAdminService>(pub Arc<T>);
+                    impl<
+                        T: MassaAdminService,
+                    > tonic::server::UnaryService<super::TestReachabilityRequest>
+                    for TestReachabilitySvc<T> {
+                        type Response = super::TestReachabilityResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::TestReachabilityRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).test_reachability(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = TestReachabilitySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/massa.api.v1.MassaAdminService/AnnotatePeer" => {
+                    #[allow(non_camel_case_types)]
+                    struct AnnotatePeerSvc<T: MassaAdminService>(pub Arc<T>);
+                    impl<
+                        T: MassaAdminService,
+                    > tonic::server::UnaryService<super::AnnotatePeerRequest>
+                    for AnnotatePeerSvc<T> {
+                        type Response = super::AnnotatePeerResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::AnnotatePeerRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).annotate_peer(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = AnnotatePeerSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: MassaAdminService> Clone for MassaAdminServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    impl<T: MassaAdminService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(Arc::clone(&self.0))
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: MassaAdminService> tonic::server::NamedService for MassaAdminServiceServer<T> {
+        const NAME: &'static str = "massa.api.v1.MassaAdminService";
     }
 }
 /// Generated server implementations.
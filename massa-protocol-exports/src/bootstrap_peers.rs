@@ -2,12 +2,15 @@ use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::ops::Bound::Included;
 
-use massa_models::serialization::{IpAddrDeserializer, IpAddrSerializer};
+use massa_models::serialization::{
+    IpAddrDeserializer, IpAddrSerializer, StringDeserializer, StringSerializer,
+};
 use massa_serialization::{
-    Deserializer, SerializeError, Serializer, U16VarIntDeserializer, U16VarIntSerializer,
-    U32VarIntDeserializer, U32VarIntSerializer,
+    Deserializer, OptionDeserializer, OptionSerializer, SerializeError, Serializer,
+    U16VarIntDeserializer, U16VarIntSerializer, U32VarIntDeserializer, U32VarIntSerializer,
 };
 use massa_signature::PUBLIC_KEY_SIZE_BYTES;
+use massa_time::{MassaTime, MassaTimeDeserializer, MassaTimeSerializer};
 use nom::{
     bytes::complete::take,
     error::{context, ContextError, ParseError},
@@ -26,15 +29,31 @@ pub struct PeerData {
     pub category: String,
 }
 
+/// Maximum length of the category name of a bootstrap peer
+pub const MAX_BOOTSTRAP_PEER_CATEGORY_LENGTH: u16 = 64;
+
+/// Peer info transmitted during bootstrap
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BootstrapPeerInfo {
+    /// Listeners of the peer with their transport
+    pub listeners: HashMap<SocketAddr, TransportType>,
+    /// Category of the peer on the bootstrap server, `None` for the default category
+    pub category: Option<String>,
+    /// Last time the bootstrap server heard of the peer
+    pub last_seen: MassaTime,
+}
+
 /// Peers that are transmitted during bootstrap
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub struct BootstrapPeers(pub Vec<(PeerId, HashMap<SocketAddr, TransportType>)>);
+pub struct BootstrapPeers(pub Vec<(PeerId, BootstrapPeerInfo)>);
 
 /// Serializer for `BootstrapPeers`
 pub struct BootstrapPeersSerializer {
     u32_serializer: U32VarIntSerializer,
     ip_addr_serializer: IpAddrSerializer,
     port_serializer: U16VarIntSerializer,
+    category_serializer: OptionSerializer<String, StringSerializer<U16VarIntSerializer, u16>>,
+    time_serializer: MassaTimeSerializer,
 }
 
 impl BootstrapPeersSerializer {
@@ -44,6 +63,10 @@ impl BootstrapPeersSerializer {
             u32_serializer: U32VarIntSerializer::new(),
             ip_addr_serializer: IpAddrSerializer::new(),
             port_serializer: U16VarIntSerializer::new(),
+            category_serializer: OptionSerializer::new(StringSerializer::new(
+                U16VarIntSerializer::new(),
+            )),
+            time_serializer: MassaTimeSerializer::new(),
         }
     }
 }
@@ -56,8 +79,9 @@ impl Default for BootstrapPeersSerializer {
 
 impl Serializer<BootstrapPeers> for BootstrapPeersSerializer {
     /// ```
-    /// use massa_protocol_exports::{BootstrapPeers, PeerId, TransportType, BootstrapPeersSerializer};
+    /// use massa_protocol_exports::{BootstrapPeerInfo, BootstrapPeers, PeerId, TransportType, BootstrapPeersSerializer};
     /// use massa_serialization::Serializer;
+    /// use massa_time::MassaTime;
     /// use peernet::types::KeyPair;
     /// use std::collections::HashMap;
     /// use std::str::FromStr;
@@ -66,11 +90,19 @@ impl Serializer<BootstrapPeers> for BootstrapPeersSerializer {
     /// let mut peers = vec![];
     /// let mut listeners1 = HashMap::default();
     /// listeners1.insert("127.0.0.1:8080".parse().unwrap(), TransportType::Tcp);
-    /// peers.push((PeerId::from_public_key(keypair1.get_public_key()), listeners1));
+    /// peers.push((PeerId::from_public_key(keypair1.get_public_key()), BootstrapPeerInfo {
+    ///     listeners: listeners1,
+    ///     category: Some("Bootstrap".to_string()),
+    ///     last_seen: MassaTime::from_millis(1000),
+    /// }));
     /// let mut keypair2 = KeyPair::generate();
     /// let mut listeners2 = HashMap::default();
     /// listeners2.insert("[::1]:8080".parse().unwrap(), TransportType::Tcp);
-    /// peers.push((PeerId::from_public_key(keypair1.get_public_key()), listeners2));
+    /// peers.push((PeerId::from_public_key(keypair1.get_public_key()), BootstrapPeerInfo {
+    ///     listeners: listeners2,
+    ///     category: None,
+    ///     last_seen: MassaTime::from_millis(2000),
+    /// }));
     /// let mut serialized = Vec::new();
     /// let peers = BootstrapPeers(peers);
     /// let peers_serializer = BootstrapPeersSerializer::new();
@@ -88,15 +120,25 @@ impl Serializer<BootstrapPeers> for BootstrapPeersSerializer {
             ))
         })?;
         self.u32_serializer.serialize(&peers_count, buffer)?;
-        for (peer_id, listeners) in value.0.iter() {
+        for (peer_id, info) in value.0.iter() {
             buffer.extend_from_slice(&peer_id.to_bytes());
             self.u32_serializer
-                .serialize(&(listeners.len() as u32), buffer)?;
-            for (addr, transport_type) in listeners.iter() {
+                .serialize(&(info.listeners.len() as u32), buffer)?;
+            for (addr, transport_type) in info.listeners.iter() {
                 self.ip_addr_serializer.serialize(&addr.ip(), buffer)?;
                 self.port_serializer.serialize(&addr.port(), buffer)?;
                 buffer.push(*transport_type as u8);
             }
+            if let Some(category) = &info.category {
+                if category.len() > MAX_BOOTSTRAP_PEER_CATEGORY_LENGTH as usize {
+                    return Err(SerializeError::StringTooBig(format!(
+                        "bootstrap peer category {} is too long",
+                        category
+                    )));
+                }
+            }
+            self.category_serializer.serialize(&info.category, buffer)?;
+            self.time_serializer.serialize(&info.last_seen, buffer)?;
         }
         Ok(())
     }
@@ -108,6 +150,9 @@ pub struct BootstrapPeersDeserializer {
     length_listeners_deserializer: U32VarIntDeserializer,
     ip_addr_deserializer: IpAddrDeserializer,
     port_deserializer: U16VarIntDeserializer,
+    category_deserializer:
+        OptionDeserializer<String, StringDeserializer<U16VarIntDeserializer, u16>>,
+    time_deserializer: MassaTimeDeserializer,
 }
 
 impl BootstrapPeersDeserializer {
@@ -125,14 +170,25 @@ impl BootstrapPeersDeserializer {
             ),
             ip_addr_deserializer: IpAddrDeserializer::new(),
             port_deserializer: U16VarIntDeserializer::new(Included(0), Included(u16::MAX)),
+            category_deserializer: OptionDeserializer::new(StringDeserializer::new(
+                U16VarIntDeserializer::new(
+                    Included(0),
+                    Included(MAX_BOOTSTRAP_PEER_CATEGORY_LENGTH),
+                ),
+            )),
+            time_deserializer: MassaTimeDeserializer::new((
+                Included(MassaTime::from_millis(0)),
+                Included(MassaTime::max()),
+            )),
         }
     }
 }
 
 impl Deserializer<BootstrapPeers> for BootstrapPeersDeserializer {
     /// ```
-    /// use massa_protocol_exports::{BootstrapPeers, PeerId, TransportType, BootstrapPeersSerializer, BootstrapPeersDeserializer};
+    /// use massa_protocol_exports::{BootstrapPeerInfo, BootstrapPeers, PeerId, TransportType, BootstrapPeersSerializer, BootstrapPeersDeserializer};
     /// use massa_serialization::{Serializer, Deserializer, DeserializeError};
+    /// use massa_time::MassaTime;
     /// use peernet::types::KeyPair;
     /// use std::collections::HashMap;
     /// use std::str::FromStr;
@@ -141,11 +197,19 @@ impl Deserializer<BootstrapPeers> for BootstrapPeersDeserializer {
     /// let mut peers = vec![];
     /// let mut listeners1 = HashMap::default();
     /// listeners1.insert("127.0.0.1:8080".parse().unwrap(), TransportType::Tcp);
-    /// peers.push((PeerId::from_public_key(keypair1.get_public_key()), listeners1));
+    /// peers.push((PeerId::from_public_key(keypair1.get_public_key()), BootstrapPeerInfo {
+    ///     listeners: listeners1,
+    ///     category: Some("Bootstrap".to_string()),
+    ///     last_seen: MassaTime::from_millis(1000),
+    /// }));
     /// let mut keypair2 = KeyPair::generate();
     /// let mut listeners2 = HashMap::default();
     /// listeners2.insert("[::1]:8080".parse().unwrap(), TransportType::Tcp);
-    /// peers.push((PeerId::from_public_key(keypair1.get_public_key()), listeners2));
+    /// peers.push((PeerId::from_public_key(keypair1.get_public_key()), BootstrapPeerInfo {
+    ///     listeners: listeners2,
+    ///     category: None,
+    ///     last_seen: MassaTime::from_millis(2000),
+    /// }));
     /// let mut serialized = Vec::new();
     /// let peers = BootstrapPeers(peers);
     /// let peers_serializer = BootstrapPeersSerializer::new();
@@ -220,7 +284,23 @@ impl Deserializer<BootstrapPeers> for BootstrapPeersDeserializer {
                             .into_iter()
                             .collect::<HashMap<SocketAddr, TransportType>>()
                     }),
+                    context("Failed category deserialization", |input| {
+                        self.category_deserializer.deserialize(input)
+                    }),
+                    context("Failed last_seen deserialization", |input| {
+                        self.time_deserializer.deserialize(input)
+                    }),
                 ))
+                .map(|(peer_id, listeners, category, last_seen)| {
+                    (
+                        peer_id,
+                        BootstrapPeerInfo {
+                            listeners,
+                            category,
+                            last_seen,
+                        },
+                    )
+                })
                 .parse(input)
             }),
         )
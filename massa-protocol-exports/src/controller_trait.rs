@@ -1,17 +1,22 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use std::collections::HashMap;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 
 use crate::error::ProtocolError;
-use crate::BootstrapPeers;
+use crate::{
+    BanReport, BootstrapClientReport, BootstrapPeers, ConnectionEvent, ListenerReachability,
+    MisbehaviorRecord, OutConnectionTargets, PeerAnnotation, PeerConnectionInfo, PeerTestResult,
+    ProtocolConfig,
+};
 
 use massa_models::prehash::{PreHashMap, PreHashSet};
-use massa_models::stats::NetworkStats;
-use massa_models::{block_header::SecuredHeader, block_id::BlockId};
+use massa_models::stats::{NetworkStats, ProtocolStats};
+use massa_models::{block_header::SecuredHeader, block_id::BlockId, slot::Slot};
 use massa_storage::Storage;
-use peernet::peer::PeerConnectionType;
+use massa_time::MassaTime;
 use peernet::peer_id::PeerId;
+use peernet::transports::TransportType;
 
 #[cfg_attr(any(test, feature = "testing"), mockall::automock)]
 pub trait ProtocolController: Send + Sync {
@@ -58,16 +63,15 @@ pub trait ProtocolController: Send + Sync {
 
     /// Get the stats from the protocol
     /// Returns a tuple containing the stats and the list of peers
-    #[allow(clippy::type_complexity)]
     fn get_stats(
         &self,
-    ) -> Result<
-        (
-            NetworkStats,
-            HashMap<PeerId, (SocketAddr, PeerConnectionType)>,
-        ),
-        ProtocolError,
-    >;
+    ) -> Result<(NetworkStats, HashMap<PeerId, PeerConnectionInfo>), ProtocolError>;
+
+    /// Get a snapshot of the state of the protocol for the monitoring dashboards: message
+    /// rates and queues of the handlers, tester backlog, bans, and the list of peers
+    fn get_protocol_stats(
+        &self,
+    ) -> Result<(ProtocolStats, HashMap<PeerId, PeerConnectionInfo>), ProtocolError>;
 
     /// Get a list of peers to be sent to someone that bootstrap to us
     fn get_bootstrap_peers(&self) -> Result<BootstrapPeers, ProtocolError>;
@@ -78,6 +82,89 @@ pub trait ProtocolController: Send + Sync {
     /// Unban a list of Peer Id
     fn unban_peers(&self, peer_ids: Vec<PeerId>) -> Result<(), ProtocolError>;
 
+    /// Add peers to test, as if they were announced by a connected peer. The ones that
+    /// answer are added to the peer database.
+    fn import_peers(&self, peers: BootstrapPeers) -> Result<(), ProtocolError>;
+
+    /// Change the number of out connections to maintain per category, without restarting.
+    /// The new targets are saved and override the configured ones at the next start.
+    fn set_out_connection_targets(
+        &self,
+        targets: OutConnectionTargets,
+    ) -> Result<(), ProtocolError>;
+
+    /// Replace the tags and note attached to a peer, an empty annotation removes them.
+    /// The annotations are saved and loaded at the next start.
+    fn annotate_peer(
+        &self,
+        peer_id: PeerId,
+        annotation: PeerAnnotation,
+    ) -> Result<(), ProtocolError>;
+
+    /// Test an address right away, ahead of the other pending tests, and return the outcome
+    fn test_peer(&self, address: SocketAddr) -> Result<PeerTestResult, ProtocolError>;
+
+    /// Ask a few connected peers to dial back each of our announced TCP listeners, and return
+    /// the reachability of each listener. Empty when the node has no routable IP.
+    fn test_reachability(&self) -> Result<Vec<ListenerReachability>, ProtocolError>;
+
+    /// Report the decision of the bootstrap server about a client IP.
+    /// Repeated offenses end up in a ban of the IP, whitelisted IPs are preferred as peers.
+    fn report_bootstrap_client(
+        &self,
+        ip: IpAddr,
+        report: BootstrapClientReport,
+    ) -> Result<(), ProtocolError>;
+
+    /// Get the offenses recorded for the given peers, or for all the peers if `None`,
+    /// the oldest first
+    fn get_peer_misbehaviors(
+        &self,
+        peer_ids: Option<Vec<PeerId>>,
+    ) -> Result<HashMap<PeerId, Vec<MisbehaviorRecord>>, ProtocolError>;
+
+    /// Get the signed reports of the bans for offenses that other nodes can check again
+    fn export_ban_reports(&self) -> Result<Vec<BanReport>, ProtocolError>;
+
+    /// Ban the peers of signed reports exported by other nodes. The offense of each report is
+    /// checked again first. Returns for each report the banned peer or why it was refused.
+    fn import_ban_reports(
+        &self,
+        reports: Vec<Vec<u8>>,
+    ) -> Result<Vec<Result<PeerId, String>>, ProtocolError>;
+
+    /// Apply the fields of a new config that can change without a restart to the running
+    /// handlers. Fails if the identity or the listeners of the node changed.
+    fn reload_config(&self, config: ProtocolConfig) -> Result<(), ProtocolError>;
+
+    /// Start listening on a new address without restarting, and announce it to the peers.
+    /// The listener isn't saved: it must be added to the config to be kept after a restart.
+    fn add_listener(&self, addr: SocketAddr, transport: TransportType)
+        -> Result<(), ProtocolError>;
+
+    /// Stop listening on an address without restarting, and announce it to the peers
+    fn remove_listener(&self, addr: SocketAddr) -> Result<(), ProtocolError>;
+
+    /// Change the IP announced to the peers, when it is discovered behind a NAT for example.
+    /// A fresh announcement is signed and sent to the peers if it changed.
+    fn set_routable_ip(&self, routable_ip: Option<IpAddr>) -> Result<(), ProtocolError>;
+
+    /// Get the connection events of the audit log recorded since `since` (all the kept ones
+    /// if `None`), for the given peer or for all the peers if `None`, the oldest first
+    fn get_connection_events(
+        &self,
+        since: Option<MassaTime>,
+        peer_id: Option<PeerId>,
+    ) -> Result<Vec<ConnectionEvent>, ProtocolError>;
+
+    /// Tell the slots at which one of our addresses produces a block or endorsements, so that
+    /// the low-priority gossip is held around them
+    fn set_production_slots(
+        &self,
+        block_slots: Vec<Slot>,
+        endorsement_slots: Vec<Slot>,
+    ) -> Result<(), ProtocolError>;
+
     /// Returns a boxed clone of self.
     /// Useful to allow cloning `Box<dyn ProtocolController>`.
     fn clone_box(&self) -> Box<dyn ProtocolController>;
@@ -41,6 +41,79 @@ pub enum ProtocolError {
     InvalidOperationError(String),
     /// Listener error: {0}
     ListenerError(String),
+    /// Config reload error: {0}
+    ConfigReloadError(String),
+    /// Peer handler error: {0}
+    PeerHandlerError(#[from] PeerHandlerError),
+}
+
+/// Step of the handshake with a peer
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakeStage {
+    /// hello
+    Hello,
+    /// exchange of the challenges
+    Challenge,
+}
+
+/// peer handler error
+#[non_exhaustive]
+#[derive(Display, Error, Debug, Clone, PartialEq, Eq)]
+pub enum PeerHandlerError {
+    /// Invalid {stage} message: {reason}
+    InvalidMessage {
+        /// step of the handshake
+        stage: HandshakeStage,
+        /// what was wrong with the message
+        reason: String,
+    },
+    /// Incompatible version: {0}
+    IncompatibleVersion(String),
+    /// Received hello from another network
+    WrongNetwork,
+    /// Peer is banned
+    PeerBanned,
+    /// Peer IP {0} is banned
+    IpBanned(IpAddr),
+    /// Invalid signature during the {0}
+    InvalidSignature(HandshakeStage),
+    /// Already connected to the peer
+    AlreadyConnected,
+    /// The peer is another identity of this node
+    OwnIdentity,
+    /// Distant peer don't have slot for us
+    NoSlot,
+    /// Only the reserved peers can use our remaining in slots
+    ReservedSlots,
+    /// Too many connections of IP {0} are in handshake
+    TooManyHalfOpenConnections(IpAddr),
+    /// Handshake not over before the deadline
+    HandshakeDeadline,
+    /// Serialization error: {0}
+    SerializationError(String),
+    /// Network error: {0}
+    NetworkError(String),
+    /// Channel error: {0}
+    ChannelError(String),
+    /// Peer DB error: {0}
+    PeerDBError(String),
+}
+
+impl PeerHandlerError {
+    /// Whether connecting to the peer again later may succeed: the failure doesn't come from
+    /// an incompatible or misbehaving peer
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            PeerHandlerError::AlreadyConnected
+                | PeerHandlerError::NoSlot
+                | PeerHandlerError::ReservedSlots
+                | PeerHandlerError::TooManyHalfOpenConnections(_)
+                | PeerHandlerError::NetworkError(_)
+                | PeerHandlerError::ChannelError(_)
+                | PeerHandlerError::PeerDBError(_)
+        )
+    }
 }
 
 #[derive(Debug)]
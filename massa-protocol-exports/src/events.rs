@@ -0,0 +1,114 @@
+//! Bus of the events of the protocol, for the explorers and the monitoring tools.
+//!
+//! The bus is a bounded ring buffer: publishing never blocks the protocol threads, and a
+//! subscriber that doesn't keep up misses the oldest events instead of slowing everyone down.
+//! It is told how many events it missed the next time it reads the bus.
+
+use massa_models::{block_id::BlockId, operation::OperationId, slot::Slot};
+use peernet::peer_id::PeerId;
+use tokio::sync::broadcast;
+
+use crate::ConnectionEvent;
+
+/// An event published by the protocol
+#[derive(Debug, Clone)]
+pub enum ProtocolEvent {
+    /// A connection changed: handshake, disconnection, ban...
+    Connection(ConnectionEvent),
+    /// A peer sent us the header of a block we didn't know
+    BlockHeaderReceived {
+        /// id of the block
+        block_id: BlockId,
+        /// slot of the block
+        slot: Slot,
+        /// peer that sent the header
+        peer_id: PeerId,
+    },
+    /// A peer sent us operations we didn't know, their signature is verified
+    OperationsReceived {
+        /// ids of the new operations
+        operation_ids: Vec<OperationId>,
+        /// peer that sent the operations
+        peer_id: PeerId,
+    },
+}
+
+/// Bus on which the protocol publishes its events
+#[derive(Debug, Clone)]
+pub struct ProtocolEventBus {
+    sender: broadcast::Sender<ProtocolEvent>,
+    /// whether the events are published
+    enabled: bool,
+}
+
+impl ProtocolEventBus {
+    /// Creates a bus keeping the last `capacity` events for its slowest subscriber
+    pub fn new(capacity: usize, enabled: bool) -> Self {
+        ProtocolEventBus {
+            sender: broadcast::channel(capacity.max(1)).0,
+            enabled,
+        }
+    }
+
+    /// Publishes an event, never blocks. The event is dropped if the bus is disabled or if
+    /// nobody listens.
+    pub fn publish(&self, event: ProtocolEvent) {
+        if self.is_listened() {
+            // the only error is the absence of subscribers
+            let _ = self.sender.send(event);
+        }
+    }
+
+    /// Whether publishing an event is useful, to avoid building the events nobody reads
+    pub fn is_listened(&self) -> bool {
+        self.enabled && self.sender.receiver_count() > 0
+    }
+
+    /// Subscribes to the events published from now on. A subscriber lagging more than the
+    /// capacity of the bus gets `RecvError::Lagged` with the number of events it missed.
+    pub fn subscribe(&self) -> broadcast::Receiver<ProtocolEvent> {
+        self.sender.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use massa_hash::Hash;
+    use massa_models::{block_id::BlockId, slot::Slot};
+    use peernet::{peer_id::PeerId, types::KeyPair};
+    use tokio::sync::broadcast::error::TryRecvError;
+
+    use super::{ProtocolEvent, ProtocolEventBus};
+
+    fn event(period: u64, peer_id: &PeerId) -> ProtocolEvent {
+        ProtocolEvent::BlockHeaderReceived {
+            block_id: BlockId(Hash::compute_from(&period.to_be_bytes())),
+            slot: Slot::new(period, 0),
+            peer_id: peer_id.clone(),
+        }
+    }
+
+    #[test]
+    fn test_slow_subscriber_misses_the_oldest_events() {
+        let peer_id = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        let bus = ProtocolEventBus::new(2, true);
+        // nobody listens yet
+        bus.publish(event(0, &peer_id));
+        let mut subscriber = bus.subscribe();
+        for period in 1..=3 {
+            bus.publish(event(period, &peer_id));
+        }
+        assert_eq!(subscriber.try_recv().unwrap_err(), TryRecvError::Lagged(1));
+        match subscriber.try_recv().unwrap() {
+            ProtocolEvent::BlockHeaderReceived { slot, .. } => assert_eq!(slot, Slot::new(2, 0)),
+            other => panic!("unexpected event {:?}", other),
+        }
+        assert!(subscriber.try_recv().is_ok());
+        assert_eq!(subscriber.try_recv().unwrap_err(), TryRecvError::Empty);
+
+        let disabled = ProtocolEventBus::new(2, false);
+        let mut subscriber = disabled.subscribe();
+        disabled.publish(event(4, &peer_id));
+        assert_eq!(subscriber.try_recv().unwrap_err(), TryRecvError::Empty);
+    }
+}
@@ -1,17 +1,29 @@
 mod bootstrap_peers;
 mod controller_trait;
 mod error;
+mod events;
+mod peer_connection;
 mod settings;
 
 pub use bootstrap_peers::{
-    BootstrapPeers, BootstrapPeersDeserializer, BootstrapPeersSerializer, PeerData,
+    BootstrapPeerInfo, BootstrapPeers, BootstrapPeersDeserializer, BootstrapPeersSerializer,
+    PeerData, MAX_BOOTSTRAP_PEER_CATEGORY_LENGTH,
 };
 pub use controller_trait::{ProtocolController, ProtocolManager};
-pub use error::ProtocolError;
+pub use error::{HandshakeStage, PeerHandlerError, ProtocolError};
+pub use events::{ProtocolEvent, ProtocolEventBus};
+pub use peer_connection::{
+    BanReport, BootstrapClientReport, ConnectionEvent, ConnectionEventKind, ListenerReachability,
+    MisbehaviorKind, MisbehaviorRecord, PeerAnnotation, PeerConnectionInfo, PeerTestResult,
+    PeerTrafficStats, LOGGED_PEER_TAG, PROTECTED_PEER_TAG,
+};
 pub use peernet::peer::PeerConnectionType;
 pub use peernet::peer_id::PeerId;
 pub use peernet::transports::TransportType;
-pub use settings::{PeerCategoryInfo, ProtocolConfig};
+pub use settings::{
+    IdentityConfig, KeypairProtection, OutConnectionTargets, PeerCategoryInfo, ProtocolConfig,
+    TransportConfig, TransportsConfig,
+};
 
 #[cfg(feature = "testing")]
 pub mod test_exports;
@@ -0,0 +1,242 @@
+use std::collections::BTreeSet;
+use std::net::SocketAddr;
+
+use crate::ProtocolError;
+
+use massa_models::{block_id::BlockId, slot::Slot};
+use massa_time::MassaTime;
+use peernet::peer::PeerConnectionType;
+use peernet::peer_id::PeerId;
+use peernet::transports::TransportType;
+use serde::{Deserialize, Serialize};
+
+/// Information about a live connection to a peer
+#[derive(Debug, Clone)]
+pub struct PeerConnectionInfo {
+    /// Address of the peer on this connection
+    pub address: SocketAddr,
+    /// Whether we dialed the peer or the peer dialed us
+    pub connection_type: PeerConnectionType,
+    /// Transport used by the connection, if it could be determined
+    pub transport: Option<TransportType>,
+    /// Time at which the handshake of the connection succeeded, if recorded
+    pub connected_since: Option<MassaTime>,
+    /// Time of the last answer of the peer to a keep-alive ping on this connection
+    pub last_pong: Option<MassaTime>,
+    /// Traffic of the peer since it connected
+    pub traffic: PeerTrafficStats,
+    /// Tags and note attached to the peer by the operator
+    pub annotation: Option<PeerAnnotation>,
+}
+
+/// Tag of the peers that are never evicted from the peer database
+pub const PROTECTED_PEER_TAG: &str = "protected";
+
+/// Tag of the peers whose connection events are always logged
+pub const LOGGED_PEER_TAG: &str = "logged";
+
+/// Tags and note attached to a peer by the operator, kept even when the peer leaves the peer
+/// database
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerAnnotation {
+    /// Labels of the peer, like "my-sentry-1". `PROTECTED_PEER_TAG` and `LOGGED_PEER_TAG` also
+    /// change how the node treats the peer.
+    pub tags: BTreeSet<String>,
+    /// Free text about the peer
+    pub note: Option<String>,
+}
+
+impl PeerAnnotation {
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.note.is_none()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+}
+
+/// Traffic of a connected peer, to tell the peers that only download or that flood the node
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PeerTrafficStats {
+    /// Block messages received from the peer
+    pub block_messages: u64,
+    /// Endorsement messages received from the peer
+    pub endorsement_messages: u64,
+    /// Operation messages received from the peer
+    pub operation_messages: u64,
+    /// Peer management messages received from the peer
+    pub peer_management_messages: u64,
+    /// Bytes received from the peer, without the ids of the messages
+    pub bytes_received: u64,
+    /// Bytes sent to the peer, without the ids of the messages
+    pub bytes_sent: u64,
+    /// Oversized, malformed or badly signed messages received from the peer
+    pub invalid_messages: u64,
+    /// Last new block header relayed by the peer, with its slot
+    pub last_block: Option<(BlockId, Slot)>,
+}
+
+/// Outcome of a test of a peer address requested by an operator
+#[derive(Debug, Clone)]
+pub struct PeerTestResult {
+    /// Address that was tested
+    pub address: SocketAddr,
+    /// Whether a connection could be opened to the address
+    pub reachable: bool,
+    /// Id of the peer if the handshake succeeded, the reason of the failure otherwise
+    pub handshake: Result<PeerId, String>,
+    /// Time taken to connect and run the handshake, if the address was reachable
+    pub latency: Option<MassaTime>,
+}
+
+/// Reachability of one of our listeners, as seen by the connected peers asked to dial it back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenerReachability {
+    /// Listener as announced to the peers
+    pub address: SocketAddr,
+    /// Peers that completed a handshake with the listener
+    pub reachable_from: Vec<PeerId>,
+    /// Peers that could not connect to the listener or complete the handshake
+    pub unreachable_from: Vec<PeerId>,
+    /// Peers that didn't answer in time, or that refused to dial the listener
+    pub unanswered: Vec<PeerId>,
+}
+
+impl ListenerReachability {
+    /// Whether at least one peer reached the listener from the outside
+    pub fn is_reachable(&self) -> bool {
+        !self.reachable_from.is_empty()
+    }
+}
+
+/// Decision of the bootstrap server about a client, fed to the peer database
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BootstrapClientReport {
+    /// The client is in the bootstrap whitelist
+    Whitelisted,
+    /// The client is in the bootstrap blacklist
+    Blacklisted,
+    /// The client asked to bootstrap again before the minimum interval
+    RateLimited,
+}
+
+/// Kind of an offense committed by a peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MisbehaviorKind {
+    /// An operation, endorsement, block or metadata with an invalid signature
+    InvalidSignature,
+    /// A message that could not be deserialized
+    MalformedMessage,
+    /// Oversized or too frequent messages
+    Spam,
+    /// Any other breach of the protocol: inconsistent blocks, unanswered requests...
+    ProtocolViolation,
+}
+
+impl MisbehaviorKind {
+    /// Kind of the offense revealed by an error raised while processing the data of a peer
+    pub fn from_error(error: &ProtocolError) -> Self {
+        match error {
+            ProtocolError::WrongSignature => MisbehaviorKind::InvalidSignature,
+            _ => MisbehaviorKind::ProtocolViolation,
+        }
+    }
+}
+
+impl std::fmt::Display for MisbehaviorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MisbehaviorKind::InvalidSignature => write!(f, "invalid signature"),
+            MisbehaviorKind::MalformedMessage => write!(f, "malformed message"),
+            MisbehaviorKind::Spam => write!(f, "spam"),
+            MisbehaviorKind::ProtocolViolation => write!(f, "protocol violation"),
+        }
+    }
+}
+
+/// An offense committed by a peer, kept to audit the bans
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MisbehaviorRecord {
+    /// Time at which the offense was noticed
+    pub timestamp: MassaTime,
+    /// Kind of the offense
+    pub kind: MisbehaviorKind,
+    /// Short description of what the peer sent
+    pub evidence: String,
+    /// Whether the offense got the peer banned
+    pub banned: bool,
+}
+
+/// Signed report of a ban for an offense that other nodes can check again, shared between
+/// operators
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BanReport {
+    /// Banned peer
+    pub peer_id: PeerId,
+    /// Kind of the offense
+    pub kind: MisbehaviorKind,
+    /// Time at which the offense was noticed
+    pub timestamp: MassaTime,
+    /// Node that noticed the offense and signed the report
+    pub reporter: PeerId,
+    /// Report signed by the reporter, with the data received from the peer
+    pub signed: Vec<u8>,
+}
+
+/// Kind of an event kept in the connection audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionEventKind {
+    /// A handshake started with a peer that dialed us or that we dialed
+    HandshakeStarted,
+    /// The handshake succeeded and the peer is connected
+    HandshakeSucceeded,
+    /// The handshake failed
+    HandshakeFailed,
+    /// A peer we have no slot for was sent some peers and disconnected
+    Fallback,
+    /// We closed the connection
+    Disconnected,
+    /// The peer told us it was closing the connection
+    PeerDisconnected,
+    /// The peer was banned
+    Banned,
+    /// The peer was unbanned
+    Unbanned,
+    /// The IP was banned
+    IpBanned,
+    /// The peer finalized another block than ours at the same slot
+    ForkDivergence,
+}
+
+impl std::fmt::Display for ConnectionEventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectionEventKind::HandshakeStarted => write!(f, "handshake started"),
+            ConnectionEventKind::HandshakeSucceeded => write!(f, "handshake succeeded"),
+            ConnectionEventKind::HandshakeFailed => write!(f, "handshake failed"),
+            ConnectionEventKind::Fallback => write!(f, "fallback"),
+            ConnectionEventKind::Disconnected => write!(f, "disconnected"),
+            ConnectionEventKind::PeerDisconnected => write!(f, "disconnected by the peer"),
+            ConnectionEventKind::Banned => write!(f, "banned"),
+            ConnectionEventKind::Unbanned => write!(f, "unbanned"),
+            ConnectionEventKind::IpBanned => write!(f, "IP banned"),
+            ConnectionEventKind::ForkDivergence => write!(f, "on another fork"),
+        }
+    }
+}
+
+/// An event of the connection audit log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionEvent {
+    /// Time of the event
+    pub timestamp: MassaTime,
+    /// What happened
+    pub kind: ConnectionEventKind,
+    /// Peer concerned, if it was known at that time
+    pub peer_id: Option<PeerId>,
+    /// Address of the other end of the connection, if known
+    pub address: Option<SocketAddr>,
+    /// Reason of the failure, disconnection or ban
+    pub reason: Option<String>,
+}
@@ -1,17 +1,18 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     net::{IpAddr, SocketAddr},
     path::PathBuf,
 };
 
+use crate::ProtocolError;
 use massa_models::version::Version;
 use massa_time::MassaTime;
-use peernet::transports::TransportType;
-use serde::Deserialize;
+use peernet::{peer_id::PeerId, transports::TransportType};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub struct PeerCategoryInfo {
     pub target_out_connections: usize,
     pub max_in_connections_pre_handshake: usize,
@@ -19,15 +20,178 @@ pub struct PeerCategoryInfo {
     pub max_in_connections_per_ip: usize,
 }
 
+/// Tunables of the connections of one transport
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct TransportConfig {
+    /// Timeout of the dials and of the handshakes
+    pub connection_timeout: MassaTime,
+    /// Maximum number of inbound connections on the listeners of the transport
+    pub max_in_connections: usize,
+    /// Number of bytes per second that can be read/write in a connection (should be a 10 multiplier)
+    pub read_write_limit_bytes_per_second: u64,
+}
+
+/// Tunables of each transport
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct TransportsConfig {
+    pub tcp: TransportConfig,
+    pub quic: TransportConfig,
+}
+
+impl TransportsConfig {
+    pub fn get(&self, transport: TransportType) -> &TransportConfig {
+        if transport == TransportType::Quic {
+            &self.quic
+        } else {
+            &self.tcp
+        }
+    }
+
+    /// Maximum number of inbound connections on `listeners`, each transport counted once
+    pub fn max_in_connections<'a>(
+        &self,
+        listeners: impl IntoIterator<Item = &'a TransportType>,
+    ) -> usize {
+        let mut transports: Vec<TransportType> = Vec::new();
+        for transport in listeners {
+            if !transports.contains(transport) {
+                transports.push(*transport);
+            }
+        }
+        transports
+            .into_iter()
+            .map(|transport| self.get(transport).max_in_connections)
+            .sum()
+    }
+}
+
+/// Number of out connections to maintain per peer category.
+/// Starts from the `target_out_connections` of the categories and can be changed at runtime.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutConnectionTargets {
+    /// Target of each category, by name
+    pub categories: HashMap<String, usize>,
+    /// Target for the peers that don't belong to any category
+    pub default: usize,
+}
+
+impl OutConnectionTargets {
+    /// Targets of the configured categories
+    pub fn from_config(config: &ProtocolConfig) -> Self {
+        Self {
+            categories: config
+                .peers_categories
+                .iter()
+                .map(|(name, info)| (name.clone(), info.target_out_connections))
+                .collect(),
+            default: config.default_category_info.target_out_connections,
+        }
+    }
+}
+
+/// How the node keypair file is protected at rest
+#[derive(Deserialize, Clone, Default, PartialEq, Eq)]
+pub enum KeypairProtection {
+    /// Stored in clear, as by the older nodes
+    #[default]
+    None,
+    /// Encrypted with a passphrase
+    Passphrase(String),
+}
+
+impl std::fmt::Debug for KeypairProtection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // never log the passphrase
+        match self {
+            KeypairProtection::None => write!(f, "None"),
+            KeypairProtection::Passphrase(_) => write!(f, "Passphrase(***)"),
+        }
+    }
+}
+
+/// Additional network identity of the node, with its own keypair, listeners and peer categories.
+/// Peers connected to it don't learn the main identity of the node, which lets a validator
+/// expose sacrificial public identities while keeping a private one for its sentries.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct IdentityConfig {
+    /// keypair file of the identity, protected like the main one
+    pub keypair_file: PathBuf,
+    /// listeners of the identity, distinct from the main ones
+    pub listeners: HashMap<SocketAddr, TransportType>,
+    /// limits of the peer categories on the listeners of the identity
+    pub peers_categories: HashMap<String, PeerCategoryInfo>,
+    /// limits of the peers that don't belong to any category
+    pub default_category_info: PeerCategoryInfo,
+}
+
 /// Dynamic protocol configuration mix in static settings and constants configurations.
 #[derive(Debug, Deserialize, Clone)]
 pub struct ProtocolConfig {
     /// self keypair
     pub keypair_file: PathBuf,
+    /// protection of the keypair file at rest
+    #[serde(default)]
+    pub keypair_protection: KeypairProtection,
+    /// additional identities, each listening with its own keypair
+    #[serde(default)]
+    pub identities: Vec<IdentityConfig>,
     /// listeners from where we can receive messages
     pub listeners: HashMap<SocketAddr, TransportType>,
     /// initial peers path
     pub initial_peers: PathBuf,
+    /// path of the out connection targets set at runtime, they override the configured ones
+    pub out_connection_targets_file: PathBuf,
+    /// path of the tags and notes attached to the peers by the operator
+    pub peer_annotations_file: PathBuf,
+    /// file where the received messages are captured to be replayed, none to disable the capture
+    #[serde(default)]
+    pub capture_file: Option<PathBuf>,
+    /// file where the recently relayed ids are saved so that they aren't relayed again after a restart, none to keep them in memory only
+    #[serde(default)]
+    pub relayed_ids_file: Option<PathBuf>,
+    /// file where the bans and the trusted peers are journaled as they change, replayed at startup so that they survive an unclean shutdown, none to disable
+    #[serde(default)]
+    pub peer_db_journal_file: Option<PathBuf>,
+    /// max number of known peers kept in the same address bucket (/16 in IPv4, /32 in IPv6)
+    pub max_peers_per_address_bucket: usize,
+    /// number of bootstrap offenses (blacklisted or rate limited) of an IP within `bootstrap_offense_window` after which the IP is banned (0 to disable)
+    pub bootstrap_offense_ban_threshold: u64,
+    /// window in which the bootstrap offenses of an IP are counted
+    pub bootstrap_offense_window: MassaTime,
+    /// duration of the ban of an IP after too many bootstrap offenses
+    pub bootstrap_ip_ban_duration: MassaTime,
+    /// number of failed handshakes of an IP within `handshake_failure_window` after which the IP is temporarily banned (0 to disable)
+    pub handshake_failure_ban_threshold: u64,
+    /// sliding window in which the failed handshakes of an IP are counted
+    pub handshake_failure_window: MassaTime,
+    /// duration of the first ban of an IP after too many failed handshakes, doubled at each new ban
+    pub handshake_failure_ban_duration: MassaTime,
+    /// maximum duration of a ban after too many failed handshakes
+    pub handshake_failure_max_ban_duration: MassaTime,
+    /// time after which a connection whose handshake isn't over is closed (0 to disable)
+    pub half_open_connection_deadline: MassaTime,
+    /// max number of connections of an IP whose handshake is in progress (0 for no limit)
+    pub max_half_open_connections_per_ip: usize,
+    /// number of connected peers asked to dial back each listener in a reachability self-test
+    pub dial_back_peers: usize,
+    /// time after which the peers that didn't answer a reachability self-test are reported as such
+    pub dial_back_timeout: MassaTime,
+    /// size of the buckets to which the hellos and the peer management messages are padded
+    /// with random bytes, so that their size reveals less (0 to disable)
+    pub traffic_padding_bucket_size: u64,
+    /// maximum percentage of the peers tested and of the peers gossiped that we only learned
+    /// about through inbound connections (100 for no limit)
+    pub max_inbound_gossip_percent: u64,
+    /// maximum number of tested addresses that never passed a handshake (0 for no limit)
+    pub max_new_addresses: usize,
+    /// maximum number of tested addresses that passed a handshake (0 for no limit)
+    pub max_tried_addresses: usize,
+    /// failed tests after which an address is dropped from the new table, or moved back to it
+    /// from the tried table (0 to keep them)
+    pub max_address_failures: u32,
+    /// time before and after each of our block and endorsement productions during which the
+    /// lists of peers and the operation announcements are held (0 to disable)
+    pub gossip_quiet_margin: MassaTime,
     /// after `ask_block_timeout` milliseconds we try to ask a block to another node
     pub ask_block_timeout: MassaTime,
     /// Max known blocks we keep in block_handler
@@ -36,18 +200,38 @@ pub struct ProtocolConfig {
     pub max_known_blocks_size: usize,
     /// max known blocks of foreign nodes we keep in memory (by node)
     pub max_node_known_blocks_size: usize,
+    /// max known blocks of foreign nodes we keep in memory (all nodes included)
+    pub max_total_node_known_blocks_size: usize,
+    /// max headers with unknown parents or early slots kept until they can be processed (0 to disable)
+    pub max_early_headers: usize,
+    /// max time between now and the slot of a header kept until its slot
+    pub early_header_max_advance: MassaTime,
+    /// max time a header waits for its parents before it is processed anyway
+    pub early_header_expiry: MassaTime,
     /// max wanted blocks per node kept in memory
     pub max_node_wanted_blocks_size: usize,
     /// max known operations current node kept in memory
     pub max_known_ops_size: usize,
     /// max known operations of foreign nodes we keep in memory (by node)
     pub max_node_known_ops_size: usize,
+    /// max known operations of foreign nodes we keep in memory (all nodes included)
+    pub max_total_node_known_ops_size: usize,
     /// max known endorsements by our node that we kept in memory
     pub max_known_endorsements_size: usize,
     /// max known endorsements of foreign nodes we keep in memory (by node)
     pub max_node_known_endorsements_size: usize,
+    /// max known endorsements of foreign nodes we keep in memory (all nodes included)
+    pub max_total_node_known_endorsements_size: usize,
     /// we ask for the same block `max_simultaneous_ask_blocks_per_node` times at the same time
     pub max_simultaneous_ask_blocks_per_node: usize,
+    /// max number of peers the missing operations of a block are asked to in parallel
+    pub max_block_body_retrieval_peers: usize,
+    /// relay the headers of new blocks as soon as they are checked, before their integration
+    pub relay_headers_before_integration: bool,
+    /// max number of slots of a range of blocks asked by a peer
+    pub max_blocks_slot_range: u64,
+    /// min time between two ranges of blocks served to the same peer
+    pub blocks_slot_range_min_interval: MassaTime,
     /// Max wait time for sending a Network or Node event.
     pub max_send_wait: MassaTime,
     /// Maximum number of batches in the memory buffer.
@@ -56,12 +240,82 @@ pub struct ProtocolConfig {
     /// Maximum number of operations in the announcement buffer.
     /// Immediately announce if overflow.
     pub operation_announcement_buffer_capacity: usize,
+    /// Number of operations received from a peer before it can be quarantined (0 to disable)
+    pub operation_quarantine_min_samples: u64,
+    /// Minimum percentage of useful operations of a peer, below it the peer is quarantined
+    pub operation_quarantine_min_useful_percent: u64,
+    /// Number of random peers to which the new operations are sent in full, the others only get their ids
+    pub operation_eager_fanout: usize,
+    /// Number of random peers to which the new endorsements are sent in full, the others only get their ids
+    pub endorsement_eager_fanout: usize,
     /// Start processing batches in the buffer each `operation_batch_proc_period` in millisecond
     pub operation_batch_proc_period: MassaTime,
     /// Maximum number of asked operations in the memory buffer.
     pub asked_operations_buffer_capacity: usize,
     /// All operations asked are prune each `operation_asked_pruning_period` millisecond
     pub asked_operations_pruning_period: MassaTime,
+    /// Maximum number of peers an operation is asked to before we stop re-asking it when asks time out
+    pub max_operation_ask_attempts: usize,
+    /// Number of requests in a row a peer can leave unanswered before being banned (0 for no limit)
+    pub max_unanswered_requests: u64,
+    /// Maximum number of messages queued for a peer whose connection is congested
+    pub max_queued_messages_per_peer: usize,
+    /// Interval at which the messages queued for congested peers are sent
+    pub send_queues_flush_interval: MassaTime,
+    /// Maximum size in bytes of a block message received from a peer
+    pub max_size_block_message: u64,
+    /// Maximum size in bytes of an endorsement message received from a peer
+    pub max_size_endorsement_message: u64,
+    /// Maximum size in bytes of an operation message received from a peer
+    pub max_size_operation_message: u64,
+    /// Maximum size in bytes of a peer management message received from a peer
+    pub max_size_peer_management_message: u64,
+    /// Number of oversized messages after which a peer is banned (0 for no ban)
+    pub max_message_size_violations: u64,
+    /// Number of threads verifying the signatures of the operations and endorsements received (0 to verify them in the handler threads)
+    pub sig_verification_thread_count: usize,
+    /// Maximum number of batches of signatures of a peer waiting to be verified
+    pub sig_verification_max_pending_per_peer: usize,
+    /// Interval at which the digest of our operations is sent to a random peer to recover the operations missed by gossip (0 to disable)
+    pub operation_reconciliation_interval: MassaTime,
+    /// Number of buckets of the operations digests
+    pub operation_reconciliation_buckets: u32,
+    /// Maximum number of operations summarized in a digest, the most recent first
+    pub operation_reconciliation_max_ids: usize,
+    /// Maximum number of peers whose offenses are kept in the misbehavior ledger
+    pub misbehavior_max_peers: usize,
+    /// Maximum number of offenses kept for each peer in the misbehavior ledger
+    pub misbehavior_max_records_per_peer: usize,
+    /// Number of offenses within `misbehavior_ban_window` after which a peer is banned (0 for no ban)
+    pub max_misbehaviors_before_ban: usize,
+    /// Period over which the offenses of a peer are counted
+    pub misbehavior_ban_window: MassaTime,
+    /// Interval between two keep-alive pings sent to a connected peer
+    pub keep_alive_interval: MassaTime,
+    /// Number of unanswered keep-alive pings after which a connection is closed (0 to disable)
+    pub keep_alive_max_missed: u64,
+    /// Interval between two statuses (final block and head of the blockclique) sent to the connected peers (0 to disable)
+    pub peer_status_interval: MassaTime,
+    /// Number of periods behind our head after which a peer is considered stuck
+    pub peer_status_max_lag: u64,
+    /// Interval between two lists of peers sent to the connected peers while we miss out connections or know few peers
+    pub peer_gossip_interval: MassaTime,
+    /// Interval between two lists of peers sent once the out connections reach their targets and enough peers are known
+    pub peer_gossip_stable_interval: MassaTime,
+    /// Number of known peers below which the lists of peers are sent at `peer_gossip_interval`
+    pub peer_gossip_min_known_peers: usize,
+    /// Number of connection events kept in the audit log (0 to disable it)
+    pub connection_audit_log_size: usize,
+    /// Maximum number of recently relayed ids kept for each kind of item (block, operation, endorsement)
+    pub relayed_ids_cache_size: usize,
+    /// Time during which a relayed item isn't relayed again (0 to disable)
+    pub relayed_ids_retention: MassaTime,
+    /// Interval between two saves of the relayed ids to `relayed_ids_file`
+    pub relayed_ids_save_interval: MassaTime,
+    /// Whether the protocol events are published for the API subscribers
+    pub broadcast_enabled: bool,
+    /// Number of protocol events kept for the slowest API subscriber
+    pub broadcast_protocol_events_channel_capacity: usize,
     /// Interval at which operations are announced in batches.
     pub operation_announcement_interval: MassaTime,
     /// Maximum time we keep an operation in the storage
@@ -142,12 +396,24 @@ pub struct ProtocolConfig {
     pub last_start_period: u64,
     /// try connection timer
     pub try_connection_timer: MassaTime,
-    /// Max in connections
+    /// Max in connections, for all the transports
     pub max_in_connections: usize,
-    /// Timeout connection
-    pub timeout_connection: MassaTime,
-    /// Number of bytes per second that can be read/write in a connection (should be a 10 multiplier)
-    pub read_write_limit_bytes_per_second: u128,
+    /// Number of in connections kept for the reserved peers: the other peers can only use the
+    /// first `max_in_connections - reserved_in_slots` ones
+    #[serde(default)]
+    pub reserved_in_slots: usize,
+    /// Peers allowed to use the reserved in slots, such as the partner nodes of a validator.
+    /// Filled from the node ids of the node settings.
+    #[serde(skip)]
+    pub reserved_peers: HashSet<PeerId>,
+    /// Timeouts, limits and rate limits of each transport
+    pub transports: TransportsConfig,
+    /// Maximum number of outbound dials in progress, for all the origins
+    pub max_concurrent_dials: usize,
+    /// Minimum time between two dials of the same IP
+    pub dial_cooldown: MassaTime,
+    /// Maximum random delay added before a dial to spread the bursts
+    pub dial_jitter: MassaTime,
     /// Optional routable ip
     pub routable_ip: Option<IpAddr>,
     /// debug prints
@@ -158,4 +424,77 @@ pub struct ProtocolConfig {
     pub default_category_info: PeerCategoryInfo,
     /// Version
     pub version: Version,
+    /// Identifier of the network, peers of other networks are disconnected
+    pub chain_id: u64,
+}
+
+impl ProtocolConfig {
+    /// The running config updated with the fields of `new` that can change without a restart:
+    /// gossip intervals, peer targets, rate limits and max message sizes. The other fields of
+    /// `new` are ignored, except the identity and the listeners of the node, which can't change.
+    pub fn reloaded(&self, new: &ProtocolConfig) -> Result<ProtocolConfig, ProtocolError> {
+        let mut unchangeable = Vec::new();
+        if new.keypair_file != self.keypair_file {
+            unchangeable.push("keypair_file");
+        }
+        if new.keypair_protection != self.keypair_protection {
+            unchangeable.push("keypair_protection");
+        }
+        if new.identities != self.identities {
+            unchangeable.push("identities");
+        }
+        if new.listeners != self.listeners {
+            unchangeable.push("listeners");
+        }
+        if new.chain_id != self.chain_id {
+            unchangeable.push("chain_id");
+        }
+        if !unchangeable.is_empty() {
+            return Err(ProtocolError::ConfigReloadError(format!(
+                "{} can't change without a restart",
+                unchangeable.join(", ")
+            )));
+        }
+        let mut config = self.clone();
+        // gossip intervals
+        config.operation_announcement_interval = new.operation_announcement_interval;
+        config.operation_announcement_buffer_capacity = new.operation_announcement_buffer_capacity;
+        config.operation_quarantine_min_samples = new.operation_quarantine_min_samples;
+        config.operation_quarantine_min_useful_percent =
+            new.operation_quarantine_min_useful_percent;
+        config.operation_eager_fanout = new.operation_eager_fanout;
+        config.operation_batch_proc_period = new.operation_batch_proc_period;
+        config.operation_batch_buffer_capacity = new.operation_batch_buffer_capacity;
+        config.asked_operations_pruning_period = new.asked_operations_pruning_period;
+        config.operation_reconciliation_interval = new.operation_reconciliation_interval;
+        config.ask_block_timeout = new.ask_block_timeout;
+        config.early_header_max_advance = new.early_header_max_advance;
+        config.early_header_expiry = new.early_header_expiry;
+        config.send_queues_flush_interval = new.send_queues_flush_interval;
+        config.try_connection_timer = new.try_connection_timer;
+        // peer targets
+        for (name, info) in config.peers_categories.iter_mut() {
+            if let Some(new_info) = new.peers_categories.get(name) {
+                info.target_out_connections = new_info.target_out_connections;
+            }
+        }
+        config.default_category_info.target_out_connections =
+            new.default_category_info.target_out_connections;
+        // rate limits
+        config.max_operations_per_message = new.max_operations_per_message;
+        config.max_simultaneous_ask_blocks_per_node = new.max_simultaneous_ask_blocks_per_node;
+        config.max_block_body_retrieval_peers = new.max_block_body_retrieval_peers;
+        config.max_message_size_violations = new.max_message_size_violations;
+        // the inbound limits of the transports only apply to the listeners started after the
+        // reload, the listeners are started once
+        config.transports = new.transports;
+        // announcement, signed again with the new IP
+        config.routable_ip = new.routable_ip;
+        // max sizes
+        config.max_size_block_message = new.max_size_block_message;
+        config.max_size_endorsement_message = new.max_size_endorsement_message;
+        config.max_size_operation_message = new.max_size_operation_message;
+        config.max_size_peer_management_message = new.max_size_peer_management_message;
+        Ok(config)
+    }
 }
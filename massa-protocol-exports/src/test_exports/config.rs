@@ -1,6 +1,9 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::{settings::PeerCategoryInfo, ProtocolConfig};
+use crate::{
+    settings::{KeypairProtection, PeerCategoryInfo, TransportConfig, TransportsConfig},
+    ProtocolConfig,
+};
 use massa_models::config::ENDORSEMENT_COUNT;
 use massa_time::MassaTime;
 use tempfile::NamedTempFile;
@@ -12,22 +15,69 @@ impl Default for ProtocolConfig {
                 .expect("cannot create temp file")
                 .path()
                 .to_path_buf(),
+            keypair_protection: KeypairProtection::None,
+            identities: Vec::new(),
             ask_block_timeout: 500.into(),
             max_known_blocks_saved_size: 300,
             max_known_blocks_size: 100,
             max_node_known_blocks_size: 100,
+            max_total_node_known_blocks_size: 10000,
+            max_early_headers: 0,
+            early_header_max_advance: MassaTime::from_millis(1000),
+            early_header_expiry: MassaTime::from_millis(10000),
             max_node_wanted_blocks_size: 100,
             max_simultaneous_ask_blocks_per_node: 10,
+            max_block_body_retrieval_peers: 1,
+            relay_headers_before_integration: false,
+            max_blocks_slot_range: 64,
+            blocks_slot_range_min_interval: MassaTime::from_millis(1000),
             max_send_wait: MassaTime::from_millis(100),
             max_known_ops_size: 1000,
             max_node_known_ops_size: 1000,
+            max_total_node_known_ops_size: 100000,
             max_known_endorsements_size: 1000,
             max_node_known_endorsements_size: 1000,
+            max_total_node_known_endorsements_size: 100000,
             operation_batch_buffer_capacity: 1000,
             operation_announcement_buffer_capacity: 1000,
+            operation_quarantine_min_samples: 0,
+            operation_quarantine_min_useful_percent: 10,
+            operation_eager_fanout: 0,
+            endorsement_eager_fanout: 1000,
             max_operation_storage_time: MassaTime::from_millis(60000),
             operation_batch_proc_period: 200.into(),
             asked_operations_buffer_capacity: 10000,
+            max_operation_ask_attempts: 3,
+            max_unanswered_requests: 0,
+            max_queued_messages_per_peer: 1024,
+            send_queues_flush_interval: MassaTime::from_millis(100),
+            max_size_block_message: 52428800,
+            max_size_endorsement_message: 1048576,
+            max_size_operation_message: 52428800,
+            max_size_peer_management_message: 1048576,
+            max_message_size_violations: 0,
+            sig_verification_thread_count: 0,
+            sig_verification_max_pending_per_peer: 64,
+            operation_reconciliation_interval: 0.into(),
+            operation_reconciliation_buckets: 128,
+            operation_reconciliation_max_ids: 20000,
+            misbehavior_max_peers: 1000,
+            misbehavior_max_records_per_peer: 20,
+            max_misbehaviors_before_ban: 0,
+            misbehavior_ban_window: MassaTime::from_millis(3600000),
+            keep_alive_interval: MassaTime::from_millis(1000),
+            keep_alive_max_missed: 0,
+            peer_status_interval: MassaTime::from_millis(0),
+            peer_status_max_lag: 10,
+            peer_gossip_interval: MassaTime::from_millis(10000),
+            peer_gossip_stable_interval: MassaTime::from_millis(10000),
+            peer_gossip_min_known_peers: 0,
+            connection_audit_log_size: 1000,
+            relayed_ids_cache_size: 10000,
+            relayed_ids_retention: MassaTime::from_millis(0),
+            relayed_ids_save_interval: MassaTime::from_millis(10000),
+            broadcast_enabled: false,
+            broadcast_protocol_events_channel_capacity: 128,
             asked_operations_pruning_period: 500.into(),
             operation_announcement_interval: 150.into(),
             max_operations_per_message: 1024,
@@ -44,6 +94,35 @@ impl Default for ProtocolConfig {
                 .expect("cannot create temp file")
                 .path()
                 .to_path_buf(),
+            out_connection_targets_file: NamedTempFile::new()
+                .expect("cannot create temp file")
+                .path()
+                .to_path_buf(),
+            peer_annotations_file: NamedTempFile::new()
+                .expect("cannot create temp file")
+                .path()
+                .to_path_buf(),
+            capture_file: None,
+            relayed_ids_file: None,
+            peer_db_journal_file: None,
+            max_peers_per_address_bucket: 32,
+            bootstrap_offense_ban_threshold: 5,
+            bootstrap_offense_window: MassaTime::from_millis(600000),
+            bootstrap_ip_ban_duration: MassaTime::from_millis(3600000),
+            handshake_failure_ban_threshold: 0,
+            handshake_failure_window: MassaTime::from_millis(60000),
+            handshake_failure_ban_duration: MassaTime::from_millis(60000),
+            handshake_failure_max_ban_duration: MassaTime::from_millis(3600000),
+            half_open_connection_deadline: MassaTime::from_millis(10000),
+            max_half_open_connections_per_ip: 0,
+            dial_back_peers: 3,
+            dial_back_timeout: MassaTime::from_millis(10000),
+            traffic_padding_bucket_size: 0,
+            max_inbound_gossip_percent: 100,
+            max_new_addresses: 10000,
+            max_tried_addresses: 2000,
+            max_address_failures: 3,
+            gossip_quiet_margin: MassaTime::from_millis(0),
             listeners: HashMap::default(),
             thread_tester_count: 2,
             max_size_channel_commands_connectivity: 1000,
@@ -72,11 +151,26 @@ impl Default for ProtocolConfig {
             max_size_listeners_per_peer: 100,
             max_size_peers_announcement: 100,
             last_start_period: 0,
-            read_write_limit_bytes_per_second: 1024 * 1000,
-            timeout_connection: MassaTime::from_millis(1000),
+            transports: TransportsConfig {
+                tcp: TransportConfig {
+                    connection_timeout: MassaTime::from_millis(1000),
+                    max_in_connections: 10,
+                    read_write_limit_bytes_per_second: 1024 * 1000,
+                },
+                quic: TransportConfig {
+                    connection_timeout: MassaTime::from_millis(1000),
+                    max_in_connections: 10,
+                    read_write_limit_bytes_per_second: 1024 * 1000,
+                },
+            },
+            max_concurrent_dials: 1000,
+            dial_cooldown: MassaTime::from_millis(0),
+            dial_jitter: MassaTime::from_millis(0),
             try_connection_timer: MassaTime::from_millis(5000),
             routable_ip: None,
             max_in_connections: 10,
+            reserved_in_slots: 0,
+            reserved_peers: HashSet::new(),
             debug: true,
             peers_categories: HashMap::default(),
             default_category_info: PeerCategoryInfo {
@@ -86,6 +180,7 @@ impl Default for ProtocolConfig {
                 max_in_connections_per_ip: 0,
             },
             version: "TEST.22.2".parse().unwrap(),
+            chain_id: 77658377,
         }
     }
 }
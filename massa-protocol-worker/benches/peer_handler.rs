@@ -0,0 +1,68 @@
+//! Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Load of the peer handler with thousands of synthetic peers: handshake throughput, latency
+//! of the ticks of the peer handler loop, floods of peer lists and waits on the peer DB lock.
+//!
+//! Run with `cargo bench -p massa_protocol_worker --features benchmarking`. The number of
+//! peers can be raised for a soak run with the `PEER_HANDLER_BENCH_PEERS` variable.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use massa_protocol_worker::bench::SyntheticLoad;
+
+/// Default number of synthetic peers
+const PEER_COUNT: usize = 5_000;
+
+/// Number of peer lists of a flood
+const FLOOD_LISTS: usize = 1_000;
+
+fn peer_count() -> usize {
+    std::env::var("PEER_HANDLER_BENCH_PEERS")
+        .ok()
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(PEER_COUNT)
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let load = SyntheticLoad::new(peer_count());
+
+    let mut group = c.benchmark_group("peer handler");
+    group.throughput(Throughput::Elements(1));
+    let mut index = 0;
+    group.bench_function("handshake", |b| {
+        b.iter(|| {
+            index += 1;
+            black_box(load.handshake(index))
+        })
+    });
+    group.bench_function("tick", |b| b.iter(|| black_box(load.tick())));
+
+    let lists = load.peer_lists(FLOOD_LISTS);
+    group.throughput(Throughput::Elements(FLOOD_LISTS as u64));
+    group.bench_function("peer lists flood", |b| {
+        b.iter(|| black_box(load.handle_peer_lists(&lists)))
+    });
+
+    group.throughput(Throughput::Elements(load.peer_count() as u64));
+    group.sample_size(10);
+    group.bench_function("peer db contention", |b| {
+        b.iter(|| black_box(load.contend(4, 4)))
+    });
+    group.finish();
+
+    // the waits are only known from the counters of the lock
+    let stats = load.contend(4, 4);
+    println!(
+        "peer db lock: {} writes, {} contended, {} us waited (max {} us); {} reads, {} contended, {} us waited; {} snapshot rebuilds",
+        stats.writes,
+        stats.contended_writes,
+        stats.write_wait_micros,
+        stats.max_write_wait_micros,
+        stats.reads,
+        stats.contended_reads,
+        stats.read_wait_micros,
+        stats.snapshot_rebuilds
+    );
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,148 @@
+//! Bounded log of the last connection events: handshakes, disconnections, bans and fallbacks.
+//!
+//! It answers "what happened with this peer at 03:12" without digging through the logs.
+//! The oldest events are dropped once the log is full. The events are also published on the
+//! protocol event bus for the API subscribers, and the events of the peers tagged by the operator
+//! with `LOGGED_PEER_TAG` are always logged.
+
+use std::{
+    collections::{HashSet, VecDeque},
+    net::SocketAddr,
+    sync::Arc,
+};
+
+use massa_protocol_exports::{
+    ConnectionEvent, ConnectionEventKind, ProtocolEvent, ProtocolEventBus,
+};
+use massa_time::MassaTime;
+use parking_lot::{Mutex, RwLock};
+use peernet::peer_id::PeerId;
+use tracing::info;
+
+use crate::clock::SharedClock;
+
+pub struct ConnectionAuditLog {
+    events: Mutex<VecDeque<ConnectionEvent>>,
+    /// maximum number of events kept (0 to disable the log)
+    capacity: usize,
+    clock: SharedClock,
+    event_bus: ProtocolEventBus,
+    /// peers whose events are always logged
+    logged_peers: RwLock<HashSet<PeerId>>,
+}
+
+pub type SharedConnectionAuditLog = Arc<ConnectionAuditLog>;
+
+impl ConnectionAuditLog {
+    pub fn shared(
+        capacity: usize,
+        clock: SharedClock,
+        event_bus: ProtocolEventBus,
+    ) -> SharedConnectionAuditLog {
+        Arc::new(ConnectionAuditLog {
+            events: Mutex::new(VecDeque::with_capacity(capacity.min(1024))),
+            capacity,
+            clock,
+            event_bus,
+            logged_peers: RwLock::new(HashSet::new()),
+        })
+    }
+
+    /// Replace the peers whose events are always logged
+    pub fn set_logged_peers(&self, peer_ids: HashSet<PeerId>) {
+        *self.logged_peers.write() = peer_ids;
+    }
+
+    pub fn record(
+        &self,
+        kind: ConnectionEventKind,
+        peer_id: Option<&PeerId>,
+        address: Option<SocketAddr>,
+        reason: Option<String>,
+    ) {
+        if let Some(peer_id) = peer_id && self.logged_peers.read().contains(peer_id) {
+            info!(
+                "Connection event of logged peer {}: {:?} (address: {:?}, reason: {:?})",
+                peer_id, kind, address, reason
+            );
+        }
+        if self.capacity == 0 && !self.event_bus.is_listened() {
+            return;
+        }
+        let event = ConnectionEvent {
+            timestamp: self.clock.now(),
+            kind,
+            peer_id: peer_id.cloned(),
+            address,
+            reason,
+        };
+        if self.event_bus.is_listened() {
+            self.event_bus
+                .publish(ProtocolEvent::Connection(event.clone()));
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        let mut events = self.events.lock();
+        while events.len() >= self.capacity {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// Events recorded since `since` (all the kept ones if `None`) concerning `peer_id` (any
+    /// peer if `None`), oldest first
+    pub fn query(
+        &self,
+        since: Option<MassaTime>,
+        peer_id: Option<&PeerId>,
+    ) -> Vec<ConnectionEvent> {
+        self.events
+            .lock()
+            .iter()
+            .filter(|event| since.map_or(true, |since| event.timestamp >= since))
+            .filter(|event| peer_id.map_or(true, |peer_id| event.peer_id.as_ref() == Some(peer_id)))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use massa_protocol_exports::{ConnectionEventKind, ProtocolEventBus};
+    use massa_time::MassaTime;
+    use peernet::{peer_id::PeerId, types::KeyPair};
+
+    use super::ConnectionAuditLog;
+    use crate::clock::ManualClock;
+
+    #[test]
+    fn test_audit_log_keeps_the_last_events() {
+        let clock = ManualClock::new(MassaTime::from_millis(0));
+        let log = ConnectionAuditLog::shared(3, clock.clone(), ProtocolEventBus::new(1, false));
+        let peer_a = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        let peer_b = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        for peer_id in [&peer_a, &peer_b, &peer_a, &peer_b] {
+            clock.advance(Duration::from_secs(1));
+            log.record(
+                ConnectionEventKind::HandshakeStarted,
+                Some(peer_id),
+                None,
+                None,
+            );
+        }
+
+        // the first event was dropped
+        let events = log.query(None, None);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].timestamp, MassaTime::from_millis(2000));
+        assert_eq!(events[0].peer_id, Some(peer_b.clone()));
+
+        assert_eq!(log.query(None, Some(&peer_a)).len(), 1);
+        let recent = log.query(Some(MassaTime::from_millis(3000)), Some(&peer_b));
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].timestamp, MassaTime::from_millis(4000));
+    }
+}
@@ -0,0 +1,178 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use crossbeam::channel::{Receiver, SendError, Sender, TrySendError};
+use massa_models::stats::{ChannelStats, HandlerStats};
+use massa_time::MassaTime;
+use tracing::debug;
+
+/// Sending side of a bounded channel from the network to a protocol handler, with an explicit
+/// policy when the channel is full:
+/// * blocking: nothing is dropped, the network thread waits for room in the channel
+/// * dropping the oldest message: used for gossip, which is worthless once it is stale
+#[derive(Clone)]
+pub struct SheddingSender<T> {
+    name: &'static str,
+    sender: Sender<T>,
+    /// clone of the receiver of the handler, to drop the oldest messages.
+    /// `None` if the channel is blocking.
+    receiver: Option<Receiver<T>>,
+    dropped: Arc<AtomicU64>,
+    sent: Arc<AtomicU64>,
+}
+
+impl<T> SheddingSender<T> {
+    /// A sender that waits for room in the channel
+    pub fn blocking(name: &'static str, sender: Sender<T>) -> Self {
+        Self {
+            name,
+            sender,
+            receiver: None,
+            dropped: Arc::new(AtomicU64::new(0)),
+            sent: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// A sender that drops the oldest message of the channel to make room for the new one.
+    /// `receiver` must be a clone of the receiver used by the handler.
+    pub fn drop_oldest(name: &'static str, sender: Sender<T>, receiver: Receiver<T>) -> Self {
+        Self {
+            name,
+            sender,
+            receiver: Some(receiver),
+            dropped: Arc::new(AtomicU64::new(0)),
+            sent: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Sends a message, following the policy of the channel if it is full.
+    pub fn send(&self, message: T) -> Result<(), SendError<T>> {
+        let Some(receiver) = &self.receiver else {
+            self.sender.send(message)?;
+            self.sent.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        };
+        let mut message = message;
+        loop {
+            match self.sender.try_send(message) {
+                Ok(()) => {
+                    self.sent.fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                }
+                Err(TrySendError::Disconnected(message)) => return Err(SendError(message)),
+                Err(TrySendError::Full(rejected)) => {
+                    message = rejected;
+                    if receiver.try_recv().is_ok() {
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+                        debug!("{} channel is full: dropped its oldest message", self.name);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Current occupancy of the channel
+    pub fn get_stats(&self) -> ChannelStats {
+        ChannelStats {
+            name: self.name.to_string(),
+            len: self.sender.len() as u64,
+            capacity: self.sender.capacity().unwrap_or_default() as u64,
+            dropped: self.dropped.load(Ordering::Relaxed),
+            sent: self.sent.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Rates of the messages sent in the channels, between two snapshots of their stats
+pub struct ChannelRates {
+    previous_time: MassaTime,
+    previous_sent: HashMap<String, u64>,
+}
+
+impl ChannelRates {
+    /// Starts measuring the rates at `now`
+    pub fn new(now: MassaTime) -> Self {
+        Self {
+            previous_time: now,
+            previous_sent: HashMap::new(),
+        }
+    }
+
+    /// Rates of the channels since the previous snapshot, or since the start for the first one
+    pub fn snapshot(
+        &mut self,
+        channel_stats: Vec<ChannelStats>,
+        now: MassaTime,
+    ) -> Vec<HandlerStats> {
+        let elapsed_secs = now.saturating_sub(self.previous_time).to_millis() as f64 / 1000.0;
+        self.previous_time = now;
+        channel_stats
+            .into_iter()
+            .map(|channel| {
+                let previous_sent = self
+                    .previous_sent
+                    .insert(channel.name.clone(), channel.sent)
+                    .unwrap_or_default();
+                let messages_per_second = if elapsed_secs > 0.0 {
+                    channel.sent.saturating_sub(previous_sent) as f64 / elapsed_secs
+                } else {
+                    0.0
+                };
+                HandlerStats {
+                    channel,
+                    messages_per_second,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam::channel::bounded;
+
+    #[test]
+    fn test_drop_oldest_when_full() {
+        let (sender, receiver) = bounded(2);
+        let shedding_sender = SheddingSender::drop_oldest("test", sender, receiver.clone());
+        for message in 0..5 {
+            shedding_sender.send(message).unwrap();
+        }
+        let stats = shedding_sender.get_stats();
+        assert_eq!((stats.len, stats.capacity, stats.dropped), (2, 2, 3));
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![3, 4]);
+    }
+
+    #[test]
+    fn test_channel_rates() {
+        let (sender, _receiver) = bounded(100);
+        let shedding_sender = SheddingSender::blocking("test", sender);
+        let mut rates = ChannelRates::new(MassaTime::from_millis(0));
+        for message in 0..20 {
+            shedding_sender.send(message).unwrap();
+        }
+        let stats = rates.snapshot(
+            vec![shedding_sender.get_stats()],
+            MassaTime::from_millis(2_000),
+        );
+        assert_eq!(stats[0].channel.sent, 20);
+        assert_eq!(stats[0].messages_per_second, 10.0);
+
+        // only the messages sent since the previous snapshot count
+        for message in 0..5 {
+            shedding_sender.send(message).unwrap();
+        }
+        let stats = rates.snapshot(
+            vec![shedding_sender.get_stats()],
+            MassaTime::from_millis(3_000),
+        );
+        assert_eq!(stats[0].channel.sent, 25);
+        assert_eq!(stats[0].messages_per_second, 5.0);
+    }
+}
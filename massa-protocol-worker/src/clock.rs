@@ -0,0 +1,132 @@
+//! Time source of the peer handler, replaced in tests to drive the gossip rounds,
+//! the ban expiries and the retries without waiting.
+
+use std::{sync::Arc, time::Duration};
+
+use crossbeam::channel::{tick, Receiver};
+use massa_time::MassaTime;
+
+pub trait Clock: Send + Sync {
+    /// Current time
+    fn now(&self) -> MassaTime;
+
+    /// Channel receiving a message every `interval`, like `crossbeam::channel::tick`
+    fn ticker(&self, interval: Duration) -> Receiver<std::time::Instant>;
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Clock of the system, used outside of the tests
+pub struct SystemClock;
+
+impl SystemClock {
+    pub fn shared() -> SharedClock {
+        Arc::new(SystemClock)
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> MassaTime {
+        MassaTime::now().expect("could not get current time")
+    }
+
+    fn ticker(&self, interval: Duration) -> Receiver<std::time::Instant> {
+        tick(interval)
+    }
+}
+
+/// Clock only moving forward when the test advances it. The tickers fire as the time passes
+/// their deadline.
+#[cfg(test)]
+pub struct ManualClock {
+    now: parking_lot::Mutex<MassaTime>,
+    tickers: parking_lot::Mutex<Vec<ManualTicker>>,
+}
+
+#[cfg(test)]
+struct ManualTicker {
+    interval: MassaTime,
+    next: MassaTime,
+    sender: crossbeam::channel::Sender<std::time::Instant>,
+}
+
+#[cfg(test)]
+impl ManualClock {
+    pub fn new(start: MassaTime) -> Arc<Self> {
+        Arc::new(ManualClock {
+            now: parking_lot::Mutex::new(start),
+            tickers: parking_lot::Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Moves the time forward and fires the tickers whose deadline passed.
+    /// Like `crossbeam::channel::tick`, a ticker keeps at most one pending tick.
+    pub fn advance(&self, duration: Duration) {
+        let now = {
+            let mut now = self.now.lock();
+            *now = now.saturating_add(MassaTime::from_millis(duration.as_millis() as u64));
+            *now
+        };
+        let mut tickers = self.tickers.lock();
+        tickers.retain_mut(|ticker| {
+            let mut fired = false;
+            while ticker.next <= now {
+                ticker.next = ticker.next.saturating_add(ticker.interval);
+                fired = true;
+            }
+            if !fired {
+                return true;
+            }
+            !matches!(
+                ticker.sender.try_send(std::time::Instant::now()),
+                Err(crossbeam::channel::TrySendError::Disconnected(_))
+            )
+        });
+    }
+}
+
+#[cfg(test)]
+impl Clock for ManualClock {
+    fn now(&self) -> MassaTime {
+        *self.now.lock()
+    }
+
+    fn ticker(&self, interval: Duration) -> Receiver<std::time::Instant> {
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        let interval = MassaTime::from_millis((interval.as_millis() as u64).max(1));
+        self.tickers.lock().push(ManualTicker {
+            interval,
+            next: self.now().saturating_add(interval),
+            sender,
+        });
+        receiver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use massa_time::MassaTime;
+
+    use super::{Clock, ManualClock};
+
+    #[test]
+    fn test_manual_clock_fires_tickers_when_advanced() {
+        let clock = ManualClock::new(MassaTime::from_millis(1000));
+        let ticker = clock.ticker(Duration::from_secs(10));
+
+        clock.advance(Duration::from_secs(9));
+        assert_eq!(clock.now(), MassaTime::from_millis(10000));
+        assert!(ticker.try_recv().is_err());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(ticker.try_recv().is_ok());
+        assert!(ticker.try_recv().is_err());
+
+        // the missed ticks are merged into one
+        clock.advance(Duration::from_secs(30));
+        assert!(ticker.try_recv().is_ok());
+        assert!(ticker.try_recv().is_err());
+    }
+}
@@ -1,23 +1,40 @@
 use crossbeam::{
-    channel::{Receiver, Sender},
+    channel::{never, tick, Receiver, Sender},
     select,
 };
 use massa_consensus_exports::ConsensusController;
-use massa_models::stats::NetworkStats;
+use massa_models::stats::{NetworkStats, ProtocolStats};
 use massa_pool_exports::PoolController;
-use massa_protocol_exports::{PeerCategoryInfo, ProtocolConfig, ProtocolError};
+use massa_protocol_exports::{
+    OutConnectionTargets, PeerCategoryInfo, PeerConnectionInfo, ProtocolConfig, ProtocolError,
+};
 use massa_storage::Storage;
+use massa_time::MassaTime;
 use parking_lot::RwLock;
-use peernet::{peer::PeerConnectionType, transports::OutConnectionConfig};
-use peernet::{peer_id::PeerId, transports::TcpOutConnectionConfig};
+use peernet::peer_id::PeerId;
+use peernet::{peer::PeerConnectionType, transports::TransportType};
 use std::net::SocketAddr;
-use std::{collections::HashMap, net::IpAddr};
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+};
 use std::{num::NonZeroUsize, sync::Arc};
 use std::{thread::JoinHandle, time::Duration};
 use tracing::{info, warn};
 
 use crate::{
-    handlers::peer_handler::models::{InitialPeers, PeerState, SharedPeerDB},
+    audit::SharedConnectionAuditLog,
+    channels::ChannelRates,
+    clock::SharedClock,
+    dial::DialScheduler,
+    handlers::peer_handler::{
+        models::{
+            load_out_connection_targets, AddressBucket, InitialPeers, PeerManagementCmd, PeerState,
+            SharedLocalAnnouncement, SharedPeerDB,
+        },
+        peer_db_lock::BannedPeers,
+    },
+    sig_verifier::SigVerifierPool,
     worker::ProtocolChannels,
 };
 use crate::{handlers::peer_handler::PeerManagementHandler, messages::MessagesHandler};
@@ -27,18 +44,40 @@ use crate::{
         endorsement_handler::{cache::EndorsementCache, EndorsementHandler},
         operation_handler::{cache::OperationCache, OperationHandler},
         peer_handler::models::PeerMessageTuple,
+        relayed_ids::{RelayedIdsCache, SharedRelayedIdsCache},
     },
-    wrap_network::NetworkController,
+    wrap_network::{ActiveConnectionsTrait, NetworkController},
 };
 
 pub enum ConnectivityCommand {
     Stop,
     GetStats {
-        #[allow(clippy::type_complexity)]
-        responder: Sender<(
-            NetworkStats,
-            HashMap<PeerId, (SocketAddr, PeerConnectionType)>,
-        )>,
+        responder: Sender<(NetworkStats, HashMap<PeerId, PeerConnectionInfo>)>,
+    },
+    /// Snapshot of the state of the protocol, for the monitoring dashboards
+    GetProtocolStats {
+        responder: Sender<(ProtocolStats, HashMap<PeerId, PeerConnectionInfo>)>,
+    },
+    /// Apply the reloadable fields of a new config to the running handlers
+    ReloadConfig {
+        config: Box<ProtocolConfig>,
+        responder: Sender<Result<(), ProtocolError>>,
+    },
+    /// Change the IP announced to the peers, found by a NAT discovery for example
+    SetRoutableIp {
+        routable_ip: Option<IpAddr>,
+        responder: Sender<Result<(), ProtocolError>>,
+    },
+    /// Start listening on a new address and announce it to the peers
+    AddListener {
+        addr: SocketAddr,
+        transport: TransportType,
+        responder: Sender<Result<(), ProtocolError>>,
+    },
+    /// Stop listening on an address and announce the remaining listeners to the peers
+    RemoveListener {
+        addr: SocketAddr,
+        responder: Sender<Result<(), ProtocolError>>,
     },
 }
 
@@ -54,12 +93,15 @@ pub(crate) fn start_connectivity_thread(
     channel_peers: (Sender<PeerMessageTuple>, Receiver<PeerMessageTuple>),
     initial_peers: InitialPeers,
     peer_db: SharedPeerDB,
+    local_announcement: SharedLocalAnnouncement,
+    audit_log: SharedConnectionAuditLog,
+    clock: SharedClock,
     storage: Storage,
     protocol_channels: ProtocolChannels,
     messages_handler: MessagesHandler,
     peer_categories: HashMap<String, (Vec<IpAddr>, PeerCategoryInfo)>,
     _default_category: PeerCategoryInfo,
-    config: ProtocolConfig,
+    mut config: ProtocolConfig,
 ) -> Result<(Sender<ConnectivityCommand>, JoinHandle<()>), ProtocolError> {
     let handle = std::thread::Builder::new()
     .name("protocol-connectivity".to_string())
@@ -69,7 +111,7 @@ pub(crate) fn start_connectivity_thread(
         let sender_blocks_propagation_ext = protocol_channels.block_handler_propagation.0.clone();
         let sender_operations_propagation_ext = protocol_channels.operation_handler_propagation.0.clone();
         move || {
-            for (addr, transport) in &config.listeners {
+            for (addr, transport) in local_announcement.read().listeners().iter() {
                 network_controller
                     .start_listener(*transport, *addr)
                     .unwrap_or_else(|_| panic!(
@@ -84,31 +126,73 @@ pub(crate) fn start_connectivity_thread(
             // Create cache outside of the op handler because it could be used by other handlers
             let total_in_slots = config.peers_categories.values().map(|v| v.max_in_connections_post_handshake).sum::<usize>() + config.default_category_info.max_in_connections_post_handshake;
             let total_out_slots = config.peers_categories.values().map(| v| v.target_out_connections).sum::<usize>() + config.default_category_info.target_out_connections;
+            // Ids relayed by all the handlers, loaded from the previous run to not relay them again
+            let mut relayed_ids = RelayedIdsCache::new(
+                NonZeroUsize::new(config.relayed_ids_cache_size.max(1)).unwrap(),
+                config.relayed_ids_retention,
+                clock.clone(),
+            );
+            if let Some(path) = config.relayed_ids_file.as_ref().filter(|path| path.is_file()) {
+                if let Err(err) = relayed_ids.load(path) {
+                    warn!("could not load the relayed ids from {}: {}", path.display(), err);
+                }
+            }
+            let relayed_ids = Arc::new(RwLock::new(relayed_ids));
             let operation_cache = Arc::new(RwLock::new(OperationCache::new(
                 NonZeroUsize::new(config.max_known_ops_size).unwrap(),
                 NonZeroUsize::new(total_in_slots + total_out_slots).unwrap(),
+                NonZeroUsize::new(config.max_node_known_ops_size).unwrap(),
+                config.max_total_node_known_ops_size,
+                relayed_ids.clone(),
             )));
             let endorsement_cache = Arc::new(RwLock::new(EndorsementCache::new(
                 NonZeroUsize::new(config.max_known_endorsements_size).unwrap(),
                 NonZeroUsize::new(total_in_slots + total_out_slots).unwrap(),
+                NonZeroUsize::new(config.max_node_known_endorsements_size).unwrap(),
+                config.max_total_node_known_endorsements_size,
+                relayed_ids.clone(),
             )));
 
             let block_cache = Arc::new(RwLock::new(BlockCache::new(
                 NonZeroUsize::new(config.max_known_blocks_size).unwrap(),
                 NonZeroUsize::new(total_in_slots + total_out_slots).unwrap(),
+                NonZeroUsize::new(config.max_node_known_blocks_size).unwrap(),
+                config.max_total_node_known_blocks_size,
+                config.max_early_headers,
+                relayed_ids.clone(),
             )));
 
+            // Shared by the handlers so that the signature checks of all peers are served in turn
+            let sig_verifier = SigVerifierPool::new(
+                config.sig_verification_thread_count,
+                config.sig_verification_max_pending_per_peer,
+            );
+
+            let out_connection_targets = Arc::new(RwLock::new(load_out_connection_targets(&config)));
+
+            // counted from the changes of the peer DB instead of scanning it at each stats request
+            let mut banned_peers = BannedPeers::new(&peer_db);
+
+            // Shared by the testers and the out connections so that all the dials are limited together
+            let dial_scheduler = DialScheduler::from_config(&config);
+
             // Start handlers
             let mut peer_management_handler = PeerManagementHandler::new(
                 initial_peers,
                 peer_id,
                 peer_db.clone(),
+                consensus_controller.clone(),
                 channel_peers,
                 protocol_channels.peer_management_handler,
-                messages_handler,
+                messages_handler.clone(),
                 network_controller.get_active_connections(),
-                peer_categories.iter().map(|(key, value)|(key.clone(), (value.0.clone(), value.1.target_out_connections))).collect(),
-                config.default_category_info.target_out_connections,
+                peer_categories.iter().map(|(key, value)|(key.clone(), value.0.clone())).collect(),
+                out_connection_targets.clone(),
+                local_announcement.clone(),
+                audit_log,
+                dial_scheduler.clone(),
+                protocol_channels.production_schedule.clone(),
+                clock.clone(),
                 &config,
             );
 
@@ -124,6 +208,10 @@ pub(crate) fn start_connectivity_thread(
                 sender_operations_propagation_ext.clone(),
                 protocol_channels.operation_handler_propagation.1.clone(),
                 peer_management_handler.sender.command_sender.clone(),
+                sig_verifier.clone(),
+                protocol_channels.event_bus.clone(),
+                protocol_channels.production_schedule.clone(),
+                peer_db.clone(),
             );
             let mut endorsement_handler = EndorsementHandler::new(
                 pool_controller.clone(),
@@ -137,6 +225,8 @@ pub(crate) fn start_connectivity_thread(
                 sender_endorsements_propagation_ext,
                 protocol_channels.endorsement_handler_propagation.1.clone(),
                 peer_management_handler.sender.command_sender.clone(),
+                sig_verifier.clone(),
+                peer_db.clone(),
             );
             let mut block_handler = BlockHandler::new(
                 network_controller.get_active_connections(),
@@ -150,15 +240,31 @@ pub(crate) fn start_connectivity_thread(
                 sender_operations_propagation_ext,
                 peer_management_handler.sender.command_sender.clone(),
                 config.clone(),
-                endorsement_cache,
-                operation_cache,
-                block_cache,
+                endorsement_cache.clone(),
+                operation_cache.clone(),
+                block_cache.clone(),
                 storage.clone_without_refs(),
+                sig_verifier.clone(),
+                protocol_channels.event_bus.clone(),
+                peer_db.clone(),
             );
 
+            let mut send_queues_ticker = tick(config.send_queues_flush_interval.to_duration());
+            let relayed_ids_save_ticker = match config.relayed_ids_file {
+                Some(_) => tick(config.relayed_ids_save_interval.to_duration()),
+                None => never(),
+            };
+            let mut channel_rates = ChannelRates::new(clock.now());
+
             //Try to connect to peers
             loop {
                 select! {
+                        recv(send_queues_ticker) -> _ => {
+                            network_controller.get_active_connections().flush_send_queues();
+                        }
+                        recv(relayed_ids_save_ticker) -> _ => {
+                            save_relayed_ids(&relayed_ids, &config);
+                        }
                         recv(protocol_channels.connectivity_thread.1) -> msg => {
                             match msg {
                                 Ok(ConnectivityCommand::Stop) => {
@@ -171,17 +277,20 @@ pub(crate) fn start_connectivity_thread(
                                     println!("Stopped endorsement handler");
                                     block_handler.stop();
                                     println!("Stopped block handler");
+                                    sig_verifier.stop();
+                                    println!("Stopped signature verification pool");
                                     peer_management_handler.stop();
                                     println!("Stopped peer handler");
+                                    save_relayed_ids(&relayed_ids, &config);
                                     break;
                                 },
                                 Ok(ConnectivityCommand::GetStats { responder }) => {
                                     let active_node_count = network_controller.get_active_connections().get_peer_ids_connected().len() as u64;
                                     let in_connection_count = network_controller.get_active_connections().get_nb_in_connections() as u64;
                                     let out_connection_count = network_controller.get_active_connections().get_nb_out_connections() as u64;
-                                    let (banned_peer_count, known_peer_count) = {
-                                        let peer_db_read = peer_db.read();
-                                        (peer_db_read.get_banned_peer_count(), peer_db_read.peers.len() as u64)
+                                    let (banned_peer_count, known_peer_count, handshake_failure_stats) = {
+                                        let peer_db_read = peer_db.snapshot();
+                                        (banned_peers.count(&peer_db), peer_db_read.peers.len() as u64, peer_db_read.get_handshake_failure_stats(MassaTime::now().unwrap()))
                                     };
                                     let stats = NetworkStats {
                                         active_node_count,
@@ -189,22 +298,108 @@ pub(crate) fn start_connectivity_thread(
                                         out_connection_count,
                                         banned_peer_count,
                                         known_peer_count,
+                                        operation_gossip_stats: operation_cache.read().get_stats(),
+                                        known_blocks_stats: block_cache.read().blocks_known_by_peer.get_stats(),
+                                        known_operations_stats: operation_cache.read().ops_known_by_peer.get_stats(),
+                                        known_endorsements_stats: endorsement_cache.read().endorsements_known_by_peer.get_stats(),
+                                        channel_stats: messages_handler.get_channel_stats(),
+                                        endorsement_dedup_stats: endorsement_cache.read().dedup_window.get_stats(),
+                                        handshake_failure_stats,
+                                        early_header_stats: block_cache.read().early_headers.get_stats(),
                                     };
-                                    let peers: HashMap<PeerId, (SocketAddr, PeerConnectionType)> = network_controller.get_active_connections().get_peers_connected().into_iter().map(|(peer_id, peer)| {
-                                        (peer_id, (peer.0, peer.1))
-                                    }).collect();
+                                    let peers = get_connected_peers(network_controller.get_active_connections().as_ref(), &peer_db);
                                     responder.send((stats, peers)).unwrap_or_else(|_| warn!("Failed to send stats to responder"));
                                 }
+                                Ok(ConnectivityCommand::GetProtocolStats { responder }) => {
+                                    // every figure is read from atomic counters or short-lived snapshots: the handlers are never blocked
+                                    let now = clock.now();
+                                    let active_connections = network_controller.get_active_connections();
+                                    let (banned_peer_count, known_peer_count, handshake_failure_stats) = {
+                                        let peer_db_read = peer_db.snapshot();
+                                        (banned_peers.count(&peer_db), peer_db_read.peers.len() as u64, peer_db_read.get_handshake_failure_stats(now))
+                                    };
+                                    let stats = ProtocolStats {
+                                        timestamp: now,
+                                        in_connection_count: active_connections.get_nb_in_connections() as u64,
+                                        out_connection_count: active_connections.get_nb_out_connections() as u64,
+                                        known_peer_count,
+                                        handler_stats: channel_rates.snapshot(messages_handler.get_channel_stats(), now),
+                                        tester_backlog: peer_management_handler.get_tester_backlog(),
+                                        banned_peer_count,
+                                        banned_ip_count: handshake_failure_stats.banned_ip_count,
+                                        ip_bans: handshake_failure_stats.ip_bans,
+                                    };
+                                    let peers = get_connected_peers(active_connections.as_ref(), &peer_db);
+                                    responder.send((stats, peers)).unwrap_or_else(|_| warn!("Failed to send protocol stats to responder"));
+                                }
+                                Ok(ConnectivityCommand::ReloadConfig { config: new_config, responder }) => {
+                                    let result = config.reloaded(&new_config).map(|new_config| {
+                                        if OutConnectionTargets::from_config(&new_config) != OutConnectionTargets::from_config(&config) {
+                                            if let Err(err) = peer_management_handler.sender.command_sender.send(PeerManagementCmd::SetTargets(OutConnectionTargets::from_config(&new_config))) {
+                                                warn!("Failed to send reloaded targets to the peer handler: {}", err);
+                                            }
+                                        }
+                                        operation_handler.update_config(&new_config);
+                                        block_handler.update_config(&new_config);
+                                        messages_handler.size_validator.update_config(&new_config);
+                                        update_routable_ip(&local_announcement, new_config.routable_ip, &peer_management_handler.sender.command_sender);
+                                        send_queues_ticker = tick(new_config.send_queues_flush_interval.to_duration());
+                                        config = new_config;
+                                        info!("Protocol config reloaded");
+                                    });
+                                    responder.send(result).unwrap_or_else(|_| warn!("Failed to send config reload result to responder"));
+                                }
+                                Ok(ConnectivityCommand::SetRoutableIp { routable_ip, responder }) => {
+                                    config.routable_ip = routable_ip;
+                                    update_routable_ip(&local_announcement, routable_ip, &peer_management_handler.sender.command_sender);
+                                    responder.send(Ok(())).unwrap_or_else(|_| warn!("Failed to send set routable ip result to responder"));
+                                }
+                                Ok(ConnectivityCommand::AddListener { addr, transport, responder }) => {
+                                    let nb_listeners = local_announcement.read().listeners().len();
+                                    let result = if local_announcement.read().listeners().contains_key(&addr) {
+                                        Err(ProtocolError::ListenerError(format!("Already listening on {}", addr)))
+                                    } else if nb_listeners as u64 >= config.max_size_listeners_per_peer {
+                                        Err(ProtocolError::ListenerError(format!("Can't announce more than {} listeners", config.max_size_listeners_per_peer)))
+                                    } else {
+                                        network_controller.start_listener(transport, addr).and_then(|_| {
+                                            local_announcement.write().add_listener(addr, transport).map_err(|err| ProtocolError::ListenerError(err.to_string()))?;
+                                            info!("Listener {} of transport {:?} started", addr, transport);
+                                            if let Err(err) = peer_management_handler.sender.command_sender.send(PeerManagementCmd::AnnounceListeners) {
+                                                warn!("Failed to announce the new listener: {}", err);
+                                            }
+                                            Ok(())
+                                        })
+                                    };
+                                    responder.send(result).unwrap_or_else(|_| warn!("Failed to send add listener result to responder"));
+                                }
+                                Ok(ConnectivityCommand::RemoveListener { addr, responder }) => {
+                                    let transport = local_announcement.read().listeners().get(&addr).copied();
+                                    let result = match transport {
+                                        None => Err(ProtocolError::ListenerError(format!("Not listening on {}", addr))),
+                                        Some(transport) => network_controller.stop_listener(transport, addr).and_then(|_| {
+                                            local_announcement.write().remove_listener(&addr).map_err(|err| ProtocolError::ListenerError(err.to_string()))?;
+                                            info!("Listener {} of transport {:?} stopped", addr, transport);
+                                            if let Err(err) = peer_management_handler.sender.command_sender.send(PeerManagementCmd::AnnounceListeners) {
+                                                warn!("Failed to announce the removed listener: {}", err);
+                                            }
+                                            Ok(())
+                                        }),
+                                    };
+                                    responder.send(result).unwrap_or_else(|_| warn!("Failed to send remove listener result to responder"));
+                                }
                                 Err(_) => {
                                     warn!("Channel to connectivity thread is closed. Stopping the protocol");
                                     break;
                                 }
                             }
                         }
-                    default(config.try_connection_timer.to_duration()) => {
+                    default(dial_scheduler.next_wakeup(config.try_connection_timer.to_duration())) => {
                         let peers_connected = network_controller.get_active_connections().get_peers_connected();
-                        let mut slots_per_category: Vec<(String, usize)> = peer_categories.iter().map(|(category, category_infos)| {
-                            (category.clone(), category_infos.1.target_out_connections.saturating_sub(peers_connected.iter().filter(|(_, peer)| {
+                        dial_scheduler.release_connected(peers_connected.values().map(|peer| peer.0.ip()));
+                        // targets can be changed at runtime
+                        let targets = out_connection_targets.read().clone();
+                        let mut slots_per_category: Vec<(String, usize)> = peer_categories.keys().map(|category| {
+                            (category.clone(), targets.categories.get(category).copied().unwrap_or_default().saturating_sub(peers_connected.iter().filter(|(_, peer)| {
                                 if peer.1 == PeerConnectionType::OUT && let Some(peer_category) = &peer.2 {
                                     category == peer_category
                                 } else {
@@ -212,13 +407,21 @@ pub(crate) fn start_connectivity_thread(
                                 }
                             }).count()))
                         }).collect();
-                        let mut slot_default_category = config.default_category_info.target_out_connections.saturating_sub(peers_connected.iter().filter(|(_, peer)| {
+                        let mut slot_default_category = targets.default.saturating_sub(peers_connected.iter().filter(|(_, peer)| {
                             peer.1 == PeerConnectionType::OUT && peer.2.is_none()
                         }).count());
                         let mut addresses_to_connect: Vec<SocketAddr> = Vec::new();
+                        // outside of the categories, only one out connection per address bucket
+                        let mut used_buckets: HashSet<AddressBucket> = peers_connected.values().filter(|peer| {
+                            peer.1 == PeerConnectionType::OUT && peer.2.is_none()
+                        }).map(|peer| AddressBucket::from_ip(peer.0.ip())).collect();
                         {
-                            let peer_db_read = peer_db.read();
-                            for (_, peer_id) in &peer_db_read.index_by_newest {
+                            let peer_db_read = peer_db.snapshot();
+                            let now = MassaTime::now().expect("could not get current time");
+                            // peers whitelisted by our bootstrap server are tried first, newest first within each group
+                            let mut candidates: Vec<&PeerId> = peer_db_read.index_by_newest.iter().map(|(_, peer_id)| peer_id).collect();
+                            candidates.sort_by_cached_key(|peer_id| !peer_db_read.is_peer_preferred(peer_id));
+                            for peer_id in candidates {
                                 if peers_connected.contains_key(peer_id) {
                                     continue;
                                 }
@@ -235,7 +438,7 @@ pub(crate) fn start_connectivity_thread(
                                     //TODO: Adapt for multiple listeners
                                     let (addr, _) = peer_info.last_announce.listeners.iter().next().unwrap();
                                     let canonical_ip = addr.ip().to_canonical();
-                                    if !canonical_ip.is_global()  {
+                                    if !canonical_ip.is_global() || peer_db_read.is_ip_banned(canonical_ip, now) {
                                         continue;
                                     }
                                     // Check if the peer is in a category and we didn't reached out target yet
@@ -253,7 +456,7 @@ pub(crate) fn start_connectivity_thread(
                                                 *category_infos -= 1;
                                             }
                                         }
-                                    } else if slot_default_category > 0 {
+                                    } else if slot_default_category > 0 && (used_buckets.insert(AddressBucket::from_ip(canonical_ip)) || peer_db_read.preferred_ips.contains(&canonical_ip)) {
                                         addresses_to_connect.push(*addr);
                                         slot_default_category -= 1;
                                     }
@@ -267,9 +470,12 @@ pub(crate) fn start_connectivity_thread(
                             }
                         }
                         for addr in addresses_to_connect {
+                            dial_scheduler.enqueue(addr);
+                        }
+                        for addr in dial_scheduler.take_due() {
                             info!("Trying to connect to addr {}", addr);
                             // We only manage TCP for now
-                            if let Err(err) = network_controller.try_connect(addr, config.timeout_connection.to_duration(), &OutConnectionConfig::Tcp(Box::new(TcpOutConnectionConfig::new(config.read_write_limit_bytes_per_second / 10, Duration::from_millis(100))))) {
+                            if let Err(err) = network_controller.try_connect(addr, TransportType::Tcp, config.transports.get(TransportType::Tcp)) {
                                 warn!("Failed to connect to peer {:?}: {:?}", addr, err);
                             }
                         }
@@ -282,3 +488,68 @@ pub(crate) fn start_connectivity_thread(
     // Start controller
     Ok((protocol_channels.connectivity_thread.0, handle))
 }
+
+/// Connections to the peers, with what the peer database recorded about them
+/// Save the relayed ids to the configured file, if any
+fn save_relayed_ids(relayed_ids: &SharedRelayedIdsCache, config: &ProtocolConfig) {
+    if let Some(path) = &config.relayed_ids_file {
+        if let Err(err) = relayed_ids.read().save(path) {
+            warn!(
+                "could not save the relayed ids to {}: {}",
+                path.display(),
+                err
+            );
+        }
+    }
+}
+
+fn get_connected_peers(
+    active_connections: &dyn ActiveConnectionsTrait,
+    peer_db: &SharedPeerDB,
+) -> HashMap<PeerId, PeerConnectionInfo> {
+    let peer_db_read = peer_db.snapshot();
+    active_connections
+        .get_peers_connected()
+        .into_iter()
+        .map(|(peer_id, (address, connection_type, _))| {
+            // the record is only used if it describes this connection and not a previous one
+            let record = peer_db_read
+                .peers
+                .get(&peer_id)
+                .and_then(|info| info.connection.as_ref())
+                .filter(|record| record.address == address);
+            let info = PeerConnectionInfo {
+                address,
+                connection_type,
+                transport: record.and_then(|record| record.transport),
+                connected_since: record.map(|record| record.established_at),
+                last_pong: record.and_then(|record| record.last_pong),
+                traffic: peer_db.traffic().get(&peer_id),
+                annotation: peer_db_read.annotations.get(&peer_id).cloned(),
+            };
+            (peer_id, info)
+        })
+        .collect()
+}
+
+/// Sign a fresh announcement and send it to the peers if the routable IP changed
+fn update_routable_ip(
+    local_announcement: &SharedLocalAnnouncement,
+    routable_ip: Option<IpAddr>,
+    peer_cmd_sender: &Sender<PeerManagementCmd>,
+) {
+    let changed = local_announcement.write().set_routable_ip(routable_ip);
+    match changed {
+        Ok(false) => {}
+        Ok(true) => {
+            info!("Routable IP changed to {:?}, announcing it", routable_ip);
+            if let Err(err) = peer_cmd_sender.send(PeerManagementCmd::AnnounceListeners) {
+                warn!("Failed to announce the new routable IP: {}", err);
+            }
+        }
+        Err(err) => warn!(
+            "Failed to sign the announcement of the routable IP {:?}: {}",
+            routable_ip, err
+        ),
+    }
+}
@@ -1,15 +1,25 @@
-use std::{collections::HashMap, net::SocketAddr, time::Duration};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
 
 use crossbeam::channel::Sender;
 use massa_models::{
     block_header::SecuredHeader,
     block_id::BlockId,
     prehash::{PreHashMap, PreHashSet},
-    stats::NetworkStats,
+    slot::Slot,
+    stats::{NetworkStats, ProtocolStats},
+};
+use massa_protocol_exports::{
+    BanReport, BootstrapClientReport, BootstrapPeers, ConnectionEvent, ListenerReachability,
+    MisbehaviorRecord, OutConnectionTargets, PeerAnnotation, PeerConnectionInfo, PeerTestResult,
+    ProtocolConfig, ProtocolController, ProtocolError,
 };
-use massa_protocol_exports::{BootstrapPeers, ProtocolController, ProtocolError};
 use massa_storage::Storage;
-use peernet::{peer::PeerConnectionType, peer_id::PeerId};
+use massa_time::MassaTime;
+use peernet::{peer_id::PeerId, transports::TransportType};
 
 use crate::{
     connectivity::ConnectivityCommand,
@@ -22,6 +32,7 @@ use crate::{
         operation_handler::commands_propagation::OperationHandlerPropagationCommand,
         peer_handler::models::PeerManagementCmd,
     },
+    production_schedule::ProductionSchedule,
 };
 
 #[derive(Clone)]
@@ -37,6 +48,7 @@ pub struct ProtocolControllerImpl {
     pub sender_endorsement_handler: Option<Sender<EndorsementHandlerPropagationCommand>>,
     pub sender_connectivity_thread: Option<Sender<ConnectivityCommand>>,
     pub sender_peer_management_thread: Option<Sender<PeerManagementCmd>>,
+    pub production_schedule: ProductionSchedule,
 }
 
 impl ProtocolControllerImpl {
@@ -47,6 +59,7 @@ impl ProtocolControllerImpl {
         sender_endorsement_handler: Sender<EndorsementHandlerPropagationCommand>,
         sender_connectivity_thread: Sender<ConnectivityCommand>,
         sender_peer_management_thread: Sender<PeerManagementCmd>,
+        production_schedule: ProductionSchedule,
     ) -> Self {
         ProtocolControllerImpl {
             sender_block_retrieval_handler: Some(sender_block_retrieval_handler),
@@ -55,6 +68,7 @@ impl ProtocolControllerImpl {
             sender_endorsement_handler: Some(sender_endorsement_handler),
             sender_connectivity_thread: Some(sender_connectivity_thread),
             sender_peer_management_thread: Some(sender_peer_management_thread),
+            production_schedule,
         }
     }
 }
@@ -140,13 +154,7 @@ impl ProtocolController for ProtocolControllerImpl {
 
     fn get_stats(
         &self,
-    ) -> Result<
-        (
-            NetworkStats,
-            HashMap<PeerId, (SocketAddr, PeerConnectionType)>,
-        ),
-        ProtocolError,
-    > {
+    ) -> Result<(NetworkStats, HashMap<PeerId, PeerConnectionInfo>), ProtocolError> {
         let (sender, receiver) = crossbeam::channel::bounded(1);
         self.sender_connectivity_thread
             .as_ref()
@@ -158,6 +166,22 @@ impl ProtocolController for ProtocolControllerImpl {
             .map_err(|_| ProtocolError::ChannelError("get_stats command receive error".into()))
     }
 
+    fn get_protocol_stats(
+        &self,
+    ) -> Result<(ProtocolStats, HashMap<PeerId, PeerConnectionInfo>), ProtocolError> {
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        self.sender_connectivity_thread
+            .as_ref()
+            .unwrap()
+            .send(ConnectivityCommand::GetProtocolStats { responder: sender })
+            .map_err(|_| {
+                ProtocolError::ChannelError("get_protocol_stats command send error".into())
+            })?;
+        receiver.recv_timeout(Duration::from_secs(10)).map_err(|_| {
+            ProtocolError::ChannelError("get_protocol_stats command receive error".into())
+        })
+    }
+
     fn ban_peers(&self, peer_ids: Vec<PeerId>) -> Result<(), ProtocolError> {
         self.sender_peer_management_thread
             .as_ref()
@@ -174,6 +198,93 @@ impl ProtocolController for ProtocolControllerImpl {
             .map_err(|_| ProtocolError::ChannelError("unban_peers command send error".into()))
     }
 
+    fn import_peers(&self, peers: BootstrapPeers) -> Result<(), ProtocolError> {
+        self.sender_peer_management_thread
+            .as_ref()
+            .unwrap()
+            .send(PeerManagementCmd::ImportPeers(
+                peers
+                    .0
+                    .into_iter()
+                    .map(|(peer_id, info)| (peer_id, info.listeners))
+                    .collect(),
+            ))
+            .map_err(|_| ProtocolError::ChannelError("import_peers command send error".into()))
+    }
+
+    fn set_out_connection_targets(
+        &self,
+        targets: OutConnectionTargets,
+    ) -> Result<(), ProtocolError> {
+        self.sender_peer_management_thread
+            .as_ref()
+            .unwrap()
+            .send(PeerManagementCmd::SetTargets(targets))
+            .map_err(|_| {
+                ProtocolError::ChannelError("set_out_connection_targets command send error".into())
+            })
+    }
+
+    fn annotate_peer(
+        &self,
+        peer_id: PeerId,
+        annotation: PeerAnnotation,
+    ) -> Result<(), ProtocolError> {
+        self.sender_peer_management_thread
+            .as_ref()
+            .unwrap()
+            .send(PeerManagementCmd::AnnotatePeer {
+                peer_id,
+                annotation,
+            })
+            .map_err(|_| ProtocolError::ChannelError("annotate_peer command send error".into()))
+    }
+
+    fn test_peer(&self, address: SocketAddr) -> Result<PeerTestResult, ProtocolError> {
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        self.sender_peer_management_thread
+            .as_ref()
+            .unwrap()
+            .send(PeerManagementCmd::TestPeer {
+                address,
+                responder: sender,
+            })
+            .map_err(|_| ProtocolError::ChannelError("test_peer command send error".into()))?;
+        // the tester may be busy with another test before picking ours
+        receiver
+            .recv_timeout(Duration::from_secs(30))
+            .map_err(|_| ProtocolError::ChannelError("test_peer command receive error".into()))
+    }
+
+    fn test_reachability(&self) -> Result<Vec<ListenerReachability>, ProtocolError> {
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        self.sender_peer_management_thread
+            .as_ref()
+            .unwrap()
+            .send(PeerManagementCmd::TestReachability { responder: sender })
+            .map_err(|_| {
+                ProtocolError::ChannelError("test_reachability command send error".into())
+            })?;
+        // the peer handler answers once all the peers answered, or at the dial back timeout
+        receiver.recv().map_err(|_| {
+            ProtocolError::ChannelError("test_reachability command receive error".into())
+        })
+    }
+
+    fn report_bootstrap_client(
+        &self,
+        ip: IpAddr,
+        report: BootstrapClientReport,
+    ) -> Result<(), ProtocolError> {
+        self.sender_peer_management_thread
+            .as_ref()
+            .unwrap()
+            .send(PeerManagementCmd::BootstrapClientReport { ip, report })
+            .map_err(|_| {
+                ProtocolError::ChannelError("report_bootstrap_client command send error".into())
+            })
+    }
+
     fn get_bootstrap_peers(&self) -> Result<BootstrapPeers, ProtocolError> {
         let (sender, receiver) = crossbeam::channel::bounded(1);
         self.sender_peer_management_thread
@@ -188,6 +299,170 @@ impl ProtocolController for ProtocolControllerImpl {
         })
     }
 
+    fn get_peer_misbehaviors(
+        &self,
+        peer_ids: Option<Vec<PeerId>>,
+    ) -> Result<HashMap<PeerId, Vec<MisbehaviorRecord>>, ProtocolError> {
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        self.sender_peer_management_thread
+            .as_ref()
+            .unwrap()
+            .send(PeerManagementCmd::GetMisbehaviors {
+                peer_ids,
+                responder: sender,
+            })
+            .map_err(|_| {
+                ProtocolError::ChannelError("get_peer_misbehaviors command send error".into())
+            })?;
+        receiver.recv_timeout(Duration::from_secs(10)).map_err(|_| {
+            ProtocolError::ChannelError("get_peer_misbehaviors command receive error".into())
+        })
+    }
+
+    fn export_ban_reports(&self) -> Result<Vec<BanReport>, ProtocolError> {
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        self.sender_peer_management_thread
+            .as_ref()
+            .unwrap()
+            .send(PeerManagementCmd::GetBanReports { responder: sender })
+            .map_err(|_| {
+                ProtocolError::ChannelError("export_ban_reports command send error".into())
+            })?;
+        receiver.recv_timeout(Duration::from_secs(10)).map_err(|_| {
+            ProtocolError::ChannelError("export_ban_reports command receive error".into())
+        })
+    }
+
+    fn import_ban_reports(
+        &self,
+        reports: Vec<Vec<u8>>,
+    ) -> Result<Vec<Result<PeerId, String>>, ProtocolError> {
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        self.sender_peer_management_thread
+            .as_ref()
+            .unwrap()
+            .send(PeerManagementCmd::ImportBanReports {
+                reports,
+                responder: sender,
+            })
+            .map_err(|_| {
+                ProtocolError::ChannelError("import_ban_reports command send error".into())
+            })?;
+        receiver.recv_timeout(Duration::from_secs(10)).map_err(|_| {
+            ProtocolError::ChannelError("import_ban_reports command receive error".into())
+        })
+    }
+
+    fn reload_config(&self, config: ProtocolConfig) -> Result<(), ProtocolError> {
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        self.sender_connectivity_thread
+            .as_ref()
+            .unwrap()
+            .send(ConnectivityCommand::ReloadConfig {
+                config: Box::new(config),
+                responder: sender,
+            })
+            .map_err(|_| ProtocolError::ChannelError("reload_config command send error".into()))?;
+        receiver
+            .recv_timeout(Duration::from_secs(10))
+            .map_err(|_| {
+                ProtocolError::ChannelError("reload_config command receive error".into())
+            })?
+    }
+
+    fn add_listener(
+        &self,
+        addr: SocketAddr,
+        transport: TransportType,
+    ) -> Result<(), ProtocolError> {
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        self.sender_connectivity_thread
+            .as_ref()
+            .unwrap()
+            .send(ConnectivityCommand::AddListener {
+                addr,
+                transport,
+                responder: sender,
+            })
+            .map_err(|_| ProtocolError::ChannelError("add_listener command send error".into()))?;
+        receiver
+            .recv_timeout(Duration::from_secs(10))
+            .map_err(|_| ProtocolError::ChannelError("add_listener command receive error".into()))?
+    }
+
+    fn remove_listener(&self, addr: SocketAddr) -> Result<(), ProtocolError> {
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        self.sender_connectivity_thread
+            .as_ref()
+            .unwrap()
+            .send(ConnectivityCommand::RemoveListener {
+                addr,
+                responder: sender,
+            })
+            .map_err(|_| {
+                ProtocolError::ChannelError("remove_listener command send error".into())
+            })?;
+        receiver
+            .recv_timeout(Duration::from_secs(10))
+            .map_err(|_| {
+                ProtocolError::ChannelError("remove_listener command receive error".into())
+            })?
+    }
+
+    fn set_routable_ip(&self, routable_ip: Option<IpAddr>) -> Result<(), ProtocolError> {
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        self.sender_connectivity_thread
+            .as_ref()
+            .unwrap()
+            .send(ConnectivityCommand::SetRoutableIp {
+                routable_ip,
+                responder: sender,
+            })
+            .map_err(|_| {
+                ProtocolError::ChannelError("set_routable_ip command send error".into())
+            })?;
+        receiver
+            .recv_timeout(Duration::from_secs(10))
+            .map_err(|_| {
+                ProtocolError::ChannelError("set_routable_ip command receive error".into())
+            })?
+    }
+
+    fn get_connection_events(
+        &self,
+        since: Option<MassaTime>,
+        peer_id: Option<PeerId>,
+    ) -> Result<Vec<ConnectionEvent>, ProtocolError> {
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        self.sender_peer_management_thread
+            .as_ref()
+            .unwrap()
+            .send(PeerManagementCmd::GetConnectionEvents {
+                since,
+                peer_id,
+                responder: sender,
+            })
+            .map_err(|_| {
+                ProtocolError::ChannelError("get_connection_events command send error".into())
+            })?;
+        receiver.recv_timeout(Duration::from_secs(10)).map_err(|_| {
+            ProtocolError::ChannelError("get_connection_events command receive error".into())
+        })
+    }
+
+    /// Records the productions in the schedule shared with the handlers, without a command:
+    /// the factories call it on their latency-critical path
+    fn set_production_slots(
+        &self,
+        block_slots: Vec<Slot>,
+        endorsement_slots: Vec<Slot>,
+    ) -> Result<(), ProtocolError> {
+        let now = MassaTime::now()?;
+        self.production_schedule
+            .record(&block_slots, &endorsement_slots, now);
+        Ok(())
+    }
+
     fn clone_box(&self) -> Box<dyn ProtocolController> {
         Box::new(self.clone())
     }
@@ -0,0 +1,242 @@
+//! Scheduler of the outbound dials.
+//!
+//! The maintenance loop of the connectivity thread, the peer testers and the tests requested by
+//! the operator all dial through it. It bounds the number of dials in progress, waits a cooldown
+//! before dialing the same IP again and spreads the dials with a random jitter, so that a burst
+//! of candidates (when a network partition heals for example) neither exhausts our sockets nor
+//! looks like a SYN flood to the peers.
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use massa_protocol_exports::ProtocolConfig;
+use parking_lot::{Condvar, Mutex};
+use rand::Rng;
+
+/// Origin of a dial
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialKind {
+    /// Test of a peer announced by the network
+    Test,
+    /// Test requested by the operator: not delayed and not subject to the cooldown
+    Operator,
+}
+
+#[derive(Default)]
+struct DialState {
+    /// dials in progress, with the time after which they are considered finished
+    in_flight: HashMap<u64, (IpAddr, Instant)>,
+    next_dial_id: u64,
+    /// time of the last dial of each destination
+    last_dials: HashMap<IpAddr, Instant>,
+    /// dials of the maintenance loop waiting for the end of their jitter
+    queue: Vec<(Instant, SocketAddr)>,
+}
+
+impl DialState {
+    fn prune(&mut self, now: Instant, cooldown: Duration) {
+        self.in_flight.retain(|_, (_, deadline)| *deadline > now);
+        self.last_dials
+            .retain(|_, dialed_at| now.saturating_duration_since(*dialed_at) < cooldown);
+    }
+
+    fn start(&mut self, ip: IpAddr, now: Instant, deadline: Instant) -> u64 {
+        let id = self.next_dial_id;
+        self.next_dial_id += 1;
+        self.in_flight.insert(id, (ip, deadline));
+        self.last_dials.insert(ip, now);
+        id
+    }
+}
+
+pub struct DialScheduler {
+    /// maximum number of dials in progress
+    max_concurrent_dials: usize,
+    /// minimum time between two dials of the same IP
+    cooldown: Duration,
+    /// maximum random delay before a dial
+    jitter: Duration,
+    /// time after which a dial without news is considered finished
+    dial_timeout: Duration,
+    state: Mutex<DialState>,
+    slot_released: Condvar,
+}
+
+pub type SharedDialScheduler = Arc<DialScheduler>;
+
+impl DialScheduler {
+    pub fn from_config(config: &ProtocolConfig) -> SharedDialScheduler {
+        Arc::new(DialScheduler {
+            max_concurrent_dials: config.max_concurrent_dials.max(1),
+            cooldown: config.dial_cooldown.to_duration(),
+            jitter: config.dial_jitter.to_duration(),
+            // we only dial with TCP for now
+            dial_timeout: config.transports.tcp.connection_timeout.to_duration(),
+            state: Mutex::new(DialState::default()),
+            slot_released: Condvar::new(),
+        })
+    }
+
+    fn random_jitter(&self) -> Duration {
+        if self.jitter.is_zero() {
+            return Duration::ZERO;
+        }
+        Duration::from_millis(rand::thread_rng().gen_range(0..=self.jitter.as_millis() as u64))
+    }
+
+    /// Queues a dial of the maintenance loop, returns false if the destination is already
+    /// queued, being dialed or in its cooldown
+    pub fn enqueue(&self, addr: SocketAddr) -> bool {
+        let ip = addr.ip().to_canonical();
+        let now = Instant::now();
+        let mut state = self.state.lock();
+        state.prune(now, self.cooldown);
+        if state.last_dials.contains_key(&ip)
+            || state.in_flight.values().any(|(dialed, _)| *dialed == ip)
+            || state
+                .queue
+                .iter()
+                .any(|(_, queued)| queued.ip().to_canonical() == ip)
+        {
+            return false;
+        }
+        let due = now + self.random_jitter();
+        state.queue.push((due, addr));
+        true
+    }
+
+    /// Takes the queued dials whose jitter is over, as long as the concurrency limit allows.
+    /// They count as in progress until the peer connects or the dial times out.
+    pub fn take_due(&self) -> Vec<SocketAddr> {
+        let now = Instant::now();
+        let mut state = self.state.lock();
+        state.prune(now, self.cooldown);
+        state.queue.sort_by_key(|(due, _)| *due);
+        let mut due = Vec::new();
+        while let Some((due_at, addr)) = state.queue.first().copied() {
+            if due_at > now || state.in_flight.len() >= self.max_concurrent_dials {
+                break;
+            }
+            state.queue.remove(0);
+            state.start(addr.ip().to_canonical(), now, now + self.dial_timeout);
+            due.push(addr);
+        }
+        due
+    }
+
+    /// Time until the next queued dial can start, at most `max`
+    pub fn next_wakeup(&self, max: Duration) -> Duration {
+        let now = Instant::now();
+        let state = self.state.lock();
+        let next = if state.in_flight.len() >= self.max_concurrent_dials {
+            state
+                .in_flight
+                .values()
+                .map(|(_, deadline)| *deadline)
+                .min()
+        } else {
+            state.queue.iter().map(|(due, _)| *due).min()
+        };
+        next.map_or(max, |next| next.saturating_duration_since(now).min(max))
+    }
+
+    /// The dials to these IPs succeeded: they don't take a slot anymore
+    pub fn release_connected(&self, ips: impl IntoIterator<Item = IpAddr>) {
+        let mut state = self.state.lock();
+        for ip in ips {
+            let ip = ip.to_canonical();
+            state.in_flight.retain(|_, (dialed, _)| *dialed != ip);
+        }
+        self.slot_released.notify_all();
+    }
+
+    /// Runs the blocking dial `dial` to `addr` once a slot is free and after a random jitter.
+    /// Returns `None` if the destination is in its cooldown or no slot was freed in time.
+    pub fn dial<T>(&self, addr: SocketAddr, kind: DialKind, dial: impl FnOnce() -> T) -> Option<T> {
+        let ip = addr.ip().to_canonical();
+        let dial_id = {
+            let mut state = self.state.lock();
+            state.prune(Instant::now(), self.cooldown);
+            if kind != DialKind::Operator && state.last_dials.contains_key(&ip) {
+                return None;
+            }
+            let wait_deadline = Instant::now() + self.dial_timeout;
+            while state.in_flight.len() >= self.max_concurrent_dials {
+                let timed_out = self
+                    .slot_released
+                    .wait_until(&mut state, wait_deadline)
+                    .timed_out();
+                // the slots of the dials that timed out are freed without notification
+                state.prune(Instant::now(), self.cooldown);
+                if timed_out && state.in_flight.len() >= self.max_concurrent_dials {
+                    return None;
+                }
+            }
+            let now = Instant::now();
+            // the jitter and the dial itself must finish before the slot expires
+            state.start(ip, now, now + self.jitter + 2 * self.dial_timeout)
+        };
+        if kind != DialKind::Operator {
+            std::thread::sleep(self.random_jitter());
+        }
+        let result = dial();
+        self.state.lock().in_flight.remove(&dial_id);
+        self.slot_released.notify_one();
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use massa_protocol_exports::ProtocolConfig;
+    use massa_time::MassaTime;
+
+    use super::{DialKind, DialScheduler};
+
+    fn scheduler(max_concurrent_dials: usize, cooldown: u64) -> super::SharedDialScheduler {
+        let mut config = ProtocolConfig::default();
+        config.max_concurrent_dials = max_concurrent_dials;
+        config.dial_cooldown = MassaTime::from_millis(cooldown);
+        config.dial_jitter = MassaTime::from_millis(0);
+        config.transports.tcp.connection_timeout = MassaTime::from_millis(60_000);
+        DialScheduler::from_config(&config)
+    }
+
+    #[test]
+    fn test_dial_queue_limits_concurrency() {
+        let scheduler = scheduler(2, 0);
+        assert!(scheduler.enqueue("1.1.1.1:31245".parse().unwrap()));
+        // same destination on another port
+        assert!(!scheduler.enqueue("1.1.1.1:31246".parse().unwrap()));
+        assert!(scheduler.enqueue("2.2.2.2:31245".parse().unwrap()));
+        assert!(scheduler.enqueue("3.3.3.3:31245".parse().unwrap()));
+
+        assert_eq!(scheduler.take_due().len(), 2);
+        assert!(scheduler.take_due().is_empty());
+        // the next dial waits for a slot
+        assert_eq!(
+            scheduler.next_wakeup(Duration::from_secs(1)),
+            Duration::from_secs(1)
+        );
+
+        scheduler.release_connected(["1.1.1.1".parse().unwrap()]);
+        assert_eq!(scheduler.take_due(), vec!["3.3.3.3:31245".parse().unwrap()]);
+    }
+
+    #[test]
+    fn test_dial_cooldown() {
+        let scheduler = scheduler(10, 60_000);
+        let addr = "1.1.1.1:31245".parse().unwrap();
+        assert_eq!(scheduler.dial(addr, DialKind::Test, || 1), Some(1));
+        assert_eq!(scheduler.dial(addr, DialKind::Test, || 2), None);
+        assert!(!scheduler.enqueue(addr));
+        // the operator isn't subject to the cooldown
+        assert_eq!(scheduler.dial(addr, DialKind::Operator, || 3), Some(3));
+    }
+}
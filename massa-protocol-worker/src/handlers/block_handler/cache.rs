@@ -1,15 +1,24 @@
-use std::{num::NonZeroUsize, sync::Arc, time::Instant};
+use std::{collections::HashSet, num::NonZeroUsize, sync::Arc, time::Instant};
 
 use lru::LruCache;
 use massa_models::{block_header::SecuredHeader, block_id::BlockId};
 use parking_lot::RwLock;
 use peernet::peer_id::PeerId;
 
+use crate::handlers::{known_items::KnownItemsCache, relayed_ids::SharedRelayedIdsCache};
+
+use super::early_headers::EarlyHeaderBuffer;
+
 pub struct BlockCache {
     pub checked_headers: LruCache<BlockId, SecuredHeader>,
-    #[allow(clippy::type_complexity)]
-    pub blocks_known_by_peer: LruCache<PeerId, (LruCache<BlockId, (bool, Instant)>, Instant)>,
-    pub max_known_blocks_by_peer: NonZeroUsize,
+    /// Whether each peer knows a block, and when we learned it
+    pub blocks_known_by_peer: KnownItemsCache<BlockId, (bool, Instant)>,
+    /// Peers to which the header of a block was relayed before the block was integrated
+    pub headers_relayed: LruCache<BlockId, HashSet<PeerId>>,
+    /// Headers waiting for their parents or their slot before being handed to consensus
+    pub early_headers: EarlyHeaderBuffer,
+    /// Ids recently relayed by all the handlers
+    pub relayed_ids: SharedRelayedIdsCache,
 }
 
 impl BlockCache {
@@ -20,23 +29,32 @@ impl BlockCache {
         val: bool,
         timeout: Instant,
     ) {
-        let (blocks, _) = self
-            .blocks_known_by_peer
-            .get_or_insert_mut(from_peer_id.clone(), || {
-                (LruCache::new(self.max_known_blocks_by_peer), Instant::now())
-            });
-        for block_id in block_ids {
-            blocks.put(*block_id, (val, timeout));
-        }
+        self.blocks_known_by_peer.insert_many(
+            from_peer_id,
+            block_ids.iter().map(|block_id| (*block_id, (val, timeout))),
+        );
     }
 }
 
 impl BlockCache {
-    pub fn new(max_known_blocks: NonZeroUsize, max_known_blocks_by_peer: NonZeroUsize) -> Self {
+    pub fn new(
+        max_known_blocks: NonZeroUsize,
+        max_peers: NonZeroUsize,
+        max_known_blocks_by_peer: NonZeroUsize,
+        max_total_known_blocks_by_peers: usize,
+        max_early_headers: usize,
+        relayed_ids: SharedRelayedIdsCache,
+    ) -> Self {
         Self {
             checked_headers: LruCache::new(max_known_blocks),
-            blocks_known_by_peer: LruCache::new(max_known_blocks_by_peer),
-            max_known_blocks_by_peer,
+            blocks_known_by_peer: KnownItemsCache::new(
+                max_peers,
+                max_known_blocks_by_peer,
+                max_total_known_blocks_by_peers,
+            ),
+            headers_relayed: LruCache::new(max_known_blocks),
+            early_headers: EarlyHeaderBuffer::new(max_early_headers),
+            relayed_ids,
         }
     }
 }
@@ -1,4 +1,4 @@
-use massa_models::block_id::BlockId;
+use massa_models::{block_header::SecuredHeader, block_id::BlockId};
 use massa_storage::Storage;
 
 /// Commands that the block handler can process
@@ -14,4 +14,6 @@ pub enum BlockHandlerPropagationCommand {
     },
     /// A block, or it's header, amounted to an attempted attack.
     AttackBlockDetected(BlockId),
+    /// Relay the header of a new block that passed the checks, before its integration.
+    RelayHeader(SecuredHeader),
 }
@@ -3,9 +3,12 @@ use massa_models::{
     block_id::BlockId,
     prehash::{PreHashMap, PreHashSet},
 };
+use massa_protocol_exports::ProtocolConfig;
 
 pub enum BlockHandlerRetrievalCommand {
     Stop,
+    /// Reloaded config
+    UpdateConfig(Box<ProtocolConfig>),
     /// Wish list delta
     WishlistDelta {
         /// add to wish list
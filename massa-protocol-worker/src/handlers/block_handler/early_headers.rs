@@ -0,0 +1,267 @@
+//! Buffer of the block headers that consensus can't process yet.
+//!
+//! A header may reference parents we haven't received yet, or arrive slightly before its slot
+//! because the clocks of the nodes drift. Instead of handing it to consensus right away, which
+//! would discard it and ask for it again, it waits here until its parents are registered and its
+//! slot started. The headers waiting too long for their parents, or evicted because the buffer
+//! is full, are handed to consensus anyway so that nothing is lost.
+
+use std::{collections::hash_map::Entry, time::Duration};
+
+use massa_models::{
+    block_header::SecuredHeader,
+    block_id::BlockId,
+    prehash::{PreHashMap, PreHashSet},
+    stats::EarlyHeaderStats,
+};
+use massa_time::MassaTime;
+
+/// Interval at which the buffer is checked for headers whose slot started or that expired
+pub const EARLY_HEADERS_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+struct EarlyHeader {
+    header: SecuredHeader,
+    /// parents not registered in consensus yet
+    missing_parents: PreHashSet<BlockId>,
+    /// start of the slot of the header
+    slot_timestamp: MassaTime,
+    /// time after which the header is handed to consensus even if its parents are missing
+    expires_at: MassaTime,
+}
+
+pub struct EarlyHeaderBuffer {
+    headers: PreHashMap<BlockId, EarlyHeader>,
+    /// buffered headers waiting for each missing parent
+    waiting_for: PreHashMap<BlockId, PreHashSet<BlockId>>,
+    /// maximum number of headers buffered (0 to disable the buffer)
+    capacity: usize,
+    inserted: u64,
+    released: u64,
+    expired: u64,
+    evicted: u64,
+}
+
+impl EarlyHeaderBuffer {
+    /// Creates a new `EarlyHeaderBuffer` keeping at most `capacity` headers
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            headers: PreHashMap::default(),
+            waiting_for: PreHashMap::default(),
+            capacity,
+            inserted: 0,
+            released: 0,
+            expired: 0,
+            evicted: 0,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Buffers a header until its `missing_parents` are registered and `slot_timestamp` is
+    /// reached, or until `expires_at`. Returns the header evicted to make room for it, which
+    /// must be handed to consensus.
+    pub fn insert(
+        &mut self,
+        header: SecuredHeader,
+        missing_parents: PreHashSet<BlockId>,
+        slot_timestamp: MassaTime,
+        expires_at: MassaTime,
+    ) -> Option<SecuredHeader> {
+        let block_id = header.id;
+        if self.headers.contains_key(&block_id) {
+            return None;
+        }
+        let mut evicted = None;
+        if self.headers.len() >= self.capacity {
+            // the header closest to its expiry leaves first
+            let oldest = self
+                .headers
+                .iter()
+                .min_by_key(|(_, early_header)| early_header.expires_at)
+                .map(|(block_id, _)| *block_id);
+            evicted = oldest.and_then(|block_id| self.remove(&block_id));
+            if evicted.is_some() {
+                self.evicted += 1;
+            }
+        }
+        for parent in &missing_parents {
+            self.waiting_for
+                .entry(*parent)
+                .or_default()
+                .insert(block_id);
+        }
+        self.headers.insert(
+            block_id,
+            EarlyHeader {
+                header,
+                missing_parents,
+                slot_timestamp,
+                expires_at,
+            },
+        );
+        self.inserted += 1;
+        evicted
+    }
+
+    fn remove(&mut self, block_id: &BlockId) -> Option<SecuredHeader> {
+        let early_header = self.headers.remove(block_id)?;
+        for parent in &early_header.missing_parents {
+            if let Entry::Occupied(mut waiting) = self.waiting_for.entry(*parent) {
+                waiting.get_mut().remove(block_id);
+                if waiting.get().is_empty() {
+                    waiting.remove();
+                }
+            }
+        }
+        Some(early_header.header)
+    }
+
+    /// Notes that a block was registered in consensus: the headers waiting for it as a parent
+    /// don't wait for it anymore
+    pub fn parent_registered(&mut self, block_id: &BlockId) {
+        let Some(children) = self.waiting_for.remove(block_id) else {
+            return;
+        };
+        for child in children {
+            if let Some(early_header) = self.headers.get_mut(&child) {
+                early_header.missing_parents.remove(block_id);
+            }
+        }
+    }
+
+    /// Takes the headers to hand to consensus at `now`: the ones whose parents are registered
+    /// and whose slot started, and the ones that expired. They are sorted by slot so that the
+    /// parents come before their children.
+    pub fn take_ready(&mut self, now: MassaTime) -> Vec<SecuredHeader> {
+        let mut ready = Vec::new();
+        let mut expired = Vec::new();
+        for (block_id, early_header) in &self.headers {
+            if early_header.missing_parents.is_empty() && early_header.slot_timestamp <= now {
+                ready.push(*block_id);
+            } else if early_header.expires_at <= now {
+                expired.push(*block_id);
+            }
+        }
+        self.released += ready.len() as u64;
+        self.expired += expired.len() as u64;
+        let mut headers: Vec<SecuredHeader> = ready
+            .iter()
+            .chain(expired.iter())
+            .filter_map(|block_id| self.remove(block_id))
+            .collect();
+        headers.sort_by_key(|header| header.content.slot);
+        headers
+    }
+
+    pub fn get_stats(&self) -> EarlyHeaderStats {
+        EarlyHeaderStats {
+            buffered_count: self.headers.len() as u64,
+            capacity: self.capacity as u64,
+            inserted: self.inserted,
+            released: self.released,
+            expired: self.expired,
+            evicted: self.evicted,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use massa_hash::Hash;
+    use massa_models::{
+        block_header::{BlockHeader, BlockHeaderSerializer, SecuredHeader},
+        block_id::BlockId,
+        prehash::PreHashSet,
+        secure_share::SecureShareContent,
+        slot::Slot,
+    };
+    use massa_signature::KeyPair;
+    use massa_time::MassaTime;
+
+    use super::EarlyHeaderBuffer;
+
+    fn header(period: u64, parents: Vec<BlockId>) -> SecuredHeader {
+        BlockHeader::new_verifiable(
+            BlockHeader {
+                slot: Slot::new(period, 0),
+                parents,
+                operation_merkle_root: Hash::compute_from(&period.to_be_bytes()),
+                endorsements: Vec::new(),
+                denunciations: Vec::new(),
+            },
+            BlockHeaderSerializer::new(),
+            &KeyPair::generate(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_early_headers_wait_for_parents_and_slot() {
+        let mut buffer = EarlyHeaderBuffer::new(2);
+        let parent = header(1, Vec::new());
+        let child = header(2, vec![parent.id]);
+        let early = header(3, Vec::new());
+        assert!(buffer
+            .insert(
+                child.clone(),
+                PreHashSet::from_iter([parent.id]),
+                MassaTime::from_millis(0),
+                MassaTime::from_millis(10_000),
+            )
+            .is_none());
+        assert!(buffer
+            .insert(
+                early.clone(),
+                PreHashSet::default(),
+                MassaTime::from_millis(2_000),
+                MassaTime::from_millis(10_000),
+            )
+            .is_none());
+
+        // the child waits for its parent, the early header for its slot
+        assert!(buffer.take_ready(MassaTime::from_millis(1_000)).is_empty());
+        buffer.parent_registered(&parent.id);
+        assert_eq!(
+            buffer.take_ready(MassaTime::from_millis(1_000))[0].id,
+            child.id
+        );
+        assert_eq!(
+            buffer.take_ready(MassaTime::from_millis(2_000))[0].id,
+            early.id
+        );
+
+        // the orphans are handed over when they expire or when the buffer is full
+        let orphan_parents = PreHashSet::from_iter([parent.id]);
+        buffer.insert(
+            header(4, vec![parent.id]),
+            orphan_parents.clone(),
+            MassaTime::from_millis(0),
+            MassaTime::from_millis(5_000),
+        );
+        buffer.insert(
+            header(5, vec![parent.id]),
+            orphan_parents.clone(),
+            MassaTime::from_millis(0),
+            MassaTime::from_millis(20_000),
+        );
+        let evicted = buffer
+            .insert(
+                header(6, vec![parent.id]),
+                orphan_parents,
+                MassaTime::from_millis(0),
+                MassaTime::from_millis(20_000),
+            )
+            .unwrap();
+        assert_eq!(evicted.content.slot, Slot::new(4, 0));
+        assert_eq!(buffer.take_ready(MassaTime::from_millis(20_000)).len(), 2);
+
+        let stats = buffer.get_stats();
+        assert_eq!(stats.buffered_count, 0);
+        assert_eq!(stats.inserted, 5);
+        assert_eq!(stats.released, 2);
+        assert_eq!(stats.expired, 2);
+        assert_eq!(stats.evicted, 1);
+    }
+}
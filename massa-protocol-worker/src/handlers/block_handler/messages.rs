@@ -7,6 +7,7 @@ use massa_models::{
         SecureShareOperation,
     },
     secure_share::{SecureShareDeserializer, SecureShareSerializer},
+    slot::{Slot, SlotDeserializer, SlotSerializer},
 };
 use massa_serialization::{Deserializer, Serializer, U64VarIntDeserializer, U64VarIntSerializer};
 use nom::{
@@ -16,7 +17,7 @@ use nom::{
     IResult, Parser,
 };
 use num_enum::{IntoPrimitive, TryFromPrimitive};
-use std::ops::Bound::Included;
+use std::ops::Bound::{Excluded, Included};
 
 /// Ask for the info about a block.
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
@@ -43,7 +44,7 @@ pub enum BlockInfoReply {
     NotFound,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 //TODO: Fix this clippy warning
 #[allow(clippy::large_enum_variant)]
 pub enum BlockMessage {
@@ -53,6 +54,8 @@ pub enum BlockMessage {
     AskForBlocks(Vec<(BlockId, AskForBlocksInfo)>),
     /// Message replying with info on a list of blocks.
     ReplyForBlocks(Vec<(BlockId, BlockInfoReply)>),
+    /// Message asking the peer for the headers of the blocks of an inclusive slot range.
+    AskBlocksBySlotRange(Slot, Slot),
 }
 
 impl BlockMessage {
@@ -61,11 +64,12 @@ impl BlockMessage {
             BlockMessage::BlockHeader(_) => MessageTypeId::BlockHeader,
             BlockMessage::AskForBlocks(_) => MessageTypeId::AskForBlocks,
             BlockMessage::ReplyForBlocks(_) => MessageTypeId::ReplyForBlocks,
+            BlockMessage::AskBlocksBySlotRange(..) => MessageTypeId::AskBlocksBySlotRange,
         }
     }
 
     pub fn max_id() -> u64 {
-        <MessageTypeId as Into<u64>>::into(MessageTypeId::ReplyForBlocks) + 1
+        <MessageTypeId as Into<u64>>::into(MessageTypeId::AskBlocksBySlotRange) + 1
     }
 }
 
@@ -76,6 +80,7 @@ pub enum MessageTypeId {
     BlockHeader,
     AskForBlocks,
     ReplyForBlocks,
+    AskBlocksBySlotRange,
 }
 
 #[derive(IntoPrimitive, Debug, Eq, PartialEq, TryFromPrimitive)]
@@ -94,6 +99,7 @@ pub struct BlockMessageSerializer {
     length_serializer: U64VarIntSerializer,
     block_id_serializer: BlockIdSerializer,
     operation_id_serializer: OperationIdSerializer,
+    slot_serializer: SlotSerializer,
 }
 
 impl BlockMessageSerializer {
@@ -104,6 +110,7 @@ impl BlockMessageSerializer {
             length_serializer: U64VarIntSerializer::new(),
             block_id_serializer: BlockIdSerializer::new(),
             operation_id_serializer: OperationIdSerializer::new(),
+            slot_serializer: SlotSerializer::new(),
         }
     }
 }
@@ -183,6 +190,10 @@ impl Serializer<BlockMessage> for BlockMessageSerializer {
                     }
                 }
             }
+            BlockMessage::AskBlocksBySlotRange(start, end) => {
+                self.slot_serializer.serialize(start, buffer)?;
+                self.slot_serializer.serialize(end, buffer)?;
+            }
         }
         Ok(())
     }
@@ -196,6 +207,7 @@ pub struct BlockMessageDeserializer {
     hash_deserializer: HashDeserializer,
     operation_ids_deserializer: OperationIdsDeserializer,
     operations_deserializer: OperationsDeserializer,
+    slot_deserializer: SlotDeserializer,
 }
 
 pub struct BlockMessageDeserializerArgs {
@@ -241,6 +253,10 @@ impl BlockMessageDeserializer {
                 args.max_op_datastore_key_length,
                 args.max_op_datastore_value_length,
             ),
+            slot_deserializer: SlotDeserializer::new(
+                (Included(0), Included(u64::MAX)),
+                (Included(0), Excluded(args.thread_count)),
+            ),
         }
     }
 
@@ -370,6 +386,19 @@ impl Deserializer<BlockMessage> for BlockMessageDeserializer {
                 )
                 .map(BlockMessage::ReplyForBlocks)
                 .parse(buffer),
+                MessageTypeId::AskBlocksBySlotRange => context(
+                    "Failed AskBlocksBySlotRange deserialization",
+                    tuple((
+                        context("Failed start slot deserialization", |input| {
+                            self.slot_deserializer.deserialize(input)
+                        }),
+                        context("Failed end slot deserialization", |input| {
+                            self.slot_deserializer.deserialize(input)
+                        }),
+                    )),
+                )
+                .map(|(start, end)| BlockMessage::AskBlocksBySlotRange(start, end))
+                .parse(buffer),
             }
         })
         .parse(buffer)
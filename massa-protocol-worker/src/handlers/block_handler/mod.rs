@@ -3,10 +3,10 @@ use std::thread::JoinHandle;
 use crossbeam::channel::{Receiver, Sender};
 use massa_consensus_exports::ConsensusController;
 use massa_pool_exports::PoolController;
-use massa_protocol_exports::ProtocolConfig;
+use massa_protocol_exports::{ProtocolConfig, ProtocolEventBus};
 use massa_storage::Storage;
 
-use crate::wrap_network::ActiveConnectionsTrait;
+use crate::{sig_verifier::SigVerifierPool, wrap_network::ActiveConnectionsTrait};
 
 use self::{
     cache::SharedBlockCache, commands_propagation::BlockHandlerPropagationCommand,
@@ -17,6 +17,7 @@ use self::{
 pub mod cache;
 pub mod commands_propagation;
 pub mod commands_retrieval;
+mod early_headers;
 mod messages;
 mod propagation;
 mod retrieval;
@@ -33,7 +34,7 @@ use super::{
     operation_handler::{
         cache::SharedOperationCache, commands_propagation::OperationHandlerPropagationCommand,
     },
-    peer_handler::models::{PeerManagementCmd, PeerMessageTuple},
+    peer_handler::models::{PeerManagementCmd, PeerMessageTuple, SharedPeerDB},
 };
 
 pub struct BlockHandler {
@@ -59,6 +60,9 @@ impl BlockHandler {
         operation_cache: SharedOperationCache,
         cache: SharedBlockCache,
         storage: Storage,
+        sig_verifier: SigVerifierPool,
+        event_bus: ProtocolEventBus,
+        peer_db: SharedPeerDB,
     ) -> Self {
         let block_retrieval_thread = start_retrieval_thread(
             active_connections.clone(),
@@ -74,6 +78,9 @@ impl BlockHandler {
             operation_cache,
             cache.clone(),
             storage.clone_without_refs(),
+            sig_verifier,
+            event_bus,
+            peer_db,
         );
         let block_propagation_thread = start_propagation_thread(
             active_connections,
@@ -89,6 +96,15 @@ impl BlockHandler {
         }
     }
 
+    /// Sends a reloaded config to the retrieval thread of the handler
+    pub fn update_config(&self, config: &ProtocolConfig) {
+        if let Some((tx, _)) = &self.block_retrieval_thread {
+            let _ = tx.send(BlockHandlerRetrievalCommand::UpdateConfig(Box::new(
+                config.clone(),
+            )));
+        }
+    }
+
     pub fn stop(&mut self) {
         if let Some((tx, thread)) = self.block_retrieval_thread.take() {
             let _ = tx.send(BlockHandlerRetrievalCommand::Stop);
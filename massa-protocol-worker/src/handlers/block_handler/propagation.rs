@@ -1,10 +1,9 @@
-use std::{collections::VecDeque, num::NonZeroUsize, thread::JoinHandle, time::Instant};
+use std::{collections::VecDeque, thread::JoinHandle};
 
 use crossbeam::channel::{Receiver, Sender};
-use lru::LruCache;
 use massa_logging::massa_trace;
 use massa_models::{block_id::BlockId, prehash::PreHashSet};
-use massa_protocol_exports::{ProtocolConfig, ProtocolError};
+use massa_protocol_exports::{MisbehaviorKind, ProtocolConfig, ProtocolError};
 use massa_storage::Storage;
 use peernet::peer_id::PeerId;
 use tracing::{debug, info, warn};
@@ -65,45 +64,28 @@ impl PropagationThread {
                                 }
                             };
 
-                            // Clean shared cache if peers do not exist anymore
+                            self.refresh_connected_peers();
                             {
                                 let mut cache_write = self.cache.write();
-                                let peers: Vec<PeerId> = cache_write
-                                    .blocks_known_by_peer
-                                    .iter()
-                                    .map(|(id, _)| id.clone())
-                                    .collect();
-                                let peers_connected =
-                                    self.active_connections.get_peer_ids_connected();
-                                for peer_id in peers {
-                                    if !peers_connected.contains(&peer_id) {
-                                        cache_write.blocks_known_by_peer.pop(&peer_id);
-                                    }
-                                }
-                                for peer_id in peers_connected {
-                                    if !cache_write.blocks_known_by_peer.contains(&peer_id) {
-                                        //TODO: Change to detect the connection before
-                                        cache_write.blocks_known_by_peer.put(
-                                            peer_id,
-                                            (
-                                                LruCache::new(
-                                                    NonZeroUsize::new(self.config.max_node_known_blocks_size)
-                                                        .expect("max_node_known_blocks_size in config must be > 0"),
-                                                ),
-                                                Instant::now(),
-                                            ),
-                                        );
-                                    }
+                                // a block relayed recently, possibly before a restart, is not relayed again
+                                if cache_write.relayed_ids.read().block_relayed(&block_id) {
+                                    debug!("Block {} was relayed recently, skip it", block_id);
+                                    continue;
                                 }
-                            }
-                            {
-                                let cache_read = self.cache.read();
-                                for (peer_id, (blocks_known, _)) in &cache_read.blocks_known_by_peer
+                                cache_write.relayed_ids.write().record_block(block_id);
+                                // the peers we relayed the header to already have it
+                                let relayed_to = cache_write
+                                    .headers_relayed
+                                    .pop(&block_id)
+                                    .unwrap_or_default();
+                                for (peer_id, cond) in
+                                    cache_write.blocks_known_by_peer.peek_all(&block_id)
                                 {
                                     // peer that isn't asking for that block
-                                    let cond = blocks_known.peek(&block_id);
                                     // if we don't know if that peer knows that hash or if we know it doesn't
-                                    if !cond.map_or_else(|| false, |v| v.0) {
+                                    if !cond.map_or_else(|| false, |v| v.0)
+                                        && !relayed_to.contains(peer_id)
+                                    {
                                         massa_trace!("protocol.protocol_worker.process_command.integrated_block.send_header", { "peer_id": peer_id, "block_id": block_id});
                                         debug!(
                                             "Send block header for slot {} to peer {}",
@@ -113,7 +95,6 @@ impl PropagationThread {
                                             peer_id,
                                             &self.block_serializer,
                                             BlockMessage::BlockHeader(header.clone()).into(),
-                                            true,
                                         ) {
                                             warn!("Error while sending block header to peer {} err: {:?}", peer_id, err);
                                         }
@@ -123,22 +104,64 @@ impl PropagationThread {
                                 }
                             }
                         }
+                        BlockHandlerPropagationCommand::RelayHeader(header) => {
+                            let block_id = header.id;
+                            massa_trace!(
+                                "protocol.protocol_worker.process_command.relay_header.begin",
+                                { "block_id": block_id }
+                            );
+                            self.refresh_connected_peers();
+                            let mut cache_write = self.cache.write();
+                            let mut relayed_to = cache_write
+                                .headers_relayed
+                                .pop(&block_id)
+                                .unwrap_or_default();
+                            for (peer_id, knows_block) in
+                                cache_write.blocks_known_by_peer.peek_all(&block_id)
+                            {
+                                // skip the peers that know the block, among which the ones that sent us the header
+                                if knows_block.map_or(false, |v| v.0)
+                                    || relayed_to.contains(peer_id)
+                                {
+                                    continue;
+                                }
+                                debug!(
+                                    "Relay block header for slot {} to peer {}",
+                                    header.content.slot, peer_id
+                                );
+                                if let Err(err) = self.active_connections.send_to_peer(
+                                    peer_id,
+                                    &self.block_serializer,
+                                    BlockMessage::BlockHeader(header.clone()).into(),
+                                ) {
+                                    warn!(
+                                        "Error while relaying block header to peer {} err: {:?}",
+                                        peer_id, err
+                                    );
+                                } else {
+                                    relayed_to.insert(peer_id.clone());
+                                }
+                            }
+                            cache_write.headers_relayed.put(block_id, relayed_to);
+                        }
                         BlockHandlerPropagationCommand::AttackBlockDetected(block_id) => {
                             let to_ban: Vec<PeerId> = self
                                 .cache
                                 .read()
                                 .blocks_known_by_peer
-                                .iter()
-                                .filter_map(|(id, (block_known, _))| {
-                                    match block_known.peek(&block_id) {
-                                        Some((true, _)) => Some(id.clone()),
-                                        _ => None,
-                                    }
+                                .peek_all(&block_id)
+                                .filter_map(|(id, block_known)| match block_known {
+                                    Some((true, _)) => Some(id.clone()),
+                                    _ => None,
                                 })
                                 .collect();
                             for id in to_ban.iter() {
                                 massa_trace!("protocol.protocol_worker.process_command.attack_block_detected.ban_node", { "node": id, "block_id": block_id });
-                                if let Err(err) = self.ban_node(id) {
+                                if let Err(err) = self.ban_node(
+                                    id,
+                                    MisbehaviorKind::ProtocolViolation,
+                                    format!("sent block {} of an attack", block_id),
+                                ) {
                                     warn!("Error while banning peer {} err: {:?}", id, err);
                                 }
                             }
@@ -157,11 +180,31 @@ impl PropagationThread {
         }
     }
 
+    /// Forget the peers that are not connected anymore and track the newly connected ones
+    fn refresh_connected_peers(&self) {
+        let peers_connected = self.active_connections.get_peer_ids_connected();
+        //TODO: Change to detect the connection before
+        self.cache
+            .write()
+            .blocks_known_by_peer
+            .sync_peers(&peers_connected);
+    }
+
     /// send a ban peer command to the peer handler
-    fn ban_node(&mut self, peer_id: &PeerId) -> Result<(), ProtocolError> {
+    fn ban_node(
+        &mut self,
+        peer_id: &PeerId,
+        kind: MisbehaviorKind,
+        evidence: String,
+    ) -> Result<(), ProtocolError> {
         massa_trace!("ban node from retrieval thread", { "peer_id": peer_id.to_string() });
         self.peer_cmd_sender
-            .send(PeerManagementCmd::Ban(vec![peer_id.clone()]))
+            .send(PeerManagementCmd::ReportMisbehavior {
+                peer_id: peer_id.clone(),
+                kind,
+                evidence,
+                ban: true,
+            })
             .map_err(|err| ProtocolError::SendError(err.to_string()))
     }
 }
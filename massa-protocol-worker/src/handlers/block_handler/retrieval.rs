@@ -11,34 +11,38 @@ use crate::{
         operation_handler::{
             cache::SharedOperationCache, commands_propagation::OperationHandlerPropagationCommand,
         },
-        peer_handler::models::{PeerManagementCmd, PeerMessageTuple},
+        peer_handler::models::{PeerManagementCmd, PeerMessageTuple, SharedPeerDB},
+        request_manager::RequestManager,
     },
     messages::MessagesSerializer,
-    sig_verifier::verify_sigs_batch,
+    sig_verifier::SigVerifierPool,
     wrap_network::ActiveConnectionsTrait,
 };
 use crossbeam::{
-    channel::{at, Receiver, Sender},
+    channel::{at, never, tick, Receiver, Sender},
     select,
 };
-use lru::LruCache;
 use massa_consensus_exports::ConsensusController;
 use massa_hash::{Hash, HASH_SIZE_BYTES};
 use massa_logging::massa_trace;
 use massa_models::{
-    block::{Block, BlockSerializer},
+    block::{Block, BlockGraphStatus, BlockSerializer},
     block_header::SecuredHeader,
     block_id::BlockId,
     endorsement::SecureShareEndorsement,
     operation::{OperationId, SecureShareOperation},
     prehash::{CapacityAllocator, PreHashMap, PreHashSet},
     secure_share::{Id, SecureShare},
+    slot::Slot,
+    timeslots::get_block_slot_timestamp,
 };
 use massa_pool_exports::PoolController;
-use massa_protocol_exports::{ProtocolConfig, ProtocolError};
+use massa_protocol_exports::{
+    MisbehaviorKind, ProtocolConfig, ProtocolError, ProtocolEvent, ProtocolEventBus,
+};
 use massa_serialization::{DeserializeError, Deserializer, Serializer};
 use massa_storage::Storage;
-use massa_time::TimeError;
+use massa_time::{MassaTime, TimeError};
 use peernet::peer_id::PeerId;
 use tracing::{debug, info, warn};
 
@@ -46,6 +50,7 @@ use super::{
     cache::SharedBlockCache,
     commands_propagation::BlockHandlerPropagationCommand,
     commands_retrieval::BlockHandlerRetrievalCommand,
+    early_headers::EARLY_HEADERS_CHECK_INTERVAL,
     messages::{
         AskForBlocksInfo, BlockInfoReply, BlockMessage, BlockMessageDeserializer,
         BlockMessageDeserializerArgs,
@@ -67,6 +72,8 @@ pub(crate) struct BlockInfo {
     pub(crate) storage: Storage,
     /// Full operations size in bytes
     pub(crate) operations_size: usize,
+    /// Missing operations asked to each peer, the body being retrieved in chunks from several peers
+    pub(crate) asked_operations: HashMap<PeerId, PreHashSet<OperationId>>,
 }
 
 impl BlockInfo {
@@ -76,6 +83,7 @@ impl BlockInfo {
             operation_ids: None,
             storage,
             operations_size: 0,
+            asked_operations: HashMap::new(),
         }
     }
 }
@@ -85,19 +93,28 @@ pub struct RetrievalThread {
     consensus_controller: Box<dyn ConsensusController>,
     pool_controller: Box<dyn PoolController>,
     receiver_network: Receiver<PeerMessageTuple>,
-    _internal_sender: Sender<BlockHandlerPropagationCommand>,
+    internal_sender: Sender<BlockHandlerPropagationCommand>,
     receiver: Receiver<BlockHandlerRetrievalCommand>,
     block_message_serializer: MessagesSerializer,
     block_wishlist: PreHashMap<BlockId, BlockInfo>,
     asked_blocks: HashMap<PeerId, PreHashMap<BlockId, Instant>>,
+    /// requests sent for the blocks of the wishlist, to spot the peers that don't answer
+    block_requests: RequestManager<BlockId>,
+    slot_ranges_served: HashMap<PeerId, Instant>,
     peer_cmd_sender: Sender<PeerManagementCmd>,
     sender_propagation_ops: Sender<OperationHandlerPropagationCommand>,
     endorsement_cache: SharedEndorsementCache,
     operation_cache: SharedOperationCache,
     next_timer_ask_block: Instant,
+    /// ticks while the early headers are buffered
+    early_headers_timer: Receiver<Instant>,
     cache: SharedBlockCache,
     config: ProtocolConfig,
     storage: Storage,
+    sig_verifier: SigVerifierPool,
+    event_bus: ProtocolEventBus,
+    /// statuses of the peers, to ask the up-to-date ones first
+    peer_db: SharedPeerDB,
 }
 
 impl RetrievalThread {
@@ -128,6 +145,9 @@ impl RetrievalThread {
                                 Ok((rest, message)) => (rest, message),
                                 Err(err) => {
                                     warn!("Error in deserializing block message: {:?}", err);
+                                    if let Err(err) = self.report_misbehavior(&peer_id, MisbehaviorKind::MalformedMessage, format!("block message {}: {}", message_id, err), false) {
+                                        warn!("Error while reporting peer {} err: {:?}", peer_id, err);
+                                    }
                                     continue;
                                 }
                             };
@@ -142,9 +162,14 @@ impl RetrievalThread {
                                         warn!("Error in on_asked_for_blocks_received: {:?}", err);
                                     }
                                 }
+                                BlockMessage::AskBlocksBySlotRange(start, end) => {
+                                    debug!("Received block message: AskBlocksBySlotRange from {}", peer_id);
+                                    self.on_asked_for_blocks_by_slot_range_received(peer_id.clone(), start, end);
+                                }
                                 BlockMessage::ReplyForBlocks(block_infos) => {
                                     debug!("Received block message: ReplyForBlocks from {}", peer_id);
                                     for (block_id, block_info) in block_infos.into_iter() {
+                                        self.block_requests.on_answer(&peer_id, &block_id);
                                         if let Err(err) = self.on_block_info_received(peer_id.clone(), block_id, block_info) {
                                             warn!("Error in on_block_info_received: {:?}", err);
                                         }
@@ -156,12 +181,13 @@ impl RetrievalThread {
                                 BlockMessage::BlockHeader(header) => {
                                     debug!("Received block message: BlockHeader from {}", peer_id);
                                     massa_trace!(BLOCK_HEADER, { "peer_id": peer_id, "header": header});
+                                    self.block_requests.on_answer(&peer_id, &header.id);
                                     if let Ok(Some((block_id, is_new))) =
                                         self.note_header_from_peer(&header, &peer_id)
                                     {
                                         if is_new {
-                                            self.consensus_controller
-                                                .register_block_header(block_id, header);
+                                            self.relay_header(&header);
+                                            self.register_header(block_id, header);
                                         }
                                         if let Err(err) = self.update_ask_block() {
                                             warn!("Error in update_ask_blocks: {:?}", err);
@@ -173,7 +199,7 @@ impl RetrievalThread {
                                             or a loss of sync between us and the remote peer",
                                             peer_id,
                                         );
-                                        if let Err(err) = self.ban_node(&peer_id) {
+                                        if let Err(err) = self.ban_node(&peer_id, MisbehaviorKind::ProtocolViolation, format!("incorrect header {}", header.id)) {
                                             warn!("Error while banning peer {} err: {:?}", peer_id, err);
                                         }
                                     }
@@ -214,6 +240,11 @@ impl RetrievalThread {
                                         {}
                                     );
                                 },
+                                BlockHandlerRetrievalCommand::UpdateConfig(config) => {
+                                    debug!("Received block message: command UpdateConfig");
+                                    self.block_requests.set_timeout(config.ask_block_timeout.to_duration());
+                                    self.config = *config;
+                                }
                                 BlockHandlerRetrievalCommand::Stop => {
                                     debug!("Received block message: command Stop");
                                     info!("Stop block retrieval thread from command receiver");
@@ -232,6 +263,9 @@ impl RetrievalThread {
                         warn!("Error in ask_blocks: {:?}", err);
                     }
                 }
+                recv(self.early_headers_timer) -> _ => {
+                    self.release_early_headers(None);
+                }
             }
         }
     }
@@ -298,15 +332,10 @@ impl RetrievalThread {
         // Clean shared cache if peers do not exist anymore
         {
             let mut cache_write = self.cache.write();
-            let peers: Vec<PeerId> = cache_write
-                .blocks_known_by_peer
-                .iter()
-                .map(|(id, _)| id.clone())
-                .collect();
             let connected_peers = self.active_connections.get_peer_ids_connected();
-            for peer_id in peers {
+            for peer_id in cache_write.blocks_known_by_peer.peer_ids() {
                 if !connected_peers.contains(&peer_id) {
-                    cache_write.blocks_known_by_peer.pop(&peer_id);
+                    cache_write.blocks_known_by_peer.remove_peer(&peer_id);
                     self.asked_blocks.remove(&peer_id);
                 }
             }
@@ -321,7 +350,6 @@ impl RetrievalThread {
                 &from_peer_id,
                 &self.block_message_serializer,
                 BlockMessage::ReplyForBlocks(sub_list.to_vec()).into(),
-                true,
             ) {
                 warn!(
                     "Error while sending reply for blocks to {}: {:?}",
@@ -332,6 +360,84 @@ impl RetrievalThread {
         Ok(())
     }
 
+    /// Network ask the local node for the blocks of a slot range
+    ///
+    /// Lets a peer that fell slightly behind catch up without bootstrapping: the headers of the
+    /// blocks we have in storage for the asked slots are sent as regular block headers, and the
+    /// peer retrieves their content with the usual per-block-id ask flow.
+    /// The range is truncated to `max_blocks_slot_range` slots and each peer is served at most
+    /// once every `blocks_slot_range_min_interval`.
+    fn on_asked_for_blocks_by_slot_range_received(
+        &mut self,
+        from_peer_id: PeerId,
+        start: Slot,
+        end: Slot,
+    ) {
+        let now = Instant::now();
+        let min_interval = self.config.blocks_slot_range_min_interval.to_duration();
+        self.slot_ranges_served
+            .retain(|_, served_at| now.saturating_duration_since(*served_at) < min_interval);
+        if self.slot_ranges_served.contains_key(&from_peer_id) {
+            debug!(
+                "Ignore blocks by slot range asked too often by {}",
+                from_peer_id
+            );
+            return;
+        }
+        self.slot_ranges_served.insert(from_peer_id.clone(), now);
+
+        let headers: Vec<SecuredHeader> = {
+            let blocks = self.storage.read_blocks();
+            let mut headers = Vec::new();
+            let mut slot = start;
+            for _ in 0..self.config.max_blocks_slot_range {
+                if slot > end {
+                    break;
+                }
+                if let Some(block_ids) = blocks.get_blocks_by_slot(&slot) {
+                    headers.extend(
+                        block_ids
+                            .iter()
+                            .filter_map(|block_id| blocks.get(block_id))
+                            .map(|block| block.content.header.clone()),
+                    );
+                }
+                slot = match slot.get_next_slot(self.config.thread_count) {
+                    Ok(slot) => slot,
+                    Err(_) => break,
+                };
+            }
+            headers
+        };
+        debug!(
+            "Send {} headers of blocks between slots {} and {} to {}",
+            headers.len(),
+            start,
+            end,
+            from_peer_id
+        );
+
+        // Mark the node as knowing the blocks we send.
+        {
+            let block_ids: Vec<BlockId> = headers.iter().map(|header| header.id).collect();
+            let mut cache_write = self.cache.write();
+            cache_write.insert_blocks_known(&from_peer_id, &block_ids, true, now);
+        }
+        for header in headers {
+            if let Err(err) = self.active_connections.send_to_peer(
+                &from_peer_id,
+                &self.block_message_serializer,
+                BlockMessage::BlockHeader(header).into(),
+            ) {
+                warn!(
+                    "Error while sending blocks by slot range to {}: {:?}",
+                    from_peer_id, err
+                );
+                return;
+            }
+        }
+    }
+
     fn on_block_info_received(
         &mut self,
         from_peer_id: PeerId,
@@ -366,6 +472,12 @@ impl RetrievalThread {
                         Instant::now(),
                     );
                 }
+                // the operations asked to this peer can be asked to another one right away
+                if let Some(info) = self.block_wishlist.get_mut(&block_id) && info.asked_operations.remove(&from_peer_id).is_some() {
+                    if let Some(asked_blocks) = self.asked_blocks.get_mut(&from_peer_id) {
+                        asked_blocks.remove(&block_id);
+                    }
+                }
                 Ok(())
             }
         }
@@ -404,17 +516,37 @@ impl RetrievalThread {
                 return Ok(());
             }
         }
-        if let Err(err) = self.note_header_from_peer(&header, &from_peer_id) {
-            warn!(
-                "peer {} sent us critically incorrect header through protocol, \
-                which may be an attack attempt by the remote node \
-                or a loss of sync between us and the remote node. Err = {}",
-                from_peer_id, err
-            );
-            if let Err(err) = self.ban_node(&from_peer_id) {
-                warn!("Error while banning peer {} err: {:?}", from_peer_id, err);
+        match self.note_header_from_peer(&header, &from_peer_id) {
+            Ok(Some((_, true))) => {
+                self.peer_db
+                    .traffic()
+                    .record_block(&from_peer_id, block_id, header.content.slot);
+                if self.event_bus.is_listened() {
+                    self.event_bus.publish(ProtocolEvent::BlockHeaderReceived {
+                        block_id,
+                        slot: header.content.slot,
+                        peer_id: from_peer_id.clone(),
+                    });
+                }
+                self.relay_header(&header)
+            }
+            Ok(_) => {}
+            Err(err) => {
+                warn!(
+                    "peer {} sent us critically incorrect header through protocol, \
+                    which may be an attack attempt by the remote node \
+                    or a loss of sync between us and the remote node. Err = {}",
+                    from_peer_id, err
+                );
+                if let Err(err) = self.ban_node(
+                    &from_peer_id,
+                    MisbehaviorKind::from_error(&err),
+                    format!("header {}: {}", header.id, err),
+                ) {
+                    warn!("Error while banning peer {} err: {:?}", from_peer_id, err);
+                }
+                return Ok(());
             }
-            return Ok(());
         };
         if let Some(info) = self.block_wishlist.get_mut(&block_id) {
             info.header = Some(header);
@@ -471,20 +603,13 @@ impl RetrievalThread {
                     true,
                     Instant::now(),
                 );
-                {
-                    let mut endorsement_cache_write = self.endorsement_cache.write();
-                    let endorsement_ids = endorsement_cache_write
-                        .endorsements_known_by_peer
-                        .get_or_insert_mut(from_peer_id.clone(), || {
-                            LruCache::new(
-                                NonZeroUsize::new(self.config.max_node_known_blocks_size)
-                                    .expect("max_node_known_blocks_size in config must be > 0"),
-                            )
-                        });
-                    for endorsement_id in block_header.content.endorsements.iter().map(|e| e.id) {
-                        endorsement_ids.put(endorsement_id, ());
-                    }
-                }
+                self.endorsement_cache
+                    .write()
+                    .endorsements_known_by_peer
+                    .insert_many(
+                        from_peer_id,
+                        block_header.content.endorsements.iter().map(|e| (e.id, ())),
+                    );
                 return Ok(Some((block_id, false)));
             }
         }
@@ -537,33 +662,179 @@ impl RetrievalThread {
                 true,
                 Instant::now(),
             );
-            {
-                let mut endorsement_cache_write = self.endorsement_cache.write();
-                let endorsement_ids = endorsement_cache_write
-                    .endorsements_known_by_peer
-                    .get_or_insert_mut(from_peer_id.clone(), || {
-                        LruCache::new(
-                            NonZeroUsize::new(self.config.max_node_known_blocks_size)
-                                .expect("max_node_known_blocks_size in config must be > 0"),
-                        )
-                    });
-                for endorsement_id in header.content.endorsements.iter().map(|e| e.id) {
-                    endorsement_ids.put(endorsement_id, ());
-                }
-            }
+            self.endorsement_cache
+                .write()
+                .endorsements_known_by_peer
+                .insert_many(
+                    from_peer_id,
+                    header.content.endorsements.iter().map(|e| (e.id, ())),
+                );
         }
         massa_trace!("protocol.protocol_worker.note_header_from_node.ok", { "node": from_peer_id, "block_id": block_id, "header": header});
         Ok(Some((block_id, true)))
     }
 
+    /// Ban the connected peers that left too many requests in a row unanswered
+    fn ban_unresponsive_peers(&mut self) {
+        let unresponsive = self.block_requests.take_unresponsive_peers();
+        if unresponsive.is_empty() {
+            return;
+        }
+        let peers_connected = self.active_connections.get_peer_ids_connected();
+        for peer_id in unresponsive {
+            if !peers_connected.contains(&peer_id) {
+                continue;
+            }
+            warn!("Peer {} doesn't answer our block requests", peer_id);
+            if let Err(err) = self.ban_node(
+                &peer_id,
+                MisbehaviorKind::ProtocolViolation,
+                "block requests left unanswered".to_string(),
+            ) {
+                warn!("Error while banning peer {} err: {:?}", peer_id, err);
+            }
+        }
+    }
+
     /// send a ban peer command to the peer handler
-    fn ban_node(&mut self, peer_id: &PeerId) -> Result<(), ProtocolError> {
+    fn ban_node(
+        &mut self,
+        peer_id: &PeerId,
+        kind: MisbehaviorKind,
+        evidence: String,
+    ) -> Result<(), ProtocolError> {
         massa_trace!("ban node from retrieval thread", { "peer_id": peer_id.to_string() });
+        self.report_misbehavior(peer_id, kind, evidence, true)
+    }
+
+    /// send an offense of a peer to the misbehavior ledger of the peer handler, which bans
+    /// the peer if `ban` is set or if it committed too many offenses
+    fn report_misbehavior(
+        &mut self,
+        peer_id: &PeerId,
+        kind: MisbehaviorKind,
+        evidence: String,
+        ban: bool,
+    ) -> Result<(), ProtocolError> {
         self.peer_cmd_sender
-            .send(PeerManagementCmd::Ban(vec![peer_id.clone()]))
+            .send(PeerManagementCmd::ReportMisbehavior {
+                peer_id: peer_id.clone(),
+                kind,
+                evidence,
+                ban,
+            })
             .map_err(|err| ProtocolError::SendError(err.to_string()))
     }
 
+    /// Relay the header of a new block that passed the checks without waiting for its integration
+    fn relay_header(&self, header: &SecuredHeader) {
+        if !self.config.relay_headers_before_integration {
+            return;
+        }
+        if let Err(err) = self
+            .internal_sender
+            .send(BlockHandlerPropagationCommand::RelayHeader(header.clone()))
+        {
+            warn!(
+                "Error while relaying header of block {}: {}",
+                header.id, err
+            );
+        }
+    }
+
+    /// Hands a new header to consensus, or buffers it while its parents are unknown to consensus
+    /// or its slot didn't start yet
+    fn register_header(&mut self, block_id: BlockId, header: SecuredHeader) {
+        if !self.cache.read().early_headers.is_enabled() {
+            self.consensus_controller
+                .register_block_header(block_id, header);
+            return;
+        }
+        let now = match MassaTime::now() {
+            Ok(now) => now,
+            Err(err) => {
+                warn!("Error while buffering header {}: {}", block_id, err);
+                self.consensus_controller
+                    .register_block_header(block_id, header);
+                return;
+            }
+        };
+        let slot_timestamp = get_block_slot_timestamp(
+            self.config.thread_count,
+            self.config.t0,
+            self.config.genesis_timestamp,
+            header.content.slot,
+        )
+        .unwrap_or(now);
+        // headers too far in the future are consensus' business
+        let early = slot_timestamp > now
+            && slot_timestamp.saturating_sub(now) <= self.config.early_header_max_advance;
+        let parents = &header.content.parents;
+        let missing_parents: PreHashSet<BlockId> = parents
+            .iter()
+            .zip(self.consensus_controller.get_block_statuses(parents))
+            .filter(|(_, status)| matches!(status, BlockGraphStatus::NotFound))
+            .map(|(parent, _)| *parent)
+            .collect();
+        if !early && missing_parents.is_empty() {
+            self.consensus_controller
+                .register_block_header(block_id, header);
+            self.release_early_headers(Some(block_id));
+            return;
+        }
+        debug!(
+            "buffering header {} ({} missing parents, early: {})",
+            block_id,
+            missing_parents.len(),
+            early
+        );
+        let evicted = self.cache.write().early_headers.insert(
+            header,
+            missing_parents,
+            slot_timestamp,
+            now.saturating_add(self.config.early_header_expiry),
+        );
+        if let Some(evicted) = evicted {
+            self.consensus_controller
+                .register_block_header(evicted.id, evicted);
+        }
+    }
+
+    /// Hands to consensus the buffered headers whose parents are registered and whose slot
+    /// started, and the ones that waited too long. `registered` is a block that was just
+    /// registered in consensus.
+    fn release_early_headers(&mut self, registered: Option<BlockId>) {
+        if !self.cache.read().early_headers.is_enabled() {
+            return;
+        }
+        let now = match MassaTime::now() {
+            Ok(now) => now,
+            Err(err) => {
+                warn!("Error while releasing the early headers: {}", err);
+                return;
+            }
+        };
+        let mut registered: Vec<BlockId> = registered.into_iter().collect();
+        loop {
+            let ready = {
+                let mut cache_write = self.cache.write();
+                for block_id in registered.drain(..) {
+                    cache_write.early_headers.parent_registered(&block_id);
+                }
+                cache_write.early_headers.take_ready(now)
+            };
+            if ready.is_empty() {
+                return;
+            }
+            // the released headers may be the parents of other buffered headers
+            for header in ready {
+                registered.push(header.id);
+                self.consensus_controller
+                    .register_block_header(header.id, header);
+            }
+        }
+    }
+
     /// Remove the given blocks from the local wishlist
     pub(crate) fn remove_asked_blocks_of_node(&mut self, remove_hashes: &PreHashSet<BlockId>) {
         massa_trace!("protocol.protocol_worker.remove_asked_blocks_of_node", {
@@ -572,6 +843,9 @@ impl RetrievalThread {
         for asked_blocks in self.asked_blocks.values_mut() {
             asked_blocks.retain(|h, _| !remove_hashes.contains(h));
         }
+        for block_id in remove_hashes {
+            self.block_requests.forget(block_id);
+        }
     }
 
     /// Note endorsements coming from a given node,
@@ -604,10 +878,10 @@ impl RetrievalThread {
             }
         }
 
-        // Batch signature verification
-        // optimized signature verification
-        verify_sigs_batch(
-            &new_endorsements
+        // Batch signature verification, in turn with the other peers
+        self.sig_verifier.verify_blocking(
+            from_peer_id,
+            new_endorsements
                 .values()
                 .map(|endorsement| {
                     (
@@ -616,7 +890,7 @@ impl RetrievalThread {
                         endorsement.content_creator_pub_key,
                     )
                 })
-                .collect::<Vec<_>>(),
+                .collect(),
         )?;
 
         {
@@ -626,18 +900,12 @@ impl RetrievalThread {
                 cache_write.checked_endorsements.put(*endorsement_id, ());
             }
             // add to known endorsements for source node.
-            let endorsements = cache_write.endorsements_known_by_peer.get_or_insert_mut(
-                from_peer_id.clone(),
-                || {
-                    LruCache::new(
-                        NonZeroUsize::new(self.config.max_node_known_endorsements_size)
-                            .expect("max_node_known_endorsements_size in config should be > 0"),
-                    )
-                },
+            cache_write.endorsements_known_by_peer.insert_many(
+                from_peer_id,
+                endorsement_ids
+                    .iter()
+                    .map(|endorsement_id| (*endorsement_id, ())),
             );
-            for endorsement_id in endorsement_ids.iter() {
-                endorsements.put(*endorsement_id, ());
-            }
         }
 
         if !new_endorsements.is_empty() {
@@ -676,21 +944,10 @@ impl RetrievalThread {
         let operation_ids_set: PreHashSet<OperationId> = operation_ids.iter().cloned().collect();
 
         // add to known ops
-        {
-            let mut cache_write = self.operation_cache.write();
-            let known_ops =
-                cache_write
-                    .ops_known_by_peer
-                    .get_or_insert_mut(from_peer_id.clone(), || {
-                        LruCache::new(
-                            NonZeroUsize::new(self.config.max_node_known_ops_size)
-                                .expect("max_node_known_ops_size in config should be > 0"),
-                        )
-                    });
-            for op_id in operation_ids_set.iter() {
-                known_ops.put(op_id.prefix(), ());
-            }
-        }
+        self.operation_cache.write().ops_known_by_peer.insert_many(
+            &from_peer_id,
+            operation_ids_set.iter().map(|op_id| (op_id.prefix(), ())),
+        );
         let info = if let Some(info) = self.block_wishlist.get_mut(&block_id) {
             info
         } else {
@@ -745,7 +1002,11 @@ impl RetrievalThread {
         if header.content.operation_merkle_root == Hash::compute_from(&total_hash) {
             if operation_ids.len() > self.config.max_operations_per_block as usize {
                 warn!("Peer id {} sent us an operations list for block id {} that contains more operations than the max allowed for a block.", from_peer_id, block_id);
-                if let Err(err) = self.ban_node(&from_peer_id) {
+                if let Err(err) = self.ban_node(
+                    &from_peer_id,
+                    MisbehaviorKind::ProtocolViolation,
+                    format!("too many operations in block {}", block_id),
+                ) {
                     warn!("Error while banning peer {} err: {:?}", from_peer_id, err);
                 }
                 return Ok(());
@@ -769,7 +1030,11 @@ impl RetrievalThread {
 
             if info.operations_size > self.config.max_serialized_operations_size_per_block {
                 warn!("Peer id {} sent us a operation list for block id {} but the operations we already have in our records exceed max size.", from_peer_id, block_id);
-                if let Err(err) = self.ban_node(&from_peer_id) {
+                if let Err(err) = self.ban_node(
+                    &from_peer_id,
+                    MisbehaviorKind::ProtocolViolation,
+                    format!("operations of block {} exceed the max size", block_id),
+                ) {
                     warn!("Error while banning peer {} err: {:?}", from_peer_id, err);
                 }
                 return Ok(());
@@ -790,7 +1055,14 @@ impl RetrievalThread {
             }
         } else {
             warn!("Peer id {} sent us a operation list for block id {} but the hash in header doesn't match.", from_peer_id, block_id);
-            if let Err(err) = self.ban_node(&from_peer_id) {
+            if let Err(err) = self.ban_node(
+                &from_peer_id,
+                MisbehaviorKind::ProtocolViolation,
+                format!(
+                    "operation list not matching the header of block {}",
+                    block_id
+                ),
+            ) {
                 warn!("Error while banning peer {} err: {:?}", from_peer_id, err);
             }
         }
@@ -835,7 +1107,11 @@ impl RetrievalThread {
                 "Peer id {} sent us operations for block id {} but they failed at verifications. Err = {}",
                 from_peer_id, block_id, err
             );
-            if let Err(err) = self.ban_node(&from_peer_id) {
+            if let Err(err) = self.ban_node(
+                &from_peer_id,
+                MisbehaviorKind::from_error(&err),
+                format!("operations of block {}: {}", block_id, err),
+            ) {
                 warn!("Error while banning peer {} err: {:?}", from_peer_id, err);
             }
             return Ok(());
@@ -874,6 +1150,7 @@ impl RetrievalThread {
                 info.storage.store_operations(operations);
                 let block_ids_set = block_operation_ids.clone().into_iter().collect();
                 let known_operations = info.storage.claim_operation_refs(&block_ids_set);
+                let asked_chunk = info.asked_operations.remove(&from_peer_id);
 
                 // Ban the node if:
                 // - mismatch with asked operations (asked operations are the one that are not in storage) + operations already in storage and block operations
@@ -887,7 +1164,11 @@ impl RetrievalThread {
                 };
                 if full_op_size > self.config.max_serialized_operations_size_per_block {
                     warn!("Peer id {} sent us full operations for block id {} but they exceed max size.", from_peer_id, block_id);
-                    if let Err(err) = self.ban_node(&from_peer_id) {
+                    if let Err(err) = self.ban_node(
+                        &from_peer_id,
+                        MisbehaviorKind::ProtocolViolation,
+                        format!("full operations of block {} exceed the max size", block_id),
+                    ) {
                         warn!("Error while banning peer {} err: {:?}", from_peer_id, err);
                     }
                     self.block_wishlist.remove(&block_id);
@@ -895,6 +1176,13 @@ impl RetrievalThread {
                         .mark_invalid_block(block_id, header);
                 } else {
                     if known_operations != block_ids_set {
+                        // the peer sent its chunk of the operations, the others are still expected from other peers
+                        if let Some(asked_chunk) = asked_chunk && asked_chunk.is_subset(&known_operations) {
+                            if let Some(asked_blocks) = self.asked_blocks.get_mut(&from_peer_id) {
+                                asked_blocks.remove(&block_id);
+                            }
+                            return Ok(());
+                        }
                         warn!(
                             "Peer id {} didn't sent us all the full operations for block id {}.",
                             from_peer_id, block_id
@@ -945,6 +1233,7 @@ impl RetrievalThread {
                     // Send to consensus
                     self.consensus_controller
                         .register_block(block_id, slot, block_storage, false);
+                    self.release_early_headers(Some(block_id));
                 }
             }
             Entry::Vacant(_) => {
@@ -996,12 +1285,13 @@ impl RetrievalThread {
                 new_operations.insert(operation_id, operation);
             };
         }
-        // optimized signature verification
-        verify_sigs_batch(
-            &new_operations
+        // optimized signature verification, in turn with the other peers
+        self.sig_verifier.verify_blocking(
+            source_peer_id,
+            new_operations
                 .iter()
                 .map(|(op_id, op)| (*op_id.get_hash(), op.signature, op.content_creator_pub_key))
-                .collect::<Vec<_>>(),
+                .collect(),
         )?;
         {
             // add to checked operations
@@ -1027,6 +1317,10 @@ impl RetrievalThread {
             .checked_add(self.config.ask_block_timeout.into())
             .ok_or(TimeError::TimeOverflowError)?;
 
+        // the asks that timed out are handled below, only the peers that never answer matter here
+        self.block_requests.expire(now);
+        self.ban_unresponsive_peers();
+
         // list blocks to re-ask and gather candidate nodes to ask from
         let mut candidate_nodes: PreHashMap<BlockId, Vec<_>> = Default::default();
         let mut ask_block_list: HashMap<PeerId, Vec<(BlockId, AskForBlocksInfo)>> =
@@ -1055,54 +1349,33 @@ impl RetrievalThread {
                     )
                 };
                 let mut needs_ask = true;
-                // Clean old peers that aren't active anymore
+                // Clean old peers that aren't active anymore and add the new ones
                 let peers_connected: HashSet<PeerId> =
                     self.active_connections.get_peer_ids_connected();
-                let peers_in_cache: Vec<PeerId> = cache_write
+                //TODO: Change to detect the connection before
+                cache_write
                     .blocks_known_by_peer
-                    .iter()
-                    .map(|(peer_id, _)| peer_id.clone())
-                    .collect();
-                for peer_id in peers_in_cache {
-                    if !peers_connected.contains(&peer_id) {
-                        cache_write.blocks_known_by_peer.pop(&peer_id);
-                    }
-                }
+                    .sync_peers(&peers_connected);
                 let peers_in_asked_blocks: Vec<PeerId> =
                     self.asked_blocks.keys().cloned().collect();
                 for peer_id in peers_in_asked_blocks {
                     if !peers_connected.contains(&peer_id) {
                         self.asked_blocks.remove(&peer_id);
+                        self.block_requests.remove_peer(&peer_id);
                     }
                 }
                 // Add new peers
                 for peer_id in peers_connected {
-                    if !cache_write.blocks_known_by_peer.contains(&peer_id) {
-                        //TODO: Change to detect the connection before
-                        cache_write.blocks_known_by_peer.put(
-                            peer_id.clone(),
-                            (
-                                LruCache::new(
-                                    NonZeroUsize::new(self.config.max_node_known_blocks_size)
-                                        .expect("max_node_known_blocks_size in config must be > 0"),
-                                ),
-                                Instant::now(),
-                            ),
-                        );
-                    } else {
-                        cache_write.blocks_known_by_peer.promote(&peer_id);
-                    }
-
                     if !self.asked_blocks.contains_key(&peer_id) {
                         self.asked_blocks
                             .insert(peer_id.clone(), PreHashMap::default());
                     }
                 }
-                for (peer_id, (blocks_known, _)) in cache_write.blocks_known_by_peer.iter_mut() {
+                for peer_id in cache_write.blocks_known_by_peer.peer_ids() {
                     // map to remove the borrow on asked_blocks. Otherwise can't call insert_known_blocks
                     let ask_time_opt = self
                         .asked_blocks
-                        .get(peer_id)
+                        .get(&peer_id)
                         .and_then(|asked_blocks| asked_blocks.get(hash).copied());
                     let (timeout_at_opt, timed_out) = if let Some(ask_time) = ask_time_opt {
                         let t = ask_time
@@ -1112,7 +1385,10 @@ impl RetrievalThread {
                     } else {
                         (None, false)
                     };
-                    let knows_block = blocks_known.get(hash);
+                    let knows_block = cache_write
+                        .blocks_known_by_peer
+                        .get(&peer_id, hash)
+                        .copied();
 
                     // check if the peer recently told us it doesn't have the block
                     if let Some((false, info_time)) = knows_block {
@@ -1135,14 +1411,23 @@ impl RetrievalThread {
                         // not timed out yet (note: recent DONTHAVBLOCK checked before the match)
                         (false, Some(timeout_at), _) => {
                             next_tick = std::cmp::min(next_tick, timeout_at);
-                            needs_ask = false; // no need to re ask
+                            // operations are asked in chunks: the ones not asked yet can still be asked to other peers
+                            if self.config.max_block_body_retrieval_peers <= 1
+                                || !matches!(required_info, AskForBlocksInfo::Operations(_))
+                            {
+                                needs_ask = false; // no need to re ask
+                            }
                             continue; // not a candidate
                         }
                         // timed out, supposed to have it
                         (true, Some(timeout_at), Some((true, info_time))) => {
-                            if info_time < &timeout_at {
+                            if info_time < timeout_at {
                                 // info less recent than timeout: mark as not having it
-                                blocks_known.put(*hash, (false, timeout_at));
+                                cache_write.blocks_known_by_peer.insert(
+                                    &peer_id,
+                                    *hash,
+                                    (false, timeout_at),
+                                );
                                 (2u8, ask_time_opt)
                             } else {
                                 // told us it has it after a timeout: good candidate again
@@ -1151,15 +1436,23 @@ impl RetrievalThread {
                         }
                         // timed out, supposed to not have it
                         (true, Some(timeout_at), Some((false, info_time))) => {
-                            if info_time < &timeout_at {
+                            if info_time < timeout_at {
                                 // info less recent than timeout: update info time
-                                blocks_known.put(*hash, (false, timeout_at));
+                                cache_write.blocks_known_by_peer.insert(
+                                    &peer_id,
+                                    *hash,
+                                    (false, timeout_at),
+                                );
                             }
                             (2u8, ask_time_opt)
                         }
                         // timed out but don't know if has it: mark as not having it
                         (true, Some(timeout_at), None) => {
-                            blocks_known.put(*hash, (false, timeout_at));
+                            cache_write.blocks_known_by_peer.insert(
+                                &peer_id,
+                                *hash,
+                                (false, timeout_at),
+                            );
                             (2u8, ask_time_opt)
                         }
                     };
@@ -1167,7 +1460,7 @@ impl RetrievalThread {
                     // add candidate peer
                     candidate_nodes.entry(*hash).or_insert_with(Vec::new).push((
                         candidate,
-                        peer_id.clone(),
+                        peer_id,
                         required_info.clone(),
                     ));
                 }
@@ -1199,9 +1492,22 @@ impl RetrievalThread {
             .collect();
         {
             let cache_read = self.cache.read();
+            let peer_db = self.peer_db.snapshot();
+            // the peers on another fork or stuck behind us are asked last
+            let status_rank = |peer_id: &PeerId| -> u8 {
+                match peer_db
+                    .peers
+                    .get(peer_id)
+                    .and_then(|info| info.status.as_ref())
+                {
+                    Some(status) if status.is_up_to_date() => 0,
+                    None => 1,
+                    Some(_) => 2,
+                }
+            };
             for (hash, criteria) in candidate_nodes.into_iter() {
-                // find the best node
-                if let Some((_knowledge, best_node, required_info, _)) = criteria
+                // order the nodes from the best to the worst
+                let mut candidates: Vec<_> = criteria
                     .into_iter()
                     .filter_map(|(knowledge, peer_id, required_info)| {
                         // filter out nodes with too many active block requests
@@ -1210,35 +1516,100 @@ impl RetrievalThread {
                         {
                             cache_read
                                 .blocks_known_by_peer
-                                .peek(&peer_id)
-                                .map(|peer_data| (knowledge, peer_id, required_info, peer_data.1))
+                                .peer_tracked_since(&peer_id)
+                                .map(|since| (knowledge, peer_id, required_info, since))
                         } else {
                             None
                         }
                     })
-                    .min_by_key(|(knowledge, peer_id, _, instant)| {
-                        (
-                            *knowledge,                                         // block knowledge
-                            *active_block_req_count.get(peer_id).unwrap_or(&0), // active requests
-                            *instant,                                           // node age
-                            peer_id.clone(),                                    // node ID
-                        )
-                    })
-                {
-                    let asked_blocks = self.asked_blocks.get_mut(&best_node).unwrap(); // will not panic, already checked
+                    .collect();
+                candidates.sort_by_key(|(knowledge, peer_id, _, instant)| {
+                    (
+                        *knowledge,                                         // block knowledge
+                        status_rank(peer_id),                               // head of the chain
+                        *active_block_req_count.get(peer_id).unwrap_or(&0), // active requests
+                        *instant,                                           // node age
+                        peer_id.clone(),                                    // node ID
+                    )
+                });
+                let Some((_, _, required_info, _)) = candidates.first() else {
+                    continue;
+                };
+
+                let asks: Vec<(PeerId, AskForBlocksInfo)> = match required_info {
+                    AskForBlocksInfo::Operations(missing_operations) => {
+                        let Some(info) = self.block_wishlist.get_mut(&hash) else {
+                            continue;
+                        };
+                        // keep the chunks whose ask didn't time out
+                        info.asked_operations.retain(|peer_id, _| {
+                            self.asked_blocks
+                                .get(peer_id)
+                                .and_then(|asked_blocks| asked_blocks.get(&hash))
+                                .and_then(|ask_time| {
+                                    ask_time.checked_add(self.config.ask_block_timeout.into())
+                                })
+                                .map_or(false, |timeout_at| timeout_at > now)
+                        });
+                        let to_ask: Vec<OperationId> = missing_operations
+                            .iter()
+                            .filter(|id| {
+                                !info
+                                    .asked_operations
+                                    .values()
+                                    .any(|chunk| chunk.contains(id))
+                            })
+                            .copied()
+                            .collect();
+                        let free_peers = self
+                            .config
+                            .max_block_body_retrieval_peers
+                            .max(1)
+                            .saturating_sub(info.asked_operations.len());
+                        if (to_ask.is_empty() && !missing_operations.is_empty()) || free_peers == 0
+                        {
+                            continue;
+                        }
+                        // the best node, and the other nodes that are known to have the block
+                        let peers: Vec<PeerId> = candidates
+                            .iter()
+                            .enumerate()
+                            .filter(|(index, (knowledge, ..))| *index == 0 || *knowledge == 0)
+                            .map(|(_, (_, peer_id, ..))| peer_id.clone())
+                            .take(free_peers.min(to_ask.len()).max(1))
+                            .collect();
+                        let chunk_size = (to_ask.len() + peers.len() - 1) / peers.len();
+                        let mut chunks = to_ask.chunks(chunk_size.max(1));
+                        peers
+                            .into_iter()
+                            .map(|peer_id| {
+                                let chunk = chunks.next().unwrap_or_default().to_vec();
+                                info.asked_operations
+                                    .insert(peer_id.clone(), chunk.iter().copied().collect());
+                                (peer_id, AskForBlocksInfo::Operations(chunk))
+                            })
+                            .collect()
+                    }
+                    _ => {
+                        let (_, best_node, required_info, _) = &candidates[0];
+                        vec![(best_node.clone(), required_info.clone())]
+                    }
+                };
+
+                let timeout_at = now
+                    .checked_add(self.config.ask_block_timeout.into())
+                    .ok_or(TimeError::TimeOverflowError)?;
+                for (peer_id, required_info) in asks {
+                    let asked_blocks = self.asked_blocks.get_mut(&peer_id).unwrap(); // will not panic, already checked
                     asked_blocks.insert(hash, now);
-                    if let Some(cnt) = active_block_req_count.get_mut(&best_node) {
+                    if let Some(cnt) = active_block_req_count.get_mut(&peer_id) {
                         *cnt += 1; // increase the number of actively asked blocks
                     }
 
                     ask_block_list
-                        .entry(best_node.clone())
+                        .entry(peer_id)
                         .or_insert_with(Vec::new)
-                        .push((hash, required_info.clone()));
-
-                    let timeout_at = now
-                        .checked_add(self.config.ask_block_timeout.into())
-                        .ok_or(TimeError::TimeOverflowError)?;
+                        .push((hash, required_info));
                     next_tick = std::cmp::min(next_tick, timeout_at);
                 }
             }
@@ -1249,11 +1620,15 @@ impl RetrievalThread {
             for (peer_id, list) in ask_block_list.iter() {
                 for sub_list in list.chunks(self.config.max_size_block_infos as usize) {
                     debug!("Send ask for blocks of len {} to {}", list.len(), peer_id);
+                    self.block_requests.send(
+                        peer_id,
+                        sub_list.iter().map(|(block_id, _)| *block_id),
+                        now,
+                    );
                     if let Err(err) = self.active_connections.send_to_peer(
                         peer_id,
                         &self.block_message_serializer,
                         BlockMessage::AskForBlocks(sub_list.to_vec()).into(),
-                        true,
                     ) {
                         warn!(
                             "Failed to send AskForBlocks to peer {} err: {}",
@@ -1276,7 +1651,7 @@ pub fn start_retrieval_thread(
     pool_controller: Box<dyn PoolController>,
     receiver_network: Receiver<PeerMessageTuple>,
     receiver: Receiver<BlockHandlerRetrievalCommand>,
-    _internal_sender: Sender<BlockHandlerPropagationCommand>,
+    internal_sender: Sender<BlockHandlerPropagationCommand>,
     sender_propagation_ops: Sender<OperationHandlerPropagationCommand>,
     peer_cmd_sender: Sender<PeerManagementCmd>,
     config: ProtocolConfig,
@@ -1284,6 +1659,9 @@ pub fn start_retrieval_thread(
     operation_cache: SharedOperationCache,
     cache: SharedBlockCache,
     storage: Storage,
+    sig_verifier: SigVerifierPool,
+    event_bus: ProtocolEventBus,
+    peer_db: SharedPeerDB,
 ) -> JoinHandle<()> {
     let block_message_serializer =
         MessagesSerializer::new().with_block_message_serializer(BlockMessageSerializer::new());
@@ -1295,19 +1673,35 @@ pub fn start_retrieval_thread(
                 consensus_controller,
                 pool_controller,
                 next_timer_ask_block: Instant::now() + config.ask_block_timeout.to_duration(),
+                early_headers_timer: if config.max_early_headers > 0 {
+                    tick(EARLY_HEADERS_CHECK_INTERVAL)
+                } else {
+                    never()
+                },
                 block_wishlist: PreHashMap::default(),
                 asked_blocks: HashMap::default(),
+                block_requests: RequestManager::new(
+                    NonZeroUsize::new(config.max_known_blocks_size)
+                        .expect("max_known_blocks_size in config must be > 0"),
+                    config.ask_block_timeout.to_duration(),
+                    usize::MAX,
+                    config.max_unanswered_requests,
+                ),
+                slot_ranges_served: HashMap::default(),
                 peer_cmd_sender,
                 sender_propagation_ops,
                 receiver_network,
                 block_message_serializer,
                 receiver,
-                _internal_sender,
+                internal_sender,
                 cache,
                 endorsement_cache,
                 operation_cache,
                 config,
                 storage,
+                sig_verifier,
+                event_bus,
+                peer_db,
             };
             retrieval_thread.run();
         })
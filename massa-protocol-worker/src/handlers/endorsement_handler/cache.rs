@@ -3,21 +3,36 @@ use std::{num::NonZeroUsize, sync::Arc};
 use lru::LruCache;
 use massa_models::endorsement::EndorsementId;
 use parking_lot::RwLock;
-use peernet::peer_id::PeerId;
+
+use crate::handlers::{known_items::KnownItemsCache, relayed_ids::SharedRelayedIdsCache};
+
+use super::dedup::EndorsementDedupWindow;
 
 pub struct EndorsementCache {
     pub checked_endorsements: LruCache<EndorsementId, ()>,
-    pub endorsements_known_by_peer: LruCache<PeerId, LruCache<EndorsementId, ()>>,
+    pub endorsements_known_by_peer: KnownItemsCache<EndorsementId>,
+    pub dedup_window: EndorsementDedupWindow,
+    /// Ids recently relayed by all the handlers
+    pub relayed_ids: SharedRelayedIdsCache,
 }
 
 impl EndorsementCache {
     pub fn new(
         max_known_endorsements: NonZeroUsize,
+        max_peers: NonZeroUsize,
         max_known_endorsements_by_peer: NonZeroUsize,
+        max_total_known_endorsements_by_peers: usize,
+        relayed_ids: SharedRelayedIdsCache,
     ) -> Self {
         Self {
             checked_endorsements: LruCache::new(max_known_endorsements),
-            endorsements_known_by_peer: LruCache::new(max_known_endorsements_by_peer),
+            dedup_window: EndorsementDedupWindow::new(max_known_endorsements.get()),
+            endorsements_known_by_peer: KnownItemsCache::new(
+                max_peers,
+                max_known_endorsements_by_peer,
+                max_total_known_endorsements_by_peers,
+            ),
+            relayed_ids,
         }
     }
 }
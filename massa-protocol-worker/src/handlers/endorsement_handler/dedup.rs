@@ -0,0 +1,156 @@
+use std::collections::{BTreeMap, HashMap};
+
+use massa_models::{
+    address::Address, endorsement::EndorsementId, endorsement::SecureShareEndorsement, slot::Slot,
+    stats::EndorsementDedupStats,
+};
+
+/// Number of different endorsements kept for a slot, index and producer. A second one is
+/// enough to denounce the producer: the next ones are only a waste of signature checks.
+const MAX_VARIANTS_PER_KEY: usize = 2;
+
+/// Endorsements seen for each (slot, index, producer) within the validity window, used to
+/// drop the duplicates before their signature is verified.
+pub struct EndorsementDedupWindow {
+    /// ids of the endorsements seen, by slot then by index and producer
+    seen: BTreeMap<Slot, HashMap<(u32, Address), Vec<EndorsementId>>>,
+    /// oldest slot in the window: the endorsements of older slots are not deduplicated
+    min_slot: Option<Slot>,
+    max_entries: usize,
+    entry_count: usize,
+    duplicates_dropped: u64,
+}
+
+impl EndorsementDedupWindow {
+    /// Creates a new `EndorsementDedupWindow` tracking at most `max_entries` endorsements
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            seen: BTreeMap::new(),
+            min_slot: None,
+            max_entries,
+            entry_count: 0,
+            duplicates_dropped: 0,
+        }
+    }
+
+    /// Forgets the endorsements of the slots older than `min_slot`
+    pub fn prune(&mut self, min_slot: Slot) {
+        self.min_slot = Some(min_slot);
+        while let Some(entry) = self.seen.first_entry() {
+            if *entry.key() >= min_slot {
+                break;
+            }
+            self.entry_count -= entry.remove().values().map(Vec::len).sum::<usize>();
+        }
+    }
+
+    /// Records an endorsement before its signature is verified. Returns false if it is a
+    /// duplicate that must be dropped.
+    pub fn check_and_record(&mut self, endorsement: &SecureShareEndorsement) -> bool {
+        let slot = endorsement.content.slot;
+        if self.min_slot.map_or(false, |min_slot| slot < min_slot) {
+            return true;
+        }
+        let key = (
+            endorsement.content.index,
+            endorsement.content_creator_address,
+        );
+        let ids = self.seen.entry(slot).or_default().entry(key).or_default();
+        if ids.contains(&endorsement.id) || ids.len() >= MAX_VARIANTS_PER_KEY {
+            self.duplicates_dropped += 1;
+            return false;
+        }
+        ids.push(endorsement.id);
+        self.entry_count += 1;
+        // drop the newest slots first: they are the easiest to forge in advance
+        while self.entry_count > self.max_entries {
+            let Some(entry) = self.seen.last_entry() else {
+                break;
+            };
+            self.entry_count -= entry.remove().values().map(Vec::len).sum::<usize>();
+        }
+        true
+    }
+
+    /// Forgets an endorsement whose signature turned out to be invalid
+    pub fn forget(&mut self, endorsement: &SecureShareEndorsement) {
+        let slot = endorsement.content.slot;
+        let key = (
+            endorsement.content.index,
+            endorsement.content_creator_address,
+        );
+        let Some(by_key) = self.seen.get_mut(&slot) else {
+            return;
+        };
+        if let Some(ids) = by_key.get_mut(&key) {
+            let len_before = ids.len();
+            ids.retain(|id| *id != endorsement.id);
+            self.entry_count -= len_before - ids.len();
+            if ids.is_empty() {
+                by_key.remove(&key);
+            }
+        }
+        if by_key.is_empty() {
+            self.seen.remove(&slot);
+        }
+    }
+
+    /// Size of the window and duplicates dropped since the node started
+    pub fn get_stats(&self) -> EndorsementDedupStats {
+        EndorsementDedupStats {
+            tracked_count: self.entry_count as u64,
+            duplicates_dropped: self.duplicates_dropped,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_hash::Hash;
+    use massa_models::{
+        block_id::BlockId,
+        endorsement::{Endorsement, EndorsementSerializer},
+        secure_share::SecureShareContent,
+    };
+    use massa_signature::KeyPair;
+
+    fn endorsement(keypair: &KeyPair, period: u64, endorsed: &[u8]) -> SecureShareEndorsement {
+        Endorsement {
+            slot: Slot::new(period, 0),
+            index: 0,
+            endorsed_block: BlockId(Hash::compute_from(endorsed)),
+        }
+        .new_verifiable(EndorsementSerializer::new(), keypair)
+        .unwrap()
+    }
+
+    #[test]
+    fn test_endorsement_dedup_window() {
+        let keypair = KeyPair::generate();
+        let mut window = EndorsementDedupWindow::new(3);
+
+        // same endorsement twice, then a third variant of the same producer
+        assert!(window.check_and_record(&endorsement(&keypair, 10, b"a")));
+        assert!(!window.check_and_record(&endorsement(&keypair, 10, b"a")));
+        assert!(window.check_and_record(&endorsement(&keypair, 10, b"b")));
+        assert!(!window.check_and_record(&endorsement(&keypair, 10, b"c")));
+        assert_eq!(window.get_stats().duplicates_dropped, 2);
+
+        // an invalid endorsement doesn't take the place of a valid one
+        let invalid = endorsement(&keypair, 11, b"a");
+        assert!(window.check_and_record(&invalid));
+        window.forget(&invalid);
+        assert!(window.check_and_record(&invalid));
+
+        // the newest slot is dropped when the window is full
+        assert!(window.check_and_record(&endorsement(&keypair, 12, b"a")));
+        assert_eq!(window.get_stats().tracked_count, 3);
+
+        // the endorsements older than the window are not deduplicated
+        window.prune(Slot::new(11, 0));
+        assert_eq!(window.get_stats().tracked_count, 1);
+        assert!(window.check_and_record(&endorsement(&keypair, 10, b"a")));
+        assert!(window.check_and_record(&endorsement(&keypair, 10, b"a")));
+    }
+}
@@ -1,6 +1,7 @@
+use massa_hash::{HashDeserializer, HashSerializer};
 use massa_models::{
-    endorsement::{Endorsement, EndorsementDeserializer, SecureShareEndorsement},
-    secure_share::{SecureShareDeserializer, SecureShareSerializer},
+    endorsement::{Endorsement, EndorsementDeserializer, EndorsementId, SecureShareEndorsement},
+    secure_share::{Id, SecureShareDeserializer, SecureShareSerializer},
 };
 use massa_serialization::{Deserializer, Serializer, U64VarIntDeserializer, U64VarIntSerializer};
 use nom::{
@@ -11,21 +12,29 @@ use nom::{
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use std::ops::Bound::Included;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum EndorsementMessage {
     /// Endorsements
     Endorsements(Vec<SecureShareEndorsement>),
+    /// Ids of new endorsements, the peer asks for the ones it doesn't have
+    EndorsementsAnnouncement(Vec<EndorsementId>),
+    /// Ask for the endorsements with these ids
+    AskForEndorsements(Vec<EndorsementId>),
 }
 
 impl EndorsementMessage {
     pub fn get_id(&self) -> MessageTypeId {
         match self {
             EndorsementMessage::Endorsements(_) => MessageTypeId::Endorsements,
+            EndorsementMessage::EndorsementsAnnouncement(_) => {
+                MessageTypeId::EndorsementsAnnouncement
+            }
+            EndorsementMessage::AskForEndorsements(_) => MessageTypeId::AskForEndorsements,
         }
     }
 
     pub fn max_id() -> u64 {
-        <MessageTypeId as Into<u64>>::into(MessageTypeId::Endorsements) + 1
+        <MessageTypeId as Into<u64>>::into(MessageTypeId::AskForEndorsements) + 1
     }
 }
 
@@ -34,12 +43,15 @@ impl EndorsementMessage {
 #[repr(u64)]
 pub enum MessageTypeId {
     Endorsements,
+    EndorsementsAnnouncement,
+    AskForEndorsements,
 }
 
 #[derive(Default, Clone)]
 pub struct EndorsementMessageSerializer {
     length_endorsements_serializer: U64VarIntSerializer,
     secure_share_serializer: SecureShareSerializer,
+    hash_serializer: HashSerializer,
 }
 
 impl EndorsementMessageSerializer {
@@ -47,6 +59,7 @@ impl EndorsementMessageSerializer {
         Self {
             length_endorsements_serializer: U64VarIntSerializer::new(),
             secure_share_serializer: SecureShareSerializer::new(),
+            hash_serializer: HashSerializer::new(),
         }
     }
 }
@@ -66,6 +79,15 @@ impl Serializer<EndorsementMessage> for EndorsementMessageSerializer {
                         .serialize(endorsement, buffer)?;
                 }
             }
+            EndorsementMessage::EndorsementsAnnouncement(endorsement_ids)
+            | EndorsementMessage::AskForEndorsements(endorsement_ids) => {
+                self.length_endorsements_serializer
+                    .serialize(&(endorsement_ids.len() as u64), buffer)?;
+                for endorsement_id in endorsement_ids {
+                    self.hash_serializer
+                        .serialize(endorsement_id.get_hash(), buffer)?;
+                }
+            }
         }
         Ok(())
     }
@@ -81,6 +103,7 @@ pub struct EndorsementMessageDeserializer {
     message_id: u64,
     length_endorsements_deserializer: U64VarIntDeserializer,
     secure_share_deserializer: SecureShareDeserializer<Endorsement, EndorsementDeserializer>,
+    hash_deserializer: HashDeserializer,
 }
 
 impl EndorsementMessageDeserializer {
@@ -95,12 +118,30 @@ impl EndorsementMessageDeserializer {
                 args.thread_count,
                 args.endorsement_count,
             )),
+            hash_deserializer: HashDeserializer::new(),
         }
     }
 
     pub fn set_message_id(&mut self, message_id: u64) {
         self.message_id = message_id;
     }
+
+    fn deserialize_ids<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], Vec<EndorsementId>, E> {
+        length_count(
+            context("Failed length deserialization", |input| {
+                self.length_endorsements_deserializer.deserialize(input)
+            }),
+            context("Failed endorsement id deserialization", |input| {
+                self.hash_deserializer
+                    .deserialize(input)
+                    .map(|(rest, hash)| (rest, EndorsementId::new(hash)))
+            }),
+        )
+        .parse(buffer)
+    }
 }
 
 impl Deserializer<EndorsementMessage> for EndorsementMessageDeserializer {
@@ -129,8 +170,62 @@ impl Deserializer<EndorsementMessage> for EndorsementMessageDeserializer {
                 )
                 .map(EndorsementMessage::Endorsements)
                 .parse(buffer),
+                MessageTypeId::EndorsementsAnnouncement => {
+                    context("Failed EndorsementsAnnouncement deserialization", |input| {
+                        self.deserialize_ids(input)
+                    })
+                    .map(EndorsementMessage::EndorsementsAnnouncement)
+                    .parse(buffer)
+                }
+                MessageTypeId::AskForEndorsements => {
+                    context("Failed AskForEndorsements deserialization", |input| {
+                        self.deserialize_ids(input)
+                    })
+                    .map(EndorsementMessage::AskForEndorsements)
+                    .parse(buffer)
+                }
             }
         })
         .parse(buffer)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use massa_hash::Hash;
+    use massa_models::{endorsement::EndorsementId, secure_share::Id};
+    use massa_serialization::{DeserializeError, Deserializer, Serializer};
+
+    use super::{
+        EndorsementMessage, EndorsementMessageDeserializer, EndorsementMessageDeserializerArgs,
+        EndorsementMessageSerializer,
+    };
+
+    #[test]
+    fn test_endorsements_announcement() {
+        let endorsement_ids: Vec<EndorsementId> = (0..3u64)
+            .map(|index| EndorsementId::new(Hash::compute_from(&index.to_be_bytes())))
+            .collect();
+        let message = EndorsementMessage::EndorsementsAnnouncement(endorsement_ids.clone());
+        let mut buffer = Vec::new();
+        EndorsementMessageSerializer::new()
+            .serialize(&message, &mut buffer)
+            .unwrap();
+
+        let mut deserializer =
+            EndorsementMessageDeserializer::new(EndorsementMessageDeserializerArgs {
+                thread_count: 32,
+                max_length_endorsements: 10,
+                endorsement_count: 16,
+            });
+        deserializer.set_message_id(message.get_id().into());
+        let (rest, deserialized) = deserializer
+            .deserialize::<DeserializeError>(&buffer)
+            .unwrap();
+        assert!(rest.is_empty());
+        match deserialized {
+            EndorsementMessage::EndorsementsAnnouncement(ids) => assert_eq!(ids, endorsement_ids),
+            _ => panic!("Bad message deserialized"),
+        }
+    }
+}
@@ -5,7 +5,7 @@ use massa_pool_exports::PoolController;
 use massa_protocol_exports::ProtocolConfig;
 use massa_storage::Storage;
 
-use crate::wrap_network::ActiveConnectionsTrait;
+use crate::{sig_verifier::SigVerifierPool, wrap_network::ActiveConnectionsTrait};
 
 use self::{
     cache::SharedEndorsementCache, commands_propagation::EndorsementHandlerPropagationCommand,
@@ -16,13 +16,14 @@ use self::{
 pub mod cache;
 pub mod commands_propagation;
 pub mod commands_retrieval;
+mod dedup;
 mod messages;
 mod propagation;
 mod retrieval;
 
 pub(crate) use messages::{EndorsementMessage, EndorsementMessageSerializer};
 
-use super::peer_handler::models::{PeerManagementCmd, PeerMessageTuple};
+use super::peer_handler::models::{PeerManagementCmd, PeerMessageTuple, SharedPeerDB};
 
 pub struct EndorsementHandler {
     pub endorsement_retrieval_thread:
@@ -45,6 +46,8 @@ impl EndorsementHandler {
         local_sender: Sender<EndorsementHandlerPropagationCommand>,
         local_receiver: Receiver<EndorsementHandlerPropagationCommand>,
         sender_peer_cmd: Sender<PeerManagementCmd>,
+        sig_verifier: SigVerifierPool,
+        peer_db: SharedPeerDB,
     ) -> Self {
         let endorsement_retrieval_thread = start_retrieval_thread(
             receiver,
@@ -55,10 +58,12 @@ impl EndorsementHandler {
             pool_controller,
             config.clone(),
             storage.clone_without_refs(),
+            sig_verifier,
+            active_connections.clone(),
         );
 
         let endorsement_propagation_thread =
-            start_propagation_thread(local_receiver, cache, config, active_connections);
+            start_propagation_thread(local_receiver, cache, config, active_connections, peer_db);
         Self {
             endorsement_retrieval_thread: Some((
                 sender_retrieval_ext,
@@ -1,16 +1,22 @@
-use std::{num::NonZeroUsize, thread::JoinHandle};
+use std::thread::JoinHandle;
 
 use crossbeam::channel::Receiver;
-use lru::LruCache;
 use massa_models::{
     endorsement::{EndorsementId, SecureShareEndorsement},
-    prehash::{PreHashMap, PreHashSet},
+    prehash::PreHashSet,
 };
 use massa_protocol_exports::ProtocolConfig;
 use peernet::peer_id::PeerId;
 use tracing::{debug, info, log::warn};
 
-use crate::{messages::MessagesSerializer, wrap_network::ActiveConnectionsTrait};
+use crate::{
+    handlers::{
+        gossip::split_fanout,
+        peer_handler::{models::SharedPeerDB, ENDORSEMENT_ANNOUNCEMENTS_FEATURE},
+    },
+    messages::MessagesSerializer,
+    wrap_network::ActiveConnectionsTrait,
+};
 
 use super::{
     cache::SharedEndorsementCache, commands_propagation::EndorsementHandlerPropagationCommand,
@@ -22,6 +28,7 @@ struct PropagationThread {
     config: ProtocolConfig,
     cache: SharedEndorsementCache,
     active_connections: Box<dyn ActiveConnectionsTrait>,
+    peer_db: SharedPeerDB,
     endorsement_serializer: MessagesSerializer,
 }
 
@@ -54,92 +61,55 @@ impl PropagationThread {
                                 .iter()
                                 .copied()
                                 .collect();
+                            let mut new_endorsements_by_peer = Vec::new();
                             {
                                 let mut cache_write = self.cache.write();
                                 for endorsement_id in endorsements_ids.iter().copied() {
                                     cache_write.checked_endorsements.put(endorsement_id, ());
                                 }
-                                // Add peers that potentially don't exist in cache
+                                // the endorsements relayed recently, possibly before a restart, are not relayed again
+                                let to_relay: Vec<EndorsementId> = {
+                                    let relayed_ids = cache_write.relayed_ids.read();
+                                    endorsements_ids
+                                        .iter()
+                                        .filter(|id| !relayed_ids.endorsement_relayed(id))
+                                        .copied()
+                                        .collect()
+                                };
+                                cache_write
+                                    .relayed_ids
+                                    .write()
+                                    .record_endorsements(to_relay.iter().copied());
+                                // Forget the peers that are not connected anymore and track the new ones
                                 let peer_connected =
                                     self.active_connections.get_peer_ids_connected();
-                                for peer_id in &peer_connected {
-                                    if !cache_write.endorsements_known_by_peer.contains(peer_id) {
-                                        cache_write.endorsements_known_by_peer.put(
-                                            peer_id.clone(),
-                                            LruCache::new(
-                                                NonZeroUsize::new(
-                                                    self.config.max_node_known_endorsements_size,
-                                                )
-                                                .expect(
-                                                    "max_node_known_endorsements_size in config is > 0",
-                                                ),
-                                            ),
-                                        );
-                                    }
-                                }
-                                let peers: Vec<PeerId> = cache_write
+                                cache_write
                                     .endorsements_known_by_peer
-                                    .iter()
-                                    .map(|(id, _)| id.clone())
-                                    .collect();
-                                // Clean shared cache if peers do not exist anymore
-                                for peer_id in peers {
-                                    if !peer_connected.contains(&peer_id) {
-                                        cache_write.endorsements_known_by_peer.pop(&peer_id);
+                                    .sync_peers(&peer_connected);
+                                let endorsements_reader = endorsements.read_endorsements();
+                                for peer_id in cache_write.endorsements_known_by_peer.peer_ids() {
+                                    let new_endorsements: Vec<SecureShareEndorsement> = to_relay
+                                        .iter()
+                                        .filter(|id| {
+                                            !cache_write
+                                                .endorsements_known_by_peer
+                                                .knows(&peer_id, id)
+                                        })
+                                        .map(|id| endorsements_reader.get(id).cloned().unwrap())
+                                        .collect();
+                                    if new_endorsements.is_empty() {
+                                        continue;
                                     }
-                                }
-                                for (peer_id, endorsement_ids) in
-                                    cache_write.endorsements_known_by_peer.iter_mut()
-                                {
-                                    let new_endorsements: PreHashMap<
-                                        EndorsementId,
-                                        SecureShareEndorsement,
-                                    > = {
-                                        let endorsements_reader = endorsements.read_endorsements();
-                                        endorsements
-                                            .get_endorsement_refs()
+                                    cache_write.endorsements_known_by_peer.insert_many(
+                                        &peer_id,
+                                        new_endorsements
                                             .iter()
-                                            .filter_map(|id| {
-                                                if endorsement_ids.contains(id) {
-                                                    return None;
-                                                }
-                                                Some((
-                                                    *id,
-                                                    endorsements_reader.get(id).cloned().unwrap(),
-                                                ))
-                                            })
-                                            .collect()
-                                    };
-                                    for endorsement_id in new_endorsements.keys().copied() {
-                                        endorsement_ids.put(endorsement_id, ());
-                                    }
-                                    let to_send =
-                                        new_endorsements.into_values().collect::<Vec<_>>();
-                                    if !to_send.is_empty() {
-                                        debug!(
-                                            "Send endorsements of len {} to {}",
-                                            to_send.len(),
-                                            peer_id
-                                        );
-                                        for sub_list in to_send.chunks(
-                                            self.config.max_endorsements_per_message as usize,
-                                        ) {
-                                            if let Err(err) = self.active_connections.send_to_peer(
-                                                peer_id,
-                                                &self.endorsement_serializer,
-                                                EndorsementMessage::Endorsements(sub_list.to_vec())
-                                                    .into(),
-                                                false,
-                                            ) {
-                                                warn!(
-                                                    "could not send endorsements batch to node {}: {}",
-                                                    peer_id, err
-                                                );
-                                            }
-                                        }
-                                    }
+                                            .map(|endorsement| (endorsement.id, ())),
+                                    );
+                                    new_endorsements_by_peer.push((peer_id, new_endorsements));
                                 }
                             }
+                            self.send_endorsements(new_endorsements_by_peer);
                         }
                         EndorsementHandlerPropagationCommand::Stop => {
                             info!("Stop endorsement propagation thread");
@@ -156,11 +126,86 @@ impl PropagationThread {
     }
 }
 
+impl PropagationThread {
+    /// Sends the new endorsements of each peer: in full to a few random peers and to the ones
+    /// that don't support the announcements, by id to the others
+    fn send_endorsements(
+        &mut self,
+        new_endorsements_by_peer: Vec<(PeerId, Vec<SecureShareEndorsement>)>,
+    ) {
+        let peer_db = self.peer_db.snapshot();
+        let (announcing_peers, pushed_peers): (Vec<PeerId>, Vec<PeerId>) = new_endorsements_by_peer
+            .iter()
+            .map(|(peer_id, _)| peer_id.clone())
+            .partition(|peer_id| {
+                peer_db
+                    .peers
+                    .get(peer_id)
+                    .and_then(|info| info.metadata.as_ref())
+                    .map_or(false, |metadata| {
+                        metadata.supports(ENDORSEMENT_ANNOUNCEMENTS_FEATURE)
+                    })
+            });
+        let (mut eager_peers, _) = split_fanout(
+            announcing_peers,
+            self.config
+                .endorsement_eager_fanout
+                .saturating_sub(pushed_peers.len()),
+        );
+        eager_peers.extend(pushed_peers);
+        for (peer_id, new_endorsements) in new_endorsements_by_peer {
+            let message_max_length = self.config.max_endorsements_per_message as usize;
+            if eager_peers.contains(&peer_id) {
+                debug!(
+                    "Send endorsements of len {} to {}",
+                    new_endorsements.len(),
+                    peer_id
+                );
+                for sub_list in new_endorsements.chunks(message_max_length) {
+                    if let Err(err) = self.active_connections.send_to_peer(
+                        &peer_id,
+                        &self.endorsement_serializer,
+                        EndorsementMessage::Endorsements(sub_list.to_vec()).into(),
+                    ) {
+                        warn!(
+                            "could not send endorsements batch to node {}: {}",
+                            peer_id, err
+                        );
+                    }
+                }
+            } else {
+                debug!(
+                    "Send endorsements announcement of len {} to {}",
+                    new_endorsements.len(),
+                    peer_id
+                );
+                let endorsement_ids: Vec<EndorsementId> = new_endorsements
+                    .iter()
+                    .map(|endorsement| endorsement.id)
+                    .collect();
+                for sub_list in endorsement_ids.chunks(message_max_length) {
+                    if let Err(err) = self.active_connections.send_to_peer(
+                        &peer_id,
+                        &self.endorsement_serializer,
+                        EndorsementMessage::EndorsementsAnnouncement(sub_list.to_vec()).into(),
+                    ) {
+                        warn!(
+                            "could not send endorsements announcement to node {}: {}",
+                            peer_id, err
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
 pub fn start_propagation_thread(
     receiver: Receiver<EndorsementHandlerPropagationCommand>,
     cache: SharedEndorsementCache,
     config: ProtocolConfig,
     active_connections: Box<dyn ActiveConnectionsTrait>,
+    peer_db: SharedPeerDB,
 ) -> JoinHandle<()> {
     std::thread::Builder::new()
         .name("protocol-endorsement-handler-propagation".to_string())
@@ -171,6 +216,7 @@ pub fn start_propagation_thread(
                 receiver,
                 config,
                 active_connections,
+                peer_db,
                 cache,
                 endorsement_serializer,
             };
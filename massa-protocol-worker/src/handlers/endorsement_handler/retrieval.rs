@@ -1,18 +1,17 @@
-use std::{num::NonZeroUsize, thread::JoinHandle};
+use std::thread::JoinHandle;
 
 use crossbeam::{
-    channel::{Receiver, Sender},
+    channel::{bounded, Receiver, Sender, TrySendError},
     select,
 };
-use lru::LruCache;
 use massa_logging::massa_trace;
 use massa_models::{
-    endorsement::SecureShareEndorsement,
+    endorsement::{EndorsementId, SecureShareEndorsement},
     prehash::{CapacityAllocator, PreHashMap, PreHashSet},
-    timeslots::get_block_slot_timestamp,
+    timeslots::{get_block_slot_timestamp, get_latest_block_slot_at_timestamp},
 };
 use massa_pool_exports::PoolController;
-use massa_protocol_exports::{ProtocolConfig, ProtocolError};
+use massa_protocol_exports::{MisbehaviorKind, ProtocolConfig, ProtocolError};
 use massa_serialization::{DeserializeError, Deserializer};
 use massa_storage::Storage;
 use massa_time::MassaTime;
@@ -24,16 +23,31 @@ use crate::{
         endorsement_handler::messages::EndorsementMessage,
         peer_handler::models::{PeerManagementCmd, PeerMessageTuple},
     },
-    sig_verifier::verify_sigs_batch,
+    messages::MessagesSerializer,
+    sig_verifier::SigVerifierPool,
+    wrap_network::ActiveConnectionsTrait,
 };
 
 use super::{
     cache::SharedEndorsementCache,
     commands_propagation::EndorsementHandlerPropagationCommand,
     commands_retrieval::EndorsementHandlerRetrievalCommand,
-    messages::{EndorsementMessageDeserializer, EndorsementMessageDeserializerArgs},
+    messages::{
+        EndorsementMessageDeserializer, EndorsementMessageDeserializerArgs,
+        EndorsementMessageSerializer,
+    },
 };
 
+/// Endorsements received from a peer, with the result of the verification of their signatures
+struct VerifiedEndorsements {
+    peer_id: PeerId,
+    /// endorsements whose signature was verified
+    new_endorsements: PreHashMap<EndorsementId, SecureShareEndorsement>,
+    /// all the endorsements received, except the duplicates
+    endorsement_ids: PreHashSet<EndorsementId>,
+    result: Result<(), ProtocolError>,
+}
+
 pub struct RetrievalThread {
     receiver: Receiver<PeerMessageTuple>,
     receiver_ext: Receiver<EndorsementHandlerRetrievalCommand>,
@@ -43,6 +57,11 @@ pub struct RetrievalThread {
     config: ProtocolConfig,
     storage: Storage,
     peer_cmd_sender: Sender<PeerManagementCmd>,
+    sig_verifier: SigVerifierPool,
+    verified_sender: Sender<VerifiedEndorsements>,
+    verified_receiver: Receiver<VerifiedEndorsements>,
+    active_connections: Box<dyn ActiveConnectionsTrait>,
+    endorsement_serializer: MessagesSerializer,
 }
 
 impl RetrievalThread {
@@ -64,6 +83,9 @@ impl RetrievalThread {
                                 Ok((rest, message)) => (rest, message),
                                 Err(err) => {
                                     warn!("Error while deserializing message from peer {} err: {:?}", peer_id, err);
+                                    if let Err(err) = self.report_misbehavior(&peer_id, MisbehaviorKind::MalformedMessage, format!("endorsement message {}: {}", message_id, err), false) {
+                                        warn!("Error while reporting peer {} err: {:?}", peer_id, err);
+                                    }
                                     continue;
                                 }
                             };
@@ -83,10 +105,22 @@ impl RetrievalThread {
                                             loss of sync between us and the remote node. Err = {}",
                                             peer_id, err
                                         );
-                                        if let Err(err) = self.ban_node(&peer_id) {
+                                        if let Err(err) = self.ban_node(&peer_id, MisbehaviorKind::from_error(&err), format!("endorsements: {}", err)) {
                                             warn!("Error while banning peer {} err: {:?}", peer_id, err);
                                         }
                                     }
+                                    // the endorsements verified in the meantime, or right away without verification threads
+                                    while let Ok(verified) = self.verified_receiver.try_recv() {
+                                        self.on_endorsements_verified(verified);
+                                    }
+                                }
+                                EndorsementMessage::EndorsementsAnnouncement(endorsement_ids) => {
+                                    debug!("Received endorsement message: EndorsementsAnnouncement from {}", peer_id);
+                                    self.on_endorsements_announced(endorsement_ids, &peer_id);
+                                }
+                                EndorsementMessage::AskForEndorsements(endorsement_ids) => {
+                                    debug!("Received endorsement message: AskForEndorsements from {}", peer_id);
+                                    self.on_endorsements_asked(endorsement_ids, &peer_id);
                                 }
                             }
                         }
@@ -96,6 +130,11 @@ impl RetrievalThread {
                         }
                     }
                 },
+                recv(self.verified_receiver) -> msg => {
+                    if let Ok(verified) = msg {
+                        self.on_endorsements_verified(verified);
+                    }
+                },
                 recv(self.receiver_ext) -> msg => {
                     match msg {
                         Ok(msg) => {
@@ -116,6 +155,57 @@ impl RetrievalThread {
         }
     }
 
+    /// Asks the announcing peer for the endorsements we haven't received yet
+    fn on_endorsements_announced(&mut self, endorsement_ids: Vec<EndorsementId>, peer_id: &PeerId) {
+        let to_ask: Vec<EndorsementId> = {
+            let mut cache_write = self.cache.write();
+            cache_write.endorsements_known_by_peer.insert_many(
+                peer_id,
+                endorsement_ids
+                    .iter()
+                    .map(|endorsement_id| (*endorsement_id, ())),
+            );
+            endorsement_ids
+                .into_iter()
+                .filter(|endorsement_id| !cache_write.checked_endorsements.contains(endorsement_id))
+                .collect()
+        };
+        if to_ask.is_empty() {
+            return;
+        }
+        if let Err(err) = self.active_connections.send_to_peer(
+            peer_id,
+            &self.endorsement_serializer,
+            EndorsementMessage::AskForEndorsements(to_ask).into(),
+        ) {
+            warn!("could not ask endorsements to node {}: {}", peer_id, err);
+        }
+    }
+
+    /// Sends the endorsements asked by a peer that we still have
+    fn on_endorsements_asked(&mut self, endorsement_ids: Vec<EndorsementId>, peer_id: &PeerId) {
+        let endorsements: Vec<SecureShareEndorsement> = {
+            let endorsements_reader = self.storage.read_endorsements();
+            endorsement_ids
+                .iter()
+                .filter_map(|endorsement_id| endorsements_reader.get(endorsement_id).cloned())
+                .collect()
+        };
+        if endorsements.is_empty() {
+            return;
+        }
+        if let Err(err) = self.active_connections.send_to_peer(
+            peer_id,
+            &self.endorsement_serializer,
+            EndorsementMessage::Endorsements(endorsements).into(),
+        ) {
+            warn!(
+                "could not send asked endorsements to node {}: {}",
+                peer_id, err
+            );
+        }
+    }
+
     /// Note endorsements coming from a given node,
     /// and propagate them when they were received outside of a header.
     ///
@@ -124,7 +214,8 @@ impl RetrievalThread {
     /// Does not ban if the endorsement is invalid
     ///
     /// Checks performed:
-    /// - Valid signature.
+    /// - Not a duplicate of an endorsement of the same slot, index and producer.
+    /// - Valid signature, by the verification pool which gives the result back to this thread.
     pub(crate) fn note_endorsements_from_peer(
         &mut self,
         endorsements: Vec<SecureShareEndorsement>,
@@ -134,32 +225,110 @@ impl RetrievalThread {
         let length = endorsements.len();
         let mut new_endorsements = PreHashMap::with_capacity(length);
         let mut endorsement_ids = PreHashSet::with_capacity(length);
-        for endorsement in endorsements.into_iter() {
-            let endorsement_id = endorsement.id;
-            endorsement_ids.insert(endorsement_id);
-            // check endorsement signature if not already checked
-            {
-                let read_cache = self.cache.read();
-                if !read_cache.checked_endorsements.contains(&endorsement_id) {
+        let now = MassaTime::now()?;
+        {
+            let mut cache_write = self.cache.write();
+            // duplicates are only dropped within the propagation window
+            if let Some(min_slot) = get_latest_block_slot_at_timestamp(
+                self.config.thread_count,
+                self.config.t0,
+                self.config.genesis_timestamp,
+                now.saturating_sub(self.config.max_endorsements_propagation_time),
+            )? {
+                cache_write.dedup_window.prune(min_slot);
+            }
+            for endorsement in endorsements.into_iter() {
+                let endorsement_id = endorsement.id;
+                // check endorsement signature if not already checked
+                if cache_write.checked_endorsements.contains(&endorsement_id) {
+                    endorsement_ids.insert(endorsement_id);
+                } else if cache_write.dedup_window.check_and_record(&endorsement) {
+                    endorsement_ids.insert(endorsement_id);
                     new_endorsements.insert(endorsement_id, endorsement);
                 }
             }
         }
 
         // Batch signature verification
-        // optimized signature verification
-        verify_sigs_batch(
-            &new_endorsements
-                .values()
-                .map(|endorsement| {
-                    (
-                        endorsement.compute_signed_hash(),
-                        endorsement.signature,
-                        endorsement.content_creator_pub_key,
-                    )
-                })
-                .collect::<Vec<_>>(),
-        )?;
+        let signatures = new_endorsements
+            .values()
+            .map(|endorsement| {
+                (
+                    endorsement.compute_signed_hash(),
+                    endorsement.signature,
+                    endorsement.content_creator_pub_key,
+                )
+            })
+            .collect::<Vec<_>>();
+        let verified_sender = self.verified_sender.clone();
+        let peer_id = from_peer_id.clone();
+        self.sig_verifier
+            .submit(from_peer_id, signatures, move |result| {
+                let verified = VerifiedEndorsements {
+                    peer_id,
+                    new_endorsements,
+                    endorsement_ids,
+                    result,
+                };
+                if let Err(TrySendError::Full(verified)) = verified_sender.try_send(verified) {
+                    debug!(
+                        "verified endorsements channel is full: dropped {} endorsements of peer {}",
+                        verified.endorsement_ids.len(),
+                        verified.peer_id
+                    );
+                }
+            });
+        Ok(())
+    }
+
+    /// Handles endorsements received from a peer once their signature is verified.
+    /// A wrong signature gets the peer banned.
+    fn on_endorsements_verified(&mut self, verified: VerifiedEndorsements) {
+        let peer_id = verified.peer_id.clone();
+        if let Err(err) = self.note_verified_endorsements(verified) {
+            warn!(
+                "peer {} sent us critically incorrect endorsements, \
+                which may be an attack attempt by the remote node or a \
+                loss of sync between us and the remote node. Err = {}",
+                peer_id, err
+            );
+            if let Err(err) = self.ban_node(
+                &peer_id,
+                MisbehaviorKind::from_error(&err),
+                format!("endorsements: {}", err),
+            ) {
+                warn!("Error while banning peer {} err: {:?}", peer_id, err);
+            }
+        }
+    }
+
+    fn note_verified_endorsements(
+        &mut self,
+        verified: VerifiedEndorsements,
+    ) -> Result<(), ProtocolError> {
+        let VerifiedEndorsements {
+            peer_id: from_peer_id,
+            new_endorsements,
+            endorsement_ids,
+            result,
+        } = verified;
+        if let Err(err) = result {
+            // the invalid endorsements must not prevent the valid ones from being received
+            let mut cache_write = self.cache.write();
+            for endorsement in new_endorsements.values() {
+                cache_write.dedup_window.forget(endorsement);
+            }
+            if matches!(err, ProtocolError::WrongSignature) {
+                return Err(err);
+            }
+            debug!(
+                "endorsements of peer {} dropped before their verification: {}",
+                from_peer_id, err
+            );
+            return Ok(());
+        }
+        let from_peer_id = &from_peer_id;
+        let now = MassaTime::now()?;
         {
             let mut cache_write = self.cache.write();
             // add to verified signature cache
@@ -167,18 +336,12 @@ impl RetrievalThread {
                 cache_write.checked_endorsements.put(*endorsement_id, ());
             }
             // add to known endorsements for source node.
-            let endorsements = cache_write.endorsements_known_by_peer.get_or_insert_mut(
-                from_peer_id.clone(),
-                || {
-                    LruCache::new(
-                        NonZeroUsize::new(self.config.max_node_known_endorsements_size)
-                            .expect("max_node_known_endorsements_size in config should be > 0"),
-                    )
-                },
+            cache_write.endorsements_known_by_peer.insert_many(
+                from_peer_id,
+                endorsement_ids
+                    .iter()
+                    .map(|endorsement_id| (*endorsement_id, ())),
             );
-            for endorsement_id in endorsement_ids.iter() {
-                endorsements.put(*endorsement_id, ());
-            }
         }
 
         if !new_endorsements.is_empty() {
@@ -189,7 +352,6 @@ impl RetrievalThread {
             // Propagate endorsements when the slot of the block they endorse isn't `max_endorsements_propagation_time` old.
             let mut endorsements_to_propagate = endorsements.clone();
             let endorsements_to_not_propagate = {
-                let now = MassaTime::now()?;
                 let read_endorsements = endorsements_to_propagate.read_endorsements();
                 endorsements_to_propagate
                     .get_endorsement_refs()
@@ -235,10 +397,32 @@ impl RetrievalThread {
     }
 
     /// send a ban peer command to the peer handler
-    fn ban_node(&mut self, peer_id: &PeerId) -> Result<(), ProtocolError> {
+    fn ban_node(
+        &mut self,
+        peer_id: &PeerId,
+        kind: MisbehaviorKind,
+        evidence: String,
+    ) -> Result<(), ProtocolError> {
         massa_trace!("ban node from retrieval thread", { "peer_id": peer_id.to_string() });
+        self.report_misbehavior(peer_id, kind, evidence, true)
+    }
+
+    /// send an offense of a peer to the misbehavior ledger of the peer handler, which bans
+    /// the peer if `ban` is set or if it committed too many offenses
+    fn report_misbehavior(
+        &mut self,
+        peer_id: &PeerId,
+        kind: MisbehaviorKind,
+        evidence: String,
+        ban: bool,
+    ) -> Result<(), ProtocolError> {
         self.peer_cmd_sender
-            .send(PeerManagementCmd::Ban(vec![peer_id.clone()]))
+            .send(PeerManagementCmd::ReportMisbehavior {
+                peer_id: peer_id.clone(),
+                kind,
+                evidence,
+                ban,
+            })
             .map_err(|err| ProtocolError::SendError(err.to_string()))
     }
 }
@@ -253,10 +437,14 @@ pub fn start_retrieval_thread(
     pool_controller: Box<dyn PoolController>,
     config: ProtocolConfig,
     storage: Storage,
+    sig_verifier: SigVerifierPool,
+    active_connections: Box<dyn ActiveConnectionsTrait>,
 ) -> JoinHandle<()> {
     std::thread::Builder::new()
         .name("protocol-endorsement-handler-retrieval".to_string())
         .spawn(move || {
+            let (verified_sender, verified_receiver) =
+                bounded(config.max_size_channel_network_to_endorsement_handler);
             let mut retrieval_thread = RetrievalThread {
                 receiver,
                 receiver_ext,
@@ -266,6 +454,12 @@ pub fn start_retrieval_thread(
                 pool_controller,
                 config,
                 storage,
+                sig_verifier,
+                verified_sender,
+                verified_receiver,
+                active_connections,
+                endorsement_serializer: MessagesSerializer::new()
+                    .with_endorsement_message_serializer(EndorsementMessageSerializer::new()),
             };
             retrieval_thread.run();
         })
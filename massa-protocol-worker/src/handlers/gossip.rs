@@ -0,0 +1,44 @@
+use peernet::peer_id::PeerId;
+use rand::seq::SliceRandom;
+
+/// Splits the peers that miss some items between the ones receiving the full items (eager push)
+/// and the ones receiving only their ids (lazy push), which ask for the items they still miss.
+///
+/// At most `eager_fanout` random peers get the full items: the items spread as fast as with a
+/// broadcast, without sending them again and again to peers that received them from others.
+pub(crate) fn split_fanout(
+    mut peers: Vec<PeerId>,
+    eager_fanout: usize,
+) -> (Vec<PeerId>, Vec<PeerId>) {
+    if peers.len() <= eager_fanout {
+        return (peers, Vec::new());
+    }
+    peers.shuffle(&mut rand::thread_rng());
+    let lazy = peers.split_off(eager_fanout);
+    (peers, lazy)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use peernet::{peer_id::PeerId, types::KeyPair};
+
+    use super::split_fanout;
+
+    #[test]
+    fn test_split_fanout() {
+        let peers: Vec<PeerId> = (0..10)
+            .map(|_| PeerId::from_public_key(KeyPair::generate().get_public_key()))
+            .collect();
+        let (eager, lazy) = split_fanout(peers.clone(), 3);
+        assert_eq!(eager.len(), 3);
+        assert_eq!(lazy.len(), 7);
+        let all: HashSet<PeerId> = eager.into_iter().chain(lazy).collect();
+        assert_eq!(all, peers.iter().cloned().collect());
+
+        let (eager, lazy) = split_fanout(peers, 20);
+        assert_eq!(eager.len(), 10);
+        assert!(lazy.is_empty());
+    }
+}
@@ -0,0 +1,238 @@
+use std::{collections::HashSet, hash::Hash, num::NonZeroUsize, time::Instant};
+
+use lru::LruCache;
+use massa_models::stats::KnownItemsStats;
+use peernet::peer_id::PeerId;
+
+/// Items (blocks, operations, endorsements...) known by each connected peer, used to avoid
+/// sending a peer something it already has.
+///
+/// Each peer has its own LRU of known items, and the total number of items over all peers is
+/// capped: when the cap is reached, the oldest items of the least recently used peer are evicted.
+/// The entries of a peer are dropped when it disconnects.
+pub struct KnownItemsCache<Id: Hash + Eq, V = ()> {
+    /// known items of each peer, with the instant the peer was first tracked
+    peers: LruCache<PeerId, (LruCache<Id, V>, Instant)>,
+    max_items_per_peer: NonZeroUsize,
+    max_items: usize,
+    item_count: usize,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl<Id: Hash + Eq, V> KnownItemsCache<Id, V> {
+    /// Creates a new `KnownItemsCache`
+    ///
+    /// # Arguments
+    /// * `max_peers`: maximum number of tracked peers
+    /// * `max_items_per_peer`: maximum number of items tracked for a peer
+    /// * `max_items`: maximum number of items tracked, all peers included
+    pub fn new(
+        max_peers: NonZeroUsize,
+        max_items_per_peer: NonZeroUsize,
+        max_items: usize,
+    ) -> Self {
+        Self {
+            peers: LruCache::new(max_peers),
+            max_items_per_peer,
+            max_items,
+            item_count: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    /// Forgets the peers that are not connected anymore and starts tracking the new ones.
+    /// The connected peers are marked as recently used.
+    pub fn sync_peers(&mut self, connected: &HashSet<PeerId>) {
+        let disconnected: Vec<PeerId> = self
+            .peers
+            .iter()
+            .filter(|(peer_id, _)| !connected.contains(peer_id))
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect();
+        for peer_id in disconnected {
+            self.remove_peer(&peer_id);
+        }
+        for peer_id in connected {
+            self.get_or_insert_peer(peer_id);
+        }
+    }
+
+    /// Forgets everything known by a peer. Returns false if the peer wasn't tracked.
+    pub fn remove_peer(&mut self, peer_id: &PeerId) -> bool {
+        match self.peers.pop(peer_id) {
+            Some((items, _)) => {
+                self.item_count -= items.len();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns true if the peer is tracked
+    pub fn contains_peer(&self, peer_id: &PeerId) -> bool {
+        self.peers.contains(peer_id)
+    }
+
+    /// Ids of the tracked peers, from the most to the least recently used
+    pub fn peer_ids(&self) -> Vec<PeerId> {
+        self.peers
+            .iter()
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect()
+    }
+
+    /// Instant at which the peer started to be tracked
+    pub fn peer_tracked_since(&self, peer_id: &PeerId) -> Option<Instant> {
+        self.peers.peek(peer_id).map(|(_, since)| *since)
+    }
+
+    /// Records that the peer knows an item, starting to track the peer if needed
+    pub fn insert(&mut self, peer_id: &PeerId, id: Id, value: V) {
+        let (items, _) = self.get_or_insert_peer(peer_id);
+        let len_before = items.len();
+        let replaced = items.put(id, value).is_some();
+        let len_after = items.len();
+        if !replaced && len_after == len_before {
+            // the oldest item of the peer made room for the new one
+            self.evictions += 1;
+        }
+        self.item_count = self.item_count + len_after - len_before;
+        self.enforce_max_items();
+    }
+
+    /// Records that the peer knows all the items, starting to track the peer if needed
+    pub fn insert_many(&mut self, peer_id: &PeerId, items: impl IntoIterator<Item = (Id, V)>) {
+        for (id, value) in items {
+            self.insert(peer_id, id, value);
+        }
+    }
+
+    /// What we know about the item for this peer, counted in the hit/miss metrics
+    pub fn get(&mut self, peer_id: &PeerId, id: &Id) -> Option<&V> {
+        match self
+            .peers
+            .peek_mut(peer_id)
+            .and_then(|(items, _)| items.get(id))
+        {
+            Some(value) => {
+                self.hits += 1;
+                Some(value)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Returns true if the peer knows the item, counted in the hit/miss metrics
+    pub fn knows(&mut self, peer_id: &PeerId, id: &Id) -> bool {
+        self.get(peer_id, id).is_some()
+    }
+
+    /// What we know about the item for this peer, without updating the LRU nor the metrics
+    pub fn peek(&self, peer_id: &PeerId, id: &Id) -> Option<&V> {
+        self.peers
+            .peek(peer_id)
+            .and_then(|(items, _)| items.peek(id))
+    }
+
+    /// Iterates over the tracked peers with what we know about the item for each of them,
+    /// without updating the LRU nor the metrics
+    pub fn peek_all<'a>(&'a self, id: &'a Id) -> impl Iterator<Item = (&'a PeerId, Option<&'a V>)> {
+        self.peers
+            .iter()
+            .map(move |(peer_id, (items, _))| (peer_id, items.peek(id)))
+    }
+
+    /// Counters and current size of the cache
+    pub fn get_stats(&self) -> KnownItemsStats {
+        KnownItemsStats {
+            peer_count: self.peers.len() as u64,
+            entry_count: self.item_count as u64,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+        }
+    }
+
+    fn get_or_insert_peer(&mut self, peer_id: &PeerId) -> &mut (LruCache<Id, V>, Instant) {
+        if !self.peers.contains(peer_id) {
+            let new_peer = (LruCache::new(self.max_items_per_peer), Instant::now());
+            if let Some((evicted_peer, (items, _))) = self.peers.push(peer_id.clone(), new_peer) {
+                // the least recently used peer was dropped to make room
+                if &evicted_peer != peer_id {
+                    self.item_count -= items.len();
+                    self.evictions += items.len() as u64;
+                }
+            }
+        }
+        self.peers
+            .get_mut(peer_id)
+            .expect("critical: peer was just inserted in the known items cache")
+    }
+
+    /// Evicts the oldest items of the least recently used peers until the total is under the cap
+    fn enforce_max_items(&mut self) {
+        while self.item_count > self.max_items {
+            let Some(peer_id) = self.peers.peek_lru().map(|(peer_id, _)| peer_id.clone()) else {
+                return;
+            };
+            let Some((items, _)) = self.peers.peek_mut(&peer_id) else {
+                return;
+            };
+            if items.pop_lru().is_some() {
+                self.item_count -= 1;
+                self.evictions += 1;
+            } else {
+                // a peer without items: stop tracking it
+                self.peers.pop_lru();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_signature::KeyPair;
+
+    fn peer() -> PeerId {
+        PeerId::from_bytes(KeyPair::generate().get_public_key().to_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_known_items_bounds_and_eviction() {
+        let (peer_a, peer_b) = (peer(), peer());
+        let mut cache: KnownItemsCache<u64> = KnownItemsCache::new(
+            NonZeroUsize::new(2).unwrap(),
+            NonZeroUsize::new(3).unwrap(),
+            4,
+        );
+
+        // per peer bound
+        cache.insert_many(&peer_a, (0..4).map(|id| (id, ())));
+        assert!(!cache.knows(&peer_a, &0));
+        assert!(cache.knows(&peer_a, &3));
+        assert_eq!(cache.get_stats().entry_count, 3);
+
+        // global bound: the least recently used peer loses its oldest items
+        cache.insert_many(&peer_b, (10..12).map(|id| (id, ())));
+        let stats = cache.get_stats();
+        assert_eq!(stats.entry_count, 4);
+        assert_eq!(stats.evictions, 2);
+        assert!(cache.peek(&peer_a, &1).is_none());
+        assert!(cache.peek(&peer_b, &10).is_some());
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+
+        // invalidation on disconnect
+        cache.sync_peers(&[peer_b.clone()].into_iter().collect());
+        assert!(!cache.contains_peer(&peer_a));
+        assert_eq!(cache.get_stats().entry_count, 2);
+        assert_eq!(cache.get_stats().peer_count, 1);
+    }
+}
@@ -1,4 +1,8 @@
 pub mod block_handler;
 pub mod endorsement_handler;
+mod gossip;
+pub mod known_items;
 pub mod operation_handler;
 pub mod peer_handler;
+pub mod relayed_ids;
+pub mod request_manager;
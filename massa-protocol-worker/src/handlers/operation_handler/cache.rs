@@ -1,22 +1,42 @@
 use std::{num::NonZeroUsize, sync::Arc};
 
 use lru::LruCache;
-use massa_models::operation::{OperationId, OperationPrefixId};
+use massa_models::{
+    operation::{OperationId, OperationPrefixId},
+    stats::OperationGossipStats,
+};
 use parking_lot::RwLock;
-use peernet::peer_id::PeerId;
+
+use crate::handlers::{known_items::KnownItemsCache, relayed_ids::SharedRelayedIdsCache};
 
 pub struct OperationCache {
     pub checked_operations: LruCache<OperationId, ()>,
     pub checked_operations_prefix: LruCache<OperationPrefixId, ()>,
-    pub ops_known_by_peer: LruCache<PeerId, LruCache<OperationPrefixId, ()>>,
+    pub ops_known_by_peer: KnownItemsCache<OperationPrefixId>,
+    /// Counters of the operation gossip, updated by the retrieval and propagation threads
+    pub stats: OperationGossipStats,
+    /// Ids recently relayed by all the handlers
+    pub relayed_ids: SharedRelayedIdsCache,
 }
 
 impl OperationCache {
-    pub fn new(max_known_ops: NonZeroUsize, max_known_ops_by_peer: NonZeroUsize) -> Self {
+    pub fn new(
+        max_known_ops: NonZeroUsize,
+        max_peers: NonZeroUsize,
+        max_known_ops_by_peer: NonZeroUsize,
+        max_total_known_ops_by_peers: usize,
+        relayed_ids: SharedRelayedIdsCache,
+    ) -> Self {
         Self {
             checked_operations: LruCache::new(max_known_ops),
             checked_operations_prefix: LruCache::new(max_known_ops),
-            ops_known_by_peer: LruCache::new(max_known_ops_by_peer),
+            ops_known_by_peer: KnownItemsCache::new(
+                max_peers,
+                max_known_ops_by_peer,
+                max_total_known_ops_by_peers,
+            ),
+            stats: OperationGossipStats::default(),
+            relayed_ids,
         }
     }
 
@@ -25,6 +45,16 @@ impl OperationCache {
         self.checked_operations_prefix
             .put(operation_id.prefix(), ());
     }
+
+    /// Counters of the operation gossip with the current size of the known operations caches
+    pub fn get_stats(&self) -> OperationGossipStats {
+        let known_ops_stats = self.ops_known_by_peer.get_stats();
+        OperationGossipStats {
+            known_ops_peer_count: known_ops_stats.peer_count,
+            known_ops_entry_count: known_ops_stats.entry_count,
+            ..self.stats.clone()
+        }
+    }
 }
 
 pub type SharedOperationCache = Arc<RwLock<OperationCache>>;
@@ -1,8 +1,20 @@
 use massa_models::{operation::OperationId, prehash::PreHashSet};
+use massa_protocol_exports::ProtocolConfig;
+use peernet::peer_id::PeerId;
 
 #[derive(Clone)]
 pub enum OperationHandlerPropagationCommand {
     Stop,
     /// operations ids
     AnnounceOperations(PreHashSet<OperationId>),
+    /// operations ids received from a peer, announced in turn with the operations of the
+    /// other peers
+    AnnounceReceivedOperations {
+        peer_id: PeerId,
+        operations: PreHashSet<OperationId>,
+        /// the peer is quarantined: announce after the other operations
+        quarantined: bool,
+    },
+    /// Reloaded config
+    UpdateConfig(Box<ProtocolConfig>),
 }
@@ -1,4 +1,8 @@
+use massa_protocol_exports::ProtocolConfig;
+
 #[derive(Clone)]
 pub enum OperationHandlerRetrievalCommand {
     Stop,
+    /// Reloaded config
+    UpdateConfig(Box<ProtocolConfig>),
 }
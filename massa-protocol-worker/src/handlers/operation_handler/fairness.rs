@@ -0,0 +1,225 @@
+//! Fairness of the operation gossip between the peers.
+//!
+//! The operations received from a peer are propagated in turn with the operations of the other
+//! peers, so that a peer sending many operations cannot take all the announcements. The peers
+//! whose operations are mostly useless (already known or too old to be propagated) are
+//! quarantined: their operations are verified and announced after the others.
+
+use std::collections::{HashMap, VecDeque};
+
+use massa_models::operation::OperationId;
+use massa_protocol_exports::ProtocolConfig;
+use peernet::peer_id::PeerId;
+
+/// Operations received from a peer that were useful or not
+#[derive(Debug, Default, Clone, Copy)]
+struct PeerOperationScore {
+    useful: u64,
+    useless: u64,
+}
+
+/// Usefulness of the operations received from each peer
+pub(crate) struct OperationPeerScores {
+    scores: HashMap<PeerId, PeerOperationScore>,
+    /// number of operations of a peer counted before it can be quarantined
+    min_samples: u64,
+    /// minimum percentage of useful operations of a peer out of quarantine
+    min_useful_percent: u64,
+}
+
+impl OperationPeerScores {
+    pub(crate) fn from_config(config: &ProtocolConfig) -> Self {
+        Self {
+            scores: HashMap::new(),
+            min_samples: config.operation_quarantine_min_samples,
+            min_useful_percent: config.operation_quarantine_min_useful_percent,
+        }
+    }
+
+    pub(crate) fn update_config(&mut self, config: &ProtocolConfig) {
+        self.min_samples = config.operation_quarantine_min_samples;
+        self.min_useful_percent = config.operation_quarantine_min_useful_percent;
+    }
+
+    /// Counts the operations of a message of `peer_id`. The counts are halved once they reach
+    /// twice `min_samples`, so a peer leaves the quarantine once its operations are useful again.
+    pub(crate) fn record(&mut self, peer_id: &PeerId, useful: u64, useless: u64) {
+        let score = self.scores.entry(peer_id.clone()).or_default();
+        score.useful = score.useful.saturating_add(useful);
+        score.useless = score.useless.saturating_add(useless);
+        if self.min_samples > 0 && score.useful + score.useless >= 2 * self.min_samples {
+            score.useful /= 2;
+            score.useless /= 2;
+        }
+    }
+
+    pub(crate) fn is_quarantined(&self, peer_id: &PeerId) -> bool {
+        if self.min_samples == 0 {
+            return false;
+        }
+        let Some(score) = self.scores.get(peer_id) else {
+            return false;
+        };
+        let total = score.useful + score.useless;
+        total >= self.min_samples && score.useful * 100 < self.min_useful_percent * total
+    }
+
+    /// Forgets the peers that aren't connected anymore
+    pub(crate) fn retain(&mut self, is_connected: impl Fn(&PeerId) -> bool) {
+        self.scores.retain(|peer_id, _| is_connected(peer_id));
+    }
+}
+
+/// Operations waiting to be announced, by origin, served in turn
+#[derive(Default)]
+struct PropagationLane {
+    /// `None` for the operations that don't come from a peer (sent by the API or in blocks)
+    queues: HashMap<Option<PeerId>, VecDeque<OperationId>>,
+    turns: VecDeque<Option<PeerId>>,
+    len: usize,
+}
+
+impl PropagationLane {
+    /// Queues operations, dropping the oldest ones of the same origin above `max_per_origin`
+    fn push(
+        &mut self,
+        origin: Option<PeerId>,
+        operations: impl IntoIterator<Item = OperationId>,
+        max_per_origin: usize,
+    ) -> usize {
+        if !self.queues.contains_key(&origin) {
+            self.turns.push_back(origin.clone());
+        }
+        let queue = self.queues.entry(origin).or_default();
+        let before = queue.len();
+        queue.extend(operations);
+        let mut dropped = 0;
+        while queue.len() > max_per_origin.max(1) {
+            queue.pop_front();
+            dropped += 1;
+        }
+        self.len = self.len + queue.len() - before;
+        dropped
+    }
+
+    /// Takes up to `max` operations, one per origin in turn
+    fn pop(&mut self, max: usize, operations: &mut Vec<OperationId>) {
+        while operations.len() < max {
+            let Some(origin) = self.turns.pop_front() else {
+                return;
+            };
+            let Some(queue) = self.queues.get_mut(&origin) else {
+                continue;
+            };
+            if let Some(operation_id) = queue.pop_front() {
+                operations.push(operation_id);
+                self.len -= 1;
+            }
+            if queue.is_empty() {
+                self.queues.remove(&origin);
+            } else {
+                self.turns.push_back(origin);
+            }
+        }
+    }
+}
+
+/// Operations waiting to be announced. The operations of the quarantined peers are only
+/// announced when no other operation is waiting.
+#[derive(Default)]
+pub(crate) struct PropagationQueue {
+    normal: PropagationLane,
+    quarantine: PropagationLane,
+}
+
+impl PropagationQueue {
+    /// Queues operations to announce, returns the number of operations of the same origin
+    /// dropped because its queue is full
+    pub(crate) fn push(
+        &mut self,
+        origin: Option<PeerId>,
+        operations: impl IntoIterator<Item = OperationId>,
+        quarantined: bool,
+        max_per_origin: usize,
+    ) -> usize {
+        if quarantined {
+            self.quarantine.push(origin, operations, max_per_origin)
+        } else {
+            self.normal.push(origin, operations, max_per_origin)
+        }
+    }
+
+    /// Number of operations waiting outside of the quarantine
+    pub(crate) fn len(&self) -> usize {
+        self.normal.len
+    }
+
+    /// Takes up to `max` operations to announce, the origins in turn
+    pub(crate) fn pop(&mut self, max: usize) -> Vec<OperationId> {
+        let mut operations = Vec::new();
+        self.normal.pop(max, &mut operations);
+        self.quarantine.pop(max, &mut operations);
+        operations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use massa_hash::Hash;
+    use massa_models::operation::OperationId;
+    use massa_protocol_exports::ProtocolConfig;
+    use peernet::{peer_id::PeerId, types::KeyPair};
+
+    use super::{OperationPeerScores, PropagationQueue};
+
+    fn operation_id(index: u64) -> OperationId {
+        OperationId::new(Hash::compute_from(&index.to_be_bytes()))
+    }
+
+    #[test]
+    fn test_propagation_queue_serves_origins_in_turn() {
+        let flooder = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        let other = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        let spammer = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        let mut queue = PropagationQueue::default();
+        // the flooder only loses its own oldest operations
+        assert_eq!(
+            queue.push(Some(flooder), (0..10).map(operation_id), false, 8),
+            2
+        );
+        queue.push(Some(other), [operation_id(100)], false, 8);
+        queue.push(Some(spammer), [operation_id(200)], true, 8);
+        queue.push(None, [operation_id(300)], false, 8);
+        assert_eq!(queue.len(), 10);
+
+        assert_eq!(
+            queue.pop(3),
+            vec![operation_id(2), operation_id(100), operation_id(300)]
+        );
+        // the quarantined operations come once the others are announced
+        let rest = queue.pop(100);
+        assert_eq!(rest.len(), 8);
+        assert_eq!(rest.last(), Some(&operation_id(200)));
+        assert_eq!(queue.len(), 0);
+    }
+
+    #[test]
+    fn test_operation_peer_scores_quarantine() {
+        let mut config = ProtocolConfig::default();
+        config.operation_quarantine_min_samples = 100;
+        config.operation_quarantine_min_useful_percent = 20;
+        let mut scores = OperationPeerScores::from_config(&config);
+        let peer_id = PeerId::from_public_key(KeyPair::generate().get_public_key());
+
+        scores.record(&peer_id, 0, 50);
+        // not enough operations to judge
+        assert!(!scores.is_quarantined(&peer_id));
+        scores.record(&peer_id, 10, 50);
+        assert!(scores.is_quarantined(&peer_id));
+        // useful again
+        for _ in 0..10 {
+            scores.record(&peer_id, 50, 0);
+        }
+        assert!(!scores.is_quarantined(&peer_id));
+    }
+}
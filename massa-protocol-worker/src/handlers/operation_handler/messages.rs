@@ -9,7 +9,11 @@ use nom::{
 };
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
-#[derive(Debug)]
+use super::reconciliation::{
+    OperationsDigest, OperationsDigestDeserializer, OperationsDigestSerializer,
+};
+
+#[derive(Debug, Clone)]
 pub enum OperationMessage {
     /// Batch of operation ids
     OperationsAnnouncement(OperationPrefixIds),
@@ -17,6 +21,8 @@ pub enum OperationMessage {
     AskForOperations(OperationPrefixIds),
     /// A list of operations
     Operations(Vec<SecureShareOperation>),
+    /// Digest of the operations known by the sender, to find the ones it missed
+    OperationsDigest(OperationsDigest),
 }
 
 impl OperationMessage {
@@ -25,27 +31,32 @@ impl OperationMessage {
             OperationMessage::OperationsAnnouncement(_) => MessageTypeId::OperationsAnnouncement,
             OperationMessage::AskForOperations(_) => MessageTypeId::AskForOperations,
             OperationMessage::Operations(_) => MessageTypeId::Operations,
+            OperationMessage::OperationsDigest(_) => MessageTypeId::OperationsDigest,
         }
     }
 
     pub fn max_id() -> u64 {
-        <MessageTypeId as Into<u64>>::into(MessageTypeId::Operations) + 1
+        <MessageTypeId as Into<u64>>::into(MessageTypeId::OperationsDigest) + 1
     }
 }
 
 // DO NOT FORGET TO UPDATE MAX ID IF YOU UPDATE THERE
+// The ids after `Operations` are sent with a wire id in the extension range of the operations,
+// see `wire_message_id`, and only to the peers that support them
 #[derive(IntoPrimitive, Debug, Eq, PartialEq, TryFromPrimitive)]
 #[repr(u64)]
 pub enum MessageTypeId {
     OperationsAnnouncement = 0,
     AskForOperations = 1,
     Operations = 2,
+    OperationsDigest = 3,
 }
 
 #[derive(Default, Clone)]
 pub struct OperationMessageSerializer {
     operation_prefix_ids_serializer: OperationPrefixIdsSerializer,
     operations_serializer: OperationsSerializer,
+    operations_digest_serializer: OperationsDigestSerializer,
 }
 
 impl OperationMessageSerializer {
@@ -53,6 +64,7 @@ impl OperationMessageSerializer {
         Self {
             operation_prefix_ids_serializer: OperationPrefixIdsSerializer::new(),
             operations_serializer: OperationsSerializer::new(),
+            operations_digest_serializer: OperationsDigestSerializer::new(),
         }
     }
 }
@@ -75,6 +87,10 @@ impl Serializer<OperationMessage> for OperationMessageSerializer {
             OperationMessage::Operations(operations) => {
                 self.operations_serializer.serialize(operations, buffer)?;
             }
+            OperationMessage::OperationsDigest(digest) => {
+                self.operations_digest_serializer
+                    .serialize(digest, buffer)?;
+            }
         }
         Ok(())
     }
@@ -83,6 +99,7 @@ impl Serializer<OperationMessage> for OperationMessageSerializer {
 pub struct OperationMessageDeserializer {
     operation_prefix_ids_deserializer: OperationPrefixIdsDeserializer,
     operations_deserializer: OperationsDeserializer,
+    operations_digest_deserializer: OperationsDigestDeserializer,
     message_id: u64,
 }
 
@@ -122,6 +139,7 @@ impl OperationMessageDeserializer {
                 args.max_op_datastore_key_length,
                 args.max_op_datastore_value_length,
             ),
+            operations_digest_deserializer: OperationsDigestDeserializer::new(),
             message_id: 0,
         }
     }
@@ -165,6 +183,13 @@ impl Deserializer<OperationMessage> for OperationMessageDeserializer {
                     .map(OperationMessage::Operations)
                     .parse(buffer)
                 }
+                MessageTypeId::OperationsDigest => {
+                    context("Failed OperationsDigest deserialization", |input| {
+                        self.operations_digest_deserializer.deserialize(input)
+                    })
+                    .map(OperationMessage::OperationsDigest)
+                    .parse(buffer)
+                }
             }
         })
         .parse(buffer)
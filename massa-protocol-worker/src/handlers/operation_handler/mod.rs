@@ -2,10 +2,13 @@ use std::thread::JoinHandle;
 
 use crossbeam::channel::{Receiver, Sender};
 use massa_pool_exports::PoolController;
-use massa_protocol_exports::ProtocolConfig;
+use massa_protocol_exports::{ProtocolConfig, ProtocolEventBus};
 use massa_storage::Storage;
 
-use crate::wrap_network::ActiveConnectionsTrait;
+use crate::{
+    production_schedule::ProductionSchedule, sig_verifier::SigVerifierPool,
+    wrap_network::ActiveConnectionsTrait,
+};
 
 use self::{
     cache::SharedOperationCache, commands_propagation::OperationHandlerPropagationCommand,
@@ -16,13 +19,15 @@ use self::{
 pub mod cache;
 pub mod commands_propagation;
 pub mod commands_retrieval;
+mod fairness;
 mod messages;
 mod propagation;
+mod reconciliation;
 mod retrieval;
 
 pub(crate) use messages::{OperationMessage, OperationMessageSerializer};
 
-use super::peer_handler::models::{PeerManagementCmd, PeerMessageTuple};
+use super::peer_handler::models::{PeerManagementCmd, PeerMessageTuple, SharedPeerDB};
 
 pub struct OperationHandler {
     pub operation_retrieval_thread:
@@ -45,6 +50,10 @@ impl OperationHandler {
         local_sender: Sender<OperationHandlerPropagationCommand>,
         local_receiver: Receiver<OperationHandlerPropagationCommand>,
         peer_cmd_sender: Sender<PeerManagementCmd>,
+        sig_verifier: SigVerifierPool,
+        event_bus: ProtocolEventBus,
+        production_schedule: ProductionSchedule,
+        peer_db: SharedPeerDB,
     ) -> Self {
         let operation_retrieval_thread = start_retrieval_thread(
             receiver_network,
@@ -56,16 +65,39 @@ impl OperationHandler {
             receiver_retrieval_ext,
             local_sender.clone(),
             peer_cmd_sender,
+            sig_verifier,
+            event_bus,
+            peer_db,
         );
 
-        let operation_propagation_thread =
-            start_propagation_thread(local_receiver, active_connections, config, cache);
+        let operation_propagation_thread = start_propagation_thread(
+            local_receiver,
+            active_connections,
+            config,
+            cache,
+            storage.clone_without_refs(),
+            production_schedule,
+        );
         Self {
             operation_retrieval_thread: Some((sender_retrieval_ext, operation_retrieval_thread)),
             operation_propagation_thread: Some((local_sender, operation_propagation_thread)),
         }
     }
 
+    /// Sends a reloaded config to the threads of the handler
+    pub fn update_config(&self, config: &ProtocolConfig) {
+        if let Some((tx, _)) = &self.operation_retrieval_thread {
+            let _ = tx.send(OperationHandlerRetrievalCommand::UpdateConfig(Box::new(
+                config.clone(),
+            )));
+        }
+        if let Some((tx, _)) = &self.operation_propagation_thread {
+            let _ = tx.send(OperationHandlerPropagationCommand::UpdateConfig(Box::new(
+                config.clone(),
+            )));
+        }
+    }
+
     pub fn stop(&mut self) {
         if let Some((tx, thread)) = self.operation_retrieval_thread.take() {
             let _ = tx.send(OperationHandlerRetrievalCommand::Stop);
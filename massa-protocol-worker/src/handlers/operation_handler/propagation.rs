@@ -1,29 +1,37 @@
-use std::{mem, num::NonZeroUsize, thread::JoinHandle};
+use std::thread::JoinHandle;
 
 use crossbeam::channel::{Receiver, RecvTimeoutError};
-use lru::LruCache;
 use massa_logging::massa_trace;
-use massa_models::operation::OperationId;
+use massa_models::{
+    operation::{OperationId, SecureShareOperation},
+    prehash::PreHashSet,
+};
 use massa_protocol_exports::ProtocolConfig;
+use massa_storage::Storage;
+use massa_time::MassaTime;
 use peernet::peer_id::PeerId;
 use tracing::{debug, info, log::warn};
 
 use crate::{
-    handlers::operation_handler::OperationMessage, messages::MessagesSerializer,
+    handlers::{gossip::split_fanout, operation_handler::OperationMessage},
+    messages::MessagesSerializer,
+    production_schedule::ProductionSchedule,
     wrap_network::ActiveConnectionsTrait,
 };
 
 use super::{
     cache::SharedOperationCache, commands_propagation::OperationHandlerPropagationCommand,
-    OperationMessageSerializer,
+    fairness::PropagationQueue, OperationMessageSerializer,
 };
 
 struct PropagationThread {
     internal_receiver: Receiver<OperationHandlerPropagationCommand>,
     active_connections: Box<dyn ActiveConnectionsTrait>,
-    operations_to_announce: Vec<OperationId>,
+    operations_to_announce: PropagationQueue,
     config: ProtocolConfig,
     cache: SharedOperationCache,
+    storage: Storage,
+    production_schedule: ProductionSchedule,
     operation_message_serializer: MessagesSerializer,
 }
 
@@ -34,35 +42,56 @@ impl PropagationThread {
             .expect("Can't init interval op propagation");
         loop {
             match self.internal_receiver.recv_deadline(next_announce) {
-                Ok(internal_message) => {
-                    match internal_message {
-                        OperationHandlerPropagationCommand::AnnounceOperations(operations_ids) => {
-                            // Note operations as checked.
-                            {
-                                let mut cache_write = self.cache.write();
-                                for op_id in operations_ids.iter().copied() {
-                                    cache_write.insert_checked_operation(op_id);
-                                }
-                            }
-                            self.operations_to_announce.extend(operations_ids);
-                            if self.operations_to_announce.len()
-                                > self.config.operation_announcement_buffer_capacity
-                            {
-                                self.announce_ops();
-                                next_announce = std::time::Instant::now()
-                                    .checked_add(
-                                        self.config.operation_announcement_interval.to_duration(),
-                                    )
-                                    .expect("Can't init interval op propagation");
-                            }
+                Ok(internal_message) => match internal_message {
+                    OperationHandlerPropagationCommand::AnnounceOperations(operations_ids) => {
+                        self.queue_operations(None, operations_ids, false);
+                        if self.operations_to_announce.len()
+                            > self.config.operation_announcement_buffer_capacity
+                            && self.quiet_until().is_none()
+                        {
+                            self.announce_ops();
+                            next_announce = std::time::Instant::now()
+                                .checked_add(
+                                    self.config.operation_announcement_interval.to_duration(),
+                                )
+                                .expect("Can't init interval op propagation");
                         }
-                        OperationHandlerPropagationCommand::Stop => {
-                            info!("Stop operation propagation thread");
-                            return;
+                    }
+                    OperationHandlerPropagationCommand::AnnounceReceivedOperations {
+                        peer_id,
+                        operations,
+                        quarantined,
+                    } => {
+                        self.queue_operations(Some(peer_id), operations, quarantined);
+                        if self.operations_to_announce.len()
+                            > self.config.operation_announcement_buffer_capacity
+                            && self.quiet_until().is_none()
+                        {
+                            self.announce_ops();
+                            next_announce = std::time::Instant::now()
+                                .checked_add(
+                                    self.config.operation_announcement_interval.to_duration(),
+                                )
+                                .expect("Can't init interval op propagation");
                         }
                     }
-                }
+                    OperationHandlerPropagationCommand::UpdateConfig(config) => {
+                        self.config = *config;
+                        next_announce = std::time::Instant::now()
+                            .checked_add(self.config.operation_announcement_interval.to_duration())
+                            .expect("Can't init interval op propagation");
+                    }
+                    OperationHandlerPropagationCommand::Stop => {
+                        info!("Stop operation propagation thread");
+                        return;
+                    }
+                },
                 Err(RecvTimeoutError::Timeout) => {
+                    // held around our productions, and announced as soon as the window ends
+                    if let Some(end) = self.quiet_until() && let Ok(end) = end.estimate_instant() {
+                        next_announce = end;
+                        continue;
+                    }
                     self.announce_ops();
                     next_announce = std::time::Instant::now()
                         .checked_add(self.config.operation_announcement_interval.to_duration())
@@ -75,80 +104,149 @@ impl PropagationThread {
         }
     }
 
+    /// End of the quiet window around one of our productions, if we are in one
+    fn quiet_until(&self) -> Option<MassaTime> {
+        self.production_schedule
+            .quiet_until(MassaTime::now().ok()?, self.config.gossip_quiet_margin)
+    }
+
+    /// Notes operations as checked and queues them for the next announcement
+    fn queue_operations(
+        &mut self,
+        origin: Option<PeerId>,
+        mut operations_ids: PreHashSet<OperationId>,
+        quarantined: bool,
+    ) {
+        {
+            let mut cache_write = self.cache.write();
+            for op_id in operations_ids.iter().copied() {
+                cache_write.insert_checked_operation(op_id);
+            }
+            // the operations relayed recently, possibly before a restart, are not relayed again
+            let relayed_ids = cache_write.relayed_ids.read();
+            operations_ids.retain(|op_id| !relayed_ids.operation_relayed(op_id));
+        }
+        if operations_ids.is_empty() {
+            return;
+        }
+        let dropped = self.operations_to_announce.push(
+            origin.clone(),
+            operations_ids,
+            quarantined,
+            self.config.operation_announcement_buffer_capacity,
+        );
+        if dropped > 0 {
+            debug!(
+                "announcement queue of {:?} is full: dropped {} operations",
+                origin, dropped
+            );
+        }
+    }
+
     fn announce_ops(&mut self) {
+        // each announcement takes at most a buffer of operations, the origins in turn
+        let operation_ids = self
+            .operations_to_announce
+            .pop(self.config.operation_announcement_buffer_capacity.max(1));
         // Quit if empty  to avoid iterating on nodes
-        if self.operations_to_announce.is_empty() {
+        if operation_ids.is_empty() {
             return;
         }
-        let operation_ids = mem::take(&mut self.operations_to_announce);
         massa_trace!("protocol.protocol_worker.announce_ops.begin", {
             "operation_ids": operation_ids
         });
+        let mut new_ops_by_peer = Vec::new();
         {
             let mut cache_write = self.cache.write();
-            let peers: Vec<PeerId> = cache_write
-                .ops_known_by_peer
-                .iter()
-                .map(|(id, _)| id.clone())
-                .collect();
+            // Clean shared cache if peers do not exist anymore, and add new potential peers
             let peers_connected = self.active_connections.get_peer_ids_connected();
-            // Clean shared cache if peers do not exist anymore
-
-            for peer_id in peers {
-                if !peers_connected.contains(&peer_id) {
-                    cache_write.ops_known_by_peer.pop(&peer_id);
-                }
-            }
-
-            // Add new potential peers
-            for peer_id in peers_connected {
-                if !cache_write.ops_known_by_peer.contains(&peer_id) {
-                    cache_write.ops_known_by_peer.put(
-                        peer_id.clone(),
-                        LruCache::new(
-                            NonZeroUsize::new(self.config.max_node_known_ops_size)
-                                .expect("max_node_known_endorsements_size in config is > 0"),
-                        ),
-                    );
-                }
-            }
+            cache_write.ops_known_by_peer.sync_peers(&peers_connected);
 
             // Propagate to peers
-            for (peer_id, ops) in cache_write.ops_known_by_peer.iter_mut() {
+            let mut announced_ids_skipped = 0;
+            for peer_id in cache_write.ops_known_by_peer.peer_ids() {
                 let new_ops: Vec<OperationId> = operation_ids
                     .iter()
-                    .filter(|id| !ops.contains(&id.prefix()))
+                    .filter(|id| !cache_write.ops_known_by_peer.knows(&peer_id, &id.prefix()))
                     .copied()
                     .collect();
+                announced_ids_skipped += (operation_ids.len() - new_ops.len()) as u64;
                 if !new_ops.is_empty() {
-                    for id in &new_ops {
-                        ops.put(id.prefix(), ());
+                    cache_write
+                        .ops_known_by_peer
+                        .insert_many(&peer_id, new_ops.iter().map(|id| (id.prefix(), ())));
+                    new_ops_by_peer.push((peer_id, new_ops));
+                }
+            }
+            cache_write.stats.announced_ids_skipped += announced_ids_skipped;
+            cache_write
+                .relayed_ids
+                .write()
+                .record_operations(operation_ids.iter().copied());
+        }
+
+        // a few random peers get the operations in full, the others only their ids
+        let (eager_peers, _) = split_fanout(
+            new_ops_by_peer
+                .iter()
+                .map(|(peer_id, _)| peer_id.clone())
+                .collect(),
+            self.config.operation_eager_fanout,
+        );
+        let mut announced_ids_sent = 0;
+        let mut ops_pushed = 0;
+        for (peer_id, new_ops) in new_ops_by_peer {
+            let mut to_announce = new_ops;
+            if eager_peers.contains(&peer_id) {
+                let operations: Vec<SecureShareOperation> = {
+                    let operations_reader = self.storage.read_operations();
+                    to_announce
+                        .iter()
+                        .filter_map(|id| operations_reader.get(id).cloned())
+                        .collect()
+                };
+                // the operations that left the storage in the meantime are only announced
+                to_announce.retain(|id| !operations.iter().any(|operation| operation.id == *id));
+                ops_pushed += operations.len() as u64;
+                debug!("Push {} operations to {}", operations.len(), peer_id);
+                for sub_list in operations.chunks(self.config.max_operations_per_message as usize) {
+                    if let Err(err) = self.active_connections.send_to_peer(
+                        &peer_id,
+                        &self.operation_message_serializer,
+                        OperationMessage::Operations(sub_list.to_vec()).into(),
+                    ) {
+                        warn!("Failed to send Operations message to peer: {}", err);
                     }
-                    debug!(
-                        "Send operations announcement of len {} to {}",
-                        new_ops.len(),
-                        peer_id
+                }
+            }
+            if to_announce.is_empty() {
+                continue;
+            }
+            announced_ids_sent += to_announce.len() as u64;
+            debug!(
+                "Send operations announcement of len {} to {}",
+                to_announce.len(),
+                peer_id
+            );
+            for sub_list in to_announce.chunks(self.config.max_operations_per_message as usize) {
+                if let Err(err) = self.active_connections.send_to_peer(
+                    &peer_id,
+                    &self.operation_message_serializer,
+                    OperationMessage::OperationsAnnouncement(
+                        sub_list.iter().map(|id| id.into_prefix()).collect(),
+                    )
+                    .into(),
+                ) {
+                    warn!(
+                        "Failed to send OperationsAnnouncement message to peer: {}",
+                        err
                     );
-                    for sub_list in new_ops.chunks(self.config.max_operations_per_message as usize)
-                    {
-                        if let Err(err) = self.active_connections.send_to_peer(
-                            peer_id,
-                            &self.operation_message_serializer,
-                            OperationMessage::OperationsAnnouncement(
-                                sub_list.iter().map(|id| id.into_prefix()).collect(),
-                            )
-                            .into(),
-                            false,
-                        ) {
-                            warn!(
-                                "Failed to send OperationsAnnouncement message to peer: {}",
-                                err
-                            );
-                        }
-                    }
                 }
             }
         }
+        let mut cache_write = self.cache.write();
+        cache_write.stats.announced_ids_sent += announced_ids_sent;
+        cache_write.stats.ops_pushed += ops_pushed;
     }
 }
 
@@ -157,6 +255,8 @@ pub fn start_propagation_thread(
     active_connections: Box<dyn ActiveConnectionsTrait>,
     config: ProtocolConfig,
     cache: SharedOperationCache,
+    storage: Storage,
+    production_schedule: ProductionSchedule,
 ) -> JoinHandle<()> {
     std::thread::Builder::new()
         .name("protocol-operation-handler-propagation".to_string())
@@ -164,9 +264,11 @@ pub fn start_propagation_thread(
             let mut propagation_thread = PropagationThread {
                 internal_receiver,
                 active_connections,
-                operations_to_announce: Vec::new(),
+                operations_to_announce: PropagationQueue::default(),
                 config,
                 cache,
+                storage,
+                production_schedule,
                 operation_message_serializer: MessagesSerializer::new()
                     .with_operation_message_serializer(OperationMessageSerializer::new()),
             };
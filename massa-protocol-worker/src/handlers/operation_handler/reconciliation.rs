@@ -0,0 +1,211 @@
+use std::ops::Bound::Included;
+
+use massa_models::operation::OperationPrefixId;
+use massa_serialization::{
+    Deserializer, SerializeError, Serializer, U32VarIntDeserializer, U32VarIntSerializer,
+    U64VarIntDeserializer, U64VarIntSerializer,
+};
+use nom::{
+    error::{context, ContextError, ParseError},
+    multi::length_count,
+    sequence::tuple,
+    IResult, Parser,
+};
+
+/// Maximum number of buckets in a digest, so that it always fits in one message
+pub const MAX_DIGEST_BUCKETS: u32 = 4096;
+
+/// Summary of a set of operations, used by two peers to find the operations one of them missed.
+///
+/// The prefix ids are spread in buckets, and each bucket is summarized by the number of
+/// operations it holds and the XOR of their fingerprints. Two peers holding the same operations
+/// have the same digest: the buckets that differ are the only ones whose operations need to be
+/// announced again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationsDigest {
+    /// number of operations and XOR of their fingerprints, by bucket
+    pub buckets: Vec<(u32, u64)>,
+}
+
+impl OperationsDigest {
+    /// Digest of a set of operations, over `bucket_count` buckets
+    pub fn compute<'a>(
+        prefixes: impl IntoIterator<Item = &'a OperationPrefixId>,
+        bucket_count: u32,
+    ) -> Self {
+        let bucket_count = bucket_count.clamp(1, MAX_DIGEST_BUCKETS);
+        let mut buckets = vec![(0u32, 0u64); bucket_count as usize];
+        for prefix in prefixes {
+            let (count, fingerprint) = &mut buckets[bucket_of(prefix, bucket_count)];
+            *count = count.saturating_add(1);
+            *fingerprint ^= fingerprint_of(prefix);
+        }
+        Self { buckets }
+    }
+
+    /// Indexes of the buckets that differ between the two digests.
+    /// Empty if the digests don't have the same number of buckets.
+    pub fn mismatching_buckets(&self, other: &OperationsDigest) -> Vec<usize> {
+        if self.buckets.len() != other.buckets.len() {
+            return Vec::new();
+        }
+        self.buckets
+            .iter()
+            .zip(other.buckets.iter())
+            .enumerate()
+            .filter(|(_, (ours, theirs))| ours != theirs)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Number of buckets of the digest
+    pub fn bucket_count(&self) -> u32 {
+        self.buckets.len() as u32
+    }
+}
+
+/// Bucket of an operation, from the second 8 bytes of its prefix
+pub fn bucket_of(prefix: &OperationPrefixId, bucket_count: u32) -> usize {
+    let bytes = Vec::<u8>::from(prefix);
+    let value = u64::from_be_bytes(
+        bytes[8..16]
+            .try_into()
+            .expect("operation prefix is shorter than 16 bytes"),
+    );
+    (value % bucket_count as u64) as usize
+}
+
+/// Fingerprint of an operation, from the first 8 bytes of its prefix
+fn fingerprint_of(prefix: &OperationPrefixId) -> u64 {
+    let bytes = Vec::<u8>::from(prefix);
+    u64::from_be_bytes(
+        bytes[..8]
+            .try_into()
+            .expect("operation prefix is shorter than 8 bytes"),
+    )
+}
+
+/// Serializer for `OperationsDigest`
+#[derive(Default, Clone)]
+pub struct OperationsDigestSerializer {
+    u32_serializer: U32VarIntSerializer,
+    u64_serializer: U64VarIntSerializer,
+}
+
+impl OperationsDigestSerializer {
+    pub fn new() -> Self {
+        Self {
+            u32_serializer: U32VarIntSerializer::new(),
+            u64_serializer: U64VarIntSerializer::new(),
+        }
+    }
+}
+
+impl Serializer<OperationsDigest> for OperationsDigestSerializer {
+    fn serialize(
+        &self,
+        value: &OperationsDigest,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        let bucket_count: u32 = value.buckets.len().try_into().map_err(|_| {
+            SerializeError::NumberTooBig("could not encode digest bucket count as u32".into())
+        })?;
+        self.u32_serializer.serialize(&bucket_count, buffer)?;
+        for (count, fingerprint) in &value.buckets {
+            self.u32_serializer.serialize(count, buffer)?;
+            self.u64_serializer.serialize(fingerprint, buffer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Deserializer for `OperationsDigest`
+pub struct OperationsDigestDeserializer {
+    length_deserializer: U32VarIntDeserializer,
+    count_deserializer: U32VarIntDeserializer,
+    fingerprint_deserializer: U64VarIntDeserializer,
+}
+
+impl OperationsDigestDeserializer {
+    pub fn new() -> Self {
+        Self {
+            length_deserializer: U32VarIntDeserializer::new(
+                Included(1),
+                Included(MAX_DIGEST_BUCKETS),
+            ),
+            count_deserializer: U32VarIntDeserializer::new(Included(0), Included(u32::MAX)),
+            fingerprint_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+        }
+    }
+}
+
+impl Default for OperationsDigestDeserializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deserializer<OperationsDigest> for OperationsDigestDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], OperationsDigest, E> {
+        context(
+            "Failed OperationsDigest deserialization",
+            length_count(
+                context("Failed length deserialization", |input| {
+                    self.length_deserializer.deserialize(input)
+                }),
+                tuple((
+                    context("Failed count deserialization", |input| {
+                        self.count_deserializer.deserialize(input)
+                    }),
+                    context("Failed fingerprint deserialization", |input| {
+                        self.fingerprint_deserializer.deserialize(input)
+                    }),
+                )),
+            ),
+        )
+        .map(|buckets| OperationsDigest { buckets })
+        .parse(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_hash::Hash;
+    use massa_models::{operation::OperationId, secure_share::Id};
+    use massa_serialization::DeserializeError;
+
+    fn prefix(index: u64) -> OperationPrefixId {
+        OperationId::new(Hash::compute_from(&index.to_be_bytes())).into_prefix()
+    }
+
+    #[test]
+    fn test_digest_finds_missing_operations() {
+        let ours: Vec<OperationPrefixId> = (0..100).map(prefix).collect();
+        let theirs: Vec<OperationPrefixId> = (0..100).filter(|i| *i != 42).map(prefix).collect();
+        let our_digest = OperationsDigest::compute(&ours, 16);
+        let their_digest = OperationsDigest::compute(&theirs, 16);
+
+        // only the bucket of the missing operation differs
+        assert_eq!(
+            our_digest.mismatching_buckets(&their_digest),
+            vec![bucket_of(&prefix(42), 16)]
+        );
+        assert!(our_digest
+            .mismatching_buckets(&OperationsDigest::compute(ours.iter().rev(), 16))
+            .is_empty());
+
+        let mut buffer = Vec::new();
+        OperationsDigestSerializer::new()
+            .serialize(&our_digest, &mut buffer)
+            .unwrap();
+        let (rest, deserialized) = OperationsDigestDeserializer::new()
+            .deserialize::<DeserializeError>(&buffer)
+            .unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(deserialized, our_digest);
+    }
+}
@@ -1,15 +1,14 @@
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     num::NonZeroUsize,
     thread::JoinHandle,
     time::Instant,
 };
 
 use crossbeam::{
-    channel::{tick, Receiver, Sender},
+    channel::{bounded, never, tick, Receiver, Sender, TrySendError},
     select,
 };
-use lru::LruCache;
 use massa_logging::massa_trace;
 use massa_models::{
     operation::{OperationId, OperationPrefixId, OperationPrefixIds, SecureShareOperation},
@@ -19,16 +18,25 @@ use massa_models::{
     timeslots::get_block_slot_timestamp,
 };
 use massa_pool_exports::PoolController;
-use massa_protocol_exports::{ProtocolConfig, ProtocolError};
+use massa_protocol_exports::{
+    MisbehaviorKind, ProtocolConfig, ProtocolError, ProtocolEvent, ProtocolEventBus,
+};
 use massa_serialization::{DeserializeError, Deserializer};
 use massa_storage::Storage;
 use massa_time::{MassaTime, TimeError};
 use peernet::peer_id::PeerId;
+use rand::seq::SliceRandom;
 
 use crate::{
-    handlers::peer_handler::models::{PeerManagementCmd, PeerMessageTuple},
+    handlers::{
+        peer_handler::{
+            models::{PeerManagementCmd, PeerMessageTuple, SharedPeerDB},
+            OPERATIONS_DIGEST_FEATURE,
+        },
+        request_manager::RequestManager,
+    },
     messages::MessagesSerializer,
-    sig_verifier::verify_sigs_batch,
+    sig_verifier::SigVerifierPool,
     wrap_network::ActiveConnectionsTrait,
 };
 use tracing::{debug, info, warn};
@@ -37,7 +45,9 @@ use super::{
     cache::SharedOperationCache,
     commands_propagation::OperationHandlerPropagationCommand,
     commands_retrieval::OperationHandlerRetrievalCommand,
+    fairness::OperationPeerScores,
     messages::{OperationMessage, OperationMessageDeserializer, OperationMessageDeserializerArgs},
+    reconciliation::{bucket_of, OperationsDigest},
     OperationMessageSerializer,
 };
 
@@ -53,11 +63,23 @@ pub struct OperationBatchItem {
     pub operations_prefix_ids: OperationPrefixIds,
 }
 
+/// Operations received from a peer, with the result of the verification of their signatures
+struct VerifiedOperations {
+    peer_id: PeerId,
+    /// operations whose signature was verified
+    new_operations: PreHashMap<OperationId, SecureShareOperation>,
+    /// all the operations received
+    received_ids: PreHashSet<OperationId>,
+    /// number of operations in the message
+    length: usize,
+    result: Result<(), ProtocolError>,
+}
+
 pub struct RetrievalThread {
     receiver: Receiver<PeerMessageTuple>,
     pool_controller: Box<dyn PoolController>,
     cache: SharedOperationCache,
-    asked_operations: LruCache<OperationPrefixId, (Instant, Vec<PeerId>)>,
+    asked_operations: RequestManager<OperationPrefixId>,
     active_connections: Box<dyn ActiveConnectionsTrait>,
     op_batch_buffer: VecDeque<OperationBatchItem>,
     stored_operations: HashMap<Instant, PreHashSet<OperationId>>,
@@ -67,23 +89,46 @@ pub struct RetrievalThread {
     receiver_ext: Receiver<OperationHandlerRetrievalCommand>,
     operation_message_serializer: MessagesSerializer,
     peer_cmd_sender: Sender<PeerManagementCmd>,
+    sig_verifier: SigVerifierPool,
+    verified_sender: Sender<VerifiedOperations>,
+    verified_receiver: Receiver<VerifiedOperations>,
+    /// last digest received from each peer, to limit the reconciliations a peer can trigger
+    digests_received: HashMap<PeerId, Instant>,
+    /// usefulness of the operations sent by each peer, to quarantine the spammers
+    peer_scores: OperationPeerScores,
+    event_bus: ProtocolEventBus,
+    peer_db: SharedPeerDB,
 }
 
 impl RetrievalThread {
+    fn message_deserializer(&self) -> OperationMessageDeserializer {
+        OperationMessageDeserializer::new(OperationMessageDeserializerArgs {
+            max_operations_prefix_ids: self.config.max_operations_per_message as u32,
+            max_operations: self.config.max_operations_per_message as u32,
+            max_datastore_value_length: self.config.max_op_datastore_value_length,
+            max_function_name_length: self.config.max_size_function_name,
+            max_parameters_size: self.config.max_size_call_sc_parameter,
+            max_op_datastore_entry_count: self.config.max_op_datastore_entry_count,
+            max_op_datastore_key_length: self.config.max_op_datastore_key_length,
+            max_op_datastore_value_length: self.config.max_op_datastore_value_length,
+        })
+    }
+
+    fn reconciliation_ticker(&self) -> Receiver<Instant> {
+        let reconciliation_interval = self.config.operation_reconciliation_interval.to_duration();
+        if reconciliation_interval.is_zero() {
+            never()
+        } else {
+            tick(reconciliation_interval)
+        }
+    }
+
     fn run(&mut self) {
-        let mut operation_message_deserializer =
-            OperationMessageDeserializer::new(OperationMessageDeserializerArgs {
-                max_operations_prefix_ids: self.config.max_operations_per_message as u32,
-                max_operations: self.config.max_operations_per_message as u32,
-                max_datastore_value_length: self.config.max_op_datastore_value_length,
-                max_function_name_length: self.config.max_size_function_name,
-                max_parameters_size: self.config.max_size_call_sc_parameter,
-                max_op_datastore_entry_count: self.config.max_op_datastore_entry_count,
-                max_op_datastore_key_length: self.config.max_op_datastore_key_length,
-                max_op_datastore_value_length: self.config.max_op_datastore_value_length,
-            });
-        let tick_ask_operations = tick(self.config.operation_batch_proc_period.to_duration());
-        let tick_clear_storage = tick(self.config.asked_operations_pruning_period.to_duration());
+        let mut operation_message_deserializer = self.message_deserializer();
+        let mut tick_ask_operations = tick(self.config.operation_batch_proc_period.to_duration());
+        let mut tick_clear_storage =
+            tick(self.config.asked_operations_pruning_period.to_duration());
+        let mut tick_reconciliation = self.reconciliation_ticker();
         loop {
             select! {
                 recv(self.receiver) -> msg => {
@@ -95,6 +140,9 @@ impl RetrievalThread {
                                     Ok((rest, message)) => (rest, message),
                                     Err(err) => {
                                         warn!("Error when deserializing message from peer {}: Err = {}", peer_id, err);
+                                        if let Err(err) = self.report_misbehavior(&peer_id, MisbehaviorKind::MalformedMessage, format!("operation message {}: {}", message_id, err), false) {
+                                            warn!("Error when reporting node: {}", err);
+                                        }
                                         continue;
                                     }
                                 };
@@ -108,10 +156,14 @@ impl RetrievalThread {
                                     if let Err(err) = self.note_operations_from_peer(ops, &peer_id) {
                                         warn!("peer {} sent us critically incorrect operation, which may be an attack attempt by the remote peer or a loss of sync between us and the remote peer. Err = {}", peer_id, err);
 
-                                        if let Err(e) = self.ban_node(&peer_id) {
+                                        if let Err(e) = self.ban_node(&peer_id, MisbehaviorKind::from_error(&err), format!("operations: {}", err)) {
                                             warn!("Error when banning node: {}", e);
                                         }
                                     }
+                                    // the operations verified in the meantime, or right away without verification threads
+                                    while let Ok(verified) = self.verified_receiver.try_recv() {
+                                        self.on_operations_verified(verified);
+                                    }
                                 }
                                 OperationMessage::OperationsAnnouncement(announcement) => {
                                     debug!("Received operation message: OperationsAnnouncement from {}", peer_id);
@@ -127,6 +179,10 @@ impl RetrievalThread {
                                         warn!("error when processing asked operations received from peer {}: Err = {}", peer_id, err);
                                     }
                                 }
+                                OperationMessage::OperationsDigest(digest) => {
+                                    debug!("Received operation message: OperationsDigest from {}", peer_id);
+                                    self.on_operations_digest_received(&peer_id, digest);
+                                }
                             }
                         }
                         Err(_) => {
@@ -142,6 +198,14 @@ impl RetrievalThread {
                                 info!("Stop operation retrieval thread");
                                 return;
                             }
+                            OperationHandlerRetrievalCommand::UpdateConfig(config) => {
+                                self.config = *config;
+                                self.peer_scores.update_config(&self.config);
+                                operation_message_deserializer = self.message_deserializer();
+                                tick_ask_operations = tick(self.config.operation_batch_proc_period.to_duration());
+                                tick_clear_storage = tick(self.config.asked_operations_pruning_period.to_duration());
+                                tick_reconciliation = self.reconciliation_ticker();
+                            }
                         },
                         Err(_) => {
                             info!("Stop operation retrieval thread");
@@ -149,6 +213,11 @@ impl RetrievalThread {
                         }
                     }
                 }
+                recv(self.verified_receiver) -> msg => {
+                    if let Ok(verified) = msg {
+                        self.on_operations_verified(verified);
+                    }
+                },
                 recv(tick_ask_operations) -> _ => {
                     if let Err(err) = self.update_ask_operation() {
                         warn!("Error in update_ask_operation: {}", err);
@@ -156,12 +225,18 @@ impl RetrievalThread {
                 },
                 recv(tick_clear_storage) -> _ => {
                     self.clear_storage();
+                },
+                recv(tick_reconciliation) -> _ => {
+                    self.send_operations_digest();
                 }
             }
         }
     }
 
     fn clear_storage(&mut self) {
+        let peers_connected = self.active_connections.get_peer_ids_connected();
+        self.peer_scores
+            .retain(|peer_id| peers_connected.contains(peer_id));
         self.stored_operations.retain(|instant, operations| {
             if instant.elapsed() > self.config.asked_operations_pruning_period.to_duration() {
                 self.storage.drop_operation_refs(operations);
@@ -200,13 +275,87 @@ impl RetrievalThread {
             };
         }
 
-        // optimized signature verification
-        verify_sigs_batch(
-            &new_operations
-                .iter()
-                .map(|(op_id, op)| (*op_id.get_hash(), op.signature, op.content_creator_pub_key))
-                .collect::<Vec<_>>(),
-        )?;
+        // the signatures are verified by the verification pool, which gives the result back
+        // to this thread
+        let signatures = new_operations
+            .iter()
+            .map(|(op_id, op)| (*op_id.get_hash(), op.signature, op.content_creator_pub_key))
+            .collect::<Vec<_>>();
+        let verified_sender = self.verified_sender.clone();
+        let peer_id = source_peer_id.clone();
+        let on_done = move |result| {
+            let verified = VerifiedOperations {
+                peer_id,
+                new_operations,
+                received_ids,
+                length,
+                result,
+            };
+            if let Err(TrySendError::Full(verified)) = verified_sender.try_send(verified) {
+                debug!(
+                    "verified operations channel is full: dropped {} operations of peer {}",
+                    verified.received_ids.len(),
+                    verified.peer_id
+                );
+            }
+        };
+        // the operations of a quarantined peer are verified after the others
+        if self.peer_scores.is_quarantined(source_peer_id) {
+            self.sig_verifier
+                .submit_quarantined(source_peer_id, signatures, on_done);
+        } else {
+            self.sig_verifier
+                .submit(source_peer_id, signatures, on_done);
+        }
+        Ok(())
+    }
+
+    /// Handles operations received from a peer once their signature is verified.
+    /// A wrong signature gets the peer banned.
+    fn on_operations_verified(&mut self, verified: VerifiedOperations) {
+        let peer_id = verified.peer_id.clone();
+        if let Err(err) = self.note_verified_operations(verified) {
+            warn!("peer {} sent us critically incorrect operation, which may be an attack attempt by the remote peer or a loss of sync between us and the remote peer. Err = {}", peer_id, err);
+
+            if let Err(e) = self.ban_node(
+                &peer_id,
+                MisbehaviorKind::from_error(&err),
+                format!("operations: {}", err),
+            ) {
+                warn!("Error when banning node: {}", e);
+            }
+        }
+    }
+
+    fn note_verified_operations(
+        &mut self,
+        verified: VerifiedOperations,
+    ) -> Result<(), ProtocolError> {
+        let VerifiedOperations {
+            peer_id,
+            new_operations,
+            received_ids,
+            length,
+            result,
+        } = verified;
+        let source_peer_id = &peer_id;
+        match result {
+            Ok(()) => {}
+            Err(ProtocolError::WrongSignature) => return Err(ProtocolError::WrongSignature),
+            Err(err) => {
+                debug!(
+                    "operations of peer {} dropped before their verification: {}",
+                    source_peer_id, err
+                );
+                return Ok(());
+            }
+        }
+
+        // the asks of these operations are answered
+        for op_id in received_ids.iter() {
+            self.asked_operations
+                .complete(source_peer_id, &op_id.prefix());
+        }
 
         {
             // add to checked operations
@@ -216,21 +365,22 @@ impl RetrievalThread {
             }
 
             // add to known ops
-            let known_ops =
-                cache_write
-                    .ops_known_by_peer
-                    .get_or_insert_mut(source_peer_id.clone(), || {
-                        LruCache::new(
-                            NonZeroUsize::new(self.config.max_node_known_ops_size)
-                                .expect("max_node_known_ops_size in config must be > 0"),
-                        )
-                    });
-            for id in received_ids {
-                known_ops.put(id.prefix(), ());
-            }
+            cache_write.ops_known_by_peer.insert_many(
+                source_peer_id,
+                received_ids.into_iter().map(|id| (id.prefix(), ())),
+            );
+            cache_write.stats.ops_received += length as u64;
         }
 
-        if !new_operations.is_empty() {
+        if new_operations.is_empty() {
+            self.peer_scores.record(source_peer_id, 0, length as u64);
+        } else {
+            if self.event_bus.is_listened() {
+                self.event_bus.publish(ProtocolEvent::OperationsReceived {
+                    operation_ids: new_operations.keys().copied().collect(),
+                    peer_id: source_peer_id.clone(),
+                });
+            }
             // Store operation, claim locally
             let mut ops = self.storage.clone_without_refs();
             ops.store_operations(new_operations.into_values().collect());
@@ -267,13 +417,23 @@ impl RetrievalThread {
             ops_to_propagate.drop_operation_refs(&operations_to_not_propagate);
             let to_announce: PreHashSet<OperationId> =
                 ops_to_propagate.get_op_refs().iter().copied().collect();
+            // the operations already known or too old to be propagated are useless
+            self.peer_scores.record(
+                source_peer_id,
+                to_announce.len() as u64,
+                length.saturating_sub(to_announce.len()) as u64,
+            );
             self.stored_operations
                 .insert(Instant::now(), to_announce.clone());
             self.storage.extend(ops_to_propagate);
             self.internal_sender
-                .send(OperationHandlerPropagationCommand::AnnounceOperations(
-                    to_announce,
-                ))
+                .send(
+                    OperationHandlerPropagationCommand::AnnounceReceivedOperations {
+                        peer_id: source_peer_id.clone(),
+                        operations: to_announce,
+                        quarantined: self.peer_scores.is_quarantined(source_peer_id),
+                    },
+                )
                 .map_err(|err| ProtocolError::SendError(err.to_string()))?;
             // Add to pool
             self.pool_controller.add_operations(ops);
@@ -310,24 +470,15 @@ impl RetrievalThread {
         // mark sender as knowing the ops
         {
             let mut cache_write = self.cache.write();
-            let known_ops =
-                cache_write
-                    .ops_known_by_peer
-                    .get_or_insert_mut(peer_id.clone(), || {
-                        LruCache::new(
-                            NonZeroUsize::new(self.config.max_node_known_ops_size)
-                                .expect("max_node_known_ops_size in config must be > 0"),
-                        )
-                    });
-            for prefix in &op_batch {
-                known_ops.put(*prefix, ());
-            }
-        }
+            cache_write
+                .ops_known_by_peer
+                .insert_many(peer_id, op_batch.iter().map(|prefix| (*prefix, ())));
 
-        // filter out the operations that we already know about
-        {
-            let cache_read = self.cache.read();
-            op_batch.retain(|prefix| !cache_read.checked_operations_prefix.contains(prefix));
+            // filter out the operations that we already know about
+            let announced_count = op_batch.len();
+            op_batch.retain(|prefix| !cache_write.checked_operations_prefix.contains(prefix));
+            cache_write.stats.announced_ids_received += announced_count as u64;
+            cache_write.stats.announced_ids_known += (announced_count - op_batch.len()) as u64;
         }
 
         let mut ask_set = OperationPrefixIds::with_capacity(op_batch.len());
@@ -336,35 +487,25 @@ impl RetrievalThread {
         let now = Instant::now();
         let mut count_reask = 0;
         for op_id in op_batch {
-            let wish = match self.asked_operations.get_mut(&op_id) {
-                Some(wish) => {
-                    if wish.1.contains(peer_id) {
-                        continue; // already asked to the `peer_id`
-                    } else {
-                        Some(wish) // already asked but at someone else
-                    }
-                }
-                None => None,
-            };
-            if let Some(wish) = wish {
+            if self.asked_operations.was_asked_to(&op_id, peer_id) {
+                continue; // already asked to the `peer_id`
+            }
+            if let Some(last_sent) = self.asked_operations.last_sent(&op_id) {
+                // already asked but at someone else
                 // Ask now if latest ask instant < now - operation_batch_proc_period
                 // otherwise add in future_set
-                if wish.0
+                if last_sent
                     < now
                         .checked_sub(self.config.operation_batch_proc_period.into())
                         .ok_or(TimeError::TimeOverflowError)?
                 {
                     count_reask += 1;
                     ask_set.insert(op_id);
-                    wish.0 = now;
-                    wish.1.push(peer_id.clone());
                 } else {
                     future_set.insert(op_id);
                 }
             } else {
                 ask_set.insert(op_id);
-                self.asked_operations
-                    .put(op_id, (now, vec![peer_id.clone()]));
             }
         } // EndOf for op_id in op_batch:
 
@@ -382,17 +523,26 @@ impl RetrievalThread {
                 operations_prefix_ids: future_set,
             });
         }
+        self.ask_operations(peer_id, ask_set, now);
+        Ok(())
+    }
+
+    /// Ask operations to a peer, registering the requests to follow their answer
+    fn ask_operations(&mut self, peer_id: &PeerId, ask_set: OperationPrefixIds, now: Instant) {
         if !ask_set.is_empty() {
             debug!(
                 "Send ask operations of len {} to {}",
                 ask_set.len(),
                 peer_id
             );
+            self.cache.write().stats.asked_ids_sent += ask_set.len() as u64;
             for sub_list in ask_set
                 .into_iter()
                 .collect::<Vec<OperationPrefixId>>()
                 .chunks(self.config.max_operations_per_message as usize)
             {
+                self.asked_operations
+                    .send(peer_id, sub_list.iter().copied(), now);
                 if let Err(err) = self.active_connections.send_to_peer(
                     peer_id,
                     &self.operation_message_serializer,
@@ -400,17 +550,16 @@ impl RetrievalThread {
                         sub_list.iter().cloned().collect::<OperationPrefixIds>(),
                     )
                     .into(),
-                    false,
                 ) {
                     warn!("Failed to send AskForOperations message to peer: {}", err);
                     {
                         let mut cache_write = self.cache.write();
-                        cache_write.ops_known_by_peer.pop(peer_id);
+                        cache_write.ops_known_by_peer.remove_peer(peer_id);
                     }
+                    self.asked_operations.remove_peer(peer_id);
                 }
             }
         }
-        Ok(())
     }
 
     fn update_ask_operation(&mut self) -> Result<(), ProtocolError> {
@@ -425,6 +574,58 @@ impl RetrievalThread {
                 &op_batch_item.peer_id,
             )?;
         }
+
+        // ask the operations that were not received in time to another peer that announced them
+        let mut retries: HashMap<PeerId, OperationPrefixIds> = HashMap::new();
+        {
+            let cache_read = self.cache.read();
+            let peers = cache_read.ops_known_by_peer.peer_ids();
+            for expired in self.asked_operations.expire(now) {
+                if !expired.can_retry || cache_read.checked_operations_prefix.contains(&expired.key)
+                {
+                    continue;
+                }
+                let alternate = peers.iter().find(|peer_id| {
+                    cache_read
+                        .ops_known_by_peer
+                        .peek(peer_id, &expired.key)
+                        .is_some()
+                        && !self.asked_operations.was_asked_to(&expired.key, peer_id)
+                        && !retries
+                            .values()
+                            .any(|prefixes| prefixes.contains(&expired.key))
+                });
+                if let Some(peer_id) = alternate {
+                    retries
+                        .entry(peer_id.clone())
+                        .or_default()
+                        .insert(expired.key);
+                }
+            }
+        }
+        for (peer_id, prefixes) in retries {
+            massa_trace!("re-ask operations.", { "count": prefixes.len() });
+            self.ask_operations(&peer_id, prefixes, now);
+        }
+
+        // ban the connected peers that leave our requests unanswered
+        let unresponsive = self.asked_operations.take_unresponsive_peers();
+        if !unresponsive.is_empty() {
+            let peers_connected = self.active_connections.get_peer_ids_connected();
+            for peer_id in unresponsive {
+                if !peers_connected.contains(&peer_id) {
+                    continue;
+                }
+                warn!("Peer {} doesn't answer our operation requests", peer_id);
+                if let Err(err) = self.ban_node(
+                    &peer_id,
+                    MisbehaviorKind::ProtocolViolation,
+                    "operation requests left unanswered".to_string(),
+                ) {
+                    warn!("Error when banning node: {}", err);
+                }
+            }
+        }
         Ok(())
     }
 
@@ -440,6 +641,7 @@ impl RetrievalThread {
         if op_pre_ids.is_empty() {
             return Ok(());
         }
+        let asked_count = op_pre_ids.len();
 
         let mut ops: Vec<SecureShareOperation> = Vec::with_capacity(op_pre_ids.len());
         {
@@ -458,29 +660,184 @@ impl RetrievalThread {
                 }
             }
         }
+        {
+            // the peer will know the operations we send, no need to announce them to it later
+            let mut cache_write = self.cache.write();
+            cache_write.stats.asked_ids_received += asked_count as u64;
+            cache_write.stats.ops_sent += ops.len() as u64;
+            cache_write
+                .ops_known_by_peer
+                .insert_many(peer_id, ops.iter().map(|op| (op.id.prefix(), ())));
+        }
         debug!("Send full operations of len {} to {}", ops.len(), peer_id);
         for sub_list in ops.chunks(self.config.max_operations_per_message as usize) {
             if let Err(err) = self.active_connections.send_to_peer(
                 peer_id,
                 &self.operation_message_serializer,
                 OperationMessage::Operations(sub_list.to_vec()).into(),
-                false,
             ) {
                 warn!("Failed to send Operations message to peer: {}", err);
                 {
                     let mut cache_write = self.cache.write();
-                    cache_write.ops_known_by_peer.pop(peer_id);
+                    cache_write.ops_known_by_peer.remove_peer(peer_id);
                 }
             }
         }
         Ok(())
     }
 
+    /// Prefix ids of the operations we can send to a peer, the most recently checked first
+    fn reconciliation_set(&self) -> Vec<OperationPrefixId> {
+        let prefixes: Vec<OperationPrefixId> = self
+            .cache
+            .read()
+            .checked_operations_prefix
+            .iter()
+            .map(|(prefix, _)| *prefix)
+            .collect();
+        let stored_ops = self.storage.read_operations();
+        prefixes
+            .into_iter()
+            .filter(|prefix| {
+                stored_ops
+                    .get_operations_by_prefix(prefix)
+                    .map_or(false, |ids| !ids.is_empty())
+            })
+            .take(self.config.operation_reconciliation_max_ids)
+            .collect()
+    }
+
+    /// Sends the digest of our operations to a random peer among the ones that support the
+    /// reconciliation, so that it announces us the operations we missed
+    fn send_operations_digest(&mut self) {
+        let now = Instant::now();
+        let interval = self.config.operation_reconciliation_interval.to_duration();
+        self.digests_received
+            .retain(|_, received| now.saturating_duration_since(*received) < interval);
+
+        let peers: Vec<PeerId> = {
+            let peer_db = self.peer_db.snapshot();
+            self.active_connections
+                .get_peer_ids_connected()
+                .into_iter()
+                .filter(|peer_id| {
+                    peer_db
+                        .peers
+                        .get(peer_id)
+                        .and_then(|info| info.metadata.as_ref())
+                        .map_or(false, |metadata| {
+                            metadata.supports(OPERATIONS_DIGEST_FEATURE)
+                        })
+                })
+                .collect()
+        };
+        let Some(peer_id) = peers.choose(&mut rand::thread_rng()) else {
+            return;
+        };
+        let digest = OperationsDigest::compute(
+            &self.reconciliation_set(),
+            self.config.operation_reconciliation_buckets,
+        );
+        debug!("Send operations digest to {}", peer_id);
+        if let Err(err) = self.active_connections.send_to_peer(
+            peer_id,
+            &self.operation_message_serializer,
+            OperationMessage::OperationsDigest(digest).into(),
+        ) {
+            warn!("Failed to send OperationsDigest message to peer: {}", err);
+        }
+    }
+
+    /// Announces to a peer the operations of the buckets where its digest differs from ours
+    fn on_operations_digest_received(&mut self, peer_id: &PeerId, digest: OperationsDigest) {
+        let interval = self.config.operation_reconciliation_interval.to_duration();
+        if interval.is_zero() {
+            return;
+        }
+        // a peer can't make us go through our operations more than twice per interval
+        let now = Instant::now();
+        if let Some(received) = self.digests_received.get(peer_id)
+            && now.saturating_duration_since(*received) < interval / 2
+        {
+            debug!("Ignored operations digest of peer {}: received too soon", peer_id);
+            return;
+        }
+        self.digests_received.insert(peer_id.clone(), now);
+
+        let ours = self.reconciliation_set();
+        let bucket_count = digest.bucket_count();
+        let mismatching: HashSet<usize> = OperationsDigest::compute(&ours, bucket_count)
+            .mismatching_buckets(&digest)
+            .into_iter()
+            .collect();
+        if mismatching.is_empty() {
+            return;
+        }
+        let to_announce: OperationPrefixIds = {
+            let mut cache_write = self.cache.write();
+            let prefixes: OperationPrefixIds = ours
+                .into_iter()
+                .filter(|prefix| {
+                    mismatching.contains(&bucket_of(prefix, bucket_count))
+                        && cache_write
+                            .ops_known_by_peer
+                            .peek(peer_id, prefix)
+                            .is_none()
+                })
+                .take(self.config.max_operations_per_message as usize)
+                .collect();
+            cache_write
+                .ops_known_by_peer
+                .insert_many(peer_id, prefixes.iter().map(|prefix| (*prefix, ())));
+            prefixes
+        };
+        if to_announce.is_empty() {
+            return;
+        }
+        debug!(
+            "Send operations missed by {} after reconciliation, len {}",
+            peer_id,
+            to_announce.len()
+        );
+        if let Err(err) = self.active_connections.send_to_peer(
+            peer_id,
+            &self.operation_message_serializer,
+            OperationMessage::OperationsAnnouncement(to_announce).into(),
+        ) {
+            warn!(
+                "Failed to send OperationsAnnouncement message to peer: {}",
+                err
+            );
+        }
+    }
+
     /// send a ban peer command to the peer handler
-    fn ban_node(&mut self, peer_id: &PeerId) -> Result<(), ProtocolError> {
+    fn ban_node(
+        &mut self,
+        peer_id: &PeerId,
+        kind: MisbehaviorKind,
+        evidence: String,
+    ) -> Result<(), ProtocolError> {
         massa_trace!("ban node from retrieval thread", { "peer_id": peer_id.to_string() });
+        self.report_misbehavior(peer_id, kind, evidence, true)
+    }
+
+    /// send an offense of a peer to the misbehavior ledger of the peer handler, which bans
+    /// the peer if `ban` is set or if it committed too many offenses
+    fn report_misbehavior(
+        &mut self,
+        peer_id: &PeerId,
+        kind: MisbehaviorKind,
+        evidence: String,
+        ban: bool,
+    ) -> Result<(), ProtocolError> {
         self.peer_cmd_sender
-            .send(PeerManagementCmd::Ban(vec![peer_id.clone()]))
+            .send(PeerManagementCmd::ReportMisbehavior {
+                peer_id: peer_id.clone(),
+                kind,
+                evidence,
+                ban,
+            })
             .map_err(|err| ProtocolError::SendError(err.to_string()))
     }
 }
@@ -496,10 +853,15 @@ pub fn start_retrieval_thread(
     receiver_ext: Receiver<OperationHandlerRetrievalCommand>,
     internal_sender: Sender<OperationHandlerPropagationCommand>,
     peer_cmd_sender: Sender<PeerManagementCmd>,
+    sig_verifier: SigVerifierPool,
+    event_bus: ProtocolEventBus,
+    peer_db: SharedPeerDB,
 ) -> JoinHandle<()> {
     std::thread::Builder::new()
         .name("protocol-operation-handler-retrieval".to_string())
         .spawn(move || {
+            let (verified_sender, verified_receiver) =
+                bounded(config.max_size_channel_network_to_operation_handler);
             let mut retrieval_thread = RetrievalThread {
                 receiver,
                 pool_controller,
@@ -509,15 +871,25 @@ pub fn start_retrieval_thread(
                 receiver_ext,
                 cache,
                 active_connections,
-                asked_operations: LruCache::new(
+                asked_operations: RequestManager::new(
                     NonZeroUsize::new(config.asked_operations_buffer_capacity)
                         .expect("asked_operations_buffer_capacity in config must be > 0"),
+                    config.operation_batch_proc_period.to_duration(),
+                    config.max_operation_ask_attempts,
+                    config.max_unanswered_requests,
                 ),
+                peer_scores: OperationPeerScores::from_config(&config),
                 config,
                 operation_message_serializer: MessagesSerializer::new()
                     .with_operation_message_serializer(OperationMessageSerializer::new()),
                 op_batch_buffer: VecDeque::new(),
                 peer_cmd_sender,
+                sig_verifier,
+                verified_sender,
+                verified_receiver,
+                digests_received: HashMap::new(),
+                event_bus,
+                peer_db,
             };
             retrieval_thread.run();
         })
@@ -0,0 +1,362 @@
+//! Addresses tested by the testers, split in two tables like the address manager of Bitcoin.
+//!
+//! The new table holds the addresses gossiped to us that never passed a handshake, the tried
+//! table the ones that passed one at least once. Gossip only fills the new table, so that a
+//! peer announcing many addresses can't push out the addresses we already reached. Both tables
+//! are capped in total and per address bucket, with their own eviction rules:
+//! - in the new table, the addresses that failed the most tests then the oldest ones are evicted
+//!   first, and an address is dropped once it failed `max_failures` tests;
+//! - in the tried table, the address that succeeded least recently is evicted first, and goes
+//!   back to the new table like an address that failed `max_failures` tests in a row.
+//!
+//! The testers retest the addresses of both tables in turn, picking the tried table half of
+//! the time so that a flooded new table can't starve it.
+
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, HashMap},
+    net::SocketAddr,
+};
+
+use massa_protocol_exports::ProtocolConfig;
+use massa_time::MassaTime;
+use peernet::peer_id::PeerId;
+use rand::Rng;
+
+use super::models::AddressBucket;
+
+/// Address that never passed a handshake
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewAddress {
+    /// Time at which the address was first tested
+    pub first_seen: MassaTime,
+    /// Time of the last test of the address
+    pub last_attempt: MassaTime,
+    /// Number of failed tests
+    pub failures: u32,
+}
+
+/// Address that passed a handshake at least once
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TriedAddress {
+    /// Peer that answered on the address the last time
+    pub peer_id: PeerId,
+    /// Time of the last successful test
+    pub last_success: MassaTime,
+    /// Time of the last test of the address
+    pub last_attempt: MassaTime,
+    /// Number of failed tests since the last successful one
+    pub failures: u32,
+}
+
+/// Limits of the tables (0 for no limit)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AddressTablesPolicy {
+    pub max_new: usize,
+    pub max_tried: usize,
+    /// maximum number of addresses of each table in the same address bucket
+    pub max_per_bucket: usize,
+    /// failed tests after which an address leaves its table
+    pub max_failures: u32,
+}
+
+impl AddressTablesPolicy {
+    pub fn from_config(config: &ProtocolConfig) -> Self {
+        Self {
+            max_new: config.max_new_addresses,
+            max_tried: config.max_tried_addresses,
+            max_per_bucket: config.max_peers_per_address_bucket,
+            max_failures: config.max_address_failures,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AddressTables {
+    pub new: HashMap<SocketAddr, NewAddress>,
+    pub tried: HashMap<SocketAddr, TriedAddress>,
+    pub policy: AddressTablesPolicy,
+}
+
+/// Whether `count` addresses exceed the `max` of a policy
+fn exceeds(count: usize, max: usize) -> bool {
+    max > 0 && count > max
+}
+
+impl AddressTables {
+    pub fn new(policy: AddressTablesPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// Time of the last test of an address, in any table
+    pub fn last_attempt(&self, addr: &SocketAddr) -> Option<MassaTime> {
+        self.tried
+            .get(addr)
+            .map(|tried| tried.last_attempt)
+            .or_else(|| self.new.get(addr).map(|new| new.last_attempt))
+    }
+
+    /// Record the start of the test of an address. Unknown addresses enter the new table.
+    pub fn record_attempt(&mut self, addr: SocketAddr, now: MassaTime) {
+        if let Some(tried) = self.tried.get_mut(&addr) {
+            tried.last_attempt = now;
+            return;
+        }
+        match self.new.entry(addr) {
+            Entry::Occupied(mut entry) => entry.get_mut().last_attempt = now,
+            Entry::Vacant(entry) => {
+                entry.insert(NewAddress {
+                    first_seen: now,
+                    last_attempt: now,
+                    failures: 0,
+                });
+                self.enforce_new_caps(&addr);
+            }
+        }
+    }
+
+    /// Record a handshake passed by `peer_id` on an address, which moves to the tried table
+    pub fn record_success(&mut self, addr: SocketAddr, peer_id: &PeerId, now: MassaTime) {
+        self.new.remove(&addr);
+        let was_tried = self
+            .tried
+            .insert(
+                addr,
+                TriedAddress {
+                    peer_id: peer_id.clone(),
+                    last_success: now,
+                    last_attempt: now,
+                    failures: 0,
+                },
+            )
+            .is_some();
+        if !was_tried {
+            self.enforce_tried_caps(&addr, now);
+        }
+    }
+
+    /// Record a failed test of an address
+    pub fn record_failure(&mut self, addr: SocketAddr, now: MassaTime) {
+        let max_failures = self.policy.max_failures;
+        if let Some(tried) = self.tried.get_mut(&addr) {
+            tried.failures += 1;
+            if max_failures > 0 && tried.failures >= max_failures {
+                self.tried.remove(&addr);
+                self.demote(addr, now);
+            }
+        } else if let Some(new) = self.new.get_mut(&addr) {
+            new.failures += 1;
+            if max_failures > 0 && new.failures >= max_failures {
+                self.new.remove(&addr);
+            }
+        }
+    }
+
+    /// Address to retest, whose last test is older than `cooldown`. The tried table is picked
+    /// half of the time, and the address tested the longest ago in it.
+    pub fn select_to_retest(
+        &self,
+        cooldown: MassaTime,
+        now: MassaTime,
+        rng: &mut impl Rng,
+    ) -> Option<SocketAddr> {
+        let oldest = |attempts: &mut dyn Iterator<Item = (&SocketAddr, MassaTime)>| {
+            attempts
+                .filter(|(_, last_attempt)| now.saturating_sub(*last_attempt) > cooldown)
+                .min_by_key(|(_, last_attempt)| *last_attempt)
+                .map(|(addr, _)| *addr)
+        };
+        let tried = || oldest(&mut self.tried.iter().map(|(a, t)| (a, t.last_attempt)));
+        let new = || oldest(&mut self.new.iter().map(|(a, n)| (a, n.last_attempt)));
+        if rng.gen_bool(0.5) {
+            tried().or_else(new)
+        } else {
+            new().or_else(tried)
+        }
+    }
+
+    /// Put an address evicted from the tried table back in the new table
+    fn demote(&mut self, addr: SocketAddr, now: MassaTime) {
+        self.new.insert(
+            addr,
+            NewAddress {
+                first_seen: now,
+                last_attempt: now,
+                failures: 0,
+            },
+        );
+        self.enforce_new_caps(&addr);
+    }
+
+    /// Evict the addresses that failed the most tests, then the oldest ones, from the bucket of
+    /// `addr` and from the whole new table. `addr` itself is kept.
+    fn enforce_new_caps(&mut self, addr: &SocketAddr) {
+        let bucket = AddressBucket::from_ip(addr.ip());
+        let worst = |new: &HashMap<SocketAddr, NewAddress>, in_bucket: bool| {
+            new.iter()
+                .filter(|(other, _)| {
+                    *other != addr && (!in_bucket || AddressBucket::from_ip(other.ip()) == bucket)
+                })
+                .max_by_key(|(_, new)| (new.failures, Reverse(new.first_seen)))
+                .map(|(other, _)| *other)
+        };
+        while exceeds(
+            self.bucket_count(&self.new, bucket),
+            self.policy.max_per_bucket,
+        ) {
+            let Some(evicted) = worst(&self.new, true) else {
+                break;
+            };
+            self.new.remove(&evicted);
+        }
+        while exceeds(self.new.len(), self.policy.max_new) {
+            let Some(evicted) = worst(&self.new, false) else {
+                break;
+            };
+            self.new.remove(&evicted);
+        }
+    }
+
+    /// Move the addresses that succeeded least recently from the bucket of `addr` and from the
+    /// whole tried table back to the new table. `addr` itself is kept.
+    fn enforce_tried_caps(&mut self, addr: &SocketAddr, now: MassaTime) {
+        let bucket = AddressBucket::from_ip(addr.ip());
+        let least_recent = |tried: &HashMap<SocketAddr, TriedAddress>, in_bucket: bool| {
+            tried
+                .iter()
+                .filter(|(other, _)| {
+                    *other != addr && (!in_bucket || AddressBucket::from_ip(other.ip()) == bucket)
+                })
+                .min_by_key(|(_, tried)| tried.last_success)
+                .map(|(other, _)| *other)
+        };
+        while exceeds(
+            self.bucket_count(&self.tried, bucket),
+            self.policy.max_per_bucket,
+        ) {
+            let Some(evicted) = least_recent(&self.tried, true) else {
+                break;
+            };
+            self.tried.remove(&evicted);
+            self.demote(evicted, now);
+        }
+        while exceeds(self.tried.len(), self.policy.max_tried) {
+            let Some(evicted) = least_recent(&self.tried, false) else {
+                break;
+            };
+            self.tried.remove(&evicted);
+            self.demote(evicted, now);
+        }
+    }
+
+    fn bucket_count<T>(&self, table: &HashMap<SocketAddr, T>, bucket: AddressBucket) -> usize {
+        table
+            .keys()
+            .filter(|addr| AddressBucket::from_ip(addr.ip()) == bucket)
+            .count()
+    }
+
+    /// Check that no address is in both tables and that the tables respect their caps
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if let Some(addr) = self.new.keys().find(|addr| self.tried.contains_key(addr)) {
+            return Err(format!(
+                "address {} is in both the new and tried tables",
+                addr
+            ));
+        }
+        if exceeds(self.new.len(), self.policy.max_new) {
+            return Err(format!("{} addresses in the new table", self.new.len()));
+        }
+        if exceeds(self.tried.len(), self.policy.max_tried) {
+            return Err(format!("{} addresses in the tried table", self.tried.len()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use massa_time::MassaTime;
+    use peernet::{peer_id::PeerId, types::KeyPair};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{AddressTables, AddressTablesPolicy};
+
+    fn addr(ip: &str) -> SocketAddr {
+        format!("{}:31244", ip).parse().unwrap()
+    }
+
+    #[test]
+    fn test_addresses_move_between_tables() {
+        let mut tables = AddressTables::new(AddressTablesPolicy {
+            max_new: 3,
+            max_tried: 1,
+            max_per_bucket: 2,
+            max_failures: 2,
+        });
+        let peer_id = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        let at = MassaTime::from_millis;
+
+        // gossiped addresses only fill the new table, capped per bucket and in total
+        tables.record_attempt(addr("82.245.0.1"), at(1));
+        tables.record_attempt(addr("82.245.0.2"), at(2));
+        tables.record_attempt(addr("82.245.0.3"), at(3));
+        assert!(!tables.new.contains_key(&addr("82.245.0.1")));
+        tables.record_attempt(addr("91.12.0.1"), at(4));
+        tables.record_attempt(addr("145.3.0.1"), at(5));
+        assert_eq!(tables.new.len(), 3);
+        assert_eq!(tables.check_invariants(), Ok(()));
+
+        // a handshake moves the address to the tried table, evicting the least recent one
+        tables.record_success(addr("91.12.0.1"), &peer_id, at(6));
+        assert!(tables.tried.contains_key(&addr("91.12.0.1")));
+        assert!(!tables.new.contains_key(&addr("91.12.0.1")));
+        tables.record_success(addr("145.3.0.1"), &peer_id, at(7));
+        assert!(tables.tried.contains_key(&addr("145.3.0.1")));
+        assert!(tables.new.contains_key(&addr("91.12.0.1")));
+        assert_eq!(tables.check_invariants(), Ok(()));
+
+        // failing tests demote a tried address and drop a new one
+        tables.record_failure(addr("145.3.0.1"), at(8));
+        tables.record_failure(addr("145.3.0.1"), at(9));
+        assert!(tables.tried.is_empty());
+        assert_eq!(tables.new[&addr("145.3.0.1")].failures, 0);
+        tables.record_failure(addr("91.12.0.1"), at(10));
+        tables.record_failure(addr("91.12.0.1"), at(11));
+        assert!(!tables.new.contains_key(&addr("91.12.0.1")));
+        assert_eq!(tables.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_select_to_retest() {
+        let mut tables = AddressTables::default();
+        let peer_id = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        let mut rng = StdRng::seed_from_u64(0);
+        tables.record_attempt(addr("82.245.0.1"), MassaTime::from_millis(1000));
+        tables.record_success(addr("91.12.0.1"), &peer_id, MassaTime::from_millis(2000));
+
+        // nothing to retest during the cooldown
+        let cooldown = MassaTime::from_millis(5000);
+        assert_eq!(
+            tables.select_to_retest(cooldown, MassaTime::from_millis(6500), &mut rng),
+            Some(addr("82.245.0.1"))
+        );
+        assert_eq!(
+            tables.select_to_retest(cooldown, MassaTime::from_millis(5500), &mut rng),
+            None
+        );
+
+        // both tables are picked from
+        let picked: std::collections::HashSet<_> = (0..50)
+            .filter_map(|_| {
+                tables.select_to_retest(cooldown, MassaTime::from_millis(10_000), &mut rng)
+            })
+            .collect();
+        assert_eq!(picked.len(), 2);
+    }
+}
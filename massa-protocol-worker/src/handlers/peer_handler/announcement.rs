@@ -113,13 +113,8 @@ impl Deserializer<Announcement> for AnnouncementDeserializer {
                     }),
                 ),
                 context("Failed timestamp deserialization", |buffer: &'a [u8]| {
-                    let timestamp = u128::from_be_bytes(buffer[..16].try_into().map_err(|_| {
-                        nom::Err::Error(ParseError::from_error_kind(
-                            buffer,
-                            nom::error::ErrorKind::LengthValue,
-                        ))
-                    })?);
-                    Ok((&buffer[16..], timestamp))
+                    let (rest, timestamp) = nom::number::complete::be_u128(buffer)?;
+                    Ok((rest, timestamp))
                 }),
             )),
         )
@@ -127,20 +122,23 @@ impl Deserializer<Announcement> for AnnouncementDeserializer {
         .parse(buffer)?;
         let serialized = buffer[..buffer.len() - rest.len()].to_vec();
         let hash = Hash::compute_from(&serialized);
-        let signature = Signature::from_bytes(&rest[..64].try_into().map_err(|_| {
-            nom::Err::Error(ParseError::from_error_kind(
-                rest,
-                nom::error::ErrorKind::LengthValue,
-            ))
-        })?)
-        .map_err(|_| {
+        let signature_bytes = rest
+            .get(..64)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| {
+                nom::Err::Error(ParseError::from_error_kind(
+                    rest,
+                    nom::error::ErrorKind::LengthValue,
+                ))
+            })?;
+        let signature = Signature::from_bytes(signature_bytes).map_err(|_| {
             nom::Err::Error(ParseError::from_error_kind(
                 rest,
                 nom::error::ErrorKind::Verify,
             ))
         })?;
         Ok((
-            rest,
+            &rest[64..],
             Announcement {
                 listeners: listeners.into_iter().collect(),
                 hash,
@@ -205,6 +203,91 @@ impl Announcement {
     }
 }
 
+/// Listeners and routable IP announced by the node, with their signed announcement.
+/// A fresh announcement is signed each time they change, replacing the stale one.
+pub struct LocalAnnouncement {
+    keypair: KeyPair,
+    listeners: HashMap<SocketAddr, TransportType>,
+    routable_ip: Option<IpAddr>,
+    announcement: Announcement,
+}
+
+impl LocalAnnouncement {
+    pub fn new(
+        keypair: KeyPair,
+        listeners: HashMap<SocketAddr, TransportType>,
+        routable_ip: Option<IpAddr>,
+    ) -> PeerNetResult<Self> {
+        let announcement = Announcement::new(listeners.clone(), routable_ip, &keypair)?;
+        Ok(Self {
+            keypair,
+            listeners,
+            routable_ip,
+            announcement,
+        })
+    }
+
+    /// Local addresses of the listeners
+    pub fn listeners(&self) -> &HashMap<SocketAddr, TransportType> {
+        &self.listeners
+    }
+
+    pub fn routable_ip(&self) -> Option<IpAddr> {
+        self.routable_ip
+    }
+
+    /// Keypair of the node, signing the announcements
+    pub fn keypair(&self) -> &KeyPair {
+        &self.keypair
+    }
+
+    /// Current signed announcement
+    pub fn announcement(&self) -> &Announcement {
+        &self.announcement
+    }
+
+    /// Listeners as reachable by the other peers, `None` without a routable IP
+    pub fn routable_listeners(&self) -> Option<HashMap<SocketAddr, TransportType>> {
+        let routable_ip = self.routable_ip?;
+        Some(
+            self.listeners
+                .iter()
+                .map(|(addr, transport)| (SocketAddr::new(routable_ip, addr.port()), *transport))
+                .collect(),
+        )
+    }
+
+    pub fn add_listener(
+        &mut self,
+        addr: SocketAddr,
+        transport: TransportType,
+    ) -> PeerNetResult<()> {
+        self.listeners.insert(addr, transport);
+        self.refresh()
+    }
+
+    pub fn remove_listener(&mut self, addr: &SocketAddr) -> PeerNetResult<()> {
+        self.listeners.remove(addr);
+        self.refresh()
+    }
+
+    /// Set the IP the other peers reach us at. Returns whether it changed.
+    pub fn set_routable_ip(&mut self, routable_ip: Option<IpAddr>) -> PeerNetResult<bool> {
+        if routable_ip == self.routable_ip {
+            return Ok(false);
+        }
+        self.routable_ip = routable_ip;
+        self.refresh()?;
+        Ok(true)
+    }
+
+    fn refresh(&mut self) -> PeerNetResult<()> {
+        self.announcement =
+            Announcement::new(self.listeners.clone(), self.routable_ip, &self.keypair)?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::handlers::peer_handler::announcement::{
@@ -214,7 +297,7 @@ mod tests {
     use peernet::{transports::TransportType, types::KeyPair};
     use std::collections::HashMap;
 
-    use super::AnnouncementSerializer;
+    use super::{AnnouncementSerializer, LocalAnnouncement};
 
     #[test]
     fn test_ser_deser() {
@@ -234,4 +317,31 @@ mod tests {
             .unwrap();
         assert_eq!(announcement, announcement_deserialized);
     }
+
+    #[test]
+    fn test_local_announcement_refresh() {
+        let mut listeners = HashMap::new();
+        listeners.insert("127.0.0.1:8081".parse().unwrap(), TransportType::Tcp);
+        let mut local_announcement =
+            LocalAnnouncement::new(KeyPair::generate(), listeners, None).unwrap();
+        // nothing is announced without a routable IP
+        assert!(local_announcement.announcement().listeners.is_empty());
+        assert!(local_announcement.routable_listeners().is_none());
+
+        let routable_ip = Some("82.245.123.77".parse().unwrap());
+        assert!(local_announcement.set_routable_ip(routable_ip).unwrap());
+        assert!(!local_announcement.set_routable_ip(routable_ip).unwrap());
+        let announcement = local_announcement.announcement().clone();
+        assert_eq!(announcement.listeners.len(), 1);
+        assert!(local_announcement
+            .routable_listeners()
+            .unwrap()
+            .contains_key(&"82.245.123.77:8081".parse().unwrap()));
+
+        // the stale announcement is replaced by a new one
+        local_announcement
+            .set_routable_ip(Some("82.245.123.78".parse().unwrap()))
+            .unwrap();
+        assert_ne!(local_announcement.announcement().hash, announcement.hash);
+    }
 }
@@ -0,0 +1,248 @@
+//! Signed reports of the bans for offenses that other nodes can check again.
+//!
+//! A report holds the peer management message received from the banned peer, the offense it
+//! proves and the node that noticed it, signed by that node. The message can't prove that the
+//! banned peer sent it: an imported report is trusted as far as the operator trusts its source.
+//! The offense itself is checked again before the ban is applied, the message must fail the
+//! check it failed on the reporter.
+
+use massa_models::config::MAX_MESSAGE_SIZE;
+use massa_protocol_exports::{BanReport, MisbehaviorKind};
+use massa_serialization::{DeserializeError, Deserializer};
+use massa_time::MassaTime;
+use peernet::{
+    peer_id::PeerId,
+    types::{Hash, KeyPair, Signature, PUBLIC_KEY_SIZE_BYTES},
+};
+
+use super::messages::{MessageTypeId, PeerManagementMessage, PeerManagementMessageDeserializer};
+
+/// Maximum number of reports kept by the peer handler, the oldest are dropped first
+pub(crate) const MAX_BAN_REPORTS: usize = 1000;
+
+/// Version of the format of the reports
+const BAN_REPORT_VERSION: u8 = 0;
+
+const SIGNATURE_SIZE_BYTES: usize = 64;
+
+/// Offense proven by the message of a report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ProvenOffense {
+    /// Peer metadata with a signature that doesn't match the peer
+    InvalidMetadataSignature = 0,
+    /// Message that can't be deserialized
+    MalformedMessage = 1,
+}
+
+impl ProvenOffense {
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(ProvenOffense::InvalidMetadataSignature),
+            1 => Some(ProvenOffense::MalformedMessage),
+            _ => None,
+        }
+    }
+
+    fn kind(&self) -> MisbehaviorKind {
+        match self {
+            ProvenOffense::InvalidMetadataSignature => MisbehaviorKind::InvalidSignature,
+            ProvenOffense::MalformedMessage => MisbehaviorKind::MalformedMessage,
+        }
+    }
+}
+
+/// Offense of a peer with the peer management message that proves it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BanEvidence {
+    pub peer_id: PeerId,
+    pub offense: ProvenOffense,
+    pub timestamp: MassaTime,
+    /// id of the peer management message, as given to the peer handler
+    pub message_id: u64,
+    /// content of the message, framed as by the peers that negotiated it
+    pub data: Vec<u8>,
+}
+
+impl BanEvidence {
+    fn content(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(PUBLIC_KEY_SIZE_BYTES + 22 + self.data.len());
+        bytes.push(BAN_REPORT_VERSION);
+        bytes.extend(self.peer_id.to_bytes());
+        bytes.push(self.offense as u8);
+        bytes.extend(self.timestamp.to_millis().to_be_bytes());
+        bytes.extend(self.message_id.to_be_bytes());
+        bytes.extend((self.data.len() as u32).to_be_bytes());
+        bytes.extend(&self.data);
+        bytes
+    }
+
+    /// Report of the offense signed by the node
+    pub fn sign(&self, keypair: &KeyPair) -> Result<BanReport, String> {
+        let reporter = PeerId::from_public_key(keypair.get_public_key());
+        let mut signed = self.content();
+        signed.extend(reporter.to_bytes());
+        let signature = keypair
+            .sign(&Hash::compute_from(&signed))
+            .map_err(|err| format!("could not sign the ban report: {:?}", err))?;
+        signed.extend(signature.to_bytes());
+        Ok(BanReport {
+            peer_id: self.peer_id.clone(),
+            kind: self.offense.kind(),
+            timestamp: self.timestamp,
+            reporter,
+            signed,
+        })
+    }
+
+    /// Read a signed report and check the signature of its reporter
+    pub fn from_signed(signed: &[u8]) -> Result<(BanEvidence, PeerId), String> {
+        let content_len = signed
+            .len()
+            .checked_sub(PUBLIC_KEY_SIZE_BYTES + SIGNATURE_SIZE_BYTES)
+            .ok_or_else(truncated)?;
+        let (content, rest) = signed.split_at(content_len);
+        let (reporter, signature) = rest.split_at(PUBLIC_KEY_SIZE_BYTES);
+        let reporter = PeerId::from_bytes(reporter.try_into().unwrap())
+            .map_err(|err| format!("invalid reporter: {:?}", err))?;
+        let signature = Signature::from_bytes(signature.try_into().unwrap())
+            .map_err(|err| format!("invalid signature: {:?}", err))?;
+        reporter
+            .verify_signature(
+                &Hash::compute_from(&signed[..content_len + PUBLIC_KEY_SIZE_BYTES]),
+                &signature,
+            )
+            .map_err(|_| "the signature of the reporter is invalid".to_string())?;
+
+        let (version, rest) = take(content, 1)?;
+        if version[0] != BAN_REPORT_VERSION {
+            return Err(format!("unsupported version {} of ban report", version[0]));
+        }
+        let (peer_id, rest) = take(rest, PUBLIC_KEY_SIZE_BYTES)?;
+        let peer_id = PeerId::from_bytes(peer_id.try_into().unwrap())
+            .map_err(|err| format!("invalid peer id: {:?}", err))?;
+        let (offense, rest) = take(rest, 1)?;
+        let offense = ProvenOffense::from_byte(offense[0])
+            .ok_or_else(|| format!("unknown offense {}", offense[0]))?;
+        let (timestamp, rest) = take(rest, 8)?;
+        let (message_id, rest) = take(rest, 8)?;
+        let (len, data) = take(rest, 4)?;
+        let len = u32::from_be_bytes(len.try_into().unwrap()) as usize;
+        if len != data.len() || len > MAX_MESSAGE_SIZE as usize {
+            return Err("invalid length of the message of the ban report".to_string());
+        }
+        Ok((
+            BanEvidence {
+                peer_id,
+                offense,
+                timestamp: MassaTime::from_millis(u64::from_be_bytes(
+                    timestamp.try_into().unwrap(),
+                )),
+                message_id: u64::from_be_bytes(message_id.try_into().unwrap()),
+                data: data.to_vec(),
+            },
+            reporter,
+        ))
+    }
+
+    /// Check again that the message proves the offense
+    pub fn check(
+        &self,
+        deserializer: &mut PeerManagementMessageDeserializer,
+    ) -> Result<(), String> {
+        if !PeerManagementMessage::is_known_id(self.message_id) {
+            return Err(format!("unknown message id {}", self.message_id));
+        }
+        deserializer.set_message(self.message_id);
+        deserializer.set_framed(true);
+        let message = deserializer.deserialize::<DeserializeError>(&self.data);
+        match (self.offense, message) {
+            (ProvenOffense::MalformedMessage, Err(_)) => Ok(()),
+            (ProvenOffense::MalformedMessage, Ok(_)) => {
+                Err("the message of the ban report is well formed".to_string())
+            }
+            (
+                ProvenOffense::InvalidMetadataSignature,
+                Ok((_, PeerManagementMessage::PeerMetadata(metadata))),
+            ) if self.message_id == u64::from(MessageTypeId::PeerMetadata) => {
+                match self
+                    .peer_id
+                    .verify_signature(&metadata.hash, &metadata.signature)
+                {
+                    Ok(()) => {
+                        Err("the metadata of the ban report is signed by the peer".to_string())
+                    }
+                    Err(_) => Ok(()),
+                }
+            }
+            (ProvenOffense::InvalidMetadataSignature, _) => {
+                Err("the ban report doesn't hold peer metadata".to_string())
+            }
+        }
+    }
+}
+
+fn truncated() -> String {
+    "the ban report is truncated".to_string()
+}
+
+fn take(bytes: &[u8], len: usize) -> Result<(&[u8], &[u8]), String> {
+    if bytes.len() < len {
+        return Err(truncated());
+    }
+    Ok(bytes.split_at(len))
+}
+
+#[cfg(test)]
+mod tests {
+    use massa_models::config::{
+        MAX_LISTENERS_PER_PEER, MAX_MESSAGE_SIZE, MAX_PEERS_IN_ANNOUNCEMENT_LIST,
+    };
+    use massa_time::MassaTime;
+    use peernet::{peer_id::PeerId, types::KeyPair};
+
+    use super::{BanEvidence, ProvenOffense};
+    use crate::handlers::peer_handler::messages::{
+        MessageTypeId, PeerManagementMessageDeserializer, PeerManagementMessageDeserializerArgs,
+    };
+
+    #[test]
+    fn test_ban_report() {
+        let mut deserializer =
+            PeerManagementMessageDeserializer::new(PeerManagementMessageDeserializerArgs {
+                max_peers_per_announcement: MAX_PEERS_IN_ANNOUNCEMENT_LIST,
+                max_listeners_per_peer: MAX_LISTENERS_PER_PEER,
+                max_message_size: MAX_MESSAGE_SIZE as u64,
+            });
+        let reporter = KeyPair::generate();
+        let evidence = BanEvidence {
+            peer_id: PeerId::from_public_key(KeyPair::generate().get_public_key()),
+            offense: ProvenOffense::MalformedMessage,
+            timestamp: MassaTime::from_millis(1000),
+            message_id: MessageTypeId::ListPeers.into(),
+            data: vec![0xFF; 3],
+        };
+        evidence.check(&mut deserializer).unwrap();
+
+        let report = evidence.sign(&reporter).unwrap();
+        assert_eq!(
+            report.reporter,
+            PeerId::from_public_key(reporter.get_public_key())
+        );
+        let (read, reporter_id) = BanEvidence::from_signed(&report.signed).unwrap();
+        assert_eq!(read, evidence);
+        assert_eq!(reporter_id, report.reporter);
+
+        // a report changed after its signature is refused
+        let mut tampered = report.signed.clone();
+        tampered[40] ^= 1;
+        assert!(BanEvidence::from_signed(&tampered).is_err());
+        assert!(BanEvidence::from_signed(&report.signed[..10]).is_err());
+
+        // a report whose message doesn't prove the offense is refused
+        let metadata = BanEvidence {
+            offense: ProvenOffense::InvalidMetadataSignature,
+            ..evidence
+        };
+        assert!(metadata.check(&mut deserializer).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+//! Synthetic load for the benchmarks of the peer handler.
+//!
+//! The load is made of thousands of generated peers with their signed announcements, without
+//! any network. The benchmarks measure the work of the handshakes, of the ticks of the peer
+//! handler loop and of the floods of peer lists, and the contention on the peer DB lock.
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    thread,
+};
+
+use massa_models::{
+    config::{MAX_LISTENERS_PER_PEER, MAX_MESSAGE_SIZE, MAX_PEERS_IN_ANNOUNCEMENT_LIST},
+    version::{Version, VersionDeserializer, VersionSerializer},
+};
+use massa_serialization::{DeserializeError, Deserializer, Serializer};
+use massa_time::MassaTime;
+use peernet::{
+    peer_id::PeerId,
+    transports::TransportType,
+    types::{Hash, KeyPair},
+};
+
+use super::{
+    announcement::{
+        Announcement, AnnouncementDeserializer, AnnouncementDeserializerArgs,
+        AnnouncementSerializer,
+    },
+    hello::{
+        deserialize_hello_content, deserialize_hello_peer_id, network_id,
+        serialize_handshake_features, HandshakeTranscript, HelloContent, HANDSHAKE_FEATURES,
+    },
+    messages::{
+        MessageTypeId, PeerManagementMessage, PeerManagementMessageDeserializer,
+        PeerManagementMessageDeserializerArgs, PeerManagementMessageSerializer,
+    },
+    models::{PeerDB, PeerState, SharedPeerDB},
+    peer_db_lock::{PeerDBLock, PeerDBLockStats},
+};
+
+/// Number of peers sent in each list, as by the peer handler loop
+const PEERS_PER_LIST: usize = 100;
+
+struct SyntheticPeer {
+    keypair: KeyPair,
+    peer_id: PeerId,
+    announcement: Announcement,
+    /// hello sent by the peer at the beginning of its handshake
+    hello: Vec<u8>,
+}
+
+/// Generated peers and a peer DB that knows all of them
+pub struct SyntheticLoad {
+    keypair: KeyPair,
+    peer_id: PeerId,
+    version: Version,
+    network_id: Hash,
+    peers: Vec<SyntheticPeer>,
+    peer_db: SharedPeerDB,
+}
+
+impl SyntheticLoad {
+    /// Generate `peer_count` peers, each listening on its own public IP, and a peer DB that
+    /// knows all of them
+    pub fn new(peer_count: usize) -> Self {
+        let version: Version = "TEST.22.2".parse().unwrap();
+        let network_id = network_id(77, MassaTime::from_millis(1000));
+        let announcement_serializer = AnnouncementSerializer::new();
+        let mut peer_db = PeerDB::new(peer_count);
+        let peers: Vec<SyntheticPeer> = (0..peer_count)
+            .map(|index| {
+                let keypair = KeyPair::generate();
+                let peer_id = PeerId::from_public_key(keypair.get_public_key());
+                let ip = IpAddr::V4(Ipv4Addr::new(
+                    82 + (index >> 16) as u8,
+                    (index >> 8) as u8,
+                    index as u8,
+                    1,
+                ));
+                let mut listeners = HashMap::new();
+                listeners.insert(SocketAddr::new(ip, 31244), TransportType::Tcp);
+                let announcement = Announcement::new(listeners, Some(ip), &keypair).unwrap();
+                let mut hello = peer_id.to_bytes();
+                VersionSerializer::new()
+                    .serialize(&version, &mut hello)
+                    .unwrap();
+                hello.push(0);
+                announcement_serializer
+                    .serialize(&announcement, &mut hello)
+                    .unwrap();
+                serialize_handshake_features(HANDSHAKE_FEATURES, &mut hello);
+                hello.extend(network_id.to_bytes());
+                peer_db.set_peer_announcement(&peer_id, announcement.clone());
+                SyntheticPeer {
+                    keypair,
+                    peer_id,
+                    announcement,
+                    hello,
+                }
+            })
+            .collect();
+        let keypair = KeyPair::generate();
+        SyntheticLoad {
+            peer_id: PeerId::from_public_key(keypair.get_public_key()),
+            keypair,
+            version,
+            network_id,
+            peers,
+            peer_db: PeerDBLock::shared(peer_db),
+        }
+    }
+
+    /// Number of generated peers
+    pub fn peer_count(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Run our side of the handshake with the peer at `index`: parse its hello, check its
+    /// announcement, sign its challenge, check its signature and record it in the peer DB.
+    pub fn handshake(&self, index: usize) -> PeerId {
+        let peer = &self.peers[index % self.peers.len()];
+        let (peer_id, rest) = deserialize_hello_peer_id(&peer.hello).unwrap();
+        let (_, content) = deserialize_hello_content(
+            rest,
+            &self.version,
+            &self.network_id,
+            &VersionDeserializer::new(),
+            &AnnouncementDeserializer::new(AnnouncementDeserializerArgs {
+                max_listeners: MAX_LISTENERS_PER_PEER,
+            }),
+        )
+        .unwrap();
+        let HelloContent::Announcement(announcement, _features) = content else {
+            panic!("the synthetic hello holds an announcement");
+        };
+        peer_id
+            .verify_signature(&announcement.hash, &announcement.signature)
+            .unwrap();
+
+        // both transcripts, ours and the one of the peer
+        let self_hello = b"synthetic hello";
+        let (self_random_bytes, peer_random_bytes) = ([1u8; 32], [2u8; 32]);
+        let mut transcript = HandshakeTranscript::default();
+        transcript.record_sent(self_hello);
+        transcript.record_received(&peer.hello);
+        transcript.record_sent(&self_random_bytes);
+        transcript.record_received(&peer_random_bytes);
+        let mut peer_transcript = HandshakeTranscript::default();
+        peer_transcript.record_sent(&peer.hello);
+        peer_transcript.record_received(self_hello);
+        peer_transcript.record_sent(&peer_random_bytes);
+        peer_transcript.record_received(&self_random_bytes);
+
+        self.keypair
+            .sign(&transcript.signed_hash(&self.peer_id, &peer_id))
+            .unwrap();
+        let peer_signature = peer
+            .keypair
+            .sign(&peer_transcript.signed_hash(&peer_id, &self.peer_id))
+            .unwrap();
+        peer_id
+            .verify_signature(
+                &transcript.verified_hash(&self.peer_id, &peer_id),
+                &peer_signature,
+            )
+            .unwrap();
+
+        self.peer_db
+            .write()
+            .set_peer_announcement(&peer_id, announcement);
+        peer_id
+    }
+
+    /// Work of a tick of the peer handler loop: pick the peers to send and serialize the list
+    pub fn tick(&self) -> Vec<u8> {
+        let peers = self
+            .peer_db
+            .snapshot()
+            .get_rand_peers_to_send(PEERS_PER_LIST, MassaTime::now().unwrap());
+        let mut bytes = Vec::new();
+        PeerManagementMessageSerializer::new()
+            .serialize(&PeerManagementMessage::ListPeers(peers), &mut bytes)
+            .unwrap();
+        bytes
+    }
+
+    /// `count` lists of peers, as received from the connected peers
+    pub fn peer_lists(&self, count: usize) -> Vec<Vec<u8>> {
+        let serializer = PeerManagementMessageSerializer::new();
+        (0..count)
+            .map(|index| {
+                let peers = (0..PEERS_PER_LIST)
+                    .map(|offset| {
+                        let peer =
+                            &self.peers[(index * PEERS_PER_LIST + offset) % self.peers.len()];
+                        (peer.peer_id.clone(), peer.announcement.listeners.clone())
+                    })
+                    .collect();
+                let mut bytes = Vec::new();
+                serializer
+                    .serialize(&PeerManagementMessage::ListPeers(peers), &mut bytes)
+                    .unwrap();
+                bytes
+            })
+            .collect()
+    }
+
+    /// Handle a flood of peer lists as the peer handler loop does before testing the peers.
+    /// Returns the number of announced peers.
+    pub fn handle_peer_lists(&self, lists: &[Vec<u8>]) -> usize {
+        let mut deserializer =
+            PeerManagementMessageDeserializer::new(PeerManagementMessageDeserializerArgs {
+                max_peers_per_announcement: MAX_PEERS_IN_ANNOUNCEMENT_LIST,
+                max_listeners_per_peer: MAX_LISTENERS_PER_PEER,
+                max_message_size: MAX_MESSAGE_SIZE as u64,
+            });
+        let mut announced = 0;
+        for (index, list) in lists.iter().enumerate() {
+            let sender = &self.peers[index % self.peers.len()].peer_id;
+            if let Some(peer) = self.peer_db.snapshot().peers.get(sender) {
+                if peer.state == PeerState::Banned {
+                    continue;
+                }
+            }
+            deserializer.set_message(MessageTypeId::ListPeers.into());
+            let (_, message) = deserializer.deserialize::<DeserializeError>(list).unwrap();
+            if let PeerManagementMessage::ListPeers(peers) = message {
+                announced += peers.len();
+            }
+        }
+        announced
+    }
+
+    /// Record the handshakes of all the peers from `writers` threads while `readers` threads
+    /// pick peers to send, as the handshakes, the loop and the testers do during a connection
+    /// storm. Returns the contention on a fresh lock of the peer DB.
+    pub fn contend(&self, writers: usize, readers: usize) -> PeerDBLockStats {
+        let peer_db = PeerDBLock::shared(self.peer_db.snapshot().as_ref().clone());
+        let writers = writers.max(1);
+        thread::scope(|scope| {
+            for writer in 0..writers {
+                let peer_db = &peer_db;
+                scope.spawn(move || {
+                    for peer in self.peers.iter().skip(writer).step_by(writers) {
+                        peer_db
+                            .write()
+                            .set_peer_announcement(&peer.peer_id, peer.announcement.clone());
+                    }
+                });
+            }
+            for _ in 0..readers {
+                let peer_db = &peer_db;
+                scope.spawn(move || {
+                    for _ in 0..self.peers.len() / writers {
+                        peer_db
+                            .snapshot()
+                            .get_rand_peers_to_send(PEERS_PER_LIST, MassaTime::now().unwrap());
+                    }
+                });
+            }
+        });
+        peer_db.lock_stats()
+    }
+}
@@ -0,0 +1,332 @@
+//! Reachability self-test of the listeners.
+//!
+//! On request of the operator, the node asks a few connected peers to dial back each of its
+//! announced TCP listeners with a `DialBackRequest`. Each peer runs the test with its testers
+//! and answers with a `DialBackResult`. A peer only dials back the TCP listeners announced by the
+//! requesting peer, each at most once at a time, so that it can't be used to dial arbitrary
+//! addresses.
+
+use std::{collections::HashMap, net::SocketAddr, time::Duration};
+
+use crossbeam::channel::Sender;
+use massa_protocol_exports::{ListenerReachability, PeerTestResult, ProtocolConfig};
+use massa_time::MassaTime;
+use peernet::{peer_id::PeerId, transports::TransportType};
+use rand::seq::SliceRandom;
+use tracing::log::debug;
+
+/// The node dials back the listeners of its peers on request
+pub const DIAL_BACK_FEATURE: &str = "dial_back";
+
+/// Interval at which the self-tests and the dial backs past their timeout are ended
+pub(crate) const DIAL_BACK_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Self-test requested by the operator
+struct SelfTest {
+    responder: Sender<Vec<ListenerReachability>>,
+    started_at: MassaTime,
+    listeners: Vec<ListenerReachability>,
+    /// requests not answered yet: nonce -> (peer asked, index of the listener)
+    pending: HashMap<u64, (PeerId, usize)>,
+}
+
+impl SelfTest {
+    /// Report the peers that didn't answer and send the result to the operator
+    fn finish(mut self) {
+        for (peer_id, index) in self.pending.into_values() {
+            self.listeners[index].unanswered.push(peer_id);
+        }
+        if let Err(err) = self.responder.send(self.listeners) {
+            debug!("error sending reachability self-test result: {}", err);
+        }
+    }
+}
+
+/// Dial back of a listener requested by a peer
+struct DialBackRequest {
+    peer_id: PeerId,
+    nonce: u64,
+    requested_at: MassaTime,
+}
+
+pub(crate) struct DialBack {
+    /// number of peers asked to dial back each listener
+    peers: usize,
+    timeout: MassaTime,
+    self_tests: Vec<SelfTest>,
+    /// dial backs requested by the peers and running in the testers, by tested address
+    requests: HashMap<SocketAddr, Vec<DialBackRequest>>,
+    next_nonce: u64,
+}
+
+impl DialBack {
+    pub(crate) fn from_config(config: &ProtocolConfig) -> Self {
+        Self {
+            peers: config.dial_back_peers,
+            timeout: config.dial_back_timeout,
+            self_tests: Vec::new(),
+            requests: HashMap::new(),
+            next_nonce: 0,
+        }
+    }
+
+    /// Start a self-test of our `listeners` by some of the `candidates`.
+    /// Returns the requests to send as (peer, nonce, listener). The operator gets an answer right
+    /// away if there is nothing to test.
+    pub(crate) fn start_self_test(
+        &mut self,
+        listeners: &HashMap<SocketAddr, TransportType>,
+        candidates: &[PeerId],
+        responder: Sender<Vec<ListenerReachability>>,
+        now: MassaTime,
+    ) -> Vec<(PeerId, u64, SocketAddr)> {
+        // the testers of the peers only dial TCP
+        let mut addresses: Vec<SocketAddr> = listeners
+            .iter()
+            .filter(|(_, transport)| **transport == TransportType::Tcp)
+            .map(|(address, _)| *address)
+            .collect();
+        addresses.sort();
+        let peers: Vec<PeerId> = candidates
+            .choose_multiple(&mut rand::thread_rng(), self.peers)
+            .cloned()
+            .collect();
+        let mut self_test = SelfTest {
+            responder,
+            started_at: now,
+            listeners: addresses
+                .iter()
+                .map(|address| ListenerReachability {
+                    address: *address,
+                    reachable_from: Vec::new(),
+                    unreachable_from: Vec::new(),
+                    unanswered: Vec::new(),
+                })
+                .collect(),
+            pending: HashMap::new(),
+        };
+        let mut requests = Vec::new();
+        for (index, address) in addresses.iter().enumerate() {
+            for peer_id in &peers {
+                let nonce = self.next_nonce;
+                self.next_nonce = self.next_nonce.wrapping_add(1);
+                self_test.pending.insert(nonce, (peer_id.clone(), index));
+                requests.push((peer_id.clone(), nonce, *address));
+            }
+        }
+        if self_test.pending.is_empty() {
+            self_test.finish();
+        } else {
+            self.self_tests.push(self_test);
+        }
+        requests
+    }
+
+    /// Record the answer of a peer to one of our requests.
+    /// Returns false if it doesn't answer a pending request sent to this peer.
+    pub(crate) fn self_test_result(
+        &mut self,
+        peer_id: &PeerId,
+        nonce: u64,
+        reachable: bool,
+    ) -> bool {
+        let Some(position) = self.self_tests.iter().position(|self_test| {
+            self_test
+                .pending
+                .get(&nonce)
+                .map_or(false, |(asked, _)| asked == peer_id)
+        }) else {
+            return false;
+        };
+        let self_test = &mut self.self_tests[position];
+        let (peer_id, index) = self_test
+            .pending
+            .remove(&nonce)
+            .expect("the request is pending");
+        let listener = &mut self_test.listeners[index];
+        if reachable {
+            listener.reachable_from.push(peer_id);
+        } else {
+            listener.unreachable_from.push(peer_id);
+        }
+        if self_test.pending.is_empty() {
+            self.self_tests.swap_remove(position).finish();
+        }
+        true
+    }
+
+    /// Whether the dial back of `address` requested by `peer_id` can start: the address must be
+    /// a TCP listener announced by the peer, and the peer must not wait for another dial back of
+    /// this address.
+    /// The request is recorded until its test is over.
+    pub(crate) fn accept_request(
+        &mut self,
+        peer_id: &PeerId,
+        nonce: u64,
+        address: SocketAddr,
+        announced_listeners: Option<&HashMap<SocketAddr, TransportType>>,
+        now: MassaTime,
+    ) -> bool {
+        let announced = announced_listeners.map_or(false, |listeners| {
+            listeners.get(&address) == Some(&TransportType::Tcp)
+        });
+        let waiting = self.requests.get(&address).map_or(false, |requests| {
+            requests.iter().any(|request| request.peer_id == *peer_id)
+        });
+        if !announced || waiting {
+            return false;
+        }
+        self.requests
+            .entry(address)
+            .or_default()
+            .push(DialBackRequest {
+                peer_id: peer_id.clone(),
+                nonce,
+                requested_at: now,
+            });
+        true
+    }
+
+    /// Forget a request whose test could not start
+    pub(crate) fn cancel_request(&mut self, peer_id: &PeerId, address: &SocketAddr) {
+        if let Some(requests) = self.requests.get_mut(address) {
+            requests.retain(|request| request.peer_id != *peer_id);
+            if requests.is_empty() {
+                self.requests.remove(address);
+            }
+        }
+    }
+
+    /// Answers to send as (peer, nonce, reachable) once the test of an address is over.
+    /// The listener is reachable if the handshake succeeded with the peer that requested it.
+    pub(crate) fn request_result(&mut self, result: &PeerTestResult) -> Vec<(PeerId, u64, bool)> {
+        self.requests
+            .remove(&result.address)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|request| {
+                let reachable = result
+                    .handshake
+                    .as_ref()
+                    .map_or(false, |peer_id| *peer_id == request.peer_id);
+                (request.peer_id, request.nonce, reachable)
+            })
+            .collect()
+    }
+
+    /// Answer the self-tests past their timeout, and forget the dial backs whose result was lost
+    pub(crate) fn tick(&mut self, now: MassaTime) {
+        let timeout = self.timeout;
+        let (expired, running): (Vec<SelfTest>, Vec<SelfTest>) =
+            std::mem::take(&mut self.self_tests)
+                .into_iter()
+                .partition(|self_test| now.saturating_sub(self_test.started_at) >= timeout);
+        self.self_tests = running;
+        for self_test in expired {
+            self_test.finish();
+        }
+        self.requests.retain(|_, requests| {
+            requests.retain(|request| now.saturating_sub(request.requested_at) < timeout);
+            !requests.is_empty()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use massa_protocol_exports::{PeerTestResult, ProtocolConfig};
+    use massa_time::MassaTime;
+    use peernet::{peer_id::PeerId, transports::TransportType, types::KeyPair};
+
+    use super::DialBack;
+
+    fn peer_id() -> PeerId {
+        PeerId::from_public_key(KeyPair::generate().get_public_key())
+    }
+
+    #[test]
+    fn test_dial_back_self_test() {
+        let mut config = ProtocolConfig::default();
+        config.dial_back_peers = 2;
+        config.dial_back_timeout = MassaTime::from_millis(10_000);
+        let mut dial_back = DialBack::from_config(&config);
+        let tcp = "1.2.3.4:31244".parse().unwrap();
+        let listeners = HashMap::from([
+            (tcp, TransportType::Tcp),
+            ("1.2.3.4:31245".parse().unwrap(), TransportType::Quic),
+        ]);
+        let (responder, results) = crossbeam::channel::bounded(1);
+
+        // only the TCP listener is tested, by two of the peers
+        let requests = dial_back.start_self_test(
+            &listeners,
+            &[peer_id(), peer_id(), peer_id()],
+            responder,
+            MassaTime::from_millis(0),
+        );
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|(_, _, address)| *address == tcp));
+        let (first, first_nonce, _) = requests[0].clone();
+        let (second, second_nonce, _) = requests[1].clone();
+        // an answer to a request sent to another peer is ignored
+        assert!(!dial_back.self_test_result(&second, first_nonce, true));
+        assert!(dial_back.self_test_result(&first, first_nonce, true));
+        assert!(results.try_recv().is_err());
+
+        // the peer that didn't answer is reported at the timeout
+        dial_back.tick(MassaTime::from_millis(10_000));
+        let result = results.try_recv().unwrap();
+        assert_eq!(result.len(), 1);
+        assert!(result[0].is_reachable());
+        assert_eq!(result[0].reachable_from, vec![first]);
+        assert_eq!(result[0].unanswered, vec![second.clone()]);
+        assert!(!dial_back.self_test_result(&second, second_nonce, false));
+
+        // nothing to test: answered right away
+        let (responder, results) = crossbeam::channel::bounded(1);
+        assert!(dial_back
+            .start_self_test(&listeners, &[], responder, MassaTime::from_millis(0))
+            .is_empty());
+        assert!(results.try_recv().unwrap()[0].unanswered.is_empty());
+    }
+
+    #[test]
+    fn test_dial_back_requests_of_peers() {
+        let mut dial_back = DialBack::from_config(&ProtocolConfig::default());
+        let requester = peer_id();
+        let listener = "1.2.3.4:31244".parse().unwrap();
+        let announced = HashMap::from([(listener, TransportType::Tcp)]);
+        let now = MassaTime::from_millis(0);
+
+        // only the announced listeners are dialed, once at a time
+        assert!(!dial_back.accept_request(
+            &requester,
+            1,
+            "5.6.7.8:31244".parse().unwrap(),
+            Some(&announced),
+            now
+        ));
+        assert!(!dial_back.accept_request(&requester, 1, listener, None, now));
+        assert!(dial_back.accept_request(&requester, 1, listener, Some(&announced), now));
+        assert!(!dial_back.accept_request(&requester, 2, listener, Some(&announced), now));
+
+        // reachable only if the handshake reached the requesting peer
+        let answers = dial_back.request_result(&PeerTestResult {
+            address: listener,
+            reachable: true,
+            handshake: Ok(peer_id()),
+            latency: None,
+        });
+        assert_eq!(answers, vec![(requester.clone(), 1, false)]);
+        assert!(dial_back.accept_request(&requester, 3, listener, Some(&announced), now));
+        let answers = dial_back.request_result(&PeerTestResult {
+            address: listener,
+            reachable: true,
+            handshake: Ok(requester.clone()),
+            latency: None,
+        });
+        assert_eq!(answers, vec![(requester, 3, true)]);
+    }
+}
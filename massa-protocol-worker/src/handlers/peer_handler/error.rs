@@ -0,0 +1,37 @@
+//! The handshakes and the testers fail with a `PeerHandlerError`, converted to a `PeerNetError`
+//! only where PeerNet calls our handlers.
+
+use massa_protocol_exports::PeerHandlerError;
+use peernet::error::{PeerNetError, PeerNetResult};
+
+pub(crate) type PeerHandlerResult<T> = Result<T, PeerHandlerError>;
+
+/// Error of PeerNet met while talking to the peer
+pub(crate) fn network_error(err: impl std::fmt::Debug) -> PeerHandlerError {
+    PeerHandlerError::NetworkError(format!("{:?}", err))
+}
+
+/// Failure to serialize or sign a message sent to the peer
+pub(crate) fn serialization_error(err: impl std::fmt::Debug) -> PeerHandlerError {
+    PeerHandlerError::SerializationError(format!("{:?}", err))
+}
+
+pub(crate) trait IntoPeerNetResult<T> {
+    /// Converts the error for PeerNet, `location` being the handler it comes from
+    fn into_peernet(self, location: &'static str) -> PeerNetResult<T>;
+}
+
+impl<T> IntoPeerNetResult<T> for PeerHandlerResult<T> {
+    fn into_peernet(self, location: &'static str) -> PeerNetResult<T> {
+        self.map_err(|err| {
+            let kind = match &err {
+                PeerHandlerError::NetworkError(_) => PeerNetError::PeerConnectionError,
+                PeerHandlerError::SerializationError(_)
+                | PeerHandlerError::ChannelError(_)
+                | PeerHandlerError::PeerDBError(_) => PeerNetError::HandlerError,
+                _ => PeerNetError::HandshakeError,
+            };
+            kind.error(location, Some(err.to_string()))
+        })
+    }
+}
@@ -0,0 +1,58 @@
+//! Fuzzing entry points of the peer handler.
+//!
+//! Each function feeds arbitrary bytes into one of the parsers that process data coming
+//! from the network before any authentication. They must never panic.
+
+use massa_models::{
+    config::{
+        CHAINID, GENESIS_TIMESTAMP, MAX_LISTENERS_PER_PEER, MAX_MESSAGE_SIZE,
+        MAX_PEERS_IN_ANNOUNCEMENT_LIST, VERSION,
+    },
+    version::VersionDeserializer,
+};
+use massa_serialization::{DeserializeError, Deserializer};
+
+use super::{
+    announcement::{AnnouncementDeserializer, AnnouncementDeserializerArgs},
+    hello::{deserialize_hello_content, deserialize_hello_peer_id, network_id},
+    messages::{PeerManagementMessageDeserializer, PeerManagementMessageDeserializerArgs},
+};
+
+/// Parse a hello message as received at the beginning of a handshake
+pub fn fuzz_handshake_hello(data: &[u8]) {
+    let Ok((_peer_id, rest)) = deserialize_hello_peer_id(data) else {
+        return;
+    };
+    let _ = deserialize_hello_content(
+        rest,
+        &VERSION,
+        &network_id(*CHAINID, *GENESIS_TIMESTAMP),
+        &VersionDeserializer::new(),
+        &AnnouncementDeserializer::new(AnnouncementDeserializerArgs {
+            max_listeners: MAX_LISTENERS_PER_PEER,
+        }),
+    );
+}
+
+/// Parse a peer management message. The first byte selects the message id.
+pub fn fuzz_peer_management_message(data: &[u8]) {
+    let Some((message_id, data)) = data.split_first() else {
+        return;
+    };
+    let mut deserializer =
+        PeerManagementMessageDeserializer::new(PeerManagementMessageDeserializerArgs {
+            max_peers_per_announcement: MAX_PEERS_IN_ANNOUNCEMENT_LIST,
+            max_listeners_per_peer: MAX_LISTENERS_PER_PEER,
+            max_message_size: MAX_MESSAGE_SIZE as u64,
+        });
+    deserializer.set_message(u64::from(*message_id));
+    let _ = deserializer.deserialize::<DeserializeError>(data);
+}
+
+/// Parse a signed announcement
+pub fn fuzz_announcement(data: &[u8]) {
+    let deserializer = AnnouncementDeserializer::new(AnnouncementDeserializerArgs {
+        max_listeners: MAX_LISTENERS_PER_PEER,
+    });
+    let _ = deserializer.deserialize::<DeserializeError>(data);
+}
@@ -0,0 +1,76 @@
+use massa_protocol_exports::ProtocolConfig;
+use massa_time::MassaTime;
+
+/// Paces the lists of peers sent to the connected peers: they are sent at every interval while
+/// the node looks for peers, and less often once its out connections are stable.
+pub(crate) struct GossipPace {
+    /// interval between two lists while the node looks for peers
+    pub(crate) interval: MassaTime,
+    /// interval between two lists once the out connections are stable
+    stable_interval: MassaTime,
+    /// number of known peers below which the node keeps looking for peers
+    min_known_peers: usize,
+    last_gossip: Option<MassaTime>,
+}
+
+impl GossipPace {
+    pub(crate) fn from_config(config: &ProtocolConfig) -> Self {
+        Self {
+            interval: config.peer_gossip_interval,
+            stable_interval: config.peer_gossip_stable_interval,
+            min_known_peers: config.peer_gossip_min_known_peers,
+            last_gossip: None,
+        }
+    }
+
+    /// Called every interval. Returns whether the list of peers is sent at this tick.
+    pub(crate) fn tick(
+        &mut self,
+        out_connections: usize,
+        target_out_connections: usize,
+        known_peers: usize,
+        now: MassaTime,
+    ) -> bool {
+        let stable =
+            out_connections >= target_out_connections && known_peers >= self.min_known_peers;
+        let due = match self.last_gossip {
+            Some(last_gossip) if stable => now.saturating_sub(last_gossip) >= self.stable_interval,
+            _ => true,
+        };
+        if due {
+            self.last_gossip = Some(now);
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use massa_protocol_exports::ProtocolConfig;
+    use massa_time::MassaTime;
+
+    use super::GossipPace;
+
+    #[test]
+    fn test_gossip_slows_down_once_stable() {
+        let mut config = ProtocolConfig::default();
+        config.peer_gossip_interval = MassaTime::from_millis(10_000);
+        config.peer_gossip_stable_interval = MassaTime::from_millis(60_000);
+        config.peer_gossip_min_known_peers = 100;
+        let mut pace = GossipPace::from_config(&config);
+        let at = |seconds: u64| MassaTime::from_millis(seconds * 1000);
+
+        // missing out connections: every tick
+        assert!(pace.tick(2, 8, 500, at(0)));
+        assert!(pace.tick(2, 8, 500, at(10)));
+        // too few known peers: every tick
+        assert!(pace.tick(8, 8, 50, at(20)));
+        // stable: once per stable interval
+        assert!(!pace.tick(8, 8, 500, at(30)));
+        assert!(!pace.tick(8, 8, 500, at(70)));
+        assert!(pace.tick(8, 8, 500, at(80)));
+        assert!(!pace.tick(8, 8, 500, at(90)));
+        // a lost connection brings the short interval back
+        assert!(pace.tick(7, 8, 500, at(100)));
+    }
+}
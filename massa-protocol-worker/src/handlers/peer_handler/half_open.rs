@@ -0,0 +1,242 @@
+//! Connections whose handshake is in progress.
+//!
+//! Until its handshake is over a connection is half-open: it holds a pre-handshake slot of its
+//! category, and a peer that never completes the handshake holds the slot until the timeout of
+//! the transport. The half-open connections of each IP are capped, and a watchdog closes the
+//! ones still half-open after `half_open_connection_deadline`.
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use massa_protocol_exports::ProtocolConfig;
+use massa_time::MassaTime;
+use parking_lot::Mutex;
+use peernet::transports::endpoint::Endpoint;
+use tracing::log::debug;
+
+use crate::clock::SharedClock;
+
+/// Interval at which the watchdog looks for the connections past their deadline
+const HALF_OPEN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Connection that the watchdog can close
+pub(crate) trait Closable: Send {
+    fn close(&mut self);
+}
+
+impl Closable for Endpoint {
+    fn close(&mut self) {
+        self.shutdown();
+    }
+}
+
+struct HalfOpenConnection<C> {
+    ip: IpAddr,
+    opened_at: MassaTime,
+    /// `None` once closed by the watchdog
+    connection: Option<C>,
+}
+
+pub(crate) struct HalfOpenConnections<C: Closable> {
+    /// 0 for no limit
+    max_per_ip: usize,
+    /// 0 to never close a connection
+    deadline: MassaTime,
+    connections: HashMap<u64, HalfOpenConnection<C>>,
+    /// number of connections of each IP that are still open
+    per_ip: HashMap<IpAddr, usize>,
+    next_id: u64,
+}
+
+pub(crate) type SharedHalfOpenConnections = Arc<Mutex<HalfOpenConnections<Endpoint>>>;
+
+impl<C: Closable> HalfOpenConnections<C> {
+    pub fn new(max_per_ip: usize, deadline: MassaTime) -> Self {
+        HalfOpenConnections {
+            max_per_ip,
+            deadline,
+            connections: HashMap::new(),
+            per_ip: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn from_config(config: &ProtocolConfig) -> Self {
+        Self::new(
+            config.max_half_open_connections_per_ip,
+            config.half_open_connection_deadline,
+        )
+    }
+
+    /// Track a connection of `ip` whose handshake starts, `None` if the IP has too many
+    pub fn open(&mut self, ip: IpAddr, connection: C, now: MassaTime) -> Option<u64> {
+        let count = self.per_ip.entry(ip).or_default();
+        if self.max_per_ip > 0 && *count >= self.max_per_ip {
+            return None;
+        }
+        *count += 1;
+        let id = self.next_id;
+        self.next_id += 1;
+        self.connections.insert(
+            id,
+            HalfOpenConnection {
+                ip,
+                opened_at: now,
+                connection: Some(connection),
+            },
+        );
+        Some(id)
+    }
+
+    /// Stop tracking a connection whose handshake is over.
+    /// Returns whether the watchdog closed it before.
+    pub fn complete(&mut self, id: u64) -> bool {
+        let Some(half_open) = self.connections.remove(&id) else {
+            return false;
+        };
+        if half_open.connection.is_none() {
+            return true;
+        }
+        self.release_ip(half_open.ip);
+        false
+    }
+
+    /// Close the connections opened more than `deadline` ago, returns their number
+    pub fn close_expired(&mut self, now: MassaTime) -> usize {
+        if self.deadline.to_millis() == 0 {
+            return 0;
+        }
+        let mut expired_ips = Vec::new();
+        for half_open in self.connections.values_mut() {
+            if now.saturating_sub(half_open.opened_at) < self.deadline {
+                continue;
+            }
+            // the entry stays until the handshake returns, to tell it was closed
+            if let Some(mut connection) = half_open.connection.take() {
+                connection.close();
+                expired_ips.push(half_open.ip);
+            }
+        }
+        for ip in &expired_ips {
+            self.release_ip(*ip);
+        }
+        expired_ips.len()
+    }
+
+    /// Number of connections of `ip` that are still open
+    pub fn count(&self, ip: &IpAddr) -> usize {
+        self.per_ip.get(ip).copied().unwrap_or_default()
+    }
+
+    fn release_ip(&mut self, ip: IpAddr) {
+        if let Some(count) = self.per_ip.get_mut(&ip) {
+            *count -= 1;
+            if *count == 0 {
+                self.per_ip.remove(&ip);
+            }
+        }
+    }
+}
+
+/// Start the thread closing the half-open connections past their deadline.
+/// It stops once the connections are dropped by all the handshakes.
+pub(crate) fn start_half_open_watchdog<C: Closable + 'static>(
+    half_open: &Arc<Mutex<HalfOpenConnections<C>>>,
+    clock: SharedClock,
+) {
+    let half_open: Weak<Mutex<HalfOpenConnections<C>>> = Arc::downgrade(half_open);
+    let ticker = clock.ticker(HALF_OPEN_CHECK_INTERVAL);
+    let spawned = std::thread::Builder::new()
+        .name("protocol-half-open-watchdog".to_string())
+        .spawn(move || {
+            while ticker.recv().is_ok() {
+                let Some(half_open) = half_open.upgrade() else {
+                    return;
+                };
+                let closed = half_open.lock().close_expired(clock.now());
+                if closed > 0 {
+                    debug!(
+                        "Closed {} connections past their handshake deadline",
+                        closed
+                    );
+                }
+            }
+        });
+    if let Err(err) = spawned {
+        debug!(
+            "could not start the half-open connections watchdog: {}",
+            err
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
+
+    use massa_time::MassaTime;
+
+    use super::{Closable, HalfOpenConnections};
+
+    struct FakeConnection(Arc<AtomicBool>);
+
+    impl Closable for FakeConnection {
+        fn close(&mut self) {
+            self.0.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn connection() -> (FakeConnection, Arc<AtomicBool>) {
+        let closed = Arc::new(AtomicBool::new(false));
+        (FakeConnection(closed.clone()), closed)
+    }
+
+    #[test]
+    fn test_half_open_connections_capped_and_closed_after_deadline() {
+        let ip = IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4));
+        let other_ip = IpAddr::V4(Ipv4Addr::new(5, 6, 7, 8));
+        let mut half_open = HalfOpenConnections::new(2, MassaTime::from_millis(1000));
+
+        let (first, first_closed) = connection();
+        let first = half_open
+            .open(ip, first, MassaTime::from_millis(0))
+            .unwrap();
+        let (second, second_closed) = connection();
+        let second = half_open
+            .open(ip, second, MassaTime::from_millis(500))
+            .unwrap();
+        // the IP reached its cap, the others can still connect
+        assert!(half_open
+            .open(ip, connection().0, MassaTime::from_millis(600))
+            .is_none());
+        assert!(half_open
+            .open(other_ip, connection().0, MassaTime::from_millis(600))
+            .is_some());
+
+        // only the connections opened before the deadline are closed
+        assert_eq!(half_open.close_expired(MassaTime::from_millis(1200)), 1);
+        assert!(first_closed.load(Ordering::Relaxed));
+        assert!(!second_closed.load(Ordering::Relaxed));
+        assert_eq!(half_open.count(&ip), 1);
+        assert!(half_open
+            .open(ip, connection().0, MassaTime::from_millis(1200))
+            .is_some());
+
+        // the handshakes learn whether their connection was closed
+        assert!(half_open.complete(first));
+        assert!(!half_open.complete(second));
+        assert!(!second_closed.load(Ordering::Relaxed));
+        assert_eq!(half_open.count(&ip), 1);
+        assert_eq!(half_open.close_expired(MassaTime::from_millis(1200)), 0);
+    }
+}
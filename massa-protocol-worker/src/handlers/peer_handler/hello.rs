@@ -0,0 +1,543 @@
+//! Parsing of the hello message that both sides send at the beginning of a handshake.
+//!
+//! The hello is made of the peer id, its version and either a signed announcement (id 0) when
+//! the peer accepted us, or a regular message (id 1) when it has no slot for us.
+//! The announcement can be followed by the handshake features, a varint whose first byte is
+//! the byte of features of the older versions. Older nodes don't send it and ignore it, which
+//! lets both sides agree on the features before the challenge exchange.
+//! The id of the network of the peer follows the features when it advertises it, the hellos
+//! of the older nodes being accepted without it.
+//! When both sides support it, the challenge signatures cover the transcript of the handshake,
+//! so that a feature byte stripped or a version rewritten on the way makes the handshake fail.
+//! Nothing is read after the features and the network id: a node configured to pad its traffic appends random
+//! bytes there, so that the size of its hello doesn't depend on its listeners.
+//! Parsing is kept independent from any endpoint so it can be run on raw bytes.
+
+use std::ops::Bound::Included;
+
+use massa_hash::{Hash, HASH_SIZE_BYTES};
+use massa_models::version::{Version, VersionDeserializer};
+use massa_protocol_exports::{HandshakeStage, PeerHandlerError};
+use massa_serialization::{
+    DeserializeError, Deserializer, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
+};
+use massa_time::MassaTime;
+use peernet::{
+    peer::PeerConnectionType,
+    peer_id::PeerId,
+    types::{Hash as PeerNetHash, PUBLIC_KEY_SIZE_BYTES},
+};
+
+use super::{
+    announcement::{Announcement, AnnouncementDeserializer},
+    error::PeerHandlerResult,
+};
+
+/// The random challenge signatures are bound to the peer ids and challenges of the connection
+pub(crate) const HANDSHAKE_FEATURE_BOUND_CHALLENGE: u64 = 0b0000_0001;
+/// When two connections with the same peer complete, the one dialed by the lowest peer id is kept
+pub(crate) const HANDSHAKE_FEATURE_DIAL_TIE_BREAK: u64 = 0b0000_0010;
+/// The messages after the handshake are prefixed by sequence numbers, to reject the replays
+pub(crate) const HANDSHAKE_FEATURE_SEQUENCE_NUMBERS: u64 = 0b0000_0100;
+/// The challenge signatures cover all the bytes exchanged during the handshake
+pub(crate) const HANDSHAKE_FEATURE_TRANSCRIPT: u64 = 0b0000_1000;
+/// The peer management messages after the handshake are padded with random bytes. Only sent by
+/// the nodes configured to pad their traffic, which accept the padded messages. The padding
+/// follows the framed content, it is only used with the peers that frame their messages.
+pub(crate) const HANDSHAKE_FEATURE_PADDING: u64 = 0b0001_0000;
+/// The id of the network of the peer follows the features, the handshake failing when it
+/// isn't ours
+pub(crate) const HANDSHAKE_FEATURE_NETWORK_ID: u64 = 0b0010_0000;
+/// The peer management messages after the handshake are framed with a version and a length,
+/// see `PEER_MANAGEMENT_MESSAGE_VERSION`
+pub(crate) const HANDSHAKE_FEATURE_FRAMED_PEER_MESSAGES: u64 = 0b0100_0000;
+/// The peer reads the signed metadata sent after the list of peers at the end of the handshake.
+/// First feature outside of the byte of the older versions, which read the continuation bit of
+/// the varint as an unknown feature.
+pub(crate) const HANDSHAKE_FEATURE_PEER_METADATA: u64 = 0b1000_0000;
+/// Handshake features supported by every node of this version, sent after the announcement of
+/// the hello
+pub(crate) const HANDSHAKE_FEATURES: u64 = HANDSHAKE_FEATURE_BOUND_CHALLENGE
+    | HANDSHAKE_FEATURE_DIAL_TIE_BREAK
+    | HANDSHAKE_FEATURE_SEQUENCE_NUMBERS
+    | HANDSHAKE_FEATURE_TRANSCRIPT
+    | HANDSHAKE_FEATURE_NETWORK_ID
+    | HANDSHAKE_FEATURE_FRAMED_PEER_MESSAGES
+    | HANDSHAKE_FEATURE_PEER_METADATA;
+
+/// Content of a hello message after the peer id and the version
+#[derive(Debug)]
+pub(crate) enum HelloContent<'a> {
+    /// The peer accepted the connection and sent its announcement and handshake features
+    Announcement(Announcement, u64),
+    /// The peer refused the connection and sent a message instead (id + content)
+    Message(&'a [u8]),
+}
+
+/// Append the handshake features to a hello
+pub(crate) fn serialize_handshake_features(features: u64, buffer: &mut Vec<u8>) {
+    U64VarIntSerializer::new()
+        .serialize(&features, buffer)
+        .expect("the serialization of a varint doesn't fail");
+}
+
+/// Identifier of the network a node belongs to, sent in the hello right after the features.
+/// The genesis timestamp is part of it so that devnets sharing a chain id are told apart.
+pub(crate) fn network_id(chain_id: u64, genesis_timestamp: MassaTime) -> Hash {
+    Hash::compute_from(
+        &[
+            chain_id.to_be_bytes(),
+            genesis_timestamp.to_millis().to_be_bytes(),
+        ]
+        .concat(),
+    )
+}
+
+/// Hash signed by `signer` to answer the challenge `verifier_random_bytes` of `verifier`.
+/// Both challenges and both peer ids are part of it, in the order of the roles, so that a
+/// signature can't be replayed on another connection nor relayed from the other side of
+/// a simultaneous handshake.
+pub(crate) fn bound_challenge_hash(
+    verifier_random_bytes: &[u8; 32],
+    signer_random_bytes: &[u8; 32],
+    signer: &PeerId,
+    verifier: &PeerId,
+) -> PeerNetHash {
+    PeerNetHash::compute_from(
+        &[
+            b"massa_handshake_challenge".as_slice(),
+            verifier_random_bytes.as_slice(),
+            signer_random_bytes.as_slice(),
+            signer.to_bytes().as_slice(),
+            verifier.to_bytes().as_slice(),
+        ]
+        .concat(),
+    )
+}
+
+/// Bytes sent and received during a handshake (hellos with their versions and features, then
+/// challenges), accumulated until the challenge signatures.
+/// What one side sent is what the other side received, so both sides compute the same hash
+/// unless an active attacker altered the bytes on the way.
+#[derive(Debug, Default)]
+pub(crate) struct HandshakeTranscript {
+    sent: Vec<u8>,
+    received: Vec<u8>,
+}
+
+impl HandshakeTranscript {
+    /// Record bytes sent to the peer
+    pub(crate) fn record_sent(&mut self, bytes: &[u8]) {
+        self.sent.extend_from_slice(bytes);
+    }
+
+    /// Record bytes received from the peer
+    pub(crate) fn record_received(&mut self, bytes: &[u8]) {
+        self.received.extend_from_slice(bytes);
+    }
+
+    /// Hash we sign to answer the challenge of `peer_id`
+    pub(crate) fn signed_hash(&self, self_peer_id: &PeerId, peer_id: &PeerId) -> PeerNetHash {
+        transcript_hash(&self.sent, &self.received, self_peer_id, peer_id)
+    }
+
+    /// Hash `peer_id` must have signed to answer our challenge
+    pub(crate) fn verified_hash(&self, self_peer_id: &PeerId, peer_id: &PeerId) -> PeerNetHash {
+        transcript_hash(&self.received, &self.sent, peer_id, self_peer_id)
+    }
+}
+
+/// Hash of a transcript in the order of the roles, like `bound_challenge_hash`
+fn transcript_hash(
+    signer_sent: &[u8],
+    verifier_sent: &[u8],
+    signer: &PeerId,
+    verifier: &PeerId,
+) -> PeerNetHash {
+    PeerNetHash::compute_from(
+        &[
+            b"massa_handshake_transcript".as_slice(),
+            Hash::compute_from(signer_sent).to_bytes().as_slice(),
+            Hash::compute_from(verifier_sent).to_bytes().as_slice(),
+            signer.to_bytes().as_slice(),
+            verifier.to_bytes().as_slice(),
+        ]
+        .concat(),
+    )
+}
+
+/// Whether the existing connection with a peer must be kept over a new one after a simultaneous
+/// dial. The connection dialed by the lowest peer id is kept, so both sides agree on it.
+pub(crate) fn keep_existing_connection(
+    self_peer_id: &PeerId,
+    peer_id: &PeerId,
+    existing_connection_type: &PeerConnectionType,
+) -> bool {
+    let existing_dialed_by_self = existing_connection_type == &PeerConnectionType::OUT;
+    existing_dialed_by_self == (self_peer_id < peer_id)
+}
+
+/// Whether a peer that isn't reserved can take an in slot, given the number of in connections
+/// of the other peers that aren't reserved. The last `reserved_in_slots` in slots are kept for
+/// the reserved peers.
+pub(crate) fn unreserved_in_slot_available(
+    unreserved_in_connections: usize,
+    max_in_connections: usize,
+    reserved_in_slots: usize,
+) -> bool {
+    unreserved_in_connections < max_in_connections.saturating_sub(reserved_in_slots)
+}
+
+/// Read the peer id at the start of a hello message.
+/// Returns the peer id and the rest of the buffer.
+pub(crate) fn deserialize_hello_peer_id(data: &[u8]) -> PeerHandlerResult<(PeerId, &[u8])> {
+    let peer_id_bytes = data
+        .get(..PUBLIC_KEY_SIZE_BYTES)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| invalid_hello(format!("Received too short message len:{}", data.len())))?;
+    let peer_id = PeerId::from_bytes(peer_id_bytes)
+        .map_err(|err| invalid_hello(format!("Invalid peer id: {:?}", err)))?;
+    Ok((peer_id, &data[PUBLIC_KEY_SIZE_BYTES..]))
+}
+
+/// Read the version and the content of a hello message (everything after the peer id).
+/// Fails if the version isn't compatible with `our_version` or if the peer advertised the id of
+/// another network.
+pub(crate) fn deserialize_hello_content<'a>(
+    data: &'a [u8],
+    our_version: &Version,
+    our_network_id: &Hash,
+    version_deserializer: &VersionDeserializer,
+    announcement_deserializer: &AnnouncementDeserializer,
+) -> PeerHandlerResult<(Version, HelloContent<'a>)> {
+    let (data, version) = version_deserializer
+        .deserialize::<DeserializeError>(data)
+        .map_err(|err| invalid_hello(format!("Failed to deserialize version: {}", err)))?;
+    if !our_version.is_compatible(&version) {
+        return Err(PeerHandlerError::IncompatibleVersion(version.to_string()));
+    }
+    let id = data
+        .first()
+        .ok_or_else(|| invalid_hello("Failed to get id".to_string()))?;
+    match id {
+        0 => {
+            let (rest, announcement) = announcement_deserializer
+                .deserialize::<DeserializeError>(&data[1..])
+                .map_err(|err| {
+                    invalid_hello(format!("Failed to deserialize announcement: {}", err))
+                })?;
+            // nodes that don't know handshake features don't send them
+            let (rest, features) = if rest.is_empty() {
+                (rest, 0)
+            } else {
+                U64VarIntDeserializer::new(Included(0), Included(u64::MAX))
+                    .deserialize::<DeserializeError>(rest)
+                    .map_err(|err| {
+                        invalid_hello(format!("Failed to deserialize features: {}", err))
+                    })?
+            };
+            if features & HANDSHAKE_FEATURE_NETWORK_ID != 0 {
+                let network_id_bytes: &[u8; HASH_SIZE_BYTES] = rest
+                    .get(..HASH_SIZE_BYTES)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| invalid_hello("Failed to get network id".to_string()))?;
+                if Hash::from_bytes(network_id_bytes) != *our_network_id {
+                    return Err(PeerHandlerError::WrongNetwork);
+                }
+            }
+            Ok((version, HelloContent::Announcement(announcement, features)))
+        }
+        1 => Ok((version, HelloContent::Message(&data[1..]))),
+        _ => Err(invalid_hello("Invalid message id".to_string())),
+    }
+}
+
+fn invalid_hello(reason: String) -> PeerHandlerError {
+    PeerHandlerError::InvalidMessage {
+        stage: HandshakeStage::Hello,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use massa_models::version::{Version, VersionDeserializer, VersionSerializer};
+    use massa_protocol_exports::PeerHandlerError;
+    use massa_serialization::Serializer;
+    use peernet::{
+        peer::PeerConnectionType, peer_id::PeerId, transports::TransportType, types::KeyPair,
+    };
+
+    use super::{
+        bound_challenge_hash, deserialize_hello_content, deserialize_hello_peer_id,
+        keep_existing_connection, network_id, serialize_handshake_features,
+        unreserved_in_slot_available, HandshakeTranscript, HelloContent, HANDSHAKE_FEATURES,
+        HANDSHAKE_FEATURE_FRAMED_PEER_MESSAGES, HANDSHAKE_FEATURE_PEER_METADATA,
+    };
+    use crate::handlers::peer_handler::announcement::{
+        Announcement, AnnouncementDeserializer, AnnouncementDeserializerArgs,
+        AnnouncementSerializer,
+    };
+
+    #[test]
+    fn test_hello_round_trip() {
+        let keypair = KeyPair::generate();
+        let version: Version = "TEST.22.2".parse().unwrap();
+        let mut listeners = HashMap::new();
+        listeners.insert("127.0.0.1:8081".parse().unwrap(), TransportType::Tcp);
+        let announcement =
+            Announcement::new(listeners, Some("127.0.0.1".parse().unwrap()), &keypair).unwrap();
+
+        let mut bytes = PeerId::from_public_key(keypair.get_public_key()).to_bytes();
+        VersionSerializer::new()
+            .serialize(&version, &mut bytes)
+            .unwrap();
+        bytes.push(0);
+        AnnouncementSerializer::new()
+            .serialize(&announcement, &mut bytes)
+            .unwrap();
+
+        let (peer_id, rest) = deserialize_hello_peer_id(&bytes).unwrap();
+        assert_eq!(peer_id, PeerId::from_public_key(keypair.get_public_key()));
+        let (_, content) = deserialize_hello_content(
+            rest,
+            &version,
+            &network_id(77, 1000.into()),
+            &VersionDeserializer::new(),
+            &AnnouncementDeserializer::new(AnnouncementDeserializerArgs { max_listeners: 100 }),
+        )
+        .unwrap();
+        // no handshake features sent, as by older nodes
+        match content {
+            HelloContent::Announcement(received, features) => {
+                assert_eq!(received, announcement);
+                assert_eq!(features, 0);
+            }
+            _ => panic!("Bad hello content deserialized"),
+        }
+
+        serialize_handshake_features(HANDSHAKE_FEATURES, &mut bytes);
+        bytes.extend(network_id(77, 1000.into()).to_bytes());
+        let (_, rest) = deserialize_hello_peer_id(&bytes).unwrap();
+        let (_, content) = deserialize_hello_content(
+            rest,
+            &version,
+            &network_id(77, 1000.into()),
+            &VersionDeserializer::new(),
+            &AnnouncementDeserializer::new(AnnouncementDeserializerArgs { max_listeners: 100 }),
+        )
+        .unwrap();
+        match content {
+            HelloContent::Announcement(_, features) => assert_eq!(features, HANDSHAKE_FEATURES),
+            _ => panic!("Bad hello content deserialized"),
+        }
+
+        // the padding after the features and the network id is ignored
+        bytes.extend([0xff; 100]);
+        let (_, rest) = deserialize_hello_peer_id(&bytes).unwrap();
+        let (_, content) = deserialize_hello_content(
+            rest,
+            &version,
+            &network_id(77, 1000.into()),
+            &VersionDeserializer::new(),
+            &AnnouncementDeserializer::new(AnnouncementDeserializerArgs { max_listeners: 100 }),
+        )
+        .unwrap();
+        match content {
+            HelloContent::Announcement(received, features) => {
+                assert_eq!(received, announcement);
+                assert_eq!(features, HANDSHAKE_FEATURES);
+            }
+            _ => panic!("Bad hello content deserialized"),
+        }
+    }
+
+    #[test]
+    fn test_handshake_features_varint() {
+        // the features of the older versions fit in their byte
+        let mut bytes = Vec::new();
+        serialize_handshake_features(HANDSHAKE_FEATURE_FRAMED_PEER_MESSAGES, &mut bytes);
+        assert_eq!(bytes, vec![HANDSHAKE_FEATURE_FRAMED_PEER_MESSAGES as u8]);
+        // the first byte keeps them, the newer features follow it
+        let mut bytes = Vec::new();
+        serialize_handshake_features(HANDSHAKE_FEATURES, &mut bytes);
+        assert_eq!(bytes.len(), 2);
+        assert_eq!(u64::from(bytes[0] & 0x7f), HANDSHAKE_FEATURES & 0x7f);
+        assert_ne!(HANDSHAKE_FEATURES & HANDSHAKE_FEATURE_PEER_METADATA, 0);
+    }
+
+    #[test]
+    fn test_bound_challenge_hash() {
+        let first = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        let second = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        let hash = bound_challenge_hash(&[1; 32], &[2; 32], &first, &second);
+        // the signature of one side can't be used by the other side nor on another connection
+        assert_ne!(
+            hash,
+            bound_challenge_hash(&[1; 32], &[2; 32], &second, &first)
+        );
+        assert_ne!(
+            hash,
+            bound_challenge_hash(&[2; 32], &[1; 32], &first, &second)
+        );
+        assert_ne!(
+            hash,
+            bound_challenge_hash(&[1; 32], &[3; 32], &first, &second)
+        );
+    }
+
+    #[test]
+    fn test_handshake_transcript() {
+        let first = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        let second = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        let hello = [1u8, 2, 3, 0x7f];
+        let other_hello = [4u8, 5, 6, 0x7f];
+        let mut transcript = HandshakeTranscript::default();
+        transcript.record_sent(&hello);
+        transcript.record_received(&other_hello);
+        transcript.record_sent(&[7; 32]);
+        transcript.record_received(&[8; 32]);
+        let mut other_transcript = HandshakeTranscript::default();
+        other_transcript.record_sent(&other_hello);
+        other_transcript.record_received(&hello);
+        other_transcript.record_sent(&[8; 32]);
+        other_transcript.record_received(&[7; 32]);
+        // what one side signs is what the other side checks, and the roles can't be swapped
+        assert_eq!(
+            transcript.signed_hash(&first, &second),
+            other_transcript.verified_hash(&second, &first)
+        );
+        assert_eq!(
+            transcript.verified_hash(&first, &second),
+            other_transcript.signed_hash(&second, &first)
+        );
+        assert_ne!(
+            transcript.signed_hash(&first, &second),
+            transcript.verified_hash(&first, &second)
+        );
+
+        // an attacker stripped the features of the hello on the way
+        let mut downgraded = HandshakeTranscript::default();
+        downgraded.record_sent(&other_hello);
+        downgraded.record_received(&hello[..3]);
+        downgraded.record_sent(&[8; 32]);
+        downgraded.record_received(&[7; 32]);
+        assert_ne!(
+            transcript.signed_hash(&first, &second),
+            downgraded.verified_hash(&second, &first)
+        );
+    }
+
+    #[test]
+    fn test_simultaneous_dial_tie_break() {
+        let mut peer_ids: Vec<PeerId> = (0..2)
+            .map(|_| PeerId::from_public_key(KeyPair::generate().get_public_key()))
+            .collect();
+        peer_ids.sort();
+        let (low, high) = (&peer_ids[0], &peer_ids[1]);
+        // the connection dialed by `low` is OUT on its side and IN on the other side
+        assert!(keep_existing_connection(
+            low,
+            high,
+            &PeerConnectionType::OUT
+        ));
+        assert!(keep_existing_connection(high, low, &PeerConnectionType::IN));
+        // the connection dialed by `high` is replaced on both sides
+        assert!(!keep_existing_connection(
+            low,
+            high,
+            &PeerConnectionType::IN
+        ));
+        assert!(!keep_existing_connection(
+            high,
+            low,
+            &PeerConnectionType::OUT
+        ));
+    }
+
+    #[test]
+    fn test_reserved_in_slots() {
+        // no reservation: all the in slots are open
+        assert!(unreserved_in_slot_available(9, 10, 0));
+        assert!(!unreserved_in_slot_available(10, 10, 0));
+        // the last 3 slots are kept for the reserved peers
+        assert!(unreserved_in_slot_available(6, 10, 3));
+        assert!(!unreserved_in_slot_available(7, 10, 3));
+        // more reserved slots than slots
+        assert!(!unreserved_in_slot_available(0, 2, 3));
+    }
+
+    #[test]
+    fn test_hello_truncated() {
+        let keypair = KeyPair::generate();
+        let bytes = PeerId::from_public_key(keypair.get_public_key()).to_bytes();
+        assert!(deserialize_hello_peer_id(&bytes[..10]).is_err());
+        let (_, rest) = deserialize_hello_peer_id(&bytes).unwrap();
+        assert!(deserialize_hello_content(
+            rest,
+            &"TEST.22.2".parse().unwrap(),
+            &network_id(77, 1000.into()),
+            &VersionDeserializer::new(),
+            &AnnouncementDeserializer::new(AnnouncementDeserializerArgs { max_listeners: 100 }),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_hello_other_network() {
+        let keypair = KeyPair::generate();
+        let version: Version = "TEST.22.2".parse().unwrap();
+        let announcement = Announcement::new(HashMap::new(), None, &keypair).unwrap();
+
+        let mut bytes = PeerId::from_public_key(keypair.get_public_key()).to_bytes();
+        VersionSerializer::new()
+            .serialize(&version, &mut bytes)
+            .unwrap();
+        bytes.push(0);
+        AnnouncementSerializer::new()
+            .serialize(&announcement, &mut bytes)
+            .unwrap();
+
+        // the older nodes don't send their network id
+        let (_, rest) = deserialize_hello_peer_id(&bytes).unwrap();
+        assert!(deserialize_hello_content(
+            rest,
+            &version,
+            &network_id(78, 1000.into()),
+            &VersionDeserializer::new(),
+            &AnnouncementDeserializer::new(AnnouncementDeserializerArgs { max_listeners: 100 }),
+        )
+        .is_ok());
+
+        // the network id is required once advertised
+        serialize_handshake_features(HANDSHAKE_FEATURES, &mut bytes);
+        let (_, rest) = deserialize_hello_peer_id(&bytes).unwrap();
+        assert!(deserialize_hello_content(
+            rest,
+            &version,
+            &network_id(77, 1000.into()),
+            &VersionDeserializer::new(),
+            &AnnouncementDeserializer::new(AnnouncementDeserializerArgs { max_listeners: 100 }),
+        )
+        .is_err());
+
+        bytes.extend(network_id(77, 1000.into()).to_bytes());
+        let (_, rest) = deserialize_hello_peer_id(&bytes).unwrap();
+        for other_network in [network_id(78, 1000.into()), network_id(77, 2000.into())] {
+            assert!(matches!(
+                deserialize_hello_content(
+                    rest,
+                    &version,
+                    &other_network,
+                    &VersionDeserializer::new(),
+                    &AnnouncementDeserializer::new(AnnouncementDeserializerArgs {
+                        max_listeners: 100
+                    }),
+                ),
+                Err(PeerHandlerError::WrongNetwork)
+            ));
+        }
+    }
+}
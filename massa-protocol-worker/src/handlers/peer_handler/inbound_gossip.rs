@@ -0,0 +1,81 @@
+//! Share of the tested peers that were gossiped by inbound peers.
+//!
+//! Anyone can open inbound connections to a node. An attacker holding many of them could fill
+//! the queue of the testers with its own peers, which then end up in the peers we dial and
+//! gossip. The peers gossiped through inbound connections are only queued while they stay
+//! below a percentage of the peers queued, the others are dropped. The counts are halved
+//! regularly so that the share follows the recent gossip.
+
+use massa_protocol_exports::{PeerConnectionType, ProtocolConfig};
+
+/// Number of queued peers after which the counts are halved
+const DECAY_THRESHOLD: u64 = 10_000;
+
+pub struct InboundGossipLimiter {
+    /// maximum percentage of the queued peers gossiped by inbound peers (no limit from 100)
+    max_percent: u64,
+    from_outbound: u64,
+    from_inbound: u64,
+}
+
+impl InboundGossipLimiter {
+    pub fn from_config(config: &ProtocolConfig) -> Self {
+        Self {
+            max_percent: config.max_inbound_gossip_percent,
+            from_outbound: 0,
+            from_inbound: 0,
+        }
+    }
+
+    /// Whether a peer gossiped through a connection in the `source` direction can be tested.
+    /// Counts it if it can.
+    pub fn allow(&mut self, source: PeerConnectionType) -> bool {
+        if self.max_percent >= 100 {
+            return true;
+        }
+        match source {
+            PeerConnectionType::OUT => self.from_outbound += 1,
+            PeerConnectionType::IN => {
+                let total = self.from_inbound + self.from_outbound + 1;
+                if (self.from_inbound + 1) * 100 > self.max_percent * total {
+                    return false;
+                }
+                self.from_inbound += 1;
+            }
+        }
+        if self.from_inbound + self.from_outbound >= DECAY_THRESHOLD {
+            self.from_inbound /= 2;
+            self.from_outbound /= 2;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use massa_protocol_exports::{PeerConnectionType, ProtocolConfig};
+
+    use super::InboundGossipLimiter;
+
+    #[test]
+    fn test_inbound_gossip_share() {
+        let mut config = ProtocolConfig::default();
+        config.max_inbound_gossip_percent = 50;
+        let mut limiter = InboundGossipLimiter::from_config(&config);
+        // nothing was gossiped by outbound peers yet
+        assert!(!limiter.allow(PeerConnectionType::IN));
+
+        for _ in 0..3 {
+            assert!(limiter.allow(PeerConnectionType::OUT));
+        }
+        let allowed = (0..10)
+            .filter(|_| limiter.allow(PeerConnectionType::IN))
+            .count();
+        assert_eq!(allowed, 3);
+
+        // no limit at 100%
+        config.max_inbound_gossip_percent = 100;
+        let mut limiter = InboundGossipLimiter::from_config(&config);
+        assert!((0..10).all(|_| limiter.allow(PeerConnectionType::IN)));
+    }
+}
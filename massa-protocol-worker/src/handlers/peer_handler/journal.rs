@@ -0,0 +1,277 @@
+//! Journal of the critical changes of the peer DB.
+//!
+//! The peer DB only lives in memory. The bans of peers and IPs, and the peers promoted to
+//! trusted with their announcement, are appended to a log as they happen, one JSON entry per
+//! line, so that they survive an unclean shutdown. The log is replayed into the DB at startup,
+//! before the peer handler starts gossiping, and then rewritten with the resulting state. It is
+//! also rewritten once the entries appended since outnumber the state, so that the peers
+//! promoted again at each of their connections don't make it grow forever.
+//!
+//! Each entry is written with a single write to a file opened in append mode: a crash can't
+//! interleave two entries, and can at worst truncate the last one, which is skipped at replay.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use massa_serialization::{DeserializeError, Deserializer, Serializer};
+use massa_time::MassaTime;
+use parking_lot::Mutex;
+use peernet::peer_id::PeerId;
+use serde::{Deserialize, Serialize};
+use tracing::log::warn;
+
+use super::{
+    announcement::{
+        AnnouncementDeserializer, AnnouncementDeserializerArgs, AnnouncementSerializer,
+    },
+    models::{PeerDB, PeerState},
+};
+
+/// Appended entries below which the journal is never compacted
+const MIN_ENTRIES_BEFORE_COMPACTION: usize = 1000;
+
+/// Critical change of the peer DB
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JournalEntry {
+    /// The peer passed a handshake and became trusted, with its serialized announcement
+    Trusted {
+        peer_id: PeerId,
+        announcement: Vec<u8>,
+    },
+    Ban(PeerId),
+    Unban(PeerId),
+    BanIp {
+        ip: IpAddr,
+        until: MassaTime,
+    },
+    UnbanIp(IpAddr),
+}
+
+pub type SharedPeerDBJournal = Arc<Mutex<PeerDBJournal>>;
+
+pub struct PeerDBJournal {
+    path: PathBuf,
+    file: File,
+    /// entries written by the last compaction
+    compacted: usize,
+    /// entries appended since the last compaction
+    appended: usize,
+}
+
+impl PeerDBJournal {
+    /// Replay the journal at `path` into `peer_db`, then rewrite it with the resulting state and
+    /// open it for appending. A missing journal is created.
+    pub fn open(
+        path: &Path,
+        peer_db: &mut PeerDB,
+        max_listeners: u64,
+        now: MassaTime,
+    ) -> io::Result<Self> {
+        if path.is_file() {
+            let deserializer =
+                AnnouncementDeserializer::new(AnnouncementDeserializerArgs { max_listeners });
+            let mut skipped = 0;
+            for line in BufReader::new(File::open(path)?).lines() {
+                match serde_json::from_str::<JournalEntry>(&line?) {
+                    Ok(entry) => {
+                        if !replay(peer_db, entry, &deserializer, now) {
+                            skipped += 1;
+                        }
+                    }
+                    Err(_) => skipped += 1,
+                }
+            }
+            if skipped > 0 {
+                warn!("skipped {} invalid entries of the peer DB journal", skipped);
+            }
+        }
+        let (file, compacted) = compact(path, peer_db, now)?;
+        Ok(PeerDBJournal {
+            path: path.to_path_buf(),
+            file,
+            compacted,
+            appended: 0,
+        })
+    }
+
+    /// Append an entry. `peer_db` is the state after the change, written instead of the log
+    /// once the log outgrew it.
+    pub fn append(&mut self, entry: &JournalEntry, peer_db: &PeerDB, now: MassaTime) {
+        let result = serde_json::to_string(entry)
+            .map_err(io::Error::from)
+            .and_then(|json| self.file.write_all(format!("{}\n", json).as_bytes()));
+        if let Err(err) = result {
+            warn!("could not append to the peer DB journal: {}", err);
+            return;
+        }
+        self.appended += 1;
+        if self.appended > MIN_ENTRIES_BEFORE_COMPACTION.max(self.compacted) {
+            match compact(&self.path, peer_db, now) {
+                Ok((file, compacted)) => {
+                    self.file = file;
+                    self.compacted = compacted;
+                    self.appended = 0;
+                }
+                Err(err) => warn!("could not compact the peer DB journal: {}", err),
+            }
+        }
+    }
+}
+
+/// Apply an entry to the DB. Returns false if it is invalid.
+fn replay(
+    peer_db: &mut PeerDB,
+    entry: JournalEntry,
+    deserializer: &AnnouncementDeserializer,
+    now: MassaTime,
+) -> bool {
+    match entry {
+        JournalEntry::Trusted {
+            peer_id,
+            announcement,
+        } => match deserializer.deserialize::<DeserializeError>(&announcement) {
+            Ok((_, announcement)) => peer_db.set_peer_announcement(&peer_id, announcement),
+            Err(_) => return false,
+        },
+        JournalEntry::Ban(peer_id) => peer_db.ban_peer(&peer_id),
+        JournalEntry::Unban(peer_id) => peer_db.unban_peer(&peer_id),
+        JournalEntry::BanIp { ip, until } => {
+            if until > now {
+                let ban_end = peer_db.banned_ips.entry(ip).or_insert(until);
+                *ban_end = (*ban_end).max(until);
+            }
+        }
+        JournalEntry::UnbanIp(ip) => {
+            peer_db.banned_ips.remove(&ip);
+        }
+    }
+    true
+}
+
+/// Entries rebuilding the critical state of the DB
+fn state_entries(peer_db: &PeerDB, now: MassaTime) -> Vec<JournalEntry> {
+    let serializer = AnnouncementSerializer::new();
+    let mut entries = Vec::new();
+    for (peer_id, info) in &peer_db.peers {
+        if !matches!(info.state, PeerState::Trusted | PeerState::Banned) {
+            continue;
+        }
+        let mut announcement = Vec::new();
+        if serializer
+            .serialize(&info.last_announce, &mut announcement)
+            .is_err()
+        {
+            continue;
+        }
+        entries.push(JournalEntry::Trusted {
+            peer_id: peer_id.clone(),
+            announcement,
+        });
+        if info.state == PeerState::Banned {
+            entries.push(JournalEntry::Ban(peer_id.clone()));
+        }
+    }
+    entries.extend(
+        peer_db
+            .banned_ips
+            .iter()
+            .filter(|(_, until)| **until > now)
+            .map(|(ip, until)| JournalEntry::BanIp {
+                ip: *ip,
+                until: *until,
+            }),
+    );
+    entries
+}
+
+/// Replace the journal with the entries of the current state, written to a temporary file
+/// first so that a crash keeps the previous journal. Returns the journal opened for appending
+/// and the number of entries written.
+fn compact(path: &Path, peer_db: &PeerDB, now: MassaTime) -> io::Result<(File, usize)> {
+    let entries = state_entries(peer_db, now);
+    let mut content = String::new();
+    for entry in &entries {
+        content.push_str(&serde_json::to_string(entry)?);
+        content.push('\n');
+    }
+    let tmp_path = path.with_extension("tmp");
+    {
+        let mut tmp = File::create(&tmp_path)?;
+        tmp.write_all(content.as_bytes())?;
+        tmp.sync_all()?;
+    }
+    std::fs::rename(&tmp_path, path)?;
+    let file = OpenOptions::new().append(true).open(path)?;
+    Ok((file, entries.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use massa_protocol_exports::BootstrapClientReport;
+    use massa_time::MassaTime;
+    use parking_lot::Mutex;
+    use peernet::{peer_id::PeerId, transports::TransportType, types::KeyPair};
+    use tempfile::tempdir;
+
+    use super::PeerDBJournal;
+    use crate::handlers::peer_handler::{
+        announcement::Announcement,
+        models::{BootstrapOffensePolicy, PeerDB, PeerState},
+    };
+
+    #[test]
+    fn test_journal_survives_restart() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("peer_db_journal.jsonl");
+        let now = MassaTime::from_millis(1_000_000);
+        let keypair = KeyPair::generate();
+        let peer_id = PeerId::from_public_key(keypair.get_public_key());
+        let announcement = Announcement::new(
+            HashMap::from([("82.245.0.1:31244".parse().unwrap(), TransportType::Tcp)]),
+            Some("82.245.0.1".parse().unwrap()),
+            &keypair,
+        )
+        .unwrap();
+        let ip = "91.12.0.1".parse().unwrap();
+
+        {
+            let mut peer_db = PeerDB::new(32);
+            let journal = PeerDBJournal::open(&path, &mut peer_db, 100, now).unwrap();
+            peer_db.journal = Some(Arc::new(Mutex::new(journal)));
+            peer_db.set_peer_announcement(&peer_id, announcement);
+            peer_db.ban_peer(&peer_id);
+            let policy = BootstrapOffensePolicy {
+                ban_threshold: 1,
+                window: MassaTime::from_millis(1000),
+                ban_duration: MassaTime::from_millis(1000),
+            };
+            peer_db.record_bootstrap_report(ip, BootstrapClientReport::Blacklisted, &policy, now);
+            // dropped without any shutdown
+        }
+
+        // the journal is a log of the changes
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), 3);
+
+        // a truncated last entry is skipped
+        std::fs::write(&path, format!("{}{{\"Ban\":", content)).unwrap();
+        let mut peer_db = PeerDB::new(32);
+        PeerDBJournal::open(&path, &mut peer_db, 100, now).unwrap();
+        assert_eq!(peer_db.peers[&peer_id].state, PeerState::Banned);
+        assert!(peer_db.is_ip_banned(ip, now));
+        assert_eq!(peer_db.check_invariants(), Ok(()));
+
+        // the expired IP bans are not kept
+        let mut peer_db = PeerDB::new(32);
+        PeerDBJournal::open(&path, &mut peer_db, 100, now.saturating_add(now)).unwrap();
+        assert!(peer_db.banned_ips.is_empty());
+        assert_eq!(peer_db.peers[&peer_id].state, PeerState::Banned);
+    }
+}
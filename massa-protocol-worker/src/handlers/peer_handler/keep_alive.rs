@@ -0,0 +1,116 @@
+use std::collections::{HashMap, HashSet};
+
+use massa_protocol_exports::ProtocolConfig;
+use massa_time::MassaTime;
+use peernet::peer_id::PeerId;
+
+/// Ping sent to a peer and not answered yet
+struct PendingPing {
+    nonce: u64,
+    /// number of intervals that passed without an answer
+    missed: u64,
+}
+
+/// Pings the connected peers at a regular interval and detects the ones that stopped
+/// answering, without waiting for the TCP timeouts.
+pub(crate) struct KeepAlive {
+    /// interval between two pings
+    pub(crate) interval: MassaTime,
+    /// number of unanswered pings after which a connection is closed (0 to disable)
+    max_missed: u64,
+    pending: HashMap<PeerId, PendingPing>,
+    next_nonce: u64,
+}
+
+impl KeepAlive {
+    pub(crate) fn from_config(config: &ProtocolConfig) -> Self {
+        Self {
+            interval: config.keep_alive_interval,
+            max_missed: config.keep_alive_max_missed,
+            pending: HashMap::new(),
+            next_nonce: 0,
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.max_missed > 0
+    }
+
+    /// Called every interval with the connected peers supporting the keep-alive.
+    /// Returns the pings to send and the peers that missed too many of them.
+    pub(crate) fn tick(&mut self, peers: HashSet<PeerId>) -> (Vec<(PeerId, u64)>, Vec<PeerId>) {
+        self.pending.retain(|peer_id, _| peers.contains(peer_id));
+        let mut pings = Vec::new();
+        let mut dead = Vec::new();
+        for peer_id in peers {
+            let nonce = self.next_nonce;
+            self.next_nonce = self.next_nonce.wrapping_add(1);
+            match self.pending.get_mut(&peer_id) {
+                Some(pending) => {
+                    pending.missed += 1;
+                    if pending.missed >= self.max_missed {
+                        self.pending.remove(&peer_id);
+                        dead.push(peer_id);
+                        continue;
+                    }
+                    // an answer to any ping would do but only the last one is remembered
+                    pending.nonce = nonce;
+                }
+                None => {
+                    self.pending
+                        .insert(peer_id.clone(), PendingPing { nonce, missed: 0 });
+                }
+            }
+            pings.push((peer_id, nonce));
+        }
+        (pings, dead)
+    }
+
+    /// Records the answer of a peer. Returns false if it doesn't answer the last ping sent.
+    pub(crate) fn pong(&mut self, peer_id: &PeerId, nonce: u64) -> bool {
+        match self.pending.get(peer_id) {
+            Some(pending) if pending.nonce == nonce => {
+                self.pending.remove(peer_id);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use massa_protocol_exports::ProtocolConfig;
+    use peernet::{peer_id::PeerId, types::KeyPair};
+
+    use super::KeepAlive;
+
+    #[test]
+    fn test_keep_alive_detects_unresponsive_peers() {
+        let mut config = ProtocolConfig::default();
+        config.keep_alive_max_missed = 2;
+        let mut keep_alive = KeepAlive::from_config(&config);
+        let responsive = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        let unresponsive = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        let peers = HashSet::from([responsive.clone(), unresponsive.clone()]);
+
+        // the first tick sends the pings, the next ones find them answered or not
+        for _ in 0..2 {
+            let (pings, dead) = keep_alive.tick(peers.clone());
+            assert_eq!(pings.len(), 2);
+            assert!(dead.is_empty());
+            let (_, nonce) = pings
+                .iter()
+                .find(|(peer_id, _)| *peer_id == responsive)
+                .unwrap();
+            assert!(!keep_alive.pong(&responsive, nonce.wrapping_add(100)));
+            assert!(keep_alive.pong(&responsive, *nonce));
+        }
+        let (pings, dead) = keep_alive.tick(peers);
+        assert_eq!(dead, vec![unresponsive]);
+        assert_eq!(pings.len(), 1);
+        assert_eq!(pings[0].0, responsive);
+    }
+}
@@ -3,6 +3,7 @@ use std::{collections::HashMap, net::SocketAddr, ops::Bound::Included};
 use massa_models::serialization::{IpAddrDeserializer, IpAddrSerializer};
 use massa_serialization::{Deserializer, Serializer, U64VarIntDeserializer, U64VarIntSerializer};
 use nom::{
+    bytes::complete::take,
     error::{context, ContextError, ParseError},
     multi::length_count,
     sequence::tuple,
@@ -11,6 +12,18 @@ use nom::{
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use peernet::{peer_id::PeerId, transports::TransportType, types::PUBLIC_KEY_SIZE_BYTES};
 
+use super::metadata::{PeerMetadata, PeerMetadataDeserializer, PeerMetadataSerializer};
+use super::status::{PeerStatus, PeerStatusDeserializer, PeerStatusSerializer};
+
+/// Version of the framing of the peer management messages.
+///
+/// With the peers that negotiated the framing in their handshake, each message is serialized as
+/// `version (u8) | content length (varint) | content`, the older nodes getting the content alone.
+/// A node reads the fields it knows at the start of the content and ignores the remainder
+/// when the version is newer than its own, so that fields can be appended to the existing
+/// messages without breaking older nodes.
+pub const PEER_MANAGEMENT_MESSAGE_VERSION: u8 = 0;
+
 #[derive(Debug, Clone)]
 //TODO: Fix this clippy warning
 #[allow(clippy::large_enum_variant)]
@@ -19,6 +32,42 @@ pub enum PeerManagementMessage {
     NewPeerConnected((PeerId, HashMap<SocketAddr, TransportType>)),
     // Receive the ip addresses sent by a peer that is already connected.
     ListPeers(Vec<(PeerId, HashMap<SocketAddr, TransportType>)>),
+    // Receive the signed metadata of a peer right after the handshake.
+    PeerMetadata(PeerMetadata),
+    // Receive the reason why a peer is about to close the connection.
+    Disconnecting(DisconnectReason),
+    // Receive a keep-alive ping, to answer with a pong carrying the same nonce.
+    Ping(u64),
+    // Receive the answer to a keep-alive ping.
+    Pong(u64),
+    // Receive the latest final block and the head of the blockclique of a peer.
+    Status(PeerStatus),
+    // Receive a request to dial back one of the listeners announced by the peer.
+    DialBackRequest { nonce: u64, address: SocketAddr },
+    // Receive the outcome of the dial back of one of our listeners.
+    DialBackResult { nonce: u64, reachable: bool },
+}
+
+/// Reason sent by a node before it closes a connection
+#[derive(IntoPrimitive, Debug, Clone, Copy, Eq, PartialEq, TryFromPrimitive)]
+#[repr(u64)]
+pub enum DisconnectReason {
+    /// Reason code unknown to this version of the node
+    Unknown = 0,
+    /// The peer has been banned
+    Banned = 1,
+    /// No connection slot is available for the peer
+    NoSlot = 2,
+    /// The node is shutting down
+    Shutdown = 3,
+    /// The peer sent invalid data
+    ProtocolError = 4,
+    /// The peer belongs to another network
+    OtherNetwork = 5,
+    /// Another connection with the peer is kept after a simultaneous dial
+    DuplicateConnection = 6,
+    /// The peer stopped answering the keep-alive pings
+    Unresponsive = 7,
 }
 
 impl PeerManagementMessage {
@@ -26,11 +75,24 @@ impl PeerManagementMessage {
         match self {
             PeerManagementMessage::NewPeerConnected(_) => MessageTypeId::NewPeerConnected,
             PeerManagementMessage::ListPeers(_) => MessageTypeId::ListPeers,
+            PeerManagementMessage::PeerMetadata(_) => MessageTypeId::PeerMetadata,
+            PeerManagementMessage::Disconnecting(_) => MessageTypeId::Disconnecting,
+            PeerManagementMessage::Ping(_) => MessageTypeId::Ping,
+            PeerManagementMessage::Pong(_) => MessageTypeId::Pong,
+            PeerManagementMessage::Status(_) => MessageTypeId::Status,
+            PeerManagementMessage::DialBackRequest { .. } => MessageTypeId::DialBackRequest,
+            PeerManagementMessage::DialBackResult { .. } => MessageTypeId::DialBackResult,
         }
     }
 
     pub fn max_id() -> u64 {
-        <MessageTypeId as Into<u64>>::into(MessageTypeId::ListPeers) + 1
+        <MessageTypeId as Into<u64>>::into(MessageTypeId::DialBackResult) + 1
+    }
+
+    /// Whether the message id is known by this version of the node.
+    /// Messages with an unknown id have been added by a newer version and must be ignored.
+    pub fn is_known_id(id: u64) -> bool {
+        MessageTypeId::try_from(id).is_ok()
     }
 }
 
@@ -40,12 +102,23 @@ impl PeerManagementMessage {
 pub enum MessageTypeId {
     NewPeerConnected = 0,
     ListPeers = 1,
+    PeerMetadata = 2,
+    Disconnecting = 3,
+    Ping = 4,
+    Pong = 5,
+    Status = 6,
+    DialBackRequest = 7,
+    DialBackResult = 8,
 }
 
 #[derive(Default, Clone)]
 pub struct PeerManagementMessageSerializer {
     length_serializer: U64VarIntSerializer,
     ip_addr_serializer: IpAddrSerializer,
+    metadata_serializer: PeerMetadataSerializer,
+    status_serializer: PeerStatusSerializer,
+    /// whether the messages are framed, see `PEER_MANAGEMENT_MESSAGE_VERSION`
+    framed: bool,
 }
 
 impl PeerManagementMessageSerializer {
@@ -53,8 +126,35 @@ impl PeerManagementMessageSerializer {
         Self {
             length_serializer: U64VarIntSerializer::new(),
             ip_addr_serializer: IpAddrSerializer::new(),
+            metadata_serializer: PeerMetadataSerializer::new(),
+            status_serializer: PeerStatusSerializer::new(),
+            framed: false,
         }
     }
+
+    /// Copy of the serializer framing the messages, for a peer that negotiated it
+    pub(crate) fn with_framing(&self, framed: bool) -> Self {
+        Self {
+            framed,
+            ..self.clone()
+        }
+    }
+
+    /// Whether the messages are framed
+    pub(crate) fn is_framed(&self) -> bool {
+        self.framed
+    }
+}
+
+/// Frame the content of a peer management message received from a peer that doesn't frame
+/// them, as it would have been framed with the version 0
+pub(crate) fn frame_content(content: &[u8]) -> Vec<u8> {
+    let mut buffer = vec![0];
+    U64VarIntSerializer::new()
+        .serialize(&(content.len() as u64), &mut buffer)
+        .expect("the serialization of a varint doesn't fail");
+    buffer.extend_from_slice(content);
+    buffer
 }
 
 impl Serializer<PeerManagementMessage> for PeerManagementMessageSerializer {
@@ -62,6 +162,25 @@ impl Serializer<PeerManagementMessage> for PeerManagementMessageSerializer {
         &self,
         value: &PeerManagementMessage,
         buffer: &mut Vec<u8>,
+    ) -> Result<(), massa_serialization::SerializeError> {
+        if !self.framed {
+            return self.serialize_content(value, buffer);
+        }
+        let mut content = Vec::new();
+        self.serialize_content(value, &mut content)?;
+        buffer.push(PEER_MANAGEMENT_MESSAGE_VERSION);
+        self.length_serializer
+            .serialize(&(content.len() as u64), buffer)?;
+        buffer.extend(content);
+        Ok(())
+    }
+}
+
+impl PeerManagementMessageSerializer {
+    fn serialize_content(
+        &self,
+        value: &PeerManagementMessage,
+        buffer: &mut Vec<u8>,
     ) -> Result<(), massa_serialization::SerializeError> {
         match value {
             PeerManagementMessage::NewPeerConnected((peer_id, listeners)) => {
@@ -90,6 +209,28 @@ impl Serializer<PeerManagementMessage> for PeerManagementMessageSerializer {
                     }
                 }
             }
+            PeerManagementMessage::PeerMetadata(metadata) => {
+                self.metadata_serializer.serialize(metadata, buffer)?;
+            }
+            PeerManagementMessage::Disconnecting(reason) => {
+                self.length_serializer
+                    .serialize(&u64::from(*reason), buffer)?;
+            }
+            PeerManagementMessage::Ping(nonce) | PeerManagementMessage::Pong(nonce) => {
+                self.length_serializer.serialize(nonce, buffer)?;
+            }
+            PeerManagementMessage::Status(status) => {
+                self.status_serializer.serialize(status, buffer)?;
+            }
+            PeerManagementMessage::DialBackRequest { nonce, address } => {
+                self.length_serializer.serialize(nonce, buffer)?;
+                self.ip_addr_serializer.serialize(&address.ip(), buffer)?;
+                buffer.extend_from_slice(&address.port().to_be_bytes());
+            }
+            PeerManagementMessage::DialBackResult { nonce, reachable } => {
+                self.length_serializer.serialize(nonce, buffer)?;
+                buffer.push(u8::from(*reachable));
+            }
         }
         Ok(())
     }
@@ -97,9 +238,16 @@ impl Serializer<PeerManagementMessage> for PeerManagementMessageSerializer {
 
 pub struct PeerManagementMessageDeserializer {
     message_id: u64,
+    content_length_deserializer: U64VarIntDeserializer,
     listeners_length_deserializer: U64VarIntDeserializer,
     peers_length_deserializer: U64VarIntDeserializer,
     ip_addr_deserializer: IpAddrDeserializer,
+    metadata_deserializer: PeerMetadataDeserializer,
+    reason_deserializer: U64VarIntDeserializer,
+    nonce_deserializer: U64VarIntDeserializer,
+    status_deserializer: PeerStatusDeserializer,
+    /// whether the messages are framed, see `PEER_MANAGEMENT_MESSAGE_VERSION`
+    framed: bool,
 }
 
 /// Limits used in the deserialization of `OperationMessage`
@@ -108,12 +256,18 @@ pub struct PeerManagementMessageDeserializerArgs {
     pub max_listeners_per_peer: u64,
     /// Maximum number of peers per announcement
     pub max_peers_per_announcement: u64,
+    /// Maximum size of the content of a message
+    pub max_message_size: u64,
 }
 
 impl PeerManagementMessageDeserializer {
     pub fn new(limits: PeerManagementMessageDeserializerArgs) -> Self {
         Self {
             message_id: 0,
+            content_length_deserializer: U64VarIntDeserializer::new(
+                Included(0),
+                Included(limits.max_message_size),
+            ),
             listeners_length_deserializer: U64VarIntDeserializer::new(
                 Included(0),
                 Included(limits.max_listeners_per_peer),
@@ -123,12 +277,22 @@ impl PeerManagementMessageDeserializer {
                 Included(limits.max_peers_per_announcement),
             ),
             ip_addr_deserializer: IpAddrDeserializer::new(),
+            metadata_deserializer: PeerMetadataDeserializer::new(),
+            reason_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+            nonce_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+            status_deserializer: PeerStatusDeserializer::new(),
+            framed: false,
         }
     }
 
     pub fn set_message(&mut self, message_id: u64) {
         self.message_id = message_id;
     }
+
+    /// Whether the next messages are framed, as negotiated by the peer that sent them
+    pub fn set_framed(&mut self, framed: bool) {
+        self.framed = framed;
+    }
 }
 
 impl Deserializer<PeerManagementMessage> for PeerManagementMessageDeserializer {
@@ -136,103 +300,235 @@ impl Deserializer<PeerManagementMessage> for PeerManagementMessageDeserializer {
         &self,
         buffer: &'a [u8],
     ) -> IResult<&'a [u8], PeerManagementMessage, E> {
+        if !self.framed {
+            return self.deserialize_content(buffer);
+        }
         context("Failed PeerManagementMessage deserialization", |buffer| {
-            let id = MessageTypeId::try_from(self.message_id).map_err(|_| {
-                nom::Err::Error(ParseError::from_error_kind(
-                    buffer,
-                    nom::error::ErrorKind::Eof,
-                ))
-            })?;
-            match id {
-                MessageTypeId::NewPeerConnected => context(
-                    "Failed NewPeerConnected deserialization",
-                    tuple((
-                        context("Failed PeerId deserialization", |buffer: &'a [u8]| {
-                            let peer_id = PeerId::from_bytes(
-                                buffer[..PUBLIC_KEY_SIZE_BYTES].try_into().map_err(|_| {
+            let (buffer, version) = context("Failed version deserialization", |buffer| {
+                nom::number::complete::u8(buffer)
+            })
+            .parse(buffer)?;
+            let (rest, content) =
+                context("Failed content deserialization", |buffer: &'a [u8]| {
+                    let (buffer, length) = self.content_length_deserializer.deserialize(buffer)?;
+                    take(length)(buffer)
+                })
+                .parse(buffer)?;
+            let (remaining, message) = self.deserialize_content(content)?;
+            // Newer versions may append fields that we don't know about
+            if version <= PEER_MANAGEMENT_MESSAGE_VERSION && !remaining.is_empty() {
+                return Err(nom::Err::Error(ParseError::from_error_kind(
+                    remaining,
+                    nom::error::ErrorKind::NonEmpty,
+                )));
+            }
+            Ok((rest, message))
+        })
+        .parse(buffer)
+    }
+}
+
+impl PeerManagementMessageDeserializer {
+    fn deserialize_content<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], PeerManagementMessage, E> {
+        context(
+            "Failed PeerManagementMessage content deserialization",
+            |buffer| {
+                let id = MessageTypeId::try_from(self.message_id).map_err(|_| {
+                    nom::Err::Error(ParseError::from_error_kind(
+                        buffer,
+                        nom::error::ErrorKind::Eof,
+                    ))
+                })?;
+                match id {
+                    MessageTypeId::NewPeerConnected => context(
+                        "Failed NewPeerConnected deserialization",
+                        tuple((
+                            context("Failed PeerId deserialization", |buffer: &'a [u8]| {
+                                let peer_id = PeerId::from_bytes(
+                                    buffer
+                                        .get(..PUBLIC_KEY_SIZE_BYTES)
+                                        .unwrap_or_default()
+                                        .try_into()
+                                        .map_err(|_| {
+                                            nom::Err::Error(ParseError::from_error_kind(
+                                                buffer,
+                                                nom::error::ErrorKind::LengthValue,
+                                            ))
+                                        })?,
+                                )
+                                .map_err(|_| {
                                     nom::Err::Error(ParseError::from_error_kind(
                                         buffer,
-                                        nom::error::ErrorKind::LengthValue,
+                                        nom::error::ErrorKind::Eof,
                                     ))
-                                })?,
-                            )
-                            .map_err(|_| {
-                                nom::Err::Error(ParseError::from_error_kind(
-                                    buffer,
-                                    nom::error::ErrorKind::Eof,
-                                ))
-                            })?;
-                            Ok((&buffer[PUBLIC_KEY_SIZE_BYTES..], peer_id))
-                        }),
-                        length_count(
-                            context("Failed length listeners deserialization", |buffer| {
-                                self.listeners_length_deserializer.deserialize(buffer)
-                            }),
-                            context("Failed listener deserialization", |buffer| {
-                                listener_deserializer(buffer, &self.ip_addr_deserializer)
+                                })?;
+                                Ok((&buffer[PUBLIC_KEY_SIZE_BYTES..], peer_id))
                             }),
-                        ),
-                    )),
-                )
-                .map(
-                    |(peer_id, listeners): (PeerId, Vec<(SocketAddr, TransportType)>)| {
-                        let listeners = listeners.into_iter().collect();
-                        PeerManagementMessage::NewPeerConnected((peer_id, listeners))
-                    },
-                )
-                .parse(buffer),
-                MessageTypeId::ListPeers => context(
-                    "Failed ListPeers deserialization",
-                    length_count(
-                        context(
-                            "Failed length peers deserialization",
-                            |buffer: &'a [u8]| self.peers_length_deserializer.deserialize(buffer),
-                        ),
-                        context(
-                            "Failed peer deserialization",
-                            tuple((
-                                context("Failed PeerId deserialization", |buffer: &'a [u8]| {
-                                    let peer_id = PeerId::from_bytes(
-                                        buffer[..PUBLIC_KEY_SIZE_BYTES].try_into().map_err(
-                                            |_| {
+                            length_count(
+                                context("Failed length listeners deserialization", |buffer| {
+                                    self.listeners_length_deserializer.deserialize(buffer)
+                                }),
+                                context("Failed listener deserialization", |buffer| {
+                                    listener_deserializer(buffer, &self.ip_addr_deserializer)
+                                }),
+                            ),
+                        )),
+                    )
+                    .map(
+                        |(peer_id, listeners): (PeerId, Vec<(SocketAddr, TransportType)>)| {
+                            let listeners = listeners.into_iter().collect();
+                            PeerManagementMessage::NewPeerConnected((peer_id, listeners))
+                        },
+                    )
+                    .parse(buffer),
+                    MessageTypeId::ListPeers => context(
+                        "Failed ListPeers deserialization",
+                        length_count(
+                            context(
+                                "Failed length peers deserialization",
+                                |buffer: &'a [u8]| {
+                                    self.peers_length_deserializer.deserialize(buffer)
+                                },
+                            ),
+                            context(
+                                "Failed peer deserialization",
+                                tuple((
+                                    context(
+                                        "Failed PeerId deserialization",
+                                        |buffer: &'a [u8]| {
+                                            let peer_id = PeerId::from_bytes(
+                                                buffer
+                                                    .get(..PUBLIC_KEY_SIZE_BYTES)
+                                                    .unwrap_or_default()
+                                                    .try_into()
+                                                    .map_err(|_| {
+                                                        nom::Err::Error(
+                                                            ParseError::from_error_kind(
+                                                                buffer,
+                                                                nom::error::ErrorKind::Eof,
+                                                            ),
+                                                        )
+                                                    })?,
+                                            )
+                                            .map_err(|_| {
                                                 nom::Err::Error(ParseError::from_error_kind(
                                                     buffer,
                                                     nom::error::ErrorKind::Eof,
                                                 ))
+                                            })?;
+                                            Ok((&buffer[PUBLIC_KEY_SIZE_BYTES..], peer_id))
+                                        },
+                                    ),
+                                    length_count(
+                                        context(
+                                            "Failed length listeners deserialization",
+                                            |buffer| {
+                                                self.listeners_length_deserializer
+                                                    .deserialize(buffer)
                                             },
-                                        )?,
+                                        ),
+                                        context("Failed listener deserialization", |buffer| {
+                                            listener_deserializer(
+                                                buffer,
+                                                &self.ip_addr_deserializer,
+                                            )
+                                        }),
                                     )
-                                    .map_err(|_| {
-                                        nom::Err::Error(ParseError::from_error_kind(
-                                            buffer,
-                                            nom::error::ErrorKind::Eof,
-                                        ))
-                                    })?;
-                                    Ok((&buffer[PUBLIC_KEY_SIZE_BYTES..], peer_id))
-                                }),
-                                length_count(
-                                    context("Failed length listeners deserialization", |buffer| {
-                                        self.listeners_length_deserializer.deserialize(buffer)
-                                    }),
-                                    context("Failed listener deserialization", |buffer| {
-                                        listener_deserializer(buffer, &self.ip_addr_deserializer)
-                                    }),
-                                )
-                                .map::<_, HashMap<SocketAddr, TransportType>>(
-                                    |listeners: Vec<(SocketAddr, TransportType)>| {
-                                        listeners.into_iter().collect()
-                                    },
-                                ),
-                            )),
+                                    .map::<_, HashMap<
+                                        SocketAddr,
+                                        TransportType,
+                                    >>(
+                                        |listeners: Vec<(SocketAddr, TransportType)>| {
+                                            listeners.into_iter().collect()
+                                        },
+                                    ),
+                                )),
+                            ),
                         ),
-                    ),
-                )
-                .map(|data: Vec<(PeerId, HashMap<SocketAddr, TransportType>)>| {
-                    PeerManagementMessage::ListPeers(data)
-                })
-                .parse(buffer),
-            }
-        })
+                    )
+                    .map(|data: Vec<(PeerId, HashMap<SocketAddr, TransportType>)>| {
+                        PeerManagementMessage::ListPeers(data)
+                    })
+                    .parse(buffer),
+                    MessageTypeId::PeerMetadata => {
+                        context("Failed PeerMetadata deserialization", |buffer| {
+                            self.metadata_deserializer.deserialize(buffer)
+                        })
+                        .map(PeerManagementMessage::PeerMetadata)
+                        .parse(buffer)
+                    }
+                    MessageTypeId::Disconnecting => {
+                        context("Failed Disconnecting deserialization", |buffer| {
+                            self.reason_deserializer.deserialize(buffer)
+                        })
+                        .map(|code| {
+                            // reasons added by newer versions are kept as unknown
+                            PeerManagementMessage::Disconnecting(
+                                DisconnectReason::try_from(code)
+                                    .unwrap_or(DisconnectReason::Unknown),
+                            )
+                        })
+                        .parse(buffer)
+                    }
+                    MessageTypeId::Ping => context("Failed Ping deserialization", |buffer| {
+                        self.nonce_deserializer.deserialize(buffer)
+                    })
+                    .map(PeerManagementMessage::Ping)
+                    .parse(buffer),
+                    MessageTypeId::Pong => context("Failed Pong deserialization", |buffer| {
+                        self.nonce_deserializer.deserialize(buffer)
+                    })
+                    .map(PeerManagementMessage::Pong)
+                    .parse(buffer),
+                    MessageTypeId::Status => context("Failed Status deserialization", |buffer| {
+                        self.status_deserializer.deserialize(buffer)
+                    })
+                    .map(PeerManagementMessage::Status)
+                    .parse(buffer),
+                    MessageTypeId::DialBackRequest => context(
+                        "Failed DialBackRequest deserialization",
+                        tuple((
+                            |buffer| self.nonce_deserializer.deserialize(buffer),
+                            context("Failed SocketAddr deserialization", |buffer| {
+                                let (buffer, ip) = self.ip_addr_deserializer.deserialize(buffer)?;
+                                let (buffer, port) = nom::number::complete::be_u16(buffer)?;
+                                Ok((buffer, SocketAddr::new(ip, port)))
+                            }),
+                        )),
+                    )
+                    .map(|(nonce, address)| PeerManagementMessage::DialBackRequest {
+                        nonce,
+                        address,
+                    })
+                    .parse(buffer),
+                    MessageTypeId::DialBackResult => context(
+                        "Failed DialBackResult deserialization",
+                        tuple((
+                            |buffer| self.nonce_deserializer.deserialize(buffer),
+                            context("Failed reachable deserialization", |buffer: &'a [u8]| {
+                                let (rest, reachable) = nom::number::complete::u8(buffer)?;
+                                match reachable {
+                                    0 => Ok((rest, false)),
+                                    1 => Ok((rest, true)),
+                                    _ => Err(nom::Err::Error(ParseError::from_error_kind(
+                                        buffer,
+                                        nom::error::ErrorKind::Verify,
+                                    ))),
+                                }
+                            }),
+                        )),
+                    )
+                    .map(|(nonce, reachable)| PeerManagementMessage::DialBackResult {
+                        nonce,
+                        reachable,
+                    })
+                    .parse(buffer),
+                }
+            },
+        )
         .parse(buffer)
     }
 }
@@ -288,12 +584,18 @@ mod tests {
     use std::collections::HashMap;
 
     use massa_serialization::{DeserializeError, Deserializer, Serializer};
-    use peernet::{peer_id::PeerId, transports::TransportType, types::KeyPair};
+    use peernet::{
+        messages::MessagesSerializer as PeerNetMessagesSerializer, peer_id::PeerId,
+        transports::TransportType, types::KeyPair,
+    };
 
     use super::{
-        PeerManagementMessage, PeerManagementMessageDeserializer,
-        PeerManagementMessageDeserializerArgs, PeerManagementMessageSerializer,
+        frame_content, DisconnectReason, MessageTypeId, PeerManagementMessage,
+        PeerManagementMessageDeserializer, PeerManagementMessageDeserializerArgs,
+        PeerManagementMessageSerializer, PEER_MANAGEMENT_MESSAGE_VERSION,
     };
+    use crate::messages::MessagesSerializer;
+    use massa_serialization::U64VarIntSerializer;
 
     #[test]
     fn test_peer_connected() {
@@ -316,6 +618,7 @@ mod tests {
             PeerManagementMessageDeserializer::new(PeerManagementMessageDeserializerArgs {
                 max_listeners_per_peer: 1000,
                 max_peers_per_announcement: 1000,
+                max_message_size: 1_000_000,
             });
         deserializer.set_message(0);
 
@@ -361,6 +664,7 @@ mod tests {
             PeerManagementMessageDeserializer::new(PeerManagementMessageDeserializerArgs {
                 max_listeners_per_peer: 1000,
                 max_peers_per_announcement: 1000,
+                max_message_size: 1_000_000,
             });
         deserializer.set_message(1);
         let (rest, message) = deserializer
@@ -384,4 +688,267 @@ mod tests {
             _ => panic!("Bad message deserialized"),
         }
     }
+
+    fn forged_list_peers(version: u8, trailing: &[u8]) -> Vec<u8> {
+        let keypair = KeyPair::generate();
+        let mut listeners = HashMap::new();
+        listeners.insert("127.0.0.1:33036".parse().unwrap(), TransportType::Tcp);
+        let message = PeerManagementMessage::ListPeers(vec![(
+            PeerId::from_public_key(keypair.get_public_key()),
+            listeners,
+        )]);
+        // Build the frame by hand with the given version and extra content appended
+        let mut content = vec![];
+        PeerManagementMessageSerializer::new()
+            .serialize_content(&message, &mut content)
+            .unwrap();
+        content.extend_from_slice(trailing);
+        let mut buffer = vec![version];
+        U64VarIntSerializer::new()
+            .serialize(&(content.len() as u64), &mut buffer)
+            .unwrap();
+        buffer.extend(content);
+        buffer
+    }
+
+    #[test]
+    fn test_newer_version_extra_fields_ignored() {
+        let buffer = forged_list_peers(PEER_MANAGEMENT_MESSAGE_VERSION + 1, &[1, 2, 3]);
+        let mut deserializer =
+            PeerManagementMessageDeserializer::new(PeerManagementMessageDeserializerArgs {
+                max_listeners_per_peer: 1000,
+                max_peers_per_announcement: 1000,
+                max_message_size: 1_000_000,
+            });
+        deserializer.set_message(1);
+        deserializer.set_framed(true);
+        let (rest, message) = deserializer
+            .deserialize::<DeserializeError>(&buffer)
+            .unwrap();
+        assert!(rest.is_empty());
+        match message {
+            PeerManagementMessage::ListPeers(peers) => assert_eq!(peers.len(), 1),
+            _ => panic!("Bad message deserialized"),
+        }
+    }
+
+    #[test]
+    fn test_same_version_extra_fields_rejected() {
+        let buffer = forged_list_peers(PEER_MANAGEMENT_MESSAGE_VERSION, &[1, 2, 3]);
+        let mut deserializer =
+            PeerManagementMessageDeserializer::new(PeerManagementMessageDeserializerArgs {
+                max_listeners_per_peer: 1000,
+                max_peers_per_announcement: 1000,
+                max_message_size: 1_000_000,
+            });
+        deserializer.set_message(1);
+        deserializer.set_framed(true);
+        assert!(deserializer
+            .deserialize::<DeserializeError>(&buffer)
+            .is_err());
+    }
+
+    #[test]
+    fn test_framing_negotiated() {
+        let message = PeerManagementMessage::Ping(42);
+        let mut content = vec![];
+        PeerManagementMessageSerializer::new()
+            .serialize(&message, &mut content)
+            .unwrap();
+        let mut framed = vec![];
+        PeerManagementMessageSerializer::new()
+            .with_framing(true)
+            .serialize(&message, &mut framed)
+            .unwrap();
+        // the older nodes get the content alone
+        assert_eq!(framed, frame_content(&content));
+        assert_eq!(framed[0], PEER_MANAGEMENT_MESSAGE_VERSION);
+
+        let mut deserializer =
+            PeerManagementMessageDeserializer::new(PeerManagementMessageDeserializerArgs {
+                max_listeners_per_peer: 1000,
+                max_peers_per_announcement: 1000,
+                max_message_size: 1_000_000,
+            });
+        deserializer.set_message(MessageTypeId::Ping.into());
+        for (framing, buffer) in [(false, &content), (true, &framed)] {
+            deserializer.set_framed(framing);
+            let (rest, received) = deserializer
+                .deserialize::<DeserializeError>(buffer)
+                .unwrap();
+            assert!(rest.is_empty());
+            assert!(matches!(received, PeerManagementMessage::Ping(42)));
+        }
+    }
+
+    #[test]
+    fn test_disconnecting() {
+        let mut deserializer =
+            PeerManagementMessageDeserializer::new(PeerManagementMessageDeserializerArgs {
+                max_listeners_per_peer: 1000,
+                max_peers_per_announcement: 1000,
+                max_message_size: 1_000_000,
+            });
+        deserializer.set_message(3);
+        let mut buffer = vec![];
+        PeerManagementMessageSerializer::new()
+            .serialize(
+                &PeerManagementMessage::Disconnecting(DisconnectReason::NoSlot),
+                &mut buffer,
+            )
+            .unwrap();
+        let (rest, message) = deserializer
+            .deserialize::<DeserializeError>(&buffer)
+            .unwrap();
+        assert!(rest.is_empty());
+        match message {
+            PeerManagementMessage::Disconnecting(reason) => {
+                assert_eq!(reason, DisconnectReason::NoSlot)
+            }
+            _ => panic!("Bad message deserialized"),
+        }
+
+        // a reason code from a newer version is read as unknown
+        deserializer.set_framed(true);
+        let buffer = vec![PEER_MANAGEMENT_MESSAGE_VERSION, 1, 100];
+        let (_, message) = deserializer
+            .deserialize::<DeserializeError>(&buffer)
+            .unwrap();
+        match message {
+            PeerManagementMessage::Disconnecting(reason) => {
+                assert_eq!(reason, DisconnectReason::Unknown)
+            }
+            _ => panic!("Bad message deserialized"),
+        }
+    }
+
+    #[test]
+    fn test_ping_pong() {
+        let mut deserializer =
+            PeerManagementMessageDeserializer::new(PeerManagementMessageDeserializerArgs {
+                max_listeners_per_peer: 1000,
+                max_peers_per_announcement: 1000,
+                max_message_size: 1_000_000,
+            });
+        for message in [
+            PeerManagementMessage::Ping(u64::MAX),
+            PeerManagementMessage::Pong(42),
+        ] {
+            let mut buffer = vec![];
+            PeerManagementMessageSerializer::new()
+                .serialize(&message, &mut buffer)
+                .unwrap();
+            deserializer.set_message(message.get_id().into());
+            let (rest, received) = deserializer
+                .deserialize::<DeserializeError>(&buffer)
+                .unwrap();
+            assert!(rest.is_empty());
+            match (message, received) {
+                (PeerManagementMessage::Ping(sent), PeerManagementMessage::Ping(received))
+                | (PeerManagementMessage::Pong(sent), PeerManagementMessage::Pong(received)) => {
+                    assert_eq!(sent, received)
+                }
+                _ => panic!("Bad message deserialized"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_dial_back() {
+        let mut deserializer =
+            PeerManagementMessageDeserializer::new(PeerManagementMessageDeserializerArgs {
+                max_listeners_per_peer: 1000,
+                max_peers_per_announcement: 1000,
+                max_message_size: 1_000_000,
+            });
+        for message in [
+            PeerManagementMessage::DialBackRequest {
+                nonce: 7,
+                address: "[2001:db8::1]:31244".parse().unwrap(),
+            },
+            PeerManagementMessage::DialBackResult {
+                nonce: 7,
+                reachable: true,
+            },
+        ] {
+            let mut buffer = vec![];
+            PeerManagementMessageSerializer::new()
+                .serialize(&message, &mut buffer)
+                .unwrap();
+            deserializer.set_message(message.get_id().into());
+            let (rest, received) = deserializer
+                .deserialize::<DeserializeError>(&buffer)
+                .unwrap();
+            assert!(rest.is_empty());
+            match (message, received) {
+                (
+                    PeerManagementMessage::DialBackRequest { nonce, address },
+                    PeerManagementMessage::DialBackRequest {
+                        nonce: received_nonce,
+                        address: received_address,
+                    },
+                ) => {
+                    assert_eq!(nonce, received_nonce);
+                    assert_eq!(address, received_address);
+                }
+                (
+                    PeerManagementMessage::DialBackResult { nonce, reachable },
+                    PeerManagementMessage::DialBackResult {
+                        nonce: received_nonce,
+                        reachable: received_reachable,
+                    },
+                ) => {
+                    assert_eq!(nonce, received_nonce);
+                    assert_eq!(reachable, received_reachable);
+                }
+                _ => panic!("Bad message deserialized"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_message_id() {
+        assert!(PeerManagementMessage::is_known_id(0));
+        assert!(PeerManagementMessage::is_known_id(1));
+        assert!(!PeerManagementMessage::is_known_id(
+            PeerManagementMessage::max_id()
+        ));
+    }
+
+    #[test]
+    fn test_padded_message() {
+        let serializer = MessagesSerializer::new()
+            .with_peer_management_message_serializer(PeerManagementMessageSerializer::new())
+            .with_framing(true)
+            .with_padding(Some(128));
+        let mut buffer = Vec::new();
+        serializer
+            .serialize(&PeerManagementMessage::Ping(42).into(), &mut buffer)
+            .unwrap();
+        assert_eq!(buffer.len() % 128, 0);
+
+        // the message is read as usual and the padding is left after it
+        let mut deserializer =
+            PeerManagementMessageDeserializer::new(PeerManagementMessageDeserializerArgs {
+                max_listeners_per_peer: 1000,
+                max_peers_per_announcement: 1000,
+                max_message_size: 1_000_000,
+            });
+        deserializer.set_message(MessageTypeId::Ping.into());
+        deserializer.set_framed(true);
+        let (rest, message) = deserializer
+            .deserialize::<DeserializeError>(&buffer)
+            .unwrap();
+        assert!(matches!(message, PeerManagementMessage::Ping(42)));
+        // version, content length and nonce
+        assert_eq!(rest.len(), buffer.len() - 3);
+
+        // the padding follows the framed content, it isn't used without the framing
+        let mut buffer = Vec::new();
+        serializer
+            .with_framing(false)
+            .serialize(&PeerManagementMessage::Ping(42).into(), &mut buffer)
+            .unwrap();
+        assert_eq!(buffer.len(), 1);
+    }
 }
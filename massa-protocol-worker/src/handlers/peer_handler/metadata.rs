@@ -0,0 +1,252 @@
+//! Metadata that a peer sends about itself right after the handshake.
+//!
+//! It tells which software runs on the other side, what it supports and which network
+//! it belongs to. It is signed with the peer's key so it can't be forged by a relay.
+
+use std::ops::Bound::Included;
+
+use massa_models::serialization::{StringDeserializer, StringSerializer};
+use massa_serialization::{
+    Deserializer, SerializeError, Serializer, U16VarIntDeserializer, U16VarIntSerializer,
+    U64VarIntDeserializer, U64VarIntSerializer,
+};
+use nom::{
+    error::{context, ContextError, ParseError},
+    multi::length_count,
+    sequence::tuple,
+    IResult, Parser,
+};
+use peernet::{
+    error::{PeerNetError, PeerNetResult},
+    types::{Hash, KeyPair, Signature},
+};
+
+use super::dial_back::DIAL_BACK_FEATURE;
+use super::status::PEER_STATUS_FEATURE;
+
+/// Maximum length of the agent string
+pub const MAX_PEER_METADATA_AGENT_LENGTH: u16 = 256;
+/// Maximum number of features advertised by a peer
+pub const MAX_PEER_METADATA_FEATURES: u64 = 64;
+/// Maximum length of a feature name
+pub const MAX_PEER_METADATA_FEATURE_LENGTH: u16 = 64;
+
+/// The node answers the requests of the blocks of a bounded slot range with their headers
+pub const BLOCKS_BY_SLOT_RANGE_FEATURE: &str = "blocks_by_slot_range";
+
+/// The node answers the keep-alive pings
+pub const KEEP_ALIVE_FEATURE: &str = "keep_alive";
+
+/// The node understands the endorsement announcements and answers the requests of endorsements
+pub const ENDORSEMENT_ANNOUNCEMENTS_FEATURE: &str = "endorsement_announcements";
+
+/// The node reconciles its recent operations with the digests of its peers
+pub const OPERATIONS_DIGEST_FEATURE: &str = "operations_digest";
+
+/// Features supported by this node, advertised in its metadata
+pub const PEER_FEATURES: &[&str] = &[
+    BLOCKS_BY_SLOT_RANGE_FEATURE,
+    KEEP_ALIVE_FEATURE,
+    ENDORSEMENT_ANNOUNCEMENTS_FEATURE,
+    OPERATIONS_DIGEST_FEATURE,
+    PEER_STATUS_FEATURE,
+    DIAL_BACK_FEATURE,
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeerMetadata {
+    /// Software name and version of the node
+    pub agent: String,
+    /// Optional features supported by the node
+    pub features: Vec<String>,
+    /// Identifier of the network the node belongs to
+    pub chain_id: u64,
+    /// Hash
+    pub hash: Hash,
+    /// serialized version
+    serialized: Vec<u8>,
+    /// Signature
+    pub signature: Signature,
+}
+
+impl PeerMetadata {
+    pub fn new(
+        agent: String,
+        features: Vec<String>,
+        chain_id: u64,
+        keypair: &KeyPair,
+    ) -> PeerNetResult<Self> {
+        let mut serialized = Vec::new();
+        let u64_serializer = U64VarIntSerializer::new();
+        let string_serializer = StringSerializer::new(U16VarIntSerializer::new());
+        let map_err = |err: SerializeError| {
+            PeerNetError::HandlerError.error("Peer metadata serialization", Some(err.to_string()))
+        };
+        u64_serializer
+            .serialize(&chain_id, &mut serialized)
+            .map_err(map_err)?;
+        string_serializer
+            .serialize(&agent, &mut serialized)
+            .map_err(map_err)?;
+        u64_serializer
+            .serialize(&(features.len() as u64), &mut serialized)
+            .map_err(map_err)?;
+        for feature in &features {
+            string_serializer
+                .serialize(feature, &mut serialized)
+                .map_err(map_err)?;
+        }
+        let hash = Hash::compute_from(&serialized);
+        Ok(Self {
+            agent,
+            features,
+            chain_id,
+            hash,
+            serialized,
+            signature: keypair.sign(&hash).map_err(|err| {
+                PeerNetError::SignError.error("Peer metadata serialization", Some(err.to_string()))
+            })?,
+        })
+    }
+
+    /// Whether the peer advertised the given feature
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.iter().any(|supported| supported == feature)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct PeerMetadataSerializer;
+
+impl PeerMetadataSerializer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Serializer<PeerMetadata> for PeerMetadataSerializer {
+    fn serialize(&self, value: &PeerMetadata, buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        buffer.extend(value.serialized.clone());
+        buffer.extend(value.signature.to_bytes());
+        Ok(())
+    }
+}
+
+pub struct PeerMetadataDeserializer {
+    chain_id_deserializer: U64VarIntDeserializer,
+    agent_deserializer: StringDeserializer<U16VarIntDeserializer, u16>,
+    features_length_deserializer: U64VarIntDeserializer,
+    feature_deserializer: StringDeserializer<U16VarIntDeserializer, u16>,
+}
+
+impl PeerMetadataDeserializer {
+    pub fn new() -> Self {
+        Self {
+            chain_id_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+            agent_deserializer: StringDeserializer::new(U16VarIntDeserializer::new(
+                Included(0),
+                Included(MAX_PEER_METADATA_AGENT_LENGTH),
+            )),
+            features_length_deserializer: U64VarIntDeserializer::new(
+                Included(0),
+                Included(MAX_PEER_METADATA_FEATURES),
+            ),
+            feature_deserializer: StringDeserializer::new(U16VarIntDeserializer::new(
+                Included(0),
+                Included(MAX_PEER_METADATA_FEATURE_LENGTH),
+            )),
+        }
+    }
+}
+
+impl Deserializer<PeerMetadata> for PeerMetadataDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], PeerMetadata, E> {
+        let (rest, (chain_id, agent, features)) = context(
+            "Failed peer metadata deserialization",
+            tuple((
+                context("Failed chain id deserialization", |buffer| {
+                    self.chain_id_deserializer.deserialize(buffer)
+                }),
+                context("Failed agent deserialization", |buffer| {
+                    self.agent_deserializer.deserialize(buffer)
+                }),
+                length_count(
+                    context("Failed features length deserialization", |buffer| {
+                        self.features_length_deserializer.deserialize(buffer)
+                    }),
+                    context("Failed feature deserialization", |buffer| {
+                        self.feature_deserializer.deserialize(buffer)
+                    }),
+                ),
+            )),
+        )
+        .parse(buffer)?;
+        let serialized = buffer[..buffer.len() - rest.len()].to_vec();
+        let hash = Hash::compute_from(&serialized);
+        let signature_bytes = rest
+            .get(..64)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| {
+                nom::Err::Error(ParseError::from_error_kind(
+                    rest,
+                    nom::error::ErrorKind::LengthValue,
+                ))
+            })?;
+        let signature = Signature::from_bytes(signature_bytes).map_err(|_| {
+            nom::Err::Error(ParseError::from_error_kind(
+                rest,
+                nom::error::ErrorKind::Verify,
+            ))
+        })?;
+        Ok((
+            &rest[64..],
+            PeerMetadata {
+                agent,
+                features,
+                chain_id,
+                hash,
+                serialized,
+                signature,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use massa_serialization::{DeserializeError, Deserializer, Serializer};
+    use peernet::{peer_id::PeerId, types::KeyPair};
+
+    use super::{
+        PeerMetadata, PeerMetadataDeserializer, PeerMetadataSerializer,
+        BLOCKS_BY_SLOT_RANGE_FEATURE,
+    };
+
+    #[test]
+    fn test_peer_metadata_round_trip() {
+        let keypair = KeyPair::generate();
+        let metadata = PeerMetadata::new(
+            "massa/TEST.22.2".to_string(),
+            vec![BLOCKS_BY_SLOT_RANGE_FEATURE.to_string()],
+            77,
+            &keypair,
+        )
+        .unwrap();
+        let mut buffer = Vec::new();
+        PeerMetadataSerializer::new()
+            .serialize(&metadata, &mut buffer)
+            .unwrap();
+        let (rest, received) = PeerMetadataDeserializer::new()
+            .deserialize::<DeserializeError>(&buffer)
+            .unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(received, metadata);
+        assert!(received.supports(BLOCKS_BY_SLOT_RANGE_FEATURE));
+        PeerId::from_public_key(keypair.get_public_key())
+            .verify_signature(&received.hash, &received.signature)
+            .unwrap();
+    }
+}
@@ -1,23 +1,32 @@
-use std::cmp::Reverse;
 use std::net::IpAddr;
-use std::{collections::HashMap, net::SocketAddr, thread::JoinHandle, time::Duration};
+use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    net::SocketAddr,
+    thread::JoinHandle,
+};
 
-use crossbeam::channel::tick;
 use crossbeam::{
-    channel::{Receiver, Sender},
+    channel::{never, Receiver, Sender},
     select,
 };
+use massa_consensus_exports::ConsensusController;
+use massa_models::config::MAX_MESSAGE_SIZE;
+use massa_models::stats::ChannelStats;
 use massa_models::version::{VersionDeserializer, VersionSerializer};
-use massa_protocol_exports::{BootstrapPeers, ProtocolConfig};
+use massa_protocol_exports::{
+    BanReport, BootstrapPeerInfo, BootstrapPeers, ConnectionEventKind, HandshakeStage,
+    MisbehaviorKind, PeerHandlerError, ProtocolConfig, LOGGED_PEER_TAG,
+};
 use massa_serialization::{DeserializeError, Deserializer, Serializer};
-use peernet::types::PUBLIC_KEY_SIZE_BYTES;
+use parking_lot::RwLock;
 use rand::{rngs::StdRng, RngCore, SeedableRng};
 
 use peernet::messages::MessagesSerializer;
 use peernet::{
-    error::{PeerNetError, PeerNetResult},
+    error::PeerNetResult,
     messages::MessagesHandler as PeerNetMessagesHandler,
-    peer::InitConnectionHandler,
+    peer::{InitConnectionHandler, PeerConnectionType},
     peer_id::PeerId,
     transports::{endpoint::Endpoint, TransportType},
     types::Hash,
@@ -25,41 +34,92 @@ use peernet::{
 };
 use tracing::log::{debug, error, info, warn};
 
+use crate::audit::{ConnectionAuditLog, SharedConnectionAuditLog};
+use crate::channels::SheddingSender;
+use crate::clock::SharedClock;
+use crate::dial::SharedDialScheduler;
 use crate::handlers::peer_handler::models::PeerState;
-use crate::messages::MessagesHandler;
+use crate::messages::{Message, MessagesHandler};
+use crate::misbehavior::MisbehaviorLedger;
+use crate::peer_traffic::pad_to_bucket;
+use crate::production_schedule::ProductionSchedule;
+use crate::replay::{first_sequence, ReplayGuard};
 use crate::wrap_network::ActiveConnectionsTrait;
 
-use self::models::PeerInfo;
 use self::{
+    ban_report::{BanEvidence, ProvenOffense, MAX_BAN_REPORTS},
+    dial_back::{DialBack, DIAL_BACK_CHECK_INTERVAL, DIAL_BACK_FEATURE},
+    gossip_pace::GossipPace,
+    half_open::{start_half_open_watchdog, HalfOpenConnections, SharedHalfOpenConnections},
+    inbound_gossip::InboundGossipLimiter,
     models::{
-        InitialPeers, PeerManagementChannel, PeerManagementCmd, PeerMessageTuple, SharedPeerDB,
+        save_peer_annotations, update_out_connection_targets, BootstrapOffensePolicy,
+        HandshakeFailurePolicy, InitialPeers, PeerConnectionRecord, PeerManagementChannel,
+        PeerManagementCmd, PeerMessageTuple, PeerToTest, SharedLocalAnnouncement,
+        SharedOutConnectionTargets, SharedPeerDB,
     },
     tester::Tester,
 };
 
 use self::{
     announcement::{
-        Announcement, AnnouncementDeserializer, AnnouncementDeserializerArgs,
-        AnnouncementSerializer,
+        AnnouncementDeserializer, AnnouncementDeserializerArgs, AnnouncementSerializer,
+    },
+    error::{network_error, serialization_error, IntoPeerNetResult, PeerHandlerResult},
+    hello::{
+        bound_challenge_hash, deserialize_hello_content, deserialize_hello_peer_id,
+        keep_existing_connection, network_id, serialize_handshake_features,
+        unreserved_in_slot_available, HandshakeTranscript, HelloContent, HANDSHAKE_FEATURES,
+        HANDSHAKE_FEATURE_BOUND_CHALLENGE, HANDSHAKE_FEATURE_DIAL_TIE_BREAK,
+        HANDSHAKE_FEATURE_FRAMED_PEER_MESSAGES, HANDSHAKE_FEATURE_PADDING,
+        HANDSHAKE_FEATURE_PEER_METADATA, HANDSHAKE_FEATURE_SEQUENCE_NUMBERS,
+        HANDSHAKE_FEATURE_TRANSCRIPT,
+    },
+    keep_alive::KeepAlive,
+    messages::{
+        frame_content, DisconnectReason, PeerManagementMessageDeserializer,
+        PeerManagementMessageDeserializerArgs,
     },
-    messages::{PeerManagementMessageDeserializer, PeerManagementMessageDeserializerArgs},
+    metadata::{PeerMetadata, KEEP_ALIVE_FEATURE, PEER_FEATURES},
+    status::{PeerStatus, ReceivedPeerStatus, PEER_STATUS_FEATURE},
 };
 
 /// This file contains the definition of the peer management handler
 /// This handler is here to check that announcements we receive are valid and
 /// that all the endpoints we received are active.
+pub mod address_tables;
 mod announcement;
+mod ban_report;
+#[cfg(feature = "benchmarking")]
+pub mod bench;
+mod dial_back;
+mod error;
+#[cfg(fuzzing)]
+pub mod fuzz;
+mod gossip_pace;
+mod half_open;
+mod hello;
+mod inbound_gossip;
+pub mod journal;
+mod keep_alive;
 mod messages;
+mod metadata;
 pub mod models;
+pub mod peer_db_lock;
+mod status;
 mod tester;
 
+pub(crate) use announcement::LocalAnnouncement;
 pub(crate) use messages::{PeerManagementMessage, PeerManagementMessageSerializer};
+pub(crate) use metadata::{ENDORSEMENT_ANNOUNCEMENTS_FEATURE, OPERATIONS_DIGEST_FEATURE};
 
 pub struct PeerManagementHandler {
     pub peer_db: SharedPeerDB,
     pub thread_join: Option<JoinHandle<()>>,
     pub sender: PeerManagementChannel,
     testers: Vec<Tester>,
+    /// announced peers waiting to be tested
+    tester_queue: SheddingSender<PeerToTest>,
 }
 
 impl PeerManagementHandler {
@@ -68,87 +128,340 @@ impl PeerManagementHandler {
         initial_peers: InitialPeers,
         peer_id: PeerId,
         peer_db: SharedPeerDB,
+        consensus_controller: Box<dyn ConsensusController>,
         (sender_msg, receiver_msg): (Sender<PeerMessageTuple>, Receiver<PeerMessageTuple>),
         (sender_cmd, receiver_cmd): (Sender<PeerManagementCmd>, Receiver<PeerManagementCmd>),
         messages_handler: MessagesHandler,
         mut active_connections: Box<dyn ActiveConnectionsTrait>,
-        target_out_connections: HashMap<String, (Vec<IpAddr>, usize)>,
-        default_target_out_connections: usize,
+        category_ips: HashMap<String, Vec<IpAddr>>,
+        out_connection_targets: SharedOutConnectionTargets,
+        local_announcement: SharedLocalAnnouncement,
+        audit_log: SharedConnectionAuditLog,
+        dial_scheduler: SharedDialScheduler,
+        production_schedule: ProductionSchedule,
+        clock: SharedClock,
         config: &ProtocolConfig,
     ) -> Self {
         let message_serializer = PeerManagementMessageSerializer::new();
 
-        let ((test_sender, test_receiver), testers) = Tester::run(
+        let ((test_sender, test_receiver), forced_test_sender, testers) = Tester::run(
             config,
             active_connections.clone(),
             peer_db.clone(),
-            messages_handler,
-            target_out_connections,
-            default_target_out_connections,
+            // the testers only receive messages before the end of the handshakes
+            messages_handler.without_replay_guard(),
+            category_ips.clone(),
+            out_connection_targets.clone(),
+            dial_scheduler,
+            clock.clone(),
         );
 
+        // the tests of announced peers are gossip: the oldest are dropped when the testers lag
+        let test_sender =
+            SheddingSender::drop_oldest("peer tests", test_sender, test_receiver.clone());
+        let tester_queue = test_sender.clone();
+
         let thread_join = std::thread::Builder::new()
         .name("protocol-peer-handler".to_string())
         .spawn({
             let peer_db = peer_db.clone();
-            let ticker = tick(Duration::from_secs(10));
             let config = config.clone();
+            let mut gossip_pace = GossipPace::from_config(&config);
+            let ticker = clock.ticker(gossip_pace.interval.to_duration());
+            let bootstrap_offense_policy = BootstrapOffensePolicy::from_config(&config);
+            let mut misbehavior_ledger = MisbehaviorLedger::from_config(&config);
+            let mut ban_reports = VecDeque::new();
+            let mut keep_alive = KeepAlive::from_config(&config);
+            let keep_alive_ticker = if keep_alive.is_enabled() {
+                clock.ticker(keep_alive.interval.to_duration())
+            } else {
+                never()
+            };
+            // our head of the chain, sent to the peers and compared to theirs
+            let status_ticker = if config.peer_status_interval.to_millis() > 0 {
+                clock.ticker(config.peer_status_interval.to_duration())
+            } else {
+                never()
+            };
+            let mut local_status: Option<PeerStatus> = None;
+            let mut dial_back = DialBack::from_config(&config);
+            let mut inbound_gossip = InboundGossipLimiter::from_config(&config);
+            let dial_back_ticker = clock.ticker(DIAL_BACK_CHECK_INTERVAL);
+            // outcome of the dial backs requested by the peers, run by the testers
+            let (dial_back_sender, dial_back_receiver) =
+                crossbeam::channel::bounded(config.max_size_channel_commands_peer_testers);
             let message_serializer = crate::messages::MessagesSerializer::new()
                 .with_peer_management_message_serializer(PeerManagementMessageSerializer::new());
             let mut message_deserializer =
                 PeerManagementMessageDeserializer::new(PeerManagementMessageDeserializerArgs {
                     max_peers_per_announcement: config.max_size_peers_announcement,
                     max_listeners_per_peer: config.max_size_listeners_per_peer,
+                    max_message_size: MAX_MESSAGE_SIZE as u64,
                 });
             move || {
                 loop {
                     select! {
                         recv(ticker) -> _ => {
-                            let peers_to_send = peer_db.read().get_rand_peers_to_send(100);
+                            // no list of peers around our productions, the next round sends it
+                            if production_schedule.quiet_until(clock.now(), config.gossip_quiet_margin).is_some() {
+                                continue;
+                            }
+                            let peer_db_read = peer_db.snapshot();
+                            let target_out_connections = {
+                                let targets = out_connection_targets.read();
+                                targets.categories.values().sum::<usize>() + targets.default
+                            };
+                            if !gossip_pace.tick(active_connections.get_nb_out_connections(), target_out_connections, peer_db_read.peers.len(), clock.now()) {
+                                continue;
+                            }
+                            let peers_to_send = peer_db_read.get_rand_peers_to_send(100, clock.now());
                             if peers_to_send.is_empty() {
                                 continue;
                             }
 
-                            let msg = PeerManagementMessage::ListPeers(peers_to_send);
+                            // the same list goes to every peer: serialize it once
+                            let msgs = match serialize_once_framed(&message_serializer, &PeerManagementMessage::ListPeers(peers_to_send).into()) {
+                                Ok(msgs) => msgs,
+                                Err(e) => {
+                                    error!("error serializing ListPeers message: {:?}", e);
+                                    continue;
+                                }
+                            };
 
                             for peer_id in &active_connections.get_peer_ids_connected() {
+                                let msg = if peer_db.traffic().framed(peer_id) { &msgs.1 } else { &msgs.0 };
                                 if let Err(e) = active_connections
-                                    .send_to_peer(peer_id, &message_serializer, msg.clone().into(), false) {
+                                    .send_to_peer(peer_id, &message_serializer, msg.clone()) {
                                     error!("error sending ListPeers message to peer: {:?}", e);
                                }
                             }
                         }
+                        recv(keep_alive_ticker) -> _ => {
+                            // only the peers that advertised the feature answer the pings
+                            let peers = {
+                                let peer_db_read = peer_db.snapshot();
+                                active_connections.get_peer_ids_connected().into_iter().filter(|peer_id| {
+                                    peer_db_read.peers.get(peer_id).and_then(|info| info.metadata.as_ref()).map_or(false, |metadata| metadata.supports(KEEP_ALIVE_FEATURE))
+                                }).collect()
+                            };
+                            let (pings, dead) = keep_alive.tick(peers);
+                            for peer_id in dead {
+                                info!("Disconnect peer {}: no answer to the keep-alive pings", peer_id);
+                                disconnect_peer(active_connections.as_mut(), &message_serializer, &audit_log, &peer_id, DisconnectReason::Unresponsive);
+                                peer_db.write().set_peer_unresponsive(&peer_id, clock.now());
+                            }
+                            for (peer_id, nonce) in pings {
+                                if let Err(e) = active_connections.send_to_peer(&peer_id, &message_serializer, PeerManagementMessage::Ping(nonce).into()) {
+                                    debug!("error sending Ping message to peer: {:?}", e);
+                                }
+                            }
+                        }
+                        recv(status_ticker) -> _ => {
+                            local_status = PeerStatus::from_heads(&consensus_controller.get_latest_final_blocks_periods(), &consensus_controller.get_best_parents());
+                            let Some(status) = local_status else {
+                                continue;
+                            };
+                            let peers: Vec<PeerId> = {
+                                let peer_db_read = peer_db.snapshot();
+                                active_connections.get_peer_ids_connected().into_iter().filter(|peer_id| {
+                                    peer_db_read.peers.get(peer_id).and_then(|info| info.metadata.as_ref()).map_or(false, |metadata| metadata.supports(PEER_STATUS_FEATURE))
+                                }).collect()
+                            };
+                            let msgs = match serialize_once_framed(&message_serializer, &PeerManagementMessage::Status(status).into()) {
+                                Ok(msgs) => msgs,
+                                Err(e) => {
+                                    error!("error serializing Status message: {:?}", e);
+                                    continue;
+                                }
+                            };
+                            for peer_id in peers {
+                                let msg = if peer_db.traffic().framed(&peer_id) { &msgs.1 } else { &msgs.0 };
+                                if let Err(e) = active_connections.send_to_peer(&peer_id, &message_serializer, msg.clone()) {
+                                    debug!("error sending Status message to peer: {:?}", e);
+                                }
+                            }
+                        }
+                        recv(dial_back_ticker) -> _ => {
+                            dial_back.tick(clock.now());
+                        }
+                        recv(dial_back_receiver) -> result => {
+                            let Ok(result) = result else {
+                                continue;
+                            };
+                            for (peer_id, nonce, reachable) in dial_back.request_result(&result) {
+                                if let Err(e) = active_connections.send_to_peer(&peer_id, &message_serializer, PeerManagementMessage::DialBackResult { nonce, reachable }.into()) {
+                                    debug!("error sending DialBackResult message to peer: {:?}", e);
+                                }
+                            }
+                        }
                         recv(receiver_cmd) -> cmd => {
                             // internal command
                            match cmd {
                              Ok(PeerManagementCmd::Ban(peer_ids)) => {
                                 // remove running handshake ?
                                 for peer_id in peer_ids {
-                                    active_connections.shutdown_connection(&peer_id);
-
-                                    // update peer_db
-                                    peer_db.write().ban_peer(&peer_id);
+                                    ban_peer(active_connections.as_mut(), &message_serializer, &peer_db, &audit_log, &peer_id, "requested by the operator".to_string());
                                 }
                             },
+                             Ok(PeerManagementCmd::ReportMisbehavior { peer_id, kind, evidence, ban }) => {
+                                if matches!(kind, MisbehaviorKind::MalformedMessage | MisbehaviorKind::InvalidSignature) {
+                                    peer_db.traffic().record_invalid(&peer_id);
+                                }
+                                let now = clock.now();
+                                if misbehavior_ledger.record(&peer_id, kind, evidence.clone(), ban, now) {
+                                    warn!("Ban peer {} for {}: {}", peer_id, kind, evidence);
+                                    ban_peer(active_connections.as_mut(), &message_serializer, &peer_db, &audit_log, &peer_id, format!("{}: {}", kind, evidence));
+                                } else {
+                                    debug!("Peer {} misbehaved ({}): {}", peer_id, kind, evidence);
+                                }
+                             },
+                             Ok(PeerManagementCmd::GetMisbehaviors { peer_ids, responder }) => {
+                                if let Err(err) = responder.send(misbehavior_ledger.get_records(peer_ids.as_deref())) {
+                                    warn!("error sending peer misbehaviors: {:?}", err);
+                                }
+                             },
+                             Ok(PeerManagementCmd::GetConnectionEvents { since, peer_id, responder }) => {
+                                if let Err(err) = responder.send(audit_log.query(since, peer_id.as_ref())) {
+                                    warn!("error sending connection events: {:?}", err);
+                                }
+                             },
+                             Ok(PeerManagementCmd::GetBanReports { responder }) => {
+                                if let Err(err) = responder.send(ban_reports.iter().cloned().collect()) {
+                                    warn!("error sending ban reports: {:?}", err);
+                                }
+                             },
+                             Ok(PeerManagementCmd::ImportBanReports { reports, responder }) => {
+                                let results = reports.iter().map(|report| -> Result<PeerId, String> {
+                                    let (evidence, reporter) = BanEvidence::from_signed(report)?;
+                                    if evidence.peer_id == peer_id {
+                                        return Err("the report is about this node".to_string());
+                                    }
+                                    evidence.check(&mut message_deserializer)?;
+                                    info!("Ban peer {} for {:?} reported by {}", evidence.peer_id, evidence.offense, reporter);
+                                    ban_peer(active_connections.as_mut(), &message_serializer, &peer_db, &audit_log, &evidence.peer_id, format!("{:?} reported by {}", evidence.offense, reporter));
+                                    Ok(evidence.peer_id)
+                                }).collect();
+                                if let Err(err) = responder.send(results) {
+                                    warn!("error sending imported ban reports: {:?}", err);
+                                }
+                             },
                              Ok(PeerManagementCmd::Unban(peer_ids)) => {
                                 for peer_id in peer_ids {
                                     peer_db.write().unban_peer(&peer_id);
+                                    audit_log.record(ConnectionEventKind::Unbanned, Some(&peer_id), None, None);
                                 }
                             },
                              Ok(PeerManagementCmd::GetBootstrapPeers { responder }) => {
-                                let mut peers = peer_db.read().get_rand_peers_to_send(100);
+                                let now = clock.now();
+                                let connected = active_connections
+                                    .get_peers_connected()
+                                    .into_iter()
+                                    .map(|(peer_id, (_, _, category))| (peer_id, category))
+                                    .collect();
+                                let mut peers = peer_db.snapshot().get_bootstrap_peers(100, &connected, &category_ips, now);
                                 // Add myself
-                                if let Some(routable_ip) = config.routable_ip {
-                                    let listeners = config.listeners.iter().map(|(addr, ty)| {
-                                        (SocketAddr::new(routable_ip, addr.port()), *ty)
-                                    }).collect();
-                                    peers.push((peer_id.clone(), listeners));
+                                if let Some(listeners) = local_announcement.read().routable_listeners() {
+                                    peers.push((peer_id.clone(), BootstrapPeerInfo {
+                                        listeners,
+                                        category: None,
+                                        last_seen: now,
+                                    }));
                                 }
                                 if let Err(err) = responder.send(BootstrapPeers(peers)) {
                                     warn!("error sending bootstrap peers: {:?}", err);
                                 }
                              },
+                             Ok(PeerManagementCmd::TestPeer { address, responder }) => {
+                                if let Err(err) = forced_test_sender.try_send((address, responder)) {
+                                    warn!("error requesting test of {}: {:?}", address, err);
+                                }
+                             },
+                             Ok(PeerManagementCmd::ImportPeers(peers)) => {
+                                info!("Importing {} peers", peers.len());
+                                for (peer_id, listeners) in peers {
+                                    if let Err(e) = test_sender.send((peer_id, listeners, PeerConnectionType::OUT)) {
+                                        debug!("error when sending msg to peer tester : {}", e);
+                                    }
+                                }
+                             },
+                             Ok(PeerManagementCmd::TestReachability { responder }) => {
+                                let listeners = local_announcement.read().routable_listeners().unwrap_or_default();
+                                // the peers of the other identities must not learn the main one
+                                let candidates: Vec<PeerId> = {
+                                    let peer_db_read = peer_db.snapshot();
+                                    active_connections.get_peer_ids_connected().into_iter().filter(|peer_id| {
+                                        active_connections.is_connected_to_main_identity(peer_id)
+                                            && peer_db_read.peers.get(peer_id).and_then(|info| info.metadata.as_ref()).map_or(false, |metadata| metadata.supports(DIAL_BACK_FEATURE))
+                                    }).collect()
+                                };
+                                let requests = dial_back.start_self_test(&listeners, &candidates, responder, clock.now());
+                                info!("Asking {} peers to dial back {} listeners", candidates.len().min(config.dial_back_peers), listeners.len());
+                                for (peer_id, nonce, address) in requests {
+                                    if let Err(e) = active_connections.send_to_peer(&peer_id, &message_serializer, PeerManagementMessage::DialBackRequest { nonce, address }.into()) {
+                                        debug!("error sending DialBackRequest message to peer: {:?}", e);
+                                    }
+                                }
+                             },
+                             Ok(PeerManagementCmd::SetTargets(new_targets)) => {
+                                let targets = {
+                                    let mut targets = out_connection_targets.write();
+                                    update_out_connection_targets(&mut targets, new_targets);
+                                    targets.clone()
+                                };
+                                info!("Out connection targets set to {:?}", targets);
+                                // persist the override so that it survives a restart
+                                match serde_json::to_string(&targets) {
+                                    Ok(json) => if let Err(err) = std::fs::write(&config.out_connection_targets_file, json) {
+                                        warn!("could not save out connection targets: {}", err);
+                                    },
+                                    Err(err) => warn!("could not serialize out connection targets: {}", err),
+                                }
+                             },
+                             Ok(PeerManagementCmd::AnnotatePeer { peer_id, annotation }) => {
+                                info!("Peer {} annotated with {:?}", peer_id, annotation);
+                                let annotations = {
+                                    let mut peer_db_write = peer_db.write();
+                                    peer_db_write.set_peer_annotation(&peer_id, annotation);
+                                    audit_log.set_logged_peers(peer_db_write.peers_with_tag(LOGGED_PEER_TAG));
+                                    peer_db_write.annotations.clone()
+                                };
+                                save_peer_annotations(&config, &annotations);
+                             },
+                             Ok(PeerManagementCmd::BootstrapClientReport { ip, report }) => {
+                                let now = clock.now();
+                                let banned = peer_db.write().record_bootstrap_report(ip, report, &bootstrap_offense_policy, now);
+                                if banned {
+                                    audit_log.record(ConnectionEventKind::IpBanned, None, None, Some(format!("IP {} after repeated bootstrap offenses", ip)));
+                                    // drop the connections already opened from the banned IP
+                                    let ip = ip.to_canonical();
+                                    for (peer_id, (addr, _, _)) in active_connections.get_peers_connected() {
+                                        if addr.ip().to_canonical() == ip {
+                                            disconnect_peer(active_connections.as_mut(), &message_serializer, &audit_log, &peer_id, DisconnectReason::Banned);
+                                        }
+                                    }
+                                }
+                             },
+                             Ok(PeerManagementCmd::AnnounceListeners) => {
+                                // the peers test the listeners, getting a new announcement signed in the handshake
+                                let Some(listeners) = local_announcement.read().routable_listeners() else {
+                                    debug!("No routable IP, listeners not announced");
+                                    continue;
+                                };
+                                let msg = PeerManagementMessage::NewPeerConnected((peer_id.clone(), listeners));
+                                // the peers of the other identities must not learn the main one
+                                for peer_id in active_connections.get_peer_ids_connected().iter().filter(|peer_id| active_connections.is_connected_to_main_identity(peer_id)) {
+                                    if let Err(e) = active_connections.send_to_peer(peer_id, &message_serializer, msg.clone().into()) {
+                                        debug!("error sending listeners announcement to peer: {:?}", e);
+                                    }
+                                }
+                             },
                              Ok(PeerManagementCmd::Stop) => {
+                                let msg = PeerManagementMessage::Disconnecting(DisconnectReason::Shutdown);
+                                for peer_id in &active_connections.get_peer_ids_connected() {
+                                    if let Err(e) = active_connections.send_to_peer(peer_id, &message_serializer, msg.clone().into()) {
+                                        debug!("error sending Disconnecting message to peer: {:?}", e);
+                                    }
+                                }
                                 while let Ok(_msg) = test_receiver.try_recv() {
                                     // nothing to do just clean the channel
                                 }
@@ -160,46 +473,170 @@ impl PeerManagementHandler {
                            }
                         },
                         recv(receiver_msg) -> msg => {
-                            let (peer_id, message_id, message) = match msg {
+                            let (peer_id, message_id, raw_message) = match msg {
                                 Ok((peer_id, message_id, message)) => (peer_id, message_id, message),
                                 Err(_) => {
                                     return;
                                 }
                             };
                             // check if peer is banned
-                            if let Some(peer) = peer_db.read().peers.get(&peer_id) {
+                            if let Some(peer) = peer_db.snapshot().peers.get(&peer_id) {
                                 if peer.state == PeerState::Banned {
                                     warn!("Banned peer sent us a message: {:?}", peer_id);
                                     continue;
                                 }
                             }
+                            if !PeerManagementMessage::is_known_id(message_id) {
+                                debug!("Ignore peer management message with unknown id {} from {}", message_id, peer_id);
+                                continue;
+                            }
                             message_deserializer.set_message(message_id);
+                            let framed = peer_db.traffic().framed(&peer_id);
+                            message_deserializer.set_framed(framed);
+                            // the ban reports hold the messages framed
+                            let evidence_data = || if framed { raw_message.clone() } else { frame_content(&raw_message) };
                             let (rest, message) = match message_deserializer
-                                .deserialize::<DeserializeError>(&message) {
+                                .deserialize::<DeserializeError>(&raw_message) {
                                 Ok((rest, message)) => (rest, message),
                                 Err(e) => {
                                     warn!("error when deserializing message: {:?}", e);
+                                    peer_db.traffic().record_invalid(&peer_id);
+                                    let now = clock.now();
+                                    if misbehavior_ledger.record(&peer_id, MisbehaviorKind::MalformedMessage, format!("peer management message {}: {}", message_id, e), false, now) {
+                                        warn!("Ban peer {} for sending too many malformed messages", peer_id);
+                                        record_ban_report(&mut ban_reports, &local_announcement, BanEvidence {
+                                            peer_id: peer_id.clone(),
+                                            offense: ProvenOffense::MalformedMessage,
+                                            timestamp: now,
+                                            message_id,
+                                            data: evidence_data(),
+                                        });
+                                        ban_peer(active_connections.as_mut(), &message_serializer, &peer_db, &audit_log, &peer_id, "too many malformed messages".to_string());
+                                    }
                                     continue;
                                 }
                             };
-                            if !rest.is_empty() {
+                            // the peers that negotiated the padding append it after the message
+                            if !rest.is_empty() && config.traffic_padding_bucket_size == 0 {
                                 warn!("message not fully deserialized");
                                 continue;
                             }
+                            // the gossiped peers inherit the direction of the connection of the sender
+                            let gossip_source = match &message {
+                                PeerManagementMessage::NewPeerConnected(_) | PeerManagementMessage::ListPeers(_) => gossip_source(active_connections.as_ref(), &peer_db, &peer_id),
+                                _ => PeerConnectionType::OUT,
+                            };
                             match message {
                                 PeerManagementMessage::NewPeerConnected((peer_id, listeners)) => {
                                     debug!("Received peer message: NewPeerConnected from {}", peer_id);
-                                    if let Err(e) = test_sender.try_send((peer_id, listeners)) {
+                                    if !inbound_gossip.allow(gossip_source) {
+                                        debug!("Don't test peer {} gossiped by an inbound peer", peer_id);
+                                        continue;
+                                    }
+                                    if let Err(e) = test_sender.send((peer_id, listeners, gossip_source)) {
                                         debug!("error when sending msg to peer tester : {}", e);
                                     }
                                 }
                                 PeerManagementMessage::ListPeers(peers) => {
                                     debug!("Received peer message: List peers from {}", peer_id);
+                                    let mut dropped = 0;
                                     for (peer_id, listeners) in peers.into_iter() {
-                                        if let Err(e) = test_sender.try_send((peer_id, listeners)) {
+                                        if !inbound_gossip.allow(gossip_source) {
+                                            dropped += 1;
+                                            continue;
+                                        }
+                                        if let Err(e) = test_sender.send((peer_id, listeners, gossip_source)) {
                                             debug!("error when sending msg to peer tester : {}", e);
                                         }
                                     }
+                                    if dropped > 0 {
+                                        debug!("Don't test {} peers gossiped by inbound peer {}", dropped, peer_id);
+                                    }
+                                }
+                                PeerManagementMessage::PeerMetadata(metadata) => {
+                                    debug!("Received peer message: PeerMetadata from {}", peer_id);
+                                    if let Err(e) = peer_id.verify_signature(&metadata.hash, &metadata.signature) {
+                                        warn!("Invalid metadata signature from {}: {:?}", peer_id, e);
+                                        peer_db.traffic().record_invalid(&peer_id);
+                                        let now = clock.now();
+                                        if misbehavior_ledger.record(&peer_id, MisbehaviorKind::InvalidSignature, "peer metadata".to_string(), false, now) {
+                                            record_ban_report(&mut ban_reports, &local_announcement, BanEvidence {
+                                                peer_id: peer_id.clone(),
+                                                offense: ProvenOffense::InvalidMetadataSignature,
+                                                timestamp: now,
+                                                message_id,
+                                                data: evidence_data(),
+                                            });
+                                            ban_peer(active_connections.as_mut(), &message_serializer, &peer_db, &audit_log, &peer_id, "invalid peer metadata signatures".to_string());
+                                        } else {
+                                            disconnect_peer(active_connections.as_mut(), &message_serializer, &audit_log, &peer_id, DisconnectReason::ProtocolError);
+                                        }
+                                        continue;
+                                    }
+                                    if metadata.chain_id != config.chain_id {
+                                        info!("Disconnect peer {} from another network (chain id {})", peer_id, metadata.chain_id);
+                                        disconnect_peer(active_connections.as_mut(), &message_serializer, &audit_log, &peer_id, DisconnectReason::OtherNetwork);
+                                        peer_db.write().set_peer_handshake_state(&peer_id, PeerState::HandshakeFailed);
+                                        continue;
+                                    }
+                                    peer_db.write().set_peer_metadata(&peer_id, metadata);
+                                }
+                                PeerManagementMessage::Disconnecting(reason) => {
+                                    info!("Peer {} is disconnecting: {:?}", peer_id, reason);
+                                    audit_log.record(ConnectionEventKind::PeerDisconnected, Some(&peer_id), None, Some(format!("{:?}", reason)));
+                                    peer_db.write().set_peer_disconnect_reason(&peer_id, reason);
+                                }
+                                PeerManagementMessage::Ping(nonce) => {
+                                    if let Err(e) = active_connections.send_to_peer(&peer_id, &message_serializer, PeerManagementMessage::Pong(nonce).into()) {
+                                        debug!("error sending Pong message to peer: {:?}", e);
+                                    }
+                                }
+                                PeerManagementMessage::Pong(nonce) => {
+                                    if keep_alive.pong(&peer_id, nonce) {
+                                        peer_db.write().set_peer_pong(&peer_id, clock.now());
+                                    }
+                                }
+                                PeerManagementMessage::Status(status) => {
+                                    if !status.is_valid(config.thread_count) {
+                                        debug!("Ignore invalid status from {}: {:?}", peer_id, status);
+                                        continue;
+                                    }
+                                    let (divergent, lagging) = local_status.as_ref().map_or((false, false), |local| {
+                                        (status.diverges_from(local), status.lags_behind(local, config.peer_status_max_lag))
+                                    });
+                                    let was_divergent = peer_db.snapshot().peers.get(&peer_id).and_then(|info| info.status.as_ref()).map_or(false, |received| received.divergent);
+                                    if divergent && !was_divergent {
+                                        let local = local_status.as_ref().expect("a divergence is found against our status");
+                                        let reason = format!("final block {} at slot {}, ours is {}", status.final_block, status.final_slot, local.final_block);
+                                        warn!("Peer {} is on another fork: {}", peer_id, reason);
+                                        audit_log.record(ConnectionEventKind::ForkDivergence, Some(&peer_id), None, Some(reason));
+                                    }
+                                    peer_db.write().set_peer_status(&peer_id, ReceivedPeerStatus {
+                                        status,
+                                        received_at: clock.now(),
+                                        divergent,
+                                        lagging,
+                                    });
+                                }
+                                PeerManagementMessage::DialBackRequest { nonce, address } => {
+                                    let accepted = {
+                                        let peer_db_read = peer_db.snapshot();
+                                        let announced = peer_db_read.peers.get(&peer_id).map(|info| &info.last_announce.listeners);
+                                        dial_back.accept_request(&peer_id, nonce, address, announced, clock.now())
+                                    };
+                                    if !accepted {
+                                        debug!("Refused to dial back {} for {}", address, peer_id);
+                                        continue;
+                                    }
+                                    if let Err(err) = forced_test_sender.try_send((address, dial_back_sender.clone())) {
+                                        debug!("error requesting dial back of {}: {:?}", address, err);
+                                        dial_back.cancel_request(&peer_id, &address);
+                                    }
+                                }
+                                PeerManagementMessage::DialBackResult { nonce, reachable } => {
+                                    if !dial_back.self_test_result(&peer_id, nonce, reachable) {
+                                        debug!("Ignore unexpected DialBackResult from {}", peer_id);
+                                    }
                                 }
                             }
                         }
@@ -227,9 +664,15 @@ impl PeerManagementHandler {
                 command_sender: sender_cmd,
             },
             testers,
+            tester_queue,
         }
     }
 
+    /// Occupancy of the queue of the peers waiting to be tested
+    pub fn get_tester_backlog(&self) -> ChannelStats {
+        self.tester_queue.get_stats()
+    }
+
     pub fn stop(&mut self) {
         self.sender
             .command_sender
@@ -245,6 +688,120 @@ impl PeerManagementHandler {
     }
 }
 
+fn invalid_challenge(reason: &str) -> PeerHandlerError {
+    PeerHandlerError::InvalidMessage {
+        stage: HandshakeStage::Challenge,
+        reason: reason.to_string(),
+    }
+}
+
+/// Tell a peer why we close the connection, then close it.
+/// Sending is best-effort: the connection may be closed before the message is flushed.
+fn disconnect_peer(
+    active_connections: &mut dyn ActiveConnectionsTrait,
+    message_serializer: &crate::messages::MessagesSerializer,
+    audit_log: &ConnectionAuditLog,
+    peer_id: &PeerId,
+    reason: DisconnectReason,
+) {
+    let address = active_connections
+        .get_peers_connected()
+        .get(peer_id)
+        .map(|(address, _, _)| *address);
+    audit_log.record(
+        ConnectionEventKind::Disconnected,
+        Some(peer_id),
+        address,
+        Some(format!("{:?}", reason)),
+    );
+    let msg = PeerManagementMessage::Disconnecting(reason);
+    if let Err(e) = active_connections.send_to_peer(peer_id, message_serializer, msg.into()) {
+        debug!("error sending Disconnecting message to peer: {:?}", e);
+    }
+    active_connections.shutdown_connection(peer_id);
+}
+
+/// Serializes a message sent to several peers once for the peers that don't frame the peer
+/// management messages, then once for the ones that do
+fn serialize_once_framed(
+    message_serializer: &crate::messages::MessagesSerializer,
+    message: &Message,
+) -> PeerNetResult<(Message, Message)> {
+    Ok((
+        message_serializer.serialize_once(message)?,
+        message_serializer
+            .with_framing(true)
+            .serialize_once(message)?,
+    ))
+}
+
+/// Keeps the signed report of a ban, dropping the oldest reports above `MAX_BAN_REPORTS`
+fn record_ban_report(
+    ban_reports: &mut VecDeque<BanReport>,
+    local_announcement: &SharedLocalAnnouncement,
+    evidence: BanEvidence,
+) {
+    match evidence.sign(local_announcement.read().keypair()) {
+        Ok(report) => {
+            if ban_reports.len() >= MAX_BAN_REPORTS {
+                ban_reports.pop_front();
+            }
+            ban_reports.push_back(report);
+        }
+        Err(err) => warn!("could not report the ban of {}: {}", evidence.peer_id, err),
+    }
+}
+
+/// Direction of the connection of a peer: the live one if it is still connected, else the last
+/// one recorded. The peers of the config, which are gossiped as if they had sent their own
+/// listeners, count as outbound.
+fn gossip_source(
+    active_connections: &dyn ActiveConnectionsTrait,
+    peer_db: &SharedPeerDB,
+    peer_id: &PeerId,
+) -> PeerConnectionType {
+    active_connections
+        .get_peer_connection_type(peer_id)
+        .or_else(|| {
+            peer_db
+                .snapshot()
+                .peers
+                .get(peer_id)?
+                .connection
+                .as_ref()
+                .map(|connection| connection.direction)
+        })
+        .unwrap_or(PeerConnectionType::OUT)
+}
+
+/// Disconnects a peer and bans it in the peer database
+fn ban_peer(
+    active_connections: &mut dyn ActiveConnectionsTrait,
+    message_serializer: &crate::messages::MessagesSerializer,
+    peer_db: &SharedPeerDB,
+    audit_log: &ConnectionAuditLog,
+    peer_id: &PeerId,
+    reason: String,
+) {
+    disconnect_peer(
+        active_connections,
+        message_serializer,
+        audit_log,
+        peer_id,
+        DisconnectReason::Banned,
+    );
+    peer_db.write().ban_peer(peer_id);
+    audit_log.record(
+        ConnectionEventKind::Banned,
+        Some(peer_id),
+        None,
+        Some(reason),
+    );
+}
+
+/// Active connections of the network, set once the network is started
+pub type HandshakeActiveConnections = Arc<RwLock<Option<Box<dyn ActiveConnectionsTrait>>>>;
+
 #[derive(Clone)]
 pub struct MassaHandshake {
     pub announcement_serializer: AnnouncementSerializer,
@@ -253,8 +810,23 @@ pub struct MassaHandshake {
     pub version_deserializer: VersionDeserializer,
     pub config: ProtocolConfig,
     pub peer_db: SharedPeerDB,
+    network_id: massa_hash::Hash,
     peer_mngt_msg_serializer: crate::messages::MessagesSerializer,
     message_handlers: MessagesHandler,
+    /// Numbers the messages of the connections when both sides support it
+    replay_guard: Option<ReplayGuard>,
+    /// Used to resolve simultaneous dials with the same peer
+    pub active_connections: HandshakeActiveConnections,
+    /// Announcement sent in the handshakes, kept up to date when the listeners or the
+    /// routable IP change at runtime
+    pub local_announcement: SharedLocalAnnouncement,
+    /// Peer ids of all the identities of the node, which must not connect to each other
+    own_peer_ids: Arc<HashSet<PeerId>>,
+    audit_log: SharedConnectionAuditLog,
+    clock: SharedClock,
+    handshake_failure_policy: HandshakeFailurePolicy,
+    /// Connections whose handshake is in progress, shared by the clones of the handshake
+    half_open: SharedHalfOpenConnections,
 }
 
 impl MassaHandshake {
@@ -262,7 +834,19 @@ impl MassaHandshake {
         peer_db: SharedPeerDB,
         config: ProtocolConfig,
         message_handlers: MessagesHandler,
+        local_announcement: SharedLocalAnnouncement,
+        audit_log: SharedConnectionAuditLog,
+        clock: SharedClock,
     ) -> Self {
+        // the messages received during the handshake are never numbered
+        let replay_guard = message_handlers.replay_guard.clone();
+        let message_handlers = message_handlers.without_replay_guard();
+        let half_open = Arc::new(parking_lot::Mutex::new(HalfOpenConnections::from_config(
+            &config,
+        )));
+        if config.half_open_connection_deadline.to_millis() > 0 {
+            start_half_open_watchdog(&half_open, clock.clone());
+        }
         Self {
             peer_db,
             announcement_serializer: AnnouncementSerializer::new(),
@@ -273,191 +857,299 @@ impl MassaHandshake {
             ),
             version_serializer: VersionSerializer::new(),
             version_deserializer: VersionDeserializer::new(),
+            network_id: network_id(config.chain_id, config.genesis_timestamp),
+            handshake_failure_policy: HandshakeFailurePolicy::from_config(&config),
             config,
             peer_mngt_msg_serializer: crate::messages::MessagesSerializer::new()
                 .with_peer_management_message_serializer(PeerManagementMessageSerializer::new()),
             message_handlers,
+            replay_guard,
+            active_connections: Arc::new(RwLock::new(None)),
+            local_announcement,
+            own_peer_ids: Arc::new(HashSet::new()),
+            audit_log,
+            clock,
+            half_open,
         }
     }
-}
 
-impl InitConnectionHandler for MassaHandshake {
-    fn perform_handshake<MassaMessagesHandler: PeerNetMessagesHandler>(
+    /// Refuse the handshakes with the other identities of the node
+    pub fn with_own_identities(mut self, own_peer_ids: HashSet<PeerId>) -> Self {
+        self.own_peer_ids = Arc::new(own_peer_ids);
+        self
+    }
+
+    /// Whether `peer_id`, which dialed us, can take an in slot. Only the reserved peers can take
+    /// the last `reserved_in_slots` ones. The reserved peers are still subject to the limits of
+    /// their category once the handshake is over.
+    fn in_slot_available(&self, peer_id: &PeerId) -> bool {
+        if self.config.reserved_in_slots == 0 || self.config.reserved_peers.contains(peer_id) {
+            return true;
+        }
+        let unreserved_in_connections = self
+            .active_connections
+            .read()
+            .as_ref()
+            .map(|active_connections| {
+                active_connections
+                    .get_peers_connected()
+                    .iter()
+                    .filter(|(peer_id, (_, connection_type, _))| {
+                        *connection_type == PeerConnectionType::IN
+                            && !self.config.reserved_peers.contains(peer_id)
+                    })
+                    .count()
+            })
+            .unwrap_or_default();
+        unreserved_in_slot_available(
+            unreserved_in_connections,
+            self.config.max_in_connections,
+            self.config.reserved_in_slots,
+        )
+    }
+
+    /// Called once a handshake with `peer_id` succeeded and both sides support the tie-break.
+    /// If another connection with the peer exists, keep the one dialed by the lowest peer id:
+    /// either the new handshake fails, or the existing connection is closed.
+    fn resolve_simultaneous_dial(
+        &self,
+        self_peer_id: &PeerId,
+        peer_id: &PeerId,
+    ) -> PeerHandlerResult<()> {
+        let mut active_connections = self.active_connections.write();
+        let Some(active_connections) = active_connections.as_mut() else {
+            return Ok(());
+        };
+        let Some(existing_connection_type) = active_connections.get_peer_connection_type(peer_id) else {
+            return Ok(());
+        };
+        if keep_existing_connection(self_peer_id, peer_id, &existing_connection_type) {
+            debug!("Keep existing connection with peer {}", peer_id);
+            return Err(PeerHandlerError::AlreadyConnected);
+        }
+        debug!("Replace existing connection with peer {}", peer_id);
+        disconnect_peer(
+            active_connections.as_mut(),
+            &self.peer_mngt_msg_serializer,
+            &self.audit_log,
+            peer_id,
+            DisconnectReason::DuplicateConnection,
+        );
+        Ok(())
+    }
+
+    /// Exchanges the hello and the signed challenges with the peer at the other end of `endpoint`
+    fn handshake<MassaMessagesHandler: PeerNetMessagesHandler>(
         &mut self,
         keypair: &KeyPair,
         endpoint: &mut Endpoint,
-        listeners: &HashMap<SocketAddr, TransportType>,
         messages_handler: MassaMessagesHandler,
-    ) -> PeerNetResult<PeerId> {
-        let mut bytes = PeerId::from_public_key(keypair.get_public_key()).to_bytes();
+    ) -> PeerHandlerResult<PeerId> {
+        let self_peer_id = PeerId::from_public_key(keypair.get_public_key());
+        let mut bytes = self_peer_id.to_bytes();
         self.version_serializer
             .serialize(&self.config.version, &mut bytes)
             .map_err(|err| {
-                PeerNetError::HandshakeError.error(
-                    "Massa Handshake",
-                    Some(format!("Failed to serialize version: {}", err)),
-                )
+                PeerHandlerError::SerializationError(format!(
+                    "Failed to serialize version: {}",
+                    err
+                ))
             })?;
         bytes.push(0);
-        let listeners_announcement =
-            Announcement::new(listeners.clone(), self.config.routable_ip, keypair).unwrap();
         self.announcement_serializer
-            .serialize(&listeners_announcement, &mut bytes)
+            .serialize(self.local_announcement.read().announcement(), &mut bytes)
             .map_err(|err| {
-                PeerNetError::HandshakeError.error(
-                    "Massa Handshake",
-                    Some(format!("Failed to serialize announcement: {}", err)),
-                )
+                PeerHandlerError::SerializationError(format!(
+                    "Failed to serialize announcement: {}",
+                    err
+                ))
             })?;
-        endpoint.send(&bytes)?;
-        let received = endpoint.receive()?;
-        if received.len() < 32 {
-            return Err(PeerNetError::HandshakeError.error(
-                "Massa Handshake",
-                Some(format!("Received too short message len:{}", received.len())),
-            ));
+        let padding_bucket_size = self.config.traffic_padding_bucket_size;
+        if padding_bucket_size > 0 {
+            serialize_handshake_features(
+                HANDSHAKE_FEATURES | HANDSHAKE_FEATURE_PADDING,
+                &mut bytes,
+            );
+            bytes.extend(self.network_id.to_bytes());
+            pad_to_bucket(&mut bytes, 0, padding_bucket_size);
+        } else {
+            serialize_handshake_features(HANDSHAKE_FEATURES, &mut bytes);
+            bytes.extend(self.network_id.to_bytes());
         }
-        let mut offset = 0;
-        let peer_id =
-            PeerId::from_bytes(&received[offset..offset + 32].try_into().map_err(|_| {
-                PeerNetError::HandshakeError.error(
-                    "Massa Handshake",
-                    Some("Failed to deserialize PeerId".to_string()),
-                )
-            })?)?;
-        offset += PUBLIC_KEY_SIZE_BYTES;
-        {
-            let peer_db_read = self.peer_db.read();
-            if let Some(info) = peer_db_read.peers.get(&peer_id) {
-                if info.state == PeerState::Banned {
-                    debug!("Banned peer tried to connect: {:?}", peer_id);
-                }
+        let mut transcript = HandshakeTranscript::default();
+        transcript.record_sent(&bytes);
+        endpoint.send(&bytes).map_err(network_error)?;
+        let received = endpoint.receive().map_err(network_error)?;
+        transcript.record_received(&received);
+        let (peer_id, received) = deserialize_hello_peer_id(&received)?;
+        if self.own_peer_ids.contains(&peer_id) {
+            return Err(PeerHandlerError::OwnIdentity);
+        }
+        // the unknown peers are only written to the DB once their handshake succeeds
+        match self.peer_db.snapshot().peers.get(&peer_id) {
+            Some(info) if info.state == PeerState::Banned => {
+                debug!("Banned peer tried to connect: {:?}", peer_id);
+                return Err(PeerHandlerError::PeerBanned);
             }
+            Some(_) => self
+                .peer_db
+                .write()
+                .set_peer_handshake_state(&peer_id, PeerState::InHandshake),
+            None => {}
         }
 
+        let mut peer_features = 0;
+        // first sequence numbers sent and received, if the connection uses them
+        let mut sequence_starts = None;
         let res = {
-            {
-                let mut peer_db_write = self.peer_db.write();
-                peer_db_write
-                    .peers
-                    .entry(peer_id.clone())
-                    .and_modify(|info| {
-                        info.state = PeerState::InHandshake;
-                    });
-            }
-
-            let (received, version) = self
-                .version_deserializer
-                .deserialize::<DeserializeError>(&received[offset..])
-                .map_err(|err| {
-                    PeerNetError::HandshakeError.error(
-                        "Massa Handshake",
-                        Some(format!("Failed to deserialize version: {}", err)),
-                    )
-                })?;
-            if !self.config.version.is_compatible(&version) {
-                return Err(PeerNetError::HandshakeError.error(
-                    "Massa Handshake",
-                    Some(format!("Received version incompatible: {}", version)),
-                ));
-            }
-            offset = 0;
-            let id = received.get(offset).ok_or(
-                PeerNetError::HandshakeError
-                    .error("Massa Handshake", Some("Failed to get id".to_string())),
+            let (_version, content) = deserialize_hello_content(
+                received,
+                &self.config.version,
+                &self.network_id,
+                &self.version_deserializer,
+                &self.announcement_deserializer,
             )?;
-            offset += 1;
-            match id {
-                0 => {
-                    let (_, announcement) = self
-                        .announcement_deserializer
-                        .deserialize::<DeserializeError>(&received[offset..])
-                        .map_err(|err| {
-                            PeerNetError::HandshakeError.error(
-                                "Massa Handshake",
-                                Some(format!("Failed to deserialize announcement: {}", err)),
-                            )
-                        })?;
+            match content {
+                HelloContent::Announcement(announcement, features) => {
+                    peer_features = features;
                     if peer_id
                         .verify_signature(&announcement.hash, &announcement.signature)
                         .is_err()
                     {
-                        return Err(PeerNetError::HandshakeError
-                            .error("Massa Handshake", Some("Invalid signature".to_string())));
+                        return Err(PeerHandlerError::InvalidSignature(HandshakeStage::Hello));
+                    }
+                    // the peer dialed us if our address isn't one of its listeners
+                    let inbound = !announcement
+                        .listeners
+                        .contains_key(endpoint.get_target_addr());
+                    if inbound && !self.in_slot_available(&peer_id) {
+                        debug!("No unreserved in slot for peer {}", peer_id);
+                        return Err(PeerHandlerError::ReservedSlots);
                     }
                     let message = PeerManagementMessage::NewPeerConnected((
                         peer_id.clone(),
                         announcement.clone().listeners,
                     ));
+                    // from here the peer management messages are framed if both sides support it
+                    let framed = features & HANDSHAKE_FEATURE_FRAMED_PEER_MESSAGES != 0;
+                    if framed {
+                        self.peer_db.traffic().start_framing(&peer_id);
+                    } else {
+                        self.peer_db.traffic().stop_framing(&peer_id);
+                    }
                     let mut bytes = Vec::new();
-                    let peer_management_message_serializer = PeerManagementMessageSerializer::new();
+                    let peer_management_message_serializer =
+                        PeerManagementMessageSerializer::new().with_framing(framed);
                     peer_management_message_serializer
                         .serialize(&message, &mut bytes)
                         .map_err(|err| {
-                            PeerNetError::HandshakeError.error(
-                                "Massa Handshake",
-                                Some(format!("Failed to serialize announcement: {}", err)),
-                            )
+                            PeerHandlerError::SerializationError(format!(
+                                "Failed to serialize announcement: {}",
+                                err
+                            ))
                         })?;
-                    messages_handler.handle(7, &bytes, &peer_id)?;
+                    messages_handler
+                        .handle(Message::from(message).get_id(), &bytes, &peer_id)
+                        .map_err(|err| PeerHandlerError::ChannelError(format!("{:?}", err)))?;
                     let mut self_random_bytes = [0u8; 32];
                     StdRng::from_entropy().fill_bytes(&mut self_random_bytes);
-                    let self_random_hash = Hash::compute_from(&self_random_bytes);
                     let mut bytes = [0u8; 32];
                     bytes[..32].copy_from_slice(&self_random_bytes);
 
-                    endpoint.send(&bytes)?;
-                    let received = endpoint.receive()?;
-                    let other_random_bytes: &[u8; 32] =
-                        received.as_slice().try_into().map_err(|_| {
-                            PeerNetError::HandshakeError.error(
-                                "Massa Handshake",
-                                Some("Failed to deserialize random bytes".to_string()),
-                            )
-                        })?;
+                    endpoint.send(&bytes).map_err(network_error)?;
+                    transcript.record_sent(&bytes);
+                    let received = endpoint.receive().map_err(network_error)?;
+                    transcript.record_received(&received);
+                    let other_random_bytes: &[u8; 32] = received
+                        .as_slice()
+                        .try_into()
+                        .map_err(|_| invalid_challenge("Failed to deserialize random bytes"))?;
+
+                    // only bind the challenges to the connection if both sides support it
+                    let bound_challenge = features & HANDSHAKE_FEATURE_BOUND_CHALLENGE != 0;
+                    // the transcript covers the challenges, so it binds them as well
+                    let signed_transcript = features & HANDSHAKE_FEATURE_TRANSCRIPT != 0;
 
                     // sign their random bytes
-                    let other_random_hash = Hash::compute_from(other_random_bytes);
+                    let other_random_hash = if signed_transcript {
+                        transcript.signed_hash(&self_peer_id, &peer_id)
+                    } else if bound_challenge {
+                        bound_challenge_hash(
+                            other_random_bytes,
+                            &self_random_bytes,
+                            &self_peer_id,
+                            &peer_id,
+                        )
+                    } else {
+                        Hash::compute_from(other_random_bytes)
+                    };
                     let self_signature = keypair.sign(&other_random_hash).map_err(|_| {
-                        PeerNetError::HandshakeError.error(
-                            "Massa Handshake",
-                            Some("Failed to sign random bytes".to_string()),
+                        PeerHandlerError::SerializationError(
+                            "Failed to sign random bytes".to_string(),
                         )
                     })?;
 
                     let mut bytes = [0u8; 64];
                     bytes.copy_from_slice(&self_signature.to_bytes());
 
-                    endpoint.send(&bytes)?;
-                    let received = endpoint.receive()?;
+                    endpoint.send(&bytes).map_err(network_error)?;
+                    let received = endpoint.receive().map_err(network_error)?;
 
-                    let other_signature =
-                        Signature::from_bytes(received.as_slice().try_into().map_err(|_| {
-                            PeerNetError::HandshakeError.error(
-                                "Massa Handshake",
-                                Some("Failed to get random bytes".to_string()),
-                            )
-                        })?)
-                        .map_err(|_| {
-                            PeerNetError::HandshakeError.error(
-                                "Massa Handshake",
-                                Some("Failed to sign 2 random bytes".to_string()),
-                            )
-                        })?;
+                    let other_signature = Signature::from_bytes(
+                        received
+                            .as_slice()
+                            .try_into()
+                            .map_err(|_| invalid_challenge("Failed to get random bytes"))?,
+                    )
+                    .map_err(|_| invalid_challenge("Failed to deserialize the signature"))?;
 
                     // check their signature
-                    peer_id.verify_signature(&self_random_hash, &other_signature)?;
+                    let self_random_hash = if signed_transcript {
+                        transcript.verified_hash(&self_peer_id, &peer_id)
+                    } else if bound_challenge {
+                        bound_challenge_hash(
+                            &self_random_bytes,
+                            other_random_bytes,
+                            &peer_id,
+                            &self_peer_id,
+                        )
+                    } else {
+                        Hash::compute_from(&self_random_bytes)
+                    };
+                    peer_id
+                        .verify_signature(&self_random_hash, &other_signature)
+                        .map_err(|_| {
+                            PeerHandlerError::InvalidSignature(HandshakeStage::Challenge)
+                        })?;
+                    if features & HANDSHAKE_FEATURE_SEQUENCE_NUMBERS != 0 {
+                        sequence_starts = Some((
+                            first_sequence(other_random_bytes),
+                            first_sequence(&self_random_bytes),
+                        ));
+                    }
                     Ok((peer_id.clone(), Some(announcement)))
                 }
-                1 => {
+                HelloContent::Message(received) => {
                     let (received, id) = self
                         .message_handlers
-                        .deserialize_id(&received[offset..], &peer_id)?;
-                    self.message_handlers.handle(id, received, &peer_id)?;
+                        .deserialize_id(received, &peer_id)
+                        .map_err(|err| PeerHandlerError::InvalidMessage {
+                        stage: HandshakeStage::Hello,
+                        reason: format!("{:?}", err),
+                    })?;
+                    // the message of a refusal isn't framed, unlike the ones of a connection
+                    let framed;
+                    let received = if self.peer_db.traffic().framed(&peer_id) {
+                        framed = frame_content(received);
+                        framed.as_slice()
+                    } else {
+                        received
+                    };
+                    self.message_handlers
+                        .handle(id, received, &peer_id)
+                        .map_err(|err| PeerHandlerError::ChannelError(format!("{:?}", err)))?;
                     Ok((peer_id.clone(), None))
                 }
-                _ => Err(PeerNetError::HandshakeError
-                    .error("Massa Handshake", Some("Invalid message id".to_string()))),
             }
         };
         {
@@ -466,60 +1158,228 @@ impl InitConnectionHandler for MassaHandshake {
             match &res {
                 Ok((peer_id, Some(announcement))) => {
                     info!("Peer connected: {:?}", peer_id);
-                    //TODO: Hacky organize better when multiple ip/listeners
-                    if !announcement.listeners.is_empty() {
-                        peer_db_write
-                            .index_by_newest
-                            .retain(|(_, peer_id_stored)| peer_id_stored != peer_id);
-                        peer_db_write
-                            .index_by_newest
-                            .insert((Reverse(announcement.timestamp), peer_id.clone()));
-                    }
-                    peer_db_write
-                        .peers
-                        .entry(peer_id.clone())
-                        .and_modify(|info| {
-                            info.last_announce = announcement.clone();
-                            info.state = PeerState::Trusted;
-                        })
-                        .or_insert(PeerInfo {
-                            last_announce: announcement.clone(),
-                            state: PeerState::Trusted,
-                        });
+                    peer_db_write.set_peer_announcement(peer_id, announcement.clone());
+                    let address = *endpoint.get_target_addr();
+                    let direction = if announcement.listeners.contains_key(&address) {
+                        PeerConnectionType::OUT
+                    } else {
+                        PeerConnectionType::IN
+                    };
+                    peer_db_write.record_peer_source(peer_id, direction);
+                    // The address is one of the announced listeners when we dialed the peer.
+                    // Otherwise the peer dialed one of our listeners, and we only know the
+                    // transport if all our listeners share it.
+                    let transport = announcement.listeners.get(&address).copied().or_else(|| {
+                        let local_announcement = self.local_announcement.read();
+                        let mut transports = local_announcement.listeners().values();
+                        let first = *transports.next()?;
+                        transports
+                            .all(|transport| *transport == first)
+                            .then_some(first)
+                    });
+                    peer_db_write.set_peer_connection(
+                        peer_id,
+                        PeerConnectionRecord {
+                            address,
+                            transport,
+                            established_at: self.clock.now(),
+                            last_pong: None,
+                            unresponsive_at: None,
+                            direction,
+                        },
+                    );
                 }
                 Ok((_peer_id, None)) => {
-                    peer_db_write.peers.entry(peer_id).and_modify(|info| {
-                        //TODO: Add the peerdb but for now impossible as we don't have announcement and we need one to place in peerdb
-                        info.state = PeerState::HandshakeFailed;
-                    });
-                    return Err(PeerNetError::HandshakeError.error(
-                        "Massa Handshake",
-                        Some("Distant peer don't have slot for us.".to_string()),
-                    ));
+                    //TODO: Add the peerdb but for now impossible as we don't have announcement and we need one to place in peerdb
+                    peer_db_write.set_peer_handshake_state(&peer_id, PeerState::HandshakeFailed);
+                    // answering the hello with a message instead of an announcement means no slot
+                    peer_db_write.set_peer_disconnect_reason(&peer_id, DisconnectReason::NoSlot);
+                    return Err(PeerHandlerError::NoSlot);
                 }
                 Err(_) => {
-                    peer_db_write.peers.entry(peer_id).and_modify(|info| {
-                        //TODO: Add the peerdb but for now impossible as we don't have announcement and we need one to place in peerdb
-                        info.state = PeerState::HandshakeFailed;
-                    });
+                    //TODO: Add the peerdb but for now impossible as we don't have announcement and we need one to place in peerdb
+                    peer_db_write.set_peer_handshake_state(&peer_id, PeerState::HandshakeFailed);
                 }
             }
         }
 
+        if let Ok((peer_id, _)) = &res && peer_features & HANDSHAKE_FEATURE_DIAL_TIE_BREAK != 0 {
+            self.resolve_simultaneous_dial(&self_peer_id, peer_id)?;
+        }
+
+        // from here the peer management messages are padded if both sides want it, after their
+        // framed content
+        if let Ok((peer_id, _)) = &res {
+            if padding_bucket_size > 0
+                && peer_features & HANDSHAKE_FEATURE_PADDING != 0
+                && self.peer_db.traffic().framed(peer_id)
+            {
+                self.peer_db
+                    .traffic()
+                    .start_padding(peer_id, padding_bucket_size);
+            } else {
+                self.peer_db.traffic().stop_padding(peer_id);
+            }
+        }
+
+        // from here the messages are numbered if both sides support it
+        if let Ok((peer_id, _)) = &res && let Some(replay_guard) = &self.replay_guard {
+            match sequence_starts {
+                Some((first_sent, first_received)) => {
+                    replay_guard.start_session(peer_id, first_sent, first_received)
+                }
+                None => replay_guard.end_session(peer_id),
+            }
+        }
+        let next_serializer = || match (&res, &self.replay_guard) {
+            (Ok((peer_id, _)), Some(replay_guard)) => replay_guard
+                .sequenced(peer_id, &self.peer_mngt_msg_serializer)
+                .unwrap_or_else(|| self.peer_mngt_msg_serializer.clone())
+                .with_framing(self.peer_db.traffic().framed(peer_id))
+                .with_padding(self.peer_db.traffic().padding(peer_id)),
+            (Ok((peer_id, _)), None) => self
+                .peer_mngt_msg_serializer
+                .with_framing(self.peer_db.traffic().framed(peer_id))
+                .with_padding(self.peer_db.traffic().padding(peer_id)),
+            _ => self.peer_mngt_msg_serializer.clone(),
+        };
+
         // Send 100 peers to the other peer
         let peers_to_send = {
-            let peer_db_read = self.peer_db.read();
-            peer_db_read.get_rand_peers_to_send(100)
+            let peer_db_read = self.peer_db.snapshot();
+            peer_db_read.get_rand_peers_to_send(100, self.clock.now())
         };
         let mut buf = Vec::new();
         let msg = PeerManagementMessage::ListPeers(peers_to_send).into();
 
-        self.peer_mngt_msg_serializer.serialize_id(&msg, &mut buf)?;
-        self.peer_mngt_msg_serializer.serialize(&msg, &mut buf)?;
-        endpoint.send(buf.as_slice())?;
+        let serializer = next_serializer();
+        serializer
+            .serialize_id(&msg, &mut buf)
+            .map_err(serialization_error)?;
+        serializer
+            .serialize(&msg, &mut buf)
+            .map_err(serialization_error)?;
+        endpoint.send(buf.as_slice()).map_err(network_error)?;
+
+        // Tell the other peer who we are, if it reads it
+        if res.is_ok() && peer_features & HANDSHAKE_FEATURE_PEER_METADATA != 0 {
+            let metadata = PeerMetadata::new(
+                format!("massa/{}", self.config.version),
+                PEER_FEATURES
+                    .iter()
+                    .map(|feature| feature.to_string())
+                    .collect(),
+                self.config.chain_id,
+                keypair,
+            )
+            .map_err(serialization_error)?;
+            let mut buf = Vec::new();
+            let msg = PeerManagementMessage::PeerMetadata(metadata).into();
+            let serializer = next_serializer();
+            serializer
+                .serialize_id(&msg, &mut buf)
+                .map_err(serialization_error)?;
+            serializer
+                .serialize(&msg, &mut buf)
+                .map_err(serialization_error)?;
+            endpoint.send(buf.as_slice()).map_err(network_error)?;
+        }
 
         res.map(|(id, _)| id)
     }
+}
+
+impl InitConnectionHandler for MassaHandshake {
+    fn perform_handshake<MassaMessagesHandler: PeerNetMessagesHandler>(
+        &mut self,
+        keypair: &KeyPair,
+        endpoint: &mut Endpoint,
+        _listeners: &HashMap<SocketAddr, TransportType>,
+        messages_handler: MassaMessagesHandler,
+    ) -> PeerNetResult<PeerId> {
+        // refuse the banned IPs before spending any work on their handshake
+        let remote_addr = *endpoint.get_target_addr();
+        let remote_ip = remote_addr.ip();
+        self.audit_log.record(
+            ConnectionEventKind::HandshakeStarted,
+            None,
+            Some(remote_addr),
+            None,
+        );
+        if self
+            .peer_db
+            .snapshot()
+            .is_ip_banned(remote_ip, self.clock.now())
+        {
+            debug!("Refused handshake from banned IP {}", remote_ip);
+            let err = PeerHandlerError::IpBanned(remote_ip);
+            self.audit_log.record(
+                ConnectionEventKind::HandshakeFailed,
+                None,
+                Some(remote_addr),
+                Some(err.to_string()),
+            );
+            return Err(err).into_peernet("Massa Handshake");
+        }
+        // the watchdog closes the connection through its own handle if the handshake lasts
+        let watched_endpoint = endpoint.try_clone()?;
+        let half_open_id =
+            self.half_open
+                .lock()
+                .open(remote_ip, watched_endpoint, self.clock.now());
+        let Some(half_open_id) = half_open_id else {
+            debug!("Too many half-open connections from IP {}", remote_ip);
+            let err = PeerHandlerError::TooManyHalfOpenConnections(remote_ip);
+            self.audit_log.record(
+                ConnectionEventKind::HandshakeFailed,
+                None,
+                Some(remote_addr),
+                Some(err.to_string()),
+            );
+            return Err(err).into_peernet("Massa Handshake");
+        };
+        let mut res = self.handshake(keypair, endpoint, messages_handler);
+        // the peer is blamed for a handshake that the watchdog had to interrupt
+        if self.half_open.lock().complete(half_open_id) && res.is_err() {
+            debug!("Closed handshake with {}: deadline reached", remote_addr);
+            res = Err(PeerHandlerError::HandshakeDeadline);
+        }
+        match &res {
+            Ok(peer_id) => self.audit_log.record(
+                ConnectionEventKind::HandshakeSucceeded,
+                Some(peer_id),
+                Some(remote_addr),
+                None,
+            ),
+            Err(err) => self.audit_log.record(
+                ConnectionEventKind::HandshakeFailed,
+                None,
+                Some(remote_addr),
+                Some(err.to_string()),
+            ),
+        }
+        // a peer isn't blamed for a failure that connecting again later may fix
+        if let Err(err) = &res && !err.is_retryable() {
+            let banned = self.peer_db.write().record_handshake_failure(
+                remote_ip,
+                &self.handshake_failure_policy,
+                self.clock.now(),
+            );
+            if banned {
+                warn!(
+                    "IP {} temporarily banned after repeated handshake failures",
+                    remote_ip
+                );
+                self.audit_log.record(
+                    ConnectionEventKind::IpBanned,
+                    None,
+                    Some(remote_addr),
+                    Some("repeated handshake failures".to_string()),
+                );
+            }
+        }
+        res.into_peernet("Massa Handshake")
+    }
 
     fn fallback_function(
         &mut self,
@@ -527,6 +1387,12 @@ impl InitConnectionHandler for MassaHandshake {
         endpoint: &mut Endpoint,
         _listeners: &HashMap<SocketAddr, TransportType>,
     ) -> PeerNetResult<()> {
+        self.audit_log.record(
+            ConnectionEventKind::Fallback,
+            None,
+            Some(*endpoint.get_target_addr()),
+            None,
+        );
         //TODO: Fix this clone
         let keypair = keypair.clone();
         let mut endpoint = endpoint.try_clone()?;
@@ -534,22 +1400,12 @@ impl InitConnectionHandler for MassaHandshake {
         let serializer = self.peer_mngt_msg_serializer.clone();
         let version_serializer = self.version_serializer.clone();
         let version = self.config.version;
+        let now = self.clock.now();
         std::thread::spawn(move || {
-            let peers_to_send = db.read().get_rand_peers_to_send(100);
+            let peers_to_send = db.read().get_rand_peers_to_send(100, now);
             let mut buf = PeerId::from_public_key(keypair.get_public_key()).to_bytes();
-            if let Err(err) = version_serializer
-                .serialize(&version, &mut buf)
-                .map_err(|err| {
-                    PeerNetError::HandshakeError.error(
-                        "Massa Handshake",
-                        Some(format!(
-                            "Failed serialize version, Err: {:?}",
-                            err.to_string()
-                        )),
-                    )
-                })
-            {
-                warn!("{}", err.to_string());
+            if let Err(err) = version_serializer.serialize(&version, &mut buf) {
+                warn!("Failed to serialize version: {}", err);
                 return;
             }
             buf.push(1);
@@ -1,38 +1,198 @@
 use crossbeam::channel::Sender;
-use massa_protocol_exports::{BootstrapPeers, ProtocolError};
+use massa_models::stats::HandshakeFailureStats;
+use massa_protocol_exports::{
+    BanReport, BootstrapClientReport, BootstrapPeerInfo, BootstrapPeers, ConnectionEvent,
+    ListenerReachability, MisbehaviorKind, MisbehaviorRecord, OutConnectionTargets, PeerAnnotation,
+    PeerConnectionType, PeerTestResult, ProtocolConfig, ProtocolError, PROTECTED_PEER_TAG,
+};
+use massa_serialization::Serializer;
 use massa_time::MassaTime;
 use parking_lot::RwLock;
 use peernet::{peer_id::PeerId, transports::TransportType};
 use rand::seq::SliceRandom;
+use rand::Rng;
 use std::cmp::Reverse;
-use std::collections::BTreeSet;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::net::IpAddr;
 use std::{collections::HashMap, net::SocketAddr, sync::Arc};
-use tracing::log::info;
+use tracing::log::{info, warn};
 
-use super::announcement::Announcement;
+use super::{
+    address_tables::AddressTables,
+    announcement::{Announcement, AnnouncementSerializer, LocalAnnouncement},
+    journal::{JournalEntry, SharedPeerDBJournal},
+    messages::DisconnectReason,
+    metadata::PeerMetadata,
+    peer_db_lock::PeerDBLock,
+    status::ReceivedPeerStatus,
+};
 
 const THREE_DAYS_MS: u128 = 3 * 24 * 60 * 60 * 1_000_000;
 
 pub type InitialPeers = HashMap<PeerId, HashMap<SocketAddr, TransportType>>;
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct PeerDB {
     pub peers: HashMap<PeerId, PeerInfo>,
     /// peers tested successfully last is the oldest value (only routable peers) //TODO: need to be pruned
     pub index_by_newest: BTreeSet<(Reverse<u128>, PeerId)>,
-    /// Tested addresses used to avoid testing the same address too often. //TODO: Need to be pruned
-    pub tested_addresses: HashMap<SocketAddr, MassaTime>,
+    /// Addresses tested by the testers, split between the new and tried tables
+    pub addresses: AddressTables,
+    /// Maximum number of peers that are not banned kept per address bucket (no limit if `None`)
+    pub max_peers_per_bucket: Option<usize>,
+    /// IPs banned after too many bootstrap offenses, with the end of their ban
+    pub banned_ips: HashMap<IpAddr, MassaTime>,
+    /// Bootstrap offenses of each IP: count and start of the window in which they are counted
+    pub bootstrap_offenses: HashMap<IpAddr, (u64, MassaTime)>,
+    /// IPs whitelisted by our bootstrap server: tried first as peers and never banned by IP
+    pub preferred_ips: HashSet<IpAddr>,
+    /// Times of the failed handshakes of each IP within the sliding window
+    pub handshake_failures: HashMap<IpAddr, VecDeque<MassaTime>>,
+    /// Number of bans of each IP after failed handshakes, and the end of the last one
+    pub handshake_failure_bans: HashMap<IpAddr, (u32, MassaTime)>,
+    /// Failed handshakes and IP bans counted since the node started
+    pub handshake_failure_stats: HandshakeFailureStats,
+    /// Tags and notes attached to the peers by the operator, kept when a peer leaves the DB
+    pub annotations: HashMap<PeerId, PeerAnnotation>,
+    /// Maximum percentage of inbound-only peers in the lists sent to other peers (no limit if
+    /// `None`)
+    pub max_inbound_only_gossip_percent: Option<u64>,
+    /// Journal of the bans and trusted promotions, replayed at startup (none if disabled)
+    pub journal: Option<SharedPeerDBJournal>,
 }
 
-pub type SharedPeerDB = Arc<RwLock<PeerDB>>;
+/// Escalation of the bootstrap offenses of an IP to a ban
+#[derive(Clone, Copy, Debug)]
+pub struct BootstrapOffensePolicy {
+    /// Number of offenses within `window` after which the IP is banned (0 to disable)
+    pub ban_threshold: u64,
+    /// Window in which the offenses are counted
+    pub window: MassaTime,
+    /// Duration of the ban
+    pub ban_duration: MassaTime,
+}
+
+impl BootstrapOffensePolicy {
+    pub fn from_config(config: &ProtocolConfig) -> Self {
+        Self {
+            ban_threshold: config.bootstrap_offense_ban_threshold,
+            window: config.bootstrap_offense_window,
+            ban_duration: config.bootstrap_ip_ban_duration,
+        }
+    }
+}
+
+/// Escalation of the failed handshakes of an IP to a temporary ban
+#[derive(Clone, Copy, Debug)]
+pub struct HandshakeFailurePolicy {
+    /// Number of failures within `window` after which the IP is banned (0 to disable)
+    pub ban_threshold: u64,
+    /// Sliding window in which the failures are counted
+    pub window: MassaTime,
+    /// Duration of the first ban, doubled at each new ban of the IP
+    pub ban_duration: MassaTime,
+    /// Maximum duration of a ban. The bans of an IP are forgotten after it behaved for that long.
+    pub max_ban_duration: MassaTime,
+}
+
+impl HandshakeFailurePolicy {
+    pub fn from_config(config: &ProtocolConfig) -> Self {
+        Self {
+            ban_threshold: config.handshake_failure_ban_threshold,
+            window: config.handshake_failure_window,
+            ban_duration: config.handshake_failure_ban_duration,
+            max_ban_duration: config.handshake_failure_max_ban_duration,
+        }
+    }
+
+    /// Duration of the ban of an IP already banned `previous_bans` times
+    fn ban_duration(&self, previous_bans: u32) -> MassaTime {
+        let factor = 1u64.checked_shl(previous_bans).unwrap_or(u64::MAX);
+        MassaTime::from_millis(self.ban_duration.to_millis().saturating_mul(factor))
+            .min(self.max_ban_duration)
+    }
+}
+
+/// Group of addresses likely to be controlled by the same operator: /16 for IPv4, /32 for IPv6
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AddressBucket {
+    V4([u8; 2]),
+    V6([u8; 4]),
+}
+
+impl AddressBucket {
+    pub fn from_ip(ip: IpAddr) -> Self {
+        match ip.to_canonical() {
+            IpAddr::V4(ip) => {
+                let octets = ip.octets();
+                AddressBucket::V4([octets[0], octets[1]])
+            }
+            IpAddr::V6(ip) => {
+                let octets = ip.octets();
+                AddressBucket::V6([octets[0], octets[1], octets[2], octets[3]])
+            }
+        }
+    }
+}
+
+pub type SharedPeerDB = Arc<PeerDBLock>;
+
+/// Announcement of the node, changed at runtime with its listeners and routable IP
+pub type SharedLocalAnnouncement = Arc<RwLock<LocalAnnouncement>>;
+
+pub type SharedOutConnectionTargets = Arc<RwLock<OutConnectionTargets>>;
 
 pub type PeerMessageTuple = (PeerId, u64, Vec<u8>);
 
-#[derive(Clone, Debug)]
+/// Peer to test, with the direction of the connection of the peer that gossiped it (`OUT` for
+/// the peers of the config and the ones imported by the operator)
+pub type PeerToTest = (
+    PeerId,
+    HashMap<SocketAddr, TransportType>,
+    PeerConnectionType,
+);
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct PeerInfo {
     pub last_announce: Announcement,
     pub state: PeerState,
+    /// Metadata sent by the peer after its last handshake
+    pub metadata: Option<PeerMetadata>,
+    /// Connection established by the last successful handshake
+    pub connection: Option<PeerConnectionRecord>,
+    /// Last reason the peer gave before closing a connection, and when we received it
+    pub last_disconnect_reason: Option<(DisconnectReason, MassaTime)>,
+    /// Last head of the chain sent by the peer
+    pub status: Option<ReceivedPeerStatus>,
+    /// Direction of the connections through which we learned about the peer: its own
+    /// connections, or the connections of the peers that gossiped it. `OUT` as soon as one of
+    /// them is outbound, unknown for the peers recorded without a source.
+    pub source: Option<PeerConnectionType>,
+}
+
+impl PeerInfo {
+    /// Whether we only learned about the peer through inbound connections, which anyone can
+    /// open to us. Such peers can't dominate the peers we gossip.
+    pub fn is_inbound_only(&self) -> bool {
+        self.source == Some(PeerConnectionType::IN)
+    }
+}
+
+/// Connection of a peer, recorded when its handshake succeeds
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeerConnectionRecord {
+    /// Address of the peer on this connection
+    pub address: SocketAddr,
+    /// Transport used, when it could be determined
+    pub transport: Option<TransportType>,
+    /// Time at which the handshake succeeded
+    pub established_at: MassaTime,
+    /// Time of the last answer to a keep-alive ping on this connection
+    pub last_pong: Option<MassaTime>,
+    /// Time at which the connection was closed because the peer stopped answering the pings
+    pub unresponsive_at: Option<MassaTime>,
+    /// Whether we dialed the peer or the peer dialed us
+    pub direction: PeerConnectionType,
 }
 
 #[warn(dead_code)]
@@ -47,104 +207,701 @@ pub enum PeerState {
 pub enum PeerManagementCmd {
     Ban(Vec<PeerId>),
     Unban(Vec<PeerId>),
-    GetBootstrapPeers { responder: Sender<BootstrapPeers> },
+    GetBootstrapPeers {
+        responder: Sender<BootstrapPeers>,
+    },
+    TestPeer {
+        address: SocketAddr,
+        responder: Sender<PeerTestResult>,
+    },
+    /// Peers to test, given by the operator
+    ImportPeers(InitialPeers),
+    /// Ask a few connected peers to dial back our listeners
+    TestReachability {
+        responder: Sender<Vec<ListenerReachability>>,
+    },
+    SetTargets(OutConnectionTargets),
+    /// Tags and note attached to a peer by the operator
+    AnnotatePeer {
+        peer_id: PeerId,
+        annotation: PeerAnnotation,
+    },
+    BootstrapClientReport {
+        ip: IpAddr,
+        report: BootstrapClientReport,
+    },
+    /// An offense of a peer, recorded in the misbehavior ledger. The peer is banned if `ban`
+    /// is set or if it committed too many offenses.
+    ReportMisbehavior {
+        peer_id: PeerId,
+        kind: MisbehaviorKind,
+        evidence: String,
+        ban: bool,
+    },
+    GetMisbehaviors {
+        peer_ids: Option<Vec<PeerId>>,
+        responder: Sender<HashMap<PeerId, Vec<MisbehaviorRecord>>>,
+    },
+    GetConnectionEvents {
+        since: Option<MassaTime>,
+        peer_id: Option<PeerId>,
+        responder: Sender<Vec<ConnectionEvent>>,
+    },
+    /// Signed reports of the bans proven by a message of the banned peer
+    GetBanReports {
+        responder: Sender<Vec<BanReport>>,
+    },
+    /// Ban the peers of reports exported by other nodes, once their offense is checked again.
+    /// Answers the banned peer or the reason of the refusal for each report.
+    ImportBanReports {
+        reports: Vec<Vec<u8>>,
+        responder: Sender<Vec<Result<PeerId, String>>>,
+    },
+    /// Send the current listeners of the node to the connected peers, after a change of the
+    /// listeners or of the routable IP
+    AnnounceListeners,
     Stop,
 }
 
+/// Load the targets of the configured categories, overridden by the ones saved at runtime
+pub fn load_out_connection_targets(config: &ProtocolConfig) -> OutConnectionTargets {
+    let mut targets = OutConnectionTargets::from_config(config);
+    if !config.out_connection_targets_file.is_file() {
+        return targets;
+    }
+    match std::fs::read_to_string(&config.out_connection_targets_file)
+        .map_err(|err| err.to_string())
+        .and_then(|content| {
+            serde_json::from_str::<OutConnectionTargets>(&content).map_err(|err| err.to_string())
+        }) {
+        Ok(saved) => update_out_connection_targets(&mut targets, saved),
+        Err(err) => warn!("could not load out connection targets: {}", err),
+    }
+    targets
+}
+
+/// Replace the targets by the new ones. Categories that are not configured are ignored.
+pub fn update_out_connection_targets(
+    targets: &mut OutConnectionTargets,
+    new_targets: OutConnectionTargets,
+) {
+    for (name, target) in new_targets.categories {
+        match targets.categories.get_mut(&name) {
+            Some(current) => *current = target,
+            None => warn!("ignore target of unknown peer category {}", name),
+        }
+    }
+    targets.default = new_targets.default;
+}
+
+/// Load the tags and notes attached to the peers by the operator
+pub fn load_peer_annotations(config: &ProtocolConfig) -> HashMap<PeerId, PeerAnnotation> {
+    if !config.peer_annotations_file.is_file() {
+        return HashMap::new();
+    }
+    match std::fs::read_to_string(&config.peer_annotations_file)
+        .map_err(|err| err.to_string())
+        .and_then(|content| {
+            serde_json::from_str::<HashMap<PeerId, PeerAnnotation>>(&content)
+                .map_err(|err| err.to_string())
+        }) {
+        Ok(annotations) => annotations,
+        Err(err) => {
+            warn!("could not load peer annotations: {}", err);
+            HashMap::new()
+        }
+    }
+}
+
+/// Save the tags and notes attached to the peers, so that they survive a restart
+pub fn save_peer_annotations(
+    config: &ProtocolConfig,
+    annotations: &HashMap<PeerId, PeerAnnotation>,
+) {
+    match serde_json::to_string(annotations) {
+        Ok(json) => {
+            if let Err(err) = std::fs::write(&config.peer_annotations_file, json) {
+                warn!("could not save peer annotations: {}", err);
+            }
+        }
+        Err(err) => warn!("could not serialize peer annotations: {}", err),
+    }
+}
+
 pub struct PeerManagementChannel {
     pub msg_sender: Sender<PeerMessageTuple>,
     pub command_sender: Sender<PeerManagementCmd>,
 }
 
 impl PeerDB {
+    pub fn new(max_peers_per_bucket: usize) -> Self {
+        PeerDB {
+            max_peers_per_bucket: Some(max_peers_per_bucket),
+            ..Default::default()
+        }
+    }
+
+    /// Bucket of a peer, given by the smallest of its announced listeners
+    pub fn peer_bucket(info: &PeerInfo) -> Option<AddressBucket> {
+        info.last_announce
+            .listeners
+            .keys()
+            .min()
+            .map(|addr| AddressBucket::from_ip(addr.ip()))
+    }
+
+    /// Append a critical change to the journal, if the DB has one
+    fn journal(&self, entry: JournalEntry) {
+        if let Some(journal) = &self.journal {
+            let now = MassaTime::now().unwrap_or(MassaTime::from_millis(0));
+            journal.lock().append(&entry, self, now);
+        }
+    }
+
     pub fn ban_peer(&mut self, peer_id: &PeerId) {
-        println!("peers: {:?}", self.peers);
         if let Some(peer) = self.peers.get_mut(peer_id) {
             peer.state = PeerState::Banned;
             info!("Banned peer: {:?}", peer_id);
+            self.journal(JournalEntry::Ban(peer_id.clone()));
         } else {
             info!("Tried to ban unknown peer: {:?}", peer_id);
         };
+        self.debug_assert_invariants();
     }
 
     pub fn unban_peer(&mut self, peer_id: &PeerId) {
-        if self.peers.contains_key(peer_id) {
-            self.peers.remove(peer_id);
+        if self.peers.remove(peer_id).is_some() {
+            self.index_by_newest
+                .retain(|(_, peer_id_stored)| peer_id_stored != peer_id);
             info!("Unbanned peer: {:?}", peer_id);
+            self.journal(JournalEntry::Unban(peer_id.clone()));
         } else {
             info!("Tried to unban unknown peer: {:?}", peer_id);
         };
+        self.debug_assert_invariants();
+    }
+
+    /// Record a valid announcement received from a peer that passed a handshake.
+    /// The announcement is only kept if it is newer than the one we know, and the peer becomes
+    /// trusted unless it is banned.
+    pub fn set_peer_announcement(&mut self, peer_id: &PeerId, announcement: Announcement) {
+        let promoted = self.peers.get(peer_id).map_or(true, |info| {
+            info.state != PeerState::Trusted && info.state != PeerState::Banned
+        });
+        let info = self
+            .peers
+            .entry(peer_id.clone())
+            .and_modify(|info| {
+                if info.last_announce.timestamp < announcement.timestamp {
+                    info.last_announce = announcement.clone();
+                }
+                if info.state != PeerState::Banned {
+                    info.state = PeerState::Trusted;
+                }
+            })
+            .or_insert(PeerInfo {
+                last_announce: announcement,
+                state: PeerState::Trusted,
+                metadata: None,
+                connection: None,
+                last_disconnect_reason: None,
+                status: None,
+                source: None,
+            });
+        let timestamp = info.last_announce.timestamp;
+        let has_listeners = !info.last_announce.listeners.is_empty();
+        self.index_by_newest
+            .retain(|(_, peer_id_stored)| peer_id_stored != peer_id);
+        //TODO: Hacky change it when better management ip/listeners
+        if has_listeners {
+            self.index_by_newest
+                .insert((Reverse(timestamp), peer_id.clone()));
+            self.enforce_bucket_cap(peer_id);
+        }
+        if promoted && let Some(info) = self.peers.get(peer_id) {
+            let mut announcement = Vec::new();
+            if AnnouncementSerializer::new()
+                .serialize(&info.last_announce, &mut announcement)
+                .is_ok()
+            {
+                self.journal(JournalEntry::Trusted {
+                    peer_id: peer_id.clone(),
+                    announcement,
+                });
+            }
+        }
+        self.debug_assert_invariants();
+    }
+
+    /// Evict the peers with the oldest announcements from the bucket of `peer_id` until the
+    /// bucket is back under its cap. Banned peers don't count and are kept to remember the ban,
+    /// the peers protected by the operator don't count either and are never evicted.
+    fn enforce_bucket_cap(&mut self, peer_id: &PeerId) {
+        let Some(max_peers) = self.max_peers_per_bucket else {
+            return;
+        };
+        let Some(bucket) = self.peers.get(peer_id).and_then(PeerDB::peer_bucket) else {
+            return;
+        };
+        // oldest first
+        let mut bucket_peers: Vec<PeerId> = self
+            .index_by_newest
+            .iter()
+            .rev()
+            .filter_map(|(_, indexed_peer_id)| {
+                let info = self.peers.get(indexed_peer_id)?;
+                (info.state != PeerState::Banned
+                    && !self.peer_has_tag(indexed_peer_id, PROTECTED_PEER_TAG)
+                    && PeerDB::peer_bucket(info) == Some(bucket))
+                .then(|| indexed_peer_id.clone())
+            })
+            .collect();
+        let excess = bucket_peers.len().saturating_sub(max_peers);
+        bucket_peers.retain(|bucket_peer_id| bucket_peer_id != peer_id);
+        for evicted in bucket_peers.into_iter().take(excess) {
+            self.peers.remove(&evicted);
+            self.index_by_newest
+                .retain(|(_, peer_id_stored)| peer_id_stored != &evicted);
+            info!(
+                "Evicted peer {} from full address bucket {:?}",
+                evicted, bucket
+            );
+        }
+    }
+
+    /// Record a decision of our bootstrap server about a client IP.
+    /// Returns true if the report got the IP banned.
+    pub fn record_bootstrap_report(
+        &mut self,
+        ip: IpAddr,
+        report: BootstrapClientReport,
+        policy: &BootstrapOffensePolicy,
+        now: MassaTime,
+    ) -> bool {
+        let ip = ip.to_canonical();
+        // forget the expired bans and offenses
+        self.banned_ips.retain(|_, ban_end| *ban_end > now);
+        self.bootstrap_offenses
+            .retain(|_, (_, window_start)| window_start.saturating_add(policy.window) > now);
+        match report {
+            BootstrapClientReport::Whitelisted => {
+                self.preferred_ips.insert(ip);
+                self.bootstrap_offenses.remove(&ip);
+                if self.banned_ips.remove(&ip).is_some() {
+                    self.journal(JournalEntry::UnbanIp(ip));
+                }
+                false
+            }
+            BootstrapClientReport::Blacklisted | BootstrapClientReport::RateLimited => {
+                if policy.ban_threshold == 0 || self.preferred_ips.contains(&ip) {
+                    return false;
+                }
+                let (count, _) = self.bootstrap_offenses.entry(ip).or_insert((0, now));
+                *count += 1;
+                if *count < policy.ban_threshold {
+                    return false;
+                }
+                self.bootstrap_offenses.remove(&ip);
+                let until = now.saturating_add(policy.ban_duration);
+                self.banned_ips.insert(ip, until);
+                info!("Banned IP {} after repeated bootstrap offenses", ip);
+                self.journal(JournalEntry::BanIp { ip, until });
+                true
+            }
+        }
+    }
+
+    /// Record a failed handshake with an IP.
+    /// Returns true if the failure got the IP temporarily banned.
+    pub fn record_handshake_failure(
+        &mut self,
+        ip: IpAddr,
+        policy: &HandshakeFailurePolicy,
+        now: MassaTime,
+    ) -> bool {
+        let ip = ip.to_canonical();
+        self.handshake_failure_stats.failures += 1;
+        // forget the expired bans, and the failures out of the window
+        self.banned_ips.retain(|_, ban_end| *ban_end > now);
+        self.handshake_failure_bans
+            .retain(|_, (_, ban_end)| ban_end.saturating_add(policy.max_ban_duration) > now);
+        let window_start = now.saturating_sub(policy.window);
+        self.handshake_failures.retain(|_, failures| {
+            while failures.front().map_or(false, |time| *time < window_start) {
+                failures.pop_front();
+            }
+            !failures.is_empty()
+        });
+        if policy.ban_threshold == 0 || self.preferred_ips.contains(&ip) {
+            return false;
+        }
+        let failures = self.handshake_failures.entry(ip).or_default();
+        failures.push_back(now);
+        if (failures.len() as u64) < policy.ban_threshold {
+            return false;
+        }
+        self.handshake_failures.remove(&ip);
+        let (previous_bans, last_ban_end) =
+            self.handshake_failure_bans.entry(ip).or_insert((0, now));
+        let ban_duration = policy.ban_duration(*previous_bans);
+        *previous_bans = previous_bans.saturating_add(1);
+        *last_ban_end = now.saturating_add(ban_duration);
+        let ban_end = self.banned_ips.entry(ip).or_insert(now);
+        *ban_end = (*ban_end).max(*last_ban_end);
+        let until = *ban_end;
+        self.handshake_failure_stats.ip_bans += 1;
+        info!(
+            "Banned IP {} for {} ms after repeated handshake failures",
+            ip,
+            ban_duration.to_millis()
+        );
+        self.journal(JournalEntry::BanIp { ip, until });
+        true
+    }
+
+    /// Failed handshakes and IP bans since the node started, and IPs currently banned
+    pub fn get_handshake_failure_stats(&self, now: MassaTime) -> HandshakeFailureStats {
+        HandshakeFailureStats {
+            banned_ip_count: self
+                .banned_ips
+                .values()
+                .filter(|ban_end| **ban_end > now)
+                .count() as u64,
+            ..self.handshake_failure_stats.clone()
+        }
+    }
+
+    /// Whether an IP is banned at `now`
+    pub fn is_ip_banned(&self, ip: IpAddr, now: MassaTime) -> bool {
+        self.banned_ips
+            .get(&ip.to_canonical())
+            .map_or(false, |ban_end| *ban_end > now)
     }
 
-    /// Retrieve the peer with the oldest test date.
-    pub fn get_oldest_peer(&self, cooldown: Duration) -> Option<SocketAddr> {
-        match self
-            .tested_addresses
+    /// Whether one of the announced listeners of a peer is whitelisted by our bootstrap server
+    pub fn is_peer_preferred(&self, peer_id: &PeerId) -> bool {
+        self.peers.get(peer_id).map_or(false, |info| {
+            info.last_announce
+                .listeners
+                .keys()
+                .any(|addr| self.preferred_ips.contains(&addr.ip().to_canonical()))
+        })
+    }
+
+    /// Record the metadata a known peer sent after its handshake.
+    /// Replace the annotation of a peer, an empty one removes it
+    pub fn set_peer_annotation(&mut self, peer_id: &PeerId, annotation: PeerAnnotation) {
+        if annotation.is_empty() {
+            self.annotations.remove(peer_id);
+        } else {
+            self.annotations.insert(peer_id.clone(), annotation);
+        }
+    }
+
+    pub fn peer_has_tag(&self, peer_id: &PeerId, tag: &str) -> bool {
+        self.annotations
+            .get(peer_id)
+            .map_or(false, |annotation| annotation.has_tag(tag))
+    }
+
+    /// Peers that the operator tagged with `tag`
+    pub fn peers_with_tag(&self, tag: &str) -> HashSet<PeerId> {
+        self.annotations
             .iter()
-            .min_by_key(|(_, timestamp)| *(*timestamp))
+            .filter(|(_, annotation)| annotation.has_tag(tag))
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect()
+    }
+
+    pub fn set_peer_metadata(&mut self, peer_id: &PeerId, metadata: PeerMetadata) {
+        if let Some(info) = self.peers.get_mut(peer_id) {
+            info.metadata = Some(metadata);
+        }
+    }
+
+    /// Record the reason a known peer gave before closing its connection.
+    pub fn set_peer_disconnect_reason(&mut self, peer_id: &PeerId, reason: DisconnectReason) {
+        if let Some(info) = self.peers.get_mut(peer_id) {
+            info.last_disconnect_reason = Some((reason, MassaTime::now().unwrap()));
+        }
+    }
+
+    /// Record the connection a known peer just completed its handshake on.
+    pub fn set_peer_connection(&mut self, peer_id: &PeerId, connection: PeerConnectionRecord) {
+        if let Some(info) = self.peers.get_mut(peer_id) {
+            info.connection = Some(connection);
+        }
+    }
+
+    /// Record the direction of a connection through which we learned about a peer. A peer
+    /// learned through an outbound connection is never considered inbound-only again.
+    pub fn record_peer_source(&mut self, peer_id: &PeerId, source: PeerConnectionType) {
+        if let Some(info) = self.peers.get_mut(peer_id)
+            && info.source != Some(PeerConnectionType::OUT)
         {
-            Some((addr, timestamp)) => {
-                if timestamp.estimate_instant().ok()?.elapsed() > cooldown {
-                    Some(*addr)
-                } else {
-                    None
-                }
+            info.source = Some(source);
+        }
+    }
+
+    /// Record the answer of a connected peer to a keep-alive ping.
+    pub fn set_peer_pong(&mut self, peer_id: &PeerId, now: MassaTime) {
+        if let Some(connection) = self
+            .peers
+            .get_mut(peer_id)
+            .and_then(|info| info.connection.as_mut())
+        {
+            connection.last_pong = Some(now);
+        }
+    }
+
+    /// Record the last head of the chain sent by a peer
+    pub fn set_peer_status(&mut self, peer_id: &PeerId, status: ReceivedPeerStatus) {
+        if let Some(info) = self.peers.get_mut(peer_id) {
+            info.status = Some(status);
+        }
+    }
+
+    /// Record that the connection of a peer was closed because it stopped answering the pings.
+    pub fn set_peer_unresponsive(&mut self, peer_id: &PeerId, now: MassaTime) {
+        if let Some(connection) = self
+            .peers
+            .get_mut(peer_id)
+            .and_then(|info| info.connection.as_mut())
+        {
+            connection.unresponsive_at = Some(now);
+        }
+    }
+
+    /// Update the state of a known peer during a handshake. Banned peers stay banned.
+    pub fn set_peer_handshake_state(&mut self, peer_id: &PeerId, state: PeerState) {
+        if let Some(info) = self.peers.get_mut(peer_id) {
+            if info.state != PeerState::Banned {
+                info.state = state;
             }
-            None => None,
         }
     }
 
+    /// Check the consistency of the database:
+    /// every peer of `index_by_newest` is in `peers` with the same announcement timestamp, and
+    /// every peer of `peers` announcing listeners is in `index_by_newest` exactly once, and
+    /// no address is in both the new and tried tables.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        for (Reverse(timestamp), peer_id) in &self.index_by_newest {
+            let Some(info) = self.peers.get(peer_id) else {
+                return Err(format!("indexed peer {} missing from peers", peer_id));
+            };
+            if info.last_announce.timestamp != *timestamp {
+                return Err(format!(
+                    "indexed peer {} has timestamp {} but its last announcement has {}",
+                    peer_id, timestamp, info.last_announce.timestamp
+                ));
+            }
+            if info.last_announce.listeners.is_empty() {
+                return Err(format!("indexed peer {} has no listeners", peer_id));
+            }
+        }
+        let listening_peers = self
+            .peers
+            .values()
+            .filter(|info| !info.last_announce.listeners.is_empty())
+            .count();
+        if listening_peers != self.index_by_newest.len() {
+            return Err(format!(
+                "{} peers with listeners but {} indexed peers",
+                listening_peers,
+                self.index_by_newest.len()
+            ));
+        }
+        self.addresses.check_invariants()
+    }
+
+    /// Check that none of the `connected` peers is banned
+    pub fn check_connected_invariants(&self, connected: &HashSet<PeerId>) -> Result<(), String> {
+        match connected.iter().find(|peer_id| {
+            self.peers
+                .get(peer_id)
+                .map_or(false, |info| info.state == PeerState::Banned)
+        }) {
+            Some(peer_id) => Err(format!("banned peer {} is connected", peer_id)),
+            None => Ok(()),
+        }
+    }
+
+    /// Panics if the database is inconsistent, only in debug builds
+    pub fn debug_assert_invariants(&self) {
+        debug_assert_eq!(
+            self.check_invariants(),
+            Ok(()),
+            "peer db invariant violated"
+        );
+    }
+
     /// Select max 100 peers to send to another peer
-    /// The selected peers should has been online within the last 3 days
+    /// The selected peers should has been online within the last 3 days.
+    /// Peers are picked in turn from each address bucket so that a single operator
+    /// can't fill the list, and the inbound-only peers are capped to
+    /// `max_inbound_only_gossip_percent` of the list.
     pub fn get_rand_peers_to_send(
         &self,
         nb_peers: usize,
+        now: MassaTime,
     ) -> Vec<(PeerId, HashMap<SocketAddr, TransportType>)> {
         //TODO: Add ourself
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backward")
-            .as_millis();
+        let min_time = (now.to_millis() as u128).saturating_sub(THREE_DAYS_MS);
 
-        let min_time = now - THREE_DAYS_MS;
+        let mut buckets: HashMap<AddressBucket, Vec<(PeerId, HashMap<SocketAddr, TransportType>)>> =
+            HashMap::new();
+        for (peer_id, peer) in &self.peers {
+            // skip old peers
+            if peer.last_announce.timestamp < min_time {
+                continue;
+            }
+            let listeners: HashMap<SocketAddr, TransportType> = peer
+                .last_announce
+                .listeners
+                .clone()
+                .into_iter()
+                .filter(|(addr, _)| addr.ip().to_canonical().is_global())
+                .collect();
+            let Some(bucket) = listeners.keys().min().map(|addr| AddressBucket::from_ip(addr.ip())) else {
+                continue;
+            };
+            buckets
+                .entry(bucket)
+                .or_default()
+                .push((peer_id.clone(), listeners));
+        }
 
-        let mut keys = self.peers.keys().cloned().collect::<Vec<_>>();
         let mut rng = rand::thread_rng();
-        keys.shuffle(&mut rng);
+        let mut buckets: Vec<_> = buckets.into_values().collect();
+        buckets.shuffle(&mut rng);
+        for bucket in &mut buckets {
+            bucket.shuffle(&mut rng);
+        }
 
+        let max_inbound_only = self
+            .max_inbound_only_gossip_percent
+            .map_or(nb_peers, |percent| nb_peers * percent as usize / 100);
+        let mut inbound_only = 0;
         let mut result = Vec::new();
-
-        for key in keys {
-            if result.len() >= nb_peers {
-                break;
-            }
-            if let Some(peer) = self.peers.get(&key) {
-                // skip old peers
-                if peer.last_announce.timestamp < min_time {
-                    continue;
+        while result.len() < nb_peers && !buckets.is_empty() {
+            buckets.retain_mut(|bucket| {
+                if result.len() < nb_peers {
+                    while let Some(peer) = bucket.pop() {
+                        if self.peers[&peer.0].is_inbound_only() {
+                            if inbound_only >= max_inbound_only {
+                                continue;
+                            }
+                            inbound_only += 1;
+                        }
+                        result.push(peer);
+                        break;
+                    }
                 }
-                let listeners: HashMap<SocketAddr, TransportType> = peer
-                    .last_announce
-                    .listeners
-                    .clone()
-                    .into_iter()
-                    .filter(|(addr, _)| addr.ip().to_canonical().is_global())
-                    .collect();
-                if listeners.is_empty() {
-                    continue;
-                }
-                result.push((key, listeners));
-            }
+                !bucket.is_empty()
+            });
         }
 
         result
     }
 
-    pub fn get_banned_peer_count(&self) -> u64 {
-        self.peers
-            .values()
-            .filter(|peer| peer.state == PeerState::Banned)
-            .count() as u64
+    /// Peers to send to a bootstrapping node.
+    ///
+    /// Only the peers announced recently with a routable listener are kept, skipping the banned
+    /// ones and the ones whose last handshake failed. Peers are spread over the address buckets
+    /// and randomly picked with a weight growing with the time they have been connected to us.
+    ///
+    /// # Arguments
+    /// * `nb_peers`: maximum number of peers to return
+    /// * `connected`: category of each peer currently connected to us
+    /// * `category_ips`: IPs of each configured peer category
+    /// * `now`: current time
+    pub fn get_bootstrap_peers(
+        &self,
+        nb_peers: usize,
+        connected: &HashMap<PeerId, Option<String>>,
+        category_ips: &HashMap<String, Vec<IpAddr>>,
+        now: MassaTime,
+    ) -> Vec<(PeerId, BootstrapPeerInfo)> {
+        let min_time = (now.to_millis() as u128).saturating_sub(THREE_DAYS_MS);
+        let mut rng = rand::thread_rng();
+
+        let mut buckets: HashMap<AddressBucket, Vec<(f64, PeerId, BootstrapPeerInfo)>> =
+            HashMap::new();
+        for (peer_id, peer) in &self.peers {
+            if matches!(peer.state, PeerState::Banned | PeerState::HandshakeFailed)
+                || peer.last_announce.timestamp < min_time
+            {
+                continue;
+            }
+            let listeners: HashMap<SocketAddr, TransportType> = peer
+                .last_announce
+                .listeners
+                .iter()
+                .filter(|(addr, _)| {
+                    addr.ip().to_canonical().is_global() && !self.is_ip_banned(addr.ip(), now)
+                })
+                .map(|(addr, transport)| (*addr, *transport))
+                .collect();
+            let Some(bucket) = listeners.keys().min().map(|addr| AddressBucket::from_ip(addr.ip())) else {
+                continue;
+            };
+            let (uptime, last_seen) = match (connected.contains_key(peer_id), &peer.connection) {
+                (true, Some(connection)) => (now.saturating_sub(connection.established_at), now),
+                (true, None) => (MassaTime::from_millis(0), now),
+                (false, _) => (
+                    MassaTime::from_millis(0),
+                    MassaTime::from_millis(peer.last_announce.timestamp as u64),
+                ),
+            };
+            let category = connected.get(peer_id).cloned().flatten().or_else(|| {
+                category_ips.iter().find_map(|(name, ips)| {
+                    listeners
+                        .keys()
+                        .any(|addr| ips.contains(&addr.ip().to_canonical()))
+                        .then(|| name.clone())
+                })
+            });
+            // weighted random sampling: the key of a peer is u^(1/weight) with u uniform in [0, 1)
+            // and its weight is one plus the number of minutes it has been connected
+            let weight = 1.0 + (uptime.to_millis() / 60_000) as f64;
+            let key = rng.gen::<f64>().powf(1.0 / weight);
+            buckets.entry(bucket).or_default().push((
+                key,
+                peer_id.clone(),
+                BootstrapPeerInfo {
+                    listeners,
+                    category,
+                    last_seen,
+                },
+            ));
+        }
+
+        // highest keys last so that they are popped first
+        let mut buckets: Vec<_> = buckets.into_values().collect();
+        for bucket in &mut buckets {
+            bucket.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+        }
+        buckets.sort_unstable_by(|a, b| {
+            let best = |bucket: &Vec<(f64, PeerId, BootstrapPeerInfo)>| {
+                bucket.last().map_or(0.0, |peer| peer.0)
+            };
+            best(b).total_cmp(&best(a))
+        });
+
+        let mut result = Vec::new();
+        while result.len() < nb_peers && !buckets.is_empty() {
+            buckets.retain_mut(|bucket| {
+                if result.len() < nb_peers {
+                    if let Some((_, peer_id, info)) = bucket.pop() {
+                        result.push((peer_id, info));
+                    }
+                }
+                !bucket.is_empty()
+            });
+        }
+
+        result
     }
 
     // Flush PeerDB to disk ?
@@ -152,3 +909,492 @@ impl PeerDB {
         unimplemented!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use massa_protocol_exports::{
+        BootstrapClientReport, BootstrapPeerInfo, OutConnectionTargets, PeerAnnotation,
+        PeerConnectionType, PROTECTED_PEER_TAG,
+    };
+    use massa_time::MassaTime;
+    use peernet::{peer_id::PeerId, transports::TransportType, types::KeyPair};
+    use proptest::prelude::*;
+
+    use super::{
+        update_out_connection_targets, AddressBucket, BootstrapOffensePolicy,
+        HandshakeFailurePolicy, PeerConnectionRecord, PeerDB, PeerState,
+    };
+    use crate::handlers::peer_handler::announcement::Announcement;
+
+    const PEER_COUNT: usize = 4;
+
+    #[derive(Debug, Clone)]
+    enum PeerDBOp {
+        /// successful handshake with an announcement of the given timestamp
+        Handshake {
+            peer: usize,
+            timestamp: u128,
+            with_listeners: bool,
+        },
+        HandshakeFailed(usize),
+        Ban(usize),
+        Unban(usize),
+        Evict(usize),
+    }
+
+    fn peer_db_op() -> impl Strategy<Value = PeerDBOp> {
+        prop_oneof![
+            (0..PEER_COUNT, 0..20u128, any::<bool>()).prop_map(
+                |(peer, timestamp, with_listeners)| PeerDBOp::Handshake {
+                    peer,
+                    timestamp,
+                    with_listeners,
+                }
+            ),
+            (0..PEER_COUNT).prop_map(PeerDBOp::HandshakeFailed),
+            (0..PEER_COUNT).prop_map(PeerDBOp::Ban),
+            (0..PEER_COUNT).prop_map(PeerDBOp::Unban),
+            (0..PEER_COUNT).prop_map(PeerDBOp::Evict),
+        ]
+    }
+
+    fn announcement(keypair: &KeyPair, timestamp: u128, with_listeners: bool) -> Announcement {
+        let mut listeners = HashMap::new();
+        listeners.insert("82.245.123.77:8081".parse().unwrap(), TransportType::Tcp);
+        let routable_ip = with_listeners.then(|| "82.245.123.77".parse().unwrap());
+        let mut announcement = Announcement::new(listeners, routable_ip, keypair).unwrap();
+        announcement.timestamp = timestamp;
+        announcement
+    }
+
+    proptest! {
+        /// Drives the peer db the same way the handshakes and the peer handler do,
+        /// keeping a set of connected peers like the network does.
+        #[test]
+        fn test_peer_db_invariants(ops in prop::collection::vec(peer_db_op(), 1..64)) {
+            let keypairs: Vec<KeyPair> = (0..PEER_COUNT).map(|_| KeyPair::generate()).collect();
+            let peer_ids: Vec<PeerId> = keypairs
+                .iter()
+                .map(|keypair| PeerId::from_public_key(keypair.get_public_key()))
+                .collect();
+            let mut peer_db = PeerDB::default();
+            let mut connected = HashSet::new();
+            for op in ops {
+                match op {
+                    PeerDBOp::Handshake { peer, timestamp, with_listeners } => {
+                        let banned = peer_db
+                            .peers
+                            .get(&peer_ids[peer])
+                            .map_or(false, |info| info.state == PeerState::Banned);
+                        if !banned {
+                            peer_db.set_peer_handshake_state(&peer_ids[peer], PeerState::InHandshake);
+                            peer_db.set_peer_announcement(
+                                &peer_ids[peer],
+                                announcement(&keypairs[peer], timestamp, with_listeners),
+                            );
+                            connected.insert(peer_ids[peer].clone());
+                        }
+                    }
+                    PeerDBOp::HandshakeFailed(peer) => {
+                        peer_db.set_peer_handshake_state(&peer_ids[peer], PeerState::HandshakeFailed);
+                    }
+                    PeerDBOp::Ban(peer) => {
+                        connected.remove(&peer_ids[peer]);
+                        peer_db.ban_peer(&peer_ids[peer]);
+                    }
+                    PeerDBOp::Unban(peer) => peer_db.unban_peer(&peer_ids[peer]),
+                    PeerDBOp::Evict(peer) => {
+                        connected.remove(&peer_ids[peer]);
+                    }
+                }
+                prop_assert_eq!(peer_db.check_invariants(), Ok(()));
+                prop_assert_eq!(peer_db.check_connected_invariants(&connected), Ok(()));
+            }
+        }
+
+        /// A peer stays banned whatever handshake happens after the ban
+        #[test]
+        fn test_peer_db_ban_is_sticky(timestamps in prop::collection::vec(0..20u128, 1..8)) {
+            let keypair = KeyPair::generate();
+            let peer_id = PeerId::from_public_key(keypair.get_public_key());
+            let mut peer_db = PeerDB::default();
+            peer_db.set_peer_announcement(&peer_id, announcement(&keypair, 0, true));
+            peer_db.ban_peer(&peer_id);
+            for timestamp in timestamps {
+                peer_db.set_peer_handshake_state(&peer_id, PeerState::HandshakeFailed);
+                peer_db.set_peer_announcement(&peer_id, announcement(&keypair, timestamp, true));
+                prop_assert_eq!(&peer_db.peers[&peer_id].state, &PeerState::Banned);
+            }
+        }
+    }
+
+    #[test]
+    fn test_peer_db_invariants_detect_drift() {
+        let keypair = KeyPair::generate();
+        let peer_id = PeerId::from_public_key(keypair.get_public_key());
+        let mut peer_db = PeerDB::default();
+        peer_db.set_peer_announcement(&peer_id, announcement(&keypair, 5, true));
+        assert_eq!(peer_db.check_invariants(), Ok(()));
+
+        // removing the peer without its index entry must be detected
+        peer_db.peers.remove(&peer_id);
+        assert!(peer_db.check_invariants().is_err());
+
+        peer_db.index_by_newest.clear();
+        assert_eq!(peer_db.check_invariants(), Ok(()));
+        peer_db.set_peer_announcement(&peer_id, announcement(&keypair, 5, true));
+        peer_db.ban_peer(&peer_id);
+        let connected = HashSet::from([peer_id]);
+        assert!(peer_db.check_connected_invariants(&connected).is_err());
+    }
+
+    fn announcement_on(keypair: &KeyPair, ip: &str, timestamp: u128) -> Announcement {
+        let mut listeners = HashMap::new();
+        listeners.insert(format!("{}:8081", ip).parse().unwrap(), TransportType::Tcp);
+        let mut announcement =
+            Announcement::new(listeners, Some(ip.parse().unwrap()), keypair).unwrap();
+        announcement.timestamp = timestamp;
+        announcement
+    }
+
+    #[test]
+    fn test_peer_db_bucket_cap() {
+        let keypairs: Vec<KeyPair> = (0..4).map(|_| KeyPair::generate()).collect();
+        let peer_ids: Vec<PeerId> = keypairs
+            .iter()
+            .map(|keypair| PeerId::from_public_key(keypair.get_public_key()))
+            .collect();
+        let mut peer_db = PeerDB::new(2);
+        peer_db.set_peer_announcement(&peer_ids[0], announcement_on(&keypairs[0], "82.245.1.1", 1));
+        peer_db.set_peer_announcement(&peer_ids[1], announcement_on(&keypairs[1], "82.245.2.2", 2));
+        peer_db.ban_peer(&peer_ids[0]);
+        peer_db.set_peer_announcement(&peer_ids[2], announcement_on(&keypairs[2], "82.245.3.3", 3));
+        // the banned peer doesn't count
+        assert_eq!(peer_db.peers.len(), 3);
+
+        // the bucket is full: the oldest peer that is not banned is evicted
+        peer_db.set_peer_announcement(&peer_ids[3], announcement_on(&keypairs[3], "82.245.4.4", 0));
+        assert_eq!(peer_db.check_invariants(), Ok(()));
+        assert!(peer_db.peers.contains_key(&peer_ids[0]));
+        assert!(!peer_db.peers.contains_key(&peer_ids[1]));
+        assert!(peer_db.peers.contains_key(&peer_ids[2]));
+        assert!(peer_db.peers.contains_key(&peer_ids[3]));
+        assert_eq!(
+            AddressBucket::from_ip("82.245.4.4".parse().unwrap()),
+            AddressBucket::from_ip("::ffff:82.245.200.1".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_protected_peers_are_never_evicted() {
+        let keypairs: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate()).collect();
+        let peer_ids: Vec<PeerId> = keypairs
+            .iter()
+            .map(|keypair| PeerId::from_public_key(keypair.get_public_key()))
+            .collect();
+        let mut peer_db = PeerDB::new(1);
+        let protected = PeerAnnotation {
+            tags: [PROTECTED_PEER_TAG.to_string(), "my-sentry-1".to_string()].into(),
+            note: Some("our sentry node".to_string()),
+        };
+        peer_db.set_peer_annotation(&peer_ids[0], protected.clone());
+        peer_db.set_peer_announcement(&peer_ids[0], announcement_on(&keypairs[0], "82.245.1.1", 1));
+        peer_db.set_peer_announcement(&peer_ids[1], announcement_on(&keypairs[1], "82.245.2.2", 2));
+        peer_db.set_peer_announcement(&peer_ids[2], announcement_on(&keypairs[2], "82.245.3.3", 3));
+
+        // the protected peer doesn't count and stays, the oldest of the others is evicted
+        assert!(peer_db.peers.contains_key(&peer_ids[0]));
+        assert!(!peer_db.peers.contains_key(&peer_ids[1]));
+        assert!(peer_db.peers.contains_key(&peer_ids[2]));
+        assert_eq!(
+            peer_db.peers_with_tag(PROTECTED_PEER_TAG),
+            HashSet::from([peer_ids[0].clone()])
+        );
+
+        // the annotation stays when the peer leaves the DB, and an empty one removes it
+        peer_db.unban_peer(&peer_ids[0]);
+        assert_eq!(peer_db.annotations.get(&peer_ids[0]), Some(&protected));
+        peer_db.set_peer_annotation(&peer_ids[0], PeerAnnotation::default());
+        assert!(peer_db.annotations.is_empty());
+    }
+
+    #[test]
+    fn test_rand_peers_sampled_across_buckets() {
+        let now = MassaTime::now().unwrap();
+        let mut peer_db = PeerDB::default();
+        let mut ips: Vec<String> = (0..10).map(|i| format!("82.245.0.{}", i + 1)).collect();
+        ips.push("91.12.0.1".to_string());
+        ips.push("145.3.0.1".to_string());
+        for ip in &ips {
+            let keypair = KeyPair::generate();
+            peer_db.set_peer_announcement(
+                &PeerId::from_public_key(keypair.get_public_key()),
+                announcement_on(&keypair, ip, now.to_millis() as u128),
+            );
+        }
+        let buckets: HashSet<AddressBucket> = peer_db
+            .get_rand_peers_to_send(3, now)
+            .iter()
+            .map(|(_, listeners)| AddressBucket::from_ip(listeners.keys().next().unwrap().ip()))
+            .collect();
+        assert_eq!(buckets.len(), 3);
+    }
+
+    #[test]
+    fn test_inbound_only_peers_are_capped_in_gossip() {
+        let now = MassaTime::now().unwrap();
+        let mut peer_db = PeerDB {
+            max_inbound_only_gossip_percent: Some(25),
+            ..Default::default()
+        };
+        let mut outbound_peers = HashSet::new();
+        for i in 0..8 {
+            let keypair = KeyPair::generate();
+            let peer_id = PeerId::from_public_key(keypair.get_public_key());
+            peer_db.set_peer_announcement(
+                &peer_id,
+                announcement_on(
+                    &keypair,
+                    &format!("82.{}.0.1", i + 1),
+                    now.to_millis() as u128,
+                ),
+            );
+            // half of the peers were only gossiped by inbound peers
+            if i % 2 == 0 {
+                peer_db.record_peer_source(&peer_id, PeerConnectionType::IN);
+            } else {
+                peer_db.record_peer_source(&peer_id, PeerConnectionType::OUT);
+                outbound_peers.insert(peer_id);
+            }
+        }
+        let peers = peer_db.get_rand_peers_to_send(8, now);
+        assert_eq!(peers.len(), 6);
+        assert_eq!(
+            peers
+                .iter()
+                .filter(|(peer_id, _)| !outbound_peers.contains(peer_id))
+                .count(),
+            2
+        );
+
+        // a peer vouched for by an outbound peer stays trusted
+        let peer_id = outbound_peers.iter().next().unwrap();
+        peer_db.record_peer_source(peer_id, PeerConnectionType::IN);
+        assert!(!peer_db.peers[peer_id].is_inbound_only());
+    }
+
+    #[test]
+    fn test_rand_peers_skip_stale_announcements() {
+        let now = MassaTime::from_millis(THREE_DAYS_MS as u64 + 1000);
+        let mut peer_db = PeerDB::default();
+        let fresh_keypair = KeyPair::generate();
+        let fresh_peer_id = PeerId::from_public_key(fresh_keypair.get_public_key());
+        peer_db.set_peer_announcement(
+            &fresh_peer_id,
+            announcement_on(&fresh_keypair, "82.245.0.1", 1000),
+        );
+        let stale_keypair = KeyPair::generate();
+        peer_db.set_peer_announcement(
+            &PeerId::from_public_key(stale_keypair.get_public_key()),
+            announcement_on(&stale_keypair, "91.12.0.1", 999),
+        );
+        let peers = peer_db.get_rand_peers_to_send(100, now);
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].0, fresh_peer_id);
+    }
+
+    #[test]
+    fn test_bootstrap_peers_are_filtered_and_annotated() {
+        let now = MassaTime::now().unwrap();
+        let timestamp = now.to_millis() as u128;
+        let ips = [
+            "82.245.1.1",
+            "91.12.0.1",
+            "145.3.0.1",
+            "83.1.0.1",
+            "84.1.0.1",
+            "85.1.0.1",
+        ];
+        let keypairs: Vec<KeyPair> = ips.iter().map(|_| KeyPair::generate()).collect();
+        let peer_ids: Vec<PeerId> = keypairs
+            .iter()
+            .map(|keypair| PeerId::from_public_key(keypair.get_public_key()))
+            .collect();
+        let mut peer_db = PeerDB::default();
+        for (i, ip) in ips.iter().enumerate() {
+            // the fourth peer has not been announced for a long time
+            let timestamp = if i == 3 { 0 } else { timestamp };
+            peer_db
+                .set_peer_announcement(&peer_ids[i], announcement_on(&keypairs[i], ip, timestamp));
+        }
+        peer_db.set_peer_connection(
+            &peer_ids[0],
+            PeerConnectionRecord {
+                address: "82.245.1.1:8081".parse().unwrap(),
+                transport: Some(TransportType::Tcp),
+                established_at: now.saturating_sub(MassaTime::from_millis(3_600_000)),
+                last_pong: None,
+                unresponsive_at: None,
+                direction: PeerConnectionType::OUT,
+            },
+        );
+        peer_db.ban_peer(&peer_ids[1]);
+        peer_db.set_peer_handshake_state(&peer_ids[2], PeerState::HandshakeFailed);
+        peer_db.banned_ips.insert(
+            "85.1.0.1".parse().unwrap(),
+            now.saturating_add(MassaTime::from_millis(60_000)),
+        );
+
+        let connected = HashMap::from([(peer_ids[0].clone(), Some("Bootstrap".to_string()))]);
+        let category_ips =
+            HashMap::from([("Relay".to_string(), vec!["84.1.0.1".parse().unwrap()])]);
+        let peers: HashMap<PeerId, BootstrapPeerInfo> = peer_db
+            .get_bootstrap_peers(10, &connected, &category_ips, now)
+            .into_iter()
+            .collect();
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers[&peer_ids[0]].category.as_deref(), Some("Bootstrap"));
+        assert_eq!(peers[&peer_ids[0]].last_seen, now);
+        assert_eq!(peers[&peer_ids[4]].category.as_deref(), Some("Relay"));
+        assert_eq!(
+            peers[&peer_ids[4]].last_seen,
+            MassaTime::from_millis(timestamp as u64)
+        );
+        assert_eq!(
+            peers[&peer_ids[4]].listeners,
+            HashMap::from([("84.1.0.1:8081".parse().unwrap(), TransportType::Tcp)])
+        );
+    }
+
+    #[test]
+    fn test_bootstrap_offenses_escalate_to_ip_ban() {
+        let policy = BootstrapOffensePolicy {
+            ban_threshold: 3,
+            window: MassaTime::from_millis(1000),
+            ban_duration: MassaTime::from_millis(5000),
+        };
+        let ip = "82.245.1.1".parse().unwrap();
+        let mut peer_db = PeerDB::default();
+
+        // offenses spread over more than the window don't add up
+        for now in [0, 600, 1200, 1800] {
+            let now = MassaTime::from_millis(now);
+            assert!(!peer_db.record_bootstrap_report(
+                ip,
+                BootstrapClientReport::RateLimited,
+                &policy,
+                now
+            ));
+        }
+        assert!(!peer_db.is_ip_banned(ip, MassaTime::from_millis(1800)));
+
+        // the third offense of the window bans the IP, in its IPv4-mapped form too
+        assert!(peer_db.record_bootstrap_report(
+            ip,
+            BootstrapClientReport::Blacklisted,
+            &policy,
+            MassaTime::from_millis(1900)
+        ));
+        assert!(peer_db.is_ip_banned(
+            "::ffff:82.245.1.1".parse().unwrap(),
+            MassaTime::from_millis(2000)
+        ));
+        assert!(!peer_db.is_ip_banned(ip, MassaTime::from_millis(6900)));
+    }
+
+    #[test]
+    fn test_handshake_failures_escalate_to_temporary_ip_bans() {
+        let policy = HandshakeFailurePolicy {
+            ban_threshold: 3,
+            window: MassaTime::from_millis(1000),
+            ban_duration: MassaTime::from_millis(2000),
+            max_ban_duration: MassaTime::from_millis(5000),
+        };
+        let ip = "82.245.1.1".parse().unwrap();
+        let mut peer_db = PeerDB::default();
+        let fail = |peer_db: &mut PeerDB, now: u64| {
+            peer_db.record_handshake_failure(ip, &policy, MassaTime::from_millis(now))
+        };
+
+        // the window slides: only the failures of the last second are counted
+        for now in [0, 600, 1200, 1800] {
+            assert!(!fail(&mut peer_db, now));
+        }
+        assert!(fail(&mut peer_db, 1900));
+        assert!(peer_db.is_ip_banned(ip, MassaTime::from_millis(3800)));
+        assert!(!peer_db.is_ip_banned(ip, MassaTime::from_millis(3900)));
+
+        // the next bans of the IP are longer, up to the maximum
+        for now in [4000, 4100] {
+            assert!(!fail(&mut peer_db, now));
+        }
+        assert!(fail(&mut peer_db, 4200));
+        assert!(peer_db.is_ip_banned(ip, MassaTime::from_millis(8100)));
+        assert!(!peer_db.is_ip_banned(ip, MassaTime::from_millis(8200)));
+        for now in [8300, 8400] {
+            assert!(!fail(&mut peer_db, now));
+        }
+        assert!(fail(&mut peer_db, 8500));
+        assert!(!peer_db.is_ip_banned(ip, MassaTime::from_millis(13500)));
+
+        let stats = peer_db.get_handshake_failure_stats(MassaTime::from_millis(8500));
+        assert_eq!(
+            (stats.failures, stats.ip_bans, stats.banned_ip_count),
+            (11, 3, 1)
+        );
+    }
+
+    #[test]
+    fn test_bootstrap_whitelisted_ip_is_preferred_and_never_banned() {
+        let policy = BootstrapOffensePolicy {
+            ban_threshold: 1,
+            window: MassaTime::from_millis(1000),
+            ban_duration: MassaTime::from_millis(5000),
+        };
+        let keypair = KeyPair::generate();
+        let peer_id = PeerId::from_public_key(keypair.get_public_key());
+        let mut peer_db = PeerDB::default();
+        peer_db.set_peer_announcement(&peer_id, announcement_on(&keypair, "82.245.1.1", 1));
+        assert!(!peer_db.is_peer_preferred(&peer_id));
+
+        let now = MassaTime::from_millis(0);
+        let ip = "82.245.1.1".parse().unwrap();
+        peer_db.record_bootstrap_report(ip, BootstrapClientReport::Whitelisted, &policy, now);
+        assert!(peer_db.is_peer_preferred(&peer_id));
+        assert!(!peer_db.record_bootstrap_report(
+            ip,
+            BootstrapClientReport::RateLimited,
+            &policy,
+            now
+        ));
+        assert!(!peer_db.is_ip_banned(ip, now));
+    }
+
+    #[test]
+    fn test_update_out_connection_targets() {
+        let mut targets = OutConnectionTargets {
+            categories: HashMap::from([("Bootstrap".to_string(), 1)]),
+            default: 10,
+        };
+        update_out_connection_targets(
+            &mut targets,
+            OutConnectionTargets {
+                categories: HashMap::from([
+                    ("Bootstrap".to_string(), 3),
+                    ("Unknown".to_string(), 5),
+                ]),
+                default: 4,
+            },
+        );
+        // unknown categories are not created
+        assert_eq!(
+            targets,
+            OutConnectionTargets {
+                categories: HashMap::from([("Bootstrap".to_string(), 3)]),
+                default: 4,
+            }
+        );
+    }
+}
@@ -0,0 +1,443 @@
+//! Lock of the peer DB shared by the handshakes, the peer handler loop, the testers and the
+//! connectivity thread.
+//!
+//! Writers take the lock for the duration of their update. Readers that only look at the DB
+//! take an immutable snapshot instead: it is rebuilt at most once per update, so a connection
+//! storm doesn't queue the handshakes behind the readers iterating over all the peers.
+//!
+//! Each write bumps the generation of the DB. Components that cache what they computed out of
+//! the DB can subscribe to its changes, and only refresh their cache when a change concerns
+//! them. The changes are only computed while there are subscribers.
+
+use std::{
+    collections::HashSet,
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crossbeam::channel::{bounded, Receiver, Sender, TryRecvError, TrySendError};
+use parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use peernet::peer_id::PeerId;
+use tracing::log::debug;
+
+use super::models::{PeerDB, PeerState, SharedPeerDB};
+use crate::peer_traffic::PeerTraffic;
+
+/// Waits for the lock longer than this are logged
+const SLOW_LOCK_WAIT: Duration = Duration::from_millis(100);
+
+/// Number of changes a subscriber can lag behind before it is unsubscribed
+const PEER_DB_CHANGES_CHANNEL_SIZE: usize = 1000;
+
+/// Peers changed by a write of the DB
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PeerDBChange {
+    /// generation of the DB after the write
+    pub generation: u64,
+    pub added: Vec<PeerId>,
+    pub updated: Vec<PeerId>,
+    pub removed: Vec<PeerId>,
+    /// the IPs banned after their offenses changed
+    pub banned_ips_changed: bool,
+}
+
+impl PeerDBChange {
+    fn between(old: &PeerDB, new: &PeerDB, generation: u64) -> Self {
+        let mut change = PeerDBChange {
+            generation,
+            banned_ips_changed: old.banned_ips != new.banned_ips,
+            ..Default::default()
+        };
+        for (peer_id, info) in &new.peers {
+            match old.peers.get(peer_id) {
+                None => change.added.push(peer_id.clone()),
+                Some(old_info) if old_info != info => change.updated.push(peer_id.clone()),
+                Some(_) => {}
+            }
+        }
+        change.removed = old
+            .peers
+            .keys()
+            .filter(|peer_id| !new.peers.contains_key(peer_id))
+            .cloned()
+            .collect();
+        change
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.updated.is_empty()
+            && self.removed.is_empty()
+            && !self.banned_ips_changed
+    }
+}
+
+/// Contention of the peer DB lock since the node started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PeerDBLockStats {
+    pub reads: u64,
+    /// reads that had to wait for a writer
+    pub contended_reads: u64,
+    pub read_wait_micros: u64,
+    pub writes: u64,
+    /// writes that had to wait for other readers or writers
+    pub contended_writes: u64,
+    pub write_wait_micros: u64,
+    pub max_write_wait_micros: u64,
+    /// snapshots rebuilt after an update
+    pub snapshot_rebuilds: u64,
+}
+
+#[derive(Default)]
+struct LockCounters {
+    reads: AtomicU64,
+    contended_reads: AtomicU64,
+    read_wait_micros: AtomicU64,
+    writes: AtomicU64,
+    contended_writes: AtomicU64,
+    write_wait_micros: AtomicU64,
+    max_write_wait_micros: AtomicU64,
+    snapshot_rebuilds: AtomicU64,
+}
+
+pub struct PeerDBLock {
+    db: RwLock<PeerDB>,
+    /// incremented by each write, under the write lock
+    generation: AtomicU64,
+    /// last snapshot of the DB and the generation it was taken at
+    snapshot: Mutex<(u64, Arc<PeerDB>)>,
+    /// senders of the changes to the subscribers
+    subscribers: Mutex<Vec<Sender<PeerDBChange>>>,
+    /// DB as of the last change published, kept while there are subscribers
+    published: Mutex<Option<Arc<PeerDB>>>,
+    counters: LockCounters,
+    /// traffic of the connected peers, counted without locking the DB
+    traffic: PeerTraffic,
+}
+
+/// Write lock of the DB, publishing the change to the subscribers when released
+pub struct PeerDBWriteGuard<'a> {
+    lock: &'a PeerDBLock,
+    guard: RwLockWriteGuard<'a, PeerDB>,
+}
+
+impl Deref for PeerDBWriteGuard<'_> {
+    type Target = PeerDB;
+
+    fn deref(&self) -> &PeerDB {
+        &self.guard
+    }
+}
+
+impl DerefMut for PeerDBWriteGuard<'_> {
+    fn deref_mut(&mut self) -> &mut PeerDB {
+        &mut self.guard
+    }
+}
+
+impl Drop for PeerDBWriteGuard<'_> {
+    fn drop(&mut self) {
+        // still under the write lock: the changes are published in the order of the writes
+        self.lock.publish(&self.guard);
+    }
+}
+
+impl PeerDBLock {
+    pub fn shared(db: PeerDB) -> SharedPeerDB {
+        let snapshot = Arc::new(db.clone());
+        Arc::new(PeerDBLock {
+            db: RwLock::new(db),
+            generation: AtomicU64::new(0),
+            snapshot: Mutex::new((0, snapshot)),
+            subscribers: Mutex::new(Vec::new()),
+            published: Mutex::new(None),
+            counters: LockCounters::default(),
+            traffic: PeerTraffic::default(),
+        })
+    }
+
+    /// Locks the DB for reading. Prefer `snapshot` unless the lock must be held across a write.
+    pub fn read(&self) -> RwLockReadGuard<'_, PeerDB> {
+        self.counters.reads.fetch_add(1, Ordering::Relaxed);
+        if let Some(guard) = self.db.try_read() {
+            return guard;
+        }
+        let start = Instant::now();
+        let guard = self.db.read();
+        let waited = start.elapsed();
+        self.counters
+            .contended_reads
+            .fetch_add(1, Ordering::Relaxed);
+        self.counters
+            .read_wait_micros
+            .fetch_add(waited.as_micros() as u64, Ordering::Relaxed);
+        if waited > SLOW_LOCK_WAIT {
+            debug!("waited {:?} to read the peer DB", waited);
+        }
+        guard
+    }
+
+    /// Locks the DB for writing, the next snapshot will include the changes
+    pub fn write(&self) -> PeerDBWriteGuard<'_> {
+        self.counters.writes.fetch_add(1, Ordering::Relaxed);
+        let guard = match self.db.try_write() {
+            Some(guard) => guard,
+            None => {
+                let start = Instant::now();
+                let guard = self.db.write();
+                let waited = start.elapsed().as_micros() as u64;
+                self.counters
+                    .contended_writes
+                    .fetch_add(1, Ordering::Relaxed);
+                self.counters
+                    .write_wait_micros
+                    .fetch_add(waited, Ordering::Relaxed);
+                self.counters
+                    .max_write_wait_micros
+                    .fetch_max(waited, Ordering::Relaxed);
+                if waited > SLOW_LOCK_WAIT.as_micros() as u64 {
+                    debug!("waited {}us to write the peer DB", waited);
+                }
+                guard
+            }
+        };
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        PeerDBWriteGuard { lock: self, guard }
+    }
+
+    /// Generation of the DB, incremented by each write
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Snapshot of the DB and the receiver of the changes made to it from that snapshot on.
+    /// A subscriber that lags behind by `PEER_DB_CHANGES_CHANNEL_SIZE` changes is unsubscribed:
+    /// its receiver disconnects and it has to subscribe again.
+    pub fn subscribe_changes(&self) -> (Arc<PeerDB>, Receiver<PeerDBChange>) {
+        let (sender, receiver) = bounded(PEER_DB_CHANGES_CHANNEL_SIZE);
+        // no write can happen between the snapshot and the subscription
+        let db = self.read();
+        let mut subscribers = self.subscribers.lock();
+        let mut published = self.published.lock();
+        let snapshot = match published.as_ref() {
+            Some(published) if !subscribers.is_empty() => published.clone(),
+            _ => Arc::new(db.clone()),
+        };
+        *published = Some(snapshot.clone());
+        subscribers.push(sender);
+        (snapshot, receiver)
+    }
+
+    /// Sends the changes of a write to the subscribers, called with the write lock held
+    fn publish(&self, db: &PeerDB) {
+        let mut subscribers = self.subscribers.lock();
+        if subscribers.is_empty() {
+            return;
+        }
+        let mut published = self.published.lock();
+        let generation = self.generation.load(Ordering::Acquire);
+        let new = Arc::new(db.clone());
+        let change = match published.replace(new.clone()) {
+            Some(old) => PeerDBChange::between(&old, &new, generation),
+            None => PeerDBChange::between(&PeerDB::default(), &new, generation),
+        };
+        // the copy made for the subscribers serves as the next snapshot, unless it is being read
+        if let Some(mut snapshot) = self.snapshot.try_lock() {
+            *snapshot = (generation, new);
+        }
+        if change.is_empty() {
+            return;
+        }
+        subscribers.retain(|sender| match sender.try_send(change.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                debug!("Unsubscribed a subscriber lagging behind the peer DB changes");
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+        if subscribers.is_empty() {
+            *published = None;
+        }
+    }
+
+    /// Immutable copy of the DB as of the last write, readable without holding any lock
+    pub fn snapshot(&self) -> Arc<PeerDB> {
+        let mut snapshot = self.snapshot.lock();
+        if snapshot.0 == self.generation.load(Ordering::Acquire) {
+            return snapshot.1.clone();
+        }
+        // the generation can't change while the read lock is held
+        let db = self.read();
+        let generation = self.generation.load(Ordering::Acquire);
+        *snapshot = (generation, Arc::new(db.clone()));
+        self.counters
+            .snapshot_rebuilds
+            .fetch_add(1, Ordering::Relaxed);
+        snapshot.1.clone()
+    }
+
+    /// Counters of the traffic of the connected peers
+    pub fn traffic(&self) -> &PeerTraffic {
+        &self.traffic
+    }
+
+    pub fn lock_stats(&self) -> PeerDBLockStats {
+        let counters = &self.counters;
+        PeerDBLockStats {
+            reads: counters.reads.load(Ordering::Relaxed),
+            contended_reads: counters.contended_reads.load(Ordering::Relaxed),
+            read_wait_micros: counters.read_wait_micros.load(Ordering::Relaxed),
+            writes: counters.writes.load(Ordering::Relaxed),
+            contended_writes: counters.contended_writes.load(Ordering::Relaxed),
+            write_wait_micros: counters.write_wait_micros.load(Ordering::Relaxed),
+            max_write_wait_micros: counters.max_write_wait_micros.load(Ordering::Relaxed),
+            snapshot_rebuilds: counters.snapshot_rebuilds.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Banned peers of the DB, kept up to date from its changes instead of counted at each request
+pub struct BannedPeers {
+    banned: HashSet<PeerId>,
+    changes: Receiver<PeerDBChange>,
+}
+
+impl BannedPeers {
+    pub fn new(peer_db: &PeerDBLock) -> Self {
+        let (snapshot, changes) = peer_db.subscribe_changes();
+        BannedPeers {
+            banned: snapshot
+                .peers
+                .iter()
+                .filter(|(_, info)| info.state == PeerState::Banned)
+                .map(|(peer_id, _)| peer_id.clone())
+                .collect(),
+            changes,
+        }
+    }
+
+    /// Number of banned peers, after the changes made since the last call
+    pub fn count(&mut self, peer_db: &PeerDBLock) -> u64 {
+        let mut changed = HashSet::new();
+        loop {
+            match self.changes.try_recv() {
+                Ok(change) => {
+                    changed.extend(change.added);
+                    changed.extend(change.updated);
+                    changed.extend(change.removed);
+                }
+                Err(TryRecvError::Empty) => break,
+                // unsubscribed after lagging behind: start again from the current DB
+                Err(TryRecvError::Disconnected) => {
+                    *self = BannedPeers::new(peer_db);
+                    return self.banned.len() as u64;
+                }
+            }
+        }
+        if !changed.is_empty() {
+            let snapshot = peer_db.snapshot();
+            for peer_id in changed {
+                match snapshot.peers.get(&peer_id) {
+                    Some(info) if info.state == PeerState::Banned => {
+                        self.banned.insert(peer_id);
+                    }
+                    _ => {
+                        self.banned.remove(&peer_id);
+                    }
+                }
+            }
+        }
+        self.banned.len() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, net::IpAddr, sync::Arc};
+
+    use massa_time::MassaTime;
+    use peernet::{peer_id::PeerId, transports::TransportType, types::KeyPair};
+
+    use super::{BannedPeers, PeerDBLock};
+    use crate::handlers::peer_handler::{
+        announcement::Announcement,
+        models::{PeerDB, PeerState},
+    };
+
+    #[test]
+    fn test_peer_db_snapshot_follows_writes() {
+        let peer_db = PeerDBLock::shared(PeerDB::default());
+        let ip: IpAddr = "82.245.123.77".parse().unwrap();
+        let first = peer_db.snapshot();
+        // no write: the same snapshot is shared
+        assert!(Arc::ptr_eq(&first, &peer_db.snapshot()));
+        assert_eq!(peer_db.lock_stats().snapshot_rebuilds, 0);
+
+        peer_db
+            .write()
+            .banned_ips
+            .insert(ip, MassaTime::from_millis(1000));
+        let second = peer_db.snapshot();
+        assert!(second.is_ip_banned(ip, MassaTime::from_millis(0)));
+        assert!(Arc::ptr_eq(&second, &peer_db.snapshot()));
+
+        // a snapshot taken before a write isn't changed by it
+        peer_db.write().banned_ips.clear();
+        assert!(second.is_ip_banned(ip, MassaTime::from_millis(0)));
+        assert!(!peer_db
+            .snapshot()
+            .is_ip_banned(ip, MassaTime::from_millis(0)));
+
+        let stats = peer_db.lock_stats();
+        assert_eq!(stats.writes, 2);
+        assert_eq!(stats.snapshot_rebuilds, 2);
+        assert_eq!(stats.contended_writes, 0);
+    }
+    #[test]
+    fn test_peer_db_changes_are_published_to_subscribers() {
+        let peer_db = PeerDBLock::shared(PeerDB::default());
+        let keypair = KeyPair::generate();
+        let peer_id = PeerId::from_public_key(keypair.get_public_key());
+        let mut listeners = HashMap::new();
+        listeners.insert("82.245.123.77:8081".parse().unwrap(), TransportType::Tcp);
+        let announcement =
+            Announcement::new(listeners, Some("82.245.123.77".parse().unwrap()), &keypair).unwrap();
+
+        let (snapshot, changes) = peer_db.subscribe_changes();
+        assert!(snapshot.peers.is_empty());
+        let mut banned_peers = BannedPeers::new(&peer_db);
+        peer_db
+            .write()
+            .set_peer_announcement(&peer_id, announcement);
+        let change = changes.try_recv().unwrap();
+        assert_eq!(change.generation, peer_db.generation());
+        assert_eq!(change.added, vec![peer_id.clone()]);
+        // the copy published to the subscribers is the next snapshot
+        assert!(peer_db.snapshot().peers.contains_key(&peer_id));
+        assert_eq!(peer_db.lock_stats().snapshot_rebuilds, 0);
+
+        // a write that changes nothing isn't published
+        drop(peer_db.write());
+        assert!(changes.try_recv().is_err());
+
+        // a late subscriber starts from the current DB
+        let (snapshot, late_changes) = peer_db.subscribe_changes();
+        assert!(snapshot.peers.contains_key(&peer_id));
+        peer_db.write().ban_peer(&peer_id);
+        let change = changes.try_recv().unwrap();
+        assert_eq!(change.updated, vec![peer_id.clone()]);
+        assert_eq!(late_changes.try_recv().unwrap(), change);
+        assert_eq!(peer_db.snapshot().peers[&peer_id].state, PeerState::Banned);
+        assert_eq!(banned_peers.count(&peer_db), 1);
+
+        drop(late_changes);
+        peer_db.write().unban_peer(&peer_id);
+        assert_eq!(changes.try_recv().unwrap().removed, vec![peer_id]);
+        assert_eq!(banned_peers.count(&peer_db), 0);
+    }
+}
@@ -0,0 +1,233 @@
+//! Head of the chain of a peer, exchanged at a regular interval.
+//!
+//! Each node sends its latest final block and the head of its blockclique to the peers that
+//! advertised the feature. The blocks summarize the state reached by the peer: a peer with
+//! another final block at the same final slot is on another fork, and a peer whose head
+//! stays far behind ours is stuck. Such peers are the last ones asked for blocks.
+
+use std::ops::Bound::{Excluded, Included};
+
+use massa_models::{
+    block_id::{BlockId, BlockIdDeserializer, BlockIdSerializer},
+    slot::{Slot, SlotDeserializer, SlotSerializer},
+};
+use massa_serialization::{Deserializer, SerializeError, Serializer};
+use massa_time::MassaTime;
+use nom::{
+    error::{context, ContextError, ParseError},
+    sequence::tuple,
+    IResult, Parser,
+};
+
+/// The node sends its status and records the statuses of its peers
+pub const PEER_STATUS_FEATURE: &str = "peer_status";
+
+/// Latest final block and head of the blockclique of a node
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PeerStatus {
+    pub final_slot: Slot,
+    pub final_block: BlockId,
+    pub best_slot: Slot,
+    pub best_block: BlockId,
+}
+
+impl PeerStatus {
+    /// Status built from the latest final blocks and the best parents of the consensus, with
+    /// one `(block id, period)` per thread. `None` if the consensus has no block yet.
+    pub fn from_heads(
+        final_blocks: &[(BlockId, u64)],
+        best_parents: &[(BlockId, u64)],
+    ) -> Option<Self> {
+        let (final_slot, final_block) = latest(final_blocks)?;
+        let (best_slot, best_block) = latest(best_parents)?;
+        Some(PeerStatus {
+            final_slot,
+            final_block,
+            best_slot,
+            best_block,
+        })
+    }
+
+    /// Whether all the slots of the status belong to a network of `thread_count` threads
+    pub fn is_valid(&self, thread_count: u8) -> bool {
+        self.final_slot.thread < thread_count
+            && self.best_slot.thread < thread_count
+            && self.final_slot <= self.best_slot
+    }
+
+    /// Whether the peer finalized another block than ours at the same slot
+    pub fn diverges_from(&self, local: &PeerStatus) -> bool {
+        self.final_slot == local.final_slot && self.final_block != local.final_block
+    }
+
+    /// Whether the head of the peer is more than `max_periods` periods behind ours
+    pub fn lags_behind(&self, local: &PeerStatus, max_periods: u64) -> bool {
+        self.best_slot.period.saturating_add(max_periods) < local.best_slot.period
+    }
+}
+
+fn latest(heads: &[(BlockId, u64)]) -> Option<(Slot, BlockId)> {
+    heads
+        .iter()
+        .enumerate()
+        .map(|(thread, (block_id, period))| (Slot::new(*period, thread as u8), *block_id))
+        .max_by_key(|(slot, _)| *slot)
+}
+
+/// Last status received from a peer, compared to ours at its reception
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReceivedPeerStatus {
+    pub status: PeerStatus,
+    pub received_at: MassaTime,
+    /// The peer finalized another block than ours
+    pub divergent: bool,
+    /// The head of the peer is far behind ours
+    pub lagging: bool,
+}
+
+impl ReceivedPeerStatus {
+    /// Whether the peer is expected to know the recent blocks
+    pub fn is_up_to_date(&self) -> bool {
+        !self.divergent && !self.lagging
+    }
+}
+
+#[derive(Clone)]
+pub struct PeerStatusSerializer {
+    slot_serializer: SlotSerializer,
+    block_id_serializer: BlockIdSerializer,
+}
+
+impl PeerStatusSerializer {
+    pub fn new() -> Self {
+        Self {
+            slot_serializer: SlotSerializer::new(),
+            block_id_serializer: BlockIdSerializer::new(),
+        }
+    }
+}
+
+impl Default for PeerStatusSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serializer<PeerStatus> for PeerStatusSerializer {
+    fn serialize(&self, value: &PeerStatus, buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        self.slot_serializer.serialize(&value.final_slot, buffer)?;
+        self.block_id_serializer
+            .serialize(&value.final_block, buffer)?;
+        self.slot_serializer.serialize(&value.best_slot, buffer)?;
+        self.block_id_serializer
+            .serialize(&value.best_block, buffer)?;
+        Ok(())
+    }
+}
+
+pub struct PeerStatusDeserializer {
+    slot_deserializer: SlotDeserializer,
+    block_id_deserializer: BlockIdDeserializer,
+}
+
+impl PeerStatusDeserializer {
+    /// The thread of the slots is checked against the thread count of the network on reception
+    pub fn new() -> Self {
+        Self {
+            slot_deserializer: SlotDeserializer::new(
+                (Included(0), Included(u64::MAX)),
+                (Included(0), Excluded(u8::MAX)),
+            ),
+            block_id_deserializer: BlockIdDeserializer::new(),
+        }
+    }
+}
+
+impl Deserializer<PeerStatus> for PeerStatusDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], PeerStatus, E> {
+        context(
+            "Failed peer status deserialization",
+            tuple((
+                context("Failed final slot deserialization", |buffer| {
+                    self.slot_deserializer.deserialize(buffer)
+                }),
+                context("Failed final block deserialization", |buffer| {
+                    self.block_id_deserializer.deserialize(buffer)
+                }),
+                context("Failed best slot deserialization", |buffer| {
+                    self.slot_deserializer.deserialize(buffer)
+                }),
+                context("Failed best block deserialization", |buffer| {
+                    self.block_id_deserializer.deserialize(buffer)
+                }),
+            )),
+        )
+        .map(
+            |(final_slot, final_block, best_slot, best_block)| PeerStatus {
+                final_slot,
+                final_block,
+                best_slot,
+                best_block,
+            },
+        )
+        .parse(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use massa_hash::Hash;
+    use massa_models::{block_id::BlockId, slot::Slot};
+    use massa_serialization::{DeserializeError, Deserializer, Serializer};
+
+    use super::{PeerStatus, PeerStatusDeserializer, PeerStatusSerializer};
+
+    fn block(seed: &str) -> BlockId {
+        BlockId(Hash::compute_from(seed.as_bytes()))
+    }
+
+    #[test]
+    fn test_peer_status() {
+        let local = PeerStatus::from_heads(
+            &[(block("f0"), 10), (block("f1"), 9)],
+            &[(block("b0"), 12), (block("b1"), 12)],
+        )
+        .unwrap();
+        assert_eq!(local.final_slot, Slot::new(10, 0));
+        assert_eq!(local.final_block, block("f0"));
+        assert_eq!(local.best_slot, Slot::new(12, 1));
+        assert!(local.is_valid(2));
+        assert!(!local.is_valid(1));
+
+        let mut buffer = Vec::new();
+        PeerStatusSerializer::new()
+            .serialize(&local, &mut buffer)
+            .unwrap();
+        let (rest, received) = PeerStatusDeserializer::new()
+            .deserialize::<DeserializeError>(&buffer)
+            .unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(received, local);
+
+        // another final block at the same slot is another fork
+        let fork = PeerStatus {
+            final_block: block("other"),
+            ..local
+        };
+        assert!(fork.diverges_from(&local));
+        assert!(!local.diverges_from(&local));
+        // a peer that finalized less isn't on another fork
+        let behind = PeerStatus {
+            final_slot: Slot::new(8, 0),
+            final_block: block("other"),
+            best_slot: Slot::new(9, 0),
+            ..local
+        };
+        assert!(!behind.diverges_from(&local));
+        assert!(behind.lags_behind(&local, 2));
+        assert!(!behind.lags_behind(&local, 3));
+    }
+}
@@ -3,29 +3,35 @@ use std::{
     io::Read,
     net::{IpAddr, SocketAddr},
     thread::JoinHandle,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+use crate::clock::SharedClock;
+use crate::dial::{DialKind, SharedDialScheduler};
 use crate::messages::MessagesHandler;
 use crossbeam::channel::{Receiver, Sender};
+use massa_hash::Hash;
 use massa_models::version::{Version, VersionDeserializer};
-use massa_protocol_exports::{PeerConnectionType, ProtocolConfig};
-use massa_serialization::{DeserializeError, Deserializer};
+use massa_protocol_exports::{
+    HandshakeStage, PeerConnectionType, PeerHandlerError, PeerTestResult, ProtocolConfig,
+};
 use massa_time::MassaTime;
 use peernet::{
-    error::{PeerNetError, PeerNetResult},
+    error::PeerNetResult,
     messages::MessagesHandler as PeerNetMessagesHandler,
     peer::InitConnectionHandler,
     peer_id::PeerId,
     transports::{endpoint::Endpoint, TransportType},
     types::KeyPair,
 };
-use std::cmp::Reverse;
 use tracing::info;
 
 use super::{
     announcement::{AnnouncementDeserializer, AnnouncementDeserializerArgs},
-    models::PeerInfo,
+    error::{network_error, IntoPeerNetResult, PeerHandlerResult},
+    hello::{deserialize_hello_content, deserialize_hello_peer_id, network_id, HelloContent},
+    messages::frame_content,
+    models::{PeerToTest, SharedOutConnectionTargets},
     SharedPeerDB,
 };
 use crate::wrap_network::ActiveConnectionsTrait;
@@ -34,6 +40,7 @@ use crate::wrap_network::ActiveConnectionsTrait;
 pub struct TesterHandshake {
     peer_db: SharedPeerDB,
     our_version: Version,
+    our_network_id: Hash,
     announcement_deserializer: AnnouncementDeserializer,
     version_deserializer: VersionDeserializer,
 }
@@ -49,6 +56,7 @@ impl TesterHandshake {
                 },
             ),
             our_version: config.version,
+            our_network_id: network_id(config.chain_id, config.genesis_timestamp),
             version_deserializer: VersionDeserializer::new(),
         }
     }
@@ -63,119 +71,17 @@ impl InitConnectionHandler for TesterHandshake {
         messages_handler: MassaMessagesHandler,
     ) -> PeerNetResult<PeerId> {
         let data = endpoint.receive()?;
-        if data.is_empty() {
-            return Err(PeerNetError::HandshakeError.error(
-                "Tester Handshake",
-                Some(String::from("Peer didn't accepted us")),
-            ));
-        }
-        let peer_id = PeerId::from_bytes(&data[..32].try_into().map_err(|_| {
-            PeerNetError::HandshakeError.error(
-                "Massa Handshake",
-                Some("Failed to deserialize PeerId".to_string()),
-            )
-        })?)?;
-        let res = {
-            {
-                // check if peer is banned
-                let mut peer_db_write = self.peer_db.write();
-                if let Some(info) = peer_db_write.peers.get_mut(&peer_id) {
-                    if info.state == super::PeerState::Banned {
-                        return Err(PeerNetError::HandshakeError
-                            .error("Tester Handshake", Some(String::from("Peer is banned"))));
-                    }
-                }
-            }
-
-            let (data, version) = self
-                .version_deserializer
-                .deserialize::<DeserializeError>(&data[32..])
-                .map_err(|err| {
-                    PeerNetError::HandshakeError.error(
-                        "Tester Handshake",
-                        Some(format!("Failed to deserialize version: {}", err)),
-                    )
-                })?;
-            if !self.our_version.is_compatible(&version) {
-                return Err(PeerNetError::HandshakeError.error(
-                    "Massa Handshake",
-                    Some(format!("Received version incompatible: {}", version)),
-                ));
-            }
-            let id = data.first().ok_or(
-                PeerNetError::HandshakeError
-                    .error("Massa Handshake", Some("Failed to get id".to_string())),
-            )?;
-            match id {
-                0 => {
-                    let (_, announcement) = self
-                        .announcement_deserializer
-                        .deserialize::<DeserializeError>(&data[1..])
-                        .map_err(|err| {
-                            PeerNetError::HandshakeError.error(
-                                "Tester Handshake",
-                                Some(format!("Failed to deserialize announcement: {}", err)),
-                            )
-                        })?;
-
-                    if peer_id
-                        .verify_signature(&announcement.hash, &announcement.signature)
-                        .is_err()
-                    {
-                        return Err(PeerNetError::HandshakeError
-                            .error("Tester Handshake", Some(String::from("Invalid signature"))));
-                    }
-                    //TODO: Check ip we are connected match one of the announced ips
-                    {
-                        let mut peer_db_write = self.peer_db.write();
-                        //TODO: Hacky change it when better management ip/listeners
-                        if !announcement.listeners.is_empty() {
-                            peer_db_write
-                                .index_by_newest
-                                .retain(|(_, peer_id_stored)| peer_id_stored != &peer_id);
-                            peer_db_write
-                                .index_by_newest
-                                .insert((Reverse(announcement.timestamp), peer_id.clone()));
-                        }
-                        peer_db_write
-                            .peers
-                            .entry(peer_id.clone())
-                            .and_modify(|info| {
-                                if info.last_announce.timestamp < announcement.timestamp {
-                                    info.last_announce = announcement.clone();
-                                }
-                                info.state = super::PeerState::Trusted;
-                            })
-                            .or_insert(PeerInfo {
-                                last_announce: announcement,
-                                state: super::PeerState::Trusted,
-                            });
-                    }
-                    Ok(peer_id.clone())
-                }
-                1 => {
-                    let (received, id) = messages_handler.deserialize_id(&data[1..], &peer_id)?;
-                    messages_handler.handle(id, received, &peer_id)?;
-                    Err(PeerNetError::HandshakeError.error(
-                        "Massa Handshake",
-                        Some("Tester Handshake failed received a message that our connection has been refused".to_string()),
-                    ))
-                    //TODO: Add the peerdb but for now impossible as we don't have announcement and we need one to place in peerdb
-                }
-                _ => Err(PeerNetError::HandshakeError
-                    .error("Massa handshake", Some("Invalid id".to_string()))),
-            }
-        };
-
-        // if handshake failed, we set the peer state to HandshakeFailed
-        if res.is_err() {
-            let mut peer_db_write = self.peer_db.write();
-            peer_db_write.peers.entry(peer_id).and_modify(|info| {
-                info.state = super::PeerState::HandshakeFailed;
-            });
-        }
+        let res = Tester::check_hello(
+            &data,
+            &messages_handler,
+            &self.peer_db,
+            &self.announcement_deserializer,
+            &self.version_deserializer,
+            &self.our_version,
+            &self.our_network_id,
+        );
         endpoint.shutdown();
-        res
+        res.into_peernet("Tester Handshake")
     }
 
     fn fallback_function(
@@ -200,13 +106,13 @@ impl Tester {
         active_connections: Box<dyn ActiveConnectionsTrait>,
         peer_db: SharedPeerDB,
         messages_handler: MessagesHandler,
-        target_out_connections: HashMap<String, (Vec<IpAddr>, usize)>,
-        default_target_out_connections: usize,
+        category_ips: HashMap<String, Vec<IpAddr>>,
+        out_connection_targets: SharedOutConnectionTargets,
+        dial_scheduler: SharedDialScheduler,
+        clock: SharedClock,
     ) -> (
-        (
-            Sender<(PeerId, HashMap<SocketAddr, TransportType>)>,
-            Receiver<(PeerId, HashMap<SocketAddr, TransportType>)>,
-        ),
+        (Sender<PeerToTest>, Receiver<PeerToTest>),
+        Sender<(SocketAddr, Sender<PeerTestResult>)>,
         Vec<Tester>,
     ) {
         let mut testers = Vec::new();
@@ -214,6 +120,9 @@ impl Tester {
         // create shared channel between thread for launching test
         let (test_sender, test_receiver) =
             crossbeam::channel::bounded(config.max_size_channel_commands_peer_testers);
+        // tests requested by an operator, handled before the others
+        let (forced_test_sender, forced_test_receiver) =
+            crossbeam::channel::bounded(config.max_size_channel_commands_peer_testers);
 
         for _ in 0..config.thread_tester_count {
             testers.push(Tester::new(
@@ -221,13 +130,31 @@ impl Tester {
                 active_connections.clone(),
                 config.clone(),
                 test_receiver.clone(),
+                forced_test_receiver.clone(),
                 messages_handler.clone(),
-                target_out_connections.clone(),
-                default_target_out_connections,
+                category_ips.clone(),
+                out_connection_targets.clone(),
+                dial_scheduler.clone(),
+                clock.clone(),
             ));
         }
 
-        ((test_sender, test_receiver), testers)
+        ((test_sender, test_receiver), forced_test_sender, testers)
+    }
+
+    /// Move a tested address between the new and tried tables. A peer without a free slot
+    /// answered but didn't complete the handshake, and a test not run says nothing.
+    fn record_test_outcome(
+        peer_db: &SharedPeerDB,
+        addr: SocketAddr,
+        res: &Option<PeerHandlerResult<PeerId>>,
+        now: MassaTime,
+    ) {
+        match res {
+            Some(Ok(peer_id)) => peer_db.write().addresses.record_success(addr, peer_id, now),
+            Some(Err(PeerHandlerError::NoSlot)) | None => {}
+            Some(Err(_)) => peer_db.write().addresses.record_failure(addr, now),
+        }
     }
 
     pub fn tcp_handshake(
@@ -237,150 +164,180 @@ impl Tester {
         version_deserializer: VersionDeserializer,
         addr: SocketAddr,
         our_version: Version,
-    ) -> PeerNetResult<PeerId> {
-        let result = {
-            let mut socket =
-                std::net::TcpStream::connect_timeout(&addr, Duration::from_millis(500))
-                    .map_err(|e| PeerNetError::PeerConnectionError.new("connect", e, None))?;
-
-            // data.receive() from Endpoint
-            let mut len_bytes = vec![0u8; 4];
-            socket
-                .read_exact(&mut len_bytes)
-                .map_err(|err| PeerNetError::PeerConnectionError.new("recv len", err, None))?;
-
-            let res_size = u32::from_be_bytes(len_bytes.try_into().map_err(|err| {
-                PeerNetError::PeerConnectionError.error("recv len", Some(format!("{:?}", err)))
-            })?);
-            if res_size > 1048576000 {
-                return Err(PeerNetError::InvalidMessage
-                    .error("len too long", Some(format!("{:?}", res_size))));
+        our_network_id: Hash,
+    ) -> PeerHandlerResult<PeerId> {
+        let socket = std::net::TcpStream::connect_timeout(&addr, Duration::from_millis(500))
+            .map_err(network_error)?;
+        Tester::tcp_handshake_on_socket(
+            socket,
+            messages_handler,
+            peer_db,
+            announcement_deserializer,
+            version_deserializer,
+            our_version,
+            our_network_id,
+        )
+    }
+
+    /// Test an address on behalf of an operator.
+    /// Unlike the periodic tests there is no cooldown and local addresses are allowed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn test_address(
+        messages_handler: MessagesHandler,
+        peer_db: SharedPeerDB,
+        announcement_deserializer: AnnouncementDeserializer,
+        version_deserializer: VersionDeserializer,
+        addr: SocketAddr,
+        our_version: Version,
+        our_network_id: Hash,
+        timeout: Duration,
+    ) -> PeerTestResult {
+        let start = Instant::now();
+        let socket = match std::net::TcpStream::connect_timeout(&addr, timeout) {
+            Ok(socket) => socket,
+            Err(err) => {
+                return PeerTestResult {
+                    address: addr,
+                    reachable: false,
+                    handshake: Err(err.to_string()),
+                    latency: None,
+                }
             }
-            let mut data = vec![0u8; res_size as usize];
-            socket
-                .read_exact(&mut data)
-                .map_err(|err| PeerNetError::PeerConnectionError.new("recv data", err, None))?;
-
-            // handshake
-            if data.is_empty() {
-                return Err(PeerNetError::HandshakeError.error(
-                    "Tester Handshake",
-                    Some(String::from("Peer didn't accepted us")),
-                ));
+        };
+        // the operator is waiting for the answer: don't let a silent peer block the tester
+        if let Err(err) = socket.set_read_timeout(Some(timeout)) {
+            tracing::log::warn!("Failed to set read timeout on socket: {}", err);
+        }
+        let handshake = Tester::tcp_handshake_on_socket(
+            socket,
+            messages_handler,
+            peer_db,
+            announcement_deserializer,
+            version_deserializer,
+            our_version,
+            our_network_id,
+        )
+        .map_err(|err| err.to_string());
+        PeerTestResult {
+            address: addr,
+            reachable: true,
+            handshake,
+            latency: Some(MassaTime::from_millis(start.elapsed().as_millis() as u64)),
+        }
+    }
+
+    fn tcp_handshake_on_socket(
+        mut socket: std::net::TcpStream,
+        messages_handler: MessagesHandler,
+        peer_db: SharedPeerDB,
+        announcement_deserializer: AnnouncementDeserializer,
+        version_deserializer: VersionDeserializer,
+        our_version: Version,
+        our_network_id: Hash,
+    ) -> PeerHandlerResult<PeerId> {
+        // data.receive() from Endpoint
+        let mut len_bytes = vec![0u8; 4];
+        socket.read_exact(&mut len_bytes).map_err(network_error)?;
+
+        let res_size = u32::from_be_bytes(len_bytes.try_into().map_err(network_error)?);
+        if res_size > 1048576000 {
+            return Err(PeerHandlerError::InvalidMessage {
+                stage: HandshakeStage::Hello,
+                reason: format!("len too long: {}", res_size),
+            });
+        }
+        let mut data = vec![0u8; res_size as usize];
+        socket.read_exact(&mut data).map_err(network_error)?;
+
+        let res = Tester::check_hello(
+            &data,
+            &messages_handler,
+            &peer_db,
+            &announcement_deserializer,
+            &version_deserializer,
+            &our_version,
+            &our_network_id,
+        );
+        if let Err(e) = socket.shutdown(std::net::Shutdown::Both) {
+            tracing::log::error!("Failed to shutdown socket: {}", e);
+        }
+        res
+    }
+
+    /// Checks the hello received from a tested peer and records its announcement
+    fn check_hello<MassaMessagesHandler: PeerNetMessagesHandler>(
+        data: &[u8],
+        messages_handler: &MassaMessagesHandler,
+        peer_db: &SharedPeerDB,
+        announcement_deserializer: &AnnouncementDeserializer,
+        version_deserializer: &VersionDeserializer,
+        our_version: &Version,
+        our_network_id: &Hash,
+    ) -> PeerHandlerResult<PeerId> {
+        if data.is_empty() {
+            return Err(PeerHandlerError::NoSlot);
+        }
+        let (peer_id, data) = deserialize_hello_peer_id(data)?;
+        // check if peer is banned
+        if let Some(info) = peer_db.snapshot().peers.get(&peer_id) {
+            if info.state == super::PeerState::Banned {
+                return Err(PeerHandlerError::PeerBanned);
             }
-            let peer_id = PeerId::from_bytes(&data[..32].try_into().map_err(|_| {
-                PeerNetError::HandshakeError.error(
-                    "Massa Handshake",
-                    Some("Failed to deserialize PeerId".to_string()),
-                )
-            })?)?;
-            let res = {
-                {
-                    // check if peer is banned
-                    let peer_db_read = peer_db.read();
-                    if let Some(info) = peer_db_read.peers.get(&peer_id) {
-                        if info.state == super::PeerState::Banned {
-                            return Err(PeerNetError::HandshakeError
-                                .error("Tester Handshake", Some(String::from("Peer is banned"))));
-                        }
-                    }
-                }
+        }
 
-                let (data, version) = version_deserializer
-                    .deserialize::<DeserializeError>(&data[32..])
-                    .map_err(|err| {
-                        PeerNetError::HandshakeError.error(
-                            "Tester Handshake",
-                            Some(format!("Failed to deserialize version: {}", err)),
-                        )
-                    })?;
-                if !our_version.is_compatible(&version) {
-                    return Err(PeerNetError::HandshakeError.error(
-                        "Massa Handshake",
-                        Some(format!("Received version incompatible: {}", version)),
-                    ));
-                }
-                let id = data.first().ok_or(
-                    PeerNetError::HandshakeError
-                        .error("Massa Handshake", Some("Failed to get id".to_string())),
-                )?;
-                match id {
-                    0 => {
-                        let (_, announcement) = announcement_deserializer
-                            .deserialize::<DeserializeError>(&data[1..])
-                            .map_err(|err| {
-                                PeerNetError::HandshakeError.error(
-                                    "Tester Handshake",
-                                    Some(format!("Failed to deserialize announcement: {}", err)),
-                                )
-                            })?;
-
-                        if peer_id
-                            .verify_signature(&announcement.hash, &announcement.signature)
-                            .is_err()
-                        {
-                            return Err(PeerNetError::HandshakeError.error(
-                                "Tester Handshake",
-                                Some(String::from("Invalid signature")),
-                            ));
-                        }
-                        //TODO: Check ip we are connected match one of the announced ips
-                        {
-                            let mut peer_db_write = peer_db.write();
-                            //TODO: Hacky change it when better management ip/listeners
-                            if !announcement.listeners.is_empty() {
-                                peer_db_write
-                                    .index_by_newest
-                                    .retain(|(_, peer_id_stored)| peer_id_stored != &peer_id);
-                                peer_db_write
-                                    .index_by_newest
-                                    .insert((Reverse(announcement.timestamp), peer_id.clone()));
-                            }
-                            peer_db_write
-                                .peers
-                                .entry(peer_id.clone())
-                                .and_modify(|info| {
-                                    if info.last_announce.timestamp < announcement.timestamp {
-                                        info.last_announce = announcement.clone();
-                                    }
-                                    info.state = super::PeerState::Trusted;
-                                })
-                                .or_insert(PeerInfo {
-                                    last_announce: announcement,
-                                    state: super::PeerState::Trusted,
-                                });
-                        }
-                        Ok(peer_id.clone())
-                    }
-                    1 => {
-                        let (received, id) =
-                            messages_handler.deserialize_id(&data[1..], &peer_id)?;
-                        messages_handler.handle(id, received, &peer_id)?;
-                        Err(PeerNetError::HandshakeError.error(
-                                "Massa Handshake",
-                                Some("Tester Handshake failed received a message that our connection has been refused".to_string()),
-                            ))
-                        //TODO: Add the peerdb but for now impossible as we don't have announcement and we need one to place in peerdb
-                    }
-                    _ => Err(PeerNetError::HandshakeError
-                        .error("Massa handshake", Some("Invalid id".to_string()))),
+        let res = deserialize_hello_content(
+            data,
+            our_version,
+            our_network_id,
+            version_deserializer,
+            announcement_deserializer,
+        )
+        .and_then(|(_version, content)| match content {
+            HelloContent::Announcement(announcement, _) => {
+                if peer_id
+                    .verify_signature(&announcement.hash, &announcement.signature)
+                    .is_err()
+                {
+                    return Err(PeerHandlerError::InvalidSignature(HandshakeStage::Hello));
                 }
-            };
-
-            // if handshake failed, we set the peer state to HandshakeFailed
-            if res.is_err() {
-                let mut peer_db_write = peer_db.write();
-                peer_db_write.peers.entry(peer_id).and_modify(|info| {
-                    info.state = super::PeerState::HandshakeFailed;
-                });
+                //TODO: Check ip we are connected match one of the announced ips
+                peer_db
+                    .write()
+                    .set_peer_announcement(&peer_id, announcement);
+                Ok(peer_id.clone())
             }
-            if let Err(e) = socket.shutdown(std::net::Shutdown::Both) {
-                tracing::log::error!("Failed to shutdown socket: {}", e);
+            HelloContent::Message(data) => {
+                // the peer refused our connection and sent us some peers instead
+                let (received, id) =
+                    messages_handler
+                        .deserialize_id(data, &peer_id)
+                        .map_err(|err| PeerHandlerError::InvalidMessage {
+                            stage: HandshakeStage::Hello,
+                            reason: format!("{:?}", err),
+                        })?;
+                // the message of a refusal isn't framed, unlike the ones of a connection
+                let framed;
+                let received = if peer_db.traffic().framed(&peer_id) {
+                    framed = frame_content(received);
+                    framed.as_slice()
+                } else {
+                    received
+                };
+                messages_handler
+                    .handle(id, received, &peer_id)
+                    .map_err(|err| PeerHandlerError::ChannelError(format!("{:?}", err)))?;
+                //TODO: Add the peerdb but for now impossible as we don't have announcement and we need one to place in peerdb
+                Err(PeerHandlerError::NoSlot)
             }
-            res
-        };
+        });
 
-        result
+        // if handshake failed, we set the peer state to HandshakeFailed
+        if res.is_err() {
+            peer_db
+                .write()
+                .set_peer_handshake_state(&peer_id, super::PeerState::HandshakeFailed);
+        }
+        res
     }
 
     /// Create a new tester (spawn a thread)
@@ -388,10 +345,13 @@ impl Tester {
         peer_db: SharedPeerDB,
         active_connections: Box<dyn ActiveConnectionsTrait>,
         protocol_config: ProtocolConfig,
-        receiver: crossbeam::channel::Receiver<(PeerId, HashMap<SocketAddr, TransportType>)>,
+        receiver: crossbeam::channel::Receiver<PeerToTest>,
+        forced_receiver: crossbeam::channel::Receiver<(SocketAddr, Sender<PeerTestResult>)>,
         messages_handler: MessagesHandler,
-        target_out_connections: HashMap<String, (Vec<IpAddr>, usize)>,
-        default_target_out_connections: usize,
+        category_ips: HashMap<String, Vec<IpAddr>>,
+        out_connection_targets: SharedOutConnectionTargets,
+        dial_scheduler: SharedDialScheduler,
+        clock: SharedClock,
     ) -> Self {
         tracing::log::debug!("running new tester");
 
@@ -410,8 +370,40 @@ impl Tester {
 
             //let mut network_manager = PeerNetManager::new(config);
             let protocol_config = protocol_config.clone();
+            let run_forced_test = |addr: SocketAddr, responder: Sender<PeerTestResult>| {
+                info!("testing addr {} on request", &addr);
+                let res = dial_scheduler.dial(addr, DialKind::Operator, || Tester::test_address(
+                    messages_handler.clone(),
+                    db.clone(),
+                    announcement_deser.clone(),
+                    VersionDeserializer::new(),
+                    addr,
+                    protocol_config.version,
+                    network_id(protocol_config.chain_id, protocol_config.genesis_timestamp),
+                    protocol_config.transports.tcp.connection_timeout.to_duration(),
+                )).unwrap_or_else(|| PeerTestResult {
+                    address: addr,
+                    reachable: false,
+                    handshake: Err("Too many dials in progress".to_string()),
+                    latency: None,
+                });
+                if let Err(e) = responder.send(res) {
+                    tracing::log::debug!("error sending test result: {}", e);
+                }
+            };
             loop {
+                // requested tests go first
+                if let Ok((addr, responder)) = forced_receiver.try_recv() {
+                    run_forced_test(addr, responder);
+                    continue;
+                }
                 crossbeam::select! {
+                    recv(forced_receiver) -> res => {
+                        match res {
+                            Ok((addr, responder)) => run_forced_test(addr, responder),
+                            Err(_e) => break,
+                        }
+                    }
                     recv(receiver) -> res => {
                         match res {
                             Ok(listener) => {
@@ -420,10 +412,13 @@ impl Tester {
                                 }
                                 //Test
                                 let peers_connected = active_connections.get_peers_connected();
-                                let slots_out_connections: HashMap<String, (Vec<IpAddr>, usize)> = target_out_connections
+                                // targets can be changed at runtime
+                                let targets = out_connection_targets.read().clone();
+                                let slots_out_connections: HashMap<String, (Vec<IpAddr>, usize)> = category_ips
                                     .iter()
-                                    .map(|(key, value)| {
-                                        let mut value = value.clone();
+                                    .map(|(key, ips)| {
+                                        let target = targets.categories.get(key).copied().unwrap_or_default();
+                                        let mut value = (ips.clone(), target);
                                         value.1 = value.1.saturating_sub(peers_connected.iter().filter(|(_, (_, ty, category))| {
                                             if ty == &PeerConnectionType::IN {
                                                 return false;
@@ -437,7 +432,7 @@ impl Tester {
                                         (key.clone(), value)
                                     })
                                     .collect();
-                                let slot_default_category = default_target_out_connections.saturating_sub(peers_connected.iter().filter(|(_, (_, ty, category))| {
+                                let slot_default_category = targets.default.saturating_sub(peers_connected.iter().filter(|(_, (_, ty, category))| {
                                     if ty == &PeerConnectionType::IN {
                                         return false;
                                     }
@@ -447,7 +442,7 @@ impl Tester {
                                     true
                                 }).count());
                                 {
-                                    let now = MassaTime::now().unwrap();
+                                    let now = clock.now();
                                     let db = db.clone();
                                     // receive new listener to test
                                     for (addr, _) in listener.1.iter() {
@@ -457,28 +452,27 @@ impl Tester {
                                             for category in &slots_out_connections {
                                                 if category.1.0.contains(&ip_canonical) {
                                                     if category.1.1 == 0 {
-                                                        break 'cooldown Duration::from_secs(60 * 60 * 2);
+                                                        break 'cooldown MassaTime::from_millis(60 * 60 * 2 * 1000);
                                                     } else {
-                                                        break 'cooldown Duration::from_secs(30);
+                                                        break 'cooldown MassaTime::from_millis(30 * 1000);
                                                     }
                                                 }
                                             }
                                             if slot_default_category == 0 {
-                                                Duration::from_secs(60 * 60 * 2)
+                                                MassaTime::from_millis(60 * 60 * 2 * 1000)
                                             } else {
-                                                Duration::from_secs(30)
+                                                MassaTime::from_millis(30 * 1000)
                                             }
                                         };
                                         //TODO: Change it to manage multiple listeners SAFETY: Check above
                                         {
                                             let mut db_write = db.write();
-                                            if let Some(last_tested_time) = db_write.tested_addresses.get(addr) {
-                                                let last_tested_time = last_tested_time.estimate_instant().expect("Time went backward");
-                                                if last_tested_time.elapsed() < cooldown {
+                                            if let Some(last_attempt) = db_write.addresses.last_attempt(addr) {
+                                                if now.saturating_sub(last_attempt) < cooldown {
                                                     continue;
                                                 }
                                             }
-                                            db_write.tested_addresses.insert(*addr, now);
+                                            db_write.addresses.record_attempt(*addr, now);
                                         }
                                         // TODO:  Don't launch test if peer is already connected to us as a normal connection.
                                         // Maybe we need to have a way to still update his last announce timestamp because he is a great peer
@@ -498,14 +492,20 @@ impl Tester {
                                             info!("testing peer {} listener addr: {}", &listener.0, &addr);
 
 
-                                            let res = Tester::tcp_handshake(
+                                            let res = dial_scheduler.dial(*addr, DialKind::Test, || Tester::tcp_handshake(
                                                 messages_handler.clone(),
                                                 db.clone(),
                                                 announcement_deser.clone(),
                                                 VersionDeserializer::new(),
                                                 *addr,
                                                 protocol_config.version,
-                                            );
+                                                network_id(protocol_config.chain_id, protocol_config.genesis_timestamp),
+                                            ));
+                                            Tester::record_test_outcome(&db, *addr, &res, clock.now());
+                                            // the peer inherits the direction of the peer that gossiped it
+                                            if let Some(Ok(tested_peer_id)) = &res {
+                                                db.write().record_peer_source(tested_peer_id, listener.2);
+                                            }
 
                                             // let _res =  network_manager.try_connect(
                                             //     *addr,
@@ -524,14 +524,11 @@ impl Tester {
                     default(Duration::from_secs(2)) => {
                         // If no message in 2 seconds they will test a peer that hasn't been tested for long time
 
-                        let Some(listener) = db.read().get_oldest_peer(Duration::from_secs(60 * 60 * 2)) else {
+                        let now = clock.now();
+                        let Some(listener) = db.read().addresses.select_to_retest(MassaTime::from_millis(60 * 60 * 2 * 1000), now, &mut rand::thread_rng()) else {
                             continue;
                         };
-
-                        {
-                            let mut db = db.write();
-                            db.tested_addresses.insert(listener, MassaTime::now().unwrap());
-                        }
+                        db.write().addresses.record_attempt(listener, now);
 
                         // we try to connect to all peer listener (For now we have only one listener)
                         let ip_canonical = listener.ip().to_canonical();
@@ -552,14 +549,16 @@ impl Tester {
                         }
                         info!("testing listener addr: {}", &listener);
 
-                        let res = Tester::tcp_handshake(
+                        let res = dial_scheduler.dial(listener, DialKind::Test, || Tester::tcp_handshake(
                             messages_handler.clone(),
                             db.clone(),
                             announcement_deser.clone(),
                             VersionDeserializer::new(),
                             listener,
                             protocol_config.version,
-                        );
+                            network_id(protocol_config.chain_id, protocol_config.genesis_timestamp),
+                        ));
+                        Tester::record_test_outcome(&db, listener, &res, clock.now());
                         // let res =  network_manager.try_connect(
                         //     listener,
                         //     protocol_config.timeout_connection.to_duration(),
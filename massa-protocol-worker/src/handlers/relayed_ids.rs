@@ -0,0 +1,297 @@
+//! Ids of the blocks, operations and endorsements recently relayed to the peers.
+//!
+//! The handlers check it before relaying an item, so that a node restarted shortly after a
+//! crash, or a restarted handler, doesn't flood its peers again with items it already spread.
+//! The cache is bounded in size and in age. When a file is configured, the ids of the last
+//! `relayed_ids_retention` are saved to it at a regular interval and loaded at startup.
+//!
+//! The file starts with `RELAYED_IDS_MAGIC` and the format version, followed by the ids:
+//! kind (u8), relay time (u64, milliseconds, big endian) and hash of the id.
+
+use std::{
+    fs::File,
+    hash::Hash as StdHash,
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    num::NonZeroUsize,
+    path::Path,
+    sync::Arc,
+};
+
+use lru::LruCache;
+use massa_hash::{Hash, HASH_SIZE_BYTES};
+use massa_models::{
+    block_id::BlockId, endorsement::EndorsementId, operation::OperationId, secure_share::Id,
+};
+use massa_protocol_exports::ProtocolError;
+use massa_time::MassaTime;
+use parking_lot::RwLock;
+
+use crate::clock::SharedClock;
+
+/// Start of the relayed ids files
+const RELAYED_IDS_MAGIC: &[u8; 12] = b"MASSARELAYED";
+
+/// Version of the format of the relayed ids files
+const RELAYED_IDS_VERSION: u8 = 0;
+
+const BLOCK_KIND: u8 = 0;
+const OPERATION_KIND: u8 = 1;
+const ENDORSEMENT_KIND: u8 = 2;
+
+/// Ids of one kind of items with the time they were last relayed
+pub struct RelayedIds<I: StdHash + Eq> {
+    ids: LruCache<I, MassaTime>,
+}
+
+impl<I: StdHash + Eq + Id + Copy> RelayedIds<I> {
+    fn new(max_ids: NonZeroUsize) -> Self {
+        RelayedIds {
+            ids: LruCache::new(max_ids),
+        }
+    }
+
+    /// Whether the item was relayed after `since`
+    fn relayed_since(&self, id: &I, since: MassaTime) -> bool {
+        self.ids
+            .peek(id)
+            .map_or(false, |relayed_at| *relayed_at >= since)
+    }
+
+    fn record(&mut self, id: I, now: MassaTime) {
+        self.ids.put(id, now);
+    }
+
+    /// Ids relayed after `since`, from the oldest to the most recent
+    fn iter_since(&self, since: MassaTime) -> impl Iterator<Item = (&I, &MassaTime)> {
+        self.ids
+            .iter()
+            .rev()
+            .filter(move |(_, relayed_at)| **relayed_at >= since)
+    }
+}
+
+/// Recently relayed ids, shared by the handlers
+pub struct RelayedIdsCache {
+    blocks: RelayedIds<BlockId>,
+    operations: RelayedIds<OperationId>,
+    endorsements: RelayedIds<EndorsementId>,
+    /// Time after which a relayed item can be relayed again, 0 to never skip an item
+    retention: MassaTime,
+    clock: SharedClock,
+}
+
+pub type SharedRelayedIdsCache = Arc<RwLock<RelayedIdsCache>>;
+
+impl RelayedIdsCache {
+    /// Cache of at most `max_ids` ids of each kind, kept for `retention`
+    pub fn new(max_ids: NonZeroUsize, retention: MassaTime, clock: SharedClock) -> Self {
+        RelayedIdsCache {
+            blocks: RelayedIds::new(max_ids),
+            operations: RelayedIds::new(max_ids),
+            endorsements: RelayedIds::new(max_ids),
+            retention,
+            clock,
+        }
+    }
+
+    fn retention_start(&self) -> MassaTime {
+        self.clock.now().saturating_sub(self.retention)
+    }
+
+    fn enabled(&self) -> bool {
+        self.retention > MassaTime::from_millis(0)
+    }
+
+    /// Whether the block was relayed within the retention
+    pub fn block_relayed(&self, block_id: &BlockId) -> bool {
+        self.enabled() && self.blocks.relayed_since(block_id, self.retention_start())
+    }
+
+    /// Whether the operation was relayed within the retention
+    pub fn operation_relayed(&self, operation_id: &OperationId) -> bool {
+        self.enabled()
+            && self
+                .operations
+                .relayed_since(operation_id, self.retention_start())
+    }
+
+    /// Whether the endorsement was relayed within the retention
+    pub fn endorsement_relayed(&self, endorsement_id: &EndorsementId) -> bool {
+        self.enabled()
+            && self
+                .endorsements
+                .relayed_since(endorsement_id, self.retention_start())
+    }
+
+    pub fn record_block(&mut self, block_id: BlockId) {
+        let now = self.clock.now();
+        self.blocks.record(block_id, now);
+    }
+
+    pub fn record_operations(&mut self, operation_ids: impl IntoIterator<Item = OperationId>) {
+        let now = self.clock.now();
+        for operation_id in operation_ids {
+            self.operations.record(operation_id, now);
+        }
+    }
+
+    pub fn record_endorsements(
+        &mut self,
+        endorsement_ids: impl IntoIterator<Item = EndorsementId>,
+    ) {
+        let now = self.clock.now();
+        for endorsement_id in endorsement_ids {
+            self.endorsements.record(endorsement_id, now);
+        }
+    }
+
+    /// Save the ids relayed within the retention to `path`, replacing its content at once
+    pub fn save(&self, path: &Path) -> Result<(), ProtocolError> {
+        let since = self.retention_start();
+        let tmp_path = path.with_extension("tmp");
+        {
+            let mut writer = BufWriter::new(File::create(&tmp_path)?);
+            writer.write_all(RELAYED_IDS_MAGIC)?;
+            writer.write_all(&[RELAYED_IDS_VERSION])?;
+            write_ids(&mut writer, BLOCK_KIND, &self.blocks, since)?;
+            write_ids(&mut writer, OPERATION_KIND, &self.operations, since)?;
+            write_ids(&mut writer, ENDORSEMENT_KIND, &self.endorsements, since)?;
+            writer.flush()?;
+        }
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Load the ids saved to `path` that are still within the retention.
+    /// A truncated file is read up to its last complete id.
+    pub fn load(&mut self, path: &Path) -> Result<(), ProtocolError> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut header = [0u8; RELAYED_IDS_MAGIC.len() + 1];
+        if !read_exact_or_end(&mut reader, &mut header)?
+            || &header[..RELAYED_IDS_MAGIC.len()] != RELAYED_IDS_MAGIC
+        {
+            return Err(ProtocolError::GeneralProtocolError(format!(
+                "{} is not a relayed ids file",
+                path.display()
+            )));
+        }
+        if header[RELAYED_IDS_MAGIC.len()] != RELAYED_IDS_VERSION {
+            return Err(ProtocolError::GeneralProtocolError(format!(
+                "unsupported version {} of the relayed ids file",
+                header[RELAYED_IDS_MAGIC.len()]
+            )));
+        }
+        let since = self.retention_start();
+        let mut entry = [0u8; 1 + 8 + HASH_SIZE_BYTES];
+        while read_exact_or_end(&mut reader, &mut entry)? {
+            let relayed_at =
+                MassaTime::from_millis(u64::from_be_bytes(entry[1..9].try_into().unwrap()));
+            if relayed_at < since {
+                continue;
+            }
+            let hash = Hash::from_bytes(entry[9..].try_into().unwrap());
+            match entry[0] {
+                BLOCK_KIND => self.blocks.record(BlockId::new(hash), relayed_at),
+                OPERATION_KIND => self.operations.record(OperationId::new(hash), relayed_at),
+                ENDORSEMENT_KIND => self
+                    .endorsements
+                    .record(EndorsementId::new(hash), relayed_at),
+                kind => {
+                    return Err(ProtocolError::GeneralProtocolError(format!(
+                        "unknown kind {} in the relayed ids file",
+                        kind
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_ids<I: StdHash + Eq + Id + Copy, W: Write>(
+    writer: &mut W,
+    kind: u8,
+    ids: &RelayedIds<I>,
+    since: MassaTime,
+) -> Result<(), ProtocolError> {
+    for (id, relayed_at) in ids.iter_since(since) {
+        writer.write_all(&[kind])?;
+        writer.write_all(&relayed_at.to_millis().to_be_bytes())?;
+        writer.write_all(id.get_hash().to_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_exact_or_end<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<bool, ProtocolError> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, num::NonZeroUsize, time::Duration};
+
+    use massa_hash::Hash;
+    use massa_models::{
+        block_id::BlockId, endorsement::EndorsementId, operation::OperationId, secure_share::Id,
+    };
+    use massa_time::MassaTime;
+
+    use super::RelayedIdsCache;
+    use crate::clock::ManualClock;
+
+    #[test]
+    fn test_relayed_ids_retention_and_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("relayed_ids");
+        let clock = ManualClock::new(MassaTime::from_millis(100_000));
+        let block_id = BlockId(Hash::compute_from(b"block"));
+        let old_operation_id = OperationId::new(Hash::compute_from(b"old operation"));
+        let operation_id = OperationId::new(Hash::compute_from(b"operation"));
+        let endorsement_id = EndorsementId::new(Hash::compute_from(b"endorsement"));
+
+        let mut cache = RelayedIdsCache::new(
+            NonZeroUsize::new(10).unwrap(),
+            MassaTime::from_millis(60_000),
+            clock.clone(),
+        );
+        cache.record_operations([old_operation_id]);
+        clock.advance(Duration::from_millis(30_000));
+        cache.record_block(block_id);
+        cache.record_operations([operation_id]);
+        cache.record_endorsements([endorsement_id]);
+        assert!(cache.block_relayed(&block_id));
+        assert!(cache.operation_relayed(&old_operation_id));
+        assert!(!cache.endorsement_relayed(&EndorsementId::new(Hash::compute_from(b"other"))));
+
+        // an item can be relayed again after the retention
+        clock.advance(Duration::from_millis(40_000));
+        assert!(!cache.operation_relayed(&old_operation_id));
+        assert!(cache.operation_relayed(&operation_id));
+
+        // the ids still within the retention survive a restart, even after a crash while saving
+        cache.save(&path).unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(&[1, 2, 3])
+            .unwrap();
+        let mut restarted = RelayedIdsCache::new(
+            NonZeroUsize::new(10).unwrap(),
+            MassaTime::from_millis(60_000),
+            clock.clone(),
+        );
+        restarted.load(&path).unwrap();
+        assert!(restarted.block_relayed(&block_id));
+        assert!(restarted.operation_relayed(&operation_id));
+        assert!(restarted.endorsement_relayed(&endorsement_id));
+        assert!(!restarted.operation_relayed(&old_operation_id));
+
+        std::fs::write(&path, b"not relayed ids").unwrap();
+        assert!(restarted.load(&path).is_err());
+    }
+}
@@ -0,0 +1,349 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    num::NonZeroUsize,
+    time::{Duration, Instant},
+};
+
+use lru::LruCache;
+use peernet::peer_id::PeerId;
+
+/// Id given to each request sent to a peer.
+///
+/// Ids are local: they are not sent on the wire, the answers are matched with the requests by
+/// the items they carry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RequestId(pub u64);
+
+/// An item that a peer didn't send before the deadline of the request asking it
+#[derive(Clone, Debug)]
+pub struct ExpiredRequest<K> {
+    pub request_id: RequestId,
+    pub key: K,
+    pub peer_id: PeerId,
+    /// false if the item was asked `max_attempts` times already
+    pub can_retry: bool,
+}
+
+/// Asks of an item, kept until the item is received or forgotten
+struct TrackedItem {
+    /// peers the item was asked to, in order
+    asked_peers: Vec<PeerId>,
+    /// time of the last ask
+    last_sent: Instant,
+    /// asks waiting for an answer, with their deadline
+    in_flight: Vec<(PeerId, RequestId, Instant)>,
+}
+
+/// A request sent to a peer, that can ask several items
+struct RequestState {
+    peer_id: PeerId,
+    /// items of the request still waiting for an answer
+    pending: usize,
+    /// whether at least one item was answered
+    answered: bool,
+    /// whether at least one item timed out
+    expired: bool,
+}
+
+/// Correlates the requests we send to peers (block infos, operations...) with their answers.
+///
+/// Each request gets an id and a deadline. The items that are not received before the
+/// deadline are returned by `expire` so that they can be asked to another peer, up to
+/// `max_attempts` times. A request none of whose items were answered counts as unanswered for
+/// its peer, and the peers that leave `max_unanswered` requests in a row unanswered are
+/// returned by `take_unresponsive_peers`.
+pub struct RequestManager<K: Hash + Eq + Clone> {
+    next_id: u64,
+    timeout: Duration,
+    max_attempts: usize,
+    max_unanswered: u64,
+    items: LruCache<K, TrackedItem>,
+    requests: HashMap<RequestId, RequestState>,
+    deadlines: BTreeMap<(Instant, RequestId), Vec<K>>,
+    /// requests in a row left unanswered by each peer
+    unanswered: HashMap<PeerId, u64>,
+}
+
+impl<K: Hash + Eq + Clone> RequestManager<K> {
+    /// Creates a new `RequestManager`
+    ///
+    /// # Arguments
+    /// * `capacity`: maximum number of items tracked
+    /// * `timeout`: time given to a peer to answer a request
+    /// * `max_attempts`: maximum number of peers an item is asked to
+    /// * `max_unanswered`: number of requests in a row a peer can leave unanswered (0 for no limit)
+    pub fn new(
+        capacity: NonZeroUsize,
+        timeout: Duration,
+        max_attempts: usize,
+        max_unanswered: u64,
+    ) -> Self {
+        Self {
+            next_id: 0,
+            timeout,
+            max_attempts,
+            max_unanswered,
+            items: LruCache::new(capacity),
+            requests: HashMap::new(),
+            deadlines: BTreeMap::new(),
+            unanswered: HashMap::new(),
+        }
+    }
+
+    /// Changes the time given to a peer to answer the next requests
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Time of the last ask of the item, if it is tracked
+    pub fn last_sent(&self, key: &K) -> Option<Instant> {
+        self.items.peek(key).map(|item| item.last_sent)
+    }
+
+    /// Whether the item was already asked to the peer
+    pub fn was_asked_to(&self, key: &K, peer_id: &PeerId) -> bool {
+        self.items
+            .peek(key)
+            .map_or(false, |item| item.asked_peers.contains(peer_id))
+    }
+
+    /// Whether an ask of the item to the peer is waiting for an answer
+    pub fn is_in_flight_to(&self, key: &K, peer_id: &PeerId) -> bool {
+        self.items.peek(key).map_or(false, |item| {
+            item.in_flight.iter().any(|(peer, ..)| peer == peer_id)
+        })
+    }
+
+    /// Peers the item was asked to
+    pub fn asked_peers(&self, key: &K) -> &[PeerId] {
+        self.items
+            .peek(key)
+            .map_or(&[], |item| item.asked_peers.as_slice())
+    }
+
+    /// Registers a request sent to a peer for the given items and returns its id
+    pub fn send(
+        &mut self,
+        peer_id: &PeerId,
+        keys: impl IntoIterator<Item = K>,
+        now: Instant,
+    ) -> RequestId {
+        let request_id = RequestId(self.next_id);
+        self.next_id += 1;
+        let deadline = now + self.timeout;
+        let mut sent_keys = Vec::new();
+        for key in keys {
+            if !self.items.contains(&key) {
+                let new_item = TrackedItem {
+                    asked_peers: Vec::new(),
+                    last_sent: now,
+                    in_flight: Vec::new(),
+                };
+                if let Some((evicted_key, evicted)) = self.items.push(key.clone(), new_item) {
+                    if evicted_key != key {
+                        self.drop_in_flight(evicted.in_flight);
+                    }
+                }
+            }
+            let item = self
+                .items
+                .get_mut(&key)
+                .expect("critical: item was just inserted in the request manager");
+            item.last_sent = now;
+            if !item.asked_peers.contains(peer_id) {
+                item.asked_peers.push(peer_id.clone());
+            }
+            item.in_flight.push((peer_id.clone(), request_id, deadline));
+            sent_keys.push(key);
+        }
+        if !sent_keys.is_empty() {
+            self.requests.insert(
+                request_id,
+                RequestState {
+                    peer_id: peer_id.clone(),
+                    pending: sent_keys.len(),
+                    answered: false,
+                    expired: false,
+                },
+            );
+            self.deadlines.insert((deadline, request_id), sent_keys);
+        }
+        request_id
+    }
+
+    /// Records that the peer answered about the item. The item stays tracked: use
+    /// `complete` when it doesn't need to be asked anymore.
+    pub fn on_answer(&mut self, peer_id: &PeerId, key: &K) {
+        let Some(item) = self.items.peek_mut(key) else {
+            return;
+        };
+        let (answered, in_flight): (Vec<_>, Vec<_>) = std::mem::take(&mut item.in_flight)
+            .into_iter()
+            .partition(|(peer, ..)| peer == peer_id);
+        item.in_flight = in_flight;
+        for (_, request_id, _) in answered {
+            if let Some(request) = self.requests.get_mut(&request_id) {
+                request.answered = true;
+            }
+            self.release(request_id);
+        }
+        self.unanswered.remove(peer_id);
+    }
+
+    /// Records that the item was received from the peer and stops tracking it
+    pub fn complete(&mut self, peer_id: &PeerId, key: &K) {
+        self.on_answer(peer_id, key);
+        self.forget(key);
+    }
+
+    /// Stops tracking an item, without counting its pending asks as unanswered
+    pub fn forget(&mut self, key: &K) {
+        if let Some(item) = self.items.pop(key) {
+            self.drop_in_flight(item.in_flight);
+        }
+    }
+
+    /// Forgets the pending asks to a peer, when it disconnects
+    pub fn remove_peer(&mut self, peer_id: &PeerId) {
+        let mut dropped = Vec::new();
+        for (_, item) in self.items.iter_mut() {
+            item.in_flight.retain(|(peer, request_id, _)| {
+                if peer == peer_id {
+                    dropped.push(*request_id);
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        for request_id in dropped {
+            self.release(request_id);
+        }
+        self.unanswered.remove(peer_id);
+    }
+
+    /// Deadline of the oldest pending request
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.deadlines.keys().next().map(|(deadline, _)| *deadline)
+    }
+
+    /// Returns the items whose ask timed out at `now`
+    pub fn expire(&mut self, now: Instant) -> Vec<ExpiredRequest<K>> {
+        let mut expired = Vec::new();
+        while let Some(entry) = self.deadlines.first_entry() {
+            if entry.key().0 > now {
+                break;
+            }
+            let ((_, request_id), keys) = entry.remove_entry();
+            for key in keys {
+                let Some(item) = self.items.peek_mut(&key) else {
+                    continue;
+                };
+                let Some(index) = item.in_flight.iter().position(|(_, id, _)| *id == request_id) else {
+                    // already answered or forgotten
+                    continue;
+                };
+                let (peer_id, _, _) = item.in_flight.swap_remove(index);
+                let can_retry = item.asked_peers.len() < self.max_attempts;
+                if let Some(request) = self.requests.get_mut(&request_id) {
+                    request.expired = true;
+                }
+                self.release(request_id);
+                expired.push(ExpiredRequest {
+                    request_id,
+                    key,
+                    peer_id,
+                    can_retry,
+                });
+            }
+        }
+        expired
+    }
+
+    /// Peers that left too many requests in a row unanswered. Their count is reset.
+    pub fn take_unresponsive_peers(&mut self) -> Vec<PeerId> {
+        if self.max_unanswered == 0 {
+            return Vec::new();
+        }
+        let peers: Vec<PeerId> = self
+            .unanswered
+            .iter()
+            .filter(|(_, count)| **count >= self.max_unanswered)
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect();
+        for peer_id in &peers {
+            self.unanswered.remove(peer_id);
+        }
+        peers
+    }
+
+    fn drop_in_flight(&mut self, in_flight: Vec<(PeerId, RequestId, Instant)>) {
+        for (_, request_id, _) in in_flight {
+            self.release(request_id);
+        }
+    }
+
+    /// One item of the request doesn't wait for an answer anymore
+    fn release(&mut self, request_id: RequestId) {
+        let Some(request) = self.requests.get_mut(&request_id) else {
+            return;
+        };
+        request.pending = request.pending.saturating_sub(1);
+        if request.pending > 0 {
+            return;
+        }
+        let request = self
+            .requests
+            .remove(&request_id)
+            .expect("critical: request was just found in the request manager");
+        if request.expired && !request.answered {
+            *self.unanswered.entry(request.peer_id).or_default() += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_signature::KeyPair;
+
+    fn peer() -> PeerId {
+        PeerId::from_bytes(KeyPair::generate().get_public_key().to_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_request_expiry_retry_and_penalty() {
+        let (peer_a, peer_b) = (peer(), peer());
+        let timeout = Duration::from_millis(100);
+        let mut manager: RequestManager<u64> =
+            RequestManager::new(NonZeroUsize::new(10).unwrap(), timeout, 2, 2);
+        let now = Instant::now();
+
+        // peer A answers one of the two items: the request counts as answered
+        manager.send(&peer_a, [1, 2], now);
+        assert_eq!(manager.next_deadline(), Some(now + timeout));
+        manager.complete(&peer_a, &1);
+        let expired = manager.expire(now + timeout);
+        assert_eq!(expired.len(), 1);
+        assert_eq!((expired[0].key, expired[0].can_retry), (2, true));
+        assert!(manager.take_unresponsive_peers().is_empty());
+
+        // the retry to peer B times out and the item can't be asked again
+        manager.send(&peer_b, [2], now + timeout);
+        assert_eq!(manager.asked_peers(&2), &[peer_a.clone(), peer_b.clone()]);
+        let expired = manager.expire(now + timeout * 2);
+        assert!(!expired[0].can_retry);
+
+        // a second unanswered request in a row penalizes peer B
+        manager.send(&peer_b, [3], now + timeout * 2);
+        manager.expire(now + timeout * 3);
+        assert_eq!(manager.take_unresponsive_peers(), vec![peer_b]);
+
+        // forgotten items are not counted as unanswered
+        manager.send(&peer_a, [4], now);
+        manager.forget(&4);
+        assert!(manager.expire(now + timeout * 4).is_empty());
+        assert!(manager.take_unresponsive_peers().is_empty());
+    }
+}
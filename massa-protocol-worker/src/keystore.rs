@@ -0,0 +1,203 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! Storage of the network keypair of the node, which identifies it to its peers.
+//!
+//! The keypair file holds either the keypair in clear, as written by the older nodes, or the
+//! keypair encrypted with a passphrase given by the node operator. A file in clear is encrypted
+//! the first time it is loaded with a protection configured.
+
+use massa_cipher::{decrypt, encrypt};
+use massa_protocol_exports::{KeypairProtection, ProtocolError};
+use massa_signature::KeyPair;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing::info;
+
+/// Content of the keypair file
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum KeystoreFile {
+    /// Keypair in clear, as written by the older nodes
+    Clear(KeyPair),
+    /// Keypair encrypted with the passphrase
+    Encrypted { encrypted_keypair: Vec<u8> },
+}
+
+/// Keypair file of the node and its protection
+pub struct NodeKeystore {
+    path: PathBuf,
+    protection: KeypairProtection,
+}
+
+impl NodeKeystore {
+    /// Keystore of the keypair stored at `path`
+    pub fn new(path: &Path, protection: KeypairProtection) -> Self {
+        NodeKeystore {
+            path: path.to_path_buf(),
+            protection,
+        }
+    }
+
+    /// Load the keypair, or generate one and save it if the file doesn't exist yet
+    pub fn load_or_generate(&self) -> Result<KeyPair, ProtocolError> {
+        if !self.path.is_file() {
+            let keypair = KeyPair::generate();
+            self.save(&self.path, &keypair)?;
+            info!("node keypair generated in {}", self.path.display());
+            return Ok(keypair);
+        }
+        let (keypair, encrypted) = self.read()?;
+        if !encrypted && self.protection != KeypairProtection::None {
+            self.save(&self.path, &keypair)?;
+            info!("node keypair file {} encrypted", self.path.display());
+        }
+        Ok(keypair)
+    }
+
+    /// Read the keypair, to export it
+    pub fn export(&self) -> Result<KeyPair, ProtocolError> {
+        self.read().map(|(keypair, _)| keypair)
+    }
+
+    /// Replace the keypair by `keypair`. The previous one is kept next to the file.
+    pub fn import(&self, keypair: &KeyPair) -> Result<(), ProtocolError> {
+        self.backup()?;
+        self.save(&self.path, keypair)
+    }
+
+    /// Replace the keypair by a new one, which changes the node id. The previous keypair is
+    /// kept next to the file.
+    pub fn rotate(&self) -> Result<KeyPair, ProtocolError> {
+        let keypair = KeyPair::generate();
+        self.import(&keypair)?;
+        Ok(keypair)
+    }
+
+    /// Path where the previous keypair is kept on import and rotation
+    pub fn backup_path(&self) -> PathBuf {
+        let mut file_name = self.path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".previous");
+        self.path.with_file_name(file_name)
+    }
+
+    fn backup(&self) -> Result<(), ProtocolError> {
+        if self.path.is_file() {
+            std::fs::copy(&self.path, self.backup_path())?;
+        }
+        Ok(())
+    }
+
+    /// Read the keypair and whether it was encrypted
+    fn read(&self) -> Result<(KeyPair, bool), ProtocolError> {
+        let content = std::fs::read_to_string(&self.path).map_err(|err| {
+            std::io::Error::new(err.kind(), format!("could not load node key file: {}", err))
+        })?;
+        match serde_json::from_str::<KeystoreFile>(&content)? {
+            KeystoreFile::Clear(keypair) => Ok((keypair, false)),
+            KeystoreFile::Encrypted { encrypted_keypair } => {
+                let Some(passphrase) = self.passphrase() else {
+                    return Err(ProtocolError::GeneralProtocolError(
+                        "the node key file is encrypted but no passphrase is configured"
+                            .to_string(),
+                    ));
+                };
+                let (_version, decrypted) =
+                    decrypt(&passphrase, &encrypted_keypair).map_err(|err| {
+                        ProtocolError::GeneralProtocolError(format!(
+                            "could not decrypt the node key file: {}",
+                            err
+                        ))
+                    })?;
+                let keypair = std::str::from_utf8(&decrypted)
+                    .ok()
+                    .and_then(|keypair| KeyPair::from_str(keypair).ok())
+                    .ok_or_else(|| {
+                        ProtocolError::GeneralProtocolError(
+                            "the decrypted node key file doesn't hold a keypair".to_string(),
+                        )
+                    })?;
+                Ok((keypair, true))
+            }
+        }
+    }
+
+    fn save(&self, path: &Path, keypair: &KeyPair) -> Result<(), ProtocolError> {
+        let content = match self.passphrase() {
+            None => KeystoreFile::Clear(keypair.clone()),
+            Some(passphrase) => KeystoreFile::Encrypted {
+                encrypted_keypair: encrypt(&passphrase, keypair.to_string().as_bytes()).map_err(
+                    |err| {
+                        ProtocolError::GeneralProtocolError(format!(
+                            "could not encrypt the node key file: {}",
+                            err
+                        ))
+                    },
+                )?,
+            },
+        };
+        std::fs::write(path, serde_json::to_string(&content)?)?;
+        Ok(())
+    }
+
+    /// Passphrase of the file, none if it is stored in clear
+    fn passphrase(&self) -> Option<String> {
+        match &self.protection {
+            KeypairProtection::None => None,
+            KeypairProtection::Passphrase(passphrase) => Some(passphrase.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NodeKeystore;
+    use massa_protocol_exports::KeypairProtection;
+    use massa_signature::KeyPair;
+
+    #[test]
+    fn test_keystore_encryption() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("node_privkey.key");
+        let clear = NodeKeystore::new(&path, KeypairProtection::None);
+        let keypair = clear.load_or_generate().unwrap().get_public_key();
+        assert_eq!(clear.load_or_generate().unwrap().get_public_key(), keypair);
+
+        // the file in clear is encrypted when it is loaded with a passphrase
+        let protected = NodeKeystore::new(&path, KeypairProtection::Passphrase("pwd".into()));
+        let secret = protected.load_or_generate().unwrap().to_string();
+        assert_eq!(protected.export().unwrap().get_public_key(), keypair);
+        assert!(!std::fs::read_to_string(&path).unwrap().contains(&secret));
+        assert!(clear.load_or_generate().is_err());
+        assert!(
+            NodeKeystore::new(&path, KeypairProtection::Passphrase("wrong".into()))
+                .export()
+                .is_err()
+        );
+
+        // the previous keypair is kept on rotation
+        let rotated = protected.rotate().unwrap().get_public_key();
+        assert_ne!(rotated, keypair);
+        assert_eq!(
+            protected.load_or_generate().unwrap().get_public_key(),
+            rotated
+        );
+        assert_eq!(
+            NodeKeystore::new(
+                &protected.backup_path(),
+                KeypairProtection::Passphrase("pwd".into())
+            )
+            .export()
+            .unwrap()
+            .get_public_key(),
+            keypair
+        );
+
+        let imported = KeyPair::generate();
+        protected.import(&imported).unwrap();
+        assert_eq!(
+            protected.export().unwrap().get_public_key(),
+            imported.get_public_key()
+        );
+    }
+}
@@ -2,16 +2,31 @@
 #![feature(let_chains)]
 #![feature(ip)]
 
+mod audit;
+mod channels;
+mod clock;
 mod connectivity;
 mod controller;
+mod dial;
 mod handlers;
+pub mod keystore;
 mod manager;
 mod messages;
+mod misbehavior;
+mod peer_traffic;
+mod production_schedule;
+pub mod recorder;
+mod replay;
 mod sig_verifier;
 mod worker;
 mod wrap_network;
 
 pub use worker::{create_protocol_controller, start_protocol_controller};
 
+#[cfg(feature = "benchmarking")]
+pub use handlers::peer_handler::bench;
+#[cfg(fuzzing)]
+pub use handlers::peer_handler::fuzz;
+
 #[cfg(test)]
 mod tests;
@@ -1,7 +1,12 @@
+use std::{cell::RefCell, collections::HashMap, sync::Arc};
+
 use crossbeam::channel::Sender;
+use massa_models::stats::ChannelStats;
+use massa_protocol_exports::{MisbehaviorKind, ProtocolConfig};
 use massa_serialization::{
     DeserializeError, Deserializer, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
 };
+use parking_lot::{Mutex, RwLock};
 use peernet::{
     error::{PeerNetError, PeerNetResult},
     messages::{
@@ -9,22 +14,47 @@ use peernet::{
     },
     peer_id::PeerId,
 };
+use tracing::warn;
 
+use crate::channels::SheddingSender;
 use crate::handlers::{
     block_handler::{BlockMessage, BlockMessageSerializer},
     endorsement_handler::{EndorsementMessage, EndorsementMessageSerializer},
     operation_handler::{OperationMessage, OperationMessageSerializer},
     peer_handler::{
-        models::PeerMessageTuple, PeerManagementMessage, PeerManagementMessageSerializer,
+        models::{PeerManagementCmd, PeerMessageTuple},
+        PeerManagementMessage, PeerManagementMessageSerializer,
     },
 };
+use crate::peer_traffic::{pad_to_bucket, MessageCategory, PeerCounters, PeerTraffic};
+use crate::recorder::SharedSessionRecorder;
+use crate::replay::ReplayGuard;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Message {
     Block(Box<BlockMessage>),
     Endorsement(EndorsementMessage),
     Operation(OperationMessage),
     PeerManagement(Box<PeerManagementMessage>),
+    /// Message already serialized, sent as is to several peers
+    Serialized(SerializedMessage),
+}
+
+/// Content of a message serialized once by `MessagesSerializer::serialize_once`. Cloning it for
+/// each peer only clones a reference to the bytes.
+#[derive(Debug, Clone)]
+pub struct SerializedMessage {
+    id: u64,
+    priority: MessagePriority,
+    content: Arc<[u8]>,
+}
+
+/// Buffers that grew above this capacity are not kept for the next messages
+const MAX_REUSED_BUFFER_CAPACITY: usize = 1024 * 1024;
+
+thread_local! {
+    /// Buffer in which each thread serializes the messages sent to several peers
+    static SERIALIZATION_BUFFER: RefCell<Vec<u8>> = RefCell::new(Vec::new());
 }
 
 //TODO: Macroize this
@@ -52,25 +82,153 @@ impl From<PeerManagementMessage> for Message {
     }
 }
 
+/// Priority class of an outgoing message.
+///
+/// When the connection with a peer is congested, the messages queued for it are sent class by
+/// class, in the order of the variants, so that peer-list gossip and large operation batches
+/// don't delay blocks and endorsements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MessagePriority {
+    /// Blocks, endorsements and disconnection notices
+    Consensus = 0,
+    /// Operation announcements, asks and batches
+    Operations = 1,
+    /// Peer management gossip
+    PeerGossip = 2,
+}
+
+impl MessagePriority {
+    /// Number of priority classes
+    pub const COUNT: usize = 3;
+
+    /// All the classes, from the most to the least important
+    pub const ALL: [MessagePriority; MessagePriority::COUNT] = [
+        MessagePriority::Consensus,
+        MessagePriority::Operations,
+        MessagePriority::PeerGossip,
+    ];
+}
+
 impl Message {
-    //TODO: Macroize get_id and max_id
-    fn get_id(&self) -> u64 {
+    /// Priority class of the message
+    pub(crate) fn priority(&self) -> MessagePriority {
         match self {
-            Message::Block(message) => message.get_id() as u64,
-            Message::Endorsement(message) => message.get_id() as u64 + BlockMessage::max_id(),
+            Message::Block(_) | Message::Endorsement(_) => MessagePriority::Consensus,
+            // reconciliation only recovers what gossip missed: it can wait
+            Message::Operation(OperationMessage::OperationsDigest(_)) => {
+                MessagePriority::PeerGossip
+            }
+            Message::Operation(_) => MessagePriority::Operations,
+            // sent right before the connection is closed, or measuring the liveness of the
+            // connection: they must not wait behind gossip
+            Message::PeerManagement(message)
+                if matches!(
+                    **message,
+                    PeerManagementMessage::Disconnecting(_)
+                        | PeerManagementMessage::Ping(_)
+                        | PeerManagementMessage::Pong(_)
+                ) =>
+            {
+                MessagePriority::Consensus
+            }
+            Message::PeerManagement(_) => MessagePriority::PeerGossip,
+            Message::Serialized(message) => message.priority,
+        }
+    }
+
+    /// Wire id of the message, see `wire_message_id`
+    pub(crate) fn get_id(&self) -> u64 {
+        match self {
+            Message::Block(message) => {
+                wire_message_id(MessageCategory::Block, message.get_id() as u64)
+            }
+            Message::Endorsement(message) => {
+                wire_message_id(MessageCategory::Endorsement, message.get_id() as u64)
+            }
             Message::Operation(message) => {
-                message.get_id() as u64 + BlockMessage::max_id() + EndorsementMessage::max_id()
+                wire_message_id(MessageCategory::Operation, message.get_id() as u64)
             }
             Message::PeerManagement(message) => {
-                message.get_id() as u64
-                    + BlockMessage::max_id()
-                    + EndorsementMessage::max_id()
-                    + OperationMessage::max_id()
+                wire_message_id(MessageCategory::PeerManagement, message.get_id() as u64)
             }
+            Message::Serialized(message) => message.id,
         }
     }
 }
 
+/// Number of message ids of each category in the first version of the protocol, whose wire ids
+/// are consecutive: the block messages, then the endorsement, operation and peer management ones
+const BLOCK_INITIAL_IDS: u64 = 3;
+const ENDORSEMENT_INITIAL_IDS: u64 = 1;
+const OPERATION_INITIAL_IDS: u64 = 3;
+/// First wire id of the peer management messages. The messages added to this category take
+/// the next wire ids, up to `EXTENSION_FIRST_ID`.
+const PEER_MANAGEMENT_FIRST_ID: u64 =
+    BLOCK_INITIAL_IDS + ENDORSEMENT_INITIAL_IDS + OPERATION_INITIAL_IDS;
+/// First wire id of the messages added to the block, endorsement and operation categories
+/// after the first version, each category having `EXTENSION_IDS_PER_CATEGORY` of them
+const EXTENSION_FIRST_ID: u64 = 256;
+const EXTENSION_IDS_PER_CATEGORY: u64 = 64;
+
+/// First wire id, number of ids in the first version and first extension wire id of a category
+/// that has an extension range
+fn category_wire_ids(category: MessageCategory) -> Option<(u64, u64, u64)> {
+    match category {
+        MessageCategory::Block => Some((0, BLOCK_INITIAL_IDS, EXTENSION_FIRST_ID)),
+        MessageCategory::Endorsement => Some((
+            BLOCK_INITIAL_IDS,
+            ENDORSEMENT_INITIAL_IDS,
+            EXTENSION_FIRST_ID + EXTENSION_IDS_PER_CATEGORY,
+        )),
+        MessageCategory::Operation => Some((
+            BLOCK_INITIAL_IDS + ENDORSEMENT_INITIAL_IDS,
+            OPERATION_INITIAL_IDS,
+            EXTENSION_FIRST_ID + 2 * EXTENSION_IDS_PER_CATEGORY,
+        )),
+        MessageCategory::PeerManagement => None,
+    }
+}
+
+/// Wire id of the message with the id `id` within its category.
+///
+/// The wire ids are fixed: adding a message to a category never shifts the ids of the messages
+/// of the other categories, that older nodes would hand to the wrong handler. The messages of
+/// the first version keep their consecutive ids, and the ones added since take an id in the
+/// extension range of their category.
+pub(crate) fn wire_message_id(category: MessageCategory, id: u64) -> u64 {
+    match category_wire_ids(category) {
+        Some((first_id, initial_ids, _)) if id < initial_ids => first_id + id,
+        Some((_, initial_ids, extension_first_id)) => extension_first_id + id - initial_ids,
+        None => PEER_MANAGEMENT_FIRST_ID + id,
+    }
+}
+
+/// Category of the message with the wire id `wire_id`, and its id within the category.
+/// The ids outside of the ranges of the block, endorsement and operation messages are given to
+/// the peer management handler, which ignores the ones added by newer versions.
+pub(crate) fn split_message_id(wire_id: u64) -> (MessageCategory, u64) {
+    for category in [
+        MessageCategory::Block,
+        MessageCategory::Endorsement,
+        MessageCategory::Operation,
+    ] {
+        let Some((first_id, initial_ids, extension_first_id)) = category_wire_ids(category) else {
+            continue;
+        };
+        if (first_id..first_id + initial_ids).contains(&wire_id) {
+            return (category, wire_id - first_id);
+        }
+        if (extension_first_id..extension_first_id + EXTENSION_IDS_PER_CATEGORY).contains(&wire_id)
+        {
+            return (category, initial_ids + wire_id - extension_first_id);
+        }
+    }
+    (
+        MessageCategory::PeerManagement,
+        wire_id.saturating_sub(PEER_MANAGEMENT_FIRST_ID),
+    )
+}
+
 #[derive(Clone)]
 pub struct MessagesSerializer {
     id_serializer: U64VarIntSerializer,
@@ -78,6 +236,12 @@ pub struct MessagesSerializer {
     operation_message_serializer: Option<OperationMessageSerializer>,
     endorsement_message_serializer: Option<EndorsementMessageSerializer>,
     peer_management_message_serializer: Option<PeerManagementMessageSerializer>,
+    /// sequence number written before the id, on the connections protected against replays
+    sequence: Option<u64>,
+    /// counters of the peer the messages are serialized for, to count the bytes sent
+    counters: Option<Arc<PeerCounters>>,
+    /// bucket size to which the peer management messages are padded, if the peer negotiated it
+    padding: Option<u64>,
 }
 
 impl Default for MessagesSerializer {
@@ -94,6 +258,46 @@ impl MessagesSerializer {
             operation_message_serializer: None,
             endorsement_message_serializer: None,
             peer_management_message_serializer: None,
+            sequence: None,
+            counters: None,
+            padding: None,
+        }
+    }
+
+    /// Copy of the serializer prefixing the message with a sequence number
+    pub(crate) fn with_sequence(&self, sequence: u64) -> Self {
+        Self {
+            sequence: Some(sequence),
+            ..self.clone()
+        }
+    }
+
+    /// Copy of the serializer counting the bytes of the messages sent to a peer
+    pub(crate) fn with_counters(&self, counters: Arc<PeerCounters>) -> Self {
+        Self {
+            counters: Some(counters),
+            ..self.clone()
+        }
+    }
+
+    /// Copy of the serializer padding the peer management messages to a multiple of
+    /// `bucket_size`, if any
+    pub(crate) fn with_padding(&self, bucket_size: Option<u64>) -> Self {
+        Self {
+            padding: bucket_size,
+            ..self.clone()
+        }
+    }
+
+    /// Copy of the serializer framing the peer management messages, for a peer that
+    /// negotiated it
+    pub(crate) fn with_framing(&self, framed: bool) -> Self {
+        Self {
+            peer_management_message_serializer: self
+                .peer_management_message_serializer
+                .as_ref()
+                .map(|serializer| serializer.with_framing(framed)),
+            ..self.clone()
         }
     }
 
@@ -128,11 +332,41 @@ impl MessagesSerializer {
         self.peer_management_message_serializer = Some(peer_management_message_serializer);
         self
     }
+
+    /// Serializes the content of a message sent to several peers once. The sequence number and
+    /// the id are still written for each peer when the message is sent.
+    pub(crate) fn serialize_once(&self, message: &Message) -> PeerNetResult<Message> {
+        SERIALIZATION_BUFFER.with(|buffer| {
+            let mut buffer = buffer.borrow_mut();
+            buffer.clear();
+            let result = self.serialize_content(message, &mut buffer);
+            let content = Arc::from(buffer.as_slice());
+            if buffer.capacity() > MAX_REUSED_BUFFER_CAPACITY {
+                *buffer = Vec::new();
+            }
+            result?;
+            Ok(Message::Serialized(SerializedMessage {
+                id: message.get_id(),
+                priority: message.priority(),
+                content,
+            }))
+        })
+    }
 }
 
 impl PeerNetMessagesSerializer<Message> for MessagesSerializer {
     /// Serialize the id of a message
     fn serialize_id(&self, message: &Message, buffer: &mut Vec<u8>) -> PeerNetResult<()> {
+        if let Some(sequence) = self.sequence {
+            self.id_serializer
+                .serialize(&sequence, buffer)
+                .map_err(|err| {
+                    PeerNetError::HandlerError.error(
+                        "MessagesSerializer",
+                        Some(format!("Failed to serialize sequence number: {}", err)),
+                    )
+                })?;
+        }
         self.id_serializer
             .serialize(&message.get_id(), buffer)
             .map_err(|err| {
@@ -144,6 +378,27 @@ impl PeerNetMessagesSerializer<Message> for MessagesSerializer {
     }
     /// Serialize the message
     fn serialize(&self, message: &Message, buffer: &mut Vec<u8>) -> PeerNetResult<()> {
+        let start = buffer.len();
+        self.serialize_content(message, buffer)?;
+        // a peer that negotiated the padding ignores the bytes after the framed content
+        if let Some(bucket_size) = self.padding
+            && MessageCategory::from_id(message.get_id()) == MessageCategory::PeerManagement
+            && self
+                .peer_management_message_serializer
+                .as_ref()
+                .map_or(false, |serializer| serializer.is_framed())
+        {
+            pad_to_bucket(buffer, start, bucket_size);
+        }
+        if let Some(counters) = &self.counters {
+            counters.record_sent(buffer.len() - start);
+        }
+        Ok(())
+    }
+}
+
+impl MessagesSerializer {
+    fn serialize_content(&self, message: &Message, buffer: &mut Vec<u8>) -> PeerNetResult<()> {
         match message {
             Message::Block(message) => {
                 if let Some(serializer) = &self.block_message_serializer {
@@ -205,25 +460,166 @@ impl PeerNetMessagesSerializer<Message> for MessagesSerializer {
                     ))
                 }
             }
+            Message::Serialized(message) => {
+                buffer.extend_from_slice(&message.content);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Rejects the messages received from peers that are bigger than the maximum of their type,
+/// before they are copied to the channel of their handler.
+///
+/// The connection of a peer sending an oversized message is closed, and the peer is banned once
+/// it sent `max_violations` of them.
+#[derive(Clone)]
+pub struct MessageSizeValidator {
+    /// limits shared by all the clones, so that they can be reloaded
+    limits: Arc<RwLock<MessageSizeLimits>>,
+    /// number of oversized messages sent by each peer
+    violations: Arc<Mutex<HashMap<PeerId, u64>>>,
+    sender_peer_cmd: Sender<PeerManagementCmd>,
+}
+
+#[derive(Clone, Copy)]
+struct MessageSizeLimits {
+    max_block_message_size: u64,
+    max_endorsement_message_size: u64,
+    max_operation_message_size: u64,
+    max_peer_management_message_size: u64,
+    /// number of oversized messages after which a peer is banned (0 for no ban)
+    max_violations: u64,
+}
+
+impl MessageSizeLimits {
+    fn from_config(config: &ProtocolConfig) -> Self {
+        Self {
+            max_block_message_size: config.max_size_block_message,
+            max_endorsement_message_size: config.max_size_endorsement_message,
+            max_operation_message_size: config.max_size_operation_message,
+            max_peer_management_message_size: config.max_size_peer_management_message,
+            max_violations: config.max_message_size_violations,
+        }
+    }
+}
+
+impl MessageSizeValidator {
+    pub fn new(config: &ProtocolConfig, sender_peer_cmd: Sender<PeerManagementCmd>) -> Self {
+        Self {
+            limits: Arc::new(RwLock::new(MessageSizeLimits::from_config(config))),
+            violations: Arc::new(Mutex::new(HashMap::new())),
+            sender_peer_cmd,
+        }
+    }
+
+    /// Applies the limits of a reloaded config
+    pub fn update_config(&self, config: &ProtocolConfig) {
+        *self.limits.write() = MessageSizeLimits::from_config(config);
+    }
+
+    /// Checks the size of the content of a message with the given id
+    fn check(&self, id: u64, size: usize, peer_id: &PeerId) -> PeerNetResult<()> {
+        let limits = *self.limits.read();
+        let (message_type, max_size) = match MessageCategory::from_id(id) {
+            MessageCategory::Block => ("block", limits.max_block_message_size),
+            MessageCategory::Endorsement => ("endorsement", limits.max_endorsement_message_size),
+            MessageCategory::Operation => ("operation", limits.max_operation_message_size),
+            MessageCategory::PeerManagement => {
+                ("peer management", limits.max_peer_management_message_size)
+            }
+        };
+        if size as u64 <= max_size {
+            return Ok(());
+        }
+        warn!(
+            "peer {} sent a {} message of {} bytes, more than the maximum of {}",
+            peer_id, message_type, size, max_size
+        );
+        if limits.max_violations > 0 {
+            let mut violations = self.violations.lock();
+            let count = violations.entry(peer_id.clone()).or_default();
+            *count += 1;
+            if *count >= limits.max_violations {
+                violations.remove(peer_id);
+                // don't block the network thread: the connection is closed anyway
+                if let Err(err) =
+                    self.sender_peer_cmd
+                        .try_send(PeerManagementCmd::ReportMisbehavior {
+                            peer_id: peer_id.clone(),
+                            kind: MisbehaviorKind::Spam,
+                            evidence: format!(
+                                "{} oversized messages, the last one a {} message of {} bytes",
+                                limits.max_violations, message_type, size
+                            ),
+                            ban: true,
+                        })
+                {
+                    warn!("failed to ban peer {}: {}", peer_id, err);
+                }
+            }
         }
+        Err(PeerNetError::HandlerError.error(
+            "MessagesHandler",
+            Some(format!(
+                "{} message of {} bytes is bigger than the maximum of {}",
+                message_type, size, max_size
+            )),
+        ))
     }
 }
 
+/// Dispatches the messages received from the network to the handlers.
+///
+/// The block channel blocks when it is full: block messages are never dropped. The other
+/// channels carry gossip and drop their oldest message to make room for a new one.
 #[derive(Clone)]
 pub struct MessagesHandler {
-    pub sender_blocks: Sender<PeerMessageTuple>,
-    pub sender_endorsements: Sender<PeerMessageTuple>,
-    pub sender_operations: Sender<PeerMessageTuple>,
-    pub sender_peers: Sender<PeerMessageTuple>,
+    pub sender_blocks: SheddingSender<PeerMessageTuple>,
+    pub sender_endorsements: SheddingSender<PeerMessageTuple>,
+    pub sender_operations: SheddingSender<PeerMessageTuple>,
+    pub sender_peers: SheddingSender<PeerMessageTuple>,
     pub id_deserializer: U64VarIntDeserializer,
+    pub size_validator: MessageSizeValidator,
+    /// Checks the sequence numbers of the connections protected against replays
+    pub replay_guard: Option<ReplayGuard>,
+    /// Captures the received messages when a capture file is configured
+    pub recorder: Option<SharedSessionRecorder>,
+    /// Counts the messages received from each peer
+    pub traffic: PeerTraffic,
+}
+
+impl MessagesHandler {
+    /// Occupancy of the channels to the handlers
+    pub fn get_channel_stats(&self) -> Vec<ChannelStats> {
+        vec![
+            self.sender_blocks.get_stats(),
+            self.sender_endorsements.get_stats(),
+            self.sender_operations.get_stats(),
+            self.sender_peers.get_stats(),
+        ]
+    }
+
+    /// Handler of the messages received before the end of a handshake, which are never
+    /// numbered even if another connection with the peer is
+    pub fn without_replay_guard(&self) -> Self {
+        Self {
+            replay_guard: None,
+            ..self.clone()
+        }
+    }
 }
 
 impl PeerNetMessagesHandler for MessagesHandler {
     fn deserialize_id<'a>(
         &self,
         data: &'a [u8],
-        _peer_id: &PeerId,
+        peer_id: &PeerId,
     ) -> PeerNetResult<(&'a [u8], u64)> {
+        let data = match &self.replay_guard {
+            Some(replay_guard) => replay_guard.check(data, peer_id)?,
+            None => data,
+        };
         if data.is_empty() {
             return Err(PeerNetError::ReceiveError.error(
                 "MessagesHandler",
@@ -241,22 +637,28 @@ impl PeerNetMessagesHandler for MessagesHandler {
     }
 
     fn handle(&self, id: u64, data: &[u8], peer_id: &PeerId) -> PeerNetResult<()> {
-        let block_max_id = BlockMessage::max_id();
-        let endorsement_max_id = EndorsementMessage::max_id();
-        let operation_max_id = OperationMessage::max_id();
-        let peer_management_max_id = PeerManagementMessage::max_id();
-        if id < block_max_id {
-            self.sender_blocks
+        if let Some(recorder) = &self.recorder {
+            recorder.record(peer_id, id, data);
+        }
+        if let Err(err) = self.size_validator.check(id, data.len(), peer_id) {
+            self.traffic.record_invalid(peer_id);
+            return Err(err);
+        }
+        let (category, id) = split_message_id(id);
+        self.traffic.record_received(peer_id, category, data.len());
+        match category {
+            MessageCategory::Block => self
+                .sender_blocks
                 .send((peer_id.clone(), id, data.to_vec()))
                 .map_err(|err| {
                     PeerNetError::HandlerError.error(
                         "MessagesHandler",
                         Some(format!("Failed to send block message to channel: {}", err)),
                     )
-                })
-        } else if id < endorsement_max_id + block_max_id {
-            self.sender_endorsements
-                .send((peer_id.clone(), id - block_max_id, data.to_vec()))
+                }),
+            MessageCategory::Endorsement => self
+                .sender_endorsements
+                .send((peer_id.clone(), id, data.to_vec()))
                 .map_err(|err| {
                     PeerNetError::HandlerError.error(
                         "MessagesHandler",
@@ -265,14 +667,10 @@ impl PeerNetMessagesHandler for MessagesHandler {
                             err
                         )),
                     )
-                })
-        } else if id < operation_max_id + block_max_id + endorsement_max_id {
-            self.sender_operations
-                .send((
-                    peer_id.clone(),
-                    id - (block_max_id + endorsement_max_id),
-                    data.to_vec(),
-                ))
+                }),
+            MessageCategory::Operation => self
+                .sender_operations
+                .send((peer_id.clone(), id, data.to_vec()))
                 .map_err(|err| {
                     PeerNetError::HandlerError.error(
                         "MessagesHandler",
@@ -281,15 +679,13 @@ impl PeerNetMessagesHandler for MessagesHandler {
                             err
                         )),
                     )
-                })
-        } else if id < peer_management_max_id + block_max_id + endorsement_max_id + operation_max_id
-        {
-            self.sender_peers
-                .send((
-                    peer_id.clone(),
-                    id - (block_max_id + endorsement_max_id + operation_max_id),
-                    data.to_vec(),
-                ))
+                }),
+            // Ids above the known ones are forwarded to the peer management handler too so that
+            // messages added by newer versions are ignored by the handler instead of closing the
+            // connection.
+            MessageCategory::PeerManagement => self
+                .sender_peers
+                .send((peer_id.clone(), id, data.to_vec()))
                 .map_err(|err| {
                     PeerNetError::HandlerError.error(
                         "MessagesHandler",
@@ -298,12 +694,124 @@ impl PeerNetMessagesHandler for MessagesHandler {
                             err
                         )),
                     )
-                })
-        } else {
-            Err(PeerNetError::HandlerError.error(
-                "MessagesHandler",
-                Some(format!("Unknown message id: {}", id)),
-            ))
+                }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam::channel::bounded;
+    use massa_signature::KeyPair;
+
+    #[test]
+    fn test_message_serialized_once_is_sent_as_is() {
+        let serializer = MessagesSerializer::new()
+            .with_peer_management_message_serializer(PeerManagementMessageSerializer::new());
+        let message = Message::from(PeerManagementMessage::ListPeers(vec![(
+            PeerId::from_public_key(KeyPair::generate().get_public_key()),
+            HashMap::new(),
+        )]));
+        let serialized = serializer.serialize_once(&message).unwrap();
+        assert_eq!(serialized.get_id(), message.get_id());
+        assert_eq!(serialized.priority(), message.priority());
+
+        // each peer still gets its own sequence number
+        for sequence in [None, Some(7)] {
+            let serializer = match sequence {
+                Some(sequence) => serializer.with_sequence(sequence),
+                None => serializer.clone(),
+            };
+            let mut expected = Vec::new();
+            serializer.serialize_id(&message, &mut expected).unwrap();
+            serializer.serialize(&message, &mut expected).unwrap();
+            let mut sent = Vec::new();
+            serializer.serialize_id(&serialized, &mut sent).unwrap();
+            serializer.serialize(&serialized, &mut sent).unwrap();
+            assert_eq!(sent, expected);
+        }
+        // sent as is even by a serializer that can't serialize its type
+        let mut sent = Vec::new();
+        MessagesSerializer::new()
+            .serialize(&serialized, &mut sent)
+            .unwrap();
+        assert!(!sent.is_empty());
+    }
+
+    #[test]
+    fn test_oversized_messages_are_rejected_and_penalized() {
+        let config = ProtocolConfig {
+            max_size_operation_message: 10,
+            max_message_size_violations: 2,
+            ..Default::default()
+        };
+        let (sender_peer_cmd, receiver_peer_cmd) = bounded(10);
+        let validator = MessageSizeValidator::new(&config, sender_peer_cmd);
+        let peer_id = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        let operation_id = wire_message_id(MessageCategory::Operation, 0);
+
+        assert!(validator.check(operation_id, 10, &peer_id).is_ok());
+        // the limit of a type doesn't apply to the others
+        assert!(validator.check(0, 11, &peer_id).is_ok());
+        assert!(validator.check(operation_id, 11, &peer_id).is_err());
+        assert!(receiver_peer_cmd.try_recv().is_err());
+        assert!(validator.check(operation_id, 11, &peer_id).is_err());
+        match receiver_peer_cmd.try_recv() {
+            Ok(PeerManagementCmd::ReportMisbehavior {
+                peer_id: reported_peer_id,
+                kind: MisbehaviorKind::Spam,
+                ban: true,
+                ..
+            }) => assert_eq!(reported_peer_id, peer_id),
+            _ => panic!("the peer should be banned"),
+        }
+    }
+
+    #[test]
+    fn test_wire_message_ids_are_fixed() {
+        // the ids of the messages of the first version don't depend on the messages added since
+        let first_version_ids = [
+            (MessageCategory::Block, 3, 0),
+            (MessageCategory::Endorsement, 1, 3),
+            (MessageCategory::Operation, 3, 4),
+            (MessageCategory::PeerManagement, 2, 7),
+        ];
+        for (category, count, first_wire_id) in first_version_ids {
+            for id in 0..count {
+                assert_eq!(wire_message_id(category, id), first_wire_id + id);
+            }
+        }
+        assert_eq!(
+            Message::from(PeerManagementMessage::ListPeers(Vec::new())).get_id(),
+            8
+        );
+
+        // all the ids of the current version fit in their range, and are split back
+        for (category, max_id) in [
+            (MessageCategory::Block, BlockMessage::max_id()),
+            (MessageCategory::Endorsement, EndorsementMessage::max_id()),
+            (MessageCategory::Operation, OperationMessage::max_id()),
+            (
+                MessageCategory::PeerManagement,
+                PeerManagementMessage::max_id(),
+            ),
+        ] {
+            for id in 0..max_id {
+                let wire_id = wire_message_id(category, id);
+                assert_eq!(split_message_id(wire_id), (category, id));
+            }
         }
+        assert!(
+            wire_message_id(
+                MessageCategory::PeerManagement,
+                PeerManagementMessage::max_id()
+            ) <= EXTENSION_FIRST_ID
+        );
+        // unknown ids go to the peer management handler
+        assert_eq!(
+            split_message_id(u64::MAX).0,
+            MessageCategory::PeerManagement
+        );
     }
 }
@@ -0,0 +1,165 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    num::NonZeroUsize,
+};
+
+use lru::LruCache;
+use massa_protocol_exports::{MisbehaviorKind, MisbehaviorRecord, ProtocolConfig};
+use massa_time::MassaTime;
+use peernet::peer_id::PeerId;
+
+/// Offenses committed by the peers, kept so that operators can audit why a peer was banned.
+///
+/// The last `max_records_per_peer` offenses of the `max_peers` most recent offenders are kept.
+/// A peer that commits `max_misbehaviors_before_ban` offenses within `ban_window` is banned,
+/// even if none of them was serious enough to be banned for on its own.
+pub struct MisbehaviorLedger {
+    records: LruCache<PeerId, VecDeque<MisbehaviorRecord>>,
+    max_records_per_peer: usize,
+    max_misbehaviors_before_ban: usize,
+    ban_window: MassaTime,
+}
+
+impl MisbehaviorLedger {
+    /// Creates a new `MisbehaviorLedger`
+    ///
+    /// # Arguments
+    /// * `max_peers`: maximum number of peers whose offenses are kept
+    /// * `max_records_per_peer`: maximum number of offenses kept for a peer
+    /// * `max_misbehaviors_before_ban`: offenses within `ban_window` after which a peer is banned (0 for no ban)
+    /// * `ban_window`: period over which the offenses are counted
+    pub fn new(
+        max_peers: NonZeroUsize,
+        max_records_per_peer: usize,
+        max_misbehaviors_before_ban: usize,
+        ban_window: MassaTime,
+    ) -> Self {
+        Self {
+            records: LruCache::new(max_peers),
+            max_records_per_peer,
+            max_misbehaviors_before_ban,
+            ban_window,
+        }
+    }
+
+    /// Creates a new `MisbehaviorLedger` with the bounds of the config
+    pub fn from_config(config: &ProtocolConfig) -> Self {
+        Self::new(
+            NonZeroUsize::new(config.misbehavior_max_peers)
+                .expect("misbehavior_max_peers in config must be > 0"),
+            config.misbehavior_max_records_per_peer,
+            config.max_misbehaviors_before_ban,
+            config.misbehavior_ban_window,
+        )
+    }
+
+    /// Records an offense of a peer. Returns true if the peer must be banned: either
+    /// `ban` is set because the offense is serious, or the peer committed too many offenses.
+    pub fn record(
+        &mut self,
+        peer_id: &PeerId,
+        kind: MisbehaviorKind,
+        evidence: String,
+        ban: bool,
+        now: MassaTime,
+    ) -> bool {
+        if !self.records.contains(peer_id) {
+            self.records.push(peer_id.clone(), VecDeque::new());
+        }
+        let records = self
+            .records
+            .get_mut(peer_id)
+            .expect("critical: peer was just inserted in the misbehavior ledger");
+        let window_start = now.saturating_sub(self.ban_window);
+        let recent_count = records
+            .iter()
+            .filter(|record| record.timestamp >= window_start)
+            .count()
+            + 1;
+        let banned = ban
+            || (self.max_misbehaviors_before_ban > 0
+                && recent_count >= self.max_misbehaviors_before_ban);
+        records.push_back(MisbehaviorRecord {
+            timestamp: now,
+            kind,
+            evidence,
+            banned,
+        });
+        while records.len() > self.max_records_per_peer {
+            records.pop_front();
+        }
+        banned
+    }
+
+    /// Offenses of the given peers, or of all the peers if `None`, the oldest first
+    pub fn get_records(
+        &self,
+        peer_ids: Option<&[PeerId]>,
+    ) -> HashMap<PeerId, Vec<MisbehaviorRecord>> {
+        match peer_ids {
+            Some(peer_ids) => peer_ids
+                .iter()
+                .filter_map(|peer_id| {
+                    self.records
+                        .peek(peer_id)
+                        .map(|records| (peer_id.clone(), records.iter().cloned().collect()))
+                })
+                .collect(),
+            None => self
+                .records
+                .iter()
+                .map(|(peer_id, records)| (peer_id.clone(), records.iter().cloned().collect()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_signature::KeyPair;
+
+    fn peer() -> PeerId {
+        PeerId::from_bytes(KeyPair::generate().get_public_key().to_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_misbehavior_ledger_bounds_and_ban() {
+        let (peer_a, peer_b, peer_c) = (peer(), peer(), peer());
+        let mut ledger = MisbehaviorLedger::new(NonZeroUsize::new(2).unwrap(), 2, 3, 1000.into());
+        let malformed = |ledger: &mut MisbehaviorLedger, peer_id: &PeerId, now: u64| {
+            ledger.record(
+                peer_id,
+                MisbehaviorKind::MalformedMessage,
+                "bad bytes".to_string(),
+                false,
+                now.into(),
+            )
+        };
+
+        // the offenses out of the window are not counted
+        assert!(!malformed(&mut ledger, &peer_a, 0));
+        assert!(!malformed(&mut ledger, &peer_a, 2000));
+        assert!(!malformed(&mut ledger, &peer_a, 2500));
+        // the third offense within the window gets the peer banned
+        assert!(malformed(&mut ledger, &peer_a, 2600));
+        let records = ledger.get_records(Some(&[peer_a.clone()]));
+        assert_eq!(records[&peer_a].len(), 2);
+        assert!(records[&peer_a][1].banned);
+
+        // a serious offense is banned right away
+        assert!(ledger.record(
+            &peer_b,
+            MisbehaviorKind::InvalidSignature,
+            "operation".to_string(),
+            true,
+            0.into()
+        ));
+
+        // the least recent offender is forgotten
+        malformed(&mut ledger, &peer_c, 0);
+        let records = ledger.get_records(None);
+        assert_eq!(records.len(), 2);
+        assert!(!records.contains_key(&peer_a));
+    }
+}
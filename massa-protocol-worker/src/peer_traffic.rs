@@ -0,0 +1,287 @@
+//! Traffic of the connected peers.
+//!
+//! The message dispatch layer counts the messages and bytes received from each peer, the send
+//! path the bytes sent to it, and the handlers the invalid messages and the blocks it relayed.
+//! The counters of a peer are atomics shared by the network threads, so that counting a message
+//! never takes the lock of the peer DB next to which they are kept. They are forgotten once the
+//! peer is disconnected.
+//!
+//! The peers that negotiated the padding in their handshake are kept next to the counters: the
+//! peer management messages sent to them are padded with random bytes to a multiple of the
+//! bucket size, so that an observer can't tell the messages apart by their size. So are the
+//! peers that negotiated the framing of the peer management messages, which the padding needs.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use massa_models::{block_id::BlockId, slot::Slot};
+use massa_protocol_exports::PeerTrafficStats;
+use parking_lot::{Mutex, RwLock};
+use peernet::peer_id::PeerId;
+use rand::{Rng, RngCore};
+
+use crate::messages::split_message_id;
+
+/// Type of a message, deduced from its id
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageCategory {
+    Block,
+    Endorsement,
+    Operation,
+    PeerManagement,
+}
+
+impl MessageCategory {
+    pub fn from_id(id: u64) -> Self {
+        split_message_id(id).0
+    }
+}
+
+/// Counters of one peer
+#[derive(Default)]
+pub struct PeerCounters {
+    /// messages received, by `MessageCategory`
+    messages: [AtomicU64; 4],
+    bytes_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    invalid_messages: AtomicU64,
+    last_block: Mutex<Option<(BlockId, Slot)>>,
+}
+
+impl PeerCounters {
+    /// Count the content of a message serialized for the peer
+    pub fn record_sent(&self, bytes: usize) {
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> PeerTrafficStats {
+        let messages =
+            |category: MessageCategory| self.messages[category as usize].load(Ordering::Relaxed);
+        PeerTrafficStats {
+            block_messages: messages(MessageCategory::Block),
+            endorsement_messages: messages(MessageCategory::Endorsement),
+            operation_messages: messages(MessageCategory::Operation),
+            peer_management_messages: messages(MessageCategory::PeerManagement),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            invalid_messages: self.invalid_messages.load(Ordering::Relaxed),
+            last_block: *self.last_block.lock(),
+        }
+    }
+}
+
+/// Time during which the padding and the framing of a peer are kept before its connection is
+/// registered
+const NEGOTIATION_REGISTRATION_DELAY: Duration = Duration::from_secs(60);
+
+/// Appends random bytes to the message written in `buffer` from `start`, so that its length
+/// becomes a multiple of `bucket_size`. A random number of bytes up to a bucket is added before
+/// rounding, so that messages of the same length don't always end up in the same bucket.
+pub fn pad_to_bucket(buffer: &mut Vec<u8>, start: usize, bucket_size: u64) {
+    if bucket_size == 0 {
+        return;
+    }
+    let mut rng = rand::thread_rng();
+    let length = (buffer.len() - start) as u64 + rng.gen_range(0..bucket_size);
+    let padded_length = (length + bucket_size - 1) / bucket_size * bucket_size;
+    let padding_start = buffer.len();
+    buffer.resize(start + padded_length as usize, 0);
+    rng.fill_bytes(&mut buffer[padding_start..]);
+}
+
+/// Counters of all the connected peers, shared by the clones
+#[derive(Clone, Default)]
+pub struct PeerTraffic {
+    peers: Arc<RwLock<HashMap<PeerId, Arc<PeerCounters>>>>,
+    /// bucket size of the peers whose messages are padded, with the time it was negotiated
+    padding: Arc<RwLock<HashMap<PeerId, (u64, Instant)>>>,
+    /// peers whose peer management messages are framed, with the time it was negotiated
+    framing: Arc<RwLock<HashMap<PeerId, Instant>>>,
+}
+
+impl PeerTraffic {
+    /// Counters of a peer, created at its first message
+    pub fn counters(&self, peer_id: &PeerId) -> Arc<PeerCounters> {
+        if let Some(counters) = self.peers.read().get(peer_id) {
+            return counters.clone();
+        }
+        self.peers
+            .write()
+            .entry(peer_id.clone())
+            .or_default()
+            .clone()
+    }
+
+    /// Count a message received from a peer, with the size of its content
+    pub fn record_received(&self, peer_id: &PeerId, category: MessageCategory, bytes: usize) {
+        let counters = self.counters(peer_id);
+        counters.messages[category as usize].fetch_add(1, Ordering::Relaxed);
+        counters
+            .bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Count a message of a peer that was oversized, malformed or badly signed
+    pub fn record_invalid(&self, peer_id: &PeerId) {
+        self.counters(peer_id)
+            .invalid_messages
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record the last new block header relayed by a peer
+    pub fn record_block(&self, peer_id: &PeerId, block_id: BlockId, slot: Slot) {
+        *self.counters(peer_id).last_block.lock() = Some((block_id, slot));
+    }
+
+    /// Traffic of a peer, zero if it sent nothing yet
+    pub fn get(&self, peer_id: &PeerId) -> PeerTrafficStats {
+        self.peers
+            .read()
+            .get(peer_id)
+            .map(|counters| counters.stats())
+            .unwrap_or_default()
+    }
+
+    /// Pad the peer management messages sent to a peer, whose handshake negotiated it
+    pub fn start_padding(&self, peer_id: &PeerId, bucket_size: u64) {
+        self.padding
+            .write()
+            .insert(peer_id.clone(), (bucket_size, Instant::now()));
+    }
+
+    /// The new connection with a peer doesn't use padding
+    pub fn stop_padding(&self, peer_id: &PeerId) {
+        self.padding.write().remove(peer_id);
+    }
+
+    /// Bucket size to which the messages sent to a peer are padded, if they are
+    pub fn padding(&self, peer_id: &PeerId) -> Option<u64> {
+        self.padding
+            .read()
+            .get(peer_id)
+            .map(|(bucket_size, _)| *bucket_size)
+    }
+
+    /// Frame the peer management messages exchanged with a peer, whose handshake negotiated it
+    pub fn start_framing(&self, peer_id: &PeerId) {
+        self.framing.write().insert(peer_id.clone(), Instant::now());
+    }
+
+    /// The new connection with a peer doesn't frame the peer management messages
+    pub fn stop_framing(&self, peer_id: &PeerId) {
+        self.framing.write().remove(peer_id);
+    }
+
+    /// Whether the peer management messages exchanged with a peer are framed
+    pub fn framed(&self, peer_id: &PeerId) -> bool {
+        self.framing.read().contains_key(peer_id)
+    }
+
+    /// Forget the counters, the padding and the framing of the disconnected peers
+    pub fn retain_connected(&self, is_connected: impl Fn(&PeerId) -> bool) {
+        self.peers
+            .write()
+            .retain(|peer_id, _| is_connected(peer_id));
+        // the padding is negotiated before the connection is registered
+        self.padding.write().retain(|peer_id, (_, negotiated_at)| {
+            is_connected(peer_id) || negotiated_at.elapsed() < NEGOTIATION_REGISTRATION_DELAY
+        });
+        self.framing.write().retain(|peer_id, negotiated_at| {
+            is_connected(peer_id) || negotiated_at.elapsed() < NEGOTIATION_REGISTRATION_DELAY
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use massa_hash::Hash;
+    use massa_models::{block_id::BlockId, slot::Slot};
+    use peernet::{peer_id::PeerId, types::KeyPair};
+
+    use super::{pad_to_bucket, MessageCategory, PeerTraffic};
+    use crate::messages::wire_message_id;
+
+    #[test]
+    fn test_peer_traffic_counters() {
+        let traffic = PeerTraffic::default();
+        let peer_id = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        let block_id = BlockId(Hash::compute_from(b"block"));
+        assert_eq!(traffic.get(&peer_id).bytes_received, 0);
+
+        traffic.record_received(&peer_id, MessageCategory::from_id(0), 100);
+        traffic.record_received(
+            &peer_id,
+            MessageCategory::from_id(wire_message_id(MessageCategory::Endorsement, 0)),
+            20,
+        );
+        traffic.record_received(&peer_id, MessageCategory::from_id(u64::MAX), 5);
+        traffic.record_invalid(&peer_id);
+        traffic.counters(&peer_id).record_sent(40);
+        traffic.record_block(&peer_id, block_id, Slot::new(3, 1));
+        let stats = traffic.get(&peer_id);
+        assert_eq!(stats.block_messages, 1);
+        assert_eq!(stats.endorsement_messages, 1);
+        assert_eq!(stats.operation_messages, 0);
+        assert_eq!(stats.peer_management_messages, 1);
+        assert_eq!(stats.bytes_received, 125);
+        assert_eq!(stats.bytes_sent, 40);
+        assert_eq!(stats.invalid_messages, 1);
+        assert_eq!(stats.last_block, Some((block_id, Slot::new(3, 1))));
+
+        // the counters of a disconnected peer are forgotten
+        traffic.retain_connected(|_| false);
+        assert_eq!(traffic.get(&peer_id), Default::default());
+    }
+
+    #[test]
+    fn test_pad_to_bucket() {
+        for length in [0, 1, 63, 64, 65, 200] {
+            let mut buffer = vec![7; 10 + length];
+            pad_to_bucket(&mut buffer, 10, 64);
+            let padded = buffer.len() - 10;
+            assert_eq!(padded % 64, 0);
+            // at most a bucket is added on top of the rounding
+            assert!(padded >= length && padded < length + 128);
+            assert!(buffer[..10 + length].iter().all(|byte| *byte == 7));
+        }
+
+        // no padding with a bucket size of 0
+        let mut buffer = vec![7; 10];
+        pad_to_bucket(&mut buffer, 0, 0);
+        assert_eq!(buffer.len(), 10);
+    }
+
+    #[test]
+    fn test_peer_padding() {
+        let traffic = PeerTraffic::default();
+        let peer_id = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        assert_eq!(traffic.padding(&peer_id), None);
+        traffic.start_padding(&peer_id, 256);
+        assert_eq!(traffic.padding(&peer_id), Some(256));
+        // kept until the connection is registered
+        traffic.retain_connected(|_| false);
+        assert_eq!(traffic.padding(&peer_id), Some(256));
+        traffic.stop_padding(&peer_id);
+        assert_eq!(traffic.padding(&peer_id), None);
+    }
+
+    #[test]
+    fn test_peer_framing() {
+        let traffic = PeerTraffic::default();
+        let peer_id = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        assert!(!traffic.framed(&peer_id));
+        traffic.start_framing(&peer_id);
+        assert!(traffic.framed(&peer_id));
+        // kept until the connection is registered
+        traffic.retain_connected(|_| false);
+        assert!(traffic.framed(&peer_id));
+        traffic.stop_framing(&peer_id);
+        assert!(!traffic.framed(&peer_id));
+    }
+}
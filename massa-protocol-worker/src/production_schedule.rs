@@ -0,0 +1,107 @@
+//! Our upcoming block and endorsement productions.
+//!
+//! The factories tell the controller the slots at which one of our addresses produces, before
+//! waiting for them. Around these times the low-priority gossip is held: the peer handler skips
+//! its lists of peers and the operation propagation keeps its announcements, so that our block
+//! and endorsements don't wait behind them. The operations queued in the meantime are announced
+//! as soon as the quiet window ends.
+
+use std::{collections::BTreeSet, ops::Bound::Included, sync::Arc};
+
+use massa_models::{slot::Slot, timeslots::get_block_slot_timestamp};
+use massa_protocol_exports::ProtocolConfig;
+use massa_time::MassaTime;
+use parking_lot::RwLock;
+
+/// Times of our productions, shared by the controller and the handlers
+#[derive(Clone)]
+pub struct ProductionSchedule {
+    thread_count: u8,
+    t0: MassaTime,
+    genesis_timestamp: MassaTime,
+    times: Arc<RwLock<BTreeSet<MassaTime>>>,
+}
+
+impl ProductionSchedule {
+    pub fn from_config(config: &ProtocolConfig) -> Self {
+        Self {
+            thread_count: config.thread_count,
+            t0: config.t0,
+            genesis_timestamp: config.genesis_timestamp,
+            times: Default::default(),
+        }
+    }
+
+    /// Record the slots at which we produce a block and endorsements. The blocks are produced
+    /// at the start of their slot, the endorsements half a period before it. The productions
+    /// older than a period are forgotten.
+    pub fn record(&self, block_slots: &[Slot], endorsement_slots: &[Slot], now: MassaTime) {
+        let slot_time = |slot: &Slot| {
+            get_block_slot_timestamp(self.thread_count, self.t0, self.genesis_timestamp, *slot).ok()
+        };
+        let half_t0 = MassaTime::from_millis(self.t0.to_millis() / 2);
+        let mut times = self.times.write();
+        times.extend(block_slots.iter().filter_map(slot_time));
+        times.extend(
+            endorsement_slots
+                .iter()
+                .filter_map(slot_time)
+                .map(|time| time.saturating_sub(half_t0)),
+        );
+        let oldest = now.saturating_sub(self.t0);
+        times.retain(|time| *time >= oldest);
+    }
+
+    /// End of the quiet window `now` is in: the gossip is held from `margin` before each of our
+    /// productions to `margin` after it. Never quiet with a `margin` of 0.
+    pub fn quiet_until(&self, now: MassaTime, margin: MassaTime) -> Option<MassaTime> {
+        if margin.to_millis() == 0 {
+            return None;
+        }
+        self.times
+            .read()
+            .range((
+                Included(now.saturating_sub(margin)),
+                Included(now.saturating_add(margin)),
+            ))
+            .next_back()
+            .map(|time| time.saturating_add(margin))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use massa_models::slot::Slot;
+    use massa_protocol_exports::ProtocolConfig;
+    use massa_time::MassaTime;
+
+    use super::ProductionSchedule;
+
+    #[test]
+    fn test_quiet_windows() {
+        let mut config = ProtocolConfig::default();
+        config.thread_count = 2;
+        config.t0 = MassaTime::from_millis(16000);
+        config.genesis_timestamp = MassaTime::from_millis(0);
+        let schedule = ProductionSchedule::from_config(&config);
+        let margin = MassaTime::from_millis(200);
+        let at = MassaTime::from_millis;
+
+        // block of slot (2, 1) at 40000, endorsements of slot (3, 0) at 40000 too
+        schedule.record(&[Slot::new(2, 1)], &[Slot::new(3, 0)], at(39000));
+        assert_eq!(schedule.quiet_until(at(39700), margin), None);
+        assert_eq!(schedule.quiet_until(at(39800), margin), Some(at(40200)));
+        assert_eq!(schedule.quiet_until(at(40200), margin), Some(at(40200)));
+        assert_eq!(schedule.quiet_until(at(40201), margin), None);
+        // no window without a margin
+        assert_eq!(
+            schedule.quiet_until(at(40000), MassaTime::from_millis(0)),
+            None
+        );
+
+        // the past productions are forgotten
+        schedule.record(&[Slot::new(4, 0)], &[], at(60000));
+        assert_eq!(schedule.quiet_until(at(40000), margin), None);
+        assert_eq!(schedule.quiet_until(at(64100), margin), Some(at(64200)));
+    }
+}
@@ -0,0 +1,244 @@
+//! Capture of the messages received from the network, replayed to reproduce a session.
+//!
+//! When a capture file is configured, every message handed to the handlers is appended to it
+//! with the time of its reception and the peer that sent it. The capture of an operator who
+//! saw a crash or a fork can then be replayed into the handlers of a test harness, in the same
+//! order and at the same times.
+//!
+//! The file starts with `CAPTURE_MAGIC` and the format version, followed by the messages:
+//! reception time (u64, milliseconds), peer id, message id (u64) and length of the content
+//! (u32), all big endian, then the content.
+
+use std::{
+    fs::File,
+    io::{BufReader, ErrorKind, Read, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use massa_protocol_exports::ProtocolError;
+use massa_time::MassaTime;
+use parking_lot::Mutex;
+use peernet::{
+    messages::MessagesHandler as PeerNetMessagesHandler, peer_id::PeerId,
+    types::PUBLIC_KEY_SIZE_BYTES,
+};
+use tracing::warn;
+
+use crate::clock::SharedClock;
+
+/// Start of the capture files
+const CAPTURE_MAGIC: &[u8; 12] = b"MASSACAPTURE";
+
+/// Version of the format of the capture files
+const CAPTURE_VERSION: u8 = 0;
+
+/// Message received from a peer, as captured
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedMessage {
+    /// time of the reception
+    pub timestamp: MassaTime,
+    pub peer_id: PeerId,
+    /// id of the message, as given to the handlers
+    pub id: u64,
+    pub data: Vec<u8>,
+}
+
+/// Appends the messages received from the network to a capture file
+pub struct SessionRecorder {
+    file: Mutex<File>,
+    clock: SharedClock,
+}
+
+pub type SharedSessionRecorder = Arc<SessionRecorder>;
+
+impl SessionRecorder {
+    /// Create the capture file at `path`, replacing an existing one
+    pub fn create(path: &Path, clock: SharedClock) -> Result<SharedSessionRecorder, ProtocolError> {
+        let mut file = File::create(path)?;
+        file.write_all(CAPTURE_MAGIC)?;
+        file.write_all(&[CAPTURE_VERSION])?;
+        Ok(Arc::new(SessionRecorder {
+            file: Mutex::new(file),
+            clock,
+        }))
+    }
+
+    /// Append a message received from `peer_id`. Each message is written at once, so that the
+    /// capture is complete up to the last message if the node crashes.
+    pub fn record(&self, peer_id: &PeerId, id: u64, data: &[u8]) {
+        let mut bytes = Vec::with_capacity(8 + PUBLIC_KEY_SIZE_BYTES + 8 + 4 + data.len());
+        bytes.extend(self.clock.now().to_millis().to_be_bytes());
+        bytes.extend(peer_id.to_bytes());
+        bytes.extend(id.to_be_bytes());
+        bytes.extend((data.len() as u32).to_be_bytes());
+        bytes.extend(data);
+        if let Err(err) = self.file.lock().write_all(&bytes) {
+            warn!("could not capture a message from {}: {}", peer_id, err);
+        }
+    }
+}
+
+fn read_exact_or_end<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<bool, ProtocolError> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Read the messages of a capture file. A message truncated by a crash of the node ends the
+/// capture.
+pub fn read_capture(path: &Path) -> Result<Vec<CapturedMessage>, ProtocolError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut header = [0u8; CAPTURE_MAGIC.len() + 1];
+    if !read_exact_or_end(&mut reader, &mut header)?
+        || &header[..CAPTURE_MAGIC.len()] != CAPTURE_MAGIC
+    {
+        return Err(ProtocolError::GeneralProtocolError(format!(
+            "{} is not a capture file",
+            path.display()
+        )));
+    }
+    if header[CAPTURE_MAGIC.len()] != CAPTURE_VERSION {
+        return Err(ProtocolError::GeneralProtocolError(format!(
+            "unsupported version {} of the capture file",
+            header[CAPTURE_MAGIC.len()]
+        )));
+    }
+    let mut messages = Vec::new();
+    loop {
+        let mut head = [0u8; 8 + PUBLIC_KEY_SIZE_BYTES + 8 + 4];
+        if !read_exact_or_end(&mut reader, &mut head)? {
+            break;
+        }
+        let (timestamp, rest) = head.split_at(8);
+        let (peer_id, rest) = rest.split_at(PUBLIC_KEY_SIZE_BYTES);
+        let (id, len) = rest.split_at(8);
+        let mut data = vec![0u8; u32::from_be_bytes(len.try_into().unwrap()) as usize];
+        if !read_exact_or_end(&mut reader, &mut data)? {
+            break;
+        }
+        messages.push(CapturedMessage {
+            timestamp: MassaTime::from_millis(u64::from_be_bytes(timestamp.try_into().unwrap())),
+            peer_id: PeerId::from_bytes(peer_id.try_into().unwrap()).map_err(|err| {
+                ProtocolError::GeneralProtocolError(format!(
+                    "invalid peer id in the capture file: {:?}",
+                    err
+                ))
+            })?,
+            id: u64::from_be_bytes(id.try_into().unwrap()),
+            data,
+        });
+    }
+    Ok(messages)
+}
+
+/// Hand the captured messages to `handler` in the order of their reception. `before` is called
+/// with the reception time of each message before it is handled, to move the clock of the test
+/// harness. Stops at the first message refused by the handler.
+pub fn replay_capture<H: PeerNetMessagesHandler>(
+    messages: &[CapturedMessage],
+    handler: &H,
+    mut before: impl FnMut(MassaTime),
+) -> Result<(), ProtocolError> {
+    for (index, message) in messages.iter().enumerate() {
+        before(message.timestamp);
+        handler
+            .handle(message.id, &message.data, &message.peer_id)
+            .map_err(|err| {
+                ProtocolError::GeneralProtocolError(format!(
+                    "captured message {} from {} refused: {:?}",
+                    index, message.peer_id, err
+                ))
+            })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, sync::Arc};
+
+    use massa_time::MassaTime;
+    use parking_lot::Mutex;
+    use peernet::{
+        messages::MessagesHandler as PeerNetMessagesHandler, peer_id::PeerId, types::KeyPair,
+    };
+
+    use super::{read_capture, replay_capture, CapturedMessage, SessionRecorder};
+    use crate::clock::ManualClock;
+
+    #[derive(Clone, Default)]
+    struct CollectingHandler(Arc<Mutex<Vec<(u64, Vec<u8>, PeerId)>>>);
+
+    impl PeerNetMessagesHandler for CollectingHandler {
+        fn deserialize_id<'a>(
+            &self,
+            data: &'a [u8],
+            _peer_id: &PeerId,
+        ) -> peernet::error::PeerNetResult<(&'a [u8], u64)> {
+            Ok((data, 0))
+        }
+
+        fn handle(
+            &self,
+            id: u64,
+            data: &[u8],
+            peer_id: &PeerId,
+        ) -> peernet::error::PeerNetResult<()> {
+            self.0.lock().push((id, data.to_vec(), peer_id.clone()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_capture_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture");
+        let clock = ManualClock::new(MassaTime::from_millis(1000));
+        let first = PeerId::from_public_key(KeyPair::generate().get_public_key());
+        let second = PeerId::from_public_key(KeyPair::generate().get_public_key());
+
+        let recorder = SessionRecorder::create(&path, clock.clone()).unwrap();
+        recorder.record(&first, 3, b"block");
+        clock.advance(std::time::Duration::from_millis(500));
+        recorder.record(&second, 12, b"");
+        // a message cut by a crash is dropped
+        recorder.file.lock().write_all(&[0, 1, 2]).unwrap();
+
+        let messages = read_capture(&path).unwrap();
+        assert_eq!(
+            messages,
+            vec![
+                CapturedMessage {
+                    timestamp: MassaTime::from_millis(1000),
+                    peer_id: first.clone(),
+                    id: 3,
+                    data: b"block".to_vec(),
+                },
+                CapturedMessage {
+                    timestamp: MassaTime::from_millis(1500),
+                    peer_id: second.clone(),
+                    id: 12,
+                    data: Vec::new(),
+                },
+            ]
+        );
+
+        let handler = CollectingHandler::default();
+        let mut times = Vec::new();
+        replay_capture(&messages, &handler, |time| times.push(time)).unwrap();
+        assert_eq!(
+            times,
+            vec![MassaTime::from_millis(1000), MassaTime::from_millis(1500)]
+        );
+        assert_eq!(
+            *handler.0.lock(),
+            vec![(3, b"block".to_vec(), first), (12, Vec::new(), second)]
+        );
+
+        std::fs::write(&path, b"not a capture").unwrap();
+        assert!(read_capture(&path).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+//! Replay protection of the messages exchanged after the handshake.
+//!
+//! When both sides of a connection support it, each message is prefixed by a sequence number.
+//! The numbering of each direction starts from the random challenge sent by the receiver in
+//! the handshake, so the messages captured in a previous session don't match the numbers
+//! expected in a new one. The receiver rejects the numbers already received or too old.
+
+use std::{
+    collections::HashMap,
+    ops::Bound::Included,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use massa_serialization::{DeserializeError, Deserializer, U64VarIntDeserializer};
+use parking_lot::Mutex;
+use peernet::{
+    error::{PeerNetError, PeerNetResult},
+    peer_id::PeerId,
+};
+use tracing::warn;
+
+use crate::messages::MessagesSerializer;
+
+/// Number of sequence numbers before the highest one received that are still accepted.
+/// Consensus messages use their own channel and can overtake thousands of gossip messages.
+const REPLAY_WINDOW_SIZE: u64 = 4096;
+
+/// Maximum difference between a sequence number and the highest one received.
+/// Numbers are only skipped when a send fails, a bigger jump is a message of another session.
+const MAX_SEQUENCE_JUMP: u64 = 1 << 32;
+
+/// Time during which the session of a peer is kept before its connection is registered
+const SESSION_REGISTRATION_DELAY: Duration = Duration::from_secs(60);
+
+/// Sequence number of the first message sent on a connection to the peer that sent `challenge`
+pub(crate) fn first_sequence(challenge: &[u8; 32]) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&challenge[..8]);
+    u64::from_be_bytes(bytes)
+}
+
+/// Sequence numbers received from a peer, relative to the first one of the session
+struct ReplayWindow {
+    first: u64,
+    /// highest offset received
+    highest: Option<u64>,
+    /// bit of each offset of the window, at the position `offset % REPLAY_WINDOW_SIZE`
+    received: [u64; (REPLAY_WINDOW_SIZE / 64) as usize],
+}
+
+impl ReplayWindow {
+    fn new(first: u64) -> Self {
+        Self {
+            first,
+            highest: None,
+            received: [0; (REPLAY_WINDOW_SIZE / 64) as usize],
+        }
+    }
+
+    fn bit(offset: u64) -> (usize, u64) {
+        let position = offset % REPLAY_WINDOW_SIZE;
+        ((position / 64) as usize, 1 << (position % 64))
+    }
+
+    /// Records a received sequence number, fails if it was already received or is too old
+    fn check(&mut self, sequence: u64) -> Result<(), String> {
+        let offset = sequence.wrapping_sub(self.first);
+        let Some(highest) = self.highest else {
+            if offset >= MAX_SEQUENCE_JUMP {
+                return Err(format!("unexpected first sequence number {}", sequence));
+            }
+            let (word, mask) = Self::bit(offset);
+            self.received[word] |= mask;
+            self.highest = Some(offset);
+            return Ok(());
+        };
+        if offset > highest {
+            if offset - highest >= MAX_SEQUENCE_JUMP {
+                return Err(format!("unexpected sequence number {}", sequence));
+            }
+            // forget the offsets leaving the window
+            if offset - highest >= REPLAY_WINDOW_SIZE {
+                self.received = [0; (REPLAY_WINDOW_SIZE / 64) as usize];
+            } else {
+                for skipped in highest + 1..offset {
+                    let (word, mask) = Self::bit(skipped);
+                    self.received[word] &= !mask;
+                }
+            }
+            let (word, mask) = Self::bit(offset);
+            self.received[word] |= mask;
+            self.highest = Some(offset);
+            return Ok(());
+        }
+        if highest - offset >= REPLAY_WINDOW_SIZE {
+            return Err(format!("sequence number {} is too old", sequence));
+        }
+        let (word, mask) = Self::bit(offset);
+        if self.received[word] & mask != 0 {
+            return Err(format!("sequence number {} already received", sequence));
+        }
+        self.received[word] |= mask;
+        Ok(())
+    }
+}
+
+struct ReplaySession {
+    next_send: u64,
+    receive_window: ReplayWindow,
+    started_at: Instant,
+}
+
+/// Sequence numbers of the connections that use them, shared by all the clones.
+#[derive(Clone)]
+pub struct ReplayGuard {
+    sessions: Arc<Mutex<HashMap<PeerId, ReplaySession>>>,
+    sequence_deserializer: U64VarIntDeserializer,
+}
+
+impl Default for ReplayGuard {
+    fn default() -> Self {
+        Self {
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            sequence_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+        }
+    }
+}
+
+impl ReplayGuard {
+    /// Numbers the messages of a new connection with `peer_id`, replacing its previous session
+    pub fn start_session(&self, peer_id: &PeerId, first_sent: u64, first_received: u64) {
+        self.sessions.lock().insert(
+            peer_id.clone(),
+            ReplaySession {
+                next_send: first_sent,
+                receive_window: ReplayWindow::new(first_received),
+                started_at: Instant::now(),
+            },
+        );
+    }
+
+    /// The new connection with `peer_id` doesn't use sequence numbers
+    pub fn end_session(&self, peer_id: &PeerId) {
+        self.sessions.lock().remove(peer_id);
+    }
+
+    /// Drops the sessions of the peers that aren't connected anymore
+    pub fn retain_connected(&self, is_connected: impl Fn(&PeerId) -> bool) {
+        self.sessions.lock().retain(|peer_id, session| {
+            is_connected(peer_id) || session.started_at.elapsed() < SESSION_REGISTRATION_DELAY
+        });
+    }
+
+    /// Serializer numbering the next message sent to `peer_id`, if its connection uses
+    /// sequence numbers
+    pub fn sequenced(
+        &self,
+        peer_id: &PeerId,
+        serializer: &MessagesSerializer,
+    ) -> Option<MessagesSerializer> {
+        let mut sessions = self.sessions.lock();
+        let session = sessions.get_mut(peer_id)?;
+        let sequence = session.next_send;
+        session.next_send = sequence.wrapping_add(1);
+        Some(serializer.with_sequence(sequence))
+    }
+
+    /// Checks and strips the sequence number of a message received from `peer_id`, if its
+    /// connection uses sequence numbers
+    pub fn check<'a>(&self, data: &'a [u8], peer_id: &PeerId) -> PeerNetResult<&'a [u8]> {
+        let mut sessions = self.sessions.lock();
+        let Some(session) = sessions.get_mut(peer_id) else {
+            return Ok(data);
+        };
+        let (rest, sequence) = self
+            .sequence_deserializer
+            .deserialize::<DeserializeError>(data)
+            .map_err(|err| {
+                PeerNetError::HandlerError.error(
+                    "ReplayGuard",
+                    Some(format!("Failed to deserialize sequence number: {}", err)),
+                )
+            })?;
+        session.receive_window.check(sequence).map_err(|err| {
+            warn!("peer {} sent a replayed message: {}", peer_id, err);
+            PeerNetError::HandlerError
+                .error("ReplayGuard", Some(format!("Replayed message: {}", err)))
+        })?;
+        Ok(rest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use massa_serialization::{Serializer, U64VarIntSerializer};
+    use massa_signature::KeyPair;
+    use peernet::peer_id::PeerId;
+
+    use super::{ReplayGuard, ReplayWindow, MAX_SEQUENCE_JUMP, REPLAY_WINDOW_SIZE};
+
+    #[test]
+    fn test_replay_window_rejects_reused_and_old_numbers() {
+        let first = u64::MAX - 2;
+        let mut window = ReplayWindow::new(first);
+        window.check(first).unwrap();
+        window.check(first.wrapping_add(3)).unwrap();
+        // reordered by the priority channels
+        window.check(first.wrapping_add(1)).unwrap();
+        assert!(window.check(first.wrapping_add(1)).is_err());
+        assert!(window.check(first.wrapping_add(3)).is_err());
+
+        window
+            .check(first.wrapping_add(REPLAY_WINDOW_SIZE + 1))
+            .unwrap();
+        // skipped but still in the window
+        window.check(first.wrapping_add(2)).unwrap();
+        assert!(window.check(first.wrapping_add(1)).is_err());
+        // regressing out of the window
+        assert!(window.check(first).is_err());
+        // numbers of another session
+        assert!(window.check(first.wrapping_sub(10)).is_err());
+        assert!(window
+            .check(first.wrapping_add(2 * MAX_SEQUENCE_JUMP))
+            .is_err());
+    }
+
+    #[test]
+    fn test_replay_guard_sessions() {
+        let guard = ReplayGuard::default();
+        let peer_id = PeerId::from_bytes(KeyPair::generate().get_public_key().to_bytes()).unwrap();
+        let sequenced = |sequence: u64| {
+            let mut data = Vec::new();
+            U64VarIntSerializer::new()
+                .serialize(&sequence, &mut data)
+                .unwrap();
+            data.push(42);
+            data
+        };
+
+        // no session: the data is left untouched
+        assert_eq!(guard.check(&[42], &peer_id).unwrap(), &[42]);
+
+        guard.start_session(&peer_id, 0, 1000);
+        assert_eq!(guard.check(&sequenced(1000), &peer_id).unwrap(), &[42]);
+        assert!(guard.check(&sequenced(1000), &peer_id).is_err());
+        guard.check(&sequenced(1001), &peer_id).unwrap();
+
+        // a new connection starts from another number, the old messages are refused
+        guard.start_session(&peer_id, 0, 1_000_000_000_000);
+        assert!(guard.check(&sequenced(1001), &peer_id).is_err());
+        guard
+            .check(&sequenced(1_000_000_000_000), &peer_id)
+            .unwrap();
+
+        guard.end_session(&peer_id);
+        assert_eq!(guard.check(&[42], &peer_id).unwrap(), &[42]);
+    }
+}
@@ -2,10 +2,20 @@
 
 //! Optimized batch signature verifier
 
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    thread::JoinHandle,
+};
+
+use crossbeam::channel::bounded;
 use massa_hash::Hash;
 use massa_protocol_exports::ProtocolError;
 use massa_signature::{verify_signature_batch, PublicKey, Signature};
+use parking_lot::{Condvar, Mutex};
+use peernet::peer_id::PeerId;
 use rayon::{prelude::ParallelIterator, slice::ParallelSlice};
+use tracing::debug;
 
 //TODO: Benchmark
 /// Limit for small batch optimization
@@ -28,3 +38,312 @@ pub fn verify_sigs_batch(ops: &[(Hash, Signature, PublicKey)]) -> Result<(), Pro
         .try_for_each(verify_signature_batch)
         .map_err(|_err| ProtocolError::WrongSignature)
 }
+
+/// Called with the result of the verification of a batch
+type VerificationCallback = Box<dyn FnOnce(Result<(), ProtocolError>) + Send>;
+
+/// Batch of signatures received from a peer, waiting to be verified
+struct VerificationJob {
+    signatures: Vec<(Hash, Signature, PublicKey)>,
+    on_done: VerificationCallback,
+    /// false if a thread is waiting for the result: the job is never dropped
+    droppable: bool,
+}
+
+#[derive(Default)]
+struct Lane {
+    /// batches waiting to be verified, by peer
+    jobs: HashMap<PeerId, VecDeque<VerificationJob>>,
+    /// peers with waiting batches, in the order they will be served
+    turns: VecDeque<PeerId>,
+}
+
+impl Lane {
+    /// Oldest batch of the next peer in turn
+    fn pop(&mut self) -> Option<VerificationJob> {
+        let peer_id = self.turns.pop_front()?;
+        let jobs = self.jobs.get_mut(&peer_id)?;
+        let job = jobs.pop_front();
+        if jobs.is_empty() {
+            self.jobs.remove(&peer_id);
+        } else {
+            self.turns.push_back(peer_id);
+        }
+        job
+    }
+
+    fn drain(&mut self) -> impl Iterator<Item = VerificationJob> + '_ {
+        self.turns.clear();
+        self.jobs.drain().flat_map(|(_, jobs)| jobs)
+    }
+}
+
+#[derive(Default)]
+struct JobQueue {
+    normal: Lane,
+    /// batches of the quarantined peers, only served when the normal lane is empty
+    quarantine: Lane,
+    stopped: bool,
+}
+
+impl JobQueue {
+    fn pop(&mut self) -> Option<VerificationJob> {
+        self.normal.pop().or_else(|| self.quarantine.pop())
+    }
+}
+
+/// Pool of threads verifying the signatures of the operations and endorsements received from
+/// peers, so that the handler threads stay free to dispatch the other messages.
+///
+/// Each peer has its own queue of batches and the peers are served in turn, so that a peer
+/// sending many batches cannot delay the others. When the queue of a peer is full, its oldest
+/// batch is dropped. The batches of the quarantined peers wait in a second lane, served when
+/// no other batch is waiting. With no thread, the batches are verified right away in the
+/// calling thread.
+#[derive(Clone)]
+pub struct SigVerifierPool {
+    queue: Arc<(Mutex<JobQueue>, Condvar)>,
+    threads: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    thread_count: usize,
+    max_pending_per_peer: usize,
+}
+
+impl SigVerifierPool {
+    /// Starts the verification threads
+    ///
+    /// # Arguments
+    /// * `thread_count`: number of verification threads (0 to verify in the calling thread)
+    /// * `max_pending_per_peer`: maximum number of batches of a peer waiting to be verified
+    pub fn new(thread_count: usize, max_pending_per_peer: usize) -> Self {
+        let queue = Arc::new((Mutex::new(JobQueue::default()), Condvar::new()));
+        let threads = (0..thread_count)
+            .map(|index| {
+                let queue = queue.clone();
+                std::thread::Builder::new()
+                    .name(format!("protocol-sig-verifier-{}", index))
+                    .spawn(move || run_verifier(&queue))
+                    .expect("OS failed to start signature verification thread")
+            })
+            .collect();
+        Self {
+            queue,
+            threads: Arc::new(Mutex::new(threads)),
+            thread_count,
+            max_pending_per_peer,
+        }
+    }
+
+    /// Queues a batch of signatures received from a peer. `on_done` is called with the result
+    /// by a verification thread, or with an error other than `WrongSignature` if the batch is
+    /// dropped before being verified.
+    pub fn submit(
+        &self,
+        peer_id: &PeerId,
+        signatures: Vec<(Hash, Signature, PublicKey)>,
+        on_done: impl FnOnce(Result<(), ProtocolError>) + Send + 'static,
+    ) {
+        self.push(peer_id, signatures, Box::new(on_done), true, false);
+    }
+
+    /// Same as `submit` for a quarantined peer: the batch is only verified when no batch of
+    /// the other peers is waiting
+    pub fn submit_quarantined(
+        &self,
+        peer_id: &PeerId,
+        signatures: Vec<(Hash, Signature, PublicKey)>,
+        on_done: impl FnOnce(Result<(), ProtocolError>) + Send + 'static,
+    ) {
+        self.push(peer_id, signatures, Box::new(on_done), true, true);
+    }
+
+    fn push(
+        &self,
+        peer_id: &PeerId,
+        signatures: Vec<(Hash, Signature, PublicKey)>,
+        on_done: VerificationCallback,
+        droppable: bool,
+        quarantined: bool,
+    ) {
+        if self.thread_count == 0 {
+            on_done(verify_sigs_batch(&signatures));
+            return;
+        }
+        let (lock, condvar) = &*self.queue;
+        let dropped = {
+            let mut queue = lock.lock();
+            if queue.stopped {
+                drop(queue);
+                on_done(Err(ProtocolError::GeneralProtocolError(
+                    "signature verification pool is stopped".to_string(),
+                )));
+                return;
+            }
+            let lane = if quarantined {
+                &mut queue.quarantine
+            } else {
+                &mut queue.normal
+            };
+            if !lane.jobs.contains_key(peer_id) {
+                lane.turns.push_back(peer_id.clone());
+            }
+            let jobs = lane.jobs.entry(peer_id.clone()).or_default();
+            jobs.push_back(VerificationJob {
+                signatures,
+                on_done,
+                droppable,
+            });
+            if jobs.len() > self.max_pending_per_peer {
+                jobs.iter()
+                    .position(|job| job.droppable)
+                    .and_then(|index| jobs.remove(index))
+            } else {
+                None
+            }
+        };
+        condvar.notify_one();
+        if let Some(job) = dropped {
+            debug!(
+                "signature verification queue of peer {} is full: dropped its oldest batch",
+                peer_id
+            );
+            (job.on_done)(Err(ProtocolError::GeneralProtocolError(
+                "signature verification queue of the peer is full".to_string(),
+            )));
+        }
+    }
+
+    /// Verifies a batch of signatures received from a peer, waiting for its turn in the pool
+    pub fn verify_blocking(
+        &self,
+        peer_id: &PeerId,
+        signatures: Vec<(Hash, Signature, PublicKey)>,
+    ) -> Result<(), ProtocolError> {
+        if self.thread_count == 0 {
+            return verify_sigs_batch(&signatures);
+        }
+        let (sender, receiver) = bounded(1);
+        self.push(
+            peer_id,
+            signatures,
+            Box::new(move |result| {
+                let _ = sender.send(result);
+            }),
+            false,
+            false,
+        );
+        receiver.recv().unwrap_or_else(|_| {
+            Err(ProtocolError::GeneralProtocolError(
+                "signature verification pool is stopped".to_string(),
+            ))
+        })
+    }
+
+    /// Stops the verification threads. The batches still queued are dropped.
+    pub fn stop(&self) {
+        let (lock, condvar) = &*self.queue;
+        let dropped: Vec<VerificationJob> = {
+            let mut queue = lock.lock();
+            queue.stopped = true;
+            let mut dropped: Vec<VerificationJob> = queue.normal.drain().collect();
+            dropped.extend(queue.quarantine.drain());
+            dropped
+        };
+        condvar.notify_all();
+        for job in dropped {
+            (job.on_done)(Err(ProtocolError::GeneralProtocolError(
+                "signature verification pool is stopped".to_string(),
+            )));
+        }
+        for thread in self.threads.lock().drain(..) {
+            thread
+                .join()
+                .expect("Failed to join signature verification thread");
+        }
+    }
+}
+
+fn run_verifier(queue: &(Mutex<JobQueue>, Condvar)) {
+    let (lock, condvar) = queue;
+    loop {
+        let job = {
+            let mut queue = lock.lock();
+            loop {
+                if queue.stopped {
+                    return;
+                }
+                if let Some(job) = queue.pop() {
+                    break job;
+                }
+                condvar.wait(&mut queue);
+            }
+        };
+        (job.on_done)(verify_sigs_batch(&job.signatures));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_signature::KeyPair;
+
+    fn peer() -> PeerId {
+        PeerId::from_bytes(KeyPair::generate().get_public_key().to_bytes()).unwrap()
+    }
+
+    fn signed_batch(count: usize, valid: bool) -> Vec<(Hash, Signature, PublicKey)> {
+        (0..count)
+            .map(|index| {
+                let keypair = KeyPair::generate();
+                let hash = Hash::compute_from(&index.to_be_bytes());
+                let signature = keypair.sign(&hash).unwrap();
+                let hash = if valid {
+                    hash
+                } else {
+                    Hash::compute_from(b"other")
+                };
+                (hash, signature, keypair.get_public_key())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sig_verifier_pool_round_robin() {
+        let (peer_a, peer_b, peer_c) = (peer(), peer(), peer());
+        let mut queue = JobQueue::default();
+        for (peer_id, count) in [(&peer_a, 3), (&peer_c, 2), (&peer_b, 1)] {
+            let lane = if *peer_id == peer_c {
+                &mut queue.quarantine
+            } else {
+                &mut queue.normal
+            };
+            lane.turns.push_back(peer_id.clone());
+            for _ in 0..count {
+                lane.jobs
+                    .entry(peer_id.clone())
+                    .or_default()
+                    .push_back(VerificationJob {
+                        signatures: signed_batch(count, true),
+                        on_done: Box::new(|_| {}),
+                        droppable: true,
+                    });
+            }
+        }
+        // the batch of peer B is served right after the first batch of peer A, the batches of
+        // the quarantined peer C last
+        let served: Vec<usize> = std::iter::from_fn(|| queue.pop())
+            .map(|job| job.signatures.len())
+            .collect();
+        assert_eq!(served, vec![3, 1, 3, 3, 2, 2]);
+
+        let pool = SigVerifierPool::new(2, 10);
+        assert!(pool.verify_blocking(&peer_a, signed_batch(5, true)).is_ok());
+        assert!(matches!(
+            pool.verify_blocking(&peer_a, signed_batch(5, false)),
+            Err(ProtocolError::WrongSignature)
+        ));
+        pool.stop();
+        assert!(pool
+            .verify_blocking(&peer_a, signed_batch(1, true))
+            .is_err());
+    }
+}
@@ -792,3 +792,114 @@ fn test_protocol_propagates_block_to_node_who_asked_for_operations_and_only_head
         },
     )
 }
+
+#[test]
+#[serial]
+fn test_protocol_relays_header_before_integration_and_not_again_after() {
+    let default_panic = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_panic(info);
+        std::process::exit(1);
+    }));
+
+    let mut protocol_config = ProtocolConfig::default();
+    protocol_config.thread_count = 2;
+    protocol_config.initial_peers = "./src/tests/empty_initial_peers.json".to_string().into();
+    protocol_config.relay_headers_before_integration = true;
+    protocol_test_with_storage(
+        &protocol_config,
+        move |mut network_controller,
+              protocol_controller,
+              protocol_manager,
+              mut consensus_event_receiver,
+              pool_event_receiver,
+              mut storage| {
+            //1. Create 2 nodes
+            let node_a_keypair = KeyPair::generate();
+            let node_b_keypair = KeyPair::generate();
+            let (node_a_peer_id, node_a) = network_controller.create_fake_connection(
+                PeerId::from_bytes(node_a_keypair.get_public_key().to_bytes()).unwrap(),
+            );
+            let (_node_b_peer_id, node_b) = network_controller.create_fake_connection(
+                PeerId::from_bytes(node_b_keypair.get_public_key().to_bytes()).unwrap(),
+            );
+
+            //2. Create a block coming from node a.
+            let block = tools::create_block(&node_a_keypair);
+            //end setup
+
+            //3. Node A send the block header to us
+            network_controller
+                .send_from_peer(
+                    &node_a_peer_id,
+                    Message::Block(Box::new(BlockMessage::BlockHeader(
+                        block.content.header.clone(),
+                    ))),
+                )
+                .unwrap();
+
+            //4. Check that we sent the block header to consensus
+            loop {
+                match consensus_event_receiver.wait_command(
+                    MassaTime::from_millis(100),
+                    |command| match command {
+                        MockConsensusControllerMessage::RegisterBlockHeader {
+                            block_id, ..
+                        } => {
+                            assert_eq!(block_id, block.id);
+                            Some(())
+                        }
+                        _evt => None,
+                    },
+                ) {
+                    Some(()) => {
+                        break;
+                    }
+                    None => {
+                        continue;
+                    }
+                }
+            }
+
+            //5. Node B should receive the header before the block is integrated
+            let msg = node_b
+                .recv_timeout(Duration::from_millis(1500))
+                .expect("Node b should receive the block header");
+            match msg {
+                Message::Block(block_msg) => match *block_msg {
+                    BlockMessage::BlockHeader(header) => {
+                        assert_eq!(header.id, block.content.header.id);
+                    }
+                    _ => {
+                        panic!("Node b should receive the block header");
+                    }
+                },
+                _ => {
+                    panic!("Node b should receive the block header");
+                }
+            }
+
+            //6. Consensus inform us that the block has been integrated
+            storage.store_block(block.clone());
+            protocol_controller
+                .integrated_block(block.id, storage)
+                .unwrap();
+
+            //7. Nobody should receive the header again
+            let _ = node_a
+                .recv_timeout(Duration::from_millis(1500))
+                .expect_err("Node a shouldn't receive the block header");
+            let _ = node_b
+                .recv_timeout(Duration::from_millis(1500))
+                .expect_err("Node b shouldn't receive the block header twice");
+
+            (
+                network_controller,
+                protocol_controller,
+                protocol_manager,
+                consensus_event_receiver,
+                pool_event_receiver,
+            )
+        },
+    )
+}
@@ -0,0 +1,93 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+use massa_pool_exports::test_exports::MockPoolControllerMessage;
+use massa_protocol_exports::{test_exports::tools, ProtocolConfig};
+use massa_signature::KeyPair;
+use peernet::peer_id::PeerId;
+use serial_test::serial;
+
+use crate::{handlers::operation_handler::OperationMessage, messages::Message};
+
+use super::context::protocol_test;
+
+#[test]
+#[serial]
+fn test_protocol_replays_captured_operations() {
+    let default_panic = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_panic(info);
+        std::process::exit(1);
+    }));
+
+    let capture_dir = tempfile::tempdir().unwrap();
+    let capture_file = capture_dir.path().join("capture.bin");
+    let mut protocol_config = ProtocolConfig::default();
+    protocol_config.thread_count = 2;
+    protocol_config.initial_peers = "./src/tests/empty_initial_peers.json".to_string().into();
+    protocol_config.capture_file = Some(capture_file.clone());
+
+    let node_a_keypair = KeyPair::generate();
+    let operation = tools::create_operation_with_expire_period(&node_a_keypair, 1);
+
+    // 1. A node sends an operation while the messages are captured
+    protocol_test(&protocol_config, {
+        let operation = operation.clone();
+        move |mut network_controller,
+              protocol_controller,
+              protocol_manager,
+              consensus_event_receiver,
+              mut pool_event_receiver| {
+            let (node_a_peer_id, _node_a) = network_controller.create_fake_connection(
+                PeerId::from_bytes(node_a_keypair.get_public_key().to_bytes()).unwrap(),
+            );
+            network_controller
+                .send_from_peer(
+                    &node_a_peer_id,
+                    Message::Operation(OperationMessage::Operations(vec![operation])),
+                )
+                .unwrap();
+            pool_event_receiver
+                .wait_command(1000.into(), |evt| match evt {
+                    evt @ MockPoolControllerMessage::AddOperations { .. } => Some(evt),
+                    _ => None,
+                })
+                .expect("the operation didn't reach the pool");
+            (
+                network_controller,
+                protocol_controller,
+                protocol_manager,
+                consensus_event_receiver,
+                pool_event_receiver,
+            )
+        }
+    });
+
+    // 2. The capture is replayed into a fresh node
+    protocol_config.capture_file = None;
+    protocol_test(
+        &protocol_config,
+        move |mut network_controller,
+              protocol_controller,
+              protocol_manager,
+              consensus_event_receiver,
+              mut pool_event_receiver| {
+            network_controller.replay_capture(&capture_file).unwrap();
+            match pool_event_receiver.wait_command(1000.into(), |evt| match evt {
+                evt @ MockPoolControllerMessage::AddOperations { .. } => Some(evt),
+                _ => None,
+            }) {
+                Some(MockPoolControllerMessage::AddOperations { operations, .. }) => {
+                    assert!(operations.get_op_refs().contains(&operation.id));
+                }
+                _ => panic!("the replayed operation didn't reach the pool"),
+            }
+            (
+                network_controller,
+                protocol_controller,
+                protocol_manager,
+                consensus_event_receiver,
+                pool_event_receiver,
+            )
+        },
+    );
+}
@@ -1,9 +1,17 @@
-use std::{collections::HashMap, fs::read_to_string, sync::Arc};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 
 use crate::{
-    connectivity::start_connectivity_thread, create_protocol_controller,
-    handlers::peer_handler::models::PeerDB, manager::ProtocolManagerImpl,
-    messages::MessagesHandler, tests::mock_network::MockNetworkController,
+    audit::ConnectionAuditLog,
+    channels::SheddingSender,
+    clock::SystemClock,
+    connectivity::start_connectivity_thread,
+    create_protocol_controller,
+    handlers::peer_handler::{models::PeerDB, peer_db_lock::PeerDBLock, LocalAnnouncement},
+    keystore::NodeKeystore,
+    manager::ProtocolManagerImpl,
+    messages::{MessageSizeValidator, MessagesHandler},
+    recorder::SessionRecorder,
+    tests::mock_network::MockNetworkController,
 };
 use crossbeam::channel::bounded;
 use massa_consensus_exports::{
@@ -23,7 +31,7 @@ use massa_storage::Storage;
 use parking_lot::RwLock;
 use peernet::types::KeyPair;
 use std::ops::Bound::Included;
-use tracing::{debug, log::warn};
+use tracing::debug;
 
 /// start a new `ProtocolController` from a `ProtocolConfig`
 ///
@@ -44,23 +52,13 @@ pub fn start_protocol_controller_with_mock_network(
     ),
     ProtocolError,
 > {
-    // try to read node keypair from file, otherwise generate it & write to file. Then derive nodeId
-    let keypair = if std::path::Path::is_file(&config.keypair_file) {
-        // file exists: try to load it
-        let keypair_bs58_check_encoded = read_to_string(&config.keypair_file).map_err(|err| {
-            std::io::Error::new(err.kind(), format!("could not load node key file: {}", err))
-        })?;
-        serde_json::from_slice::<KeyPair>(keypair_bs58_check_encoded.as_bytes())?
-    } else {
-        // node file does not exist: generate the key and save it
-        let keypair = KeyPair::generate();
-        if let Err(e) = std::fs::write(&config.keypair_file, serde_json::to_string(&keypair)?) {
-            warn!("could not generate node key file: {}", e);
-        }
-        keypair
-    };
+    // read the node keypair from its keystore, otherwise generate it & save it. Then derive nodeId
+    let keypair = NodeKeystore::new(&config.keypair_file, config.keypair_protection.clone())
+        .load_or_generate()?;
+    let keypair = KeyPair::from_str(&keypair.to_string()).unwrap();
     debug!("starting protocol controller with mock network");
-    let peer_db = Arc::new(RwLock::new(PeerDB::default()));
+    let peer_db = PeerDBLock::shared(PeerDB::default());
+    let clock = SystemClock::shared();
 
     let (sender_operations, receiver_operations) =
         bounded(config.max_size_channel_network_to_operation_handler);
@@ -70,17 +68,39 @@ pub fn start_protocol_controller_with_mock_network(
         bounded(config.max_size_channel_network_to_block_handler);
     let (sender_peers, receiver_peers) = bounded(config.max_size_channel_network_to_peer_handler);
 
+    let (controller, channels) = create_protocol_controller(config.clone());
+
     // Register channels for handlers
+    let sender_peer_cmd = channels.peer_management_handler.0.clone();
     let message_handlers: MessagesHandler = MessagesHandler {
-        sender_blocks: sender_blocks.clone(),
-        sender_endorsements: sender_endorsements.clone(),
-        sender_operations: sender_operations.clone(),
-        sender_peers: sender_peers.clone(),
+        sender_blocks: SheddingSender::blocking("blocks", sender_blocks.clone()),
+        sender_endorsements: SheddingSender::drop_oldest(
+            "endorsements",
+            sender_endorsements.clone(),
+            receiver_endorsements.clone(),
+        ),
+        sender_operations: SheddingSender::drop_oldest(
+            "operations",
+            sender_operations.clone(),
+            receiver_operations.clone(),
+        ),
+        sender_peers: SheddingSender::drop_oldest(
+            "peers",
+            sender_peers.clone(),
+            receiver_peers.clone(),
+        ),
         id_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+        size_validator: MessageSizeValidator::new(&config, sender_peer_cmd),
+        // the mock connections don't number their messages
+        replay_guard: None,
+        recorder: config
+            .capture_file
+            .as_ref()
+            .map(|path| SessionRecorder::create(path, clock.clone()))
+            .transpose()?,
+        traffic: peer_db.traffic().clone(),
     };
 
-    let (controller, channels) = create_protocol_controller(config.clone());
-
     let network_controller = Box::new(MockNetworkController::new(message_handlers.clone()));
 
     let connectivity_thread_handle = start_connectivity_thread(
@@ -94,6 +114,20 @@ pub fn start_protocol_controller_with_mock_network(
         (sender_peers, receiver_peers),
         HashMap::default(),
         peer_db,
+        Arc::new(RwLock::new(
+            LocalAnnouncement::new(
+                keypair.clone(),
+                config.listeners.clone(),
+                config.routable_ip,
+            )
+            .map_err(|err| ProtocolError::GeneralProtocolError(err.to_string()))?,
+        )),
+        ConnectionAuditLog::shared(
+            config.connection_audit_log_size,
+            clock.clone(),
+            channels.event_bus.clone(),
+        ),
+        clock,
         storage,
         channels,
         message_handlers,
@@ -0,0 +1,224 @@
+//! Fault injection in the mock network, driven by a scenario script.
+//!
+//! A scenario is a list of steps, one per line:
+//! `<inbound|outbound> <deliver|drop P%|delay Nms|corrupt P%|kill> [xN]`
+//!
+//! The steps of each direction apply in turn: a step applies to the next `N` messages of its
+//! direction, or to all of them if `xN` is omitted. Inbound messages are the ones sent by the
+//! fake peers to the node, outbound messages the ones sent by the node to the fake peers.
+//! Only the inbound messages can be corrupted: they are the only ones serialized by the mock.
+//! Empty lines and the lines starting with `#` are ignored.
+//!
+//! ```text
+//! # let the announcement through, then cut the connection
+//! inbound deliver x1
+//! inbound kill
+//! ```
+
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Direction of the messages a fault applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// from the fake peers to the node
+    Inbound,
+    /// from the node to the fake peers
+    Outbound,
+}
+
+/// Fault injected in the messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// deliver the messages untouched
+    Deliver,
+    /// drop the given percentage of the messages
+    Drop { percent: u8 },
+    /// delay the messages
+    Delay(Duration),
+    /// flip a random byte of the content of the given percentage of the messages
+    Corrupt { percent: u8 },
+    /// close the connection instead of delivering the message
+    Kill,
+}
+
+/// What the mock network must do with a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultAction {
+    Deliver,
+    Drop,
+    Delay(Duration),
+    /// flip the byte of the content at this index, modulo the size of the content
+    Corrupt(usize),
+    Kill,
+}
+
+#[derive(Debug, Clone)]
+struct FaultStep {
+    fault: Fault,
+    /// number of messages the step still applies to, `None` for all of them
+    remaining: Option<usize>,
+}
+
+/// Steps of a scenario, consumed as the messages go through the mock network
+pub struct FaultScenario {
+    inbound: VecDeque<FaultStep>,
+    outbound: VecDeque<FaultStep>,
+    rng: StdRng,
+}
+
+impl FaultScenario {
+    /// Parses a scenario script. The seed makes the probabilistic faults reproducible.
+    pub fn parse(script: &str, seed: u64) -> Result<Self, String> {
+        let mut scenario = FaultScenario {
+            inbound: VecDeque::new(),
+            outbound: VecDeque::new(),
+            rng: StdRng::seed_from_u64(seed),
+        };
+        for (line_index, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (direction, step) = parse_step(line)
+                .map_err(|err| format!("line {}: {}: {}", line_index + 1, err, line))?;
+            match direction {
+                Direction::Inbound => scenario.inbound.push_back(step),
+                Direction::Outbound => scenario.outbound.push_back(step),
+            }
+        }
+        Ok(scenario)
+    }
+
+    /// Decides the fate of the next message of a direction
+    pub fn next_action(&mut self, direction: Direction) -> FaultAction {
+        let steps = match direction {
+            Direction::Inbound => &mut self.inbound,
+            Direction::Outbound => &mut self.outbound,
+        };
+        let Some(step) = steps.front_mut() else {
+            return FaultAction::Deliver;
+        };
+        let fault = step.fault;
+        if let Some(remaining) = &mut step.remaining {
+            *remaining -= 1;
+            if *remaining == 0 {
+                steps.pop_front();
+            }
+        }
+        match fault {
+            Fault::Deliver => FaultAction::Deliver,
+            Fault::Drop { percent } if self.rng.gen_range(0..100) < percent => FaultAction::Drop,
+            Fault::Corrupt { percent } if self.rng.gen_range(0..100) < percent => {
+                FaultAction::Corrupt(self.rng.gen())
+            }
+            Fault::Drop { .. } | Fault::Corrupt { .. } => FaultAction::Deliver,
+            Fault::Delay(delay) => FaultAction::Delay(delay),
+            Fault::Kill => FaultAction::Kill,
+        }
+    }
+
+    /// Whether all the steps limited to a number of messages were consumed
+    pub fn is_finished(&self) -> bool {
+        self.inbound
+            .iter()
+            .chain(self.outbound.iter())
+            .all(|step| step.remaining.is_none())
+    }
+}
+
+fn parse_step(line: &str) -> Result<(Direction, FaultStep), String> {
+    let mut words = line.split_whitespace();
+    let direction = match words.next() {
+        Some("inbound") => Direction::Inbound,
+        Some("outbound") => Direction::Outbound,
+        _ => return Err("expected inbound or outbound".to_string()),
+    };
+    let fault = match words.next() {
+        Some("deliver") => Fault::Deliver,
+        Some("drop") => Fault::Drop {
+            percent: parse_percent(words.next())?,
+        },
+        Some("delay") => {
+            let millis = words
+                .next()
+                .and_then(|word| word.strip_suffix("ms"))
+                .and_then(|millis| millis.parse().ok())
+                .ok_or_else(|| "expected a delay in ms".to_string())?;
+            Fault::Delay(Duration::from_millis(millis))
+        }
+        Some("corrupt") if direction == Direction::Inbound => Fault::Corrupt {
+            percent: parse_percent(words.next())?,
+        },
+        Some("corrupt") => return Err("only inbound messages can be corrupted".to_string()),
+        Some("kill") => Fault::Kill,
+        _ => return Err("expected deliver, drop, delay, corrupt or kill".to_string()),
+    };
+    let remaining = match words.next() {
+        None => None,
+        Some(count) => Some(
+            count
+                .strip_prefix('x')
+                .and_then(|count| count.parse().ok())
+                .filter(|count| *count > 0)
+                .ok_or_else(|| "expected a message count like x3".to_string())?,
+        ),
+    };
+    if words.next().is_some() {
+        return Err("unexpected trailing words".to_string());
+    }
+    Ok((direction, FaultStep { fault, remaining }))
+}
+
+fn parse_percent(word: Option<&str>) -> Result<u8, String> {
+    word.and_then(|word| word.strip_suffix('%'))
+        .and_then(|percent| percent.parse().ok())
+        .filter(|percent| *percent <= 100)
+        .ok_or_else(|| "expected a percentage like 50%".to_string())
+}
+
+/// Scenario shared by the mock network and its connections
+pub type SharedFaultScenario = Arc<Mutex<FaultScenario>>;
+
+#[test]
+fn test_fault_scenario_script() {
+    let mut scenario = FaultScenario::parse(
+        "
+        # handshake goes through, then the peer gets flaky
+        inbound deliver x1
+        inbound drop 100% x2
+        inbound corrupt 100% x1
+        outbound delay 20ms x1
+        outbound kill
+        ",
+        0,
+    )
+    .unwrap();
+    assert_eq!(
+        scenario.next_action(Direction::Inbound),
+        FaultAction::Deliver
+    );
+    assert_eq!(scenario.next_action(Direction::Inbound), FaultAction::Drop);
+    assert_eq!(scenario.next_action(Direction::Inbound), FaultAction::Drop);
+    assert!(matches!(
+        scenario.next_action(Direction::Inbound),
+        FaultAction::Corrupt(_)
+    ));
+    assert_eq!(
+        scenario.next_action(Direction::Inbound),
+        FaultAction::Deliver
+    );
+    assert_eq!(
+        scenario.next_action(Direction::Outbound),
+        FaultAction::Delay(Duration::from_millis(20))
+    );
+    assert!(scenario.is_finished());
+    assert_eq!(scenario.next_action(Direction::Outbound), FaultAction::Kill);
+    assert_eq!(scenario.next_action(Direction::Outbound), FaultAction::Kill);
+
+    assert!(FaultScenario::parse("outbound corrupt 10%", 0).is_err());
+    assert!(FaultScenario::parse("inbound drop 150%", 0).is_err());
+    assert!(FaultScenario::parse("inbound kill x0", 0).is_err());
+}
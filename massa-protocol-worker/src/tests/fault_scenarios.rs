@@ -0,0 +1,182 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use massa_pool_exports::test_exports::MockPoolControllerMessage;
+use massa_protocol_exports::{test_exports::tools, ProtocolConfig};
+use massa_signature::KeyPair;
+use peernet::peer_id::PeerId;
+use serial_test::serial;
+
+use crate::{
+    handlers::operation_handler::OperationMessage, messages::Message,
+    wrap_network::ActiveConnectionsTrait,
+};
+
+use super::{context::protocol_test, fault_injection::FaultScenario};
+
+#[test]
+#[serial]
+fn test_protocol_recovers_from_dropped_and_corrupted_messages() {
+    let default_panic = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_panic(info);
+        std::process::exit(1);
+    }));
+
+    let mut protocol_config = ProtocolConfig::default();
+    protocol_config.thread_count = 2;
+    protocol_config.initial_peers = "./src/tests/empty_initial_peers.json".to_string().into();
+    protocol_test(
+        &protocol_config,
+        move |mut network_controller,
+              protocol_controller,
+              protocol_manager,
+              consensus_event_receiver,
+              mut pool_event_receiver| {
+            //1. Create 1 node whose first message is corrupted and second one lost
+            let node_a_keypair = KeyPair::generate();
+            let (node_a_peer_id, _node_a) = network_controller.create_fake_connection(
+                PeerId::from_bytes(node_a_keypair.get_public_key().to_bytes()).unwrap(),
+            );
+            let scenario = network_controller.set_fault_scenario(
+                FaultScenario::parse(
+                    "
+                    inbound corrupt 100% x1
+                    inbound drop 100% x1
+                    ",
+                    0,
+                )
+                .unwrap(),
+            );
+            let operation = tools::create_operation_with_expire_period(&node_a_keypair, 1);
+
+            //2. The corrupted announcement is rejected, the operations never arrive
+            let _ = network_controller.send_from_peer(
+                &node_a_peer_id,
+                Message::Operation(OperationMessage::OperationsAnnouncement(
+                    vec![operation.id.into_prefix()].into_iter().collect(),
+                )),
+            );
+            network_controller
+                .send_from_peer(
+                    &node_a_peer_id,
+                    Message::Operation(OperationMessage::Operations(vec![operation.clone()])),
+                )
+                .unwrap();
+            assert!(scenario.lock().is_finished());
+            if let Some(MockPoolControllerMessage::AddOperations { .. }) = pool_event_receiver
+                .wait_command(500.into(), |evt| match evt {
+                    evt @ MockPoolControllerMessage::AddOperations { .. } => Some(evt),
+                    _ => None,
+                })
+            {
+                panic!("The dropped operations reached the pool.");
+            }
+
+            //3. The operations sent again go through, and the peer is still connected
+            network_controller
+                .send_from_peer(
+                    &node_a_peer_id,
+                    Message::Operation(OperationMessage::Operations(vec![operation.clone()])),
+                )
+                .unwrap();
+            match pool_event_receiver.wait_command(1000.into(), |evt| match evt {
+                evt @ MockPoolControllerMessage::AddOperations { .. } => Some(evt),
+                _ => None,
+            }) {
+                Some(MockPoolControllerMessage::AddOperations { operations, .. }) => {
+                    assert!(operations.get_op_refs().contains(&operation.id))
+                }
+                _ => panic!("Unexpected or no protocol pool event."),
+            }
+            assert!(network_controller
+                .get_connections()
+                .get_peer_ids_connected()
+                .contains(&node_a_peer_id));
+            (
+                network_controller,
+                protocol_controller,
+                protocol_manager,
+                consensus_event_receiver,
+                pool_event_receiver,
+            )
+        },
+    )
+}
+
+#[test]
+#[serial]
+fn test_protocol_handles_connection_killed_mid_exchange() {
+    let default_panic = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_panic(info);
+        std::process::exit(1);
+    }));
+
+    let mut protocol_config = ProtocolConfig::default();
+    protocol_config.thread_count = 2;
+    protocol_config.initial_peers = "./src/tests/empty_initial_peers.json".to_string().into();
+    protocol_test(
+        &protocol_config,
+        move |mut network_controller,
+              protocol_controller,
+              protocol_manager,
+              consensus_event_receiver,
+              mut pool_event_receiver| {
+            //1. Create 1 node whose connection dies after its first message
+            let node_a_keypair = KeyPair::generate();
+            let (node_a_peer_id, _node_a) = network_controller.create_fake_connection(
+                PeerId::from_bytes(node_a_keypair.get_public_key().to_bytes()).unwrap(),
+            );
+            network_controller.set_fault_scenario(
+                FaultScenario::parse(
+                    "
+                    inbound deliver x1
+                    inbound kill
+                    ",
+                    0,
+                )
+                .unwrap(),
+            );
+            let operation_1 = tools::create_operation_with_expire_period(&node_a_keypair, 1);
+            let operation_2 = tools::create_operation_with_expire_period(&node_a_keypair, 1);
+
+            //2. The first operation arrives, the connection is closed on the second one
+            network_controller
+                .send_from_peer(
+                    &node_a_peer_id,
+                    Message::Operation(OperationMessage::Operations(vec![operation_1.clone()])),
+                )
+                .unwrap();
+            assert!(network_controller
+                .send_from_peer(
+                    &node_a_peer_id,
+                    Message::Operation(OperationMessage::Operations(vec![operation_2.clone()])),
+                )
+                .is_err());
+            match pool_event_receiver.wait_command(1000.into(), |evt| match evt {
+                evt @ MockPoolControllerMessage::AddOperations { .. } => Some(evt),
+                _ => None,
+            }) {
+                Some(MockPoolControllerMessage::AddOperations { operations, .. }) => {
+                    let op_refs = operations.get_op_refs();
+                    assert!(op_refs.contains(&operation_1.id));
+                    assert!(!op_refs.contains(&operation_2.id));
+                }
+                _ => panic!("Unexpected or no protocol pool event."),
+            }
+
+            //3. The node keeps working without the peer
+            assert!(network_controller
+                .get_connections()
+                .get_peer_ids_connected()
+                .is_empty());
+            (
+                network_controller,
+                protocol_controller,
+                protocol_manager,
+                consensus_event_receiver,
+                pool_event_receiver,
+            )
+        },
+    )
+}
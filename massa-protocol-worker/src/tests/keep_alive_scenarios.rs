@@ -0,0 +1,67 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use std::time::Duration;
+
+use massa_protocol_exports::ProtocolConfig;
+use massa_signature::KeyPair;
+use peernet::peer_id::PeerId;
+use serial_test::serial;
+
+use crate::{handlers::peer_handler::PeerManagementMessage, messages::Message};
+
+use super::context::protocol_test;
+
+#[test]
+#[serial]
+fn test_protocol_answers_keep_alive_pings() {
+    let default_panic = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_panic(info);
+        std::process::exit(1);
+    }));
+
+    let mut protocol_config = ProtocolConfig::default();
+    protocol_config.thread_count = 2;
+    protocol_config.initial_peers = "./src/tests/empty_initial_peers.json".to_string().into();
+    protocol_test(
+        &protocol_config,
+        move |mut network_controller,
+              protocol_controller,
+              protocol_manager,
+              consensus_event_receiver,
+              pool_event_receiver| {
+            //1. Create 1 node connected to us
+            let node_a_keypair = KeyPair::generate();
+            let (node_a_peer_id, node_a) = network_controller.create_fake_connection(
+                PeerId::from_bytes(node_a_keypair.get_public_key().to_bytes()).unwrap(),
+            );
+
+            //2. The node pings us, we answer with the same nonce
+            network_controller
+                .send_from_peer(
+                    &node_a_peer_id,
+                    Message::from(PeerManagementMessage::Ping(42)),
+                )
+                .unwrap();
+            let nonce = loop {
+                match node_a.recv_timeout(Duration::from_millis(1000)) {
+                    Ok(Message::PeerManagement(message)) => {
+                        if let PeerManagementMessage::Pong(nonce) = *message {
+                            break nonce;
+                        }
+                    }
+                    Ok(_) => continue,
+                    Err(_) => panic!("Ping not answered"),
+                }
+            };
+            assert_eq!(nonce, 42);
+            (
+                network_controller,
+                protocol_controller,
+                protocol_manager,
+                consensus_event_receiver,
+                pool_event_receiver,
+            )
+        },
+    )
+}
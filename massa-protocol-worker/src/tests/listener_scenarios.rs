@@ -0,0 +1,169 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use std::{collections::HashMap, net::SocketAddr, time::Duration};
+
+use crossbeam::channel::Receiver;
+use massa_protocol_exports::ProtocolConfig;
+use massa_signature::KeyPair;
+use peernet::{peer_id::PeerId, transports::TransportType, types::KeyPair as PeerNetKeyPair};
+use serial_test::serial;
+
+use crate::{handlers::peer_handler::PeerManagementMessage, messages::Message};
+
+use super::context::protocol_test;
+
+/// Waits for the listeners of `peer_id` announced to a node
+fn wait_listeners_announcement(
+    node: &Receiver<Message>,
+    peer_id: &PeerId,
+) -> Option<HashMap<SocketAddr, TransportType>> {
+    loop {
+        match node.recv_timeout(Duration::from_millis(1000)) {
+            Ok(Message::PeerManagement(message)) => {
+                if let PeerManagementMessage::NewPeerConnected((announced_peer_id, listeners)) =
+                    *message && announced_peer_id == *peer_id
+                {
+                    return Some(listeners);
+                }
+            }
+            Ok(_) => continue,
+            Err(_) => return None,
+        }
+    }
+}
+
+#[test]
+#[serial]
+fn test_protocol_announces_listeners_changed_at_runtime() {
+    let default_panic = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_panic(info);
+        std::process::exit(1);
+    }));
+
+    let mut protocol_config = ProtocolConfig::default();
+    protocol_config.thread_count = 2;
+    protocol_config.initial_peers = "./src/tests/empty_initial_peers.json".to_string().into();
+    protocol_config.routable_ip = Some("1.2.3.4".parse().unwrap());
+    protocol_config
+        .listeners
+        .insert("127.0.0.1:8081".parse().unwrap(), TransportType::Tcp);
+    let keypair_bs58_check_encoded =
+        std::fs::read_to_string("./src/tests/test_keypair1.json").unwrap();
+    let self_keypair =
+        serde_json::from_slice::<PeerNetKeyPair>(keypair_bs58_check_encoded.as_bytes()).unwrap();
+    let self_peer_id = PeerId::from_public_key(self_keypair.get_public_key());
+    protocol_config.keypair_file = "./src/tests/test_keypair1.json".to_string().into();
+    protocol_test(
+        &protocol_config,
+        move |mut network_controller,
+              protocol_controller,
+              protocol_manager,
+              consensus_event_receiver,
+              pool_event_receiver| {
+            //1. Create 1 node connected to us
+            let node_a_keypair = KeyPair::generate();
+            let (_node_a_peer_id, node_a) = network_controller.create_fake_connection(
+                PeerId::from_bytes(node_a_keypair.get_public_key().to_bytes()).unwrap(),
+            );
+
+            //2. Add a listener, it is announced with the routable IP
+            protocol_controller
+                .add_listener("127.0.0.1:8082".parse().unwrap(), TransportType::Quic)
+                .unwrap();
+            let listeners = wait_listeners_announcement(&node_a, &self_peer_id)
+                .expect("New listener not announced");
+            assert_eq!(listeners.len(), 2);
+            assert_eq!(
+                listeners.get(&"1.2.3.4:8082".parse().unwrap()),
+                Some(&TransportType::Quic)
+            );
+
+            //3. Adding the same listener or removing an unknown one fails
+            assert!(protocol_controller
+                .add_listener("127.0.0.1:8082".parse().unwrap(), TransportType::Quic)
+                .is_err());
+            assert!(protocol_controller
+                .remove_listener("127.0.0.1:8083".parse().unwrap())
+                .is_err());
+
+            //4. Remove the original listener, only the new one is announced
+            protocol_controller
+                .remove_listener("127.0.0.1:8081".parse().unwrap())
+                .unwrap();
+            let listeners = wait_listeners_announcement(&node_a, &self_peer_id)
+                .expect("Removed listener not announced");
+            assert_eq!(listeners.len(), 1);
+            assert!(listeners.contains_key(&"1.2.3.4:8082".parse().unwrap()));
+            (
+                network_controller,
+                protocol_controller,
+                protocol_manager,
+                consensus_event_receiver,
+                pool_event_receiver,
+            )
+        },
+    )
+}
+
+#[test]
+#[serial]
+fn test_protocol_announces_new_routable_ip() {
+    let default_panic = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_panic(info);
+        std::process::exit(1);
+    }));
+
+    let mut protocol_config = ProtocolConfig::default();
+    protocol_config.thread_count = 2;
+    protocol_config.initial_peers = "./src/tests/empty_initial_peers.json".to_string().into();
+    protocol_config.routable_ip = Some("1.2.3.4".parse().unwrap());
+    protocol_config
+        .listeners
+        .insert("127.0.0.1:8081".parse().unwrap(), TransportType::Tcp);
+    let keypair_bs58_check_encoded =
+        std::fs::read_to_string("./src/tests/test_keypair1.json").unwrap();
+    let self_keypair =
+        serde_json::from_slice::<PeerNetKeyPair>(keypair_bs58_check_encoded.as_bytes()).unwrap();
+    let self_peer_id = PeerId::from_public_key(self_keypair.get_public_key());
+    protocol_config.keypair_file = "./src/tests/test_keypair1.json".to_string().into();
+    protocol_test(
+        &protocol_config,
+        move |mut network_controller,
+              protocol_controller,
+              protocol_manager,
+              consensus_event_receiver,
+              pool_event_receiver| {
+            //1. Create 1 node connected to us
+            let node_a_keypair = KeyPair::generate();
+            let (_node_a_peer_id, node_a) = network_controller.create_fake_connection(
+                PeerId::from_bytes(node_a_keypair.get_public_key().to_bytes()).unwrap(),
+            );
+
+            //2. The same IP isn't announced again
+            protocol_controller
+                .set_routable_ip(Some("1.2.3.4".parse().unwrap()))
+                .unwrap();
+            assert!(wait_listeners_announcement(&node_a, &self_peer_id).is_none());
+
+            //3. A new IP is announced
+            protocol_controller
+                .set_routable_ip(Some("5.6.7.8".parse().unwrap()))
+                .unwrap();
+            let listeners = wait_listeners_announcement(&node_a, &self_peer_id)
+                .expect("New routable IP not announced");
+            assert_eq!(
+                listeners.keys().copied().collect::<Vec<SocketAddr>>(),
+                vec!["5.6.7.8:8081".parse().unwrap()]
+            );
+            (
+                network_controller,
+                protocol_controller,
+                protocol_manager,
+                consensus_event_receiver,
+                pool_event_receiver,
+            )
+        },
+    )
+}
@@ -1,11 +1,12 @@
 use std::{
     collections::{HashMap, HashSet},
+    path::Path,
     sync::Arc,
 };
 
 use crossbeam::channel::{Receiver, Sender};
 use massa_protocol_exports::ProtocolError;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use peernet::{
     messages::{
         MessagesHandler as PeerNetMessagesHandler, MessagesSerializer as PeerNetMessagesSerializer,
@@ -21,19 +22,32 @@ use crate::{
         peer_handler::PeerManagementMessageSerializer,
     },
     messages::{Message, MessagesHandler, MessagesSerializer},
+    recorder::{read_capture, replay_capture},
     wrap_network::{ActiveConnectionsTrait, NetworkController},
 };
 
+use super::fault_injection::{Direction, FaultAction, FaultScenario, SharedFaultScenario};
+
 pub struct MockActiveConnections {
     pub connections: HashMap<PeerId, Sender<Message>>,
+    /// faults injected in the messages, if any
+    pub faults: Option<SharedFaultScenario>,
 }
 
 impl MockActiveConnections {
     pub fn new() -> Self {
         Self {
             connections: HashMap::new(),
+            faults: None,
         }
     }
+
+    /// Fate of the next message of a direction
+    fn next_action(&self, direction: Direction) -> FaultAction {
+        self.faults.as_ref().map_or(FaultAction::Deliver, |faults| {
+            faults.lock().next_action(direction)
+        })
+    }
 }
 
 type SharedMockActiveConnections = Arc<RwLock<MockActiveConnections>>;
@@ -72,6 +86,13 @@ impl ActiveConnectionsTrait for SharedMockActiveConnections {
             .collect()
     }
 
+    fn get_peer_connection_type(&self, peer_id: &PeerId) -> Option<PeerConnectionType> {
+        self.read()
+            .connections
+            .contains_key(peer_id)
+            .then_some(PeerConnectionType::OUT)
+    }
+
     fn get_peer_ids_connected(&self) -> std::collections::HashSet<PeerId> {
         self.read().connections.keys().cloned().collect()
     }
@@ -81,12 +102,23 @@ impl ActiveConnectionsTrait for SharedMockActiveConnections {
         peer_id: &PeerId,
         _message_serializer: &crate::messages::MessagesSerializer,
         message: Message,
-        _high_priority: bool,
     ) -> Result<(), massa_protocol_exports::ProtocolError> {
+        let action = self.read().next_action(Direction::Outbound);
+        match action {
+            FaultAction::Drop => return Ok(()),
+            FaultAction::Delay(delay) => std::thread::sleep(delay),
+            FaultAction::Kill => {
+                self.write().connections.remove(peer_id);
+                return Ok(());
+            }
+            FaultAction::Deliver | FaultAction::Corrupt(_) => {}
+        }
         let _ = self.read().connections.get(peer_id).unwrap().send(message);
         Ok(())
     }
 
+    fn flush_send_queues(&self) {}
+
     fn shutdown_connection(&mut self, peer_id: &PeerId) {
         self.write().connections.remove(peer_id);
     }
@@ -140,6 +172,13 @@ impl MockNetworkController {
         self.connections.write().connections.remove(peer_id);
     }
 
+    /// Inject the faults of a scenario in the messages exchanged with the fake peers
+    pub fn set_fault_scenario(&mut self, scenario: FaultScenario) -> SharedFaultScenario {
+        let scenario = Arc::new(Mutex::new(scenario));
+        self.connections.write().faults = Some(scenario.clone());
+        scenario
+    }
+
     /// Simulate a peer that send a message to us
     pub fn send_from_peer(
         &mut self,
@@ -162,9 +201,28 @@ impl MockNetworkController {
         self.message_serializer
             .serialize_id(&message, &mut data)
             .map_err(|err| ProtocolError::GeneralProtocolError(err.to_string()))?;
+        let id_len = data.len();
         self.message_serializer
             .serialize(&message, &mut data)
             .map_err(|err| ProtocolError::GeneralProtocolError(err.to_string()))?;
+        let action = self.connections.read().next_action(Direction::Inbound);
+        match action {
+            FaultAction::Deliver => {}
+            FaultAction::Drop => return Ok(()),
+            FaultAction::Delay(delay) => std::thread::sleep(delay),
+            // the id is left intact: the content reaches the handler of its type
+            FaultAction::Corrupt(index) if data.len() > id_len => {
+                let content_len = data.len() - id_len;
+                data[id_len + index % content_len] ^= 0xFF;
+            }
+            FaultAction::Corrupt(_) => {}
+            FaultAction::Kill => {
+                self.remove_fake_connection(peer_id);
+                return Err(ProtocolError::GeneralProtocolError(
+                    "Connection killed".to_string(),
+                ));
+            }
+        }
         let (rest, id) = self
             .messages_handler
             .deserialize_id(&data, peer_id)
@@ -175,6 +233,23 @@ impl MockNetworkController {
         Ok(())
     }
 
+    /// Replay the messages of a capture file, as received by the node of an operator. The
+    /// peers that sent them are connected first.
+    pub fn replay_capture(&mut self, path: &Path) -> Result<(), ProtocolError> {
+        let messages = read_capture(path)?;
+        for message in &messages {
+            if !self
+                .connections
+                .read()
+                .connections
+                .contains_key(&message.peer_id)
+            {
+                self.create_fake_connection(message.peer_id.clone());
+            }
+        }
+        replay_capture(&messages, &self.messages_handler, |_| {})
+    }
+
     pub fn get_connections(&self) -> SharedMockActiveConnections {
         self.connections.clone()
     }
@@ -200,8 +275,8 @@ impl NetworkController for MockNetworkController {
     fn try_connect(
         &mut self,
         _addr: std::net::SocketAddr,
-        _timeout: std::time::Duration,
-        _out_connection_config: &peernet::transports::OutConnectionConfig,
+        _transport: peernet::transports::TransportType,
+        _transport_config: &massa_protocol_exports::TransportConfig,
     ) -> Result<(), massa_protocol_exports::ProtocolError> {
         Ok(())
     }
@@ -12,9 +12,14 @@ use crate::{create_protocol_controller, start_protocol_controller};
 mod ban_nodes_scenarios;
 mod block_scenarios;
 mod cache_scenarios;
+mod capture_scenarios;
 mod context;
 mod endorsements_scenarios;
+mod fault_injection;
+mod fault_scenarios;
 mod in_block_operations_scenarios;
+mod keep_alive_scenarios;
+mod listener_scenarios;
 mod mock_network;
 mod operations_scenarios;
 mod tools;
@@ -3,24 +3,32 @@ use massa_consensus_exports::ConsensusController;
 use massa_models::node::NodeId;
 use massa_pool_exports::PoolController;
 use massa_protocol_exports::{
-    BootstrapPeers, PeerData, PeerId, ProtocolConfig, ProtocolController, ProtocolError,
-    ProtocolManager,
+    BootstrapPeers, PeerCategoryInfo, PeerData, PeerId, ProtocolConfig, ProtocolController,
+    ProtocolError, ProtocolEventBus, ProtocolManager, TransportType, LOGGED_PEER_TAG,
 };
 use massa_serialization::U64VarIntDeserializer;
 use massa_signature::KeyPair;
 use massa_storage::Storage;
-use parking_lot::RwLock;
+use massa_time::MassaTime;
+use parking_lot::{Mutex, RwLock};
 use peernet::{
     config::{PeerNetCategoryInfo, PeerNetConfiguration},
     network_manager::PeerNetManager,
     types::KeyPair as PeerNetKeyPair,
 };
 use std::{
-    collections::HashMap, fs::read_to_string, ops::Bound::Included, str::FromStr, sync::Arc,
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    ops::Bound::Included,
+    str::FromStr,
+    sync::Arc,
 };
-use tracing::{debug, log::warn};
+use tracing::{debug, info, warn};
 
 use crate::{
+    audit::ConnectionAuditLog,
+    channels::SheddingSender,
+    clock::SystemClock,
     connectivity::{start_connectivity_thread, ConnectivityCommand},
     controller::ProtocolControllerImpl,
     handlers::{
@@ -37,13 +45,20 @@ use crate::{
             commands_retrieval::OperationHandlerRetrievalCommand,
         },
         peer_handler::{
-            models::{PeerDB, PeerManagementCmd},
-            MassaHandshake,
+            address_tables::{AddressTables, AddressTablesPolicy},
+            journal::PeerDBJournal,
+            models::{load_peer_annotations, PeerDB, PeerManagementCmd},
+            peer_db_lock::PeerDBLock,
+            LocalAnnouncement, MassaHandshake,
         },
     },
+    keystore::NodeKeystore,
     manager::ProtocolManagerImpl,
-    messages::MessagesHandler,
-    wrap_network::NetworkControllerImpl,
+    messages::{MessageSizeValidator, MessagesHandler},
+    production_schedule::ProductionSchedule,
+    recorder::SessionRecorder,
+    replay::ReplayGuard,
+    wrap_network::{NetworkController, NetworkControllerImpl},
 };
 
 pub struct ProtocolChannels {
@@ -73,6 +88,10 @@ pub struct ProtocolChannels {
     ),
     pub connectivity_thread: (Sender<ConnectivityCommand>, Receiver<ConnectivityCommand>),
     pub peer_management_handler: (Sender<PeerManagementCmd>, Receiver<PeerManagementCmd>),
+    /// events published for the API subscribers
+    pub event_bus: ProtocolEventBus,
+    /// our upcoming productions, around which the low-priority gossip is held
+    pub production_schedule: ProductionSchedule,
 }
 
 /// This function exists because consensus need the protocol controller and we need consensus controller.
@@ -98,6 +117,7 @@ pub fn create_protocol_controller(
         Sender<PeerManagementCmd>,
         Receiver<PeerManagementCmd>,
     ) = crossbeam::channel::bounded(config.max_size_channel_commands_peers);
+    let production_schedule = ProductionSchedule::from_config(&config);
     (
         Box::new(ProtocolControllerImpl::new(
             sender_blocks_retrieval_ext.clone(),
@@ -106,6 +126,7 @@ pub fn create_protocol_controller(
             sender_endorsements_propagation_ext.clone(),
             sender_connectivity_ext.clone(),
             sender_peer_management_ext.clone(),
+            production_schedule.clone(),
         )),
         ProtocolChannels {
             operation_handler_retrieval: (
@@ -131,6 +152,11 @@ pub fn create_protocol_controller(
             ),
             connectivity_thread: (sender_connectivity_ext, receiver_connectivity_ext),
             peer_management_handler: (sender_peer_management_ext, receiver_peer_management_ext),
+            event_bus: ProtocolEventBus::new(
+                config.broadcast_protocol_events_channel_capacity,
+                config.broadcast_enabled,
+            ),
+            production_schedule,
         },
     )
 }
@@ -151,7 +177,24 @@ pub fn start_protocol_controller(
     protocol_channels: ProtocolChannels,
 ) -> Result<(Box<dyn ProtocolManager>, KeyPair, NodeId), ProtocolError> {
     debug!("starting protocol controller");
-    let peer_db = Arc::new(RwLock::new(PeerDB::default()));
+    let mut peer_db = PeerDB::new(config.max_peers_per_address_bucket);
+    peer_db.annotations = load_peer_annotations(&config);
+    peer_db.max_inbound_only_gossip_percent = Some(config.max_inbound_gossip_percent);
+    peer_db.addresses = AddressTables::new(AddressTablesPolicy::from_config(&config));
+    // the journal is replayed before the handlers start gossiping
+    if let Some(path) = &config.peer_db_journal_file {
+        match PeerDBJournal::open(
+            path,
+            &mut peer_db,
+            config.max_size_listeners_per_peer,
+            MassaTime::now()?,
+        ) {
+            Ok(journal) => peer_db.journal = Some(Arc::new(Mutex::new(journal))),
+            Err(err) => warn!("could not open the peer DB journal: {}", err),
+        }
+    }
+    let logged_peers = peer_db.peers_with_tag(LOGGED_PEER_TAG);
+    let peer_db = PeerDBLock::shared(peer_db);
 
     let (sender_operations, receiver_operations) =
         bounded(config.max_size_channel_network_to_operation_handler);
@@ -162,34 +205,43 @@ pub fn start_protocol_controller(
     let (sender_peers, receiver_peers) = bounded(config.max_size_channel_network_to_peer_handler);
 
     // Register channels for handlers
+    let sender_peer_cmd = protocol_channels.peer_management_handler.0.clone();
+    let replay_guard = ReplayGuard::default();
+    let clock = SystemClock::shared();
+    let recorder = match &config.capture_file {
+        Some(path) => {
+            info!("Capturing the received messages to {}", path.display());
+            Some(SessionRecorder::create(path, clock.clone())?)
+        }
+        None => None,
+    };
     let message_handlers: MessagesHandler = MessagesHandler {
-        sender_blocks: sender_blocks.clone(),
-        sender_endorsements: sender_endorsements.clone(),
-        sender_operations: sender_operations.clone(),
-        sender_peers: sender_peers.clone(),
+        sender_blocks: SheddingSender::blocking("blocks", sender_blocks.clone()),
+        sender_endorsements: SheddingSender::drop_oldest(
+            "endorsements",
+            sender_endorsements.clone(),
+            receiver_endorsements.clone(),
+        ),
+        sender_operations: SheddingSender::drop_oldest(
+            "operations",
+            sender_operations.clone(),
+            receiver_operations.clone(),
+        ),
+        sender_peers: SheddingSender::drop_oldest(
+            "peers",
+            sender_peers.clone(),
+            receiver_peers.clone(),
+        ),
         id_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
+        size_validator: MessageSizeValidator::new(&config, sender_peer_cmd),
+        replay_guard: Some(replay_guard.clone()),
+        recorder,
+        traffic: peer_db.traffic().clone(),
     };
 
-    let mut peernet_config = PeerNetConfiguration::default(
-        MassaHandshake::new(peer_db.clone(), config.clone(), message_handlers.clone()),
-        message_handlers.clone(),
-    );
-
-    // try to read node keypair from file, otherwise generate it & write to file. Then derive nodeId
-    let keypair = if std::path::Path::is_file(&config.keypair_file) {
-        // file exists: try to load it
-        let keypair_bs58_check_encoded = read_to_string(&config.keypair_file).map_err(|err| {
-            std::io::Error::new(err.kind(), format!("could not load node key file: {}", err))
-        })?;
-        serde_json::from_slice::<KeyPair>(keypair_bs58_check_encoded.as_bytes())?
-    } else {
-        // node file does not exist: generate the key and save it
-        let keypair = KeyPair::generate();
-        if let Err(e) = std::fs::write(&config.keypair_file, serde_json::to_string(&keypair)?) {
-            warn!("could not generate node key file: {}", e);
-        }
-        keypair
-    };
+    // read the node keypair from its keystore, otherwise generate it & save it. Then derive nodeId
+    let keypair = NodeKeystore::new(&config.keypair_file, config.keypair_protection.clone())
+        .load_or_generate()?;
 
     let initial_peers_infos = serde_json::from_str::<HashMap<PeerId, PeerData>>(
         &std::fs::read_to_string(&config.initial_peers)?,
@@ -200,6 +252,7 @@ pub fn start_protocol_controller(
         bootstrap_peers
             .0
             .into_iter()
+            .map(|(peer_id, info)| (peer_id, info.listeners))
             .chain(
                 initial_peers_infos
                     .iter()
@@ -214,56 +267,116 @@ pub fn start_protocol_controller(
     };
 
     let peernet_keypair = PeerNetKeyPair::from_str(&keypair.to_string()).unwrap();
-    peernet_config.self_keypair = peernet_keypair.clone();
-    let peernet_categories = config
-        .peers_categories
+    // peer ids of all the identities of the node, which must not connect to each other
+    let identity_keypairs = config
+        .identities
         .iter()
-        .map(|(category_name, infos)| {
-            (
-                category_name.clone(),
-                (
-                    initial_peers_infos
-                        .iter()
-                        .filter_map(|info| {
-                            if info.1.category == *category_name {
-                                //TODO: Adapt for multiple listeners
-                                Some(
-                                    info.1
-                                        .listeners
-                                        .iter()
-                                        .next()
-                                        .map(|addr| addr.0.ip().to_canonical())
-                                        .unwrap(),
-                                )
-                            } else {
-                                None
-                            }
-                        })
-                        .collect(),
-                    PeerNetCategoryInfo {
-                        max_in_connections_post_handshake: infos.max_in_connections_post_handshake,
-                        max_in_connections_pre_handshake: infos.max_in_connections_pre_handshake,
-                        max_in_connections_per_ip: infos.max_in_connections_per_ip,
-                    },
-                ),
+        .map(|identity| {
+            NodeKeystore::new(&identity.keypair_file, config.keypair_protection.clone())
+                .load_or_generate()
+        })
+        .collect::<Result<Vec<_>, ProtocolError>>()?;
+    let own_peer_ids: HashSet<PeerId> = std::iter::once(&keypair)
+        .chain(&identity_keypairs)
+        .map(|keypair| {
+            PeerId::from_public_key(
+                PeerNetKeyPair::from_str(&keypair.to_string())
+                    .unwrap()
+                    .get_public_key(),
             )
         })
         .collect();
-    peernet_config.peers_categories = peernet_categories;
-    peernet_config.default_category_info = PeerNetCategoryInfo {
-        max_in_connections_pre_handshake: config
-            .default_category_info
-            .max_in_connections_pre_handshake,
-        max_in_connections_post_handshake: config
-            .default_category_info
-            .max_in_connections_post_handshake,
-        max_in_connections_per_ip: config.default_category_info.max_in_connections_per_ip,
-    };
-    peernet_config.max_in_connections = config.max_in_connections;
+    let local_announcement = Arc::new(RwLock::new(
+        LocalAnnouncement::new(
+            peernet_keypair.clone(),
+            config.listeners.clone(),
+            config.routable_ip,
+        )
+        .map_err(|err| ProtocolError::GeneralProtocolError(err.to_string()))?,
+    ));
+    let audit_log = ConnectionAuditLog::shared(
+        config.connection_audit_log_size,
+        clock.clone(),
+        protocol_channels.event_bus.clone(),
+    );
+    audit_log.set_logged_peers(logged_peers);
+    let handshake = MassaHandshake::new(
+        peer_db.clone(),
+        config.clone(),
+        message_handlers.clone(),
+        local_announcement.clone(),
+        audit_log.clone(),
+        clock.clone(),
+    )
+    .with_own_identities(own_peer_ids.clone());
+    let mut handshakes_active_connections = vec![handshake.active_connections.clone()];
+    let main_peernet_manager = peernet_manager(
+        &config,
+        handshake,
+        message_handlers.clone(),
+        peernet_keypair.clone(),
+        &config.peers_categories,
+        &config.default_category_info,
+        &config.listeners,
+        &initial_peers_infos,
+    );
+
+    // the additional identities listen right away, with their own keypair and announcement
+    let mut identity_managers = Vec::new();
+    for (identity, identity_keypair) in config.identities.iter().zip(identity_keypairs) {
+        let identity_node_id = NodeId::new(identity_keypair.get_public_key());
+        let identity_keypair = PeerNetKeyPair::from_str(&identity_keypair.to_string()).unwrap();
+        let identity_announcement = Arc::new(RwLock::new(
+            LocalAnnouncement::new(
+                identity_keypair.clone(),
+                identity.listeners.clone(),
+                config.routable_ip,
+            )
+            .map_err(|err| ProtocolError::GeneralProtocolError(err.to_string()))?,
+        ));
+        let identity_handshake = MassaHandshake::new(
+            peer_db.clone(),
+            config.clone(),
+            message_handlers.clone(),
+            identity_announcement,
+            audit_log.clone(),
+            clock.clone(),
+        )
+        .with_own_identities(own_peer_ids.clone());
+        handshakes_active_connections.push(identity_handshake.active_connections.clone());
+        let mut identity_manager = peernet_manager(
+            &config,
+            identity_handshake,
+            message_handlers.clone(),
+            identity_keypair,
+            &identity.peers_categories,
+            &identity.default_category_info,
+            &identity.listeners,
+            &initial_peers_infos,
+        );
+        for (addr, transport) in &identity.listeners {
+            identity_manager
+                .start_listener(*transport, *addr)
+                .map_err(|err| ProtocolError::ListenerError(err.to_string()))?;
+        }
+        info!(
+            "Identity {} listening on {:?}",
+            identity_node_id,
+            identity.listeners.keys()
+        );
+        identity_managers.push(identity_manager);
+    }
 
-    let network_controller = Box::new(NetworkControllerImpl::new(PeerNetManager::new(
-        peernet_config,
-    )));
+    let network_controller = Box::new(NetworkControllerImpl::new(
+        main_peernet_manager,
+        identity_managers,
+        config.max_queued_messages_per_peer,
+        replay_guard,
+        peer_db.traffic().clone(),
+    ));
+    for handshake_active_connections in handshakes_active_connections {
+        *handshake_active_connections.write() = Some(network_controller.get_active_connections());
+    }
 
     let connectivity_thread_handle = start_connectivity_thread(
         PeerId::from_public_key(peernet_keypair.get_public_key()),
@@ -276,6 +389,9 @@ pub fn start_protocol_controller(
         (sender_peers, receiver_peers),
         initial_peers,
         peer_db,
+        local_announcement,
+        audit_log,
+        clock,
         storage,
         protocol_channels,
         message_handlers,
@@ -321,3 +437,64 @@ pub fn start_protocol_controller(
         NodeId::new(keypair.get_public_key()),
     ))
 }
+
+/// Peernet manager of an identity of the node, listening on `listeners` with the limits of
+/// `peers_categories` and `default_category_info`
+#[allow(clippy::too_many_arguments)]
+fn peernet_manager(
+    config: &ProtocolConfig,
+    handshake: MassaHandshake,
+    message_handlers: MessagesHandler,
+    keypair: PeerNetKeyPair,
+    peers_categories: &HashMap<String, PeerCategoryInfo>,
+    default_category_info: &PeerCategoryInfo,
+    listeners: &HashMap<SocketAddr, TransportType>,
+    initial_peers_infos: &HashMap<PeerId, PeerData>,
+) -> PeerNetManager<MassaHandshake, MessagesHandler> {
+    let mut peernet_config = PeerNetConfiguration::default(handshake, message_handlers);
+    peernet_config.self_keypair = keypair;
+    let peernet_categories = peers_categories
+        .iter()
+        .map(|(category_name, infos)| {
+            (
+                category_name.clone(),
+                (
+                    initial_peers_infos
+                        .iter()
+                        .filter_map(|info| {
+                            if info.1.category == *category_name {
+                                //TODO: Adapt for multiple listeners
+                                Some(
+                                    info.1
+                                        .listeners
+                                        .iter()
+                                        .next()
+                                        .map(|addr| addr.0.ip().to_canonical())
+                                        .unwrap(),
+                                )
+                            } else {
+                                None
+                            }
+                        })
+                        .collect(),
+                    PeerNetCategoryInfo {
+                        max_in_connections_post_handshake: infos.max_in_connections_post_handshake,
+                        max_in_connections_pre_handshake: infos.max_in_connections_pre_handshake,
+                        max_in_connections_per_ip: infos.max_in_connections_per_ip,
+                    },
+                ),
+            )
+        })
+        .collect();
+    peernet_config.peers_categories = peernet_categories;
+    peernet_config.default_category_info = PeerNetCategoryInfo {
+        max_in_connections_pre_handshake: default_category_info.max_in_connections_pre_handshake,
+        max_in_connections_post_handshake: default_category_info.max_in_connections_post_handshake,
+        max_in_connections_per_ip: default_category_info.max_in_connections_per_ip,
+    };
+    // the transports of the listeners bound the global limit
+    peernet_config.max_in_connections = config
+        .max_in_connections
+        .min(config.transports.max_in_connections(listeners.values()));
+    PeerNetManager::new(peernet_config)
+}
@@ -1,37 +1,59 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     net::SocketAddr,
+    sync::Arc,
+    time::Duration,
 };
 
-use massa_protocol_exports::ProtocolError;
+use massa_protocol_exports::{ProtocolError, TransportConfig};
+use parking_lot::Mutex;
 use peernet::{
     network_manager::{PeerNetManager, SharedActiveConnections},
     peer::PeerConnectionType,
     peer_id::PeerId,
-    transports::{OutConnectionConfig, TransportType},
+    transports::{OutConnectionConfig, TcpOutConnectionConfig, TransportType},
 };
+use tracing::debug;
 
 use crate::{
-    handlers::peer_handler::MassaHandshake,
-    messages::{Message, MessagesHandler, MessagesSerializer},
+    handlers::{
+        block_handler::BlockMessageSerializer,
+        endorsement_handler::EndorsementMessageSerializer,
+        operation_handler::OperationMessageSerializer,
+        peer_handler::{MassaHandshake, PeerManagementMessageSerializer},
+    },
+    messages::{Message, MessagePriority, MessagesHandler, MessagesSerializer},
+    peer_traffic::PeerTraffic,
+    replay::ReplayGuard,
 };
 
 pub trait ActiveConnectionsTrait: Send + Sync {
+    /// Sends a message to a peer. The priority of the message is given by its class: when the
+    /// connection is congested, the most important messages are sent first.
     fn send_to_peer(
         &self,
         peer_id: &PeerId,
         message_serializer: &MessagesSerializer,
         message: Message,
-        high_priority: bool,
     ) -> Result<(), ProtocolError>;
+    /// Sends the messages queued for the congested connections and drops the queues of the
+    /// disconnected peers
+    fn flush_send_queues(&self);
     fn clone_box(&self) -> Box<dyn ActiveConnectionsTrait>;
     fn get_peer_ids_connected(&self) -> HashSet<PeerId>;
     fn get_peers_connected(
         &self,
     ) -> HashMap<PeerId, (SocketAddr, PeerConnectionType, Option<String>)>;
+    /// Direction of the connection with a peer, if it is connected
+    fn get_peer_connection_type(&self, peer_id: &PeerId) -> Option<PeerConnectionType>;
     fn get_nb_out_connections(&self) -> usize;
     fn get_nb_in_connections(&self) -> usize;
     fn shutdown_connection(&mut self, peer_id: &PeerId);
+    /// Whether a peer is connected to the main identity of the node, rather than to one of its
+    /// additional identities. Only these peers learn the main identity.
+    fn is_connected_to_main_identity(&self, _peer_id: &PeerId) -> bool {
+        true
+    }
 }
 
 impl Clone for Box<dyn ActiveConnectionsTrait> {
@@ -40,66 +62,277 @@ impl Clone for Box<dyn ActiveConnectionsTrait> {
     }
 }
 
-impl ActiveConnectionsTrait for SharedActiveConnections {
+/// Messages waiting to be handed to the connection of a congested peer, one queue per
+/// priority class
+#[derive(Default)]
+struct PeerSendQueue {
+    queues: [VecDeque<Message>; MessagePriority::COUNT],
+}
+
+impl PeerSendQueue {
+    fn len(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.queues.iter().all(VecDeque::is_empty)
+    }
+
+    /// Queues a message, dropping the oldest message of the least important class if the
+    /// queue is full. Returns the message back if everything queued is more important.
+    fn push(&mut self, message: Message, max_len: usize) -> Result<(), Message> {
+        let priority = message.priority();
+        if self.len() >= max_len {
+            let Some(lowest) = MessagePriority::ALL
+                .into_iter()
+                .rev()
+                .find(|class| !self.queues[*class as usize].is_empty()) else {
+                return Err(message);
+            };
+            if lowest < priority {
+                return Err(message);
+            }
+            self.queues[lowest as usize].pop_front();
+            debug!("dropped a queued {:?} message to a congested peer", lowest);
+        }
+        self.queues[priority as usize].push_back(message);
+        Ok(())
+    }
+
+    /// Hands the queued messages to `send` class by class, until it refuses one.
+    /// `send` is given the message and whether it goes through the high priority channel.
+    fn drain(&mut self, mut send: impl FnMut(Message, bool) -> bool) {
+        for class in MessagePriority::ALL {
+            let queue = &mut self.queues[class as usize];
+            while let Some(message) = queue.front() {
+                if !send(message.clone(), class == MessagePriority::Consensus) {
+                    return;
+                }
+                queue.pop_front();
+            }
+        }
+    }
+}
+
+/// Active connections of peernet, with send queues for the congested peers.
+/// The connections of the main identity come first, then the ones of each additional identity.
+///
+/// Peernet has a high and a low priority channel per connection: consensus messages go
+/// through the high priority one, the others through the low priority one. When a channel of
+/// a peer is full, the peer is considered congested and the next messages for it are queued by
+/// priority class until the queue is emptied, so that gossip cannot get ahead of blocks and
+/// endorsements.
+#[derive(Clone)]
+pub struct PrioritizedActiveConnections {
+    connections: Vec<SharedActiveConnections>,
+    send_queues: Arc<Mutex<HashMap<PeerId, PeerSendQueue>>>,
+    /// serializer for all the message types, used to send the queued messages
+    queue_serializer: MessagesSerializer,
+    max_queued_messages_per_peer: usize,
+    /// numbers the messages of the connections protected against replays
+    replay_guard: ReplayGuard,
+    /// counts the bytes sent to each peer
+    traffic: PeerTraffic,
+}
+
+impl PrioritizedActiveConnections {
+    pub fn new(
+        connections: Vec<SharedActiveConnections>,
+        max_queued_messages_per_peer: usize,
+        replay_guard: ReplayGuard,
+        traffic: PeerTraffic,
+    ) -> Self {
+        Self {
+            connections,
+            send_queues: Arc::new(Mutex::new(HashMap::new())),
+            queue_serializer: MessagesSerializer::new()
+                .with_block_message_serializer(BlockMessageSerializer::new())
+                .with_endorsement_message_serializer(EndorsementMessageSerializer::new())
+                .with_operation_message_serializer(OperationMessageSerializer::new())
+                .with_peer_management_message_serializer(PeerManagementMessageSerializer::new()),
+            max_queued_messages_per_peer,
+            replay_guard,
+            traffic,
+        }
+    }
+
+    /// Serializer for the next message sent to `peer_id`: numbered if its connection uses
+    /// sequence numbers, framed and padded if it negotiated it, and counting the bytes sent
+    fn peer_serializer(
+        &self,
+        peer_id: &PeerId,
+        serializer: &MessagesSerializer,
+    ) -> MessagesSerializer {
+        self.replay_guard
+            .sequenced(peer_id, serializer)
+            .as_ref()
+            .unwrap_or(serializer)
+            .with_counters(self.traffic.counters(peer_id))
+            .with_framing(self.traffic.framed(peer_id))
+            .with_padding(self.traffic.padding(peer_id))
+    }
+}
+
+impl ActiveConnectionsTrait for PrioritizedActiveConnections {
     fn send_to_peer(
         &self,
         peer_id: &PeerId,
         message_serializer: &MessagesSerializer,
         message: Message,
-        high_priority: bool,
     ) -> Result<(), ProtocolError> {
-        if let Some(connection) = self.read().connections.get(peer_id) {
-            connection
-                .send_channels
-                .send(message_serializer, message, high_priority)
-                .map_err(|err| ProtocolError::SendError(err.to_string()))
-        } else {
-            Err(ProtocolError::SendError(
+        let connections: Vec<_> = self.connections.iter().map(|c| c.read()).collect();
+        let mut send_queues = self.send_queues.lock();
+        let Some(connection) = connections
+            .iter()
+            .find_map(|connections| connections.connections.get(peer_id)) else {
+            send_queues.remove(peer_id);
+            return Err(ProtocolError::SendError(
                 "Peer isn't connected anymore".to_string(),
-            ))
+            ));
+        };
+        match send_queues.get_mut(peer_id) {
+            Some(queue) => {
+                // congested: the message waits for the more important ones
+                let pushed = queue.push(message, self.max_queued_messages_per_peer);
+                queue.drain(|message, high_priority| {
+                    let serializer = self.peer_serializer(peer_id, &self.queue_serializer);
+                    connection
+                        .send_channels
+                        .send(&serializer, message, high_priority)
+                        .is_ok()
+                });
+                if queue.is_empty() {
+                    send_queues.remove(peer_id);
+                }
+                pushed.map_err(|_| {
+                    ProtocolError::SendError(
+                        "Send queue of the peer is full of more important messages".to_string(),
+                    )
+                })
+            }
+            None => {
+                let priority = message.priority();
+                let serializer = self.peer_serializer(peer_id, message_serializer);
+                connection
+                    .send_channels
+                    .send(&serializer, message, priority == MessagePriority::Consensus)
+                    .map_err(|err| {
+                        // the connection is congested: queue the next messages
+                        send_queues.insert(peer_id.clone(), PeerSendQueue::default());
+                        ProtocolError::SendError(err.to_string())
+                    })
+            }
         }
     }
 
+    fn flush_send_queues(&self) {
+        let connections: Vec<_> = self.connections.iter().map(|c| c.read()).collect();
+        let mut send_queues = self.send_queues.lock();
+        send_queues.retain(|peer_id, queue| {
+            let Some(connection) = connections
+                .iter()
+                .find_map(|connections| connections.connections.get(peer_id)) else {
+                return false;
+            };
+            queue.drain(|message, high_priority| {
+                let serializer = self.peer_serializer(peer_id, &self.queue_serializer);
+                connection
+                    .send_channels
+                    .send(&serializer, message, high_priority)
+                    .is_ok()
+            });
+            !queue.is_empty()
+        });
+        let is_connected = |peer_id: &PeerId| {
+            connections
+                .iter()
+                .any(|connections| connections.connections.contains_key(peer_id))
+        };
+        self.replay_guard.retain_connected(is_connected);
+        self.traffic.retain_connected(is_connected);
+    }
+
     fn clone_box(&self) -> Box<dyn ActiveConnectionsTrait> {
         Box::new(self.clone())
     }
 
     fn get_peer_ids_connected(&self) -> HashSet<PeerId> {
-        self.read().connections.keys().cloned().collect()
+        self.connections
+            .iter()
+            .flat_map(|connections| {
+                connections
+                    .read()
+                    .connections
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .collect()
     }
 
     fn get_peers_connected(
         &self,
     ) -> HashMap<PeerId, (SocketAddr, PeerConnectionType, Option<String>)> {
-        self.read()
-            .connections
-            .iter()
-            .map(|(peer_id, connection)| {
-                (
-                    peer_id.clone(),
-                    (
-                        *connection.endpoint.get_target_addr(),
-                        connection.connection_type,
-                        connection.category_name.clone(),
-                    ),
-                )
-            })
-            .collect()
+        let mut peers = HashMap::new();
+        for connections in &self.connections {
+            peers.extend(
+                connections
+                    .read()
+                    .connections
+                    .iter()
+                    .map(|(peer_id, connection)| {
+                        (
+                            peer_id.clone(),
+                            (
+                                *connection.endpoint.get_target_addr(),
+                                connection.connection_type,
+                                connection.category_name.clone(),
+                            ),
+                        )
+                    }),
+            );
+        }
+        peers
+    }
+
+    fn get_peer_connection_type(&self, peer_id: &PeerId) -> Option<PeerConnectionType> {
+        self.connections.iter().find_map(|connections| {
+            connections
+                .read()
+                .connections
+                .get(peer_id)
+                .map(|connection| connection.connection_type)
+        })
     }
 
     fn get_nb_out_connections(&self) -> usize {
-        self.read().nb_out_connections
+        self.connections
+            .iter()
+            .map(|connections| connections.read().nb_out_connections)
+            .sum()
     }
 
     fn get_nb_in_connections(&self) -> usize {
-        self.read().nb_in_connections
+        self.connections
+            .iter()
+            .map(|connections| connections.read().nb_in_connections)
+            .sum()
     }
 
     fn shutdown_connection(&mut self, peer_id: &PeerId) {
-        if let Some(connection) = self.write().connections.get_mut(peer_id) {
-            connection.shutdown();
+        self.send_queues.lock().remove(peer_id);
+        for connections in &self.connections {
+            if let Some(connection) = connections.write().connections.get_mut(peer_id) {
+                connection.shutdown();
+            }
         }
     }
+
+    fn is_connected_to_main_identity(&self, peer_id: &PeerId) -> bool {
+        self.connections.first().map_or(false, |connections| {
+            connections.read().connections.contains_key(peer_id)
+        })
+    }
 }
 
 pub trait NetworkController: Send + Sync {
@@ -114,27 +347,51 @@ pub trait NetworkController: Send + Sync {
         transport_type: TransportType,
         addr: SocketAddr,
     ) -> Result<(), ProtocolError>;
+    /// Dials `addr` with `transport`, using the timeout and the rate limit of its config
     fn try_connect(
         &mut self,
         addr: SocketAddr,
-        timeout: std::time::Duration,
-        out_connection_config: &OutConnectionConfig,
+        transport: TransportType,
+        transport_config: &TransportConfig,
     ) -> Result<(), ProtocolError>;
 }
 
 pub struct NetworkControllerImpl {
     peernet_manager: PeerNetManager<MassaHandshake, MessagesHandler>,
+    /// networks of the additional identities, their listeners are started with them and the
+    /// node only dials with its main identity
+    _identity_managers: Vec<PeerNetManager<MassaHandshake, MessagesHandler>>,
+    active_connections: PrioritizedActiveConnections,
 }
 
 impl NetworkControllerImpl {
-    pub fn new(peernet_manager: PeerNetManager<MassaHandshake, MessagesHandler>) -> Self {
-        Self { peernet_manager }
+    pub fn new(
+        peernet_manager: PeerNetManager<MassaHandshake, MessagesHandler>,
+        identity_managers: Vec<PeerNetManager<MassaHandshake, MessagesHandler>>,
+        max_queued_messages_per_peer: usize,
+        replay_guard: ReplayGuard,
+        traffic: PeerTraffic,
+    ) -> Self {
+        let active_connections = PrioritizedActiveConnections::new(
+            std::iter::once(&peernet_manager)
+                .chain(&identity_managers)
+                .map(|manager| manager.active_connections.clone())
+                .collect(),
+            max_queued_messages_per_peer,
+            replay_guard,
+            traffic,
+        );
+        Self {
+            peernet_manager,
+            _identity_managers: identity_managers,
+            active_connections,
+        }
     }
 }
 
 impl NetworkController for NetworkControllerImpl {
     fn get_active_connections(&self) -> Box<dyn ActiveConnectionsTrait> {
-        Box::new(self.peernet_manager.active_connections.clone())
+        Box::new(self.active_connections.clone())
     }
 
     fn start_listener(
@@ -160,12 +417,74 @@ impl NetworkController for NetworkControllerImpl {
     fn try_connect(
         &mut self,
         addr: SocketAddr,
-        timeout: std::time::Duration,
-        out_connection_config: &OutConnectionConfig,
+        transport: TransportType,
+        transport_config: &TransportConfig,
     ) -> Result<(), ProtocolError> {
+        let out_connection_config = match transport {
+            TransportType::Tcp => OutConnectionConfig::Tcp(Box::new(TcpOutConnectionConfig::new(
+                transport_config.read_write_limit_bytes_per_second as u128 / 10,
+                Duration::from_millis(100),
+            ))),
+            _ => {
+                return Err(ProtocolError::GeneralProtocolError(format!(
+                    "Dialing with {:?} is not supported",
+                    transport
+                )))
+            }
+        };
         self.peernet_manager
-            .try_connect(addr, timeout, out_connection_config)
+            .try_connect(
+                addr,
+                transport_config.connection_timeout.to_duration(),
+                &out_connection_config,
+            )
             .map_err(|err| ProtocolError::GeneralProtocolError(err.to_string()))?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{
+        endorsement_handler::EndorsementMessage, operation_handler::OperationMessage,
+        peer_handler::PeerManagementMessage,
+    };
+
+    #[test]
+    fn test_send_queue_priority_and_bound() {
+        let gossip = || Message::from(PeerManagementMessage::ListPeers(Vec::new()));
+        let operations = || Message::from(OperationMessage::AskForOperations(Default::default()));
+        let endorsements = || Message::from(EndorsementMessage::Endorsements(Vec::new()));
+
+        let mut queue = PeerSendQueue::default();
+        queue.push(gossip(), 3).unwrap();
+        queue.push(operations(), 3).unwrap();
+        queue.push(endorsements(), 3).unwrap();
+        // full: the gossip makes room for the endorsements
+        queue.push(endorsements(), 3).unwrap();
+        assert!(queue.queues[MessagePriority::PeerGossip as usize].is_empty());
+        // full of more important messages
+        assert!(queue.push(gossip(), 3).is_err());
+
+        // consensus first, and the queue stops at the first refused message
+        let mut sent = Vec::new();
+        queue.drain(|message, high_priority| {
+            if sent.len() == 2 {
+                return false;
+            }
+            sent.push((message.priority(), high_priority));
+            true
+        });
+        assert_eq!(
+            sent,
+            vec![
+                (MessagePriority::Consensus, true),
+                (MessagePriority::Consensus, true)
+            ]
+        );
+        assert_eq!(queue.len(), 1);
+        queue.drain(|_, high_priority| !high_priority);
+        assert!(queue.is_empty());
+    }
+}
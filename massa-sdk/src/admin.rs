@@ -0,0 +1,338 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+use crate::to_error_obj;
+use jsonrpsee::core::RpcResult;
+use massa_api_exports::node::{
+    ConnectedPeerInfo, ExportedBanReportInfo, ExportedPeerInfo, ImportedBanReportInfo,
+    ListenerReachabilityInfo, PeerTestInfo, PeerTrafficInfo,
+};
+use massa_models::{node::NodeId, slot::Slot};
+use massa_proto::massa::api::v1 as grpc;
+use massa_proto::massa::api::v1::massa_admin_service_client::MassaAdminServiceClient;
+use massa_time::MassaTime;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::transport::{Channel, Endpoint};
+
+/// Client of the gRPC admin service of a node, which manages its peers
+pub struct AdminClient {
+    client: MassaAdminServiceClient<Channel>,
+    /// `authorization` metadata carrying the admin token, none if the token is unknown
+    authorization: Option<MetadataValue<Ascii>>,
+}
+
+impl AdminClient {
+    /// Creates a client of the admin service listening on `ip:port`. The connection is opened at
+    /// the first request.
+    pub fn new(ip: IpAddr, port: u16, token: Option<&str>) -> AdminClient {
+        let url = format!("http://{}", SocketAddr::new(ip, port));
+        let channel = Endpoint::from_shared(url)
+            .expect("a socket address is a valid URI")
+            .connect_lazy();
+        AdminClient {
+            client: MassaAdminServiceClient::new(channel),
+            authorization: token.and_then(|token| format!("Bearer {}", token).parse().ok()),
+        }
+    }
+
+    fn request<T>(&self, message: T) -> RpcResult<tonic::Request<T>> {
+        let Some(authorization) = &self.authorization else {
+            return Err(to_error_obj(
+                "the admin token is missing, copy it from the node config folder".to_string(),
+            ));
+        };
+        let mut request = tonic::Request::new(message);
+        request
+            .metadata_mut()
+            .insert("authorization", authorization.clone());
+        Ok(request)
+    }
+
+    /// List the connected peers
+    pub async fn list_peers(&self) -> RpcResult<Vec<ConnectedPeerInfo>> {
+        let request = self.request(grpc::ListPeersRequest { id: String::new() })?;
+        let response = self
+            .client
+            .clone()
+            .list_peers(request)
+            .await
+            .map_err(|e| to_error_obj(e.message().to_string()))?;
+        response
+            .into_inner()
+            .peers
+            .into_iter()
+            .map(|peer| {
+                Ok(ConnectedPeerInfo {
+                    node_id: parse(&peer.node_id)?,
+                    address: parse(&peer.address)?,
+                    is_outgoing: peer.is_outgoing,
+                    transport: peer.transport,
+                    connected_since: peer.connected_since.map(MassaTime::from_millis),
+                    traffic: peer
+                        .traffic
+                        .map(peer_traffic_info)
+                        .transpose()?
+                        .unwrap_or_default(),
+                    tags: peer.tags,
+                    note: peer.note,
+                })
+            })
+            .collect()
+    }
+
+    /// Ban peers, disconnecting them
+    pub async fn ban_peers(&self, node_ids: Vec<NodeId>) -> RpcResult<()> {
+        let request = self.request(grpc::BanPeerRequest {
+            id: String::new(),
+            node_ids: node_ids.iter().map(|node_id| node_id.to_string()).collect(),
+        })?;
+        self.client
+            .clone()
+            .ban_peer(request)
+            .await
+            .map_err(|e| to_error_obj(e.message().to_string()))?;
+        Ok(())
+    }
+
+    /// Unban peers
+    pub async fn unban_peers(&self, node_ids: Vec<NodeId>) -> RpcResult<()> {
+        let request = self.request(grpc::UnbanPeerRequest {
+            id: String::new(),
+            node_ids: node_ids.iter().map(|node_id| node_id.to_string()).collect(),
+        })?;
+        self.client
+            .clone()
+            .unban_peer(request)
+            .await
+            .map_err(|e| to_error_obj(e.message().to_string()))?;
+        Ok(())
+    }
+
+    /// Replace the tags and note attached to a peer, no tag and no note remove them
+    pub async fn annotate_peer(
+        &self,
+        node_id: NodeId,
+        tags: Vec<String>,
+        note: Option<String>,
+    ) -> RpcResult<()> {
+        let request = self.request(grpc::AnnotatePeerRequest {
+            id: String::new(),
+            node_id: node_id.to_string(),
+            tags,
+            note,
+        })?;
+        self.client
+            .clone()
+            .annotate_peer(request)
+            .await
+            .map_err(|e| to_error_obj(e.message().to_string()))?;
+        Ok(())
+    }
+
+    /// Test an address right away and return the outcome
+    pub async fn test_peer(&self, address: SocketAddr) -> RpcResult<PeerTestInfo> {
+        let request = self.request(grpc::TestPeerRequest {
+            id: String::new(),
+            address: address.to_string(),
+        })?;
+        let response = self
+            .client
+            .clone()
+            .test_peer(request)
+            .await
+            .map_err(|e| to_error_obj(e.message().to_string()))?
+            .into_inner();
+        Ok(PeerTestInfo {
+            address: parse(&response.address)?,
+            reachable: response.reachable,
+            node_id: response.node_id.as_deref().map(parse).transpose()?,
+            error: response.error,
+            latency: response.latency.map(MassaTime::from_millis),
+        })
+    }
+
+    /// Export the known peers with their listeners
+    pub async fn export_peers(&self) -> RpcResult<Vec<ExportedPeerInfo>> {
+        let request = self.request(grpc::ExportPeersRequest { id: String::new() })?;
+        let response = self
+            .client
+            .clone()
+            .export_peers(request)
+            .await
+            .map_err(|e| to_error_obj(e.message().to_string()))?;
+        response
+            .into_inner()
+            .peers
+            .into_iter()
+            .map(|peer| {
+                Ok(ExportedPeerInfo {
+                    node_id: parse(&peer.node_id)?,
+                    listeners: peer
+                        .listeners
+                        .into_iter()
+                        .map(|listener| Ok((parse(&listener.address)?, listener.transport)))
+                        .collect::<RpcResult<_>>()?,
+                    category: peer.category,
+                    last_seen: MassaTime::from_millis(peer.last_seen),
+                })
+            })
+            .collect()
+    }
+
+    /// Import peers, as exported by another node. They are tested before being added to the
+    /// peers of the node.
+    pub async fn import_peers(&self, peers: Vec<ExportedPeerInfo>) -> RpcResult<()> {
+        let request = self.request(grpc::ImportPeersRequest {
+            id: String::new(),
+            peers: peers
+                .into_iter()
+                .map(|peer| grpc::ExportedPeer {
+                    node_id: peer.node_id.to_string(),
+                    listeners: peer
+                        .listeners
+                        .into_iter()
+                        .map(|(address, transport)| grpc::PeerListener {
+                            address: address.to_string(),
+                            transport,
+                        })
+                        .collect(),
+                    category: peer.category,
+                    last_seen: peer.last_seen.to_millis(),
+                })
+                .collect(),
+        })?;
+        self.client
+            .clone()
+            .import_peers(request)
+            .await
+            .map_err(|e| to_error_obj(e.message().to_string()))?;
+        Ok(())
+    }
+
+    /// Export the signed reports of the bans proven by a message of the banned peer
+    pub async fn export_ban_reports(&self) -> RpcResult<Vec<ExportedBanReportInfo>> {
+        let request = self.request(grpc::ExportBanReportsRequest { id: String::new() })?;
+        let response = self
+            .client
+            .clone()
+            .export_ban_reports(request)
+            .await
+            .map_err(|e| to_error_obj(e.message().to_string()))?;
+        response
+            .into_inner()
+            .reports
+            .into_iter()
+            .map(|report| {
+                Ok(ExportedBanReportInfo {
+                    node_id: parse(&report.node_id)?,
+                    kind: report.kind,
+                    timestamp: MassaTime::from_millis(report.timestamp),
+                    reporter: parse(&report.reporter)?,
+                    report: report.report,
+                })
+            })
+            .collect()
+    }
+
+    /// Import ban reports, as exported by another node. The node checks the offense of each
+    /// report again before banning the peer.
+    pub async fn import_ban_reports(
+        &self,
+        reports: Vec<ExportedBanReportInfo>,
+    ) -> RpcResult<Vec<ImportedBanReportInfo>> {
+        let request = self.request(grpc::ImportBanReportsRequest {
+            id: String::new(),
+            reports: reports
+                .into_iter()
+                .map(|report| grpc::ExportedBanReport {
+                    node_id: report.node_id.to_string(),
+                    kind: report.kind,
+                    timestamp: report.timestamp.to_millis(),
+                    reporter: report.reporter.to_string(),
+                    report: report.report,
+                })
+                .collect(),
+        })?;
+        let response = self
+            .client
+            .clone()
+            .import_ban_reports(request)
+            .await
+            .map_err(|e| to_error_obj(e.message().to_string()))?;
+        response
+            .into_inner()
+            .results
+            .into_iter()
+            .map(|result| {
+                Ok(ImportedBanReportInfo {
+                    node_id: result.node_id.as_deref().map(parse).transpose()?,
+                    error: result.error,
+                })
+            })
+            .collect()
+    }
+
+    /// Ask a few connected peers to dial back the listeners of the node, and return the
+    /// reachability of each listener
+    pub async fn test_reachability(&self) -> RpcResult<Vec<ListenerReachabilityInfo>> {
+        let request = self.request(grpc::TestReachabilityRequest { id: String::new() })?;
+        let response = self
+            .client
+            .clone()
+            .test_reachability(request)
+            .await
+            .map_err(|e| to_error_obj(e.message().to_string()))?;
+        let node_ids = |node_ids: Vec<String>| -> RpcResult<Vec<NodeId>> {
+            node_ids.iter().map(|node_id| parse(node_id)).collect()
+        };
+        response
+            .into_inner()
+            .listeners
+            .into_iter()
+            .map(|listener| {
+                Ok(ListenerReachabilityInfo {
+                    address: parse(&listener.address)?,
+                    reachable_from: node_ids(listener.reachable_from)?,
+                    unreachable_from: node_ids(listener.unreachable_from)?,
+                    unanswered: node_ids(listener.unanswered)?,
+                })
+            })
+            .collect()
+    }
+}
+
+fn peer_traffic_info(traffic: grpc::PeerTraffic) -> RpcResult<PeerTrafficInfo> {
+    let last_block = match (
+        traffic.last_block_id,
+        traffic.last_block_period,
+        traffic.last_block_thread,
+    ) {
+        (Some(block_id), Some(period), Some(thread)) => Some((
+            parse(&block_id)?,
+            Slot::new(
+                period,
+                u8::try_from(thread).map_err(|_| {
+                    to_error_obj(format!("invalid thread in the response: {}", thread))
+                })?,
+            ),
+        )),
+        _ => None,
+    };
+    Ok(PeerTrafficInfo {
+        block_messages: traffic.block_messages,
+        endorsement_messages: traffic.endorsement_messages,
+        operation_messages: traffic.operation_messages,
+        peer_management_messages: traffic.peer_management_messages,
+        bytes_received: traffic.bytes_received,
+        bytes_sent: traffic.bytes_sent,
+        invalid_messages: traffic.invalid_messages,
+        last_block,
+    })
+}
+
+fn parse<T: FromStr>(value: &str) -> RpcResult<T> {
+    value
+        .parse()
+        .map_err(|_| to_error_obj(format!("invalid value in the response: {}", value)))
+}
@@ -30,6 +30,8 @@ pub struct HttpConfig {
     pub client_config: ClientConfig,
     /// whether to enable HTTP.
     pub enabled: bool,
+    /// whether to connect with HTTPS.
+    pub tls: bool,
 }
 
 /// WebSocket client settings.
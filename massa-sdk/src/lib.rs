@@ -11,7 +11,7 @@ use jsonrpsee::http_client::HttpClient;
 use jsonrpsee::rpc_params;
 use jsonrpsee::types::ErrorObject;
 use jsonrpsee::ws_client::{HeaderMap, HeaderValue, WsClient, WsClientBuilder};
-use massa_api_exports::page::PagedVecV2;
+use massa_api_exports::page::{PageRequest, PagedVecV2};
 use massa_api_exports::ApiRequest;
 use massa_api_exports::{
     address::AddressInfo,
@@ -19,7 +19,7 @@ use massa_api_exports::{
     datastore::{DatastoreEntryInput, DatastoreEntryOutput},
     endorsement::EndorsementInfo,
     execution::{ExecuteReadOnlyResponse, ReadOnlyBytecodeExecution, ReadOnlyCall},
-    node::NodeStatus,
+    node::{BootstrapServerCheck, ConnectionEventInfo, NodeStatus, ProtocolStatsInfo},
     operation::{OperationInfo, OperationInput},
     TimeInterval,
 };
@@ -32,7 +32,7 @@ use massa_models::{
     clique::Clique,
     composite::PubkeySig,
     endorsement::EndorsementId,
-    execution::EventFilter,
+    execution::{EventFilter, ExecutedOpsDump},
     node::NodeId,
     operation::{Operation, OperationId},
     output_event::SCOutputEvent,
@@ -44,10 +44,13 @@ use jsonrpsee_http_client as _;
 use jsonrpsee_ws_client as _;
 
 use jsonrpsee::{core::RpcResult, http_client::HttpClientBuilder};
+use massa_time::MassaTime;
 use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
 
+mod admin;
 mod config;
+pub use admin::AdminClient;
 pub use config::ClientConfig;
 pub use config::HttpConfig;
 pub use config::WsConfig;
@@ -58,6 +61,8 @@ pub struct Client {
     pub public: RpcClient,
     /// private component
     pub private: RpcClient,
+    /// admin component, on the gRPC API
+    pub admin: AdminClient,
 }
 
 impl Client {
@@ -66,15 +71,19 @@ impl Client {
         ip: IpAddr,
         public_port: u16,
         private_port: u16,
+        grpc_port: u16,
+        admin_token: Option<&str>,
         http_config: &HttpConfig,
     ) -> Client {
         let public_socket_addr = SocketAddr::new(ip, public_port);
         let private_socket_addr = SocketAddr::new(ip, private_port);
-        let public_url = format!("http://{}", public_socket_addr);
-        let private_url = format!("http://{}", private_socket_addr);
+        let scheme = if http_config.tls { "https" } else { "http" };
+        let public_url = format!("{}://{}", scheme, public_socket_addr);
+        let private_url = format!("{}://{}", scheme, private_socket_addr);
         Client {
             public: RpcClient::from_url(&public_url, http_config).await,
             private: RpcClient::from_url(&private_url, http_config).await,
+            admin: AdminClient::new(ip, grpc_port, admin_token),
         }
     }
 }
@@ -252,6 +261,41 @@ impl RpcClient {
             .map_err(|e| to_error_obj(e.to_string()))
     }
 
+    /// Check a bootstrap server without bootstrapping from it.
+    /// If no node id is given, the node looks it up in its bootstrap list.
+    pub async fn node_check_bootstrap(
+        &self,
+        addr: SocketAddr,
+        node_id: Option<NodeId>,
+    ) -> RpcResult<BootstrapServerCheck> {
+        self.http_client
+            .request("node_check_bootstrap", rpc_params![addr, node_id])
+            .await
+            .map_err(|e| to_error_obj(e.to_string()))
+    }
+
+    /// Dump the final executed operations by slot, paginated over the slots.
+    pub async fn dump_executed_ops(
+        &self,
+        page_request: Option<PageRequest>,
+    ) -> RpcResult<ExecutedOpsDump> {
+        self.http_client
+            .request("dump_executed_ops", rpc_params![page_request])
+            .await
+            .map_err(|e| to_error_obj(e.to_string()))
+    }
+
+    /// Last connection events kept by the node, since the given time if any.
+    pub async fn node_get_connection_events(
+        &self,
+        since: Option<MassaTime>,
+    ) -> RpcResult<Vec<ConnectionEventInfo>> {
+        self.http_client
+            .request("node_get_connection_events", rpc_params![since])
+            .await
+            .map_err(|e| to_error_obj(e.to_string()))
+    }
+
     ////////////////
     // public-api //
     ////////////////
@@ -266,6 +310,14 @@ impl RpcClient {
             .map_err(|e| to_error_obj(e.to_string()))
     }
 
+    /// Snapshot of the protocol state: message rates, queues, tester backlog, bans and connections
+    pub async fn get_protocol_stats(&self) -> RpcResult<ProtocolStatsInfo> {
+        self.http_client
+            .request("get_protocol_stats", rpc_params![])
+            .await
+            .map_err(|e| to_error_obj(e.to_string()))
+    }
+
     pub(crate) async fn _get_cliques(&self) -> RpcResult<Vec<Clique>> {
         self.http_client
             .request("get_cliques", rpc_params![])
@@ -44,7 +44,12 @@
 //! are provided by the trait to avoid re writing these query functions.
 //!
 //! Unit tests in versioning_factory.rs shows a basic but realistic implementation of a AddressFactory (impl the Factory trait)
+//!
+//! # MIP list
+//!
+//! The MIPs known by this version of the node are listed in mips.rs, the MipStore being initialized with them at startup
 
+pub mod mips;
 pub mod versioning;
 pub mod versioning_factory;
 pub mod versioning_ser_der;
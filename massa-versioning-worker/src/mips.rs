@@ -0,0 +1,26 @@
+// Copyright (c) 2023 MASSA LABS <info@massa.net>
+
+//! MIPs known by this version of the node
+
+use std::collections::HashMap;
+
+use massa_models::config::constants::{
+    VM_V1_MIP_ACTIVATION_DELAY, VM_V1_MIP_START, VM_V1_MIP_VOTE_DURATION,
+};
+
+use crate::versioning::{MipComponent, MipInfo, MipState};
+
+/// Get the MIPs known by this version of the node, with their initial state
+pub fn get_mip_list() -> [(MipInfo, MipState); 1] {
+    // VM version 1: the data of the events is limited to `max_event_size` and charged per byte
+    let mip_vm_v1 = MipInfo {
+        name: "MIP-0001-VM-EventDataLimit".to_string(),
+        version: 1,
+        components: HashMap::from([(MipComponent::VM, 1)]),
+        start: *VM_V1_MIP_START,
+        timeout: VM_V1_MIP_START.saturating_add(VM_V1_MIP_VOTE_DURATION),
+        activation_delay: VM_V1_MIP_ACTIVATION_DELAY,
+    };
+
+    [(mip_vm_v1, MipState::new(*VM_V1_MIP_START))]
+}
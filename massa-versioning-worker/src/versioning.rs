@@ -459,6 +459,24 @@ impl MipStore {
             .unwrap_or(0)
     }
 
+    /// Retrieve the version of a component active at the given timestamp
+    /// return 0 if no version of the component is active at that time
+    pub fn get_latest_component_version_at(&self, component: &MipComponent, ts: MassaTime) -> u32 {
+        let lock = self.0.read();
+        let store = lock.deref();
+        // Latest MIP of the component whose state was active at the given timestamp
+        store
+            .store
+            .iter()
+            .rev()
+            .find_map(|(k, v)| {
+                let component_version = k.components.get(component)?;
+                (v.state_at(ts, k.start, k.timeout) == Ok(ComponentStateTypeId::Active))
+                    .then_some(*component_version)
+            })
+            .unwrap_or(0)
+    }
+
     pub fn update_network_version_stats(
         &mut self,
         slot_timestamp: MassaTime,
@@ -1030,6 +1048,56 @@ mod test {
         assert_eq!(vs.get_network_version_to_announce(), 0);
     }
 
+    #[test]
+    fn test_versioning_store_component_version_at() {
+        // Test MipStore::get_latest_component_version_at()
+
+        let (start, _timeout, mi) = get_a_version_info();
+        let defined_at = MassaTime::from(start.timestamp() as u64);
+        let mip_stats_cfg = MipStatsConfig {
+            block_count_considered: 10,
+            counters_max: 5,
+        };
+
+        // Active since it was defined
+        let vs = MipState {
+            state: ComponentState::active(),
+            history: MipState::new(defined_at).history,
+        };
+        let vs_raw = MipStoreRaw {
+            store: BTreeMap::from([(mi.clone(), vs)]),
+            stats: MipStoreStats::new(mip_stats_cfg.clone()),
+        };
+        let store = MipStore(Arc::new(RwLock::new(vs_raw)));
+
+        let after = defined_at.saturating_add(MassaTime::from(1));
+        let before = defined_at.saturating_sub(MassaTime::from(1));
+        assert_eq!(
+            store.get_latest_component_version_at(&MipComponent::Address, after),
+            mi.components[&MipComponent::Address]
+        );
+        assert_eq!(
+            store.get_latest_component_version_at(&MipComponent::Address, before),
+            0
+        );
+        assert_eq!(
+            store.get_latest_component_version_at(&MipComponent::VM, after),
+            0
+        );
+
+        // Not active yet
+        let vs = MipState::new(defined_at);
+        let vs_raw = MipStoreRaw {
+            store: BTreeMap::from([(mi, vs)]),
+            stats: MipStoreStats::new(mip_stats_cfg),
+        };
+        let store = MipStore(Arc::new(RwLock::new(vs_raw)));
+        assert_eq!(
+            store.get_latest_component_version_at(&MipComponent::Address, after),
+            0
+        );
+    }
+
     #[test]
     fn test_is_coherent_with() {
         // Test MipStateHistory::is_coherent_with